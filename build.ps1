@@ -2,7 +2,7 @@
 $ErrorActionPreference = "Stop"
 
 Write-Host "========================================" -ForegroundColor Cyan
-Write-Host "Building Vello CPU FFI and .NET Bindings" -ForegroundColor Cyan
+Write-Host "Building Vello FFI Libraries and .NET Bindings" -ForegroundColor Cyan
 Write-Host "========================================" -ForegroundColor Cyan
 Write-Host ""
 
@@ -14,7 +14,7 @@ Write-Host "Platform: $PLATFORM" -ForegroundColor White
 Write-Host ""
 
 # Build Rust FFI library
-Write-Host "Step 1/3: Building Rust FFI library..." -ForegroundColor Green
+Write-Host "Step 1/5: Building Rust FFI library..." -ForegroundColor Green
 Push-Location vello_cpu_ffi
 cargo build --release
 
@@ -28,8 +28,47 @@ Write-Host "✓ Native library built: target/release/${LIB_PREFIX}vello_cpu_ffi.
 Pop-Location
 Write-Host ""
 
+# Build hybrid CPU/GPU FFI library
+Write-Host "Step 2/5: Building hybrid FFI library..." -ForegroundColor Green
+Push-Location vello_hybrid_ffi
+cargo build --release
+
+if (-not (Test-Path "target/release/${LIB_PREFIX}vello_hybrid_ffi.${LIB_EXT}")) {
+    Write-Host "Error: Hybrid library was not built successfully" -ForegroundColor Red
+    Pop-Location
+    exit 1
+}
+
+Write-Host "✓ Hybrid library built: target/release/${LIB_PREFIX}vello_hybrid_ffi.${LIB_EXT}" -ForegroundColor Green
+Pop-Location
+Write-Host ""
+
+# Build Python bindings
+Write-Host "Step 3/5: Building Python bindings..." -ForegroundColor Green
+Push-Location vello_cpu_py
+if (Get-Command maturin -ErrorAction SilentlyContinue) {
+    maturin build --release
+    if ($LASTEXITCODE -ne 0) {
+        Write-Host "Error: Python wheel build failed" -ForegroundColor Red
+        Pop-Location
+        exit 1
+    }
+    Write-Host "✓ Python wheel built: vello_cpu_py/target/wheels/" -ForegroundColor Green
+} else {
+    cargo build --release
+    if (-not (Test-Path "target/release/vello_cpu_py.dll")) {
+        Write-Host "Error: Python bindings were not built successfully" -ForegroundColor Red
+        Pop-Location
+        exit 1
+    }
+    Write-Host "✓ Python extension built: target/release/vello_cpu_py.dll" -ForegroundColor Green
+    Write-Host "  Install maturin to package it as a wheel" -ForegroundColor Yellow
+}
+Pop-Location
+Write-Host ""
+
 # Build .NET projects
-Write-Host "Step 2/3: Building .NET projects..." -ForegroundColor Green
+Write-Host "Step 4/5: Building .NET projects..." -ForegroundColor Green
 Push-Location dotnet
 dotnet build -c Release
 
@@ -44,7 +83,7 @@ Pop-Location
 Write-Host ""
 
 # Verify native library was copied
-Write-Host "Step 3/3: Verifying native library deployment..." -ForegroundColor Green
+Write-Host "Step 5/5: Verifying native library deployment..." -ForegroundColor Green
 
 $ARCH = [System.Runtime.InteropServices.RuntimeInformation]::ProcessArchitecture
 if ($ARCH -eq "X64") {
@@ -70,6 +109,8 @@ Write-Host "Build Complete!" -ForegroundColor Cyan
 Write-Host "========================================" -ForegroundColor Cyan
 Write-Host ""
 Write-Host "Native library: vello_cpu_ffi/target/release/${LIB_PREFIX}vello_cpu_ffi.${LIB_EXT}"
+Write-Host "Hybrid library: vello_hybrid_ffi/target/release/${LIB_PREFIX}vello_hybrid_ffi.${LIB_EXT}"
+Write-Host "Python wheel:   vello_cpu_py/target/wheels/ (with maturin)"
 Write-Host ".NET binaries:  dotnet/src/Vello/bin/Release/net8.0/"
 Write-Host ""
 Write-Host "To run samples:"
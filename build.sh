@@ -2,7 +2,7 @@
 set -e
 
 echo "========================================"
-echo "Building Vello CPU FFI and .NET Bindings"
+echo "Building Vello FFI Libraries and .NET Bindings"
 echo "========================================"
 echo ""
 
@@ -24,7 +24,7 @@ echo "Platform: $PLATFORM"
 echo ""
 
 # Build Rust FFI library
-echo "Step 1/3: Building Rust FFI library..."
+echo "Step 1/5: Building Rust FFI library..."
 cd vello_cpu_ffi
 cargo build --release
 
@@ -37,8 +37,40 @@ echo "✓ Native library built: target/release/${LIB_PREFIX}vello_cpu_ffi.${LIB_
 cd ..
 echo ""
 
+# Build hybrid CPU/GPU FFI library
+echo "Step 2/5: Building hybrid FFI library..."
+cd vello_hybrid_ffi
+cargo build --release
+
+if [ ! -f "target/release/${LIB_PREFIX}vello_hybrid_ffi.${LIB_EXT}" ]; then
+    echo "Error: Hybrid library was not built successfully"
+    exit 1
+fi
+
+echo "✓ Hybrid library built: target/release/${LIB_PREFIX}vello_hybrid_ffi.${LIB_EXT}"
+cd ..
+echo ""
+
+# Build Python bindings
+echo "Step 3/5: Building Python bindings..."
+cd vello_cpu_py
+if command -v maturin > /dev/null; then
+    maturin build --release
+    echo "✓ Python wheel built: vello_cpu_py/target/wheels/"
+else
+    cargo build --release
+    if [ ! -f "target/release/${LIB_PREFIX}vello_cpu_py.${LIB_EXT}" ]; then
+        echo "Error: Python bindings were not built successfully"
+        exit 1
+    fi
+    echo "✓ Python extension built: target/release/${LIB_PREFIX}vello_cpu_py.${LIB_EXT}"
+    echo "  Install maturin to package it as a wheel"
+fi
+cd ..
+echo ""
+
 # Build .NET projects
-echo "Step 2/3: Building .NET projects..."
+echo "Step 4/5: Building .NET projects..."
 cd dotnet
 dotnet build -c Release
 
@@ -52,7 +84,7 @@ cd ..
 echo ""
 
 # Verify native library was copied
-echo "Step 3/3: Verifying native library deployment..."
+echo "Step 5/5: Verifying native library deployment..."
 
 if [[ "$PLATFORM" == "macOS" ]]; then
     ARCH=$(uname -m)
@@ -85,8 +117,13 @@ echo "Build Complete!"
 echo "========================================"
 echo ""
 echo "Native library: vello_cpu_ffi/target/release/${LIB_PREFIX}vello_cpu_ffi.${LIB_EXT}"
+echo "Hybrid library: vello_hybrid_ffi/target/release/${LIB_PREFIX}vello_hybrid_ffi.${LIB_EXT}"
+echo "Python wheel:   vello_cpu_py/target/wheels/ (with maturin)"
 echo ".NET binaries:  dotnet/src/Vello/bin/Release/net8.0/"
 echo ""
 echo "To run samples:"
 echo "  cd dotnet/samples/Vello.Samples"
 echo "  dotnet run -c Release"
+echo ""
+echo "To test the Python bindings:"
+echo "  scripts/build-python.sh"
@@ -0,0 +1,148 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Pixel conversion benchmarks for vello_cpu_ffi
+//!
+//! Measures the pixmap helpers that work on pixels directly, against a plain
+//! per-pixel loop doing the same work:
+//! - Premultiplied to straight alpha conversion
+//! - Straight to premultiplied alpha conversion
+//! - Pixmap blits
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use vello_cpu_ffi::{
+    vello_pixmap_blit, vello_pixmap_data_mut, vello_pixmap_free, vello_pixmap_load_unpremultiplied,
+    vello_pixmap_new, vello_pixmap_to_unpremultiplied, VelloCompose, VelloPixmap,
+    VelloPremulRgba8,
+};
+
+// Standard benchmark size
+const WIDTH: u16 = 1920;
+const HEIGHT: u16 = 1080;
+const PIXELS: usize = WIDTH as usize * HEIGHT as usize;
+
+/// Pixmap filled with semi-transparent premultiplied pixels
+fn test_pixmap() -> *mut VelloPixmap {
+    let pixmap = vello_pixmap_new(WIDTH, HEIGHT);
+    let (mut data, mut len) = (std::ptr::null_mut(), 0);
+    assert_eq!(vello_pixmap_data_mut(pixmap, &mut data, &mut len), 0);
+    let pixels = unsafe { std::slice::from_raw_parts_mut(data, len) };
+    for (i, px) in pixels.iter_mut().enumerate() {
+        let a = (i % 256) as u8;
+        *px = VelloPremulRgba8 { r: a / 2, g: a / 3, b: a, a };
+    }
+    pixmap
+}
+
+/// Straight alpha RGBA8 bytes for a pixmap of the benchmark size
+fn test_bytes() -> Vec<u8> {
+    (0..PIXELS * 4).map(|i| (i * 7 % 256) as u8).collect()
+}
+
+// ============================================================================
+// Alpha Conversion Benchmarks
+// ============================================================================
+
+fn bench_unpremultiply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("unpremultiply");
+    group.throughput(Throughput::Elements(PIXELS as u64));
+
+    let pixmap = test_pixmap();
+    let mut buffer = vec![0u8; PIXELS * 4];
+
+    group.bench_function("ffi", |b| {
+        b.iter(|| {
+            let pixmap = black_box(pixmap);
+            let result = vello_pixmap_to_unpremultiplied(pixmap, buffer.as_mut_ptr(), buffer.len());
+            black_box(result);
+        });
+    });
+
+    let (mut data, mut len) = (std::ptr::null_mut(), 0);
+    assert_eq!(vello_pixmap_data_mut(pixmap, &mut data, &mut len), 0);
+    let pixels = unsafe { std::slice::from_raw_parts(data, len) };
+    group.bench_function("per_pixel", |b| {
+        b.iter(|| {
+            for (out, px) in buffer.chunks_exact_mut(4).zip(black_box(pixels)) {
+                let a = px.a as u32;
+                let channel = |c: u8| match a {
+                    0 => 0,
+                    _ => ((c as u32 * 255 + a / 2) / a).min(255) as u8,
+                };
+                out.copy_from_slice(&[channel(px.r), channel(px.g), channel(px.b), px.a]);
+            }
+        });
+    });
+
+    vello_pixmap_free(pixmap);
+    group.finish();
+}
+
+fn bench_premultiply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("premultiply");
+    group.throughput(Throughput::Elements(PIXELS as u64));
+
+    let pixmap = vello_pixmap_new(WIDTH, HEIGHT);
+    let bytes = test_bytes();
+
+    group.bench_function("ffi", |b| {
+        b.iter(|| {
+            let result =
+                vello_pixmap_load_unpremultiplied(pixmap, black_box(bytes.as_ptr()), bytes.len());
+            black_box(result);
+        });
+    });
+
+    let mut pixels = vec![VelloPremulRgba8 { r: 0, g: 0, b: 0, a: 0 }; PIXELS];
+    group.bench_function("per_pixel", |b| {
+        b.iter(|| {
+            for (px, c) in pixels.iter_mut().zip(black_box(&bytes).chunks_exact(4)) {
+                let a = c[3] as u32;
+                let channel = |v: u8| ((v as u32 * a + 127) / 255) as u8;
+                *px = VelloPremulRgba8 {
+                    r: channel(c[0]),
+                    g: channel(c[1]),
+                    b: channel(c[2]),
+                    a: c[3],
+                };
+            }
+        });
+    });
+
+    vello_pixmap_free(pixmap);
+    group.finish();
+}
+
+// ============================================================================
+// Blit Benchmarks
+// ============================================================================
+
+fn bench_blit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("blit");
+    group.throughput(Throughput::Elements(PIXELS as u64));
+
+    let src = test_pixmap();
+    let dst = test_pixmap();
+
+    for (name, compose) in [
+        ("copy", VelloCompose::Copy),
+        ("src_over", VelloCompose::SrcOver),
+        ("xor", VelloCompose::Xor),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(vello_pixmap_blit(dst, 0, 0, black_box(src), compose)));
+        });
+    }
+
+    vello_pixmap_free(src);
+    vello_pixmap_free(dst);
+    group.finish();
+}
+
+// ============================================================================
+// Criterion Configuration
+// ============================================================================
+
+criterion_group!(benches, bench_unpremultiply, bench_premultiply, bench_blit);
+
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+REPO_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+PYTHON="${PYTHON:-python3}"
+
+echo "========================================"
+echo "SparseStrips Python bindings build"
+echo "========================================"
+echo ""
+
+cd "$REPO_ROOT/vello_cpu_py"
+
+echo "Running Rust tests..."
+PYO3_PYTHON="$PYTHON" cargo test --no-default-features
+echo ""
+
+echo "Building vello_cpu wheel..."
+rm -rf target/wheels
+"$PYTHON" -m maturin build --release --interpreter "$PYTHON"
+echo "✓ Wheel built."
+echo ""
+
+echo "Installing wheel..."
+"$PYTHON" -m pip install --force-reinstall --no-deps target/wheels/*.whl
+echo ""
+
+echo "Running pytest..."
+"$PYTHON" -m pytest tests
+echo ""
+echo "Python bindings build finished successfully."
@@ -1,5 +1,6 @@
 use std::env;
 use std::path::PathBuf;
+use std::process::Command;
 
 fn main() {
     let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
@@ -18,6 +19,49 @@ fn main() {
         .write_to_file(&output_file);
 
     println!("cargo:warning=Generated C header at: {}", output_file);
+
+    emit_build_info();
+}
+
+/// Expose build metadata to `vello_build_info` through `VELLO_BUILD_*` variables
+fn emit_build_info() {
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=VELLO_BUILD_GIT_HASH={}", git_hash);
+
+    println!(
+        "cargo:rustc-env=VELLO_BUILD_TARGET={}",
+        env::var("TARGET").unwrap()
+    );
+    println!(
+        "cargo:rustc-env=VELLO_BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap()
+    );
+
+    let lock = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    for (package, var) in [
+        ("vello_cpu", "VELLO_BUILD_VELLO_CPU_VERSION"),
+        ("vello_common", "VELLO_BUILD_VELLO_COMMON_VERSION"),
+    ] {
+        println!("cargo:rustc-env={}={}", var, locked_version(&lock, package));
+    }
+}
+
+/// Version of `package` as resolved in Cargo.lock
+fn locked_version(lock: &str, package: &str) -> String {
+    let name_line = format!("name = \"{}\"", package);
+    lock.lines()
+        .zip(lock.lines().skip(1))
+        .find(|(name, _)| *name == name_line)
+        .and_then(|(_, version)| version.strip_prefix("version = "))
+        .map(|version| version.trim_matches('"').to_string())
+        .unwrap_or_else(|| "unknown".to_string())
 }
 
 fn target_dir() -> PathBuf {
@@ -0,0 +1,1308 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Function table for dynamic loaders
+//!
+//! `vello_get_api` hands out one struct holding a pointer to every exported
+//! function, so hosts that load the library at runtime need a single symbol
+//! lookup. Entries are only ever appended; functions behind a disabled cargo
+//! feature are null. Reordering or changing existing entries bumps
+//! `VELLO_API_VERSION` and retires the older versions.
+
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int};
+
+use crate::*;
+
+/// Newest function table version this library provides
+pub const VELLO_API_VERSION: u32 = 2;
+
+/// Oldest function table version this library provides
+///
+/// Version 1 tables had their entries in a different order, so hosts built
+/// against them would call the wrong functions.
+pub const VELLO_API_MIN_VERSION: u32 = 2;
+
+/// Table of all exported functions, named without the `vello_` prefix
+#[repr(C)]
+pub struct VelloApi {
+    /// Size of this struct in bytes; entries beyond it are not present
+    pub size: usize,
+    // Utilities
+    pub version: extern "C" fn() -> *const c_char,
+    pub features: extern "C" fn() -> u32,
+    pub build_info: extern "C" fn() -> *const c_char,
+    pub simd_detect: extern "C" fn() -> VelloSimdLevel,
+    pub abi_version: extern "C" fn(*mut u32, *mut u32, *mut u32) -> c_int,
+    pub check_abi: extern "C" fn(u32, u32) -> c_int,
+    pub abi_struct_size: extern "C" fn(VelloAbiStruct) -> usize,
+    pub affine_transform_points:
+        extern "C" fn(*const VelloAffine, *const VelloPoint, *mut VelloPoint, usize) -> c_int,
+    // Errors and logging
+    pub set_log_callback:
+        extern "C" fn(Option<VelloLogCallback>, VelloLogLevel, *mut c_void) -> c_int,
+    pub set_panic_policy:
+        extern "C" fn(VelloPanicPolicy, Option<VelloPanicCallback>, *mut c_void) -> c_int,
+    pub get_last_error: extern "C" fn() -> *const c_char,
+    pub get_last_error_msg: extern "C" fn(*mut c_char, usize, *mut usize) -> c_int,
+    pub clear_last_error: extern "C" fn(),
+    // Handles
+    pub handle_generation: extern "C" fn(*const c_void) -> u32,
+    pub debug_set_handle_tracking: extern "C" fn(bool) -> c_int,
+    pub debug_dump_live_handles:
+        extern "C" fn(Option<VelloLiveHandleCallback>, *mut c_void) -> usize,
+    // Enum strings
+    pub mix_to_string: extern "C" fn(VelloMix) -> *const c_char,
+    pub mix_parse: extern "C" fn(*const c_char, *mut VelloMix) -> c_int,
+    pub compose_to_string: extern "C" fn(VelloCompose) -> *const c_char,
+    pub compose_parse: extern "C" fn(*const c_char, *mut VelloCompose) -> c_int,
+    pub extend_to_string: extern "C" fn(VelloExtend) -> *const c_char,
+    pub extend_parse: extern "C" fn(*const c_char, *mut VelloExtend) -> c_int,
+    pub fill_rule_to_string: extern "C" fn(VelloFillRule) -> *const c_char,
+    pub fill_rule_parse: extern "C" fn(*const c_char, *mut VelloFillRule) -> c_int,
+    pub simd_level_to_string: extern "C" fn(VelloSimdLevel) -> *const c_char,
+    pub simd_level_parse: extern "C" fn(*const c_char, *mut VelloSimdLevel) -> c_int,
+    // Render context
+    pub render_context_new: extern "C" fn(u16, u16) -> *mut VelloRenderContext,
+    pub render_context_new_synchronized: extern "C" fn(u16, u16) -> *mut VelloRenderContext,
+    pub render_context_try_new: extern "C" fn(u16, u16, *mut *mut VelloRenderContext) -> c_int,
+    pub render_context_new_with:
+        extern "C" fn(u16, u16, *const VelloRenderSettings) -> *mut VelloRenderContext,
+    pub render_context_free: extern "C" fn(*mut VelloRenderContext),
+    pub render_context_width: extern "C" fn(*const VelloRenderContext) -> u16,
+    pub render_context_height: extern "C" fn(*const VelloRenderContext) -> u16,
+    pub render_context_color_space: extern "C" fn(*const VelloRenderContext) -> VelloColorSpace,
+    pub render_context_set_color_space:
+        extern "C" fn(*mut VelloRenderContext, VelloColorSpace) -> c_int,
+    pub render_context_set_glyph_hinting: extern "C" fn(*mut VelloRenderContext, bool) -> c_int,
+    pub render_context_glyph_hinting: extern "C" fn(*const VelloRenderContext) -> bool,
+    pub render_context_set_glyph_cache_limit:
+        extern "C" fn(*mut VelloRenderContext, usize) -> c_int,
+    pub render_context_glyph_cache_stats:
+        extern "C" fn(*const VelloRenderContext, *mut VelloGlyphCacheStats) -> c_int,
+    pub render_context_glyph_cache_clear: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    pub render_context_reset: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    pub render_context_set_paint_solid:
+        extern "C" fn(*mut VelloRenderContext, u8, u8, u8, u8) -> c_int,
+    pub render_context_set_paint_linear_gradient: extern "C" fn(
+        *mut VelloRenderContext,
+        f64,
+        f64,
+        f64,
+        f64,
+        *const VelloColorStop,
+        usize,
+        VelloExtend,
+    ) -> c_int,
+    pub render_context_set_paint_radial_gradient: extern "C" fn(
+        *mut VelloRenderContext,
+        f64,
+        f64,
+        f64,
+        *const VelloColorStop,
+        usize,
+        VelloExtend,
+    ) -> c_int,
+    pub render_context_set_paint_sweep_gradient: extern "C" fn(
+        *mut VelloRenderContext,
+        f64,
+        f64,
+        f32,
+        f32,
+        *const VelloColorStop,
+        usize,
+        VelloExtend,
+    ) -> c_int,
+    pub render_context_set_transform:
+        extern "C" fn(*mut VelloRenderContext, *const VelloAffine) -> c_int,
+    pub render_context_reset_transform: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    pub render_context_get_transform:
+        extern "C" fn(*const VelloRenderContext, *mut VelloAffine) -> c_int,
+    pub render_context_set_stroke:
+        extern "C" fn(*mut VelloRenderContext, *const VelloStroke) -> c_int,
+    pub render_context_set_fill_rule:
+        extern "C" fn(*mut VelloRenderContext, VelloFillRule) -> c_int,
+    pub render_context_fill_rect: extern "C" fn(*mut VelloRenderContext, *const VelloRect) -> c_int,
+    pub render_context_stroke_rect:
+        extern "C" fn(*mut VelloRenderContext, *const VelloRect) -> c_int,
+    pub render_context_fill_blurred_rounded_rect:
+        extern "C" fn(*mut VelloRenderContext, *const VelloRect, f32, f32) -> c_int,
+    pub render_context_push_blend_layer:
+        extern "C" fn(*mut VelloRenderContext, *const VelloBlendMode) -> c_int,
+    pub render_context_push_clip_layer:
+        extern "C" fn(*mut VelloRenderContext, *const VelloBezPath) -> c_int,
+    pub render_context_push_opacity_layer: extern "C" fn(*mut VelloRenderContext, f32) -> c_int,
+    pub render_context_push_backdrop_blur_layer:
+        extern "C" fn(*mut VelloRenderContext, f32, *const VelloBezPath) -> c_int,
+    pub render_context_pop_layer: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    pub render_context_layer_depth: extern "C" fn(*const VelloRenderContext) -> usize,
+    pub render_context_flush: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    pub render_context_get_stroke:
+        extern "C" fn(*const VelloRenderContext, *mut VelloStroke) -> c_int,
+    pub render_context_get_fill_rule: extern "C" fn(*const VelloRenderContext) -> VelloFillRule,
+    pub render_context_set_paint_transform:
+        extern "C" fn(*mut VelloRenderContext, *const VelloAffine) -> c_int,
+    pub render_context_get_paint_transform:
+        extern "C" fn(*const VelloRenderContext, *mut VelloAffine) -> c_int,
+    pub render_context_reset_paint_transform: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    pub render_context_get_paint_kind: extern "C" fn(*const VelloRenderContext) -> VelloPaintKind,
+    pub render_context_set_aliasing_threshold: extern "C" fn(*mut VelloRenderContext, i16) -> c_int,
+    pub render_context_push_layer: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloBezPath,
+        *const VelloBlendMode,
+        f32,
+        *const VelloMask,
+    ) -> c_int,
+    pub render_context_push_blend_layer_bounded:
+        extern "C" fn(*mut VelloRenderContext, *const VelloBlendMode, *const VelloRect) -> c_int,
+    pub render_context_push_opacity_layer_bounded:
+        extern "C" fn(*mut VelloRenderContext, f32, *const VelloRect) -> c_int,
+    pub render_context_push_layer_bounded: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloBezPath,
+        *const VelloBlendMode,
+        f32,
+        *const VelloMask,
+        *const VelloRect,
+    ) -> c_int,
+    pub render_context_get_render_settings:
+        extern "C" fn(*const VelloRenderContext, *mut VelloRenderSettings) -> c_int,
+    pub render_context_render_to_buffer_strided: extern "C" fn(
+        *mut VelloRenderContext,
+        *mut u8,
+        usize,
+        u16,
+        u16,
+        usize,
+        VelloRenderMode,
+    ) -> c_int,
+    pub render_context_render_to_buffer_format: extern "C" fn(
+        *mut VelloRenderContext,
+        *mut u8,
+        usize,
+        u16,
+        u16,
+        usize,
+        VelloPixelFormat,
+        VelloRenderMode,
+    ) -> c_int,
+    pub render_context_render_to_buffer:
+        extern "C" fn(*mut VelloRenderContext, *mut u8, usize, u16, u16, VelloRenderMode) -> c_int,
+    // Pixmap
+    pub pixmap_new: extern "C" fn(u16, u16) -> *mut VelloPixmap,
+    pub pixmap_try_new: extern "C" fn(u16, u16, *mut *mut VelloPixmap) -> c_int,
+    pub pixmap_free: extern "C" fn(*mut VelloPixmap),
+    pub pixmap_width: extern "C" fn(*const VelloPixmap) -> u16,
+    pub pixmap_height: extern "C" fn(*const VelloPixmap) -> u16,
+    pub pixmap_color_space: extern "C" fn(*const VelloPixmap) -> VelloColorSpace,
+    pub pixmap_set_color_space: extern "C" fn(*mut VelloPixmap, VelloColorSpace) -> c_int,
+    pub pixmap_convert_color_space: extern "C" fn(*mut VelloPixmap, VelloColorSpace) -> c_int,
+    pub pixmap_data:
+        extern "C" fn(*const VelloPixmap, *mut *const VelloPremulRgba8, *mut usize) -> c_int,
+    pub pixmap_data_mut:
+        extern "C" fn(*mut VelloPixmap, *mut *mut VelloPremulRgba8, *mut usize) -> c_int,
+    pub pixmap_resize: extern "C" fn(*mut VelloPixmap, u16, u16) -> c_int,
+    pub pixmap_resize_to:
+        extern "C" fn(*const VelloPixmap, u16, u16, VelloResampleFilter) -> *mut VelloPixmap,
+    pub pixmap_flip_horizontal: extern "C" fn(*mut VelloPixmap) -> c_int,
+    pub pixmap_flip_vertical: extern "C" fn(*mut VelloPixmap) -> c_int,
+    pub pixmap_rotate90: extern "C" fn(*const VelloPixmap) -> *mut VelloPixmap,
+    pub pixmap_rotate180: extern "C" fn(*const VelloPixmap) -> *mut VelloPixmap,
+    pub pixmap_rotate270: extern "C" fn(*const VelloPixmap) -> *mut VelloPixmap,
+    pub pixmap_clear: extern "C" fn(*mut VelloPixmap) -> c_int,
+    pub pixmap_fill: extern "C" fn(*mut VelloPixmap, u8, u8, u8, u8) -> c_int,
+    pub pixmap_sample: extern "C" fn(*const VelloPixmap, u16, u16, *mut VelloPremulRgba8) -> c_int,
+    pub pixmap_row:
+        extern "C" fn(*mut VelloPixmap, u16, *mut *mut VelloPremulRgba8, *mut usize) -> c_int,
+    pub pixmap_set_pixel: extern "C" fn(*mut VelloPixmap, u16, u16, VelloPremulRgba8) -> c_int,
+    pub pixmap_apply_color_matrix: extern "C" fn(*mut VelloPixmap, *const f32) -> c_int,
+    pub pixmap_hash: extern "C" fn(*const VelloPixmap, *mut u64) -> c_int,
+    pub pixmap_compare: extern "C" fn(
+        *const VelloPixmap,
+        *const VelloPixmap,
+        u8,
+        *mut usize,
+        *mut *mut VelloPixmap,
+    ) -> c_int,
+    pub pixmap_blit:
+        extern "C" fn(*mut VelloPixmap, i32, i32, *const VelloPixmap, VelloCompose) -> c_int,
+    pub pixmap_to_unpremultiplied: extern "C" fn(*const VelloPixmap, *mut u8, usize) -> c_int,
+    pub pixmap_load_unpremultiplied: extern "C" fn(*mut VelloPixmap, *const u8, usize) -> c_int,
+    pub pixmap_convert:
+        extern "C" fn(*const VelloPixmap, VelloPixelFormat, *mut u8, usize, usize) -> c_int,
+    pub pixmap_extract_alpha: extern "C" fn(*const VelloPixmap, *mut u8, usize) -> c_int,
+    pub pixmap_from_buffer: extern "C" fn(*const u8, usize, u16, u16, usize) -> *mut VelloPixmap,
+    pub pixmap_copy_to_buffer: extern "C" fn(*const VelloPixmap, *mut u8, usize, usize) -> c_int,
+    pub pixmap_wrap: extern "C" fn(*mut u8, u16, u16, usize) -> *mut VelloPixmap,
+    pub render_context_render_to_pixmap:
+        extern "C" fn(*const VelloRenderContext, *mut VelloPixmap) -> c_int,
+    pub pixmap_from_png: Option<extern "C" fn(*const u8, usize) -> *mut VelloPixmap>,
+    pub pixmap_to_png: Option<extern "C" fn(*const VelloPixmap, *mut *mut u8, *mut usize) -> c_int>,
+    pub pixmap_from_png_file: Option<extern "C" fn(*const c_char) -> *mut VelloPixmap>,
+    pub pixmap_to_png_file: Option<extern "C" fn(*const VelloPixmap, *const c_char) -> c_int>,
+    pub png_data_free: Option<extern "C" fn(*mut u8, usize)>,
+    pub pixmap_from_webp: Option<extern "C" fn(*const u8, usize, *mut *mut VelloPixmap) -> c_int>,
+    pub pixmap_to_webp:
+        Option<extern "C" fn(*const VelloPixmap, bool, f32, *mut *mut u8, *mut usize) -> c_int>,
+    pub webp_data_free: Option<extern "C" fn(*mut u8, usize)>,
+    // Path
+    pub bezpath_new: extern "C" fn() -> *mut VelloBezPath,
+    pub bezpath_free: extern "C" fn(*mut VelloBezPath),
+    pub bezpath_move_to: extern "C" fn(*mut VelloBezPath, f64, f64) -> c_int,
+    pub bezpath_line_to: extern "C" fn(*mut VelloBezPath, f64, f64) -> c_int,
+    pub bezpath_quad_to: extern "C" fn(*mut VelloBezPath, f64, f64, f64, f64) -> c_int,
+    pub bezpath_curve_to: extern "C" fn(*mut VelloBezPath, f64, f64, f64, f64, f64, f64) -> c_int,
+    pub bezpath_close: extern "C" fn(*mut VelloBezPath) -> c_int,
+    pub bezpath_clear: extern "C" fn(*mut VelloBezPath) -> c_int,
+    pub render_context_fill_path:
+        extern "C" fn(*mut VelloRenderContext, *const VelloBezPath) -> c_int,
+    pub render_context_stroke_path:
+        extern "C" fn(*mut VelloRenderContext, *const VelloBezPath) -> c_int,
+    pub render_context_fill_path_shadow: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloBezPath,
+        f64,
+        f64,
+        f32,
+        u8,
+        u8,
+        u8,
+        u8,
+    ) -> c_int,
+    // Text
+    pub font_data_new: extern "C" fn(*const u8, usize, u32) -> *mut VelloFontData,
+    pub font_data_free: extern "C" fn(*mut VelloFontData),
+    pub render_context_fill_glyphs: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloFontData,
+        f32,
+        *const VelloGlyph,
+        usize,
+    ) -> c_int,
+    pub render_context_stroke_glyphs: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloFontData,
+        f32,
+        *const VelloGlyph,
+        usize,
+    ) -> c_int,
+    pub font_data_text_to_glyphs: extern "C" fn(
+        *const VelloFontData,
+        *const std::os::raw::c_char,
+        *mut VelloGlyph,
+        usize,
+        *mut usize,
+    ) -> c_int,
+    pub font_data_glyph_outline:
+        extern "C" fn(*const VelloFontData, u32, f32, *mut *mut VelloBezPath) -> c_int,
+    pub font_data_glyph_metrics: extern "C" fn(
+        *const VelloFontData,
+        *const u32,
+        usize,
+        f32,
+        *mut f32,
+        *mut VelloRect,
+    ) -> c_int,
+    pub font_data_glyph_advances:
+        extern "C" fn(*const VelloFontData, *const u32, usize, f32, *mut f32) -> c_int,
+    pub font_data_measure_text: extern "C" fn(
+        *const VelloFontData,
+        *const std::os::raw::c_char,
+        f32,
+        *mut f32,
+        *mut f32,
+    ) -> c_int,
+    pub font_data_glyph_kind:
+        extern "C" fn(*const VelloFontData, u32, *mut VelloGlyphKind) -> c_int,
+    pub font_data_face_count: extern "C" fn(*const u8, usize) -> u32,
+    pub font_face_name: extern "C" fn(
+        *const u8,
+        usize,
+        u32,
+        u16,
+        *mut std::os::raw::c_char,
+        usize,
+        *mut usize,
+    ) -> c_int,
+    pub render_context_fill_text_on_path: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloFontData,
+        f32,
+        *const std::os::raw::c_char,
+        *const VelloBezPath,
+        f32,
+        f32,
+    ) -> c_int,
+    pub font_data_info: extern "C" fn(*const VelloFontData, *mut VelloFontInfo) -> c_int,
+    pub render_context_draw_text_decoration: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloFontData,
+        f32,
+        f64,
+        f64,
+        f64,
+        VelloTextDecoration,
+    ) -> c_int,
+    pub render_context_fill_glyphs_ex: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloFontData,
+        f32,
+        *const VelloGlyphEx,
+        usize,
+    ) -> c_int,
+    pub render_context_draw_text: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloFontData,
+        f32,
+        f32,
+        f32,
+        *const std::os::raw::c_char,
+    ) -> c_int,
+    pub glyph_run_new:
+        extern "C" fn(*mut VelloRenderContext, *const VelloFontData) -> *mut VelloGlyphRun,
+    pub glyph_run_free: extern "C" fn(*mut VelloGlyphRun),
+    pub glyph_run_set_font_size: extern "C" fn(*mut VelloGlyphRun, f32) -> c_int,
+    pub glyph_run_set_hint: extern "C" fn(*mut VelloGlyphRun, bool) -> c_int,
+    pub glyph_run_set_normalized_coords:
+        extern "C" fn(*mut VelloGlyphRun, *const i16, usize) -> c_int,
+    pub glyph_run_set_glyph_transform:
+        extern "C" fn(*mut VelloGlyphRun, *const VelloAffine) -> c_int,
+    pub glyph_run_set_transform: extern "C" fn(*mut VelloGlyphRun, *const VelloAffine) -> c_int,
+    pub glyph_run_fill: extern "C" fn(*const VelloGlyphRun, *const VelloGlyph, usize) -> c_int,
+    pub glyph_run_stroke: extern "C" fn(*const VelloGlyphRun, *const VelloGlyph, usize) -> c_int,
+    pub font_rasterize_glyph: extern "C" fn(
+        *const VelloFontData,
+        u32,
+        f32,
+        f32,
+        *mut u8,
+        usize,
+        *mut VelloGlyphBitmapMetrics,
+    ) -> c_int,
+    // Shaping
+    pub shape_text: Option<
+        extern "C" fn(
+            *const VelloFontData,
+            *const c_char,
+            f32,
+            VelloTextDirection,
+            *const c_char,
+            *const VelloFontFeature,
+            usize,
+            *mut VelloShapedGlyph,
+            usize,
+            *mut usize,
+        ) -> c_int,
+    >,
+    pub font_feature_tag: extern "C" fn(*const c_char) -> u32,
+    pub font_feature_parse: extern "C" fn(*const c_char, *mut VelloFontFeature) -> c_int,
+    pub shape_text_runs: Option<
+        extern "C" fn(
+            *const VelloFontData,
+            *const c_char,
+            f32,
+            VelloTextDirection,
+            *const c_char,
+            *const VelloFontFeature,
+            usize,
+            *mut VelloTextRun,
+            usize,
+            *mut usize,
+        ) -> c_int,
+    >,
+    // System fonts
+    #[cfg(feature = "system-fonts")]
+    pub font_enumerate_system:
+        Option<extern "C" fn(Option<VelloFontEnumCallback>, *mut c_void) -> c_int>,
+    #[cfg(not(feature = "system-fonts"))]
+    pub font_enumerate_system: Option<extern "C" fn()>,
+    #[cfg(feature = "system-fonts")]
+    pub font_data_new_from_system:
+        Option<extern "C" fn(*const c_char, u16, VelloFontStyle) -> *mut VelloFontData>,
+    #[cfg(not(feature = "system-fonts"))]
+    pub font_data_new_from_system: Option<extern "C" fn()>,
+    // Text layout
+    pub text_layout_new: extern "C" fn(
+        *const VelloFontData,
+        *const c_char,
+        f32,
+        VelloTextDirection,
+        *const c_char,
+        *const VelloFontFeature,
+        usize,
+    ) -> *mut VelloTextLayout,
+    pub text_layout_free: extern "C" fn(*mut VelloTextLayout),
+    pub text_layout_advance: extern "C" fn(*const VelloTextLayout) -> f32,
+    pub text_layout_glyphs:
+        extern "C" fn(*const VelloTextLayout, *mut *const VelloShapedGlyph, *mut usize) -> c_int,
+    pub text_layout_runs:
+        extern "C" fn(*const VelloTextLayout, *mut *const VelloTextRun, *mut usize) -> c_int,
+    pub text_layout_carets:
+        extern "C" fn(*const VelloTextLayout, *mut *const VelloCaret, *mut usize) -> c_int,
+    pub text_layout_hit_test: extern "C" fn(*const VelloTextLayout, f32, f32, *mut u32) -> c_int,
+    // Font registry
+    pub font_register: extern "C" fn(*const u8, usize, u32) -> u32,
+    pub font_register_font_data: extern "C" fn(*const VelloFontData) -> u32,
+    pub font_unregister: extern "C" fn(u32) -> c_int,
+    pub font_registry_get: extern "C" fn(u32) -> *mut VelloFontData,
+    pub render_context_fill_glyphs_by_id:
+        extern "C" fn(*mut VelloRenderContext, u32, f32, *const VelloGlyph, usize) -> c_int,
+    // Mask
+    pub mask_new_alpha: extern "C" fn(*const VelloPixmap) -> *mut VelloMask,
+    pub mask_new_luminance: extern "C" fn(*const VelloPixmap) -> *mut VelloMask,
+    pub mask_new_from_pixmap_alpha: extern "C" fn(*const VelloPixmap) -> *mut VelloMask,
+    pub mask_free: extern "C" fn(*mut VelloMask),
+    pub mask_get_width: extern "C" fn(*const VelloMask) -> u16,
+    pub mask_get_height: extern "C" fn(*const VelloMask) -> u16,
+    pub mask_data: extern "C" fn(*const VelloMask, *mut *const u8, *mut usize) -> c_int,
+    pub mask_sample: extern "C" fn(*const VelloMask, u16, u16, *mut u8) -> c_int,
+    pub mask_crop: extern "C" fn(*const VelloMask, *const VelloRect) -> *mut VelloMask,
+    pub mask_resize:
+        extern "C" fn(*const VelloMask, u16, u16, VelloResampleFilter) -> *mut VelloMask,
+    pub mask_new_linear_gradient: extern "C" fn(
+        u16,
+        u16,
+        f64,
+        f64,
+        f64,
+        f64,
+        *const VelloAlphaStop,
+        usize,
+        VelloExtend,
+    ) -> *mut VelloMask,
+    pub render_context_push_mask_layer:
+        extern "C" fn(*mut crate::types::VelloRenderContext, *const VelloMask) -> c_int,
+    pub render_context_push_mask_layer_from_path:
+        extern "C" fn(*mut crate::types::VelloRenderContext, *const VelloBezPath, bool) -> c_int,
+    // Image
+    pub image_new_from_pixmap: extern "C" fn(
+        *const VelloPixmap,
+        VelloExtend,
+        VelloExtend,
+        VelloImageQuality,
+        f32,
+    ) -> *mut VelloImage,
+    pub image_free: extern "C" fn(*mut VelloImage),
+    pub render_context_set_paint_image:
+        extern "C" fn(*mut crate::types::VelloRenderContext, *const VelloImage) -> c_int,
+    // Recording
+    pub recording_new: extern "C" fn() -> *mut VelloRecording,
+    pub recording_free: extern "C" fn(*mut VelloRecording),
+    pub recording_clear: extern "C" fn(*mut VelloRecording) -> i32,
+    pub recording_len: extern "C" fn(*const VelloRecording) -> usize,
+    pub recording_has_cached_strips: extern "C" fn(*const VelloRecording) -> i32,
+    pub recording_strip_count: extern "C" fn(*const VelloRecording) -> usize,
+    pub recording_alpha_count: extern "C" fn(*const VelloRecording) -> usize,
+    pub recording_serialize:
+        extern "C" fn(*const VelloRecording, *mut *mut u8, *mut usize) -> c_int,
+    pub recording_deserialize: extern "C" fn(*const u8, usize) -> *mut VelloRecording,
+    pub recording_data_free: extern "C" fn(*mut u8, usize),
+    pub recording_format_version: extern "C" fn() -> u32,
+    pub render_context_execute_recording_range:
+        extern "C" fn(*mut VelloRenderContext, *const VelloRecording, usize, usize) -> c_int,
+    pub recording_append: extern "C" fn(*mut VelloRecording, *const VelloRecording) -> c_int,
+    pub recording_to_svg: Option<
+        extern "C" fn(*const VelloRecording, u32, u32, *mut *mut std::os::raw::c_char) -> c_int,
+    >,
+    pub recording_svg_free: Option<extern "C" fn(*mut std::os::raw::c_char)>,
+    pub recording_invalidate_range: extern "C" fn(*mut VelloRecording, usize, usize) -> c_int,
+    pub recording_replace_range:
+        extern "C" fn(*mut VelloRecording, usize, usize, *const VelloRecording) -> c_int,
+    pub render_context_record: extern "C" fn(
+        *mut c_void,
+        *mut VelloRecording,
+        extern "C" fn(*mut c_void, *mut c_void),
+        *mut c_void,
+    ) -> i32,
+    pub render_context_begin_record:
+        extern "C" fn(*mut VelloRenderContext, *mut VelloRecording) -> *mut c_void,
+    pub render_context_end_record: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    pub render_context_prepare_recording: extern "C" fn(*mut c_void, *mut VelloRecording) -> i32,
+    pub render_context_execute_recording: extern "C" fn(*mut c_void, *const VelloRecording) -> i32,
+    pub render_context_execute_recording_with_transform:
+        extern "C" fn(*mut VelloRenderContext, *mut VelloRecording, *const VelloAffine) -> c_int,
+    pub render_context_execute_recording_instanced: extern "C" fn(
+        *mut VelloRenderContext,
+        *mut VelloRecording,
+        *const VelloAffine,
+        usize,
+    ) -> c_int,
+    pub recorder_fill_rect: extern "C" fn(*mut c_void, *const VelloRect) -> i32,
+    pub recorder_stroke_rect: extern "C" fn(*mut c_void, *const VelloRect) -> i32,
+    pub recorder_fill_path: extern "C" fn(*mut c_void, *const c_void) -> i32,
+    pub recorder_stroke_path: extern "C" fn(*mut c_void, *const c_void) -> i32,
+    pub recorder_set_paint_solid: extern "C" fn(*mut c_void, u8, u8, u8, u8) -> i32,
+    pub recorder_set_transform: extern "C" fn(*mut c_void, *const VelloAffine) -> i32,
+    pub recorder_set_fill_rule: extern "C" fn(*mut c_void, VelloFillRule) -> i32,
+    pub recorder_set_stroke: extern "C" fn(*mut c_void, *const VelloStroke) -> i32,
+    pub recorder_set_paint_transform: extern "C" fn(*mut c_void, *const VelloAffine) -> i32,
+    pub recorder_reset_paint_transform: extern "C" fn(*mut c_void) -> i32,
+    pub recorder_push_clip_layer: extern "C" fn(*mut c_void, *const c_void) -> i32,
+    pub recorder_pop_layer: extern "C" fn(*mut c_void) -> i32,
+    pub recorder_set_paint_linear_gradient: extern "C" fn(
+        *mut c_void,
+        f64,
+        f64,
+        f64,
+        f64,
+        *const VelloColorStop,
+        usize,
+        VelloExtend,
+    ) -> c_int,
+    pub recorder_set_paint_radial_gradient: extern "C" fn(
+        *mut c_void,
+        f64,
+        f64,
+        f64,
+        *const VelloColorStop,
+        usize,
+        VelloExtend,
+    ) -> c_int,
+    pub recorder_set_paint_sweep_gradient: extern "C" fn(
+        *mut c_void,
+        f64,
+        f64,
+        f32,
+        f32,
+        *const VelloColorStop,
+        usize,
+        VelloExtend,
+    ) -> c_int,
+    pub recorder_set_paint_image: extern "C" fn(*mut c_void, *const VelloImage) -> c_int,
+    pub recorder_fill_blurred_rounded_rect:
+        extern "C" fn(*mut c_void, *const VelloRect, f32, f32, u8, u8, u8, u8) -> c_int,
+    pub recorder_push_blend_layer: extern "C" fn(*mut c_void, *const VelloBlendMode) -> c_int,
+    pub recorder_push_opacity_layer: extern "C" fn(*mut c_void, f32) -> c_int,
+    pub recorder_push_mask_layer: extern "C" fn(*mut c_void, *const VelloMask) -> c_int,
+    pub recorder_push_layer: extern "C" fn(
+        *mut c_void,
+        *const VelloBezPath,
+        *const VelloBlendMode,
+        f32,
+        *const VelloMask,
+    ) -> c_int,
+    pub recorder_fill_glyphs:
+        extern "C" fn(*mut c_void, *const VelloFontData, f32, *const VelloGlyph, usize) -> c_int,
+    pub recorder_stroke_glyphs:
+        extern "C" fn(*mut c_void, *const VelloFontData, f32, *const VelloGlyph, usize) -> c_int,
+    pub render_context_set_paint_pattern: extern "C" fn(
+        *mut VelloRenderContext,
+        *mut VelloRecording,
+        *const VelloRect,
+        VelloExtend,
+        VelloExtend,
+    ) -> c_int,
+    // Filter
+    pub filter_new: extern "C" fn() -> *mut VelloFilter,
+    pub filter_free: extern "C" fn(*mut VelloFilter),
+    pub filter_len: extern "C" fn(*const VelloFilter) -> usize,
+    pub filter_gaussian_blur: extern "C" fn(*mut VelloFilter, i32, f32, f32, *mut i32) -> c_int,
+    pub filter_color_matrix: extern "C" fn(*mut VelloFilter, i32, *const f32, *mut i32) -> c_int,
+    pub filter_offset: extern "C" fn(*mut VelloFilter, i32, i32, i32, *mut i32) -> c_int,
+    pub filter_composite:
+        extern "C" fn(*mut VelloFilter, i32, i32, VelloCompose, *mut i32) -> c_int,
+    pub filter_merge: extern "C" fn(*mut VelloFilter, *const i32, usize, *mut i32) -> c_int,
+    pub render_context_push_filter_layer:
+        extern "C" fn(*mut VelloRenderContext, *const VelloFilter, *const VelloBezPath) -> c_int,
+    // Handle table
+    pub handle_table_insert: extern "C" fn(VelloHandleKind, *mut c_void, *mut u64) -> c_int,
+    pub handle_table_get: extern "C" fn(VelloHandleKind, u64) -> *mut c_void,
+    pub handle_table_remove: extern "C" fn(u64) -> c_int,
+    pub handle_table_len: extern "C" fn() -> usize,
+    // Render context threading
+    pub render_context_set_num_threads: extern "C" fn(*mut VelloRenderContext, u16) -> c_int,
+    pub render_context_adopt_thread_pool:
+        extern "C" fn(*mut VelloRenderContext, *const VelloThreadPool) -> c_int,
+    // Thread pools
+    pub thread_pool_new: extern "C" fn(*const VelloWorkerSettings) -> *mut VelloThreadPool,
+    pub thread_pool_free: extern "C" fn(*mut VelloThreadPool),
+    pub thread_pool_num_threads: extern "C" fn(*const VelloThreadPool) -> usize,
+    // Incremental rendering
+    pub render_context_render_dirty: extern "C" fn(
+        *const VelloRenderContext,
+        *mut VelloPixmap,
+        *const VelloRect,
+        usize,
+    ) -> c_int,
+    pub render_context_tile_count: extern "C" fn(*const VelloRenderContext, u16) -> usize,
+    pub render_context_render_tile:
+        extern "C" fn(*const VelloRenderContext, usize, *mut VelloPixmap) -> c_int,
+    // Batched commands
+    pub render_context_submit:
+        extern "C" fn(*mut VelloRenderContext, *const VelloCommand, usize) -> c_int,
+    // Memory management
+    pub render_context_memory_usage:
+        extern "C" fn(*const VelloRenderContext, *mut VelloMemoryUsage) -> c_int,
+    pub render_context_trim: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    // Retained layers
+    pub layer_cache_new: extern "C" fn(u16, u16) -> *mut VelloLayerCache,
+    pub layer_cache_free: extern "C" fn(*mut VelloLayerCache),
+    pub layer_cache_begin: extern "C" fn(*mut VelloLayerCache) -> *mut VelloRenderContext,
+    pub layer_cache_end: extern "C" fn(*mut VelloLayerCache) -> c_int,
+    pub layer_cache_invalidate: extern "C" fn(*mut VelloLayerCache) -> c_int,
+    pub layer_cache_is_valid: extern "C" fn(*const VelloLayerCache) -> bool,
+    pub render_context_draw_cached_layer:
+        extern "C" fn(*mut VelloRenderContext, *const VelloLayerCache, f64, f64) -> c_int,
+    // Strip access
+    pub render_context_strips:
+        extern "C" fn(*mut VelloRenderContext, *mut VelloRecording, *mut VelloStripBuffer) -> c_int,
+    pub rasterize_path_coverage: extern "C" fn(
+        *const VelloBezPath,
+        *const VelloAffine,
+        VelloFillRule,
+        Option<VelloCoverageCallback>,
+        *mut c_void,
+    ) -> c_int,
+    // Pixmap pools
+    pub pixmap_pool_new: extern "C" fn(u16, u16, usize) -> *mut VelloPixmapPool,
+    pub pixmap_pool_free: extern "C" fn(*mut VelloPixmapPool),
+    pub pixmap_pool_acquire: extern "C" fn(*const VelloPixmapPool) -> *mut VelloPixmap,
+    pub pixmap_pool_release: extern "C" fn(*const VelloPixmapPool, *mut VelloPixmap) -> c_int,
+    pub pixmap_pool_idle_count: extern "C" fn(*const VelloPixmapPool) -> usize,
+    // Multi-context compositing
+    pub render_context_render_to_pixmap_at:
+        extern "C" fn(*const VelloRenderContext, *mut VelloPixmap, u16, u16) -> c_int,
+    // Context snapshots
+    pub render_context_clone: extern "C" fn(*mut VelloRenderContext) -> *mut VelloRenderContext,
+    // Per-draw anti-aliasing
+    pub render_context_fill_rect_aa:
+        extern "C" fn(*mut VelloRenderContext, *const VelloRect, VelloAaMode) -> c_int,
+    pub render_context_stroke_rect_aa:
+        extern "C" fn(*mut VelloRenderContext, *const VelloRect, VelloAaMode) -> c_int,
+    pub render_context_fill_path_aa:
+        extern "C" fn(*mut VelloRenderContext, *const VelloBezPath, VelloAaMode) -> c_int,
+    pub render_context_stroke_path_aa:
+        extern "C" fn(*mut VelloRenderContext, *const VelloBezPath, VelloAaMode) -> c_int,
+    // Pixel snapping
+    pub render_context_set_pixel_snapping: extern "C" fn(*mut VelloRenderContext, bool) -> c_int,
+    pub render_context_pixel_snapping: extern "C" fn(*const VelloRenderContext) -> bool,
+    // Blurred rounded rects with per-corner radii
+    pub render_context_fill_blurred_rounded_rect4: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloRect,
+        *const VelloCornerRadii,
+        f32,
+        f32,
+    ) -> c_int,
+    // Hit testing
+    pub render_context_set_hit_testing: extern "C" fn(*mut VelloRenderContext, bool) -> c_int,
+    pub render_context_set_draw_id: extern "C" fn(*mut VelloRenderContext, u64) -> c_int,
+    pub render_context_hit_test:
+        extern "C" fn(*const VelloRenderContext, u16, u16, *mut u64, usize, *mut usize) -> c_int,
+    // Per-draw bounds
+    pub render_context_command_bounds:
+        extern "C" fn(*const VelloRenderContext, u64, *mut VelloRect) -> c_int,
+    // Base color
+    pub render_context_set_base_color: extern "C" fn(*mut VelloRenderContext, u8, u8, u8) -> c_int,
+    pub render_context_clear_base_color: extern "C" fn(*mut VelloRenderContext) -> c_int,
+    // Per-call render mode
+    pub render_context_render_to_pixmap_mode:
+        extern "C" fn(*const VelloRenderContext, *mut VelloPixmap, VelloRenderMode) -> c_int,
+    // Dashed strokes
+    pub render_context_set_stroke_dashed: extern "C" fn(
+        *mut VelloRenderContext,
+        *const VelloStroke,
+        *const f64,
+        usize,
+        f64,
+        f64,
+        f64,
+    ) -> c_int,
+    pub render_context_get_stroke_dashes:
+        extern "C" fn(*const VelloRenderContext, *mut f64, usize, *mut usize, *mut f64) -> c_int,
+    pub recorder_set_stroke_dashed:
+        extern "C" fn(*mut c_void, *const VelloStroke, *const f64, usize, f64, f64, f64) -> i32,
+    // SVG documents
+    pub svg_load: Option<extern "C" fn(*const u8, usize) -> *mut VelloSvgDocument>,
+    pub svg_free: Option<extern "C" fn(*mut VelloSvgDocument)>,
+    pub svg_size: Option<extern "C" fn(*const VelloSvgDocument, *mut f32, *mut f32) -> c_int>,
+    pub render_context_draw_svg: Option<
+        extern "C" fn(
+            *mut VelloRenderContext,
+            *const VelloSvgDocument,
+            *const VelloAffine,
+        ) -> c_int,
+    >,
+    // Skia compatibility
+    pub skia_canvas_new: Option<extern "C" fn(*mut VelloRenderContext) -> *mut SkCanvas>,
+    // Canvas
+    pub canvas_new: extern "C" fn(*mut VelloRenderContext) -> *mut VelloCanvas,
+    pub canvas_free: extern "C" fn(*mut VelloCanvas),
+    pub canvas_save: extern "C" fn(*mut VelloCanvas) -> c_int,
+    pub canvas_restore: extern "C" fn(*mut VelloCanvas) -> c_int,
+    pub canvas_translate: extern "C" fn(*mut VelloCanvas, f64, f64) -> c_int,
+    pub canvas_scale: extern "C" fn(*mut VelloCanvas, f64, f64) -> c_int,
+    pub canvas_rotate: extern "C" fn(*mut VelloCanvas, f64) -> c_int,
+    pub canvas_transform: extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64, f64, f64) -> c_int,
+    pub canvas_set_transform:
+        extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64, f64, f64) -> c_int,
+    pub canvas_reset_transform: extern "C" fn(*mut VelloCanvas) -> c_int,
+    pub canvas_get_transform: extern "C" fn(*const VelloCanvas, *mut VelloAffine) -> c_int,
+    pub canvas_set_fill_style_color: extern "C" fn(*mut VelloCanvas, u8, u8, u8, u8) -> c_int,
+    pub canvas_set_stroke_style_color: extern "C" fn(*mut VelloCanvas, u8, u8, u8, u8) -> c_int,
+    pub canvas_set_fill_style_linear_gradient:
+        extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64, *const VelloColorStop, usize) -> c_int,
+    pub canvas_set_stroke_style_linear_gradient:
+        extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64, *const VelloColorStop, usize) -> c_int,
+    pub canvas_set_fill_style_radial_gradient: extern "C" fn(
+        *mut VelloCanvas,
+        f64,
+        f64,
+        f32,
+        f64,
+        f64,
+        f32,
+        *const VelloColorStop,
+        usize,
+    ) -> c_int,
+    pub canvas_set_stroke_style_radial_gradient: extern "C" fn(
+        *mut VelloCanvas,
+        f64,
+        f64,
+        f32,
+        f64,
+        f64,
+        f32,
+        *const VelloColorStop,
+        usize,
+    ) -> c_int,
+    pub canvas_set_line_width: extern "C" fn(*mut VelloCanvas, f64) -> c_int,
+    pub canvas_set_line_cap: extern "C" fn(*mut VelloCanvas, VelloCap) -> c_int,
+    pub canvas_set_line_join: extern "C" fn(*mut VelloCanvas, VelloJoin) -> c_int,
+    pub canvas_set_miter_limit: extern "C" fn(*mut VelloCanvas, f64) -> c_int,
+    pub canvas_set_line_dash: extern "C" fn(*mut VelloCanvas, *const f64, usize) -> c_int,
+    pub canvas_set_line_dash_offset: extern "C" fn(*mut VelloCanvas, f64) -> c_int,
+    pub canvas_set_global_alpha: extern "C" fn(*mut VelloCanvas, f32) -> c_int,
+    pub canvas_set_global_composite_operation:
+        extern "C" fn(*mut VelloCanvas, *const VelloBlendMode) -> c_int,
+    pub canvas_begin_path: extern "C" fn(*mut VelloCanvas) -> c_int,
+    pub canvas_move_to: extern "C" fn(*mut VelloCanvas, f64, f64) -> c_int,
+    pub canvas_line_to: extern "C" fn(*mut VelloCanvas, f64, f64) -> c_int,
+    pub canvas_quadratic_curve_to: extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64) -> c_int,
+    pub canvas_bezier_curve_to:
+        extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64, f64, f64) -> c_int,
+    pub canvas_arc: extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64, f64, bool) -> c_int,
+    pub canvas_rect: extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64) -> c_int,
+    pub canvas_close_path: extern "C" fn(*mut VelloCanvas) -> c_int,
+    pub canvas_fill: extern "C" fn(*mut VelloCanvas, VelloFillRule) -> c_int,
+    pub canvas_stroke: extern "C" fn(*mut VelloCanvas) -> c_int,
+    pub canvas_clip: extern "C" fn(*mut VelloCanvas, VelloFillRule) -> c_int,
+    pub canvas_fill_rect: extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64) -> c_int,
+    pub canvas_stroke_rect: extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64) -> c_int,
+    pub canvas_clear_rect: extern "C" fn(*mut VelloCanvas, f64, f64, f64, f64) -> c_int,
+    pub canvas_draw_image:
+        extern "C" fn(*mut VelloCanvas, *const VelloImage, f64, f64, f64, f64) -> c_int,
+    pub canvas_draw_image_source: extern "C" fn(
+        *mut VelloCanvas,
+        *const VelloImage,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+    ) -> c_int,
+    pub canvas_set_font: extern "C" fn(*mut VelloCanvas, *const VelloFontData, f32) -> c_int,
+    pub canvas_set_text_align: extern "C" fn(*mut VelloCanvas, VelloCanvasTextAlign) -> c_int,
+    pub canvas_set_text_baseline: extern "C" fn(*mut VelloCanvas, VelloCanvasTextBaseline) -> c_int,
+    pub canvas_fill_text: extern "C" fn(*mut VelloCanvas, *const c_char, f64, f64) -> c_int,
+    pub canvas_stroke_text: extern "C" fn(*mut VelloCanvas, *const c_char, f64, f64) -> c_int,
+    pub canvas_measure_text: extern "C" fn(*mut VelloCanvas, *const c_char, *mut f32) -> c_int,
+    // Handle table pinning
+    pub handle_table_acquire: extern "C" fn(VelloHandleKind, u64) -> *mut c_void,
+    pub handle_table_release: extern "C" fn(u64) -> c_int,
+    // Scene strips
+    pub render_context_scene_strips:
+        extern "C" fn(*mut VelloRenderContext, *mut VelloStripBuffer) -> c_int,
+    // Worker settings
+    pub render_context_set_worker_settings:
+        extern "C" fn(*mut VelloRenderContext, *const VelloWorkerSettings) -> c_int,
+    // Gradient ramp size
+    pub render_context_set_gradient_ramp_size: extern "C" fn(*mut VelloRenderContext, u16) -> c_int,
+}
+
+static API: VelloApi = VelloApi {
+    size: std::mem::size_of::<VelloApi>(),
+    // Utilities
+    version: vello_version,
+    features: vello_features,
+    build_info: vello_build_info,
+    simd_detect: vello_simd_detect,
+    abi_version: vello_abi_version,
+    check_abi: vello_check_abi,
+    abi_struct_size: vello_abi_struct_size,
+    affine_transform_points: vello_affine_transform_points,
+    // Errors and logging
+    set_log_callback: vello_set_log_callback,
+    set_panic_policy: vello_set_panic_policy,
+    get_last_error: vello_get_last_error,
+    get_last_error_msg: vello_get_last_error_msg,
+    clear_last_error: vello_clear_last_error,
+    // Handles
+    handle_generation: vello_handle_generation,
+    debug_set_handle_tracking: vello_debug_set_handle_tracking,
+    debug_dump_live_handles: vello_debug_dump_live_handles,
+    // Enum strings
+    mix_to_string: vello_mix_to_string,
+    mix_parse: vello_mix_parse,
+    compose_to_string: vello_compose_to_string,
+    compose_parse: vello_compose_parse,
+    extend_to_string: vello_extend_to_string,
+    extend_parse: vello_extend_parse,
+    fill_rule_to_string: vello_fill_rule_to_string,
+    fill_rule_parse: vello_fill_rule_parse,
+    simd_level_to_string: vello_simd_level_to_string,
+    simd_level_parse: vello_simd_level_parse,
+    // Render context
+    render_context_new: vello_render_context_new,
+    render_context_new_synchronized: vello_render_context_new_synchronized,
+    render_context_try_new: vello_render_context_try_new,
+    render_context_new_with: vello_render_context_new_with,
+    render_context_free: vello_render_context_free,
+    render_context_width: vello_render_context_width,
+    render_context_height: vello_render_context_height,
+    render_context_color_space: vello_render_context_color_space,
+    render_context_set_color_space: vello_render_context_set_color_space,
+    render_context_set_glyph_hinting: vello_render_context_set_glyph_hinting,
+    render_context_glyph_hinting: vello_render_context_glyph_hinting,
+    render_context_set_glyph_cache_limit: vello_render_context_set_glyph_cache_limit,
+    render_context_glyph_cache_stats: vello_render_context_glyph_cache_stats,
+    render_context_glyph_cache_clear: vello_render_context_glyph_cache_clear,
+    render_context_reset: vello_render_context_reset,
+    render_context_set_paint_solid: vello_render_context_set_paint_solid,
+    render_context_set_paint_linear_gradient: vello_render_context_set_paint_linear_gradient,
+    render_context_set_paint_radial_gradient: vello_render_context_set_paint_radial_gradient,
+    render_context_set_paint_sweep_gradient: vello_render_context_set_paint_sweep_gradient,
+    render_context_set_transform: vello_render_context_set_transform,
+    render_context_reset_transform: vello_render_context_reset_transform,
+    render_context_get_transform: vello_render_context_get_transform,
+    render_context_set_stroke: vello_render_context_set_stroke,
+    render_context_set_fill_rule: vello_render_context_set_fill_rule,
+    render_context_fill_rect: vello_render_context_fill_rect,
+    render_context_stroke_rect: vello_render_context_stroke_rect,
+    render_context_fill_blurred_rounded_rect: vello_render_context_fill_blurred_rounded_rect,
+    render_context_push_blend_layer: vello_render_context_push_blend_layer,
+    render_context_push_clip_layer: vello_render_context_push_clip_layer,
+    render_context_push_opacity_layer: vello_render_context_push_opacity_layer,
+    render_context_push_backdrop_blur_layer: vello_render_context_push_backdrop_blur_layer,
+    render_context_pop_layer: vello_render_context_pop_layer,
+    render_context_layer_depth: vello_render_context_layer_depth,
+    render_context_flush: vello_render_context_flush,
+    render_context_get_stroke: vello_render_context_get_stroke,
+    render_context_get_fill_rule: vello_render_context_get_fill_rule,
+    render_context_set_paint_transform: vello_render_context_set_paint_transform,
+    render_context_get_paint_transform: vello_render_context_get_paint_transform,
+    render_context_reset_paint_transform: vello_render_context_reset_paint_transform,
+    render_context_get_paint_kind: vello_render_context_get_paint_kind,
+    render_context_set_aliasing_threshold: vello_render_context_set_aliasing_threshold,
+    render_context_push_layer: vello_render_context_push_layer,
+    render_context_push_blend_layer_bounded: vello_render_context_push_blend_layer_bounded,
+    render_context_push_opacity_layer_bounded: vello_render_context_push_opacity_layer_bounded,
+    render_context_push_layer_bounded: vello_render_context_push_layer_bounded,
+    render_context_get_render_settings: vello_render_context_get_render_settings,
+    render_context_render_to_buffer_strided: vello_render_context_render_to_buffer_strided,
+    render_context_render_to_buffer_format: vello_render_context_render_to_buffer_format,
+    render_context_render_to_buffer: vello_render_context_render_to_buffer,
+    // Pixmap
+    pixmap_new: vello_pixmap_new,
+    pixmap_try_new: vello_pixmap_try_new,
+    pixmap_free: vello_pixmap_free,
+    pixmap_width: vello_pixmap_width,
+    pixmap_height: vello_pixmap_height,
+    pixmap_color_space: vello_pixmap_color_space,
+    pixmap_set_color_space: vello_pixmap_set_color_space,
+    pixmap_convert_color_space: vello_pixmap_convert_color_space,
+    pixmap_data: vello_pixmap_data,
+    pixmap_data_mut: vello_pixmap_data_mut,
+    pixmap_resize: vello_pixmap_resize,
+    pixmap_resize_to: vello_pixmap_resize_to,
+    pixmap_flip_horizontal: vello_pixmap_flip_horizontal,
+    pixmap_flip_vertical: vello_pixmap_flip_vertical,
+    pixmap_rotate90: vello_pixmap_rotate90,
+    pixmap_rotate180: vello_pixmap_rotate180,
+    pixmap_rotate270: vello_pixmap_rotate270,
+    pixmap_clear: vello_pixmap_clear,
+    pixmap_fill: vello_pixmap_fill,
+    pixmap_sample: vello_pixmap_sample,
+    pixmap_row: vello_pixmap_row,
+    pixmap_set_pixel: vello_pixmap_set_pixel,
+    pixmap_apply_color_matrix: vello_pixmap_apply_color_matrix,
+    pixmap_hash: vello_pixmap_hash,
+    pixmap_compare: vello_pixmap_compare,
+    pixmap_blit: vello_pixmap_blit,
+    pixmap_to_unpremultiplied: vello_pixmap_to_unpremultiplied,
+    pixmap_load_unpremultiplied: vello_pixmap_load_unpremultiplied,
+    pixmap_convert: vello_pixmap_convert,
+    pixmap_extract_alpha: vello_pixmap_extract_alpha,
+    pixmap_from_buffer: vello_pixmap_from_buffer,
+    pixmap_copy_to_buffer: vello_pixmap_copy_to_buffer,
+    pixmap_wrap: vello_pixmap_wrap,
+    render_context_render_to_pixmap: vello_render_context_render_to_pixmap,
+    #[cfg(feature = "png")]
+    pixmap_from_png: Some(vello_pixmap_from_png),
+    #[cfg(not(feature = "png"))]
+    pixmap_from_png: None,
+    #[cfg(feature = "png")]
+    pixmap_to_png: Some(vello_pixmap_to_png),
+    #[cfg(not(feature = "png"))]
+    pixmap_to_png: None,
+    #[cfg(feature = "png")]
+    pixmap_from_png_file: Some(vello_pixmap_from_png_file),
+    #[cfg(not(feature = "png"))]
+    pixmap_from_png_file: None,
+    #[cfg(feature = "png")]
+    pixmap_to_png_file: Some(vello_pixmap_to_png_file),
+    #[cfg(not(feature = "png"))]
+    pixmap_to_png_file: None,
+    #[cfg(feature = "png")]
+    png_data_free: Some(vello_png_data_free),
+    #[cfg(not(feature = "png"))]
+    png_data_free: None,
+    #[cfg(feature = "webp")]
+    pixmap_from_webp: Some(vello_pixmap_from_webp),
+    #[cfg(not(feature = "webp"))]
+    pixmap_from_webp: None,
+    #[cfg(feature = "webp")]
+    pixmap_to_webp: Some(vello_pixmap_to_webp),
+    #[cfg(not(feature = "webp"))]
+    pixmap_to_webp: None,
+    #[cfg(feature = "webp")]
+    webp_data_free: Some(vello_webp_data_free),
+    #[cfg(not(feature = "webp"))]
+    webp_data_free: None,
+    // Path
+    bezpath_new: vello_bezpath_new,
+    bezpath_free: vello_bezpath_free,
+    bezpath_move_to: vello_bezpath_move_to,
+    bezpath_line_to: vello_bezpath_line_to,
+    bezpath_quad_to: vello_bezpath_quad_to,
+    bezpath_curve_to: vello_bezpath_curve_to,
+    bezpath_close: vello_bezpath_close,
+    bezpath_clear: vello_bezpath_clear,
+    render_context_fill_path: vello_render_context_fill_path,
+    render_context_stroke_path: vello_render_context_stroke_path,
+    render_context_fill_path_shadow: vello_render_context_fill_path_shadow,
+    // Text
+    font_data_new: vello_font_data_new,
+    font_data_free: vello_font_data_free,
+    render_context_fill_glyphs: vello_render_context_fill_glyphs,
+    render_context_stroke_glyphs: vello_render_context_stroke_glyphs,
+    font_data_text_to_glyphs: vello_font_data_text_to_glyphs,
+    font_data_glyph_outline: vello_font_data_glyph_outline,
+    font_data_glyph_metrics: vello_font_data_glyph_metrics,
+    font_data_glyph_advances: vello_font_data_glyph_advances,
+    font_data_measure_text: vello_font_data_measure_text,
+    font_data_glyph_kind: vello_font_data_glyph_kind,
+    font_data_face_count: vello_font_data_face_count,
+    font_face_name: vello_font_face_name,
+    render_context_fill_text_on_path: vello_render_context_fill_text_on_path,
+    font_data_info: vello_font_data_info,
+    render_context_draw_text_decoration: vello_render_context_draw_text_decoration,
+    render_context_fill_glyphs_ex: vello_render_context_fill_glyphs_ex,
+    render_context_draw_text: vello_render_context_draw_text,
+    glyph_run_new: vello_glyph_run_new,
+    glyph_run_free: vello_glyph_run_free,
+    glyph_run_set_font_size: vello_glyph_run_set_font_size,
+    glyph_run_set_hint: vello_glyph_run_set_hint,
+    glyph_run_set_normalized_coords: vello_glyph_run_set_normalized_coords,
+    glyph_run_set_glyph_transform: vello_glyph_run_set_glyph_transform,
+    glyph_run_set_transform: vello_glyph_run_set_transform,
+    glyph_run_fill: vello_glyph_run_fill,
+    glyph_run_stroke: vello_glyph_run_stroke,
+    font_rasterize_glyph: vello_font_rasterize_glyph,
+    // Shaping
+    #[cfg(feature = "shaping")]
+    shape_text: Some(vello_shape_text),
+    #[cfg(not(feature = "shaping"))]
+    shape_text: None,
+    font_feature_tag: vello_font_feature_tag,
+    font_feature_parse: vello_font_feature_parse,
+    #[cfg(feature = "shaping")]
+    shape_text_runs: Some(vello_shape_text_runs),
+    #[cfg(not(feature = "shaping"))]
+    shape_text_runs: None,
+    // System fonts
+    #[cfg(feature = "system-fonts")]
+    font_enumerate_system: Some(vello_font_enumerate_system),
+    #[cfg(not(feature = "system-fonts"))]
+    font_enumerate_system: None,
+    #[cfg(feature = "system-fonts")]
+    font_data_new_from_system: Some(vello_font_data_new_from_system),
+    #[cfg(not(feature = "system-fonts"))]
+    font_data_new_from_system: None,
+    // Text layout
+    text_layout_new: vello_text_layout_new,
+    text_layout_free: vello_text_layout_free,
+    text_layout_advance: vello_text_layout_advance,
+    text_layout_glyphs: vello_text_layout_glyphs,
+    text_layout_runs: vello_text_layout_runs,
+    text_layout_carets: vello_text_layout_carets,
+    text_layout_hit_test: vello_text_layout_hit_test,
+    // Font registry
+    font_register: vello_font_register,
+    font_register_font_data: vello_font_register_font_data,
+    font_unregister: vello_font_unregister,
+    font_registry_get: vello_font_registry_get,
+    render_context_fill_glyphs_by_id: vello_render_context_fill_glyphs_by_id,
+    // Mask
+    mask_new_alpha: vello_mask_new_alpha,
+    mask_new_luminance: vello_mask_new_luminance,
+    mask_new_from_pixmap_alpha: vello_mask_new_from_pixmap_alpha,
+    mask_free: vello_mask_free,
+    mask_get_width: vello_mask_get_width,
+    mask_get_height: vello_mask_get_height,
+    mask_data: vello_mask_data,
+    mask_sample: vello_mask_sample,
+    mask_crop: vello_mask_crop,
+    mask_resize: vello_mask_resize,
+    mask_new_linear_gradient: vello_mask_new_linear_gradient,
+    render_context_push_mask_layer: vello_render_context_push_mask_layer,
+    render_context_push_mask_layer_from_path: vello_render_context_push_mask_layer_from_path,
+    // Image
+    image_new_from_pixmap: vello_image_new_from_pixmap,
+    image_free: vello_image_free,
+    render_context_set_paint_image: vello_render_context_set_paint_image,
+    // Recording
+    recording_new: vello_recording_new,
+    recording_free: vello_recording_free,
+    recording_clear: vello_recording_clear,
+    recording_len: vello_recording_len,
+    recording_has_cached_strips: vello_recording_has_cached_strips,
+    recording_strip_count: vello_recording_strip_count,
+    recording_alpha_count: vello_recording_alpha_count,
+    recording_serialize: vello_recording_serialize,
+    recording_deserialize: vello_recording_deserialize,
+    recording_data_free: vello_recording_data_free,
+    recording_format_version: vello_recording_format_version,
+    render_context_execute_recording_range: vello_render_context_execute_recording_range,
+    recording_append: vello_recording_append,
+    #[cfg(feature = "svg-export")]
+    recording_to_svg: Some(vello_recording_to_svg),
+    #[cfg(not(feature = "svg-export"))]
+    recording_to_svg: None,
+    #[cfg(feature = "svg-export")]
+    recording_svg_free: Some(vello_recording_svg_free),
+    #[cfg(not(feature = "svg-export"))]
+    recording_svg_free: None,
+    recording_invalidate_range: vello_recording_invalidate_range,
+    recording_replace_range: vello_recording_replace_range,
+    render_context_record: vello_render_context_record,
+    render_context_begin_record: vello_render_context_begin_record,
+    render_context_end_record: vello_render_context_end_record,
+    render_context_prepare_recording: vello_render_context_prepare_recording,
+    render_context_execute_recording: vello_render_context_execute_recording,
+    render_context_execute_recording_with_transform:
+        vello_render_context_execute_recording_with_transform,
+    render_context_execute_recording_instanced: vello_render_context_execute_recording_instanced,
+    recorder_fill_rect: vello_recorder_fill_rect,
+    recorder_stroke_rect: vello_recorder_stroke_rect,
+    recorder_fill_path: vello_recorder_fill_path,
+    recorder_stroke_path: vello_recorder_stroke_path,
+    recorder_set_paint_solid: vello_recorder_set_paint_solid,
+    recorder_set_transform: vello_recorder_set_transform,
+    recorder_set_fill_rule: vello_recorder_set_fill_rule,
+    recorder_set_stroke: vello_recorder_set_stroke,
+    recorder_set_paint_transform: vello_recorder_set_paint_transform,
+    recorder_reset_paint_transform: vello_recorder_reset_paint_transform,
+    recorder_push_clip_layer: vello_recorder_push_clip_layer,
+    recorder_pop_layer: vello_recorder_pop_layer,
+    recorder_set_paint_linear_gradient: vello_recorder_set_paint_linear_gradient,
+    recorder_set_paint_radial_gradient: vello_recorder_set_paint_radial_gradient,
+    recorder_set_paint_sweep_gradient: vello_recorder_set_paint_sweep_gradient,
+    recorder_set_paint_image: vello_recorder_set_paint_image,
+    recorder_fill_blurred_rounded_rect: vello_recorder_fill_blurred_rounded_rect,
+    recorder_push_blend_layer: vello_recorder_push_blend_layer,
+    recorder_push_opacity_layer: vello_recorder_push_opacity_layer,
+    recorder_push_mask_layer: vello_recorder_push_mask_layer,
+    recorder_push_layer: vello_recorder_push_layer,
+    recorder_fill_glyphs: vello_recorder_fill_glyphs,
+    recorder_stroke_glyphs: vello_recorder_stroke_glyphs,
+    render_context_set_paint_pattern: vello_render_context_set_paint_pattern,
+    // Filter
+    filter_new: vello_filter_new,
+    filter_free: vello_filter_free,
+    filter_len: vello_filter_len,
+    filter_gaussian_blur: vello_filter_gaussian_blur,
+    filter_color_matrix: vello_filter_color_matrix,
+    filter_offset: vello_filter_offset,
+    filter_composite: vello_filter_composite,
+    filter_merge: vello_filter_merge,
+    render_context_push_filter_layer: vello_render_context_push_filter_layer,
+    // Handle table
+    handle_table_insert: vello_handle_table_insert,
+    handle_table_get: vello_handle_table_get,
+    handle_table_remove: vello_handle_table_remove,
+    handle_table_len: vello_handle_table_len,
+    // Render context threading
+    render_context_set_num_threads: vello_render_context_set_num_threads,
+    render_context_adopt_thread_pool: vello_render_context_adopt_thread_pool,
+    // Thread pools
+    thread_pool_new: vello_thread_pool_new,
+    thread_pool_free: vello_thread_pool_free,
+    thread_pool_num_threads: vello_thread_pool_num_threads,
+    // Incremental rendering
+    render_context_render_dirty: vello_render_context_render_dirty,
+    render_context_tile_count: vello_render_context_tile_count,
+    render_context_render_tile: vello_render_context_render_tile,
+    // Batched commands
+    render_context_submit: vello_render_context_submit,
+    // Memory management
+    render_context_memory_usage: vello_render_context_memory_usage,
+    render_context_trim: vello_render_context_trim,
+    // Retained layers
+    layer_cache_new: vello_layer_cache_new,
+    layer_cache_free: vello_layer_cache_free,
+    layer_cache_begin: vello_layer_cache_begin,
+    layer_cache_end: vello_layer_cache_end,
+    layer_cache_invalidate: vello_layer_cache_invalidate,
+    layer_cache_is_valid: vello_layer_cache_is_valid,
+    render_context_draw_cached_layer: vello_render_context_draw_cached_layer,
+    // Strip access
+    render_context_strips: vello_render_context_strips,
+    rasterize_path_coverage: vello_rasterize_path_coverage,
+    // Pixmap pools
+    pixmap_pool_new: vello_pixmap_pool_new,
+    pixmap_pool_free: vello_pixmap_pool_free,
+    pixmap_pool_acquire: vello_pixmap_pool_acquire,
+    pixmap_pool_release: vello_pixmap_pool_release,
+    pixmap_pool_idle_count: vello_pixmap_pool_idle_count,
+    // Multi-context compositing
+    render_context_render_to_pixmap_at: vello_render_context_render_to_pixmap_at,
+    // Context snapshots
+    render_context_clone: vello_render_context_clone,
+    // Per-draw anti-aliasing
+    render_context_fill_rect_aa: vello_render_context_fill_rect_aa,
+    render_context_stroke_rect_aa: vello_render_context_stroke_rect_aa,
+    render_context_fill_path_aa: vello_render_context_fill_path_aa,
+    render_context_stroke_path_aa: vello_render_context_stroke_path_aa,
+    // Pixel snapping
+    render_context_set_pixel_snapping: vello_render_context_set_pixel_snapping,
+    render_context_pixel_snapping: vello_render_context_pixel_snapping,
+    // Blurred rounded rects with per-corner radii
+    render_context_fill_blurred_rounded_rect4: vello_render_context_fill_blurred_rounded_rect4,
+    // Hit testing
+    render_context_set_hit_testing: vello_render_context_set_hit_testing,
+    render_context_set_draw_id: vello_render_context_set_draw_id,
+    render_context_hit_test: vello_render_context_hit_test,
+    // Per-draw bounds
+    render_context_command_bounds: vello_render_context_command_bounds,
+    // Base color
+    render_context_set_base_color: vello_render_context_set_base_color,
+    render_context_clear_base_color: vello_render_context_clear_base_color,
+    // Per-call render mode
+    render_context_render_to_pixmap_mode: vello_render_context_render_to_pixmap_mode,
+    // Dashed strokes
+    render_context_set_stroke_dashed: vello_render_context_set_stroke_dashed,
+    render_context_get_stroke_dashes: vello_render_context_get_stroke_dashes,
+    recorder_set_stroke_dashed: vello_recorder_set_stroke_dashed,
+    // SVG documents
+    #[cfg(feature = "svg")]
+    svg_load: Some(vello_svg_load),
+    #[cfg(not(feature = "svg"))]
+    svg_load: None,
+    #[cfg(feature = "svg")]
+    svg_free: Some(vello_svg_free),
+    #[cfg(not(feature = "svg"))]
+    svg_free: None,
+    #[cfg(feature = "svg")]
+    svg_size: Some(vello_svg_size),
+    #[cfg(not(feature = "svg"))]
+    svg_size: None,
+    #[cfg(feature = "svg")]
+    render_context_draw_svg: Some(vello_render_context_draw_svg),
+    #[cfg(not(feature = "svg"))]
+    render_context_draw_svg: None,
+    // Skia compatibility
+    #[cfg(feature = "skia-compat")]
+    skia_canvas_new: Some(vello_skia_canvas_new),
+    #[cfg(not(feature = "skia-compat"))]
+    skia_canvas_new: None,
+    // Canvas
+    canvas_new: vello_canvas_new,
+    canvas_free: vello_canvas_free,
+    canvas_save: vello_canvas_save,
+    canvas_restore: vello_canvas_restore,
+    canvas_translate: vello_canvas_translate,
+    canvas_scale: vello_canvas_scale,
+    canvas_rotate: vello_canvas_rotate,
+    canvas_transform: vello_canvas_transform,
+    canvas_set_transform: vello_canvas_set_transform,
+    canvas_reset_transform: vello_canvas_reset_transform,
+    canvas_get_transform: vello_canvas_get_transform,
+    canvas_set_fill_style_color: vello_canvas_set_fill_style_color,
+    canvas_set_stroke_style_color: vello_canvas_set_stroke_style_color,
+    canvas_set_fill_style_linear_gradient: vello_canvas_set_fill_style_linear_gradient,
+    canvas_set_stroke_style_linear_gradient: vello_canvas_set_stroke_style_linear_gradient,
+    canvas_set_fill_style_radial_gradient: vello_canvas_set_fill_style_radial_gradient,
+    canvas_set_stroke_style_radial_gradient: vello_canvas_set_stroke_style_radial_gradient,
+    canvas_set_line_width: vello_canvas_set_line_width,
+    canvas_set_line_cap: vello_canvas_set_line_cap,
+    canvas_set_line_join: vello_canvas_set_line_join,
+    canvas_set_miter_limit: vello_canvas_set_miter_limit,
+    canvas_set_line_dash: vello_canvas_set_line_dash,
+    canvas_set_line_dash_offset: vello_canvas_set_line_dash_offset,
+    canvas_set_global_alpha: vello_canvas_set_global_alpha,
+    canvas_set_global_composite_operation: vello_canvas_set_global_composite_operation,
+    canvas_begin_path: vello_canvas_begin_path,
+    canvas_move_to: vello_canvas_move_to,
+    canvas_line_to: vello_canvas_line_to,
+    canvas_quadratic_curve_to: vello_canvas_quadratic_curve_to,
+    canvas_bezier_curve_to: vello_canvas_bezier_curve_to,
+    canvas_arc: vello_canvas_arc,
+    canvas_rect: vello_canvas_rect,
+    canvas_close_path: vello_canvas_close_path,
+    canvas_fill: vello_canvas_fill,
+    canvas_stroke: vello_canvas_stroke,
+    canvas_clip: vello_canvas_clip,
+    canvas_fill_rect: vello_canvas_fill_rect,
+    canvas_stroke_rect: vello_canvas_stroke_rect,
+    canvas_clear_rect: vello_canvas_clear_rect,
+    canvas_draw_image: vello_canvas_draw_image,
+    canvas_draw_image_source: vello_canvas_draw_image_source,
+    canvas_set_font: vello_canvas_set_font,
+    canvas_set_text_align: vello_canvas_set_text_align,
+    canvas_set_text_baseline: vello_canvas_set_text_baseline,
+    canvas_fill_text: vello_canvas_fill_text,
+    canvas_stroke_text: vello_canvas_stroke_text,
+    canvas_measure_text: vello_canvas_measure_text,
+    handle_table_acquire: vello_handle_table_acquire,
+    handle_table_release: vello_handle_table_release,
+    render_context_scene_strips: vello_render_context_scene_strips,
+    render_context_set_worker_settings: vello_render_context_set_worker_settings,
+    render_context_set_gradient_ramp_size: vello_render_context_set_gradient_ramp_size,
+};
+
+/// Get the function table, or null if this library does not provide `version`
+///
+/// Tables are backwards compatible from `VELLO_API_MIN_VERSION` on: a host built
+/// against version N can use the table of any library reporting N or later.
+/// Check `size` before reading entries added after the host was built.
+#[no_mangle]
+pub extern "C" fn vello_get_api(version: u32) -> *const VelloApi {
+    if !(VELLO_API_MIN_VERSION..=VELLO_API_VERSION).contains(&version) {
+        crate::error::set_last_error(format!(
+            "Function table version {} is not available (library provides {} to {})",
+            version, VELLO_API_MIN_VERSION, VELLO_API_VERSION
+        ));
+        return std::ptr::null();
+    }
+    &API
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retired_table_versions_are_refused() {
+        assert!(vello_get_api(0).is_null());
+        assert!(vello_get_api(1).is_null());
+        assert!(vello_get_api(VELLO_API_VERSION + 1).is_null());
+
+        let api = unsafe { &*vello_get_api(VELLO_API_VERSION) };
+        assert_eq!(api.size, std::mem::size_of::<VelloApi>());
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Gaussian blur helpers shared by the shadow and layer effect bindings
+
+use std::sync::Arc;
+
+use vello_common::paint::{Image, ImageSource};
+use vello_cpu::kurbo::{Affine, Rect};
+use vello_cpu::peniko::color::PremulRgba8;
+use vello_cpu::peniko::{self, Extend, ImageQuality};
+use vello_cpu::Pixmap;
+
+use crate::context::RenderContextHandle;
+use crate::scene::Scene;
+
+/// Build a normalized 1D gaussian kernel covering three standard deviations
+///
+/// Taps further than `max_radius` from the center are left out; the weights
+/// stay normalized over the full kernel, so the dropped taps only lose the
+/// coverage they would have spread outside a line of `max_radius + 1` samples.
+pub(crate) fn gaussian_kernel(std_dev: f32, max_radius: usize) -> Vec<f32> {
+    let full_radius = (std_dev * 3.0).ceil();
+    let radius = full_radius.min(max_radius as f32) as i32;
+    // Keep tiny deviations from dividing zero by zero at the center tap
+    let two_sigma_sq = (2.0 * std_dev * std_dev).max(f32::MIN_POSITIVE);
+    let mut kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-(i as f32 * i as f32) / two_sigma_sq).exp())
+        .collect();
+    let sum: f32 = if full_radius <= radius as f32 {
+        kernel.iter().sum()
+    } else {
+        // Sum of the untruncated kernel
+        std_dev * (2.0 * std::f32::consts::PI).sqrt()
+    };
+    for k in &mut kernel {
+        *k /= sum;
+    }
+    kernel
+}
+
+/// Number of pixels a blur with the given standard deviation spreads outwards
+pub(crate) fn blur_extent(std_dev: f32) -> f64 {
+    (std_dev.max(0.0) * 3.0).ceil() as f64
+}
+
+/// Blur premultiplied pixels in place; pixels outside the buffer are treated as transparent
+pub(crate) fn gaussian_blur(
+    data: &mut [PremulRgba8],
+    width: usize,
+    height: usize,
+    std_dev_x: f32,
+    std_dev_y: f32,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let mut scratch = vec![[0.0f32; 4]; width * height];
+    for (dst, src) in scratch.iter_mut().zip(data.iter()) {
+        *dst = [src.r as f32, src.g as f32, src.b as f32, src.a as f32];
+    }
+
+    if std_dev_x > 0.0 {
+        let kernel = gaussian_kernel(std_dev_x, width - 1);
+        blur_pass(&mut scratch, width, height, &kernel, 1, width);
+    }
+    if std_dev_y > 0.0 {
+        let kernel = gaussian_kernel(std_dev_y, height - 1);
+        blur_pass(&mut scratch, height, width, &kernel, width, 1);
+    }
+
+    for (dst, src) in data.iter_mut().zip(scratch.iter()) {
+        let a = src[3].round().clamp(0.0, 255.0);
+        // Keep the result a valid premultiplied color after rounding
+        *dst = PremulRgba8 {
+            r: src[0].round().clamp(0.0, a) as u8,
+            g: src[1].round().clamp(0.0, a) as u8,
+            b: src[2].round().clamp(0.0, a) as u8,
+            a: a as u8,
+        };
+    }
+}
+
+/// Convolve `lines` lines of `len` samples each; `step` is the distance between
+/// samples of a line and `line_step` the distance between the starts of two lines
+///
+/// The kernel must not be longer than `2 * len - 1` samples.
+fn blur_pass(
+    data: &mut [[f32; 4]],
+    len: usize,
+    lines: usize,
+    kernel: &[f32],
+    step: usize,
+    line_step: usize,
+) {
+    let radius = kernel.len() / 2;
+    let mut line = vec![[0.0f32; 4]; len];
+
+    for l in 0..lines {
+        let start = l * line_step;
+        for (i, px) in line.iter_mut().enumerate() {
+            *px = data[start + i * step];
+        }
+
+        for i in 0..len {
+            // Only the taps landing inside the line contribute
+            let first = radius.saturating_sub(i);
+            let last = kernel.len().min(len + radius - i);
+            let mut acc = [0.0f32; 4];
+            for (k, weight) in kernel.iter().enumerate().take(last).skip(first) {
+                for (sum, value) in acc.iter_mut().zip(line[i + k - radius]) {
+                    *sum += value * weight;
+                }
+            }
+            data[start + i * step] = acc;
+        }
+    }
+}
+
+/// Draw content into an offscreen context, blur it and composite the result
+/// onto `ctx`, leaving its state intact
+///
+/// `device_bounds` is the device-space area covered by the content before
+/// blurring. `draw` receives a context whose transform is `transform` shifted
+/// onto the offscreen area; it must set its own paint. The content is rendered
+/// on the context's thread pool if it has one.
+pub(crate) fn fill_blurred(
+    ctx: &mut RenderContextHandle,
+    device_bounds: Rect,
+    transform: Affine,
+    std_dev_x: f32,
+    std_dev_y: f32,
+    draw: impl FnOnce(&mut Scene),
+) {
+    // Device-space area touched by the blur, clipped to the canvas
+    let canvas = Rect::new(0.0, 0.0, ctx.width() as f64, ctx.height() as f64);
+    let bounds = device_bounds
+        .inflate(blur_extent(std_dev_x), blur_extent(std_dev_y))
+        .intersect(canvas)
+        .expand();
+    if bounds.width() < 1.0 || bounds.height() < 1.0 {
+        return;
+    }
+
+    let (width, height) = (bounds.width() as u16, bounds.height() as u16);
+    let mut blur_ctx = Scene::scratch(width, height, ctx.thread_pool());
+    blur_ctx.set_transform(Affine::translate((-bounds.x0, -bounds.y0)) * transform);
+    draw(&mut blur_ctx);
+    blur_ctx.flush();
+
+    let mut blurred = Pixmap::new(width, height);
+    blur_ctx.render_pixmap(&mut blurred, ctx.thread_pool());
+    gaussian_blur(
+        blurred.data_mut(),
+        width as usize,
+        height as usize,
+        std_dev_x,
+        std_dev_y,
+    );
+
+    let image = Image {
+        image: ImageSource::Pixmap(Arc::new(blurred)),
+        sampler: peniko::ImageSampler {
+            x_extend: Extend::Pad,
+            y_extend: Extend::Pad,
+            quality: ImageQuality::Low,
+            alpha: 1.0,
+        },
+    };
+
+    // Composite in device space, then restore the caller's state
+    let ctx: &mut Scene = ctx;
+    let saved_paint = ctx.paint().clone();
+    let saved_paint_transform = *ctx.paint_transform();
+    let saved_transform = *ctx.transform();
+
+    ctx.set_transform(Affine::IDENTITY);
+    ctx.set_paint(image);
+    ctx.set_paint_transform(Affine::translate((bounds.x0, bounds.y0)));
+    ctx.fill_rect(&bounds);
+
+    ctx.set_transform(saved_transform);
+    ctx.set_paint(saved_paint);
+    ctx.set_paint_transform(saved_paint_transform);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernels_are_clamped_to_the_line() {
+        let kernel = gaussian_kernel(1.0, 16);
+        assert_eq!(kernel.len(), 7);
+        assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+        // Huge deviations spread almost everything outside the line
+        for std_dev in [1e30, f32::MAX] {
+            let kernel = gaussian_kernel(std_dev, 4);
+            assert_eq!(kernel.len(), 9);
+            assert!(kernel.iter().all(|k| k.is_finite() && *k >= 0.0 && *k < 1e-6));
+        }
+        assert_eq!(gaussian_kernel(1e-30, 4), [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn huge_deviations_blur_to_transparent() {
+        let opaque = PremulRgba8 { r: 255, g: 128, b: 0, a: 255 };
+        let mut data = vec![opaque; 6 * 4];
+        gaussian_blur(&mut data, 6, 4, 1e30, f32::MAX);
+        assert!(data.iter().all(|px| *px == PremulRgba8 { r: 0, g: 0, b: 0, a: 0 }));
+
+        // A small blur keeps the center of a uniform area intact
+        let mut data = vec![opaque; 9 * 9];
+        gaussian_blur(&mut data, 9, 9, 0.5, 0.5);
+        assert_eq!(data[4 * 9 + 4], opaque);
+    }
+}
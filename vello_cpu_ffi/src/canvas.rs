@@ -0,0 +1,1298 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Stateful 2D canvas following the HTML `CanvasRenderingContext2D` model
+//!
+//! A `VelloCanvas` keeps the drawing state of an HTML canvas (fill and stroke
+//! styles, line settings, global alpha and composite operation, font, text
+//! alignment, transform and clip) on a save/restore stack, plus a current path
+//! that is built with `vello_canvas_move_to`, `vello_canvas_line_to` and
+//! friends and drawn with `vello_canvas_fill`, `vello_canvas_stroke` or
+//! `vello_canvas_clip`. As in HTML, path points are transformed when they are
+//! added, while line widths and gradient coordinates use the transform current
+//! at the time of drawing.
+//!
+//! The canvas draws into a render context, which must outlive it, and
+//! overwrites the context's paint, transform, fill rule and stroke as it goes.
+//! Shadows, filters, patterns and image smoothing settings are not modelled,
+//! and `Start`/`End` text alignment assumes left-to-right text.
+
+use std::os::raw::{c_char, c_int};
+
+use vello_common::paint::PaintType;
+use vello_cpu::kurbo::{self, Affine, BezPath, Cap, Join, Point, Rect, Shape, Stroke};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::{BlendMode, ColorStop, Compose, Fill, FontData, Gradient, Mix};
+use vello_cpu::Glyph;
+
+use crate::context::{color_stops, RenderContextHandle};
+use crate::error::set_last_error;
+use crate::ffi_catch;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::image::{ImageHandle, VelloImage};
+use crate::shaping::{shape_text, VelloTextDirection};
+use crate::text::{FontHandle, VelloFontData};
+use crate::types::*;
+
+#[repr(C)]
+pub struct VelloCanvas {
+    _private: [u8; 0],
+}
+
+/// Horizontal text alignment relative to the drawing position (`textAlign`)
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloCanvasTextAlign {
+    Start = 0,
+    End = 1,
+    Left = 2,
+    Right = 3,
+    Center = 4,
+}
+
+/// Vertical text alignment relative to the drawing position (`textBaseline`)
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloCanvasTextBaseline {
+    Alphabetic = 0,
+    Top = 1,
+    Middle = 2,
+    Bottom = 3,
+}
+
+/// Drawing state saved and restored by `vello_canvas_save`/`vello_canvas_restore`
+#[derive(Clone)]
+struct CanvasState {
+    transform: Affine,
+    fill_style: PaintType,
+    stroke_style: PaintType,
+    /// Line width, caps, join, miter limit and dash pattern
+    stroke: Stroke,
+    global_alpha: f32,
+    composite: BlendMode,
+    font: Option<FontData>,
+    font_size: f32,
+    text_align: VelloCanvasTextAlign,
+    text_baseline: VelloCanvasTextBaseline,
+    /// Clip layers open on the context while this state is current
+    clips: usize,
+}
+
+impl Default for CanvasState {
+    fn default() -> Self {
+        Self {
+            transform: Affine::IDENTITY,
+            fill_style: PaintType::Solid(AlphaColor::BLACK),
+            stroke_style: PaintType::Solid(AlphaColor::BLACK),
+            stroke: Stroke::new(1.0)
+                .with_caps(Cap::Butt)
+                .with_join(Join::Miter)
+                .with_miter_limit(10.0),
+            global_alpha: 1.0,
+            composite: BlendMode::new(Mix::Normal, Compose::SrcOver),
+            font: None,
+            font_size: 10.0,
+            text_align: VelloCanvasTextAlign::Start,
+            text_baseline: VelloCanvasTextBaseline::Alphabetic,
+            clips: 0,
+        }
+    }
+}
+
+/// Current path, kept in device space
+#[derive(Default)]
+struct CanvasPath {
+    path: BezPath,
+    /// Start of the current subpath
+    start: Option<Point>,
+    /// Whether the current subpath is closed, so the next segment starts a
+    /// new subpath at `start`
+    closed: bool,
+}
+
+impl CanvasPath {
+    fn move_to(&mut self, p: Point) {
+        self.path.move_to(p);
+        self.start = Some(p);
+        self.closed = false;
+    }
+
+    /// Make sure a segment can be added; without a subpath `p` starts one
+    /// and false is returned
+    fn ensure_subpath(&mut self, p: Point) -> bool {
+        match self.start {
+            None => {
+                self.move_to(p);
+                false
+            }
+            Some(start) => {
+                if self.closed {
+                    self.move_to(start);
+                }
+                true
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        if self.start.is_some() && !self.closed {
+            self.path.close_path();
+            self.closed = true;
+        }
+    }
+}
+
+/// Object behind a `VelloCanvas` pointer
+#[repr(C)]
+pub(crate) struct CanvasHandle {
+    header: HandleHeader,
+    ctx: *mut VelloRenderContext,
+    state: CanvasState,
+    saved: Vec<CanvasState>,
+    path: CanvasPath,
+}
+
+impl TypedHandle for CanvasHandle {
+    const TAG: u32 = handle::tag(b"VCNV");
+    const NAME: &'static str = "canvas";
+}
+
+impl CanvasHandle {
+    fn restore(&mut self, ctx: &mut RenderContextHandle) {
+        if let Some(state) = self.saved.pop() {
+            self.pop_clips(ctx, state.clips);
+            self.state = state;
+        }
+    }
+
+    fn pop_clips(&mut self, ctx: &mut RenderContextHandle, count: usize) {
+        while self.state.clips > count {
+            ctx.pop_layer();
+            self.state.clips -= 1;
+        }
+    }
+
+    /// Whether the transform is invertible; nothing is drawn otherwise
+    fn drawable(&self) -> bool {
+        let det = self.state.transform.determinant();
+        det.is_finite() && det != 0.0
+    }
+
+    /// Run a draw with global alpha and the composite operation applied
+    fn composited(
+        &self,
+        ctx: &mut RenderContextHandle,
+        draw: impl FnOnce(&mut RenderContextHandle),
+    ) {
+        let state = &self.state;
+        let layered = state.global_alpha < 1.0
+            || state.composite != BlendMode::new(Mix::Normal, Compose::SrcOver);
+        if layered {
+            ctx.push_layer(None, Some(state.composite), Some(state.global_alpha), None);
+        }
+        draw(ctx);
+        if layered {
+            ctx.pop_layer();
+        }
+    }
+
+    /// Fill `path`, given in the space of `transform`, with the fill style
+    fn fill(
+        &self,
+        ctx: &mut RenderContextHandle,
+        path: &BezPath,
+        transform: Affine,
+        fill_rule: Fill,
+    ) {
+        // Gradients are defined in the current user space
+        let paint_transform = transform.inverse() * self.state.transform;
+        self.composited(ctx, |ctx| {
+            ctx.set_paint(self.state.fill_style.clone());
+            ctx.set_paint_transform(paint_transform);
+            ctx.set_transform(transform);
+            ctx.set_fill_rule(fill_rule);
+            ctx.fill_path(path);
+        });
+    }
+
+    /// Stroke `path`, given in user space, with the stroke style and line settings
+    fn stroke(&self, ctx: &mut RenderContextHandle, path: &BezPath) {
+        self.composited(ctx, |ctx| {
+            ctx.set_paint(self.state.stroke_style.clone());
+            ctx.set_paint_transform(Affine::IDENTITY);
+            ctx.set_transform(self.state.transform);
+            ctx.set_stroke(self.state.stroke.clone());
+            ctx.stroke_path(path);
+        });
+    }
+
+    /// Shape `text` and place it according to the text alignment settings
+    fn layout_text(&self, text: &str) -> Result<(&FontData, Vec<Glyph>), &'static str> {
+        use skrifa::instance::{LocationRef, Size};
+        use skrifa::MetadataProvider;
+
+        let state = &self.state;
+        let Some(font) = state.font.as_ref() else {
+            return Err("No font set on the canvas");
+        };
+        let shaped = shape_text(
+            font,
+            text,
+            state.font_size,
+            VelloTextDirection::Auto,
+            None,
+            &[],
+        )?;
+        let width: f32 = shaped.iter().map(|g| g.x_advance).sum();
+        let dx = match state.text_align {
+            VelloCanvasTextAlign::Start | VelloCanvasTextAlign::Left => 0.0,
+            VelloCanvasTextAlign::End | VelloCanvasTextAlign::Right => -width,
+            VelloCanvasTextAlign::Center => -width / 2.0,
+        };
+        let dy = if state.text_baseline == VelloCanvasTextBaseline::Alphabetic {
+            0.0
+        } else {
+            let font_ref = crate::shaping::font_ref(font).ok_or("Invalid font data")?;
+            let metrics = font_ref.metrics(Size::new(state.font_size), LocationRef::default());
+            match state.text_baseline {
+                VelloCanvasTextBaseline::Alphabetic => 0.0,
+                VelloCanvasTextBaseline::Top => metrics.ascent,
+                VelloCanvasTextBaseline::Middle => (metrics.ascent + metrics.descent) / 2.0,
+                VelloCanvasTextBaseline::Bottom => metrics.descent,
+            }
+        };
+        let glyphs = shaped
+            .iter()
+            .map(|g| Glyph {
+                id: g.id,
+                x: g.x + dx,
+                y: g.y + dy,
+            })
+            .collect();
+        Ok((font, glyphs))
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut RenderContextHandle,
+        text: &str,
+        x: f64,
+        y: f64,
+        stroke: bool,
+    ) -> c_int {
+        let (font, glyphs) = match self.layout_text(text) {
+            Ok(layout) => layout,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+        if !self.drawable() {
+            return VELLO_OK;
+        }
+        let hint = ctx.glyph_hinting();
+        self.composited(ctx, |ctx| {
+            ctx.set_paint_transform(Affine::IDENTITY);
+            ctx.set_transform(self.state.transform * Affine::translate((x, y)));
+            let font_size = self.state.font_size;
+            if stroke {
+                ctx.set_paint(self.state.stroke_style.clone());
+                ctx.set_stroke(self.state.stroke.clone());
+                ctx.glyph_run(font)
+                    .font_size(font_size)
+                    .hint(hint)
+                    .stroke_glyphs(glyphs.into_iter());
+            } else {
+                ctx.set_paint(self.state.fill_style.clone());
+                ctx.glyph_run(font)
+                    .font_size(font_size)
+                    .hint(hint)
+                    .fill_glyphs(glyphs.into_iter());
+            }
+        });
+        VELLO_OK
+    }
+}
+
+/// Run `f` on a canvas without touching its context
+fn with_canvas(canvas: *mut VelloCanvas, f: impl FnOnce(&mut CanvasHandle) -> c_int) -> c_int {
+    if canvas.is_null() {
+        set_last_error("Null canvas pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    ffi_catch!({ f(CanvasHandle::from_ptr_mut(canvas)) })
+}
+
+/// Run `f` on a canvas and the context it draws into
+fn with_context(
+    canvas: *mut VelloCanvas,
+    f: impl FnOnce(&mut CanvasHandle, &mut RenderContextHandle) -> c_int,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        let ctx = RenderContextHandle::from_ptr_mut(canvas.ctx);
+        f(canvas, ctx)
+    })
+}
+
+/// Convert gradient color stops, validating their offsets
+fn to_color_stops(
+    stops: *const VelloColorStop,
+    count: usize,
+    ramp_size: u16,
+) -> Result<Vec<ColorStop>, String> {
+    if count < 2 {
+        return Err("Gradient requires at least 2 color stops".to_string());
+    }
+    let stops = unsafe { std::slice::from_raw_parts(stops, count) };
+    color_stops(stops, ramp_size)
+}
+
+/// Set the fill or stroke style of a canvas to a gradient
+fn set_gradient_style(
+    canvas: *mut VelloCanvas,
+    stroke: bool,
+    gradient: Gradient,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+) -> c_int {
+    if stops.is_null() {
+        set_last_error("Null stops pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(canvas, |canvas, ctx| {
+        let stops = match to_color_stops(stops, stop_count, ctx.gradient_ramp_size) {
+            Ok(stops) => stops,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+        let style = PaintType::Gradient(gradient.with_stops(&stops[..]));
+        if stroke {
+            canvas.state.stroke_style = style;
+        } else {
+            canvas.state.fill_style = style;
+        }
+        VELLO_OK
+    })
+}
+
+/// Angle swept by `arc`, following the HTML canvas rules
+fn arc_sweep(start_angle: f64, end_angle: f64, anticlockwise: bool) -> f64 {
+    use std::f64::consts::TAU;
+
+    if anticlockwise {
+        if start_angle - end_angle >= TAU {
+            -TAU
+        } else {
+            -(start_angle - end_angle).rem_euclid(TAU)
+        }
+    } else if end_angle - start_angle >= TAU {
+        TAU
+    } else {
+        (end_angle - start_angle).rem_euclid(TAU)
+    }
+}
+
+fn text_arg<'a>(text: *const c_char) -> Option<&'a str> {
+    let text = unsafe { std::ffi::CStr::from_ptr(text) }.to_str().ok();
+    if text.is_none() {
+        set_last_error("Invalid UTF-8");
+    }
+    text
+}
+
+/// Create a canvas drawing into a render context
+///
+/// The context must outlive the canvas. The canvas starts with the HTML
+/// defaults: identity transform, no clip, black fill and stroke styles, line
+/// width 1 and no font.
+#[no_mangle]
+pub extern "C" fn vello_canvas_new(ctx: *mut VelloRenderContext) -> *mut VelloCanvas {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return std::ptr::null_mut();
+    }
+
+    if !RenderContextHandle::check(ctx) {
+        return std::ptr::null_mut();
+    }
+
+    let canvas = CanvasHandle {
+        header: HandleHeader::new::<CanvasHandle>(),
+        ctx,
+        state: CanvasState::default(),
+        saved: Vec::new(),
+        path: CanvasPath::default(),
+    };
+    handle::into_raw(canvas) as *mut VelloCanvas
+}
+
+/// Free a canvas, closing the clip layers it left open on its context
+#[no_mangle]
+pub extern "C" fn vello_canvas_free(canvas: *mut VelloCanvas) {
+    if !canvas.is_null() && CanvasHandle::check(canvas) {
+        with_context(canvas, |canvas, ctx| {
+            canvas.pop_clips(ctx, 0);
+            VELLO_OK
+        });
+        handle::free::<CanvasHandle, _>(canvas);
+    }
+}
+
+/// Push the drawing state onto the state stack
+#[no_mangle]
+pub extern "C" fn vello_canvas_save(canvas: *mut VelloCanvas) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.saved.push(canvas.state.clone());
+        VELLO_OK
+    })
+}
+
+/// Pop the drawing state, removing clips added since the save
+///
+/// Does nothing if the stack is empty, as in HTML.
+#[no_mangle]
+pub extern "C" fn vello_canvas_restore(canvas: *mut VelloCanvas) -> c_int {
+    with_context(canvas, |canvas, ctx| {
+        canvas.restore(ctx);
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_translate(canvas: *mut VelloCanvas, x: f64, y: f64) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.transform *= Affine::translate((x, y));
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_scale(canvas: *mut VelloCanvas, x: f64, y: f64) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.transform *= Affine::scale_non_uniform(x, y);
+        VELLO_OK
+    })
+}
+
+/// Rotate clockwise by `angle` radians
+#[no_mangle]
+pub extern "C" fn vello_canvas_rotate(canvas: *mut VelloCanvas, angle: f64) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.transform *= Affine::rotate(angle);
+        VELLO_OK
+    })
+}
+
+/// Multiply the transform by the matrix `[a c e; b d f; 0 0 1]`
+#[no_mangle]
+pub extern "C" fn vello_canvas_transform(
+    canvas: *mut VelloCanvas,
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.transform *= Affine::new([a, b, c, d, e, f]);
+        VELLO_OK
+    })
+}
+
+/// Replace the transform with the matrix `[a c e; b d f; 0 0 1]`
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_transform(
+    canvas: *mut VelloCanvas,
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.transform = Affine::new([a, b, c, d, e, f]);
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_reset_transform(canvas: *mut VelloCanvas) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.transform = Affine::IDENTITY;
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_get_transform(
+    canvas: *const VelloCanvas,
+    out_transform: *mut VelloAffine,
+) -> c_int {
+    if out_transform.is_null() {
+        set_last_error("Null transform pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_canvas(canvas as *mut VelloCanvas, |canvas| {
+        let [m11, m12, m21, m22, m13, m23] = canvas.state.transform.as_coeffs();
+        unsafe {
+            *out_transform = VelloAffine {
+                m11,
+                m12,
+                m21,
+                m22,
+                m13,
+                m23,
+            };
+        }
+        VELLO_OK
+    })
+}
+
+/// Set the fill style to a solid color (non-premultiplied RGBA8)
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_fill_style_color(
+    canvas: *mut VelloCanvas,
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.fill_style = PaintType::Solid(AlphaColor::from_rgba8(r, g, b, a));
+        VELLO_OK
+    })
+}
+
+/// Set the stroke style to a solid color (non-premultiplied RGBA8)
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_stroke_style_color(
+    canvas: *mut VelloCanvas,
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.stroke_style = PaintType::Solid(AlphaColor::from_rgba8(r, g, b, a));
+        VELLO_OK
+    })
+}
+
+/// Set the fill style to a linear gradient (`createLinearGradient`)
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_fill_style_linear_gradient(
+    canvas: *mut VelloCanvas,
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+) -> c_int {
+    let gradient = Gradient::new_linear(Point::new(x0, y0), Point::new(x1, y1));
+    set_gradient_style(canvas, false, gradient, stops, stop_count)
+}
+
+/// Set the stroke style to a linear gradient (`createLinearGradient`)
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_stroke_style_linear_gradient(
+    canvas: *mut VelloCanvas,
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+) -> c_int {
+    let gradient = Gradient::new_linear(Point::new(x0, y0), Point::new(x1, y1));
+    set_gradient_style(canvas, true, gradient, stops, stop_count)
+}
+
+/// Set the fill style to a two-circle radial gradient (`createRadialGradient`)
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn vello_canvas_set_fill_style_radial_gradient(
+    canvas: *mut VelloCanvas,
+    x0: f64,
+    y0: f64,
+    r0: f32,
+    x1: f64,
+    y1: f64,
+    r1: f32,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+) -> c_int {
+    if !(r0.is_finite() && r0 >= 0.0 && r1.is_finite() && r1 >= 0.0) {
+        set_last_error("Gradient radii must be non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    let gradient = Gradient::new_two_point_radial(Point::new(x0, y0), r0, Point::new(x1, y1), r1);
+    set_gradient_style(canvas, false, gradient, stops, stop_count)
+}
+
+/// Set the stroke style to a two-circle radial gradient (`createRadialGradient`)
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn vello_canvas_set_stroke_style_radial_gradient(
+    canvas: *mut VelloCanvas,
+    x0: f64,
+    y0: f64,
+    r0: f32,
+    x1: f64,
+    y1: f64,
+    r1: f32,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+) -> c_int {
+    if !(r0.is_finite() && r0 >= 0.0 && r1.is_finite() && r1 >= 0.0) {
+        set_last_error("Gradient radii must be non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    let gradient = Gradient::new_two_point_radial(Point::new(x0, y0), r0, Point::new(x1, y1), r1);
+    set_gradient_style(canvas, true, gradient, stops, stop_count)
+}
+
+/// Set the line width; must be finite and positive
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_line_width(canvas: *mut VelloCanvas, width: f64) -> c_int {
+    if !(width.is_finite() && width > 0.0) {
+        set_last_error("Line width must be finite and positive");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    with_canvas(canvas, |canvas| {
+        canvas.state.stroke.width = width;
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_line_cap(canvas: *mut VelloCanvas, cap: VelloCap) -> c_int {
+    let cap = match cap {
+        VelloCap::Butt => Cap::Butt,
+        VelloCap::Square => Cap::Square,
+        VelloCap::Round => Cap::Round,
+    };
+    with_canvas(canvas, |canvas| {
+        canvas.state.stroke.start_cap = cap;
+        canvas.state.stroke.end_cap = cap;
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_line_join(canvas: *mut VelloCanvas, join: VelloJoin) -> c_int {
+    let join = match join {
+        VelloJoin::Bevel => Join::Bevel,
+        VelloJoin::Miter => Join::Miter,
+        VelloJoin::Round => Join::Round,
+    };
+    with_canvas(canvas, |canvas| {
+        canvas.state.stroke.join = join;
+        VELLO_OK
+    })
+}
+
+/// Set the miter limit; must be finite and positive
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_miter_limit(canvas: *mut VelloCanvas, limit: f64) -> c_int {
+    if !(limit.is_finite() && limit > 0.0) {
+        set_last_error("Miter limit must be finite and positive");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    with_canvas(canvas, |canvas| {
+        canvas.state.stroke.miter_limit = limit;
+        VELLO_OK
+    })
+}
+
+/// Set the dash pattern (`setLineDash`); an empty pattern draws solid lines
+///
+/// Patterns with an odd number of entries are repeated to make them even.
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_line_dash(
+    canvas: *mut VelloCanvas,
+    dashes: *const f64,
+    count: usize,
+) -> c_int {
+    if count > 0 && dashes.is_null() {
+        set_last_error("Null dashes pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    let dashes = if count > 0 {
+        unsafe { std::slice::from_raw_parts(dashes, count) }
+    } else {
+        &[]
+    };
+    if dashes.iter().any(|d| !(d.is_finite() && *d >= 0.0)) {
+        set_last_error("Dash lengths must be finite and non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    with_canvas(canvas, |canvas| {
+        let repeat = if dashes.len() % 2 == 1 { 2 } else { 1 };
+        let pattern = dashes.iter().copied().cycle().take(dashes.len() * repeat);
+        let stroke = &mut canvas.state.stroke;
+        *stroke = stroke.clone().with_dashes(stroke.dash_offset, pattern);
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_line_dash_offset(
+    canvas: *mut VelloCanvas,
+    offset: f64,
+) -> c_int {
+    if !offset.is_finite() {
+        set_last_error("Dash offset must be finite");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    with_canvas(canvas, |canvas| {
+        canvas.state.stroke.dash_offset = offset;
+        VELLO_OK
+    })
+}
+
+/// Set the opacity applied to every draw, in [0, 1]
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_global_alpha(canvas: *mut VelloCanvas, alpha: f32) -> c_int {
+    if !(0.0..=1.0).contains(&alpha) {
+        set_last_error("Global alpha must be in [0, 1]");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    with_canvas(canvas, |canvas| {
+        canvas.state.global_alpha = alpha;
+        VELLO_OK
+    })
+}
+
+/// Set the blend mode applied to every draw (`globalCompositeOperation`)
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_global_composite_operation(
+    canvas: *mut VelloCanvas,
+    blend_mode: *const VelloBlendMode,
+) -> c_int {
+    if blend_mode.is_null() {
+        set_last_error("Null blend mode pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_canvas(canvas, |canvas| {
+        canvas.state.composite = unsafe { *blend_mode }.into();
+        VELLO_OK
+    })
+}
+
+/// Clear the current path
+#[no_mangle]
+pub extern "C" fn vello_canvas_begin_path(canvas: *mut VelloCanvas) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.path = CanvasPath::default();
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_move_to(canvas: *mut VelloCanvas, x: f64, y: f64) -> c_int {
+    with_canvas(canvas, |canvas| {
+        let p = canvas.state.transform * Point::new(x, y);
+        canvas.path.move_to(p);
+        VELLO_OK
+    })
+}
+
+/// Add a line to the current path; without a subpath this starts one instead
+#[no_mangle]
+pub extern "C" fn vello_canvas_line_to(canvas: *mut VelloCanvas, x: f64, y: f64) -> c_int {
+    with_canvas(canvas, |canvas| {
+        let p = canvas.state.transform * Point::new(x, y);
+        if canvas.path.ensure_subpath(p) {
+            canvas.path.path.line_to(p);
+        }
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_quadratic_curve_to(
+    canvas: *mut VelloCanvas,
+    cpx: f64,
+    cpy: f64,
+    x: f64,
+    y: f64,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        let t = canvas.state.transform;
+        let (p1, p) = (t * Point::new(cpx, cpy), t * Point::new(x, y));
+        canvas.path.ensure_subpath(p1);
+        canvas.path.path.quad_to(p1, p);
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_bezier_curve_to(
+    canvas: *mut VelloCanvas,
+    cp1x: f64,
+    cp1y: f64,
+    cp2x: f64,
+    cp2y: f64,
+    x: f64,
+    y: f64,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        let t = canvas.state.transform;
+        let (p1, p2) = (t * Point::new(cp1x, cp1y), t * Point::new(cp2x, cp2y));
+        let p = t * Point::new(x, y);
+        canvas.path.ensure_subpath(p1);
+        canvas.path.path.curve_to(p1, p2, p);
+        VELLO_OK
+    })
+}
+
+/// Add a circular arc, connected to the current subpath by a straight line
+///
+/// Angles are in radians, measured clockwise from the positive x axis.
+#[no_mangle]
+pub extern "C" fn vello_canvas_arc(
+    canvas: *mut VelloCanvas,
+    x: f64,
+    y: f64,
+    radius: f64,
+    start_angle: f64,
+    end_angle: f64,
+    anticlockwise: bool,
+) -> c_int {
+    if !(radius.is_finite() && radius >= 0.0) {
+        set_last_error("Arc radius must be non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    with_canvas(canvas, |canvas| {
+        let t = canvas.state.transform;
+        let sweep = arc_sweep(start_angle, end_angle, anticlockwise);
+        let arc = kurbo::Arc::new((x, y), (radius, radius), start_angle, sweep, 0.0);
+        let (sin, cos) = start_angle.sin_cos();
+        let first = t * Point::new(x + radius * cos, y + radius * sin);
+        if canvas.path.ensure_subpath(first) {
+            canvas.path.path.line_to(first);
+        }
+        for el in arc.append_iter(0.1) {
+            canvas.path.path.push(t * el);
+        }
+        VELLO_OK
+    })
+}
+
+/// Add a closed rectangle subpath
+#[no_mangle]
+pub extern "C" fn vello_canvas_rect(
+    canvas: *mut VelloCanvas,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        let t = canvas.state.transform;
+        canvas.path.move_to(t * Point::new(x, y));
+        canvas.path.path.line_to(t * Point::new(x + width, y));
+        canvas
+            .path
+            .path
+            .line_to(t * Point::new(x + width, y + height));
+        canvas.path.path.line_to(t * Point::new(x, y + height));
+        canvas.path.close();
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_close_path(canvas: *mut VelloCanvas) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.path.close();
+        VELLO_OK
+    })
+}
+
+/// Fill the current path with the fill style
+#[no_mangle]
+pub extern "C" fn vello_canvas_fill(canvas: *mut VelloCanvas, fill_rule: VelloFillRule) -> c_int {
+    with_context(canvas, |canvas, ctx| {
+        if canvas.drawable() {
+            let fill_rule = match fill_rule {
+                VelloFillRule::NonZero => Fill::NonZero,
+                VelloFillRule::EvenOdd => Fill::EvenOdd,
+            };
+            canvas.fill(ctx, &canvas.path.path, Affine::IDENTITY, fill_rule);
+        }
+        VELLO_OK
+    })
+}
+
+/// Stroke the current path with the stroke style and line settings
+#[no_mangle]
+pub extern "C" fn vello_canvas_stroke(canvas: *mut VelloCanvas) -> c_int {
+    with_context(canvas, |canvas, ctx| {
+        if canvas.drawable() {
+            // Line widths apply in the current user space
+            let path = canvas.state.transform.inverse() * canvas.path.path.clone();
+            canvas.stroke(ctx, &path);
+        }
+        VELLO_OK
+    })
+}
+
+/// Intersect the clip with the current path
+#[no_mangle]
+pub extern "C" fn vello_canvas_clip(canvas: *mut VelloCanvas, fill_rule: VelloFillRule) -> c_int {
+    with_context(canvas, |canvas, ctx| {
+        ctx.set_transform(Affine::IDENTITY);
+        ctx.set_fill_rule(match fill_rule {
+            VelloFillRule::NonZero => Fill::NonZero,
+            VelloFillRule::EvenOdd => Fill::EvenOdd,
+        });
+        ctx.push_clip_layer(&canvas.path.path);
+        canvas.state.clips += 1;
+        VELLO_OK
+    })
+}
+
+/// Fill a rectangle with the fill style, leaving the current path unchanged
+#[no_mangle]
+pub extern "C" fn vello_canvas_fill_rect(
+    canvas: *mut VelloCanvas,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> c_int {
+    with_context(canvas, |canvas, ctx| {
+        if canvas.drawable() {
+            let rect = Rect::new(x, y, x + width, y + height).to_path(0.1);
+            canvas.fill(ctx, &rect, canvas.state.transform, Fill::NonZero);
+        }
+        VELLO_OK
+    })
+}
+
+/// Stroke a rectangle with the stroke style, leaving the current path unchanged
+#[no_mangle]
+pub extern "C" fn vello_canvas_stroke_rect(
+    canvas: *mut VelloCanvas,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> c_int {
+    with_context(canvas, |canvas, ctx| {
+        if canvas.drawable() {
+            canvas.stroke(ctx, &Rect::new(x, y, x + width, y + height).to_path(0.1));
+        }
+        VELLO_OK
+    })
+}
+
+/// Make a rectangle transparent, within the clip
+#[no_mangle]
+pub extern "C" fn vello_canvas_clear_rect(
+    canvas: *mut VelloCanvas,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> c_int {
+    with_context(canvas, |canvas, ctx| {
+        if canvas.drawable() {
+            let rect = Rect::new(x, y, x + width, y + height);
+            ctx.set_transform(canvas.state.transform);
+            ctx.set_fill_rule(Fill::NonZero);
+            ctx.set_paint(AlphaColor::<Srgb>::TRANSPARENT);
+            ctx.set_paint_transform(Affine::IDENTITY);
+            // Copy replaces everything under the layer, so clip it to the rect
+            ctx.push_layer(
+                Some(&rect.to_path(0.1)),
+                Some(BlendMode::new(Mix::Normal, Compose::Copy)),
+                None,
+                None,
+            );
+            // Not a draw, so it bypasses hit testing and snapping
+            (**ctx).fill_rect(&rect);
+            ctx.pop_layer();
+        }
+        VELLO_OK
+    })
+}
+
+/// Draw the `sx, sy, sw, sh` part of an image into `dx, dy, dw, dh`
+fn draw_image(
+    canvas: *mut VelloCanvas,
+    image: *const VelloImage,
+    src: Option<Rect>,
+    dst: Rect,
+) -> c_int {
+    if image.is_null() {
+        set_last_error("Null image pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(canvas, |canvas, ctx| {
+        let image = ImageHandle::from_ptr(image);
+        if (image.image.sampler.alpha - 1.0).abs() > f32::EPSILON {
+            set_last_error("Image opacity is not supported yet");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        let Some((width, height)) = image.size() else {
+            set_last_error("Image has no pixel data");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        let src = src.unwrap_or(Rect::new(0.0, 0.0, width as f64, height as f64));
+        if src.area() == 0.0 || dst.area() == 0.0 || !canvas.drawable() {
+            return VELLO_OK;
+        }
+
+        let image_transform = Affine::translate(dst.origin().to_vec2())
+            * Affine::scale_non_uniform(dst.width() / src.width(), dst.height() / src.height())
+            * Affine::translate(-src.origin().to_vec2());
+        let paint = image.in_color_space(ctx.color_space());
+        canvas.composited(ctx, |ctx| {
+            ctx.set_paint(paint);
+            ctx.set_paint_transform(image_transform);
+            ctx.set_transform(canvas.state.transform);
+            ctx.set_fill_rule(Fill::NonZero);
+            ctx.fill_path(&dst.to_path(0.1));
+        });
+        VELLO_OK
+    })
+}
+
+/// Draw a whole image scaled into a rectangle (`drawImage` with five arguments)
+#[no_mangle]
+pub extern "C" fn vello_canvas_draw_image(
+    canvas: *mut VelloCanvas,
+    image: *const VelloImage,
+    dx: f64,
+    dy: f64,
+    dw: f64,
+    dh: f64,
+) -> c_int {
+    draw_image(canvas, image, None, Rect::new(dx, dy, dx + dw, dy + dh))
+}
+
+/// Draw part of an image scaled into a rectangle (`drawImage` with nine arguments)
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn vello_canvas_draw_image_source(
+    canvas: *mut VelloCanvas,
+    image: *const VelloImage,
+    sx: f64,
+    sy: f64,
+    sw: f64,
+    sh: f64,
+    dx: f64,
+    dy: f64,
+    dw: f64,
+    dh: f64,
+) -> c_int {
+    let src = Rect::new(sx, sy, sx + sw, sy + sh);
+    draw_image(
+        canvas,
+        image,
+        Some(src),
+        Rect::new(dx, dy, dx + dw, dy + dh),
+    )
+}
+
+/// Set the font and its size in pixels
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_font(
+    canvas: *mut VelloCanvas,
+    font: *const VelloFontData,
+    font_size: f32,
+) -> c_int {
+    if font.is_null() {
+        set_last_error("Null font pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    if !(font_size.is_finite() && font_size > 0.0) {
+        set_last_error("Font size must be finite and positive");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    with_canvas(canvas, |canvas| {
+        canvas.state.font = Some(FontHandle::from_ptr(font).font.clone());
+        canvas.state.font_size = font_size;
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_text_align(
+    canvas: *mut VelloCanvas,
+    align: VelloCanvasTextAlign,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.text_align = align;
+        VELLO_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn vello_canvas_set_text_baseline(
+    canvas: *mut VelloCanvas,
+    baseline: VelloCanvasTextBaseline,
+) -> c_int {
+    with_canvas(canvas, |canvas| {
+        canvas.state.text_baseline = baseline;
+        VELLO_OK
+    })
+}
+
+/// Fill a line of UTF-8 text with the fill style at `x`, `y`
+#[no_mangle]
+pub extern "C" fn vello_canvas_fill_text(
+    canvas: *mut VelloCanvas,
+    text: *const c_char,
+    x: f64,
+    y: f64,
+) -> c_int {
+    if text.is_null() {
+        set_last_error("Null text pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(canvas, |canvas, ctx| match text_arg(text) {
+        Some(text) => canvas.draw_text(ctx, text, x, y, false),
+        None => VELLO_ERROR_INVALID_PARAMETER,
+    })
+}
+
+/// Stroke a line of UTF-8 text with the stroke style at `x`, `y`
+#[no_mangle]
+pub extern "C" fn vello_canvas_stroke_text(
+    canvas: *mut VelloCanvas,
+    text: *const c_char,
+    x: f64,
+    y: f64,
+) -> c_int {
+    if text.is_null() {
+        set_last_error("Null text pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(canvas, |canvas, ctx| match text_arg(text) {
+        Some(text) => canvas.draw_text(ctx, text, x, y, true),
+        None => VELLO_ERROR_INVALID_PARAMETER,
+    })
+}
+
+/// Measure the advance width of a line of UTF-8 text in the current font
+#[no_mangle]
+pub extern "C" fn vello_canvas_measure_text(
+    canvas: *mut VelloCanvas,
+    text: *const c_char,
+    out_width: *mut f32,
+) -> c_int {
+    if text.is_null() || out_width.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_canvas(canvas, |canvas| {
+        let Some(text) = text_arg(text) else {
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        let Some(font) = canvas.state.font.as_ref() else {
+            set_last_error("No font set on the canvas");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        let state = &canvas.state;
+        match shape_text(
+            font,
+            text,
+            state.font_size,
+            VelloTextDirection::Auto,
+            None,
+            &[],
+        ) {
+            Ok(glyphs) => {
+                unsafe { *out_width = glyphs.iter().map(|g| g.x_advance).sum() };
+                VELLO_OK
+            }
+            Err(msg) => {
+                set_last_error(msg);
+                VELLO_ERROR_INVALID_PARAMETER
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_free, vello_render_context_new, vello_render_context_pop_layer,
+    };
+    use crate::test_util::{render, translate};
+
+    #[test]
+    fn unbalanced_restore_keeps_the_state() {
+        let ctx = vello_render_context_new(8, 8);
+        let canvas = vello_canvas_new(ctx);
+        assert_eq!(vello_canvas_translate(canvas, 2.0, 3.0), VELLO_OK);
+        assert_eq!(vello_canvas_restore(canvas), VELLO_OK);
+
+        let mut transform = translate(0.0, 0.0);
+        assert_eq!(vello_canvas_get_transform(canvas, &mut transform), VELLO_OK);
+        assert_eq!(transform, translate(2.0, 3.0));
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_ERROR_LAYER_STACK_EMPTY);
+
+        vello_canvas_free(canvas);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn restore_pops_the_clips_added_since_the_save() {
+        let ctx = vello_render_context_new(16, 8);
+        let canvas = vello_canvas_new(ctx);
+        assert_eq!(vello_canvas_save(canvas), VELLO_OK);
+        assert_eq!(vello_canvas_rect(canvas, 0.0, 0.0, 8.0, 8.0), VELLO_OK);
+        assert_eq!(vello_canvas_clip(canvas, VelloFillRule::NonZero), VELLO_OK);
+        assert_eq!(vello_canvas_set_fill_style_color(canvas, 255, 0, 0, 255), VELLO_OK);
+        assert_eq!(vello_canvas_fill_rect(canvas, 0.0, 0.0, 16.0, 8.0), VELLO_OK);
+        assert_eq!(vello_canvas_restore(canvas), VELLO_OK);
+
+        // Drawing outside the old clip shows up again
+        assert_eq!(vello_canvas_set_fill_style_color(canvas, 0, 0, 255, 255), VELLO_OK);
+        assert_eq!(vello_canvas_fill_rect(canvas, 12.0, 0.0, 4.0, 8.0), VELLO_OK);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_ERROR_LAYER_STACK_EMPTY);
+        let pixels = render(ctx, 16, 8);
+        assert_eq!(pixels[4 * 16 + 2], [255, 0, 0, 255]);
+        assert_eq!(pixels[4 * 16 + 10], [0, 0, 0, 0]);
+        assert_eq!(pixels[4 * 16 + 14], [0, 0, 255, 255]);
+
+        // Freeing the canvas closes the clips it left open
+        assert_eq!(vello_canvas_clip(canvas, VelloFillRule::NonZero), VELLO_OK);
+        vello_canvas_free(canvas);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_ERROR_LAYER_STACK_EMPTY);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn restore_brings_back_the_saved_state() {
+        let ctx = vello_render_context_new(8, 8);
+        let canvas = vello_canvas_new(ctx);
+        assert_eq!(vello_canvas_save(canvas), VELLO_OK);
+        assert_eq!(vello_canvas_translate(canvas, 4.0, 4.0), VELLO_OK);
+        assert_eq!(vello_canvas_set_fill_style_color(canvas, 255, 0, 0, 255), VELLO_OK);
+        assert_eq!(vello_canvas_set_global_alpha(canvas, 0.5), VELLO_OK);
+        assert_eq!(vello_canvas_restore(canvas), VELLO_OK);
+
+        let mut transform = translate(4.0, 4.0);
+        assert_eq!(vello_canvas_get_transform(canvas, &mut transform), VELLO_OK);
+        assert_eq!(transform, translate(0.0, 0.0));
+        // Opaque black at the origin is the default fill style
+        assert_eq!(vello_canvas_fill_rect(canvas, 0.0, 0.0, 4.0, 4.0), VELLO_OK);
+        let pixels = render(ctx, 8, 8);
+        assert_eq!(pixels[2 * 8 + 2], [0, 0, 0, 255]);
+        assert_eq!(pixels[6 * 8 + 6], [0, 0, 0, 0]);
+
+        vello_canvas_free(canvas);
+        vello_render_context_free(ctx);
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Color space conversion of premultiplied pixel data
+
+use std::sync::OnceLock;
+
+use vello_cpu::peniko::color::PremulRgba8;
+
+use crate::types::VelloColorSpace;
+
+/// Linear sRGB to linear Display P3 (both D65)
+const SRGB_TO_P3: [[f32; 3]; 3] = [
+    [0.822_462_1, 0.177_538, 0.0],
+    [0.033_194_2, 0.966_805_8, 0.0],
+    [0.017_082_7, 0.072_397_4, 0.910_519_9],
+];
+
+/// Linear Display P3 to linear sRGB (both D65)
+const P3_TO_SRGB: [[f32; 3]; 3] = [
+    [1.224_940_1, -0.224_940_4, 0.0],
+    [-0.042_056_9, 1.042_057_1, 0.0],
+    [-0.019_637_6, -0.078_636_1, 1.098_273_5],
+];
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.040_45 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// sRGB-encoded 8-bit value to linear light
+fn decode_lut() -> &'static [f32; 256] {
+    static LUT: OnceLock<[f32; 256]> = OnceLock::new();
+    LUT.get_or_init(|| std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)))
+}
+
+fn multiply(m: &[[f32; 3]; 3], c: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|row| m[row][0] * c[0] + m[row][1] * c[1] + m[row][2] * c[2])
+}
+
+/// Convert premultiplied pixels between color spaces in place
+///
+/// Colors outside the destination gamut are clipped.
+pub(crate) fn convert(data: &mut [PremulRgba8], from: VelloColorSpace, to: VelloColorSpace) {
+    if from == to {
+        return;
+    }
+
+    let lut = decode_lut();
+    for px in data.iter_mut() {
+        let [r, g, b, a] = crate::pixel::unpremultiply_rgba8(*px);
+        if a == 0 {
+            continue;
+        }
+
+        let encoded = [r, g, b];
+        let mut linear = match from {
+            VelloColorSpace::Srgb | VelloColorSpace::DisplayP3 => encoded.map(|v| lut[v as usize]),
+            VelloColorSpace::LinearSrgb => encoded.map(|v| v as f32 / 255.0),
+        };
+
+        // Both sRGB variants share primaries, so only P3 needs a gamut change
+        match (from, to) {
+            (VelloColorSpace::DisplayP3, _) => linear = multiply(&P3_TO_SRGB, linear),
+            (_, VelloColorSpace::DisplayP3) => linear = multiply(&SRGB_TO_P3, linear),
+            _ => {}
+        }
+
+        let out = match to {
+            VelloColorSpace::Srgb | VelloColorSpace::DisplayP3 => {
+                linear.map(|v| linear_to_srgb(v.clamp(0.0, 1.0)))
+            }
+            VelloColorSpace::LinearSrgb => linear.map(|v| v.clamp(0.0, 1.0)),
+        };
+        let channel = |v: f32| (v * 255.0).round() as u8;
+        *px = crate::pixel::premultiply_rgba8([channel(out[0]), channel(out[1]), channel(out[2]), a]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_fill_rect, vello_render_context_free, vello_render_context_new,
+        vello_render_context_set_color_space,
+    };
+    use crate::image::{
+        vello_image_free, vello_image_new_from_pixmap, vello_render_context_set_paint_image,
+    };
+    use crate::pixmap::{vello_pixmap_free, vello_pixmap_new, vello_pixmap_set_color_space};
+    use crate::test_util::{pixmap_pixels, render};
+    use crate::types::*;
+
+    fn opaque(r: u8, g: u8, b: u8) -> PremulRgba8 {
+        PremulRgba8 { r, g, b, a: 255 }
+    }
+
+    fn assert_near(actual: [u8; 4], expected: [u8; 4], tolerance: u8) {
+        let near = actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= tolerance);
+        assert!(near, "{actual:?} is not within {tolerance} of {expected:?}");
+    }
+
+    fn rgba(px: PremulRgba8) -> [u8; 4] {
+        [px.r, px.g, px.b, px.a]
+    }
+
+    #[test]
+    fn linear_round_trips_stay_within_tolerance() {
+        for i in 0..=255 {
+            let v = i as f32 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(v)) - v).abs() < 1e-5, "{i}");
+        }
+
+        // 8-bit linear data is too coarse for dark tones, so only check the upper half
+        let mut data: Vec<PremulRgba8> = (128..=255).map(|v| opaque(v, v, v)).collect();
+        let original = data.clone();
+        convert(&mut data, VelloColorSpace::Srgb, VelloColorSpace::LinearSrgb);
+        assert_eq!(rgba(data[188 - 128]), [128, 128, 128, 255]);
+        assert_eq!(rgba(data[127]), [255, 255, 255, 255]);
+        convert(&mut data, VelloColorSpace::LinearSrgb, VelloColorSpace::Srgb);
+        for (back, original) in data.into_iter().zip(original) {
+            assert_near(rgba(back), rgba(original), 1);
+        }
+    }
+
+    #[test]
+    fn primaries_map_between_srgb_and_display_p3() {
+        // sRGB red lies inside P3; P3 red is outside sRGB and clips
+        let transparent = PremulRgba8 { r: 0, g: 0, b: 0, a: 0 };
+        let mut data = [opaque(255, 0, 0), opaque(255, 255, 255), transparent];
+        convert(&mut data, VelloColorSpace::Srgb, VelloColorSpace::DisplayP3);
+        assert_near(rgba(data[0]), [234, 51, 35, 255], 1);
+        assert_eq!(rgba(data[1]), [255; 4]);
+        assert_eq!(rgba(data[2]), [0; 4]);
+
+        let mut p3_red = [opaque(255, 0, 0)];
+        convert(&mut p3_red, VelloColorSpace::DisplayP3, VelloColorSpace::Srgb);
+        assert_eq!(rgba(p3_red[0]), [255, 0, 0, 255]);
+
+        // Premultiplied pixels convert their unpremultiplied color
+        let mut translucent = [PremulRgba8 { r: 128, g: 0, b: 0, a: 128 }];
+        convert(&mut translucent, VelloColorSpace::Srgb, VelloColorSpace::DisplayP3);
+        assert_near(rgba(translucent[0]), [117, 26, 18, 128], 1);
+    }
+
+    #[test]
+    fn image_paints_are_converted_to_the_target_color_space() {
+        let draw = |PremulRgba8 { r, g, b, a }, tag, target| {
+            let pixmap = vello_pixmap_new(4, 4);
+            pixmap_pixels(pixmap).fill(VelloPremulRgba8 { r, g, b, a });
+            assert_eq!(vello_pixmap_set_color_space(pixmap, tag), VELLO_OK);
+            let (pad, low) = (VelloExtend::Pad, VelloImageQuality::Low);
+            let image = vello_image_new_from_pixmap(pixmap, pad, pad, low, 1.0);
+
+            let ctx = vello_render_context_new(4, 4);
+            assert_eq!(vello_render_context_set_color_space(ctx, target), VELLO_OK);
+            assert_eq!(vello_render_context_set_paint_image(ctx, image), VELLO_OK);
+            let rect = VelloRect { x0: 0.0, y0: 0.0, x1: 4.0, y1: 4.0 };
+            assert_eq!(vello_render_context_fill_rect(ctx, &rect), VELLO_OK);
+            let pixels = render(ctx, 4, 4);
+
+            vello_render_context_free(ctx);
+            vello_image_free(image);
+            vello_pixmap_free(pixmap);
+            pixels[5]
+        };
+        let (srgb, p3) = (VelloColorSpace::Srgb, VelloColorSpace::DisplayP3);
+        let p3_color = opaque(234, 51, 35);
+
+        assert_eq!(draw(p3_color, srgb, srgb), [234, 51, 35, 255]);
+        assert_near(draw(p3_color, p3, srgb), [255, 0, 0, 255], 2);
+        assert_near(draw(opaque(255, 0, 0), srgb, p3), [234, 51, 35, 255], 1);
+        assert_eq!(draw(p3_color, p3, p3), [234, 51, 35, 255]);
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Batched command submission
+//!
+//! Hosts with per-call marshaling overhead can fill an array of
+//! [`VelloCommand`] structures and execute all of them with a single call to
+//! [`vello_render_context_submit`], instead of one FFI transition per draw.
+
+use std::os::raw::c_int;
+
+use vello_cpu::kurbo::{Affine, Rect};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::Fill;
+
+use crate::context::{to_stroke, RenderContextHandle};
+use crate::error::set_last_error;
+use crate::ffi_catch;
+use crate::handle::TypedHandle;
+use crate::path::PathHandle;
+use crate::types::*;
+
+/// Operation performed by a [`VelloCommand`]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloCommandKind {
+    /// Set solid paint from `data.rgba` (non-premultiplied)
+    SetPaintSolid = 0,
+    /// Set the transform from `data.transform`
+    SetTransform = 1,
+    /// Reset the transform to identity
+    ResetTransform = 2,
+    /// Set the fill rule from `data.fill_rule`
+    SetFillRule = 3,
+    /// Set stroke parameters from `data.stroke`
+    SetStroke = 4,
+    /// Fill `data.rect`
+    FillRect = 5,
+    /// Stroke `data.rect`
+    StrokeRect = 6,
+    /// Fill the path referenced by `data.path`
+    FillPath = 7,
+    /// Stroke the path referenced by `data.path`
+    StrokePath = 8,
+    /// Push a clip layer with the path referenced by `data.path`
+    PushClipLayer = 9,
+    /// Push a blend layer with `data.blend_mode`
+    PushBlendLayer = 10,
+    /// Push an opacity layer with `data.opacity`
+    PushOpacityLayer = 11,
+    /// Pop the innermost layer
+    PopLayer = 12,
+}
+
+/// Payload of a [`VelloCommand`]; the active field is selected by its kind
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union VelloCommandData {
+    pub rgba: [u8; 4],
+    pub transform: VelloAffine,
+    pub fill_rule: VelloFillRule,
+    pub stroke: VelloStroke,
+    pub rect: VelloRect,
+    pub path: *const VelloBezPath,
+    pub blend_mode: VelloBlendMode,
+    pub opacity: f32,
+}
+
+/// One entry of a command batch
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct VelloCommand {
+    pub kind: VelloCommandKind,
+    pub data: VelloCommandData,
+}
+
+/// Execute one command, returning an error code and message on failure
+fn execute(ctx: &mut RenderContextHandle, cmd: &VelloCommand) -> Result<(), (c_int, &'static str)> {
+    let data = &cmd.data;
+    unsafe {
+        match cmd.kind {
+            VelloCommandKind::SetPaintSolid => {
+                let [r, g, b, a] = data.rgba;
+                ctx.set_paint(AlphaColor::<Srgb>::from_rgba8(r, g, b, a));
+            }
+            VelloCommandKind::SetTransform => {
+                let t = &data.transform;
+                ctx.set_transform(Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]));
+            }
+            VelloCommandKind::ResetTransform => ctx.reset_transform(),
+            VelloCommandKind::SetFillRule => ctx.set_fill_rule(match data.fill_rule {
+                VelloFillRule::NonZero => Fill::NonZero,
+                VelloFillRule::EvenOdd => Fill::EvenOdd,
+            }),
+            VelloCommandKind::SetStroke => {
+                let stroke =
+                    to_stroke(&data.stroke).map_err(|msg| (VELLO_ERROR_INVALID_PARAMETER, msg))?;
+                ctx.set_stroke(stroke);
+            }
+            VelloCommandKind::FillRect => {
+                let r = &data.rect;
+                ctx.fill_rect(&Rect::new(r.x0, r.y0, r.x1, r.y1));
+            }
+            VelloCommandKind::StrokeRect => {
+                let r = &data.rect;
+                ctx.stroke_rect(&Rect::new(r.x0, r.y0, r.x1, r.y1));
+            }
+            VelloCommandKind::FillPath
+            | VelloCommandKind::StrokePath
+            | VelloCommandKind::PushClipLayer => {
+                if data.path.is_null() {
+                    return Err((VELLO_ERROR_NULL_POINTER, "null path"));
+                }
+                if !PathHandle::check(data.path) {
+                    return Err((VELLO_ERROR_INVALID_HANDLE, "invalid path handle"));
+                }
+                let path = &PathHandle::from_ptr(data.path).path;
+                match cmd.kind {
+                    VelloCommandKind::FillPath => ctx.fill_path(path),
+                    VelloCommandKind::StrokePath => ctx.stroke_path(path),
+                    _ => ctx.push_clip_layer(path),
+                }
+            }
+            VelloCommandKind::PushBlendLayer => ctx.push_blend_layer(data.blend_mode.into()),
+            VelloCommandKind::PushOpacityLayer => ctx.push_opacity_layer(data.opacity),
+            VelloCommandKind::PopLayer => {
+                if !ctx.pop_layer() {
+                    return Err((VELLO_ERROR_LAYER_STACK_EMPTY, "no layer to pop"));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Execute `count` commands in order with a single call
+///
+/// Equivalent to calling the matching `vello_render_context_*` function for
+/// each command. Execution stops at the first failing command; the commands
+/// before it stay applied and the error message names the failing index.
+#[no_mangle]
+pub extern "C" fn vello_render_context_submit(
+    ctx: *mut VelloRenderContext,
+    cmds: *const VelloCommand,
+    count: usize,
+) -> c_int {
+    if ctx.is_null() || (cmds.is_null() && count > 0) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        if count == 0 {
+            return VELLO_OK;
+        }
+        let cmds = unsafe { std::slice::from_raw_parts(cmds, count) };
+        for (i, cmd) in cmds.iter().enumerate() {
+            if let Err((code, msg)) = execute(ctx, cmd) {
+                set_last_error(format!("Command {i} ({:?}): {msg}", cmd.kind));
+                return code;
+            }
+        }
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_free, vello_render_context_new, vello_render_context_pop_layer,
+        vello_render_context_push_opacity_layer, vello_render_context_set_paint_solid,
+    };
+    use crate::path::{
+        vello_bezpath_close, vello_bezpath_free, vello_bezpath_line_to, vello_bezpath_move_to,
+        vello_bezpath_new, vello_render_context_fill_path,
+    };
+    use crate::pixmap::{vello_pixmap_free, vello_pixmap_new};
+    use crate::test_util::{fill, render};
+
+    #[test]
+    fn submitted_commands_match_direct_calls() {
+        let path = vello_bezpath_new();
+        assert_eq!(vello_bezpath_move_to(path, 4.0, 4.0), VELLO_OK);
+        assert_eq!(vello_bezpath_line_to(path, 12.0, 4.0), VELLO_OK);
+        assert_eq!(vello_bezpath_line_to(path, 8.0, 12.0), VELLO_OK);
+        assert_eq!(vello_bezpath_close(path), VELLO_OK);
+
+        let direct = vello_render_context_new(16, 16);
+        fill(direct, [255, 0, 0, 255], 0.0, 0.0, 8.0, 8.0);
+        assert_eq!(vello_render_context_push_opacity_layer(direct, 0.5), VELLO_OK);
+        assert_eq!(vello_render_context_set_paint_solid(direct, 0, 0, 255, 255), VELLO_OK);
+        assert_eq!(vello_render_context_fill_path(direct, path), VELLO_OK);
+        assert_eq!(vello_render_context_pop_layer(direct), VELLO_OK);
+
+        let command = |kind, data| VelloCommand { kind, data };
+        let rect = VelloRect { x0: 0.0, y0: 0.0, x1: 8.0, y1: 8.0 };
+        let commands = [
+            command(VelloCommandKind::SetPaintSolid, VelloCommandData { rgba: [255, 0, 0, 255] }),
+            command(VelloCommandKind::FillRect, VelloCommandData { rect }),
+            command(VelloCommandKind::PushOpacityLayer, VelloCommandData { opacity: 0.5 }),
+            command(VelloCommandKind::SetPaintSolid, VelloCommandData { rgba: [0, 0, 255, 255] }),
+            command(VelloCommandKind::FillPath, VelloCommandData { path }),
+            command(VelloCommandKind::PopLayer, VelloCommandData { opacity: 0.0 }),
+        ];
+        let submitted = vello_render_context_new(16, 16);
+        let result = vello_render_context_submit(submitted, commands.as_ptr(), commands.len());
+        assert_eq!(result, VELLO_OK);
+        assert_eq!(render(submitted, 16, 16), render(direct, 16, 16));
+
+        // Execution stops at the failing command, which the error names
+        let pixmap = vello_pixmap_new(1, 1);
+        let commands = [
+            command(VelloCommandKind::FillRect, VelloCommandData { rect }),
+            command(VelloCommandKind::FillPath, VelloCommandData { path: pixmap as *const _ }),
+        ];
+        let ctx = vello_render_context_new(16, 16);
+        let result = vello_render_context_submit(ctx, commands.as_ptr(), commands.len());
+        assert_eq!(result, VELLO_ERROR_INVALID_HANDLE);
+        let message = unsafe { std::ffi::CStr::from_ptr(crate::error::vello_get_last_error()) };
+        assert!(message.to_str().unwrap().starts_with("Command 1"));
+        let pop = [command(VelloCommandKind::PopLayer, VelloCommandData { opacity: 0.0 })];
+        let result = vello_render_context_submit(ctx, pop.as_ptr(), 1);
+        assert_eq!(result, VELLO_ERROR_LAYER_STACK_EMPTY);
+
+        vello_render_context_free(ctx);
+        vello_render_context_free(submitted);
+        vello_render_context_free(direct);
+        vello_pixmap_free(pixmap);
+        vello_bezpath_free(path);
+    }
+}
@@ -3,24 +3,681 @@
 
 //! RenderContext FFI bindings
 
+use std::ffi::c_void;
+use std::ops::{Deref, DerefMut};
 use std::os::raw::c_int;
+use std::sync::Arc;
 
-use vello_cpu::RenderContext;
+use rayon::ThreadPool;
+
+use vello_cpu::kurbo::{self, Affine, BezPath, Cap, Join, Rect, Stroke};
+use vello_cpu::peniko::color::PremulRgba8;
+use vello_cpu::peniko::BlendMode;
+use vello_cpu::{Mask, Pixmap, RenderContext};
 
 use crate::error::set_last_error;
+use crate::glyph_cache::{GlyphCache, VelloGlyphCacheStats};
+use crate::handle::{self, CallScope, HandleHeader, HandleLock, TypedHandle};
+use crate::hit_test::{self, HitGeometry, HitTestState};
+use crate::mask::MaskHandle;
+use crate::path::PathHandle;
+use crate::pixmap::try_new_pixmap;
+use crate::recording::RecorderHandle;
+use crate::scene::{DrawTarget, Scene};
+use crate::snap;
+use crate::strips::StripOutput;
+use crate::thread_pool::{ThreadPoolHandle, VelloThreadPool};
 use crate::types::*;
 use crate::{ffi_catch, ffi_catch_ptr};
 
+/// Completion step of an offscreen layer: receives the parent context and the
+/// rendered layer contents and composites them into the parent
+pub(crate) type OffscreenComposite = Box<dyn FnOnce(&mut Scene, Pixmap) + Send>;
+
+/// Layer rendered into its own context and composited by the FFI layer on pop
+pub(crate) struct OffscreenLayer {
+    ctx: Scene,
+    /// Number of regular layers currently open inside this offscreen layer
+    depth: usize,
+    composite: OffscreenComposite,
+}
+
+/// Coverage threshold used by `VelloAaMode::Aliased`
+const ALIASED_THRESHOLD: u8 = 128;
+
+/// Object behind a `VelloRenderContext` pointer
+///
+/// Dereferences to the context currently receiving draw calls, which is the
+/// innermost offscreen layer if one is open. The layer methods shadow the
+/// `RenderContext` ones so offscreen layers are balanced correctly, and the
+/// size, settings, flush and render methods shadow them so queries always see
+/// the root context.
+#[repr(C)]
+pub struct RenderContextHandle {
+    header: HandleHeader,
+    base: Scene,
+    /// Number of regular layers currently open on the base context
+    depth: usize,
+    offscreen: Vec<OffscreenLayer>,
+    /// Color space of the render target; image paints are converted into it
+    color_space: VelloColorSpace,
+    /// Staging pixmap reused by format-converting readbacks
+    scratch: Option<Pixmap>,
+    /// Whether glyph runs are hinted (only effective for axis-aligned transforms)
+    glyph_hinting: bool,
+    pub(crate) glyph_cache: GlyphCache,
+    /// Recorder opened by `vello_render_context_begin_record`; it borrows the
+    /// caller's recording until `vello_render_context_end_record`
+    pub(crate) recorder: Option<Box<RecorderHandle>>,
+    /// Lock serializing calls from different threads, for synchronized contexts
+    sync: Option<HandleLock>,
+    /// Caller-provided pool that flushes and renders run on
+    thread_pool: Option<Arc<ThreadPool>>,
+    /// Buffers returned by `vello_render_context_strips`
+    pub(crate) strip_output: StripOutput,
+    /// Threshold set with `vello_render_context_set_aliasing_threshold`
+    aliasing_threshold: Option<u8>,
+    /// Whether axis-aligned rects and hairlines are snapped to pixels
+    pixel_snapping: bool,
+    /// Draws tagged for `vello_render_context_hit_test`
+    pub(crate) hit_test: HitTestState,
+    /// Opaque color rendered output is composited over
+    base_color: Option<PremulRgba8>,
+    /// Stops gradient paints are resampled into, 0 to keep the given stops
+    pub(crate) gradient_ramp_size: u16,
+    /// Tolerance set with `vello_render_context_set_stroke_dashed`
+    dash_tolerance: Option<f64>,
+}
+
+impl RenderContextHandle {
+    pub(crate) fn new(ctx: RenderContext) -> Self {
+        Self::with_base(Scene::new(ctx))
+    }
+
+    fn with_base(base: Scene) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            base,
+            depth: 0,
+            offscreen: Vec::new(),
+            color_space: VelloColorSpace::Srgb,
+            scratch: None,
+            glyph_hinting: true,
+            glyph_cache: GlyphCache::default(),
+            recorder: None,
+            sync: None,
+            thread_pool: None,
+            strip_output: StripOutput::default(),
+            aliasing_threshold: None,
+            pixel_snapping: false,
+            hit_test: HitTestState::default(),
+            base_color: None,
+            gradient_ramp_size: 0,
+            dash_tolerance: None,
+        }
+    }
+
+    pub(crate) fn glyph_hinting(&self) -> bool {
+        self.glyph_hinting
+    }
+
+    pub(crate) fn color_space(&self) -> VelloColorSpace {
+        self.color_space
+    }
+
+    /// Total number of open layers, including offscreen layers
+    pub(crate) fn layer_depth(&self) -> usize {
+        self.depth
+            + self
+                .offscreen
+                .iter()
+                .map(|layer| layer.depth + 1)
+                .sum::<usize>()
+    }
+
+    /// Redirect drawing into a transparent offscreen context until the matching pop
+    pub(crate) fn push_offscreen_layer(&mut self, composite: OffscreenComposite) {
+        let parent = self.deref();
+        let mut ctx = self.new_scene(0);
+        copy_state(parent, &mut ctx);
+        ctx.set_aliasing_threshold(self.aliasing_threshold);
+        ctx.set_dash_tolerance(self.dash_tolerance);
+        self.hit_test.push_layer(None, Affine::IDENTITY);
+        self.offscreen.push(OffscreenLayer {
+            ctx,
+            depth: 0,
+            composite,
+        });
+    }
+
+    fn layer_pushed(&mut self, clip_path: Option<&BezPath>) {
+        let transform = *self.transform();
+        self.hit_test.push_layer(clip_path, transform);
+        match self.offscreen.last_mut() {
+            Some(layer) => layer.depth += 1,
+            None => self.depth += 1,
+        }
+    }
+
+    pub(crate) fn push_layer(
+        &mut self,
+        clip_path: Option<&BezPath>,
+        blend_mode: Option<BlendMode>,
+        opacity: Option<f32>,
+        mask: Option<Mask>,
+    ) {
+        self.deref_mut().push_layer(clip_path, blend_mode, opacity, mask);
+        self.layer_pushed(clip_path);
+    }
+
+    /// Push a layer with a bounds hint; see `Scene::push_layer_bounded`
+    pub(crate) fn push_layer_bounded(
+        &mut self,
+        clip_path: Option<&BezPath>,
+        blend_mode: Option<BlendMode>,
+        opacity: Option<f32>,
+        mask: Option<Mask>,
+        bounds: Option<Rect>,
+    ) {
+        match bounds {
+            Some(bounds) => {
+                let ctx = self.deref_mut();
+                ctx.push_layer_bounded(clip_path, blend_mode, opacity, mask, bounds);
+            }
+            None => self.deref_mut().push_layer(clip_path, blend_mode, opacity, mask),
+        }
+        self.layer_pushed(clip_path);
+    }
+
+    pub(crate) fn push_clip_layer(&mut self, path: &BezPath) {
+        self.deref_mut().push_clip_layer(path);
+        self.layer_pushed(Some(path));
+    }
+
+    pub(crate) fn push_blend_layer(&mut self, blend_mode: BlendMode) {
+        self.deref_mut().push_blend_layer(blend_mode);
+        self.layer_pushed(None);
+    }
+
+    pub(crate) fn push_opacity_layer(&mut self, opacity: f32) {
+        self.deref_mut().push_opacity_layer(opacity);
+        self.layer_pushed(None);
+    }
+
+    pub(crate) fn push_mask_layer(&mut self, mask: Mask) {
+        self.deref_mut().push_mask_layer(mask);
+        self.layer_pushed(None);
+    }
+
+    /// Pop the innermost layer; returns false if no layer is open
+    pub(crate) fn pop_layer(&mut self) -> bool {
+        match self.offscreen.last_mut() {
+            Some(layer) if layer.depth > 0 => {
+                layer.depth -= 1;
+                layer.ctx.pop_layer();
+            }
+            Some(_) => {
+                let mut layer = self.offscreen.pop().unwrap();
+                layer.ctx.flush();
+                let mut contents = Pixmap::new(layer.ctx.width(), layer.ctx.height());
+                layer.ctx.render_pixmap(&mut contents, self.thread_pool.as_deref());
+
+                // State changes inside a layer are not scoped to it
+                let parent = self.deref_mut();
+                copy_state(&layer.ctx, parent);
+                (layer.composite)(parent, contents);
+            }
+            None if self.depth > 0 => {
+                self.depth -= 1;
+                self.base.pop_layer();
+            }
+            None => return false,
+        }
+        self.hit_test.pop_layer();
+        true
+    }
+
+    /// Fill a path, recording it for hit testing
+    pub(crate) fn fill_path(&mut self, path: &BezPath) {
+        self.record_fill(path, VelloAaMode::Inherit);
+        self.deref_mut().fill_path(path);
+    }
+
+    /// Fill a rectangle, snapped to pixels if enabled
+    pub(crate) fn fill_rect(&mut self, rect: &Rect) {
+        let rect = self.snap_fill_rect(rect);
+        self.record_fill(&hit_test::rect_path(&rect), VelloAaMode::Inherit);
+        self.deref_mut().fill_rect(&rect);
+    }
+
+    /// Stroke a rectangle, snapped to pixels if enabled
+    pub(crate) fn stroke_rect(&mut self, rect: &Rect) {
+        let rect = self.snap_stroke_rect(rect);
+        self.record_stroke(&hit_test::rect_path(&rect), VelloAaMode::Inherit);
+        self.deref_mut().stroke_rect(&rect);
+    }
+
+    /// Stroke a path, snapping hairlines to pixels if enabled
+    pub(crate) fn stroke_path(&mut self, path: &BezPath) {
+        let snapped = self.snap_hairline(path);
+        let path = snapped.as_ref().unwrap_or(path);
+        self.record_stroke(path, VelloAaMode::Inherit);
+        self.deref_mut().stroke_path(path);
+    }
+
+    /// Record a fill of `path` with the current state if a draw ID is set
+    pub(crate) fn record_fill(&mut self, path: &BezPath, aa_mode: VelloAaMode) {
+        if self.hit_test.recording() {
+            let geometry = HitGeometry::Fill(path.clone(), *self.fill_rule());
+            self.record_hit(geometry, aa_mode);
+        }
+    }
+
+    /// Record a stroke of `path` with the current state if a draw ID is set
+    pub(crate) fn record_stroke(&mut self, path: &BezPath, aa_mode: VelloAaMode) {
+        if self.hit_test.recording() {
+            let geometry = HitGeometry::Stroke(path.clone(), self.stroke().clone());
+            self.record_hit(geometry, aa_mode);
+        }
+    }
+
+    fn record_hit(&mut self, geometry: HitGeometry, aa_mode: VelloAaMode) {
+        let threshold = match aa_mode {
+            VelloAaMode::Inherit => self.aliasing_threshold,
+            VelloAaMode::Analytic => None,
+            VelloAaMode::Aliased => Some(ALIASED_THRESHOLD),
+        };
+        let transform = *self.transform();
+        self.hit_test.record(geometry, transform, threshold);
+    }
+
+    pub(crate) fn snap_fill_rect(&self, rect: &Rect) -> Rect {
+        if !self.pixel_snapping {
+            return *rect;
+        }
+        snap::fill_rect(self.transform(), rect)
+    }
+
+    pub(crate) fn snap_stroke_rect(&self, rect: &Rect) -> Rect {
+        if !self.pixel_snapping {
+            return *rect;
+        }
+        snap::stroke_rect(self.transform(), self.stroke(), rect)
+    }
+
+    pub(crate) fn snap_hairline(&self, path: &BezPath) -> Option<BezPath> {
+        if !self.pixel_snapping {
+            return None;
+        }
+        snap::hairline(self.transform(), self.stroke(), path)
+    }
+
+    /// Width of the render target
+    pub(crate) fn width(&self) -> u16 {
+        self.base.width()
+    }
+
+    /// Height of the render target
+    pub(crate) fn height(&self) -> u16 {
+        self.base.height()
+    }
+
+    /// Settings of the root context; offscreen layers always render on the
+    /// calling thread
+    pub(crate) fn render_settings(&self) -> &vello_cpu::RenderSettings {
+        self.base.render_settings()
+    }
+
+    /// Flush the root context and any open offscreen layers
+    pub(crate) fn flush(&mut self) {
+        self.base.flush();
+        for layer in &mut self.offscreen {
+            layer.ctx.flush();
+        }
+    }
+
+    /// Render into premultiplied RGBA8 bytes, over the base color if one is set
+    ///
+    /// Runs on the adopted thread pool if there is one. Drawing inside open
+    /// offscreen layers is not part of the root context until the layers are
+    /// popped.
+    pub(crate) fn render_to_buffer(
+        &self,
+        buffer: &mut [u8],
+        width: u16,
+        height: u16,
+        render_mode: vello_cpu::RenderMode,
+    ) {
+        let pool = self.thread_pool.as_deref();
+        self.base.render(buffer, width, height, render_mode, pool);
+        if let Some(base) = self.base_color {
+            crate::pixel::fill_under(buffer, base);
+        }
+    }
+
+    /// Render into a pixmap, over the base color if one is set
+    pub(crate) fn render_to_pixmap(&self, pixmap: &mut Pixmap) {
+        self.base.render_pixmap(pixmap, self.thread_pool.as_deref());
+        if let Some(base) = self.base_color {
+            crate::pixel::fill_under(pixmap.data_as_u8_slice_mut(), base);
+        }
+    }
+
+    /// Render the `width` x `height` region at (x, y), over the base color if
+    /// one is set
+    pub(crate) fn render_region(&self, x: u16, y: u16, width: u16, height: u16) -> Pixmap {
+        let pool = self.thread_pool.as_deref();
+        let mut region = self.base.render_region((x, y), width, height, pool);
+        if let Some(base) = self.base_color {
+            crate::pixel::fill_under(region.data_as_u8_slice_mut(), base);
+        }
+        region
+    }
+
+    /// Set the aliasing threshold of the base context and any open offscreen layers
+    pub(crate) fn set_aliasing_threshold(&mut self, threshold: Option<u8>) {
+        self.aliasing_threshold = threshold;
+        self.base.set_aliasing_threshold(threshold);
+        for layer in &mut self.offscreen {
+            layer.ctx.set_aliasing_threshold(threshold);
+        }
+    }
+
+    /// Dash strokes in the FFI layer, flattening curves to `tolerance` in
+    /// user space, or leave dashing to the renderer with None
+    pub(crate) fn set_dash_tolerance(&mut self, tolerance: Option<f64>) {
+        self.dash_tolerance = tolerance;
+        self.base.set_dash_tolerance(tolerance);
+        for layer in &mut self.offscreen {
+            layer.ctx.set_dash_tolerance(tolerance);
+        }
+    }
+
+    /// Run a draw call with anti-aliasing overridden by `mode`
+    pub(crate) fn with_aa_mode<R>(
+        &mut self,
+        mode: VelloAaMode,
+        draw: impl FnOnce(&mut Scene) -> R,
+    ) -> R {
+        let threshold = match mode {
+            VelloAaMode::Inherit => return draw(self.deref_mut()),
+            VelloAaMode::Analytic => None,
+            VelloAaMode::Aliased => Some(ALIASED_THRESHOLD),
+        };
+        let restore = self.aliasing_threshold;
+        let ctx = self.deref_mut();
+        ctx.set_aliasing_threshold(threshold);
+        let result = draw(ctx);
+        ctx.set_aliasing_threshold(restore);
+        result
+    }
+
+    /// Reset the context, discarding any open offscreen layers
+    pub(crate) fn reset(&mut self) {
+        self.offscreen.clear();
+        self.depth = 0;
+        self.hit_test.clear();
+        self.base.reset();
+    }
+
+    /// Adopt `pool` for rendering, dropping the context's own workers
+    ///
+    /// With a pool the scene is only logged while drawing and rendered in bands
+    /// on the pool's workers; without one it draws on the calling thread.
+    pub(crate) fn set_thread_pool(&mut self, pool: Option<Arc<ThreadPool>>) {
+        self.thread_pool = pool;
+        self.rebuild(0);
+        for layer in &mut self.offscreen {
+            let mut ctx = Self::scene_for(&self.base, self.thread_pool.is_some(), 0);
+            layer.ctx.replay(&mut ctx, Affine::IDENTITY);
+            layer.ctx = ctx;
+        }
+    }
+
+    /// Pool adopted with `render_context_set_thread_pool`
+    pub(crate) fn thread_pool(&self) -> Option<&ThreadPool> {
+        self.thread_pool.as_deref()
+    }
+
+    /// Rebuild the base context with `num_threads` workers, keeping the queued
+    /// drawing, open layers, drawing state and everything cached on the handle
+    pub(crate) fn set_num_threads(&mut self, num_threads: u16) {
+        if self.thread_pool.is_none() && self.base.render_settings().num_threads != num_threads {
+            self.rebuild(num_threads);
+        }
+    }
+
+    /// Release scratch buffers, cached outlines and the base context's strip
+    /// storage
+    ///
+    /// The base context is rebuilt from the scene, so queued drawing only
+    /// occupies the storage it needs afterwards.
+    pub(crate) fn trim(&mut self) {
+        self.scratch = None;
+        self.strip_output = StripOutput::default();
+        self.glyph_cache.trim();
+        self.rebuild(self.base.render_settings().num_threads);
+    }
+
+    /// Independent context with the same drawing, settings and drawing state,
+    /// built by replaying the scene; returns None while layers are open
+    pub(crate) fn snapshot(&self) -> Option<Self> {
+        if self.layer_depth() > 0 {
+            return None;
+        }
+        let mut base = self.new_scene(self.base.render_settings().num_threads);
+        self.base.replay(&mut base, Affine::IDENTITY);
+
+        let mut copy = Self::with_base(base);
+        copy.color_space = self.color_space;
+        copy.glyph_hinting = self.glyph_hinting;
+        copy.aliasing_threshold = self.aliasing_threshold;
+        copy.pixel_snapping = self.pixel_snapping;
+        copy.base_color = self.base_color;
+        copy.gradient_ramp_size = self.gradient_ramp_size;
+        copy.set_dash_tolerance(self.dash_tolerance);
+        copy.glyph_cache.set_limit(self.glyph_cache.stats().limit);
+        copy.sync = self.sync.as_ref().map(|_| HandleLock::default());
+        copy.thread_pool = self.thread_pool.clone();
+        Some(copy)
+    }
+
+    /// Memory held by the handle outside of vello_cpu
+    pub(crate) fn memory_usage(&self) -> VelloMemoryUsage {
+        let scratch_bytes = self
+            .scratch
+            .as_ref()
+            .map_or(0, |p| p.width() as usize * p.height() as usize * 4);
+        let glyph_cache_bytes = self.glyph_cache.stats().bytes;
+        let scenes = || std::iter::once(&self.base).chain(self.offscreen.iter().map(|l| &l.ctx));
+        let scene_bytes = scenes().map(Scene::log_bytes).sum();
+        let strip_bytes = scenes().map(Scene::strip_bytes).sum();
+        VelloMemoryUsage {
+            scratch_bytes,
+            glyph_cache_bytes,
+            offscreen_layers: self.offscreen.len(),
+            scene_bytes,
+            strip_bytes,
+            total_bytes: scratch_bytes + glyph_cache_bytes + scene_bytes + strip_bytes,
+        }
+    }
+
+    /// Empty scene with the base context's size and settings but `num_threads`
+    /// workers, deferred to the thread pool if one is adopted
+    fn new_scene(&self, num_threads: u16) -> Scene {
+        Self::scene_for(&self.base, self.thread_pool.is_some(), num_threads)
+    }
+
+    fn scene_for(base: &RenderContext, deferred: bool, num_threads: u16) -> Scene {
+        let settings = base.render_settings();
+        let ctx = RenderContext::new_with(
+            base.width(),
+            base.height(),
+            vello_cpu::RenderSettings {
+                level: settings.level,
+                // The pool's workers render deferred scenes
+                num_threads: if deferred { 0 } else { num_threads },
+                render_mode: settings.render_mode,
+            },
+        );
+        if deferred {
+            Scene::new_deferred(ctx)
+        } else {
+            Scene::new(ctx)
+        }
+    }
+
+    /// Replace the base context with a fresh one, replaying the scene into it
+    fn rebuild(&mut self, num_threads: u16) {
+        let mut base = self.new_scene(num_threads);
+        self.base.replay(&mut base, Affine::IDENTITY);
+        base.set_dash_tolerance(self.dash_tolerance);
+        self.base = base;
+    }
+}
+
+impl TypedHandle for RenderContextHandle {
+    const TAG: u32 = handle::tag(b"VCTX");
+    const NAME: &'static str = "render context";
+
+    unsafe fn lock<'a>(ptr: *const Self) -> Option<&'a HandleLock> {
+        unsafe { (*std::ptr::addr_of!((*ptr).sync)).as_ref() }
+    }
+}
+
+impl Deref for RenderContextHandle {
+    type Target = Scene;
+
+    fn deref(&self) -> &Scene {
+        match self.offscreen.last() {
+            Some(layer) => &layer.ctx,
+            None => &self.base,
+        }
+    }
+}
+
+impl DerefMut for RenderContextHandle {
+    fn deref_mut(&mut self) -> &mut Scene {
+        match self.offscreen.last_mut() {
+            Some(layer) => &mut layer.ctx,
+            None => &mut self.base,
+        }
+    }
+}
+
+/// Copy paint, transform, stroke and fill state between contexts
+pub(crate) fn copy_state(from: &RenderContext, to: &mut impl DrawTarget) {
+    to.set_paint(from.paint().clone());
+    to.set_paint_transform(*from.paint_transform());
+    to.set_transform(*from.transform());
+    to.set_stroke(from.stroke().clone());
+    to.set_fill_rule(*from.fill_rule());
+}
+
+/// Draw a device-space pixmap onto a context, optionally clipped, keeping its state intact
+pub(crate) fn composite_pixmap(
+    ctx: &mut Scene,
+    pixmap: Pixmap,
+    clip_path: Option<&BezPath>,
+) {
+    use std::sync::Arc;
+    use vello_common::paint::{Image, ImageSource};
+    use vello_cpu::peniko::{self, Extend, ImageQuality};
+
+    let canvas = Rect::new(0.0, 0.0, ctx.width() as f64, ctx.height() as f64);
+    let image = Image {
+        image: ImageSource::Pixmap(Arc::new(pixmap)),
+        sampler: peniko::ImageSampler {
+            x_extend: Extend::Pad,
+            y_extend: Extend::Pad,
+            quality: ImageQuality::Low,
+            alpha: 1.0,
+        },
+    };
+
+    let saved_paint = ctx.paint().clone();
+    let saved_paint_transform = *ctx.paint_transform();
+    let saved_transform = *ctx.transform();
+
+    if let Some(clip) = clip_path {
+        ctx.push_clip_layer(clip);
+    }
+    ctx.set_transform(Affine::IDENTITY);
+    ctx.set_paint(image);
+    ctx.reset_paint_transform();
+    ctx.fill_rect(&canvas);
+    if clip_path.is_some() {
+        ctx.pop_layer();
+    }
+
+    ctx.set_transform(saved_transform);
+    ctx.set_paint(saved_paint);
+    ctx.set_paint_transform(saved_paint_transform);
+}
+
 /// Create new render context with default settings
 #[no_mangle]
 pub extern "C" fn vello_render_context_new(width: u16, height: u16) -> *mut VelloRenderContext {
     ffi_catch_ptr!({
         let ctx = RenderContext::new(width, height);
-        Box::into_raw(Box::new(ctx)) as *mut VelloRenderContext
+        handle::into_raw(RenderContextHandle::new(ctx)) as *mut VelloRenderContext
+    })
+}
+
+/// Create new render context that may be used from any thread
+///
+/// Every call taking the context holds its lock for the duration of the call,
+/// so calls from different threads are serialized instead of racing. A glyph
+/// run or recorder obtained from the context is only covered while the call
+/// using it runs. Freeing the context must still not overlap other calls.
+#[no_mangle]
+pub extern "C" fn vello_render_context_new_synchronized(
+    width: u16,
+    height: u16,
+) -> *mut VelloRenderContext {
+    ffi_catch_ptr!({
+        let mut handle = RenderContextHandle::new(RenderContext::new(width, height));
+        handle.sync = Some(HandleLock::default());
+        handle::into_raw(handle) as *mut VelloRenderContext
+    })
+}
+
+/// Create new render context, failing with `VELLO_ERROR_OUT_OF_MEMORY` when
+/// the frame-sized staging pixmap cannot be allocated
+///
+/// The staging pixmap used by format-converting readbacks is allocated up
+/// front, so a context that was created can also be read back. Only that
+/// allocation is fallible: vello_cpu allocates the context itself, and its
+/// per-tile storage, infallibly, so running out of memory there still aborts.
+/// That storage is a small fraction of a frame, which makes this call fail
+/// before it in practice, but it is not a guarantee.
+#[no_mangle]
+pub extern "C" fn vello_render_context_try_new(
+    width: u16,
+    height: u16,
+    out_ctx: *mut *mut VelloRenderContext,
+) -> c_int {
+    if out_ctx.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let Some(scratch) = try_new_pixmap(width, height) else {
+            set_last_error(format!("Failed to allocate {width}x{height} render context"));
+            return VELLO_ERROR_OUT_OF_MEMORY;
+        };
+        let mut handle = RenderContextHandle::new(RenderContext::new(width, height));
+        handle.scratch = Some(scratch);
+        unsafe { *out_ctx = handle::into_raw(handle) as *mut VelloRenderContext };
+        VELLO_OK
     })
 }
 
 /// Create new render context with custom settings
+///
+/// The requested SIMD level is used exactly; returns null, with the reason as
+/// the last error, if vello_common has no code path for it or the CPU lacks it.
 #[no_mangle]
 pub extern "C" fn vello_render_context_new_with(
     width: u16,
@@ -34,48 +691,257 @@ pub extern "C" fn vello_render_context_new_with(
 
     ffi_catch_ptr!({
         let settings = unsafe { &*settings };
+        let level = match settings.level.to_vello_level() {
+            Ok(level) => level,
+            Err(reason) => {
+                set_last_error(format!("SIMD level {:?} {reason}", settings.level));
+                return std::ptr::null_mut();
+            }
+        };
         let render_settings = vello_cpu::RenderSettings {
-            level: settings.level.to_vello_level(),
+            level,
             num_threads: settings.num_threads,
             render_mode: settings.render_mode.into(),
         };
         let ctx = RenderContext::new_with(width, height, render_settings);
-        Box::into_raw(Box::new(ctx)) as *mut VelloRenderContext
+        handle::into_raw(RenderContextHandle::new(ctx)) as *mut VelloRenderContext
     })
 }
 
 /// Free render context
 #[no_mangle]
 pub extern "C" fn vello_render_context_free(ctx: *mut VelloRenderContext) {
-    if !ctx.is_null() {
-        unsafe {
-            drop(Box::from_raw(ctx as *mut RenderContext));
-        }
+    if !ctx.is_null() && RenderContextHandle::check(ctx) {
+        handle::free::<RenderContextHandle, _>(ctx);
     }
 }
 
+/// Create an independent copy of a context
+///
+/// The copy starts with everything drawn since the last reset, replayed from
+/// the context's scene, and the same settings, paint, transform, stroke and
+/// fill rule. Nothing is rasterized until either context renders. Both
+/// contexts can then be drawn to and rendered separately, e.g. on different
+/// threads. Returns null while layers are open.
+#[no_mangle]
+pub extern "C" fn vello_render_context_clone(
+    ctx: *mut VelloRenderContext,
+) -> *mut VelloRenderContext {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        let Some(copy) = ctx.snapshot() else {
+            set_last_error("Cannot clone a render context while layers are open");
+            return std::ptr::null_mut();
+        };
+        handle::into_raw(copy) as *mut VelloRenderContext
+    })
+}
+
 /// Get width
 #[no_mangle]
 pub extern "C" fn vello_render_context_width(ctx: *const VelloRenderContext) -> u16 {
-    if ctx.is_null() {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
         return 0;
     }
-    unsafe {
-        let ctx = &*(ctx as *const RenderContext);
-        ctx.width()
-    }
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    ctx.width()
 }
 
 /// Get height
 #[no_mangle]
 pub extern "C" fn vello_render_context_height(ctx: *const VelloRenderContext) -> u16 {
-    if ctx.is_null() {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
         return 0;
     }
-    unsafe {
-        let ctx = &*(ctx as *const RenderContext);
-        ctx.height()
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    ctx.height()
+}
+
+/// Get the color space of the render target
+#[no_mangle]
+pub extern "C" fn vello_render_context_color_space(ctx: *const VelloRenderContext) -> VelloColorSpace {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
+        return VelloColorSpace::Srgb;
+    }
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    ctx.color_space()
+}
+
+/// Set the color space of the render target
+///
+/// Colors passed to the context are interpreted in this space, image paints
+/// are converted into it and pixmaps rendered to are tagged with it. The tag
+/// is kept across `vello_render_context_reset`.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_color_space(
+    ctx: *mut VelloRenderContext,
+    color_space: VelloColorSpace,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.color_space = color_space;
+        VELLO_OK
+    })
+}
+
+/// Enable or disable hinting for glyph runs drawn with this context (default on)
+///
+/// Hinting snaps outlines to the pixel grid for sharper small text and is only
+/// applied when the transform is an axis-aligned scale and translation.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_glyph_hinting(
+    ctx: *mut VelloRenderContext,
+    hint: bool,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.glyph_hinting = hint;
+        VELLO_OK
+    })
+}
+
+/// Get whether glyph runs drawn with this context are hinted
+#[no_mangle]
+pub extern "C" fn vello_render_context_glyph_hinting(ctx: *const VelloRenderContext) -> bool {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
+        return false;
+    }
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    ctx.glyph_hinting()
+}
+
+/// Limit the memory used by the context's glyph outline cache, evicting the
+/// oldest outlines when over budget (0 disables caching)
+///
+/// The cache holds outlines placed by the FFI's path-based text functions;
+/// regular glyph runs are rasterized by vello_cpu without it.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_glyph_cache_limit(
+    ctx: *mut VelloRenderContext,
+    limit_bytes: usize,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.glyph_cache.set_limit(limit_bytes);
+        VELLO_OK
+    })
+}
+
+/// Get glyph outline cache statistics
+#[no_mangle]
+pub extern "C" fn vello_render_context_glyph_cache_stats(
+    ctx: *const VelloRenderContext,
+    out_stats: *mut VelloGlyphCacheStats,
+) -> c_int {
+    if ctx.is_null() || out_stats.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        unsafe { *out_stats = ctx.glyph_cache.stats() };
+        VELLO_OK
+    })
+}
+
+/// Drop all cached glyph outlines (statistics counters are kept)
+#[no_mangle]
+pub extern "C" fn vello_render_context_glyph_cache_clear(ctx: *mut VelloRenderContext) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.glyph_cache.clear();
+        VELLO_OK
+    })
+}
+
+/// Memory held by a render context
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VelloMemoryUsage {
+    /// Staging pixmap kept by format-converting readbacks
+    pub scratch_bytes: usize,
+    /// Approximate size of cached glyph outlines
+    pub glyph_cache_bytes: usize,
+    /// Number of open offscreen layers, each holding a full context
+    pub offscreen_layers: usize,
+    /// Approximate size of the logged scene kept for replay
+    pub scene_bytes: usize,
+    /// Strips and coverage the queued drawing rasterizes to
+    pub strip_bytes: usize,
+    /// Sum of the byte counts above
+    pub total_bytes: usize,
+}
+
+/// Get the memory held by a render context
+///
+/// vello_cpu does not expose its strip storage, so `strip_bytes` is measured by
+/// rasterizing the drawing queued since the last reset into strips again,
+/// which costs about as much as a flush. Per-tile command lists are not
+/// included. vello_cpu's buffers grow to the largest frame rendered and are
+/// released by `vello_render_context_trim`.
+#[no_mangle]
+pub extern "C" fn vello_render_context_memory_usage(
+    ctx: *const VelloRenderContext,
+    out_usage: *mut VelloMemoryUsage,
+) -> c_int {
+    if ctx.is_null() || out_usage.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        unsafe { *out_usage = ctx.memory_usage() };
+        VELLO_OK
+    })
+}
+
+/// Release scratch buffers, strip storage and caches back to the allocator
+///
+/// Queued drawing, open layers and drawing state are kept: the context is
+/// rebuilt and the scene drawn since the last reset is replayed into it.
+#[no_mangle]
+pub extern "C" fn vello_render_context_trim(ctx: *mut VelloRenderContext) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
     }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.trim();
+        VELLO_OK
+    })
 }
 
 /// Reset to initial state
@@ -87,7 +953,7 @@ pub extern "C" fn vello_render_context_reset(ctx: *mut VelloRenderContext) -> c_
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         ctx.reset();
         VELLO_OK
     })
@@ -108,7 +974,7 @@ pub extern "C" fn vello_render_context_set_paint_solid(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
 
         // Convert u8 RGBA values to AlphaColor<Srgb>
         use vello_cpu::peniko::color::{AlphaColor, Srgb};
@@ -121,6 +987,175 @@ pub extern "C" fn vello_render_context_set_paint_solid(
     })
 }
 
+/// Convert FFI stroke parameters, rejecting widths and miter limits that are
+/// negative or not finite
+pub fn to_stroke(s: &VelloStroke) -> Result<Stroke, &'static str> {
+    if !(s.width.is_finite() && s.width >= 0.0) {
+        return Err("Stroke width must be finite and non-negative");
+    }
+    if !(s.miter_limit.is_finite() && s.miter_limit >= 0.0) {
+        return Err("Miter limit must be finite and non-negative");
+    }
+    let cap = |cap| match cap {
+        VelloCap::Butt => Cap::Butt,
+        VelloCap::Square => Cap::Square,
+        VelloCap::Round => Cap::Round,
+    };
+    Ok(Stroke {
+        width: s.width as f64,
+        join: match s.join {
+            VelloJoin::Bevel => Join::Bevel,
+            VelloJoin::Miter => Join::Miter,
+            VelloJoin::Round => Join::Round,
+        },
+        start_cap: cap(s.start_cap),
+        end_cap: cap(s.end_cap),
+        miter_limit: s.miter_limit as f64,
+        ..Default::default()
+    })
+}
+
+/// Convert FFI stroke parameters and a dash pattern, validating both
+pub(crate) fn to_dashed_stroke(
+    s: &VelloStroke,
+    dashes: &[f64],
+    dash_offset: f64,
+) -> Result<Stroke, &'static str> {
+    let stroke = to_stroke(s)?;
+    if dashes.is_empty() {
+        return Ok(stroke);
+    }
+    if !dash_offset.is_finite() {
+        return Err("Dash offset must be finite");
+    }
+    if dashes.iter().any(|d| !(d.is_finite() && *d >= 0.0)) {
+        return Err("Dash lengths must be finite and non-negative");
+    }
+    if dashes.iter().sum::<f64>() <= 0.0 {
+        return Err("Dash pattern must have a positive length");
+    }
+    Ok(stroke.with_dashes(dash_offset, dashes.iter().copied()))
+}
+
+/// Convert a device-space dash tolerance and the expected user-to-device
+/// scale into a user-space tolerance; a tolerance of 0 gives None
+pub(crate) fn to_dash_tolerance(tolerance: f64, scale: f64) -> Result<Option<f64>, &'static str> {
+    if !(tolerance.is_finite() && tolerance >= 0.0) {
+        return Err("Dash tolerance must be finite and non-negative");
+    }
+    if tolerance == 0.0 {
+        return Ok(None);
+    }
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err("Dash scale must be finite and positive");
+    }
+    Ok(Some(tolerance / scale))
+}
+
+/// `stroke` without its dash pattern
+pub(crate) fn undashed(stroke: &Stroke) -> Stroke {
+    Stroke {
+        dash_pattern: Default::default(),
+        dash_offset: 0.0,
+        ..stroke.clone()
+    }
+}
+
+/// Dash `path` with the pattern of `stroke`, flattening curves to `tolerance`
+/// first so dash lengths are measured along the flattened outline
+pub(crate) fn dash_path(path: &BezPath, stroke: &Stroke, tolerance: f64) -> BezPath {
+    let mut lines = BezPath::new();
+    kurbo::flatten(path.iter(), tolerance, |el| lines.push(el));
+    kurbo::dash(lines.into_iter(), stroke.dash_offset, &stroke.dash_pattern).collect()
+}
+
+/// Check that gradient stop offsets are finite, within [0, 1] and sorted
+pub(crate) fn validate_stop_offsets(offsets: impl IntoIterator<Item = f32>) -> Result<(), String> {
+    let mut previous = 0.0;
+    for (i, offset) in offsets.into_iter().enumerate() {
+        if !(0.0..=1.0).contains(&offset) {
+            return Err(format!("Gradient stop {i} has offset {offset} outside of [0, 1]"));
+        }
+        if offset < previous {
+            return Err(format!(
+                "Gradient stop {i} has offset {offset} below the previous stop's {previous}"
+            ));
+        }
+        previous = offset;
+    }
+    Ok(())
+}
+
+/// Convert gradient color stops to peniko's, checking their offsets
+///
+/// There is no limit on the number of stops. With a `ramp_size` of 2 or more
+/// the gradient is resampled into a ramp of that many evenly spaced stops, so
+/// its fidelity and cost no longer depend on the stops passed; 0 keeps them.
+pub(crate) fn color_stops(
+    stops: &[VelloColorStop],
+    ramp_size: u16,
+) -> Result<Vec<vello_cpu::peniko::ColorStop>, String> {
+    use vello_cpu::peniko::color::{AlphaColor, Srgb};
+    use vello_cpu::peniko::ColorStop;
+
+    validate_stop_offsets(stops.iter().map(|s| s.offset))?;
+    let rgba = |s: &VelloColorStop| [s.r, s.g, s.b, s.a].map(|c| c as f32 / 255.0);
+    let stop = |offset, rgba| ColorStop {
+        offset,
+        color: AlphaColor::<Srgb>::new(rgba).into(),
+    };
+    if ramp_size < 2 {
+        return Ok(stops.iter().map(|s| stop(s.offset, rgba(s))).collect());
+    }
+
+    let last = (ramp_size - 1) as f32;
+    let ramp = (0..ramp_size).map(|i| {
+        let t = i as f32 / last;
+        // Stops around t; the ends are padded with the outermost colors
+        let next = stops.partition_point(|s| s.offset < t);
+        let color = match (next.checked_sub(1).map(|i| &stops[i]), stops.get(next)) {
+            (Some(a), Some(b)) => {
+                let f = (t - a.offset) / (b.offset - a.offset);
+                let (a, b) = (rgba(a), rgba(b));
+                std::array::from_fn(|c| a[c] + (b[c] - a[c]) * f)
+            }
+            (Some(s), None) | (None, Some(s)) => rgba(s),
+            (None, None) => [0.0; 4],
+        };
+        stop(t, color)
+    });
+    Ok(ramp.collect())
+}
+
+/// Set the resolution of gradient color ramps
+///
+/// Gradient paints set afterwards, on the context or through recorders opened
+/// on it, are resampled into `size` evenly spaced stops. Use it to bound the
+/// cost of gradients with many stops or to get the same ramp regardless of the
+/// stops passed. 0, the default, keeps the stops as given; 1 is rejected with
+/// `VELLO_ERROR_INVALID_PARAMETER`.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_gradient_ramp_size(
+    ctx: *mut VelloRenderContext,
+    size: u16,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    if size == 1 {
+        set_last_error("Gradient ramps need at least 2 stops");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.gradient_ramp_size = size;
+        VELLO_OK
+    })
+}
+
 /// Set paint to linear gradient
 #[no_mangle]
 pub extern "C" fn vello_render_context_set_paint_linear_gradient(
@@ -144,25 +1179,21 @@ pub extern "C" fn vello_render_context_set_paint_linear_gradient(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        let stops_slice = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let stops = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let stops = match color_stops(stops, ctx.gradient_ramp_size) {
+            Ok(stops) => stops,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
 
-        // Convert color stops to peniko format
-        use vello_cpu::peniko::{ColorStop, Extend, Gradient};
-        use vello_cpu::peniko::color::{AlphaColor, Srgb};
+        use vello_cpu::peniko::{Extend, Gradient};
         use vello_cpu::kurbo::Point;
 
-        let mut color_stops = Vec::with_capacity(stop_count);
-        for stop in stops_slice {
-            let color = AlphaColor::<Srgb>::from_rgba8(stop.r, stop.g, stop.b, stop.a);
-            color_stops.push(ColorStop {
-                offset: stop.offset,
-                color: color.into(),
-            });
-        }
-
         let gradient = Gradient::new_linear(Point::new(x0, y0), Point::new(x1, y1))
-            .with_stops(&color_stops[..])
+            .with_stops(&stops[..])
             .with_extend(match extend {
                 VelloExtend::Pad => Extend::Pad,
                 VelloExtend::Repeat => Extend::Repeat,
@@ -196,25 +1227,21 @@ pub extern "C" fn vello_render_context_set_paint_radial_gradient(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        let stops_slice = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let stops = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let stops = match color_stops(stops, ctx.gradient_ramp_size) {
+            Ok(stops) => stops,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
 
-        // Convert color stops to peniko format
-        use vello_cpu::peniko::{ColorStop, Extend, Gradient};
-        use vello_cpu::peniko::color::{AlphaColor, Srgb};
+        use vello_cpu::peniko::{Extend, Gradient};
         use vello_cpu::kurbo::Point;
 
-        let mut color_stops = Vec::with_capacity(stop_count);
-        for stop in stops_slice {
-            let color = AlphaColor::<Srgb>::from_rgba8(stop.r, stop.g, stop.b, stop.a);
-            color_stops.push(ColorStop {
-                offset: stop.offset,
-                color: color.into(),
-            });
-        }
-
         let gradient = Gradient::new_radial(Point::new(cx, cy), radius as f32)
-            .with_stops(&color_stops[..])
+            .with_stops(&stops[..])
             .with_extend(match extend {
                 VelloExtend::Pad => Extend::Pad,
                 VelloExtend::Repeat => Extend::Repeat,
@@ -249,25 +1276,21 @@ pub extern "C" fn vello_render_context_set_paint_sweep_gradient(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        let stops_slice = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let stops = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let stops = match color_stops(stops, ctx.gradient_ramp_size) {
+            Ok(stops) => stops,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
 
-        // Convert color stops to peniko format
-        use vello_cpu::peniko::{ColorStop, Extend, Gradient};
-        use vello_cpu::peniko::color::{AlphaColor, Srgb};
+        use vello_cpu::peniko::{Extend, Gradient};
         use vello_cpu::kurbo::Point;
 
-        let mut color_stops = Vec::with_capacity(stop_count);
-        for stop in stops_slice {
-            let color = AlphaColor::<Srgb>::from_rgba8(stop.r, stop.g, stop.b, stop.a);
-            color_stops.push(ColorStop {
-                offset: stop.offset,
-                color: color.into(),
-            });
-        }
-
         let gradient = Gradient::new_sweep(Point::new(cx, cy), start_angle, end_angle)
-            .with_stops(&color_stops[..])
+            .with_stops(&stops[..])
             .with_extend(match extend {
                 VelloExtend::Pad => Extend::Pad,
                 VelloExtend::Repeat => Extend::Repeat,
@@ -291,7 +1314,7 @@ pub extern "C" fn vello_render_context_set_transform(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let t = unsafe { &*transform };
         let affine = vello_cpu::kurbo::Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]);
         ctx.set_transform(affine);
@@ -308,7 +1331,7 @@ pub extern "C" fn vello_render_context_reset_transform(ctx: *mut VelloRenderCont
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         ctx.reset_transform();
         VELLO_OK
     })
@@ -326,7 +1349,7 @@ pub extern "C" fn vello_render_context_get_transform(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &*(ctx as *const RenderContext) };
+        let ctx = RenderContextHandle::from_ptr(ctx);
         let transform = ctx.transform();
         let coeffs = transform.as_coeffs();
         let out = unsafe { &mut *out_transform };
@@ -352,37 +1375,66 @@ pub extern "C" fn vello_render_context_set_stroke(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        let s = unsafe { &*stroke };
-
-        let join = match s.join {
-            VelloJoin::Bevel => vello_cpu::kurbo::Join::Bevel,
-            VelloJoin::Miter => vello_cpu::kurbo::Join::Miter,
-            VelloJoin::Round => vello_cpu::kurbo::Join::Round,
-        };
-
-        let start_cap = match s.start_cap {
-            VelloCap::Butt => vello_cpu::kurbo::Cap::Butt,
-            VelloCap::Square => vello_cpu::kurbo::Cap::Square,
-            VelloCap::Round => vello_cpu::kurbo::Cap::Round,
-        };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        match to_stroke(unsafe { &*stroke }) {
+            Ok(stroke) => {
+                ctx.set_stroke(stroke);
+                ctx.set_dash_tolerance(None);
+            }
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        }
+        VELLO_OK
+    })
+}
 
-        let end_cap = match s.end_cap {
-            VelloCap::Butt => vello_cpu::kurbo::Cap::Butt,
-            VelloCap::Square => vello_cpu::kurbo::Cap::Square,
-            VelloCap::Round => vello_cpu::kurbo::Cap::Round,
-        };
+/// Set stroke parameters with a dash pattern
+///
+/// `dashes` holds `dash_count` alternating dash and gap lengths in user
+/// space, starting `dash_offset` into the pattern; a count of 0 draws solid
+/// strokes. Lengths must be finite and non-negative with a positive sum.
+/// `vello_render_context_set_stroke` clears the pattern.
+///
+/// `tolerance` is the largest distance in device pixels allowed between a
+/// curve and the segments dashes are measured along, and `scale` the expected
+/// user-to-device scale of the transforms the stroke is drawn with. With a
+/// tolerance of 0 the renderer dashes at its fixed tolerance and `scale` is
+/// ignored.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_stroke_dashed(
+    ctx: *mut VelloRenderContext,
+    stroke: *const VelloStroke,
+    dashes: *const f64,
+    dash_count: usize,
+    dash_offset: f64,
+    tolerance: f64,
+    scale: f64,
+) -> c_int {
+    if ctx.is_null() || stroke.is_null() || (dashes.is_null() && dash_count > 0) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
 
-        let stroke = vello_cpu::kurbo::Stroke {
-            width: s.width as f64,
-            join,
-            start_cap,
-            end_cap,
-            miter_limit: s.miter_limit as f64,
-            ..Default::default()
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let dashes = if dash_count == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(dashes, dash_count) }
         };
-
-        ctx.set_stroke(stroke);
+        let stroke = to_dashed_stroke(unsafe { &*stroke }, dashes, dash_offset);
+        match stroke.and_then(|stroke| Ok((stroke, to_dash_tolerance(tolerance, scale)?))) {
+            Ok((stroke, tolerance)) => {
+                ctx.set_stroke(stroke);
+                ctx.set_dash_tolerance(tolerance);
+            }
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        }
         VELLO_OK
     })
 }
@@ -399,7 +1451,7 @@ pub extern "C" fn vello_render_context_set_fill_rule(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let rule = match fill_rule {
             VelloFillRule::NonZero => vello_cpu::peniko::Fill::NonZero,
             VelloFillRule::EvenOdd => vello_cpu::peniko::Fill::EvenOdd,
@@ -421,7 +1473,7 @@ pub extern "C" fn vello_render_context_fill_rect(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let r = unsafe { &*rect };
         let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
         ctx.fill_rect(&rect);
@@ -441,7 +1493,7 @@ pub extern "C" fn vello_render_context_stroke_rect(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let r = unsafe { &*rect };
         let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
         ctx.stroke_rect(&rect);
@@ -449,6 +1501,52 @@ pub extern "C" fn vello_render_context_stroke_rect(
     })
 }
 
+/// Fill rectangle with anti-aliasing overridden for this call only
+#[no_mangle]
+pub extern "C" fn vello_render_context_fill_rect_aa(
+    ctx: *mut VelloRenderContext,
+    rect: *const VelloRect,
+    aa_mode: VelloAaMode,
+) -> c_int {
+    if ctx.is_null() || rect.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let r = unsafe { &*rect };
+        let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
+        let rect = ctx.snap_fill_rect(&rect);
+        ctx.record_fill(&hit_test::rect_path(&rect), aa_mode);
+        ctx.with_aa_mode(aa_mode, |ctx| ctx.fill_rect(&rect));
+        VELLO_OK
+    })
+}
+
+/// Stroke rectangle with anti-aliasing overridden for this call only
+#[no_mangle]
+pub extern "C" fn vello_render_context_stroke_rect_aa(
+    ctx: *mut VelloRenderContext,
+    rect: *const VelloRect,
+    aa_mode: VelloAaMode,
+) -> c_int {
+    if ctx.is_null() || rect.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let r = unsafe { &*rect };
+        let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
+        let rect = ctx.snap_stroke_rect(&rect);
+        ctx.record_stroke(&hit_test::rect_path(&rect), aa_mode);
+        ctx.with_aa_mode(aa_mode, |ctx| ctx.stroke_rect(&rect));
+        VELLO_OK
+    })
+}
+
 /// Fill a blurred rounded rectangle
 #[no_mangle]
 pub extern "C" fn vello_render_context_fill_blurred_rounded_rect(
@@ -463,7 +1561,7 @@ pub extern "C" fn vello_render_context_fill_blurred_rounded_rect(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let r = unsafe { &*rect };
         let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
         ctx.fill_blurred_rounded_rect(&rect, radius, std_dev);
@@ -471,6 +1569,83 @@ pub extern "C" fn vello_render_context_fill_blurred_rounded_rect(
     })
 }
 
+/// Fill a blurred rounded rectangle with independent corner radii and
+/// horizontal and vertical standard deviations, using the current paint
+///
+/// Uniform radii with equal deviations take the same analytic path as
+/// `vello_render_context_fill_blurred_rounded_rect`. Other shapes are
+/// rasterized and blurred in device space, with the deviations scaled by the
+/// current transform.
+#[no_mangle]
+pub extern "C" fn vello_render_context_fill_blurred_rounded_rect4(
+    ctx: *mut VelloRenderContext,
+    rect: *const VelloRect,
+    radii: *const VelloCornerRadii,
+    std_dev_x: f32,
+    std_dev_y: f32,
+) -> c_int {
+    if ctx.is_null() || rect.is_null() || radii.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let valid = |std_dev: f32| std_dev.is_finite() && std_dev >= 0.0;
+    if !(valid(std_dev_x) && valid(std_dev_y)) {
+        set_last_error("Blur standard deviations must be finite and non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        use vello_cpu::kurbo::{RoundedRect, RoundedRectRadii, Shape};
+
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let r = unsafe { &*rect };
+        let radii = unsafe { *radii };
+        let rect = Rect::new(r.x0, r.y0, r.x1, r.y1);
+
+        let uniform = radii.top_left == radii.top_right
+            && radii.top_left == radii.bottom_right
+            && radii.top_left == radii.bottom_left;
+        if uniform && std_dev_x == std_dev_y {
+            ctx.fill_blurred_rounded_rect(&rect, radii.top_left, std_dev_x);
+            return VELLO_OK;
+        }
+
+        let shape = RoundedRect::from_rect(
+            rect,
+            RoundedRectRadii::new(
+                radii.top_left as f64,
+                radii.top_right as f64,
+                radii.bottom_right as f64,
+                radii.bottom_left as f64,
+            ),
+        )
+        .to_path(0.1);
+        let transform = *ctx.transform();
+        let [a, b, c, d, _, _] = transform.as_coeffs();
+        // Scaling can overflow f32; the blur clamps its kernel to the canvas
+        let device = |std_dev: f32, scale: f64| (std_dev as f64 * scale).min(f32::MAX as f64);
+        let device_std_dev_x = device(std_dev_x, a.hypot(b)) as f32;
+        let device_std_dev_y = device(std_dev_y, c.hypot(d)) as f32;
+        let device_bounds = transform.transform_rect_bbox(rect);
+        let paint = ctx.paint().clone();
+        let paint_transform = *ctx.paint_transform();
+        crate::blur::fill_blurred(
+            ctx,
+            device_bounds,
+            transform,
+            device_std_dev_x,
+            device_std_dev_y,
+            |blur| {
+                blur.set_paint(paint);
+                blur.set_paint_transform(paint_transform);
+                blur.fill_path(&shape);
+            },
+        );
+        VELLO_OK
+    })
+}
+
 /// Push a blend layer with specified blend mode
 #[no_mangle]
 pub extern "C" fn vello_render_context_push_blend_layer(
@@ -483,7 +1658,7 @@ pub extern "C" fn vello_render_context_push_blend_layer(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let bm = unsafe { &*blend_mode };
 
         use vello_cpu::peniko::{BlendMode, Compose, Mix};
@@ -542,8 +1717,8 @@ pub extern "C" fn vello_render_context_push_clip_layer(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        let path = unsafe { &*(path as *const vello_cpu::kurbo::BezPath) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let path = &PathHandle::from_ptr(path).path;
         ctx.push_clip_layer(path);
         VELLO_OK
     })
@@ -561,12 +1736,108 @@ pub extern "C" fn vello_render_context_push_opacity_layer(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         ctx.push_opacity_layer(opacity);
         VELLO_OK
     })
 }
 
+/// Push a backdrop blur layer (frosted glass effect)
+///
+/// Everything drawn so far is snapshotted, blurred and written back inside the
+/// clip path (or the whole canvas when null), then a clip layer is pushed for the
+/// layer contents. Pop it with `vello_render_context_pop_layer`. The snapshot is
+/// rendered from a replay of the scene, so the context itself is not flushed.
+/// Returns `VELLO_ERROR_INVALID_PARAMETER` while other layers are open, since
+/// their contents are not composited yet and cannot form a backdrop.
+#[no_mangle]
+pub extern "C" fn vello_render_context_push_backdrop_blur_layer(
+    ctx: *mut VelloRenderContext,
+    std_dev: f32,
+    clip_path: *const VelloBezPath,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    if !(std_dev.is_finite() && std_dev >= 0.0) {
+        set_last_error("Blur standard deviation must be finite and non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        if ctx.layer_depth() > 0 {
+            set_last_error("Backdrop blur layers cannot be pushed inside other layers");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        use std::sync::Arc;
+        use vello_common::paint::{Image, ImageSource};
+        use vello_cpu::kurbo::Shape;
+        use vello_cpu::peniko::{self, Compose, Extend, ImageQuality, Mix};
+
+        let (width, height) = (ctx.width(), ctx.height());
+        let canvas = Rect::new(0.0, 0.0, width as f64, height as f64);
+
+        // The clip path is interpreted in user space, like any other clip layer
+        let clip = if clip_path.is_null() {
+            ctx.transform().inverse() * canvas.to_path(0.1)
+        } else {
+            PathHandle::from_ptr(clip_path).path.clone()
+        };
+
+        // Render the backdrop from a replay so the frame keeps accumulating
+        let mut scene = ctx.new_scene(ctx.render_settings().num_threads);
+        ctx.base.replay(&mut scene, Affine::IDENTITY);
+        let mut backdrop = Pixmap::new(width, height);
+        scene.flush();
+        scene.render_pixmap(&mut backdrop, ctx.thread_pool.as_deref());
+        crate::blur::gaussian_blur(
+            backdrop.data_mut(),
+            width as usize,
+            height as usize,
+            std_dev,
+            std_dev,
+        );
+
+        let image = Image {
+            image: ImageSource::Pixmap(Arc::new(backdrop)),
+            sampler: peniko::ImageSampler {
+                x_extend: Extend::Pad,
+                y_extend: Extend::Pad,
+                quality: ImageQuality::Low,
+                alpha: 1.0,
+            },
+        };
+
+        let saved_paint = ctx.paint().clone();
+        let saved_paint_transform = *ctx.paint_transform();
+        let saved_transform = *ctx.transform();
+
+        // Replace the backdrop inside the clip with its blurred version
+        ctx.push_layer(
+            Some(&clip),
+            Some(BlendMode::new(Mix::Normal, Compose::Copy)),
+            None,
+            None,
+        );
+        ctx.set_transform(Affine::IDENTITY);
+        ctx.set_paint(image);
+        ctx.reset_paint_transform();
+        ctx.fill_rect(&canvas);
+        ctx.pop_layer();
+
+        ctx.set_transform(saved_transform);
+        ctx.set_paint(saved_paint);
+        ctx.set_paint_transform(saved_paint_transform);
+
+        ctx.push_clip_layer(&clip);
+        VELLO_OK
+    })
+}
+
 /// Pop current layer (blend/clip/mask)
 #[no_mangle]
 pub extern "C" fn vello_render_context_pop_layer(ctx: *mut VelloRenderContext) -> c_int {
@@ -576,12 +1847,26 @@ pub extern "C" fn vello_render_context_pop_layer(ctx: *mut VelloRenderContext) -
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        ctx.pop_layer();
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        if !ctx.pop_layer() {
+            set_last_error("No layer to pop");
+            return VELLO_ERROR_LAYER_STACK_EMPTY;
+        }
         VELLO_OK
     })
 }
 
+/// Get the number of currently open layers
+#[no_mangle]
+pub extern "C" fn vello_render_context_layer_depth(ctx: *const VelloRenderContext) -> usize {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
+        return 0;
+    }
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    ctx.layer_depth()
+}
+
 /// Flush rendering (required for multithreading)
 #[no_mangle]
 pub extern "C" fn vello_render_context_flush(ctx: *mut VelloRenderContext) -> c_int {
@@ -591,7 +1876,7 @@ pub extern "C" fn vello_render_context_flush(ctx: *mut VelloRenderContext) -> c_
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         ctx.flush();
         VELLO_OK
     })
@@ -609,7 +1894,7 @@ pub extern "C" fn vello_render_context_get_stroke(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &*(ctx as *const RenderContext) };
+        let ctx = RenderContextHandle::from_ptr(ctx);
         let stroke = ctx.stroke();
         let out = unsafe { &mut *out_stroke };
 
@@ -638,16 +1923,52 @@ pub extern "C" fn vello_render_context_get_stroke(
     })
 }
 
+/// Get the dash pattern of the current stroke
+///
+/// Writes up to `max_dashes` lengths to `out_dashes` and the pattern's full
+/// length to `out_count`; `out_offset` may be null. A count of 0 means solid
+/// strokes.
+#[no_mangle]
+pub extern "C" fn vello_render_context_get_stroke_dashes(
+    ctx: *const VelloRenderContext,
+    out_dashes: *mut f64,
+    max_dashes: usize,
+    out_count: *mut usize,
+    out_offset: *mut f64,
+) -> c_int {
+    if ctx.is_null() || out_count.is_null() || (out_dashes.is_null() && max_dashes > 0) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        let stroke = ctx.stroke();
+        let dashes = &stroke.dash_pattern;
+        for (i, dash) in dashes.iter().take(max_dashes).enumerate() {
+            unsafe { *out_dashes.add(i) = *dash };
+        }
+        unsafe {
+            *out_count = dashes.len();
+            if let Some(out_offset) = out_offset.as_mut() {
+                *out_offset = stroke.dash_offset;
+            }
+        }
+        VELLO_OK
+    })
+}
+
 /// Get current fill rule
 #[no_mangle]
 pub extern "C" fn vello_render_context_get_fill_rule(
     ctx: *const VelloRenderContext,
 ) -> VelloFillRule {
-    if ctx.is_null() {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
         return VelloFillRule::NonZero; // Default
     }
 
-    let ctx = unsafe { &*(ctx as *const RenderContext) };
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
     let fill_rule = ctx.fill_rule();
     match fill_rule {
         vello_cpu::peniko::Fill::NonZero => VelloFillRule::NonZero,
@@ -667,7 +1988,7 @@ pub extern "C" fn vello_render_context_set_paint_transform(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let t = unsafe { &*transform };
         let affine = vello_cpu::kurbo::Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]);
         ctx.set_paint_transform(affine);
@@ -687,7 +2008,7 @@ pub extern "C" fn vello_render_context_get_paint_transform(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &*(ctx as *const RenderContext) };
+        let ctx = RenderContextHandle::from_ptr(ctx);
         let transform = ctx.paint_transform();
         let coeffs = transform.as_coeffs();
         let out = unsafe { &mut *out_transform };
@@ -701,47 +2022,121 @@ pub extern "C" fn vello_render_context_get_paint_transform(
     })
 }
 
-/// Reset paint transform to identity
+/// Reset paint transform to identity
+#[no_mangle]
+pub extern "C" fn vello_render_context_reset_paint_transform(ctx: *mut VelloRenderContext) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.reset_paint_transform();
+        VELLO_OK
+    })
+}
+
+/// Get the current paint kind (for querying paint type)
+#[no_mangle]
+pub extern "C" fn vello_render_context_get_paint_kind(
+    ctx: *const VelloRenderContext,
+) -> VelloPaintKind {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
+        set_last_error("Null context pointer");
+        return VelloPaintKind::Solid; // Default fallback
+    }
+
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    let paint = ctx.paint();
+
+    use vello_cpu::peniko::Brush;
+    match paint {
+        Brush::Solid(_) => VelloPaintKind::Solid,
+        Brush::Gradient(grad) => {
+            use vello_cpu::peniko::GradientKind;
+            match grad.kind {
+                GradientKind::Linear { .. } => VelloPaintKind::LinearGradient,
+                GradientKind::Radial { .. } => VelloPaintKind::RadialGradient,
+                GradientKind::Sweep { .. } => VelloPaintKind::SweepGradient,
+            }
+        }
+        Brush::Image(_) => VelloPaintKind::Image,
+    }
+}
+
+/// Enable or disable pixel snapping of axis-aligned geometry
+///
+/// When enabled, rectangle edges are moved to pixel boundaries and hairline
+/// strokes made of straight segments onto pixel centres before rasterization,
+/// keeping 1px borders crisp at fractional scales. Only applies while the
+/// transform is a scale and translation. Disabled by default.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_pixel_snapping(
+    ctx: *mut VelloRenderContext,
+    enabled: bool,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.pixel_snapping = enabled;
+        VELLO_OK
+    })
+}
+
+/// Check whether pixel snapping is enabled
 #[no_mangle]
-pub extern "C" fn vello_render_context_reset_paint_transform(ctx: *mut VelloRenderContext) -> c_int {
+pub extern "C" fn vello_render_context_pixel_snapping(ctx: *const VelloRenderContext) -> bool {
+    if ctx.is_null() || !RenderContextHandle::check(ctx) {
+        return false;
+    }
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    ctx.pixel_snapping
+}
+
+/// Set an opaque color that rendered output is composited over
+///
+/// Pixels not fully covered by the scene come out blended with this color
+/// instead of transparent, so hosts get opaque output without filling the
+/// target first. Applies to every render and readback call on the context.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_base_color(
+    ctx: *mut VelloRenderContext,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> c_int {
     if ctx.is_null() {
         set_last_error("Null context pointer");
         return VELLO_ERROR_NULL_POINTER;
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        ctx.reset_paint_transform();
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.base_color = Some(PremulRgba8 { r, g, b, a: 255 });
         VELLO_OK
     })
 }
 
-/// Get the current paint kind (for querying paint type)
+/// Remove the base color, rendering uncovered pixels as transparent black again
 #[no_mangle]
-pub extern "C" fn vello_render_context_get_paint_kind(
-    ctx: *const VelloRenderContext,
-) -> VelloPaintKind {
+pub extern "C" fn vello_render_context_clear_base_color(ctx: *mut VelloRenderContext) -> c_int {
     if ctx.is_null() {
         set_last_error("Null context pointer");
-        return VelloPaintKind::Solid; // Default fallback
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let ctx = unsafe { &*(ctx as *const RenderContext) };
-    let paint = ctx.paint();
-
-    use vello_cpu::peniko::Brush;
-    match paint {
-        Brush::Solid(_) => VelloPaintKind::Solid,
-        Brush::Gradient(grad) => {
-            use vello_cpu::peniko::GradientKind;
-            match grad.kind {
-                GradientKind::Linear { .. } => VelloPaintKind::LinearGradient,
-                GradientKind::Radial { .. } => VelloPaintKind::RadialGradient,
-                GradientKind::Sweep { .. } => VelloPaintKind::SweepGradient,
-            }
-        }
-        Brush::Image(_) => VelloPaintKind::Image,
-    }
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.base_color = None;
+        VELLO_OK
+    })
 }
 
 /// Set anti-aliasing threshold (0-255, or negative to use default)
@@ -756,7 +2151,7 @@ pub extern "C" fn vello_render_context_set_aliasing_threshold(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
         let threshold_opt = if threshold < 0 {
             None
         } else {
@@ -783,12 +2178,12 @@ pub extern "C" fn vello_render_context_push_layer(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
 
         let clip_path_opt = if clip_path.is_null() {
             None
         } else {
-            let path = unsafe { &*(clip_path as *const vello_cpu::kurbo::BezPath) };
+            let path = &PathHandle::from_ptr(clip_path).path;
             Some(path)
         };
 
@@ -847,8 +2242,8 @@ pub extern "C" fn vello_render_context_push_layer(
         let mask_opt = if mask.is_null() {
             None
         } else {
-            let m = unsafe { &*(mask as *const vello_cpu::Mask) };
-            Some(m.clone())
+            let m = MaskHandle::from_ptr(mask);
+            Some(m.mask.clone())
         };
 
         ctx.push_layer(clip_path_opt, blend_mode_opt, opacity_opt, mask_opt);
@@ -856,6 +2251,128 @@ pub extern "C" fn vello_render_context_push_layer(
     })
 }
 
+/// Convert an optional user-space bounds hint, rejecting non-finite ones
+fn to_bounds(bounds: *const VelloRect) -> Result<Option<Rect>, &'static str> {
+    if bounds.is_null() {
+        return Ok(None);
+    }
+    let b = unsafe { &*bounds };
+    let bounds = Rect::new(b.x0, b.y0, b.x1, b.y1);
+    if !bounds.is_finite() {
+        return Err("Layer bounds must be finite");
+    }
+    Ok(Some(bounds.abs()))
+}
+
+/// Push a blend layer with a bounds hint (null bounds = whole canvas)
+///
+/// The bounds should contain everything drawn in the layer, so only that
+/// region needs an intermediate buffer. They do not clip: drawing outside them
+/// still shows, at the cost of the saving.
+#[no_mangle]
+pub extern "C" fn vello_render_context_push_blend_layer_bounded(
+    ctx: *mut VelloRenderContext,
+    blend_mode: *const VelloBlendMode,
+    bounds: *const VelloRect,
+) -> c_int {
+    if ctx.is_null() || blend_mode.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let blend_mode = unsafe { *blend_mode };
+        let bounds = match to_bounds(bounds) {
+            Ok(bounds) => bounds,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+        ctx.push_layer_bounded(None, Some(blend_mode.into()), None, None, bounds);
+        VELLO_OK
+    })
+}
+
+/// Push an opacity layer with a bounds hint (null bounds = whole canvas)
+///
+/// See `vello_render_context_push_blend_layer_bounded`.
+#[no_mangle]
+pub extern "C" fn vello_render_context_push_opacity_layer_bounded(
+    ctx: *mut VelloRenderContext,
+    opacity: f32,
+    bounds: *const VelloRect,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let bounds = match to_bounds(bounds) {
+            Ok(bounds) => bounds,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+        ctx.push_layer_bounded(None, None, Some(opacity), None, bounds);
+        VELLO_OK
+    })
+}
+
+/// Push a general layer with a bounds hint
+///
+/// Same as `vello_render_context_push_layer`, with the bounds intersected with
+/// the clip path to limit the layer's buffer. See
+/// `vello_render_context_push_blend_layer_bounded`.
+#[no_mangle]
+pub extern "C" fn vello_render_context_push_layer_bounded(
+    ctx: *mut VelloRenderContext,
+    clip_path: *const VelloBezPath,
+    blend_mode: *const VelloBlendMode,
+    opacity: f32,
+    mask: *const VelloMask,
+    bounds: *const VelloRect,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let bounds = match to_bounds(bounds) {
+            Ok(bounds) => bounds,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+        let clip_path = if clip_path.is_null() {
+            None
+        } else {
+            Some(&PathHandle::from_ptr(clip_path).path)
+        };
+        let blend_mode_opt = if blend_mode.is_null() {
+            None
+        } else {
+            Some(unsafe { *blend_mode }.into())
+        };
+        let opacity_opt = if opacity < 0.0 { None } else { Some(opacity) };
+        let mask_opt = if mask.is_null() {
+            None
+        } else {
+            Some(MaskHandle::from_ptr(mask).mask.clone())
+        };
+
+        ctx.push_layer_bounded(clip_path, blend_mode_opt, opacity_opt, mask_opt, bounds);
+        VELLO_OK
+    })
+}
+
 /// Get render settings
 #[no_mangle]
 pub extern "C" fn vello_render_context_get_render_settings(
@@ -868,7 +2385,7 @@ pub extern "C" fn vello_render_context_get_render_settings(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &*(ctx as *const RenderContext) };
+        let ctx = RenderContextHandle::from_ptr(ctx);
         let settings = ctx.render_settings();
         let out = unsafe { &mut *out_settings };
 
@@ -880,6 +2397,221 @@ pub extern "C" fn vello_render_context_get_render_settings(
     })
 }
 
+/// Change the number of worker threads (0 renders on the calling thread)
+///
+/// The context is rebuilt with the new workers and the scene drawn since the
+/// last reset is replayed into it, so queued drawing, open layers, the current
+/// paint, transform and stroke and all caches are kept. Has no effect while a
+/// thread pool is adopted, whose workers render instead.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_num_threads(
+    ctx: *mut VelloRenderContext,
+    num_threads: u16,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.set_num_threads(num_threads);
+        VELLO_OK
+    })
+}
+
+/// Render this context on an existing rayon thread pool
+///
+/// The context drops its own worker threads, so an application already using
+/// rayon keeps a single pool. Drawing is then only logged, and every render
+/// replays it in one band of rows per worker of the pool, so a frame uses the
+/// whole pool; contexts sharing the pool take turns. Scenes with mask layers
+/// render on one worker. Pass `None` to detach, after which the context renders
+/// on the calling thread.
+pub fn render_context_set_thread_pool(
+    ctx: *mut VelloRenderContext,
+    pool: Option<Arc<ThreadPool>>,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.set_thread_pool(pool);
+        VELLO_OK
+    })
+}
+
+/// Adopt a worker pool created with `vello_thread_pool_new`
+///
+/// The context keeps its own reference, so the pool may be freed while it is
+/// adopted. Null detaches the current pool. See
+/// `render_context_set_thread_pool` for the Rust equivalent, which accepts any
+/// rayon pool.
+#[no_mangle]
+pub extern "C" fn vello_render_context_adopt_thread_pool(
+    ctx: *mut VelloRenderContext,
+    pool: *const VelloThreadPool,
+) -> c_int {
+    if !pool.is_null() && !ThreadPoolHandle::check(pool) {
+        return VELLO_ERROR_INVALID_HANDLE;
+    }
+    let pool = if pool.is_null() {
+        None
+    } else {
+        let _scope = CallScope::enter();
+        Some(ThreadPoolHandle::from_ptr(pool).pool.clone())
+    };
+    render_context_set_thread_pool(ctx, pool)
+}
+
+/// Bytes spanned by `height` rows of `row_len` bytes placed `stride` bytes
+/// apart, or None if that does not fit in a usize
+pub(crate) fn strided_len(stride: usize, height: u16, row_len: usize) -> Option<usize> {
+    match height {
+        0 => Some(0),
+        height => stride.checked_mul(height as usize - 1)?.checked_add(row_len),
+    }
+}
+
+/// Render into a buffer whose rows are `stride` bytes apart
+///
+/// Tightly packed buffers are rendered into directly; padded rows go through
+/// `scratch` and are copied row by row.
+pub(crate) fn render_strided(
+    ctx: &RenderContextHandle,
+    buffer: &mut [u8],
+    width: u16,
+    height: u16,
+    stride: usize,
+    render_mode: vello_cpu::RenderMode,
+    scratch: &mut Vec<u8>,
+) {
+    let row_len = width as usize * 4;
+    if row_len == 0 || height == 0 {
+        return;
+    }
+
+    if stride == row_len {
+        ctx.render_to_buffer(&mut buffer[..row_len * height as usize], width, height, render_mode);
+    } else {
+        scratch.resize(row_len * height as usize, 0);
+        ctx.render_to_buffer(scratch, width, height, render_mode);
+        for (y, row) in scratch.chunks_exact(row_len).enumerate() {
+            buffer[y * stride..y * stride + row_len].copy_from_slice(row);
+        }
+    }
+}
+
+/// Render to raw RGBA buffer with a row stride (u8 bytes, premultiplied)
+/// `stride` is the distance between rows in bytes and must be at least width * 4
+#[no_mangle]
+pub extern "C" fn vello_render_context_render_to_buffer_strided(
+    ctx: *mut VelloRenderContext,
+    buffer: *mut u8,
+    buffer_len: usize,
+    width: u16,
+    height: u16,
+    stride: usize,
+    render_mode: VelloRenderMode,
+) -> c_int {
+    if ctx.is_null() || buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let row_len = width as usize * 4;
+    if stride < row_len {
+        set_last_error("Stride must be at least width * 4 bytes");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    let Some(required_len) = strided_len(stride, height, row_len) else {
+        set_last_error("Buffer size overflows");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    };
+    if buffer_len < required_len {
+        set_last_error("Buffer too small");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+
+        let buffer_slice = unsafe { std::slice::from_raw_parts_mut(buffer, buffer_len) };
+        let mode = render_mode.into();
+        render_strided(ctx, buffer_slice, width, height, stride, mode, &mut Vec::new());
+        VELLO_OK
+    })
+}
+
+/// Render to a caller buffer in the given pixel format
+/// `stride` is the distance between rows in bytes (0 = tightly packed)
+///
+/// Tightly packed RGBA8 is rendered in place. Other layouts are rendered into a
+/// staging pixmap kept with the context, whose rows are converted straight into
+/// the buffer.
+#[no_mangle]
+pub extern "C" fn vello_render_context_render_to_buffer_format(
+    ctx: *mut VelloRenderContext,
+    buffer: *mut u8,
+    buffer_len: usize,
+    width: u16,
+    height: u16,
+    stride: usize,
+    format: VelloPixelFormat,
+    render_mode: VelloRenderMode,
+) -> c_int {
+    if ctx.is_null() || buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let row_len = width as usize * format.bytes_per_pixel();
+    let stride = if stride == 0 { row_len } else { stride };
+    if stride < row_len {
+        set_last_error("Stride too small for the pixel format");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    let Some(required_len) = strided_len(stride, height, row_len) else {
+        set_last_error("Buffer size overflows");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    };
+    if buffer_len < required_len {
+        set_last_error("Buffer too small");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let buffer_slice = unsafe { std::slice::from_raw_parts_mut(buffer, buffer_len) };
+        if row_len == 0 || height == 0 {
+            return VELLO_OK;
+        }
+
+        if format == VelloPixelFormat::Rgba8 && stride == row_len {
+            let out = &mut buffer_slice[..row_len * height as usize];
+            ctx.render_to_buffer(out, width, height, render_mode.into());
+            return VELLO_OK;
+        }
+
+        let mut scratch = ctx.scratch.take().unwrap_or_else(|| Pixmap::new(width, height));
+        if scratch.width() != width || scratch.height() != height {
+            scratch.resize(width, height);
+        }
+        ctx.render_to_buffer(scratch.data_as_u8_slice_mut(), width, height, render_mode.into());
+        for (y, row) in scratch.data().chunks_exact(width as usize).enumerate() {
+            let out = &mut buffer_slice[y * stride..y * stride + row_len];
+            crate::pixel::convert_row(row, format, out);
+        }
+        ctx.scratch = Some(scratch);
+        VELLO_OK
+    })
+}
+
 /// Render to raw RGBA buffer (u8 bytes, premultiplied)
 /// Buffer must be at least width * height * 4 bytes
 #[no_mangle]
@@ -897,7 +2629,7 @@ pub extern "C" fn vello_render_context_render_to_buffer(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &*(ctx as *const RenderContext) };
+        let ctx = RenderContextHandle::from_ptr(ctx);
         let required_len = (width as usize) * (height as usize) * 4;
 
         if buffer_len < required_len {
@@ -913,3 +2645,433 @@ pub extern "C" fn vello_render_context_render_to_buffer(
         VELLO_OK
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{
+        vello_filter_free, vello_filter_new, vello_render_context_push_filter_layer,
+    };
+    use crate::layer_cache::{
+        vello_layer_cache_free, vello_layer_cache_is_valid, vello_layer_cache_new,
+    };
+    use crate::path::{vello_bezpath_free, vello_bezpath_new};
+    use crate::pixmap::vello_render_context_tile_count;
+    use crate::recording::{vello_recording_free, vello_recording_new, vello_render_context_record};
+    use crate::test_util::{fill, render, worker_settings};
+    use crate::thread_pool::{
+        vello_thread_pool_free, vello_thread_pool_new, vello_thread_pool_num_threads,
+    };
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    static RECORDING_STARTED: AtomicBool = AtomicBool::new(false);
+    static RECORDING_DONE: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn slow_record(_user_data: *mut c_void, _recorder: *mut c_void) {
+        RECORDING_STARTED.store(true, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        RECORDING_DONE.store(true, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn synchronized_getters_wait_for_the_context_lock() {
+        let ctx = vello_render_context_new_synchronized(16, 8);
+        let address = ctx as usize;
+        let recorder = std::thread::spawn(move || {
+            let recording = vello_recording_new();
+            let ctx = address as *mut c_void;
+            let result = vello_render_context_record(ctx, recording, slow_record, std::ptr::null_mut());
+            vello_recording_free(recording);
+            result
+        });
+
+        while !RECORDING_STARTED.load(Ordering::SeqCst) {
+            std::thread::yield_now();
+        }
+        // Each getter blocks until the call holding the lock has returned
+        assert_eq!(vello_render_context_width(ctx), 16);
+        assert!(RECORDING_DONE.load(Ordering::SeqCst));
+        assert_eq!(vello_render_context_height(ctx), 8);
+        assert_eq!(vello_render_context_color_space(ctx), VelloColorSpace::Srgb);
+        assert!(vello_render_context_glyph_hinting(ctx));
+        assert!(!vello_render_context_pixel_snapping(ctx));
+        assert_eq!(vello_render_context_tile_count(ctx, 4), 8);
+        assert_eq!(recorder.join().unwrap(), VELLO_OK);
+
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn clone_replays_the_scene() {
+        let ctx = vello_render_context_new(8, 8);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 4.0, 4.0);
+        let copy = vello_render_context_clone(ctx);
+        assert!(!copy.is_null());
+
+        // The copy keeps the paint and can be drawn to independently
+        assert_eq!(vello_render_context_get_paint_kind(copy), VelloPaintKind::Solid);
+        fill(copy, [0, 0, 255, 255], 4.0, 4.0, 8.0, 8.0);
+
+        let original = render(ctx, 8, 8);
+        let copied = render(copy, 8, 8);
+        assert_eq!(original[9], [255, 0, 0, 255]);
+        assert_eq!(original[6 * 8 + 6], [0, 0, 0, 0]);
+        assert_eq!(copied[9], [255, 0, 0, 255]);
+        assert_eq!(copied[6 * 8 + 6], [0, 0, 255, 255]);
+
+        vello_render_context_free(copy);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn set_num_threads_keeps_queued_drawing() {
+        let ctx = vello_render_context_new(8, 8);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 4.0, 4.0);
+        assert_eq!(vello_render_context_push_opacity_layer(ctx, 0.5), VELLO_OK);
+        assert_eq!(vello_render_context_set_num_threads(ctx, 2), VELLO_OK);
+        fill(ctx, [0, 0, 255, 255], 4.0, 4.0, 8.0, 8.0);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+
+        let pixels = render(ctx, 8, 8);
+        assert_eq!(pixels[9], [255, 0, 0, 255]);
+        assert!((126..=129).contains(&pixels[6 * 8 + 6][3]));
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn queries_use_the_root_context_inside_offscreen_layers() {
+        let ctx = vello_render_context_new(8, 8);
+        assert_eq!(vello_render_context_set_num_threads(ctx, 2), VELLO_OK);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 4.0, 4.0);
+
+        let filter = vello_filter_new();
+        let result = vello_render_context_push_filter_layer(ctx, filter, std::ptr::null());
+        assert_eq!(result, VELLO_OK);
+        fill(ctx, [0, 0, 255, 255], 4.0, 4.0, 8.0, 8.0);
+
+        let mut settings = VelloRenderSettings {
+            level: VelloSimdLevel::Fallback,
+            num_threads: 0,
+            render_mode: VelloRenderMode::OptimizeSpeed,
+            _padding: 0,
+        };
+        assert_eq!(vello_render_context_get_render_settings(ctx, &mut settings), VELLO_OK);
+        assert_eq!(settings.num_threads, 2);
+        assert_eq!(vello_render_context_width(ctx), 8);
+
+        // The root context is rendered; the layer's drawing lands on pop
+        let pixels = render(ctx, 8, 8);
+        assert_eq!(pixels[9], [255, 0, 0, 255]);
+        assert_eq!(pixels[6 * 8 + 6], [0, 0, 0, 0]);
+
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+        let pixels = render(ctx, 8, 8);
+        assert!(pixels[6 * 8 + 6][2] > 250 && pixels[6 * 8 + 6][3] > 250);
+
+        vello_filter_free(filter);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn backdrop_blur_layer_keeps_the_frame_and_rejects_nesting() {
+        let ctx = vello_render_context_new(16, 16);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 16.0, 16.0);
+        let result = vello_render_context_push_backdrop_blur_layer(ctx, 1.0, std::ptr::null());
+        assert_eq!(result, VELLO_OK);
+        fill(ctx, [0, 0, 255, 255], 12.0, 12.0, 16.0, 16.0);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+
+        // Away from the edges a uniform backdrop blurs to itself
+        let pixels = render(ctx, 16, 16);
+        let [r, g, _, a] = pixels[6 * 16 + 6];
+        assert!(r >= 250 && g == 0 && a >= 250);
+        assert_eq!(pixels[14 * 16 + 14], [0, 0, 255, 255]);
+
+        assert_eq!(vello_render_context_push_opacity_layer(ctx, 0.5), VELLO_OK);
+        let result = vello_render_context_push_backdrop_blur_layer(ctx, 2.0, std::ptr::null());
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_ERROR_LAYER_STACK_EMPTY);
+
+        let no_clip = std::ptr::null();
+        for std_dev in [f32::INFINITY, f32::NAN, -1.0] {
+            let result = vello_render_context_push_backdrop_blur_layer(ctx, std_dev, no_clip);
+            assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        }
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_ERROR_LAYER_STACK_EMPTY);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn blurred_rounded_rects_reject_non_finite_deviations() {
+        let ctx = vello_render_context_new(16, 16);
+        assert_eq!(vello_render_context_set_paint_solid(ctx, 255, 0, 0, 255), VELLO_OK);
+        let radii = VelloCornerRadii {
+            top_left: 2.0,
+            top_right: 0.0,
+            bottom_right: 2.0,
+            bottom_left: 0.0,
+        };
+        let blurred = |rect: VelloRect, x, y| {
+            vello_render_context_fill_blurred_rounded_rect4(ctx, &rect, &radii, x, y)
+        };
+        let rect = VelloRect { x0: 4.0, y0: 4.0, x1: 12.0, y1: 12.0 };
+        assert_eq!(blurred(rect, f32::INFINITY, 1.0), VELLO_ERROR_INVALID_PARAMETER);
+        assert_eq!(blurred(rect, 1.0, f32::NAN), VELLO_ERROR_INVALID_PARAMETER);
+
+        // The scale overflows the device-space deviation, which spreads the shape out
+        let scale = VelloAffine { m11: 1e20, m12: 0.0, m21: 0.0, m22: 1e20, m13: 0.0, m23: 0.0 };
+        assert_eq!(vello_render_context_set_transform(ctx, &scale), VELLO_OK);
+        let rect = VelloRect { x0: 0.0, y0: 0.0, x1: 1.2e-19, y1: 1.2e-19 };
+        assert_eq!(blurred(rect, 1e30, 1.0), VELLO_OK);
+        assert!(render(ctx, 16, 16).iter().all(|px| px[3] == 0));
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn adopted_pool_renders_the_same_pixels() {
+        let pool = vello_thread_pool_new(&worker_settings(3));
+        assert_eq!(vello_thread_pool_num_threads(pool), 3);
+
+        let draw = |ctx| {
+            fill(ctx, [255, 0, 0, 255], 1.5, 2.5, 30.0, 29.0);
+            assert_eq!(vello_render_context_push_opacity_layer(ctx, 0.5), VELLO_OK);
+            fill(ctx, [0, 0, 255, 255], 8.0, 6.25, 24.0, 27.75);
+            assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+        };
+        let single = vello_render_context_new(32, 30);
+        draw(single);
+        let pooled = vello_render_context_new(32, 30);
+        assert_eq!(vello_render_context_adopt_thread_pool(pooled, pool), VELLO_OK);
+        // The context keeps the pool alive
+        vello_thread_pool_free(pool);
+        draw(pooled);
+        assert_eq!(render(pooled, 32, 30), render(single, 32, 30));
+
+        let path = vello_bezpath_new();
+        let result = vello_render_context_adopt_thread_pool(pooled, path as *const _);
+        assert_eq!(result, VELLO_ERROR_INVALID_HANDLE);
+        assert_eq!(vello_render_context_adopt_thread_pool(pooled, std::ptr::null()), VELLO_OK);
+        assert_eq!(render(pooled, 32, 30), render(single, 32, 30));
+
+        vello_bezpath_free(path);
+        vello_render_context_free(pooled);
+        vello_render_context_free(single);
+    }
+
+    #[test]
+    fn render_to_buffer_format_converts_padded_rows() {
+        let ctx = vello_render_context_new(8, 4);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 4.0, 4.0);
+        fill(ctx, [0, 0, 128, 128], 4.0, 0.0, 8.0, 4.0);
+        let expected = render(ctx, 8, 4);
+
+        let mode = VelloRenderMode::OptimizeSpeed;
+        for format in [VelloPixelFormat::Rgba8, VelloPixelFormat::Bgra8] {
+            // Two bytes of padding per row are left untouched
+            let mut buffer = vec![7u8; 34 * 4];
+            let (data, len) = (buffer.as_mut_ptr(), buffer.len());
+            let result = vello_render_context_render_to_buffer_format(
+                ctx, data, len, 8, 4, 34, format, mode,
+            );
+            assert_eq!(result, VELLO_OK);
+            for (y, row) in buffer.chunks_exact(34).enumerate() {
+                assert_eq!(&row[32..], &[7, 7]);
+                for (x, px) in row[..32].chunks_exact(4).enumerate() {
+                    let [r, g, b, a] = expected[y * 8 + x];
+                    let rgba = match format {
+                        VelloPixelFormat::Bgra8 => [b, g, r, a],
+                        _ => [r, g, b, a],
+                    };
+                    assert_eq!(px, &rgba);
+                }
+            }
+        }
+
+        let mut buffer = [0u8; 4];
+        let (data, len) = (buffer.as_mut_ptr(), buffer.len());
+        let format = VelloPixelFormat::Rgba8;
+        let result = vello_render_context_render_to_buffer_format(
+            ctx,
+            data,
+            len,
+            8,
+            4,
+            usize::MAX / 2,
+            format,
+            mode,
+        );
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn strided_rendering_checks_the_buffer_size() {
+        let ctx = vello_render_context_new(8, 4);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 8.0, 4.0);
+        assert_eq!(vello_render_context_flush(ctx), VELLO_OK);
+        let mode = VelloRenderMode::OptimizeSpeed;
+
+        let mut buffer = vec![0u8; 40 * 3 + 32];
+        let draw = |buffer: &mut [u8], stride| {
+            let (data, len) = (buffer.as_mut_ptr(), buffer.len());
+            vello_render_context_render_to_buffer_strided(ctx, data, len, 8, 4, stride, mode)
+        };
+        assert_eq!(draw(&mut buffer, 40), VELLO_OK);
+        assert_eq!(&buffer[120..124], &[255, 0, 0, 255]);
+        assert_eq!(draw(&mut buffer[1..], 40), VELLO_ERROR_INVALID_PARAMETER);
+        // stride * (height - 1) does not fit in a usize
+        assert_eq!(draw(&mut buffer, usize::MAX / 2), VELLO_ERROR_INVALID_PARAMETER);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn gradient_ramps_resample_the_stops() {
+        let stop = |offset, r, b| VelloColorStop { offset, r, g: 0, b, a: 255 };
+        let stops = [stop(0.0, 255, 0), stop(0.25, 255, 0), stop(1.0, 0, 255)];
+        assert_eq!(color_stops(&stops, 0).unwrap().len(), 3);
+
+        let ramp = color_stops(&stops, 5).unwrap();
+        let offsets: Vec<f32> = ramp.iter().map(|s| s.offset).collect();
+        assert_eq!(offsets, [0.0, 0.25, 0.5, 0.75, 1.0]);
+        let red = |i: usize| ramp[i].color.components[0];
+        assert_eq!([red(0), red(1), red(4)], [1.0, 1.0, 0.0]);
+        assert!((red(2) - 2.0 / 3.0).abs() < 1e-6);
+
+        let unsorted = [stop(0.5, 255, 0), stop(0.25, 0, 255)];
+        assert!(color_stops(&unsorted, 5).is_err());
+
+        let ctx = vello_render_context_new(8, 8);
+        let result = vello_render_context_set_gradient_ramp_size(ctx, 1);
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        assert_eq!(vello_render_context_set_gradient_ramp_size(ctx, 256), VELLO_OK);
+        let (data, count) = (stops.as_ptr(), stops.len());
+        let result = vello_render_context_set_paint_linear_gradient(
+            ctx,
+            0.0,
+            0.0,
+            8.0,
+            0.0,
+            data,
+            count,
+            VelloExtend::Pad,
+        );
+        assert_eq!(result, VELLO_OK);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn dash_tolerance_flattens_dashes_in_the_ffi_layer() {
+        let stroke = VelloStroke {
+            width: 2.0,
+            miter_limit: 4.0,
+            join: VelloJoin::Miter,
+            start_cap: VelloCap::Butt,
+            end_cap: VelloCap::Butt,
+            _padding: [0; 3],
+        };
+        let dashes = [3.0, 2.0];
+        let rect = VelloRect { x0: 4.0, y0: 4.0, x1: 28.0, y1: 20.0 };
+        let draw = |tolerance: f64, scale: f64| {
+            let ctx = vello_render_context_new(32, 24);
+            assert_eq!(vello_render_context_set_paint_solid(ctx, 0, 0, 0, 255), VELLO_OK);
+            let result = vello_render_context_set_stroke_dashed(
+                ctx,
+                &stroke,
+                dashes.as_ptr(),
+                dashes.len(),
+                1.0,
+                tolerance,
+                scale,
+            );
+            assert_eq!(result, VELLO_OK);
+            assert_eq!(vello_render_context_stroke_rect(ctx, &rect), VELLO_OK);
+            let pixels = render(ctx, 32, 24);
+            vello_render_context_free(ctx);
+            pixels
+        };
+
+        // Straight edges flatten exactly, so both dashers agree
+        let renderer = draw(0.0, 0.0);
+        let ffi = draw(0.1, 2.0);
+        assert!(renderer.iter().any(|px| px[3] == 0) && renderer.iter().any(|px| px[3] > 0));
+        for (a, b) in renderer.iter().zip(&ffi) {
+            assert!(a.iter().zip(b).all(|(a, b)| a.abs_diff(*b) <= 1), "{a:?} != {b:?}");
+        }
+
+        let ctx = vello_render_context_new(8, 8);
+        let set = |tolerance, scale| {
+            vello_render_context_set_stroke_dashed(
+                ctx,
+                &stroke,
+                dashes.as_ptr(),
+                dashes.len(),
+                0.0,
+                tolerance,
+                scale,
+            )
+        };
+        assert_eq!(set(-1.0, 1.0), VELLO_ERROR_INVALID_PARAMETER);
+        assert_eq!(set(0.25, 0.0), VELLO_ERROR_INVALID_PARAMETER);
+        assert_eq!(set(0.25, f64::NAN), VELLO_ERROR_INVALID_PARAMETER);
+        assert_eq!(set(0.0, 0.0), VELLO_OK);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn layer_bounds_do_not_clip() {
+        let draw = |bounds: Option<VelloRect>| {
+            let ctx = vello_render_context_new(24, 16);
+            let bounds = bounds.as_ref().map_or(std::ptr::null(), |b| b as *const VelloRect);
+            let result = vello_render_context_push_opacity_layer_bounded(ctx, 0.5, bounds);
+            assert_eq!(result, VELLO_OK);
+            fill(ctx, [255, 0, 0, 255], 2.5, 2.0, 9.5, 8.0);
+            fill(ctx, [0, 0, 255, 255], 6.0, 5.0, 20.0, 14.0);
+            assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+            let pixels = render(ctx, 24, 16);
+            vello_render_context_free(ctx);
+            pixels
+        };
+
+        let unbounded = draw(None);
+        // A hint holding all of the drawing, and one the blue rect leaves
+        let holding = draw(Some(VelloRect { x0: 2.5, y0: 2.0, x1: 20.0, y1: 14.0 }));
+        let leaving = draw(Some(VelloRect { x0: 0.0, y0: 0.0, x1: 10.0, y1: 8.0 }));
+        assert_eq!(holding, unbounded);
+        assert_eq!(leaving, unbounded);
+
+        let ctx = vello_render_context_new(8, 8);
+        let bounds = VelloRect { x0: 0.0, y0: f64::NAN, x1: 4.0, y1: 4.0 };
+        let result = vello_render_context_push_opacity_layer_bounded(ctx, 0.5, &bounds);
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn trim_keeps_drawing_and_memory_usage_counts_strips() {
+        let ctx = vello_render_context_new(16, 16);
+        let mut usage = VelloMemoryUsage::default();
+        assert_eq!(vello_render_context_memory_usage(ctx, &mut usage), VELLO_OK);
+        assert_eq!(usage.strip_bytes, 0);
+
+        fill(ctx, [255, 0, 0, 255], 0.5, 0.5, 8.5, 8.5);
+        assert_eq!(vello_render_context_memory_usage(ctx, &mut usage), VELLO_OK);
+        assert!(usage.strip_bytes > 0);
+        assert!(usage.scene_bytes > 0);
+        assert!(usage.total_bytes >= usage.strip_bytes + usage.scene_bytes);
+
+        assert_eq!(vello_render_context_trim(ctx), VELLO_OK);
+        assert_eq!(render(ctx, 16, 16)[4 * 16 + 4], [255, 0, 0, 255]);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn getters_reject_other_handle_kinds() {
+        let cache = vello_layer_cache_new(4, 4);
+        assert!(!vello_layer_cache_is_valid(cache));
+        assert_eq!(vello_render_context_width(cache as *const _), 0);
+        assert_eq!(vello_render_context_tile_count(cache as *const _, 4), 0);
+        vello_layer_cache_free(cache);
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! String conversion for enums used in settings and diagnostics
+//!
+//! Names are lowercase snake_case (`color_dodge`, `src_over`). Parsing ignores
+//! case, `_` and `-`, so `ColorDodge` and `color-dodge` are accepted as well.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+use crate::error::set_last_error;
+use crate::types::*;
+
+/// Enum with a fixed table of NUL-terminated names
+trait EnumNames: Copy + PartialEq + 'static {
+    const KIND: &'static str;
+    const NAMES: &'static [(Self, &'static str)];
+}
+
+impl EnumNames for VelloMix {
+    const KIND: &'static str = "mix mode";
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (VelloMix::Normal, "normal\0"),
+        (VelloMix::Multiply, "multiply\0"),
+        (VelloMix::Screen, "screen\0"),
+        (VelloMix::Overlay, "overlay\0"),
+        (VelloMix::Darken, "darken\0"),
+        (VelloMix::Lighten, "lighten\0"),
+        (VelloMix::ColorDodge, "color_dodge\0"),
+        (VelloMix::ColorBurn, "color_burn\0"),
+        (VelloMix::HardLight, "hard_light\0"),
+        (VelloMix::SoftLight, "soft_light\0"),
+        (VelloMix::Difference, "difference\0"),
+        (VelloMix::Exclusion, "exclusion\0"),
+        (VelloMix::Hue, "hue\0"),
+        (VelloMix::Saturation, "saturation\0"),
+        (VelloMix::Color, "color\0"),
+        (VelloMix::Luminosity, "luminosity\0"),
+    ];
+}
+
+impl EnumNames for VelloCompose {
+    const KIND: &'static str = "compose mode";
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (VelloCompose::Clear, "clear\0"),
+        (VelloCompose::Copy, "copy\0"),
+        (VelloCompose::Dest, "dest\0"),
+        (VelloCompose::SrcOver, "src_over\0"),
+        (VelloCompose::DestOver, "dest_over\0"),
+        (VelloCompose::SrcIn, "src_in\0"),
+        (VelloCompose::DestIn, "dest_in\0"),
+        (VelloCompose::SrcOut, "src_out\0"),
+        (VelloCompose::DestOut, "dest_out\0"),
+        (VelloCompose::SrcAtop, "src_atop\0"),
+        (VelloCompose::DestAtop, "dest_atop\0"),
+        (VelloCompose::Xor, "xor\0"),
+        (VelloCompose::Plus, "plus\0"),
+        (VelloCompose::PlusLighter, "plus_lighter\0"),
+    ];
+}
+
+impl EnumNames for VelloExtend {
+    const KIND: &'static str = "extend mode";
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (VelloExtend::Pad, "pad\0"),
+        (VelloExtend::Repeat, "repeat\0"),
+        (VelloExtend::Reflect, "reflect\0"),
+    ];
+}
+
+impl EnumNames for VelloFillRule {
+    const KIND: &'static str = "fill rule";
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (VelloFillRule::NonZero, "non_zero\0"),
+        (VelloFillRule::EvenOdd, "even_odd\0"),
+    ];
+}
+
+impl EnumNames for VelloSimdLevel {
+    const KIND: &'static str = "SIMD level";
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (VelloSimdLevel::Fallback, "fallback\0"),
+        (VelloSimdLevel::Sse2, "sse2\0"),
+        (VelloSimdLevel::Sse42, "sse42\0"),
+        (VelloSimdLevel::Avx, "avx\0"),
+        (VelloSimdLevel::Avx2, "avx2\0"),
+        (VelloSimdLevel::Avx512, "avx512\0"),
+        (VelloSimdLevel::Neon, "neon\0"),
+    ];
+}
+
+fn to_c_str<T: EnumNames>(value: T) -> *const c_char {
+    let name = T::NAMES
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map_or("unknown\0", |(_, name)| name);
+    name.as_ptr() as *const c_char
+}
+
+fn parse<T: EnumNames>(name: *const c_char, out: *mut T) -> c_int {
+    if name.is_null() || out.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| !matches!(c, '_' | '-' | '\0'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    let wanted = normalize(&name);
+    match T::NAMES.iter().find(|(_, n)| normalize(n) == wanted) {
+        Some((value, _)) => {
+            unsafe { *out = *value };
+            VELLO_OK
+        }
+        None => {
+            set_last_error(format!("Unknown {} '{}'", T::KIND, name));
+            VELLO_ERROR_INVALID_PARAMETER
+        }
+    }
+}
+
+/// Get the name of a mix mode (static lifetime)
+#[no_mangle]
+pub extern "C" fn vello_mix_to_string(mix: VelloMix) -> *const c_char {
+    to_c_str(mix)
+}
+
+/// Parse a mix mode name
+#[no_mangle]
+pub extern "C" fn vello_mix_parse(name: *const c_char, out_mix: *mut VelloMix) -> c_int {
+    parse(name, out_mix)
+}
+
+/// Get the name of a compose mode (static lifetime)
+#[no_mangle]
+pub extern "C" fn vello_compose_to_string(compose: VelloCompose) -> *const c_char {
+    to_c_str(compose)
+}
+
+/// Parse a compose mode name
+#[no_mangle]
+pub extern "C" fn vello_compose_parse(
+    name: *const c_char,
+    out_compose: *mut VelloCompose,
+) -> c_int {
+    parse(name, out_compose)
+}
+
+/// Get the name of an extend mode (static lifetime)
+#[no_mangle]
+pub extern "C" fn vello_extend_to_string(extend: VelloExtend) -> *const c_char {
+    to_c_str(extend)
+}
+
+/// Parse an extend mode name
+#[no_mangle]
+pub extern "C" fn vello_extend_parse(name: *const c_char, out_extend: *mut VelloExtend) -> c_int {
+    parse(name, out_extend)
+}
+
+/// Get the name of a fill rule (static lifetime)
+#[no_mangle]
+pub extern "C" fn vello_fill_rule_to_string(fill_rule: VelloFillRule) -> *const c_char {
+    to_c_str(fill_rule)
+}
+
+/// Parse a fill rule name
+#[no_mangle]
+pub extern "C" fn vello_fill_rule_parse(
+    name: *const c_char,
+    out_fill_rule: *mut VelloFillRule,
+) -> c_int {
+    parse(name, out_fill_rule)
+}
+
+/// Get the name of a SIMD level (static lifetime)
+#[no_mangle]
+pub extern "C" fn vello_simd_level_to_string(level: VelloSimdLevel) -> *const c_char {
+    to_c_str(level)
+}
+
+/// Parse a SIMD level name
+#[no_mangle]
+pub extern "C" fn vello_simd_level_parse(
+    name: *const c_char,
+    out_level: *mut VelloSimdLevel,
+) -> c_int {
+    parse(name, out_level)
+}
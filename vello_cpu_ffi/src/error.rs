@@ -3,24 +3,199 @@
 
 //! Error handling for FFI
 
-use std::cell::RefCell;
-use std::ffi::CString;
-use std::os::raw::c_char;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_void, CString};
+use std::os::raw::{c_char, c_int};
+use std::sync::RwLock;
+
+use crate::handle::InvalidHandle;
+use crate::types::{
+    VELLO_ERROR_INVALID_HANDLE, VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_RENDER_FAILED, VELLO_OK,
+};
 
 thread_local! {
     static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+    /// Set while a log callback runs, so errors raised from inside it are not logged again
+    static IN_LOG_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Severity of a log message
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VelloLogLevel {
+    Debug = 0,
+    Info = 1,
+    Warning = 2,
+    Error = 3,
+}
+
+/// Log callback: receives the level, a NUL-terminated UTF-8 message that is only
+/// valid during the call, and the registered user data. It may be invoked from
+/// any thread that calls into the library.
+pub type VelloLogCallback =
+    extern "C" fn(level: VelloLogLevel, message: *const c_char, user_data: *mut c_void);
+
+#[derive(Copy, Clone)]
+struct LogSink {
+    callback: VelloLogCallback,
+    min_level: VelloLogLevel,
+    /// Stored as an address so the sink can live in a static
+    user_data: usize,
+}
+
+static LOG_SINK: RwLock<Option<LogSink>> = RwLock::new(None);
+
+/// Forward a message to the registered log callback, if its level is enabled
+///
+/// The message is only built when it will be delivered.
+pub(crate) fn log(level: VelloLogLevel, message: impl FnOnce() -> String) {
+    if IN_LOG_CALLBACK.with(Cell::get) {
+        return;
+    }
+    // Copied out so the lock is released before the callback, which may
+    // register a different one
+    let sink = *LOG_SINK.read().unwrap_or_else(|e| e.into_inner());
+    let Some(sink) = sink.filter(|sink| level >= sink.min_level) else {
+        return;
+    };
+    let Ok(message) = CString::new(message()) else {
+        return;
+    };
+
+    IN_LOG_CALLBACK.with(|flag| flag.set(true));
+    let result = std::panic::catch_unwind(|| {
+        (sink.callback)(level, message.as_ptr(), sink.user_data as *mut c_void)
+    });
+    IN_LOG_CALLBACK.with(|flag| flag.set(false));
+    drop(result);
 }
 
 /// Set the last error message
+///
+/// The message is also forwarded to the log callback at error level.
 pub fn set_last_error(err: impl Into<String>) {
+    let err_string = err.into();
+    log(VelloLogLevel::Error, || err_string.clone());
     LAST_ERROR.with(|e| {
-        let err_string = err.into();
         if let Ok(c_string) = CString::new(err_string) {
             *e.borrow_mut() = Some(c_string);
         }
     });
 }
 
+/// Register a callback for errors, warnings and diagnostics
+///
+/// Messages below `min_level` are dropped. Pass a null callback to unregister.
+/// Errors are still stored for `vello_get_last_error` as well.
+#[no_mangle]
+pub extern "C" fn vello_set_log_callback(
+    callback: Option<VelloLogCallback>,
+    min_level: VelloLogLevel,
+    user_data: *mut c_void,
+) -> c_int {
+    let mut sink = LOG_SINK.write().unwrap_or_else(|e| e.into_inner());
+    *sink = callback.map(|callback| LogSink {
+        callback,
+        min_level,
+        user_data: user_data as usize,
+    });
+    VELLO_OK
+}
+
+/// What happens when a panic is caught at the FFI boundary
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloPanicPolicy {
+    /// Store the panic message as the last error and return an error code
+    CatchAndReturn = 0,
+    /// Abort the process, so crash reporters capture the failing state; the
+    /// message is sent to the log callback first
+    Abort = 1,
+    /// Invoke the panic callback, then return an error code
+    Callback = 2,
+}
+
+/// Panic callback: receives the NUL-terminated panic message, only valid during
+/// the call, and the registered user data
+pub type VelloPanicCallback = extern "C" fn(message: *const c_char, user_data: *mut c_void);
+
+#[derive(Copy, Clone)]
+struct PanicHandler {
+    policy: VelloPanicPolicy,
+    callback: Option<VelloPanicCallback>,
+    user_data: usize,
+}
+
+static PANIC_HANDLER: RwLock<PanicHandler> = RwLock::new(PanicHandler {
+    policy: VelloPanicPolicy::CatchAndReturn,
+    callback: None,
+    user_data: 0,
+});
+
+/// Choose how panics inside the library are handled
+///
+/// `callback` is required for `VelloPanicPolicy::Callback` and ignored otherwise.
+/// Invalid handle errors are not panics from the caller's point of view and
+/// always return `VELLO_ERROR_INVALID_HANDLE`.
+#[no_mangle]
+pub extern "C" fn vello_set_panic_policy(
+    policy: VelloPanicPolicy,
+    callback: Option<VelloPanicCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    if policy == VelloPanicPolicy::Callback && callback.is_none() {
+        set_last_error("Panic callback policy requires a callback");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    let mut handler = PANIC_HANDLER.write().unwrap_or_else(|e| e.into_inner());
+    *handler = PanicHandler {
+        policy,
+        callback,
+        user_data: user_data as usize,
+    };
+    VELLO_OK
+}
+
+/// Turn a panic caught by `ffi_catch!` or `ffi_catch_ptr!` into an error code,
+/// applying the panic policy
+#[doc(hidden)]
+pub fn report_panic(payload: Box<dyn Any + Send>) -> c_int {
+    if let Some(handle) = payload.downcast_ref::<InvalidHandle>() {
+        set_last_error(format!("Invalid {} handle", handle.0));
+        return VELLO_ERROR_INVALID_HANDLE;
+    }
+
+    let msg = if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Unknown panic occurred".to_string()
+    };
+    let msg = format!("Panic: {}", msg);
+
+    // Copied out so the lock is released before the callback runs
+    let handler = *PANIC_HANDLER.read().unwrap_or_else(|e| e.into_inner());
+    match (handler.policy, handler.callback) {
+        (VelloPanicPolicy::Abort, _) => {
+            log(VelloLogLevel::Error, || format!("{msg}, aborting"));
+            std::process::abort();
+        }
+        (VelloPanicPolicy::Callback, Some(callback)) => {
+            if let Ok(c_msg) = CString::new(msg.as_str()) {
+                let user_data = handler.user_data as *mut c_void;
+                let _ = std::panic::catch_unwind(|| callback(c_msg.as_ptr(), user_data));
+            }
+        }
+        _ => {}
+    }
+
+    set_last_error(msg);
+    VELLO_ERROR_RENDER_FAILED
+}
+
 /// Get the last error message (thread-local, UTF-8)
 #[no_mangle]
 pub extern "C" fn vello_get_last_error() -> *const c_char {
@@ -30,6 +205,46 @@ pub extern "C" fn vello_get_last_error() -> *const c_char {
     })
 }
 
+/// Copy the last error message into a caller buffer (thread-local, UTF-8)
+///
+/// The message is NUL-terminated and truncated to fit. `out_required` receives
+/// the buffer size needed for the whole message including the terminator, or 0
+/// when there is no error; pass a null buffer to only query it. Returns
+/// `VELLO_ERROR_INVALID_PARAMETER` when the message was truncated. The stored
+/// error is left untouched either way.
+#[no_mangle]
+pub extern "C" fn vello_get_last_error_msg(
+    buffer: *mut c_char,
+    buffer_len: usize,
+    out_required: *mut usize,
+) -> c_int {
+    LAST_ERROR.with(|e| {
+        let message = e.borrow();
+        let message = message.as_ref().map(|m| m.to_bytes()).unwrap_or_default();
+        let required = if message.is_empty() { 0 } else { message.len() + 1 };
+        if !out_required.is_null() {
+            unsafe { *out_required = required };
+        }
+        if buffer.is_null() {
+            return VELLO_OK;
+        }
+        if buffer_len == 0 {
+            return if required == 0 { VELLO_OK } else { VELLO_ERROR_INVALID_PARAMETER };
+        }
+
+        let count = message.len().min(buffer_len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(message.as_ptr(), buffer as *mut u8, count);
+            *buffer.add(count) = 0;
+        }
+        if count < message.len() {
+            VELLO_ERROR_INVALID_PARAMETER
+        } else {
+            VELLO_OK
+        }
+    })
+}
+
 /// Clear the last error
 #[no_mangle]
 pub extern "C" fn vello_clear_last_error() {
@@ -41,41 +256,50 @@ pub extern "C" fn vello_clear_last_error() {
 /// Helper macro for wrapping FFI functions with panic catching (returns error code)
 #[macro_export]
 macro_rules! ffi_catch {
-    ($body:expr) => {
+    ($body:expr) => {{
+        let _scope = $crate::handle::CallScope::enter();
         match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| $body)) {
             Ok(result) => result,
-            Err(e) => {
-                let msg = if let Some(s) = e.downcast_ref::<&str>() {
-                    s.to_string()
-                } else if let Some(s) = e.downcast_ref::<String>() {
-                    s.clone()
-                } else {
-                    "Unknown panic occurred".to_string()
-                };
-                $crate::error::set_last_error(format!("Panic: {}", msg));
-                $crate::types::VELLO_ERROR_RENDER_FAILED
-            }
+            Err(e) => $crate::error::report_panic(e),
         }
-    };
+    }};
 }
 
 /// Helper macro for wrapping FFI functions that return pointers
 #[macro_export]
 macro_rules! ffi_catch_ptr {
-    ($body:expr) => {
+    ($body:expr) => {{
+        let _scope = $crate::handle::CallScope::enter();
         match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| $body)) {
             Ok(result) => result,
             Err(e) => {
-                let msg = if let Some(s) = e.downcast_ref::<&str>() {
-                    s.to_string()
-                } else if let Some(s) = e.downcast_ref::<String>() {
-                    s.clone()
-                } else {
-                    "Unknown panic occurred".to_string()
-                };
-                $crate::error::set_last_error(format!("Panic: {}", msg));
+                $crate::error::report_panic(e);
                 std::ptr::null_mut()
             }
         }
-    };
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static UNREGISTERED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn unregister(_level: VelloLogLevel, _message: *const c_char, _: *mut c_void) {
+        let result = vello_set_log_callback(None, VelloLogLevel::Error, std::ptr::null_mut());
+        assert_eq!(result, VELLO_OK);
+        UNREGISTERED.store(true, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn log_callbacks_can_replace_themselves() {
+        let user_data = std::ptr::null_mut();
+        let result = vello_set_log_callback(Some(unregister), VelloLogLevel::Error, user_data);
+        assert_eq!(result, VELLO_OK);
+        set_last_error("Replaced from the callback");
+        assert!(UNREGISTERED.load(Ordering::SeqCst));
+        assert!(LOG_SINK.read().unwrap().is_none());
+    }
 }
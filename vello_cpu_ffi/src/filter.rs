@@ -0,0 +1,393 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Filter effect FFI bindings
+//!
+//! A filter is a small graph of primitives modelled on SVG filter effects. Each
+//! primitive reads its inputs from the layer contents (`SourceGraphic`), their
+//! alpha channel (`SourceAlpha`) or the result of an earlier primitive, and the
+//! result of the last primitive is composited when the filter layer is popped.
+
+use std::os::raw::c_int;
+
+use vello_cpu::peniko::color::PremulRgba8;
+use vello_cpu::Pixmap;
+
+use crate::context::{composite_pixmap, RenderContextHandle};
+use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::path::PathHandle;
+use crate::types::*;
+use crate::{ffi_catch, ffi_catch_ptr};
+
+/// Filter input: the contents of the filter layer
+pub const VELLO_FILTER_INPUT_SOURCE_GRAPHIC: i32 = -1;
+/// Filter input: the alpha channel of the filter layer contents
+pub const VELLO_FILTER_INPUT_SOURCE_ALPHA: i32 = -2;
+/// Filter input: the result of the previous primitive (source graphic for the first one)
+pub const VELLO_FILTER_INPUT_PREVIOUS: i32 = -3;
+
+/// Opaque handle to a filter graph.
+#[repr(C)]
+pub struct VelloFilter {
+    header: HandleHeader,
+    pub(crate) graph: FilterGraph,
+}
+
+impl TypedHandle for VelloFilter {
+    const TAG: u32 = handle::tag(b"VFLT");
+    const NAME: &'static str = "filter";
+}
+
+#[derive(Clone, Debug)]
+enum FilterNode {
+    GaussianBlur { input: i32, std_dev_x: f32, std_dev_y: f32 },
+    ColorMatrix { input: i32, matrix: [f32; 20] },
+    Offset { input: i32, dx: i32, dy: i32 },
+    Composite { input: i32, input2: i32, compose: VelloCompose },
+    Merge { inputs: Vec<i32> },
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FilterGraph {
+    nodes: Vec<FilterNode>,
+}
+
+impl FilterGraph {
+    fn check_input(&self, input: i32) -> bool {
+        matches!(
+            input,
+            VELLO_FILTER_INPUT_SOURCE_GRAPHIC
+                | VELLO_FILTER_INPUT_SOURCE_ALPHA
+                | VELLO_FILTER_INPUT_PREVIOUS
+        ) || (input >= 0 && (input as usize) < self.nodes.len())
+    }
+
+    fn add(&mut self, node: FilterNode) -> i32 {
+        self.nodes.push(node);
+        self.nodes.len() as i32 - 1
+    }
+
+    /// Run the graph over premultiplied layer contents
+    pub(crate) fn apply(&self, source: &[PremulRgba8], width: usize, height: usize) -> Vec<PremulRgba8> {
+        let source_alpha: Vec<PremulRgba8> = source
+            .iter()
+            .map(|px| PremulRgba8 { r: 0, g: 0, b: 0, a: px.a })
+            .collect();
+        let mut results: Vec<Vec<PremulRgba8>> = Vec::with_capacity(self.nodes.len());
+
+        for node in &self.nodes {
+            let resolve = |input: i32, results: &[Vec<PremulRgba8>]| -> Vec<PremulRgba8> {
+                match input {
+                    VELLO_FILTER_INPUT_SOURCE_GRAPHIC => source.to_vec(),
+                    VELLO_FILTER_INPUT_SOURCE_ALPHA => source_alpha.clone(),
+                    VELLO_FILTER_INPUT_PREVIOUS => match results.last() {
+                        Some(previous) => previous.clone(),
+                        None => source.to_vec(),
+                    },
+                    index => results[index as usize].clone(),
+                }
+            };
+
+            let result = match node {
+                FilterNode::GaussianBlur { input, std_dev_x, std_dev_y } => {
+                    let mut data = resolve(*input, &results);
+                    crate::blur::gaussian_blur(&mut data, width, height, *std_dev_x, *std_dev_y);
+                    data
+                }
+                FilterNode::ColorMatrix { input, matrix } => {
+                    let mut data = resolve(*input, &results);
+                    apply_color_matrix(&mut data, matrix);
+                    data
+                }
+                FilterNode::Offset { input, dx, dy } => {
+                    let data = resolve(*input, &results);
+                    let mut shifted = vec![PremulRgba8 { r: 0, g: 0, b: 0, a: 0 }; data.len()];
+                    for y in 0..height as i64 {
+                        let sy = y - *dy as i64;
+                        if sy < 0 || sy >= height as i64 {
+                            continue;
+                        }
+                        for x in 0..width as i64 {
+                            let sx = x - *dx as i64;
+                            if sx < 0 || sx >= width as i64 {
+                                continue;
+                            }
+                            shifted[y as usize * width + x as usize] =
+                                data[sy as usize * width + sx as usize];
+                        }
+                    }
+                    shifted
+                }
+                FilterNode::Composite { input, input2, compose } => {
+                    let src = resolve(*input, &results);
+                    let mut dst = resolve(*input2, &results);
+                    for (d, s) in dst.iter_mut().zip(src) {
+                        *d = crate::pixel::compose(s, *d, *compose);
+                    }
+                    dst
+                }
+                FilterNode::Merge { inputs } => {
+                    let mut merged = vec![PremulRgba8 { r: 0, g: 0, b: 0, a: 0 }; source.len()];
+                    for input in inputs {
+                        let layer = resolve(*input, &results);
+                        for (d, s) in merged.iter_mut().zip(layer) {
+                            *d = crate::pixel::compose(s, *d, VelloCompose::SrcOver);
+                        }
+                    }
+                    merged
+                }
+            };
+            results.push(result);
+        }
+
+        results.pop().unwrap_or_else(|| source.to_vec())
+    }
+}
+
+/// Apply a row-major 4x5 color matrix to straight-alpha colors (offsets in 0..1)
+pub(crate) fn apply_color_matrix(data: &mut [PremulRgba8], matrix: &[f32; 20]) {
+    for px in data.iter_mut() {
+        let c = crate::pixel::unpremultiply(*px);
+        let mut out = [0.0f32; 4];
+        for (row, value) in out.iter_mut().enumerate() {
+            let m = &matrix[row * 5..row * 5 + 5];
+            *value = m[0] * c[0] + m[1] * c[1] + m[2] * c[2] + m[3] * c[3] + m[4];
+        }
+        *px = crate::pixel::premultiply(out);
+    }
+}
+
+/// Create a new empty filter graph
+#[no_mangle]
+pub extern "C" fn vello_filter_new() -> *mut VelloFilter {
+    ffi_catch_ptr!({
+        handle::into_raw(VelloFilter {
+            header: HandleHeader::new::<VelloFilter>(),
+            graph: FilterGraph::default(),
+        })
+    })
+}
+
+/// Free a filter graph
+#[no_mangle]
+pub extern "C" fn vello_filter_free(filter: *mut VelloFilter) {
+    if !filter.is_null() && VelloFilter::check(filter) {
+        handle::free::<VelloFilter, _>(filter);
+    }
+}
+
+/// Get the number of primitives in a filter graph
+#[no_mangle]
+pub extern "C" fn vello_filter_len(filter: *const VelloFilter) -> usize {
+    if filter.is_null() || !VelloFilter::check(filter) {
+        return 0;
+    }
+    let filter = unsafe { &*filter };
+    filter.graph.nodes.len()
+}
+
+fn add_node(filter: *mut VelloFilter, inputs: &[i32], node: FilterNode, out_node: *mut i32) -> c_int {
+    let filter = VelloFilter::from_ptr_mut(filter);
+    if !inputs.iter().all(|input| filter.graph.check_input(*input)) {
+        set_last_error("Invalid filter input");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+    let index = filter.graph.add(node);
+    if !out_node.is_null() {
+        unsafe { *out_node = index };
+    }
+    VELLO_OK
+}
+
+/// Add a gaussian blur primitive (standard deviations in device pixels)
+#[no_mangle]
+pub extern "C" fn vello_filter_gaussian_blur(
+    filter: *mut VelloFilter,
+    input: i32,
+    std_dev_x: f32,
+    std_dev_y: f32,
+    out_node: *mut i32,
+) -> c_int {
+    if filter.is_null() {
+        set_last_error("Null filter pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    // Checked here so that applying the graph when the layer is popped cannot fail
+    let valid = |std_dev: f32| std_dev.is_finite() && std_dev >= 0.0;
+    if !(valid(std_dev_x) && valid(std_dev_y)) {
+        set_last_error("Blur standard deviation must be finite and non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        add_node(
+            filter,
+            &[input],
+            FilterNode::GaussianBlur { input, std_dev_x, std_dev_y },
+            out_node,
+        )
+    })
+}
+
+/// Add a color matrix primitive (row-major 4x5 matrix, 20 floats)
+#[no_mangle]
+pub extern "C" fn vello_filter_color_matrix(
+    filter: *mut VelloFilter,
+    input: i32,
+    matrix: *const f32,
+    out_node: *mut i32,
+) -> c_int {
+    if filter.is_null() || matrix.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let mut values = [0.0f32; 20];
+        values.copy_from_slice(unsafe { std::slice::from_raw_parts(matrix, 20) });
+        add_node(filter, &[input], FilterNode::ColorMatrix { input, matrix: values }, out_node)
+    })
+}
+
+/// Add an offset primitive (offsets in device pixels)
+#[no_mangle]
+pub extern "C" fn vello_filter_offset(
+    filter: *mut VelloFilter,
+    input: i32,
+    dx: i32,
+    dy: i32,
+    out_node: *mut i32,
+) -> c_int {
+    if filter.is_null() {
+        set_last_error("Null filter pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({ add_node(filter, &[input], FilterNode::Offset { input, dx, dy }, out_node) })
+}
+
+/// Add a composite primitive placing `input` onto `input2` with a Porter-Duff operator
+#[no_mangle]
+pub extern "C" fn vello_filter_composite(
+    filter: *mut VelloFilter,
+    input: i32,
+    input2: i32,
+    compose: VelloCompose,
+    out_node: *mut i32,
+) -> c_int {
+    if filter.is_null() {
+        set_last_error("Null filter pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        add_node(
+            filter,
+            &[input, input2],
+            FilterNode::Composite { input, input2, compose },
+            out_node,
+        )
+    })
+}
+
+/// Add a merge primitive stacking the inputs in order (first at the bottom)
+#[no_mangle]
+pub extern "C" fn vello_filter_merge(
+    filter: *mut VelloFilter,
+    inputs: *const i32,
+    input_count: usize,
+    out_node: *mut i32,
+) -> c_int {
+    if filter.is_null() || (input_count > 0 && inputs.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let inputs = if input_count > 0 {
+            unsafe { std::slice::from_raw_parts(inputs, input_count) }.to_vec()
+        } else {
+            Vec::new()
+        };
+        let node = FilterNode::Merge { inputs: inputs.clone() };
+        add_node(filter, &inputs, node, out_node)
+    })
+}
+
+/// Push a layer whose contents are run through a filter graph when popped
+///
+/// The filter is copied, so it can be freed or modified afterwards. The optional
+/// clip path limits where the filter result is composited.
+#[no_mangle]
+pub extern "C" fn vello_render_context_push_filter_layer(
+    ctx: *mut VelloRenderContext,
+    filter: *const VelloFilter,
+    clip_path: *const VelloBezPath,
+) -> c_int {
+    if ctx.is_null() || filter.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let graph = VelloFilter::from_ptr(filter).graph.clone();
+        let clip = if clip_path.is_null() {
+            None
+        } else {
+            // Clip in the user space active at push time
+            let path = &PathHandle::from_ptr(clip_path).path;
+            Some(*ctx.transform() * path.clone())
+        };
+
+        ctx.push_offscreen_layer(Box::new(move |parent, contents: Pixmap| {
+            let (width, height) = (contents.width(), contents.height());
+            let filtered = graph.apply(contents.data(), width as usize, height as usize);
+            let result = Pixmap::from_parts(filtered, width, height);
+
+            let saved_transform = *parent.transform();
+            parent.set_transform(vello_cpu::kurbo::Affine::IDENTITY);
+            composite_pixmap(parent, result, clip.as_ref());
+            parent.set_transform(saved_transform);
+        }));
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_free, vello_render_context_new, vello_render_context_pop_layer,
+    };
+    use crate::test_util::{fill, render};
+
+    #[test]
+    fn blur_deviations_are_checked_when_the_node_is_added() {
+        let filter = vello_filter_new();
+        let input = VELLO_FILTER_INPUT_PREVIOUS;
+        let mut node = -1;
+        for (x, y) in [(f32::INFINITY, 1.0), (1.0, f32::NAN), (-1.0, 0.0)] {
+            let result = vello_filter_gaussian_blur(filter, input, x, y, &mut node);
+            assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        }
+        assert_eq!(vello_filter_len(filter), 0);
+        assert_eq!(node, -1);
+
+        // Finite deviations wider than the layer blur it away and keep the stack balanced
+        let result = vello_filter_gaussian_blur(filter, input, 1e30, 1e30, &mut node);
+        assert_eq!(result, VELLO_OK);
+        let ctx = vello_render_context_new(8, 8);
+        let result = vello_render_context_push_filter_layer(ctx, filter, std::ptr::null());
+        assert_eq!(result, VELLO_OK);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 8.0, 8.0);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_ERROR_LAYER_STACK_EMPTY);
+        assert!(render(ctx, 8, 8).iter().all(|px| px[3] == 0));
+
+        vello_render_context_free(ctx);
+        vello_filter_free(filter);
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Process-wide font registry FFI bindings
+//!
+//! Fonts are registered once and referenced by a stable `u32` ID from any
+//! context or thread. Registering identical data again returns the existing
+//! ID. ID 0 is never assigned.
+
+use std::collections::HashMap;
+use std::os::raw::c_int;
+use std::sync::{Mutex, OnceLock};
+
+use vello_cpu::peniko::{Blob, FontData};
+
+use crate::context::RenderContextHandle;
+use crate::error::set_last_error;
+use crate::handle::{self, TypedHandle};
+use crate::text::{FontHandle, VelloFontData, VelloGlyph};
+use crate::types::*;
+use crate::{ffi_catch, ffi_catch_ptr};
+
+#[derive(Default)]
+struct Registry {
+    fonts: HashMap<u32, FontData>,
+    /// Content digest of the data and face index, for deduplication
+    by_digest: HashMap<u64, u32>,
+    next_id: u32,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry { next_id: 1, ..Default::default() }))
+}
+
+/// Look up a registered font; the returned data shares the registered blob
+pub(crate) fn registered_font(id: u32) -> Option<FontData> {
+    let registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.fonts.get(&id).cloned()
+}
+
+fn digest(data: &[u8], index: u32) -> u64 {
+    let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+    hasher.update(&index.to_le_bytes());
+    hasher.update(data);
+    hasher.digest()
+}
+
+/// Register font data (copied) and get its ID; returns 0 on failure
+#[no_mangle]
+pub extern "C" fn vello_font_register(data: *const u8, len: usize, index: u32) -> u32 {
+    if data.is_null() || len == 0 {
+        set_last_error("Null or empty font data");
+        return 0;
+    }
+
+    let slice = unsafe { std::slice::from_raw_parts(data, len) };
+    if skrifa::FontRef::from_index(slice, index).is_err() {
+        set_last_error("Invalid font data or face index");
+        return 0;
+    }
+
+    let key = digest(slice, index);
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = registry.by_digest.get(&key) {
+        return *id;
+    }
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.fonts.insert(id, FontData::new(Blob::from(slice.to_vec()), index));
+    registry.by_digest.insert(key, id);
+    id
+}
+
+/// Register an existing font handle and get its ID; returns 0 on failure
+#[no_mangle]
+pub extern "C" fn vello_font_register_font_data(font: *const VelloFontData) -> u32 {
+    if font.is_null() {
+        set_last_error("Null font pointer");
+        return 0;
+    }
+    if !FontHandle::check(font) {
+        return 0;
+    }
+
+    let font = &unsafe { &*(font as *const FontHandle) }.font;
+    let key = digest(font.data.as_ref(), font.index);
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = registry.by_digest.get(&key) {
+        return *id;
+    }
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.fonts.insert(id, font.clone());
+    registry.by_digest.insert(key, id);
+    id
+}
+
+/// Remove a font from the registry
+///
+/// Handles obtained with `vello_font_registry_get` stay valid. Returns
+/// `VELLO_ERROR_INVALID_HANDLE` for unknown IDs.
+#[no_mangle]
+pub extern "C" fn vello_font_unregister(id: u32) -> c_int {
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    if registry.fonts.remove(&id).is_none() {
+        set_last_error("Unknown font ID");
+        return VELLO_ERROR_INVALID_HANDLE;
+    }
+    registry.by_digest.retain(|_, v| *v != id);
+    VELLO_OK
+}
+
+/// Get a font handle for a registered font (free with `vello_font_data_free`)
+#[no_mangle]
+pub extern "C" fn vello_font_registry_get(id: u32) -> *mut VelloFontData {
+    ffi_catch_ptr!({
+        match registered_font(id) {
+            Some(font) => handle::into_raw(FontHandle::new(font)) as *mut VelloFontData,
+            None => {
+                set_last_error("Unknown font ID");
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Fill glyphs of a registered font with the current paint
+#[no_mangle]
+pub extern "C" fn vello_render_context_fill_glyphs_by_id(
+    ctx: *mut VelloRenderContext,
+    font_id: u32,
+    font_size: f32,
+    glyphs: *const VelloGlyph,
+    glyph_count: usize,
+) -> c_int {
+    if ctx.is_null() || (glyph_count > 0 && glyphs.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use vello_cpu::Glyph;
+
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let Some(font) = registered_font(font_id) else {
+            set_last_error("Unknown font ID");
+            return VELLO_ERROR_INVALID_HANDLE;
+        };
+        let glyph_slice = if glyph_count > 0 {
+            unsafe { std::slice::from_raw_parts(glyphs, glyph_count) }
+        } else {
+            &[]
+        };
+
+        let hint = ctx.glyph_hinting();
+        ctx.glyph_run(&font)
+            .font_size(font_size)
+            .hint(hint)
+            .fill_glyphs(glyph_slice.iter().map(|g| Glyph { id: g.id, x: g.x, y: g.y }));
+        VELLO_OK
+    })
+}
@@ -0,0 +1,136 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Per-context cache of scaled glyph outlines
+//!
+//! vello_cpu extracts outlines afresh for every glyph run; this cache serves the
+//! path-based text helpers of the FFI, which place individual glyph outlines.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use vello_cpu::kurbo::BezPath;
+use vello_cpu::peniko::FontData;
+
+/// Default cache budget in bytes
+pub(crate) const DEFAULT_LIMIT: usize = 4 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    blob: u64,
+    index: u32,
+    glyph_id: u32,
+    size_bits: u32,
+}
+
+/// Glyph outline cache statistics
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VelloGlyphCacheStats {
+    /// Number of cached outlines
+    pub entries: usize,
+    /// Approximate memory used by cached outlines in bytes
+    pub bytes: usize,
+    /// Memory budget in bytes
+    pub limit: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+pub(crate) struct GlyphCache {
+    entries: HashMap<GlyphKey, Arc<BezPath>>,
+    /// Insertion order, oldest first, for eviction
+    order: VecDeque<GlyphKey>,
+    bytes: usize,
+    limit: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for GlyphCache {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            bytes: 0,
+            limit: DEFAULT_LIMIT,
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+fn path_bytes(path: &BezPath) -> usize {
+    std::mem::size_of::<BezPath>() + std::mem::size_of_val(path.elements())
+}
+
+impl GlyphCache {
+    /// Outline of `glyph_id` at `font_size`, extracting and caching it on a miss
+    pub(crate) fn outline(
+        &mut self,
+        font: &FontData,
+        glyph_id: u32,
+        font_size: f32,
+    ) -> Result<Arc<BezPath>, &'static str> {
+        let key = GlyphKey {
+            blob: font.data.id(),
+            index: font.index,
+            glyph_id,
+            size_bits: font_size.to_bits(),
+        };
+        if let Some(path) = self.entries.get(&key) {
+            self.hits += 1;
+            return Ok(path.clone());
+        }
+
+        self.misses += 1;
+        let path = Arc::new(crate::text::glyph_outline(font, glyph_id, font_size)?);
+        let size = path_bytes(&path);
+        if size <= self.limit {
+            self.bytes += size;
+            self.entries.insert(key, path.clone());
+            self.order.push_back(key);
+            self.evict();
+        }
+        Ok(path)
+    }
+
+    fn evict(&mut self) {
+        while self.bytes > self.limit {
+            let Some(key) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(path) = self.entries.remove(&key) {
+                self.bytes -= path_bytes(&path);
+            }
+        }
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.evict();
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    /// Drop all cached outlines and release the table storage
+    pub(crate) fn trim(&mut self) {
+        self.entries = HashMap::new();
+        self.order = VecDeque::new();
+        self.bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> VelloGlyphCacheStats {
+        VelloGlyphCacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            limit: self.limit,
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+}
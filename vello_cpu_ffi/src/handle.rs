@@ -0,0 +1,404 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Typed handle validation
+//!
+//! Every object handed out as an opaque pointer is registered with its address,
+//! per-type tag and generation number in a table of live handles, either by
+//! [`into_raw`] or, for objects owned by another handle, by [`register`].
+//! Handle structs are `#[repr(C)]` and start with a [`HandleHeader`], which
+//! removes the entry again when the object is dropped. Entry points look the
+//! pointer up before dereferencing, so passing a pixmap where a context is
+//! expected, or a handle that was already freed, fails with
+//! `VELLO_ERROR_INVALID_HANDLE` without ever reading the pointed-to memory.
+//!
+//! With leak tracking enabled, every header created also registers itself in a
+//! table of live handles together with a creation backtrace, and removes itself
+//! when dropped.
+//!
+//! The module is public so that the other binding crates in this repository,
+//! such as `vello_hybrid_ffi`, validate their handles the same way.
+//!
+//! Handles may also own a [`HandleLock`]. Borrowing such a handle acquires its
+//! lock for the rest of the enclosing [`CallScope`], which every `ffi_catch!`
+//! opens, so each FFI call runs with exclusive access to the object.
+
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::ffi::{c_void, CString};
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Condvar, Mutex, RwLock};
+use std::thread::ThreadId;
+
+use crate::types::VELLO_OK;
+
+static NEXT_GENERATION: AtomicU32 = AtomicU32::new(1);
+
+static TRACKING: AtomicBool = AtomicBool::new(false);
+
+/// Live handles by generation, only filled while tracking is enabled
+static LIVE_HANDLES: Mutex<BTreeMap<u32, LiveHandle>> = Mutex::new(BTreeMap::new());
+
+struct LiveHandle {
+    name: &'static str,
+    backtrace: String,
+}
+
+/// Every handle currently handed out, by address
+static REGISTRY: RwLock<BTreeMap<usize, Registered>> = RwLock::new(BTreeMap::new());
+
+#[derive(Copy, Clone)]
+struct Registered {
+    tag: u32,
+    generation: u32,
+}
+
+/// Leading field of every typed handle; the owning struct must be `#[repr(C)]`
+#[repr(C)]
+pub struct HandleHeader {
+    generation: u32,
+    tracked: bool,
+}
+
+impl HandleHeader {
+    pub fn new<T: TypedHandle>() -> Self {
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        let tracked = TRACKING.load(Ordering::Relaxed);
+        if tracked {
+            let handle = LiveHandle {
+                name: T::NAME,
+                backtrace: Backtrace::force_capture().to_string(),
+            };
+            live_handles().insert(generation, handle);
+        }
+        Self {
+            generation,
+            tracked,
+        }
+    }
+}
+
+impl Drop for HandleHeader {
+    fn drop(&mut self) {
+        if self.tracked {
+            live_handles().remove(&self.generation);
+        }
+        // The header is the leading field, so its address is the handle's
+        let address = self as *const Self as usize;
+        let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+        if registry
+            .get(&address)
+            .is_some_and(|entry| entry.generation == self.generation)
+        {
+            registry.remove(&address);
+        }
+    }
+}
+
+fn live_handles() -> std::sync::MutexGuard<'static, BTreeMap<u32, LiveHandle>> {
+    LIVE_HANDLES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn lookup(ptr: *const c_void) -> Option<Registered> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.get(&(ptr as usize)).copied()
+}
+
+/// Move a handle to the heap and register it, returning the pointer handed out
+pub fn into_raw<T: TypedHandle>(handle: T) -> *mut T {
+    into_raw_box(Box::new(handle))
+}
+
+/// [`into_raw`] for a handle that is already boxed
+pub fn into_raw_box<T: TypedHandle>(handle: Box<T>) -> *mut T {
+    let ptr = Box::into_raw(handle);
+    register(unsafe { &mut *ptr })
+}
+
+/// Register a handle that stays owned by Rust code, e.g. by another handle,
+/// returning the pointer handed out; it is unregistered when dropped
+///
+/// The handle must not move while registered.
+pub fn register<T: TypedHandle>(handle: &mut T) -> *mut T {
+    let ptr = handle as *mut T;
+    // The header is the leading field of every handle struct
+    let generation = unsafe { (*(ptr as *const HandleHeader)).generation };
+    let entry = Registered {
+        tag: T::TAG,
+        generation,
+    };
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(ptr as usize, entry);
+    ptr
+}
+
+/// Unregister a handle of type `T` and take back ownership of it
+///
+/// Returns None, leaving the pointer alone, if it is not a live `T`.
+pub fn take<T: TypedHandle, P>(ptr: *mut P) -> Option<Box<T>> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    match registry.get(&(ptr as usize)) {
+        Some(entry) if entry.tag == T::TAG => {
+            registry.remove(&(ptr as usize));
+            drop(registry);
+            Some(unsafe { Box::from_raw(ptr as *mut T) })
+        }
+        _ => None,
+    }
+}
+
+/// Free a handle of type `T`; returns false if the pointer is not a live `T`
+pub fn free<T: TypedHandle, P>(ptr: *mut P) -> bool {
+    take::<T, P>(ptr).is_some()
+}
+
+/// Object that can be handed out as a validated opaque pointer
+pub trait TypedHandle: Sized {
+    /// Four ASCII characters identifying the type
+    const TAG: u32;
+    /// Type name used in error messages
+    const NAME: &'static str;
+
+    /// Lock guarding the object, if it is shared between threads
+    ///
+    /// Called before any reference to the object is created, so implementations
+    /// must only touch the lock field through `ptr`.
+    unsafe fn lock<'a>(_ptr: *const Self) -> Option<&'a HandleLock> {
+        None
+    }
+
+    /// Whether a pointer refers to a live handle of this type
+    fn is_valid<P>(ptr: *const P) -> bool {
+        lookup(ptr as *const c_void).is_some_and(|entry| entry.tag == Self::TAG)
+    }
+
+    /// Check a handle outside of `ffi_catch!`, recording an error on failure
+    fn check<P>(ptr: *const P) -> bool {
+        let valid = Self::is_valid(ptr);
+        if !valid {
+            crate::error::set_last_error(format!("Invalid {} handle", Self::NAME));
+        }
+        valid
+    }
+
+    /// Borrow a handle inside `ffi_catch!`, unwinding with [`InvalidHandle`] on a tag mismatch
+    fn from_ptr<'a, P>(ptr: *const P) -> &'a Self {
+        if !Self::is_valid(ptr) {
+            std::panic::resume_unwind(Box::new(InvalidHandle(Self::NAME)));
+        }
+        if let Some(lock) = unsafe { Self::lock(ptr as *const Self) } {
+            lock.acquire_for_scope();
+        }
+        unsafe { &*(ptr as *const Self) }
+    }
+
+    /// Mutable version of [`TypedHandle::from_ptr`]
+    fn from_ptr_mut<'a, P>(ptr: *mut P) -> &'a mut Self {
+        if !Self::is_valid(ptr) {
+            std::panic::resume_unwind(Box::new(InvalidHandle(Self::NAME)));
+        }
+        if let Some(lock) = unsafe { Self::lock(ptr as *const Self) } {
+            lock.acquire_for_scope();
+        }
+        unsafe { &mut *(ptr as *mut Self) }
+    }
+}
+
+thread_local! {
+    static SCOPE_DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// Locks held by this thread with the scope depth that acquired them
+    static HELD_LOCKS: RefCell<Vec<(usize, *const HandleLock)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Re-entrant lock owned by a handle shared between threads
+#[derive(Default)]
+pub struct HandleLock {
+    owner: Mutex<Option<ThreadId>>,
+    released: Condvar,
+}
+
+impl HandleLock {
+    /// Acquire the lock until the innermost [`CallScope`] ends
+    ///
+    /// A thread that already holds the lock keeps it without nesting.
+    fn acquire_for_scope(&self) {
+        let me = std::thread::current().id();
+        let mut owner = self.owner.lock().unwrap_or_else(|e| e.into_inner());
+        if *owner == Some(me) {
+            return;
+        }
+        while owner.is_some() {
+            owner = self.released.wait(owner).unwrap_or_else(|e| e.into_inner());
+        }
+        *owner = Some(me);
+        drop(owner);
+
+        let depth = SCOPE_DEPTH.with(Cell::get);
+        debug_assert!(depth > 0, "handle lock acquired outside of a call scope");
+        HELD_LOCKS.with(|held| held.borrow_mut().push((depth, self as *const Self)));
+    }
+
+    fn release(&self) {
+        *self.owner.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.released.notify_one();
+    }
+}
+
+/// Span of one FFI call; handle locks acquired inside it are released on drop,
+/// including when the call unwinds
+pub struct CallScope(());
+
+impl CallScope {
+    pub fn enter() -> Self {
+        SCOPE_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        CallScope(())
+    }
+}
+
+impl Drop for CallScope {
+    fn drop(&mut self) {
+        let depth = SCOPE_DEPTH.with(Cell::get);
+        HELD_LOCKS.with(|held| {
+            let mut held = held.borrow_mut();
+            while held.last().is_some_and(|&(d, _)| d >= depth) {
+                let (_, lock) = held.pop().unwrap();
+                // Locks are only freed together with their handle, which
+                // cannot happen while a call on it is in progress
+                unsafe { (*lock).release() };
+            }
+        });
+        SCOPE_DEPTH.with(|d| d.set(depth - 1));
+    }
+}
+
+/// Panic payload raised by [`TypedHandle::from_ptr`] and [`TypedHandle::from_ptr_mut`]
+///
+/// `ffi_catch!` and `ffi_catch_ptr!` turn it into `VELLO_ERROR_INVALID_HANDLE`
+/// and a null pointer respectively.
+pub struct InvalidHandle(pub(crate) &'static str);
+
+/// Get the generation number of a handle, or 0 if it is not a live handle
+///
+/// Generations are unique per created object. Bindings that cache handle
+/// pointers can store the generation alongside and compare it later to tell a
+/// recycled address from the object they originally received.
+#[no_mangle]
+pub extern "C" fn vello_handle_generation(handle: *const c_void) -> u32 {
+    lookup(handle).map_or(0, |entry| entry.generation)
+}
+
+/// Callback receiving one live handle: its type name, generation and creation
+/// backtrace. Both strings are NUL-terminated and only valid during the call.
+pub type VelloLiveHandleCallback = extern "C" fn(
+    type_name: *const c_char,
+    generation: u32,
+    backtrace: *const c_char,
+    user_data: *mut c_void,
+);
+
+/// Enable or disable tracking of live handles
+///
+/// Only handles created while tracking is enabled are recorded. Capturing a
+/// backtrace per handle is slow, so this is meant for debugging leaks.
+#[no_mangle]
+pub extern "C" fn vello_debug_set_handle_tracking(enabled: bool) -> c_int {
+    TRACKING.store(enabled, Ordering::Relaxed);
+    VELLO_OK
+}
+
+/// Report every tracked handle that has not been freed yet, oldest first
+///
+/// Returns the number of live handles. The callback may be null to only count
+/// them, and may free handles while the dump is running.
+#[no_mangle]
+pub extern "C" fn vello_debug_dump_live_handles(
+    callback: Option<VelloLiveHandleCallback>,
+    user_data: *mut c_void,
+) -> usize {
+    // Snapshot first so the callback can call back into the library
+    let snapshot: Vec<(u32, &'static str, String)> = live_handles()
+        .iter()
+        .map(|(&generation, h)| (generation, h.name, h.backtrace.clone()))
+        .collect();
+
+    if let Some(callback) = callback {
+        for (generation, name, backtrace) in &snapshot {
+            let name = CString::new(*name).unwrap_or_default();
+            let backtrace = CString::new(backtrace.replace('\0', "")).unwrap_or_default();
+            let _ = std::panic::catch_unwind(|| {
+                callback(name.as_ptr(), *generation, backtrace.as_ptr(), user_data)
+            });
+        }
+    }
+    snapshot.len()
+}
+
+/// Build a tag from four ASCII characters
+pub const fn tag(name: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{vello_render_context_free, vello_render_context_new};
+    use crate::filter::{vello_filter_free, vello_filter_len, vello_filter_new};
+    use crate::path::{vello_bezpath_free, vello_bezpath_move_to, vello_bezpath_new};
+    use crate::recording::{
+        vello_recorder_pop_layer, vello_recording_clear, vello_recording_free, vello_recording_new,
+    };
+    use crate::types::{VelloBezPath, VELLO_ERROR_INVALID_HANDLE};
+
+    #[test]
+    fn wrong_handle_kind_is_rejected() {
+        let path = vello_bezpath_new();
+        let recording = vello_recording_new();
+        let filter = vello_filter_new();
+
+        assert_eq!(vello_recorder_pop_layer(path as *mut c_void), VELLO_ERROR_INVALID_HANDLE);
+        assert_eq!(vello_recording_clear(path as *mut _), VELLO_ERROR_INVALID_HANDLE);
+        assert_eq!(
+            vello_bezpath_move_to(recording as *mut VelloBezPath, 0.0, 0.0),
+            VELLO_ERROR_INVALID_HANDLE
+        );
+        assert_eq!(vello_filter_len(path as *const _), 0);
+        assert_eq!(vello_bezpath_move_to(path, 1.0, 2.0), VELLO_OK);
+
+        // Freeing through the wrong function leaves the handle alive
+        vello_filter_free(path as *mut _);
+        assert_ne!(vello_handle_generation(path as *const c_void), 0);
+
+        vello_filter_free(filter);
+        vello_recording_free(recording);
+        vello_bezpath_free(path);
+    }
+
+    #[test]
+    fn generation_identifies_every_handle_kind() {
+        let ctx = vello_render_context_new(4, 4);
+        let path = vello_bezpath_new();
+        let recording = vello_recording_new();
+
+        let generations = [
+            vello_handle_generation(ctx as *const c_void),
+            vello_handle_generation(path as *const c_void),
+            vello_handle_generation(recording as *const c_void),
+        ];
+        assert!(generations.iter().all(|&generation| generation != 0));
+        assert_ne!(generations[0], generations[1]);
+        assert_ne!(generations[1], generations[2]);
+        assert_eq!(vello_handle_generation(std::ptr::null()), 0);
+
+        // A freed handle no longer reports its generation, even if the address
+        // was reused in the meantime
+        vello_bezpath_free(path);
+        assert_ne!(vello_handle_generation(path as *const c_void), generations[1]);
+
+        vello_recording_free(recording);
+        vello_render_context_free(ctx);
+    }
+}
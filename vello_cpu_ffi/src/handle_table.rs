@@ -0,0 +1,362 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Integer handle table
+//!
+//! An alternative to passing raw pointers around in hosts without pointer
+//! safety: objects are moved into a global slot map and referenced by 64-bit
+//! IDs. The low 32 bits of an ID select a slot, the high 32 bits hold the
+//! slot's generation, which changes whenever the slot is freed. A stale or
+//! forged ID therefore fails to resolve instead of reaching freed memory.
+//!
+//! Hosts resolve an ID to a pointer right before each call and never keep the
+//! pointer. `vello_handle_table_acquire` pins the object until the matching
+//! `vello_handle_table_release`, so another thread removing the ID in the
+//! meantime defers the free instead of leaving the caller with a dangling
+//! pointer.
+
+use std::ffi::c_void;
+use std::os::raw::c_int;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use crate::context::{vello_render_context_free, RenderContextHandle};
+use crate::error::set_last_error;
+use crate::filter::{vello_filter_free, VelloFilter};
+use crate::handle::TypedHandle;
+use crate::image::{vello_image_free, ImageHandle};
+use crate::layer_cache::{vello_layer_cache_free, LayerCacheHandle};
+use crate::mask::{vello_mask_free, MaskHandle};
+use crate::path::{vello_bezpath_free, PathHandle};
+use crate::pixmap::{vello_pixmap_free, PixmapHandle};
+use crate::pixmap_pool::{vello_pixmap_pool_free, PixmapPoolHandle};
+use crate::recording::{vello_recording_free, VelloRecording};
+use crate::text::{vello_font_data_free, vello_glyph_run_free, FontHandle, VelloGlyphRun};
+use crate::text_layout::{vello_text_layout_free, VelloTextLayout};
+use crate::types::*;
+
+/// Kind of object stored in the handle table
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloHandleKind {
+    RenderContext = 0,
+    Pixmap = 1,
+    Mask = 2,
+    Image = 3,
+    BezPath = 4,
+    Recording = 5,
+    FontData = 6,
+    Filter = 7,
+    TextLayout = 8,
+    GlyphRun = 9,
+    LayerCache = 10,
+    PixmapPool = 11,
+}
+
+impl VelloHandleKind {
+    /// Whether `ptr` is a live handle of this kind
+    fn is_valid(self, ptr: *const c_void) -> bool {
+        match self {
+            VelloHandleKind::RenderContext => RenderContextHandle::is_valid(ptr),
+            VelloHandleKind::Pixmap => PixmapHandle::is_valid(ptr),
+            VelloHandleKind::Mask => MaskHandle::is_valid(ptr),
+            VelloHandleKind::Image => ImageHandle::is_valid(ptr),
+            VelloHandleKind::LayerCache => LayerCacheHandle::is_valid(ptr),
+            VelloHandleKind::PixmapPool => PixmapPoolHandle::is_valid(ptr),
+            VelloHandleKind::BezPath => PathHandle::is_valid(ptr),
+            VelloHandleKind::Recording => VelloRecording::is_valid(ptr),
+            VelloHandleKind::FontData => FontHandle::is_valid(ptr),
+            VelloHandleKind::Filter => VelloFilter::is_valid(ptr),
+            VelloHandleKind::TextLayout => VelloTextLayout::is_valid(ptr),
+            VelloHandleKind::GlyphRun => VelloGlyphRun::is_valid(ptr),
+        }
+    }
+
+    fn free(self, ptr: *mut c_void) {
+        match self {
+            VelloHandleKind::RenderContext => vello_render_context_free(ptr),
+            VelloHandleKind::Pixmap => vello_pixmap_free(ptr),
+            VelloHandleKind::Mask => vello_mask_free(ptr),
+            VelloHandleKind::Image => vello_image_free(ptr as *mut _),
+            VelloHandleKind::BezPath => vello_bezpath_free(ptr),
+            VelloHandleKind::Recording => vello_recording_free(ptr as *mut _),
+            VelloHandleKind::FontData => vello_font_data_free(ptr as *mut _),
+            VelloHandleKind::Filter => vello_filter_free(ptr as *mut _),
+            VelloHandleKind::TextLayout => vello_text_layout_free(ptr as *mut _),
+            VelloHandleKind::GlyphRun => vello_glyph_run_free(ptr as *mut _),
+            VelloHandleKind::LayerCache => vello_layer_cache_free(ptr as *mut _),
+            VelloHandleKind::PixmapPool => vello_pixmap_pool_free(ptr as *mut _),
+        }
+    }
+}
+
+struct Slot {
+    generation: u32,
+    entry: Option<Entry>,
+}
+
+/// Object held by an occupied slot
+#[derive(Copy, Clone)]
+struct Entry {
+    ptr: usize,
+    kind: VelloHandleKind,
+    /// Outstanding `vello_handle_table_acquire` calls
+    pins: u32,
+    /// Removed while pinned; freed by the last release
+    removed: bool,
+}
+
+struct HandleTable {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    /// Addresses of the objects held, to refuse inserting one twice
+    addresses: BTreeSet<usize>,
+}
+
+static TABLE: Mutex<HandleTable> = Mutex::new(HandleTable {
+    slots: Vec::new(),
+    free: Vec::new(),
+    addresses: BTreeSet::new(),
+});
+
+fn table() -> std::sync::MutexGuard<'static, HandleTable> {
+    TABLE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl HandleTable {
+    fn insert(&mut self, ptr: *mut c_void, kind: VelloHandleKind) -> Option<u64> {
+        if !self.addresses.insert(ptr as usize) {
+            return None;
+        }
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 1,
+                    entry: None,
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.entry = Some(Entry {
+            ptr: ptr as usize,
+            kind,
+            pins: 0,
+            removed: false,
+        });
+        Some(((slot.generation as u64) << 32) | index as u64)
+    }
+
+    /// Entry for `id`, including one that was removed while pinned
+    fn entry_mut(&mut self, id: u64) -> Option<&mut Entry> {
+        let slot = self.slots.get_mut((id & 0xFFFF_FFFF) as usize)?;
+        if slot.generation as u64 != id >> 32 {
+            return None;
+        }
+        slot.entry.as_mut()
+    }
+
+    fn get(&mut self, id: u64) -> Option<&mut Entry> {
+        self.entry_mut(id).filter(|entry| !entry.removed)
+    }
+
+    /// Empty the slot of `id`, returning the object to free
+    fn vacate(&mut self, id: u64) -> Entry {
+        let index = (id & 0xFFFF_FFFF) as u32;
+        let slot = &mut self.slots[index as usize];
+        let entry = slot.entry.take().expect("vacating an empty slot");
+        // Generation 0 is skipped so that no valid ID is ever 0
+        slot.generation = slot.generation.checked_add(1).unwrap_or(1);
+        self.free.push(index);
+        self.addresses.remove(&entry.ptr);
+        entry
+    }
+
+    /// Remove `id`, returning the object if it can be freed right away
+    fn remove(&mut self, id: u64) -> Result<Option<Entry>, ()> {
+        let entry = self.get(id).ok_or(())?;
+        if entry.pins > 0 {
+            entry.removed = true;
+            return Ok(None);
+        }
+        Ok(Some(self.vacate(id)))
+    }
+
+    /// Drop one pin of `id`, returning the object if it was the last pin of a
+    /// removed entry
+    fn release(&mut self, id: u64) -> Result<Option<Entry>, ()> {
+        let entry = self.entry_mut(id).filter(|entry| entry.pins > 0).ok_or(())?;
+        entry.pins -= 1;
+        if entry.pins == 0 && entry.removed {
+            return Ok(Some(self.vacate(id)));
+        }
+        Ok(None)
+    }
+}
+
+/// Move an object into the handle table and get its ID
+///
+/// The table takes ownership: release the object with `vello_handle_table_remove`
+/// instead of its own free function. IDs are never 0. Inserting an object that
+/// is already in the table fails with `VELLO_ERROR_INVALID_HANDLE`.
+#[no_mangle]
+pub extern "C" fn vello_handle_table_insert(
+    kind: VelloHandleKind,
+    handle: *mut c_void,
+    out_id: *mut u64,
+) -> c_int {
+    if handle.is_null() || out_id.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    if !kind.is_valid(handle) {
+        set_last_error(format!("Handle is not a {:?}", kind));
+        return VELLO_ERROR_INVALID_HANDLE;
+    }
+
+    let Some(id) = table().insert(handle, kind) else {
+        set_last_error("Handle is already in the handle table");
+        return VELLO_ERROR_INVALID_HANDLE;
+    };
+    unsafe { *out_id = id };
+    VELLO_OK
+}
+
+fn resolve(table: &mut HandleTable, kind: VelloHandleKind, id: u64) -> Option<&mut Entry> {
+    match table.get(id) {
+        Some(entry) if entry.kind == kind => Some(entry),
+        Some(entry) => {
+            set_last_error(format!("Handle {id:#x} is a {:?}, not a {:?}", entry.kind, kind));
+            None
+        }
+        None => {
+            set_last_error(format!("Stale or unknown handle {id:#x}"));
+            None
+        }
+    }
+}
+
+/// Resolve an ID to the object pointer, or null if the ID is stale or of another kind
+///
+/// The pointer is only valid until the ID is removed, which another thread may
+/// do at any time; hosts sharing IDs between threads use
+/// `vello_handle_table_acquire` instead.
+#[no_mangle]
+pub extern "C" fn vello_handle_table_get(kind: VelloHandleKind, id: u64) -> *mut c_void {
+    match resolve(&mut table(), kind, id) {
+        Some(entry) => entry.ptr as *mut c_void,
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Resolve an ID to the object pointer and keep the object alive until
+/// `vello_handle_table_release`, or return null if the ID is stale or of
+/// another kind
+///
+/// Removing a pinned ID makes it stale right away but frees the object only
+/// when the last pin is released.
+#[no_mangle]
+pub extern "C" fn vello_handle_table_acquire(kind: VelloHandleKind, id: u64) -> *mut c_void {
+    match resolve(&mut table(), kind, id) {
+        Some(entry) => {
+            entry.pins += 1;
+            entry.ptr as *mut c_void
+        }
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Release a pointer obtained with `vello_handle_table_acquire`
+#[no_mangle]
+pub extern "C" fn vello_handle_table_release(id: u64) -> c_int {
+    let entry = match table().release(id) {
+        Ok(entry) => entry,
+        Err(()) => {
+            set_last_error(format!("Handle {id:#x} is not acquired"));
+            return VELLO_ERROR_INVALID_HANDLE;
+        }
+    };
+    // Freed outside the table lock, a context may own other resources
+    if let Some(entry) = entry {
+        entry.kind.free(entry.ptr as *mut c_void);
+    }
+    VELLO_OK
+}
+
+/// Remove an object from the handle table and free it
+///
+/// Removing a stale ID fails with `VELLO_ERROR_INVALID_HANDLE`, so double frees
+/// are detected rather than corrupting memory. An object still pinned by
+/// `vello_handle_table_acquire` is freed by its last release.
+#[no_mangle]
+pub extern "C" fn vello_handle_table_remove(id: u64) -> c_int {
+    let entry = match table().remove(id) {
+        Ok(entry) => entry,
+        Err(()) => {
+            set_last_error(format!("Stale or unknown handle {id:#x}"));
+            return VELLO_ERROR_INVALID_HANDLE;
+        }
+    };
+    // Freed outside the table lock, a context may own other resources
+    if let Some(entry) = entry {
+        entry.kind.free(entry.ptr as *mut c_void);
+    }
+    VELLO_OK
+}
+
+/// Number of objects currently held by the handle table
+#[no_mangle]
+pub extern "C" fn vello_handle_table_len() -> usize {
+    table()
+        .slots
+        .iter()
+        .filter(|slot| slot.entry.is_some_and(|entry| !entry.removed))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::vello_bezpath_new;
+    use crate::pixmap::{vello_pixmap_new, vello_pixmap_width};
+
+    #[test]
+    fn rejects_wrong_kind_and_duplicates() {
+        let path = vello_bezpath_new() as *mut c_void;
+        let mut id = 0u64;
+        assert_eq!(
+            vello_handle_table_insert(VelloHandleKind::Recording, path, &mut id),
+            VELLO_ERROR_INVALID_HANDLE
+        );
+        assert_eq!(vello_handle_table_insert(VelloHandleKind::BezPath, path, &mut id), VELLO_OK);
+        let mut second = 0u64;
+        assert_eq!(
+            vello_handle_table_insert(VelloHandleKind::BezPath, path, &mut second),
+            VELLO_ERROR_INVALID_HANDLE
+        );
+        assert!(vello_handle_table_get(VelloHandleKind::Pixmap, id).is_null());
+        assert_eq!(vello_handle_table_get(VelloHandleKind::BezPath, id), path);
+
+        assert_eq!(vello_handle_table_remove(id), VELLO_OK);
+        assert_eq!(vello_handle_table_remove(id), VELLO_ERROR_INVALID_HANDLE);
+    }
+
+    #[test]
+    fn pinned_object_outlives_remove() {
+        let pixmap = vello_pixmap_new(3, 2) as *mut c_void;
+        let mut id = 0u64;
+        assert_eq!(vello_handle_table_insert(VelloHandleKind::Pixmap, pixmap, &mut id), VELLO_OK);
+
+        let pinned = vello_handle_table_acquire(VelloHandleKind::Pixmap, id);
+        assert_eq!(pinned, pixmap);
+        assert_eq!(vello_handle_table_remove(id), VELLO_OK);
+
+        // The ID is stale, but the pinned object is still usable
+        assert!(vello_handle_table_get(VelloHandleKind::Pixmap, id).is_null());
+        assert_eq!(vello_pixmap_width(pinned as *const _), 3);
+
+        assert_eq!(vello_handle_table_release(id), VELLO_OK);
+        assert_eq!(vello_handle_table_release(id), VELLO_ERROR_INVALID_HANDLE);
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Picking of draw calls by pixel
+//!
+//! With hit testing enabled, fills and strokes issued while a draw ID is set
+//! are recorded together with their transform, the clip paths of the layers
+//! open at the time and the aliasing threshold. A hit test rasterizes each
+//! candidate into a single pixel with the same rasterizer, so the result
+//! matches rendered coverage exactly, including anti-aliased edges and clips.
+//! Candidates whose conservative bounds miss the pixel are skipped, and the
+//! rest share one scratch context per worker.
+
+use std::os::raw::c_int;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use vello_cpu::kurbo::{self, Affine, BezPath, Rect, Shape, Stroke, StrokeOpts};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::Fill;
+use vello_cpu::{Pixmap, RenderContext};
+
+use crate::context::RenderContextHandle;
+use crate::error::set_last_error;
+use crate::ffi_catch;
+use crate::handle::TypedHandle;
+use crate::recording_chunks::draw_bounds;
+use crate::types::*;
+
+/// Shape drawn by a recorded call
+pub(crate) enum HitGeometry {
+    Fill(BezPath, Fill),
+    Stroke(BezPath, Stroke),
+}
+
+/// Clip path of an open layer and the transform it was pushed with
+type Clip = Arc<(BezPath, Affine)>;
+
+/// Draw call tagged with an ID
+pub(crate) struct HitRecord {
+    id: u64,
+    geometry: HitGeometry,
+    transform: Affine,
+    clips: Vec<Clip>,
+    aliasing_threshold: Option<u8>,
+    /// Conservative device-space bounds of any pixel the draw can cover
+    cull: Rect,
+}
+
+/// Scratch context and pixel candidates are rasterized into
+struct Probe {
+    ctx: RenderContext,
+    pixel: Pixmap,
+}
+
+impl Probe {
+    fn new() -> Self {
+        Self {
+            ctx: RenderContext::new(1, 1),
+            pixel: Pixmap::new(1, 1),
+        }
+    }
+}
+
+impl HitRecord {
+    /// Device-space bounds of the draw after its clips
+    fn device_bounds(&self) -> Rect {
+        let bounds = match &self.geometry {
+            HitGeometry::Fill(path, _) => (self.transform * path.clone()).bounding_box(),
+            HitGeometry::Stroke(path, stroke) => {
+                let outline = kurbo::stroke(path.iter(), stroke, &StrokeOpts::default(), 0.1);
+                (self.transform * outline).bounding_box()
+            }
+        };
+        self.clips.iter().fold(bounds, |bounds, clip| {
+            bounds.intersect((clip.1 * clip.0.clone()).bounding_box())
+        })
+    }
+
+    /// Whether the draw covers pixel (x, y) at all, rasterizing it with `probe`
+    /// unless its bounds already rule the pixel out
+    fn covers(&self, probe: &mut Probe, x: u16, y: u16) -> bool {
+        let (px, py) = (x as f64, y as f64);
+        let cull = &self.cull;
+        if !(cull.x0 < px + 1.0 && cull.x1 > px && cull.y0 < py + 1.0 && cull.y1 > py) {
+            return false;
+        }
+
+        let shift = Affine::translate((-px, -py));
+        let ctx = &mut probe.ctx;
+        ctx.reset();
+        ctx.set_aliasing_threshold(self.aliasing_threshold);
+        for clip in &self.clips {
+            ctx.set_transform(shift * clip.1);
+            ctx.push_clip_layer(&clip.0);
+        }
+        ctx.set_transform(shift * self.transform);
+        ctx.set_paint(AlphaColor::<Srgb>::BLACK);
+        match &self.geometry {
+            HitGeometry::Fill(path, fill_rule) => {
+                ctx.set_fill_rule(*fill_rule);
+                ctx.fill_path(path);
+            }
+            HitGeometry::Stroke(path, stroke) => {
+                ctx.set_stroke(stroke.clone());
+                ctx.stroke_path(path);
+            }
+        }
+        for _ in &self.clips {
+            ctx.pop_layer();
+        }
+        ctx.flush();
+
+        ctx.render_to_pixmap(&mut probe.pixel);
+        probe.pixel.data()[0].a > 0
+    }
+}
+
+/// Hit testing state of a render context
+#[derive(Default)]
+pub(crate) struct HitTestState {
+    enabled: bool,
+    /// ID attached to subsequent draws, 0 for none
+    draw_id: u64,
+    /// Clip of every open layer, `None` for layers without one
+    layers: Vec<Option<Clip>>,
+    /// Recorded draws in painting order
+    records: Vec<HitRecord>,
+}
+
+impl HitTestState {
+    /// Whether the next draw is recorded
+    pub(crate) fn recording(&self) -> bool {
+        self.enabled && self.draw_id != 0
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        geometry: HitGeometry,
+        transform: Affine,
+        aliasing_threshold: Option<u8>,
+    ) {
+        if !self.recording() {
+            return;
+        }
+        let clips: Vec<Clip> = self.layers.iter().flatten().cloned().collect();
+        let bounds = match &geometry {
+            HitGeometry::Fill(path, _) => draw_bounds(path.bounding_box(), transform, None),
+            HitGeometry::Stroke(path, stroke) => {
+                draw_bounds(path.bounding_box(), transform, Some(stroke))
+            }
+        };
+        let bounds = clips.iter().fold(bounds, |bounds, clip| {
+            bounds.intersect(clip.1.transform_rect_bbox(clip.0.bounding_box()))
+        });
+        self.records.push(HitRecord {
+            id: self.draw_id,
+            geometry,
+            transform,
+            clips,
+            aliasing_threshold,
+            // Anti-aliasing and hairlines reach up to a pixel further
+            cull: bounds.inflate(1.0, 1.0),
+        });
+    }
+
+    pub(crate) fn push_layer(&mut self, clip: Option<&BezPath>, transform: Affine) {
+        let clip = clip
+            .filter(|_| self.enabled)
+            .map(|path| Arc::new((path.clone(), transform)));
+        self.layers.push(clip);
+    }
+
+    pub(crate) fn pop_layer(&mut self) {
+        self.layers.pop();
+    }
+
+    /// Forget recorded draws and open layers, keeping the mode and draw ID
+    pub(crate) fn clear(&mut self) {
+        self.layers.clear();
+        self.records.clear();
+    }
+}
+
+/// Geometry of a rectangle draw
+pub(crate) fn rect_path(rect: &Rect) -> BezPath {
+    rect.to_path(0.1)
+}
+
+/// Enable or disable recording of draw IDs for hit testing
+///
+/// Disabling the mode discards recorded draws. Draws are only recorded while a
+/// nonzero ID is set with `vello_render_context_set_draw_id`.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_hit_testing(
+    ctx: *mut VelloRenderContext,
+    enabled: bool,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.hit_test.enabled = enabled;
+        if !enabled {
+            ctx.hit_test.records.clear();
+        }
+        VELLO_OK
+    })
+}
+
+/// Attach `id` to subsequent fills and strokes of paths and rectangles (0 detaches)
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_draw_id(ctx: *mut VelloRenderContext, id: u64) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        ctx.hit_test.draw_id = id;
+        VELLO_OK
+    })
+}
+
+/// Find the draws covering pixel (x, y), topmost first
+///
+/// Writes up to `max_ids` IDs to `out_ids` and the total number of covering
+/// draws, which may be larger, to `out_count`. A draw covering the pixel only
+/// partially through anti-aliasing counts as a hit, and an ID appears once per
+/// covering draw. Text, images and mask layers are not taken into account.
+/// Pixels outside the canvas have no hits.
+#[no_mangle]
+pub extern "C" fn vello_render_context_hit_test(
+    ctx: *const VelloRenderContext,
+    x: u16,
+    y: u16,
+    out_ids: *mut u64,
+    max_ids: usize,
+    out_count: *mut usize,
+) -> c_int {
+    if ctx.is_null() || out_count.is_null() || (out_ids.is_null() && max_ids > 0) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        if x >= ctx.width() || y >= ctx.height() {
+            unsafe { *out_count = 0 };
+            return VELLO_OK;
+        }
+
+        // Candidates are rasterized independently, on the pool if there is one
+        let records = &ctx.hit_test.records;
+        let hits: Vec<u64> = match ctx.thread_pool() {
+            Some(pool) => pool.install(|| {
+                let hits = records.par_iter().rev().map_init(Probe::new, |probe, record| {
+                    record.covers(probe, x, y).then_some(record.id)
+                });
+                hits.flatten().collect()
+            }),
+            None => {
+                let mut probe = Probe::new();
+                let hits = records.iter().rev().filter(|record| record.covers(&mut probe, x, y));
+                hits.map(|record| record.id).collect()
+            }
+        };
+        for (i, id) in hits.iter().take(max_ids).enumerate() {
+            unsafe { *out_ids.add(i) = *id };
+        }
+        unsafe { *out_count = hits.len() };
+        VELLO_OK
+    })
+}
+
+/// Get the device-space bounds of the draws tagged with `id`
+///
+/// The bounds cover every draw recorded with the ID after its transform and
+/// clips, rounded out to whole pixels and limited to the canvas. Draws clipped
+/// away entirely yield an empty rectangle. Fails with
+/// `VELLO_ERROR_INVALID_PARAMETER` if no draw was recorded with the ID.
+#[no_mangle]
+pub extern "C" fn vello_render_context_command_bounds(
+    ctx: *const VelloRenderContext,
+    id: u64,
+    out_rect: *mut VelloRect,
+) -> c_int {
+    if ctx.is_null() || out_rect.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        let mut tagged = ctx.hit_test.records.iter().filter(|record| record.id == id);
+        let Some(first) = tagged.next() else {
+            set_last_error(format!("No draw recorded with ID {id}"));
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+
+        // Clipped-away draws have negative or zero-area bounds and add nothing
+        let bounds = std::iter::once(first)
+            .chain(tagged)
+            .map(HitRecord::device_bounds)
+            .filter(|bounds| bounds.width() > 0.0 && bounds.height() > 0.0)
+            .map(|bounds| bounds.expand())
+            .reduce(|a, b| a.union(b));
+        let canvas = Rect::new(0.0, 0.0, ctx.width() as f64, ctx.height() as f64);
+        let bounds = bounds
+            .map(|bounds| bounds.intersect(canvas))
+            .filter(|bounds| bounds.width() > 0.0 && bounds.height() > 0.0)
+            .unwrap_or(Rect::ZERO);
+        unsafe {
+            *out_rect = VelloRect {
+                x0: bounds.x0,
+                y0: bounds.y0,
+                x1: bounds.x1,
+                y1: bounds.y1,
+            };
+        }
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_adopt_thread_pool, vello_render_context_free,
+        vello_render_context_new,
+    };
+    use crate::test_util::{fill, worker_settings};
+    use crate::thread_pool::{vello_thread_pool_free, vello_thread_pool_new};
+
+    #[test]
+    fn hit_tests_agree_with_and_without_a_pool() {
+        let pool = vello_thread_pool_new(&worker_settings(2));
+        let single = vello_render_context_new(32, 24);
+        let pooled = vello_render_context_new(32, 24);
+        assert_eq!(vello_render_context_adopt_thread_pool(pooled, pool), VELLO_OK);
+        vello_thread_pool_free(pool);
+
+        for ctx in [single, pooled] {
+            assert_eq!(vello_render_context_set_hit_testing(ctx, true), VELLO_OK);
+            assert_eq!(vello_render_context_set_draw_id(ctx, 1), VELLO_OK);
+            fill(ctx, [255, 0, 0, 255], 2.5, 2.5, 20.0, 12.0);
+            assert_eq!(vello_render_context_set_draw_id(ctx, 2), VELLO_OK);
+            fill(ctx, [0, 0, 255, 255], 10.0, 8.0, 30.0, 22.0);
+            assert_eq!(vello_render_context_set_draw_id(ctx, 3), VELLO_OK);
+            // Only the last query reaches it; its bounds cull it for the others
+            fill(ctx, [0, 255, 0, 255], 28.0, 0.0, 32.0, 2.0);
+        }
+
+        let hits = |ctx, x, y| {
+            let mut ids = [0u64; 4];
+            let mut count = 0;
+            let result = vello_render_context_hit_test(ctx, x, y, ids.as_mut_ptr(), 4, &mut count);
+            assert_eq!(result, VELLO_OK);
+            ids[..count].to_vec()
+        };
+        // Partially covered edge pixels count as hits
+        for (x, y, expected) in [
+            (2, 2, vec![1]),
+            (12, 10, vec![2, 1]),
+            (25, 20, vec![2]),
+            (1, 20, vec![]),
+            (29, 1, vec![3]),
+        ] {
+            assert_eq!(hits(single, x, y), expected, "({x}, {y})");
+            assert_eq!(hits(pooled, x, y), expected, "({x}, {y})");
+        }
+        vello_render_context_free(single);
+        vello_render_context_free(pooled);
+    }
+}
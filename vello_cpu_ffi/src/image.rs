@@ -3,12 +3,15 @@
 
 //! FFI bindings for Image
 
+use crate::context::RenderContextHandle;
 use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
 use crate::{ffi_catch, ffi_catch_ptr};
-use crate::types::{VelloExtend, VelloImageQuality, VelloPixmap, VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_NULL_POINTER, VELLO_OK};
+use crate::pixmap::PixmapHandle;
+use crate::types::{VelloColorSpace, VelloExtend, VelloImageQuality, VelloPixmap, VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_NULL_POINTER, VELLO_OK};
 use std::os::raw::c_int;
-use std::sync::Arc;
-use vello_cpu::{Pixmap, RenderContext};
+use std::sync::{Arc, Mutex};
+use vello_cpu::Pixmap;
 use vello_cpu::peniko::{self, Extend, ImageQuality};
 use vello_common::paint::{Image, ImageSource};
 
@@ -17,7 +20,62 @@ pub struct VelloImage {
     _private: [u8; 0],
 }
 
+/// Object behind a `VelloImage` pointer
+#[repr(C)]
+pub(crate) struct ImageHandle {
+    header: HandleHeader,
+    pub(crate) image: Image,
+    color_space: VelloColorSpace,
+    /// Most recent conversion of the image into a render target color space
+    converted: Mutex<Option<(VelloColorSpace, Image)>>,
+}
+
+impl TypedHandle for ImageHandle {
+    const TAG: u32 = handle::tag(b"VIMG");
+    const NAME: &'static str = "image";
+}
+
+impl ImageHandle {
+    /// Size in pixels, for images backed by a pixmap
+    pub(crate) fn size(&self) -> Option<(u16, u16)> {
+        if let ImageSource::Pixmap(pixmap) = &self.image.image {
+            Some((pixmap.width(), pixmap.height()))
+        } else {
+            None
+        }
+    }
+
+    /// The image with its pixels in `color_space`
+    pub(crate) fn in_color_space(&self, color_space: VelloColorSpace) -> Image {
+        if color_space == self.color_space {
+            return self.image.clone();
+        }
+
+        let mut converted = self.converted.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((space, image)) = converted.as_ref() {
+            if *space == color_space {
+                return image.clone();
+            }
+        }
+
+        crate::error::log(crate::error::VelloLogLevel::Debug, || {
+            format!("Converting image from {:?} to {:?}", self.color_space, color_space)
+        });
+        let mut image = self.image.clone();
+        if let ImageSource::Pixmap(pixmap) = &image.image {
+            let mut pixmap = Pixmap::clone(pixmap);
+            crate::color_space::convert(pixmap.data_mut(), self.color_space, color_space);
+            image.image = ImageSource::Pixmap(Arc::new(pixmap));
+        }
+        *converted = Some((color_space, image.clone()));
+        image
+    }
+}
+
 /// Create an image from a pixmap
+///
+/// The image keeps the pixmap's color space tag and is converted to the
+/// render target's color space when used as a paint.
 #[no_mangle]
 pub extern "C" fn vello_image_new_from_pixmap(
     pixmap: *const VelloPixmap,
@@ -32,7 +90,7 @@ pub extern "C" fn vello_image_new_from_pixmap(
     }
 
     ffi_catch_ptr!({
-        let pixmap = unsafe { &*(pixmap as *const Pixmap) };
+        let pixmap = PixmapHandle::from_ptr(pixmap);
 
         let x_ext = match x_extend {
             VelloExtend::Pad => Extend::Pad,
@@ -53,7 +111,7 @@ pub extern "C" fn vello_image_new_from_pixmap(
         };
 
         let image = Image {
-            image: ImageSource::Pixmap(Arc::new(pixmap.clone())),
+            image: ImageSource::Pixmap(Arc::new(pixmap.pixmap.clone())),
             sampler: peniko::ImageSampler {
                 x_extend: x_ext,
                 y_extend: y_ext,
@@ -62,17 +120,21 @@ pub extern "C" fn vello_image_new_from_pixmap(
             },
         };
 
-        Box::into_raw(Box::new(image)) as *mut VelloImage
+        let image = ImageHandle {
+            header: HandleHeader::new::<ImageHandle>(),
+            image,
+            color_space: pixmap.color_space,
+            converted: Mutex::new(None),
+        };
+        handle::into_raw(image) as *mut VelloImage
     })
 }
 
 /// Free an image
 #[no_mangle]
 pub extern "C" fn vello_image_free(image: *mut VelloImage) {
-    if !image.is_null() {
-        unsafe {
-            let _ = Box::from_raw(image as *mut Image);
-        }
+    if !image.is_null() && ImageHandle::check(image) {
+        handle::free::<ImageHandle, _>(image);
     }
 }
 
@@ -88,15 +150,17 @@ pub extern "C" fn vello_render_context_set_paint_image(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut RenderContext) };
-        let image = unsafe { &*(image as *const Image) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let image = ImageHandle::from_ptr(image);
 
-        if (image.sampler.alpha - 1.0).abs() > f32::EPSILON {
+        if (image.image.sampler.alpha - 1.0).abs() > f32::EPSILON {
             set_last_error("Image opacity is not supported yet");
             return VELLO_ERROR_INVALID_PARAMETER;
         }
 
-        ctx.set_paint(image.clone());
+        // Images tagged with another color space are converted to the target's
+        let paint = image.in_color_space(ctx.color_space());
+        ctx.set_paint(paint);
         VELLO_OK
     })
 }
@@ -0,0 +1,193 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Retained layers
+//!
+//! A layer cache owns a render context for drawing static content and the
+//! pixels it last rendered. Hosts draw the content once between
+//! `vello_layer_cache_begin` and `vello_layer_cache_end`, then composite the
+//! cached pixels every frame until they invalidate the cache.
+
+use std::os::raw::c_int;
+use std::sync::Arc;
+
+use vello_common::paint::{Image, ImageSource};
+use vello_cpu::kurbo::{Affine, Rect};
+use vello_cpu::peniko::{self, Extend, ImageQuality};
+use vello_cpu::{Pixmap, RenderContext};
+
+use crate::context::RenderContextHandle;
+use crate::error::set_last_error;
+use crate::handle::{self, CallScope, HandleHeader, TypedHandle};
+use crate::types::*;
+use crate::{ffi_catch, ffi_catch_ptr};
+
+#[repr(C)]
+pub struct VelloLayerCache {
+    _private: [u8; 0],
+}
+
+/// Object behind a `VelloLayerCache` pointer
+#[repr(C)]
+pub(crate) struct LayerCacheHandle {
+    header: HandleHeader,
+    /// Context receiving the layer contents; boxed so its pointer stays stable
+    ctx: Box<RenderContextHandle>,
+    /// Pixels of the last completed render, until invalidated
+    contents: Option<Arc<Pixmap>>,
+}
+
+impl TypedHandle for LayerCacheHandle {
+    const TAG: u32 = handle::tag(b"VLYC");
+    const NAME: &'static str = "layer cache";
+}
+
+/// Create a retained layer of the given size
+#[no_mangle]
+pub extern "C" fn vello_layer_cache_new(width: u16, height: u16) -> *mut VelloLayerCache {
+    ffi_catch_ptr!({
+        let cache = LayerCacheHandle {
+            header: HandleHeader::new::<LayerCacheHandle>(),
+            ctx: Box::new(RenderContextHandle::new(RenderContext::new(width, height))),
+            contents: None,
+        };
+        handle::into_raw(cache) as *mut VelloLayerCache
+    })
+}
+
+/// Free a retained layer, including the context returned by `vello_layer_cache_begin`
+#[no_mangle]
+pub extern "C" fn vello_layer_cache_free(cache: *mut VelloLayerCache) {
+    if !cache.is_null() && LayerCacheHandle::check(cache) {
+        handle::free::<LayerCacheHandle, _>(cache);
+    }
+}
+
+/// Start drawing the layer contents
+///
+/// Resets the layer's context and returns it for regular drawing calls. The
+/// context is owned by the cache and must not be freed. The cached pixels
+/// stay valid until `vello_layer_cache_end` replaces them.
+#[no_mangle]
+pub extern "C" fn vello_layer_cache_begin(cache: *mut VelloLayerCache) -> *mut VelloRenderContext {
+    if cache.is_null() {
+        set_last_error("Null layer cache pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let cache = LayerCacheHandle::from_ptr_mut(cache);
+        cache.ctx.reset();
+        &mut *cache.ctx as *mut RenderContextHandle as *mut VelloRenderContext
+    })
+}
+
+/// Render the contents drawn since `vello_layer_cache_begin` into the cache
+///
+/// All layers opened on the layer's context must be closed.
+#[no_mangle]
+pub extern "C" fn vello_layer_cache_end(cache: *mut VelloLayerCache) -> c_int {
+    if cache.is_null() {
+        set_last_error("Null layer cache pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let cache = LayerCacheHandle::from_ptr_mut(cache);
+        let ctx = &mut *cache.ctx;
+        if ctx.layer_depth() > 0 {
+            set_last_error("Layer cache contents have unbalanced layers");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        let mut contents = Pixmap::new(ctx.width(), ctx.height());
+        ctx.flush();
+        ctx.render_to_pixmap(&mut contents);
+        cache.contents = Some(Arc::new(contents));
+        VELLO_OK
+    })
+}
+
+/// Discard the cached pixels so the layer is redrawn before its next use
+#[no_mangle]
+pub extern "C" fn vello_layer_cache_invalidate(cache: *mut VelloLayerCache) -> c_int {
+    if cache.is_null() {
+        set_last_error("Null layer cache pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let cache = LayerCacheHandle::from_ptr_mut(cache);
+        cache.contents = None;
+        VELLO_OK
+    })
+}
+
+/// Check whether a retained layer holds rendered pixels
+#[no_mangle]
+pub extern "C" fn vello_layer_cache_is_valid(cache: *const VelloLayerCache) -> bool {
+    if cache.is_null() || !LayerCacheHandle::check(cache) {
+        return false;
+    }
+    let _scope = CallScope::enter();
+    let cache = LayerCacheHandle::from_ptr(cache);
+    cache.contents.is_some()
+}
+
+/// Composite a retained layer with its top-left corner at (x, y)
+///
+/// The layer is placed in user space, so the current transform applies.
+/// Paint state is left untouched. Fails with `VELLO_ERROR_INVALID_PARAMETER`
+/// if the layer has not been rendered or was invalidated.
+#[no_mangle]
+pub extern "C" fn vello_render_context_draw_cached_layer(
+    ctx: *mut VelloRenderContext,
+    cache: *const VelloLayerCache,
+    x: f64,
+    y: f64,
+) -> c_int {
+    if ctx.is_null() || cache.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let cache = LayerCacheHandle::from_ptr(cache);
+        let Some(contents) = cache.contents.clone() else {
+            set_last_error("Layer cache has not been rendered");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+
+        // Pixel-aligned placements are copied exactly; anything else is filtered
+        let [a, b, c, d, e, f] = (*ctx.transform() * Affine::translate((x, y))).as_coeffs();
+        let aligned = [a, b, c, d] == [1.0, 0.0, 0.0, 1.0] && e.fract() == 0.0 && f.fract() == 0.0;
+        let rect = Rect::new(
+            x,
+            y,
+            x + contents.width() as f64,
+            y + contents.height() as f64,
+        );
+        let image = Image {
+            image: ImageSource::Pixmap(contents),
+            sampler: peniko::ImageSampler {
+                x_extend: Extend::Pad,
+                y_extend: Extend::Pad,
+                quality: if aligned {
+                    ImageQuality::Low
+                } else {
+                    ImageQuality::Medium
+                },
+                alpha: 1.0,
+            },
+        };
+
+        let saved_paint = ctx.paint().clone();
+        let saved_paint_transform = *ctx.paint_transform();
+        ctx.set_paint(image);
+        ctx.set_paint_transform(Affine::translate((x, y)));
+        ctx.fill_rect(&rect);
+        ctx.set_paint(saved_paint);
+        ctx.set_paint_transform(saved_paint_transform);
+        VELLO_OK
+    })
+}
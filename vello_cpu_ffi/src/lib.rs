@@ -15,53 +15,165 @@
 //! - Multithreading support
 //! - Comprehensive error handling
 //! - PNG support (optional, via `png` feature)
+//! - WebP support (optional, via `webp` feature)
+//! - Text shaping (optional, via `shaping` feature)
+//! - System font discovery (optional, via `system-fonts` feature)
+//! - SVG export of recordings (optional, via `svg-export` feature)
+//! - SVG document rendering (optional, via `svg` feature)
+//! - Skia C API compatibility layer (optional, via `skia-compat` feature)
 //!
 //! ## Safety
 //!
 //! All functions perform null pointer checks and use panic catching to prevent
-//! unwinding across FFI boundaries. Error messages are stored in thread-local
-//! storage and can be retrieved via `vello_get_last_error()`.
+//! unwinding across FFI boundaries. Every handle is looked up in a registry of
+//! live objects before use, so passing the wrong kind of handle or one that was
+//! already freed fails with `VELLO_ERROR_INVALID_HANDLE`. Error messages are
+//! stored in thread-local storage and can be retrieved via
+//! `vello_get_last_error()`.
 
 #![allow(clippy::missing_safety_doc)]
 
 pub mod types;
 pub mod error;
+pub mod handle;
 pub mod utils;
+pub mod enum_strings;
 pub mod context;
+pub mod command;
 pub mod pixmap;
+pub mod pixmap_pool;
 pub mod path;
 pub mod text;
+pub mod shaping;
+pub mod text_layout;
+pub mod font_registry;
+pub mod glyph_cache;
+#[cfg(feature = "system-fonts")]
+pub mod system_fonts;
 pub mod mask;
 pub mod image;
+pub mod layer_cache;
+pub mod hit_test;
+pub mod canvas;
+#[cfg(feature = "svg")]
+pub mod svg;
+#[cfg(feature = "skia-compat")]
+pub mod skia;
 pub mod recording;
+pub mod strips;
+pub mod filter;
+pub mod thread_pool;
+pub mod api;
 pub mod marshaling_tests;
 
+mod blur;
+mod color_space;
+mod handle_table;
+mod pixel;
+mod recording_chunks;
+mod recording_format;
+mod resample;
+mod scene;
+mod snap;
+#[cfg(feature = "svg-export")]
+mod svg_export;
+#[cfg(test)]
+mod test_util;
+
 // Re-export main types for convenience
 pub use types::*;
 
 // Re-export error handling
-pub use error::{vello_clear_last_error, vello_get_last_error};
+pub use error::{
+    vello_clear_last_error, vello_get_last_error, vello_get_last_error_msg, vello_set_log_callback,
+    vello_set_panic_policy, VelloLogCallback, VelloLogLevel, VelloPanicCallback, VelloPanicPolicy,
+};
+
+// Re-export handle functions
+pub use handle::{
+    vello_debug_dump_live_handles, vello_debug_set_handle_tracking, vello_handle_generation,
+    VelloLiveHandleCallback,
+};
+pub use handle_table::{
+    vello_handle_table_acquire, vello_handle_table_get, vello_handle_table_insert,
+    vello_handle_table_len, vello_handle_table_release, vello_handle_table_remove,
+    VelloHandleKind,
+};
 
 // Re-export utility functions
-pub use utils::{vello_simd_detect, vello_version};
+pub use utils::*;
+
+// Re-export enum string conversions
+pub use enum_strings::*;
 
 // Re-export context functions
 pub use context::*;
 
+// Re-export batched commands
+pub use command::*;
+
 // Re-export pixmap functions
 pub use pixmap::*;
 
+// Re-export pixmap pool functions
+pub use pixmap_pool::*;
+
 // Re-export path functions
 pub use path::*;
 
 // Re-export text functions
 pub use text::*;
 
+// Re-export shaping functions
+pub use shaping::*;
+
+// Re-export system font functions
+#[cfg(feature = "system-fonts")]
+pub use system_fonts::*;
+
+// Re-export text layout functions
+pub use text_layout::*;
+
+// Re-export font registry functions
+pub use font_registry::*;
+
+// Re-export glyph cache types
+pub use glyph_cache::VelloGlyphCacheStats;
+
 // Re-export mask functions
 pub use mask::*;
 
 // Re-export image functions
 pub use image::*;
 
+// Re-export retained layers
+pub use layer_cache::*;
+
+// Re-export hit testing functions
+pub use hit_test::*;
+
+// Re-export canvas functions
+pub use canvas::*;
+
+// Re-export SVG document functions
+#[cfg(feature = "svg")]
+pub use svg::*;
+
+// Re-export Skia compatibility functions
+#[cfg(feature = "skia-compat")]
+pub use skia::*;
+
 // Re-export recording functions
 pub use recording::*;
+
+// Re-export strip access
+pub use strips::*;
+
+// Re-export filter functions
+pub use filter::*;
+
+// Re-export thread pool functions
+pub use thread_pool::*;
+
+// Re-export function table
+pub use api::*;
@@ -3,13 +3,72 @@
 
 //! FFI bindings for Mask
 
+use crate::context::{copy_state, RenderContextHandle};
 use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::path::PathHandle;
+use crate::pixmap::PixmapHandle;
+use crate::scene::Scene;
 use crate::{ffi_catch, ffi_catch_ptr};
-use crate::types::{VelloMask, VelloPixmap, VELLO_ERROR_NULL_POINTER, VELLO_OK};
+use crate::types::{
+    VelloAlphaStop, VelloBezPath, VelloExtend, VelloMask, VelloPixmap, VelloRect,
+    VelloResampleFilter,
+    VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_NULL_POINTER, VELLO_OK,
+};
 use std::os::raw::c_int;
 use vello_cpu::Pixmap;
+use std::ops::Deref;
+use std::sync::OnceLock;
 use vello_cpu::Mask;
 
+/// Object behind a `VelloMask` pointer
+///
+/// Keeps a lazily built copy of the coverage values so they can be handed out
+/// by pointer.
+#[repr(C)]
+pub struct MaskHandle {
+    header: HandleHeader,
+    pub(crate) mask: Mask,
+    coverage: OnceLock<Box<[u8]>>,
+}
+
+impl MaskHandle {
+    pub(crate) fn new(mask: Mask) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            mask,
+            coverage: OnceLock::new(),
+        }
+    }
+
+    /// Coverage values in row-major order
+    pub(crate) fn coverage(&self) -> &[u8] {
+        self.coverage.get_or_init(|| {
+            let (width, height) = (self.mask.width(), self.mask.height());
+            let mut data = Vec::with_capacity(width as usize * height as usize);
+            for y in 0..height {
+                for x in 0..width {
+                    data.push(self.mask.sample(x, y));
+                }
+            }
+            data.into_boxed_slice()
+        })
+    }
+}
+
+impl TypedHandle for MaskHandle {
+    const TAG: u32 = handle::tag(b"VMSK");
+    const NAME: &'static str = "mask";
+}
+
+impl Deref for MaskHandle {
+    type Target = Mask;
+
+    fn deref(&self) -> &Mask {
+        &self.mask
+    }
+}
+
 /// Create a new alpha mask from a pixmap
 #[no_mangle]
 pub extern "C" fn vello_mask_new_alpha(pixmap: *const VelloPixmap) -> *mut VelloMask {
@@ -19,9 +78,9 @@ pub extern "C" fn vello_mask_new_alpha(pixmap: *const VelloPixmap) -> *mut Vello
     }
 
     ffi_catch_ptr!({
-        let pixmap = unsafe { &*(pixmap as *const Pixmap) };
+        let pixmap = PixmapHandle::from_ptr(pixmap);
         let mask = Mask::new_alpha(pixmap);
-        Box::into_raw(Box::new(mask)) as *mut VelloMask
+        handle::into_raw(MaskHandle::new(mask)) as *mut VelloMask
     })
 }
 
@@ -34,44 +93,267 @@ pub extern "C" fn vello_mask_new_luminance(pixmap: *const VelloPixmap) -> *mut V
     }
 
     ffi_catch_ptr!({
-        let pixmap = unsafe { &*(pixmap as *const Pixmap) };
+        let pixmap = PixmapHandle::from_ptr(pixmap);
         let mask = Mask::new_luminance(pixmap);
-        Box::into_raw(Box::new(mask)) as *mut VelloMask
+        handle::into_raw(MaskHandle::new(mask)) as *mut VelloMask
     })
 }
 
+/// Create a mask from the alpha channel of a pixmap
+///
+/// Equivalent to `vello_mask_new_alpha`: coverage is the pixel alpha, unlike
+/// `vello_mask_new_luminance` which derives it from the color.
+#[no_mangle]
+pub extern "C" fn vello_mask_new_from_pixmap_alpha(pixmap: *const VelloPixmap) -> *mut VelloMask {
+    vello_mask_new_alpha(pixmap)
+}
+
 /// Free a mask
 #[no_mangle]
 pub extern "C" fn vello_mask_free(mask: *mut VelloMask) {
-    if !mask.is_null() {
-        unsafe {
-            let _ = Box::from_raw(mask as *mut Mask);
-        }
+    if !mask.is_null() && MaskHandle::check(mask) {
+        handle::free::<MaskHandle, _>(mask);
     }
 }
 
 /// Get the width of a mask
 #[no_mangle]
 pub extern "C" fn vello_mask_get_width(mask: *const VelloMask) -> u16 {
-    if mask.is_null() {
+    if mask.is_null() || !MaskHandle::check(mask) {
         return 0;
     }
 
-    let mask = unsafe { &*(mask as *const Mask) };
+    let mask = MaskHandle::from_ptr(mask);
     mask.width()
 }
 
 /// Get the height of a mask
 #[no_mangle]
 pub extern "C" fn vello_mask_get_height(mask: *const VelloMask) -> u16 {
-    if mask.is_null() {
+    if mask.is_null() || !MaskHandle::check(mask) {
         return 0;
     }
 
-    let mask = unsafe { &*(mask as *const Mask) };
+    let mask = MaskHandle::from_ptr(mask);
     mask.height()
 }
 
+/// Get mask coverage data pointer and length (read-only, row-major, one byte per pixel)
+///
+/// The pointer stays valid until the mask is freed.
+#[no_mangle]
+pub extern "C" fn vello_mask_data(
+    mask: *const VelloMask,
+    out_ptr: *mut *const u8,
+    out_len: *mut usize,
+) -> c_int {
+    if mask.is_null() || out_ptr.is_null() || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let mask = MaskHandle::from_ptr(mask);
+        let data = mask.coverage();
+        unsafe {
+            *out_ptr = data.as_ptr();
+            *out_len = data.len();
+        }
+        VELLO_OK
+    })
+}
+
+/// Sample mask coverage at coordinates
+#[no_mangle]
+pub extern "C" fn vello_mask_sample(
+    mask: *const VelloMask,
+    x: u16,
+    y: u16,
+    out_value: *mut u8,
+) -> c_int {
+    if mask.is_null() || out_value.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let mask = MaskHandle::from_ptr(mask);
+        if x >= mask.width() || y >= mask.height() {
+            set_last_error("Coordinates out of bounds");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        unsafe {
+            *out_value = mask.sample(x, y);
+        }
+        VELLO_OK
+    })
+}
+
+/// Build a mask from row-major coverage values
+pub(crate) fn mask_from_coverage(coverage: &[u8], width: u16, height: u16) -> Mask {
+    use vello_cpu::peniko::color::PremulRgba8;
+
+    let data = coverage
+        .iter()
+        .map(|&a| PremulRgba8 { r: 0, g: 0, b: 0, a })
+        .collect();
+    Mask::new_alpha(&Pixmap::from_parts(data, width, height))
+}
+
+/// Create a new mask from a rectangular region of a mask (pixel coordinates)
+#[no_mangle]
+pub extern "C" fn vello_mask_crop(mask: *const VelloMask, rect: *const VelloRect) -> *mut VelloMask {
+    if mask.is_null() || rect.is_null() {
+        set_last_error("Null pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let mask = MaskHandle::from_ptr(mask);
+        let r = unsafe { &*rect };
+        let x0 = r.x0.floor().clamp(0.0, mask.width() as f64) as usize;
+        let y0 = r.y0.floor().clamp(0.0, mask.height() as f64) as usize;
+        let x1 = r.x1.ceil().clamp(0.0, mask.width() as f64) as usize;
+        let y1 = r.y1.ceil().clamp(0.0, mask.height() as f64) as usize;
+        if x1 <= x0 || y1 <= y0 {
+            set_last_error("Crop rectangle does not intersect the mask");
+            return std::ptr::null_mut();
+        }
+
+        let stride = mask.width() as usize;
+        let coverage = mask.coverage();
+        let mut cropped = Vec::with_capacity((x1 - x0) * (y1 - y0));
+        for y in y0..y1 {
+            cropped.extend_from_slice(&coverage[y * stride + x0..y * stride + x1]);
+        }
+
+        let cropped = mask_from_coverage(&cropped, (x1 - x0) as u16, (y1 - y0) as u16);
+        handle::into_raw(MaskHandle::new(cropped)) as *mut VelloMask
+    })
+}
+
+/// Create a resampled copy of a mask
+#[no_mangle]
+pub extern "C" fn vello_mask_resize(
+    mask: *const VelloMask,
+    width: u16,
+    height: u16,
+    filter: VelloResampleFilter,
+) -> *mut VelloMask {
+    if mask.is_null() {
+        set_last_error("Null mask pointer");
+        return std::ptr::null_mut();
+    }
+
+    if width == 0 || height == 0 {
+        set_last_error("Mask dimensions must be non-zero");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let mask = MaskHandle::from_ptr(mask);
+        let src: Vec<[f32; 1]> = mask.coverage().iter().map(|&v| [v as f32]).collect();
+        let resized = crate::resample::resample(
+            &src,
+            mask.width() as usize,
+            mask.height() as usize,
+            width as usize,
+            height as usize,
+            filter,
+        );
+        let coverage: Vec<u8> = resized
+            .iter()
+            .map(|v| v[0].round().clamp(0.0, 255.0) as u8)
+            .collect();
+
+        let resized = mask_from_coverage(&coverage, width, height);
+        handle::into_raw(MaskHandle::new(resized)) as *mut VelloMask
+    })
+}
+
+/// Create a mask from a linear alpha gradient between two points (pixel coordinates)
+///
+/// Stop offsets must be sorted and within [0, 1]. Useful for fade-out and
+/// feathered-edge masks.
+#[no_mangle]
+pub extern "C" fn vello_mask_new_linear_gradient(
+    width: u16,
+    height: u16,
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    stops: *const VelloAlphaStop,
+    stop_count: usize,
+    extend: VelloExtend,
+) -> *mut VelloMask {
+    if stop_count > 0 && stops.is_null() {
+        set_last_error("Null stops pointer");
+        return std::ptr::null_mut();
+    }
+
+    if stop_count < 2 {
+        set_last_error("Gradient requires at least 2 alpha stops");
+        return std::ptr::null_mut();
+    }
+
+    if width == 0 || height == 0 {
+        set_last_error("Mask dimensions must be non-zero");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let stops = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        if let Err(msg) = crate::context::validate_stop_offsets(stops.iter().map(|s| s.offset)) {
+            set_last_error(msg);
+            return std::ptr::null_mut();
+        }
+
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let len_sq = dx * dx + dy * dy;
+        let mut coverage = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (px, py) = (x as f64 + 0.5 - x0, y as f64 + 0.5 - y0);
+                let t = if len_sq > 0.0 { (px * dx + py * dy) / len_sq } else { 0.0 };
+                let t = match extend {
+                    VelloExtend::Pad => t.clamp(0.0, 1.0),
+                    VelloExtend::Repeat => t.rem_euclid(1.0),
+                    VelloExtend::Reflect => 1.0 - ((t.rem_euclid(2.0)) - 1.0).abs(),
+                } as f32;
+                let alpha = alpha_at(stops, t);
+                coverage.push((alpha.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+
+        let mask = mask_from_coverage(&coverage, width, height);
+        handle::into_raw(MaskHandle::new(mask)) as *mut VelloMask
+    })
+}
+
+/// Interpolate sorted alpha stops at offset `t`
+fn alpha_at(stops: &[VelloAlphaStop], t: f32) -> f32 {
+    let first = stops[0];
+    let last = stops[stops.len() - 1];
+    if t <= first.offset {
+        return first.alpha;
+    }
+    if t >= last.offset {
+        return last.alpha;
+    }
+    for pair in stops.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if t <= b.offset {
+            let span = b.offset - a.offset;
+            if span <= f32::EPSILON {
+                return b.alpha;
+            }
+            return a.alpha + (b.alpha - a.alpha) * (t - a.offset) / span;
+        }
+    }
+    last.alpha
+}
+
 /// Push a mask layer
 #[no_mangle]
 pub extern "C" fn vello_render_context_push_mask_layer(
@@ -84,9 +366,158 @@ pub extern "C" fn vello_render_context_push_mask_layer(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut vello_cpu::RenderContext) };
-        let mask = unsafe { &*(mask as *const Mask) };
-        ctx.push_mask_layer(mask.clone());
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let mask = MaskHandle::from_ptr(mask);
+        ctx.push_mask_layer(mask.mask.clone());
+        VELLO_OK
+    })
+}
+
+/// Rasterize a path with the current paint into a mask and push it as a mask layer
+///
+/// The path is filled using the current transform, paint and fill rule. Pass
+/// `use_luminance = true` for SVG-style luminance masks, false for alpha masks.
+#[no_mangle]
+pub extern "C" fn vello_render_context_push_mask_layer_from_path(
+    ctx: *mut crate::types::VelloRenderContext,
+    path: *const VelloBezPath,
+    use_luminance: bool,
+) -> c_int {
+    if ctx.is_null() || path.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let path = &PathHandle::from_ptr(path).path;
+
+        let (width, height) = (ctx.width(), ctx.height());
+        let mut mask_ctx = Scene::scratch(width, height, ctx.thread_pool());
+        copy_state(ctx, &mut mask_ctx);
+        mask_ctx.fill_path(path);
+        mask_ctx.flush();
+
+        let mut pixmap = Pixmap::new(width, height);
+        mask_ctx.render_pixmap(&mut pixmap, ctx.thread_pool());
+
+        let mask = if use_luminance {
+            Mask::new_luminance(&pixmap)
+        } else {
+            Mask::new_alpha(&pixmap)
+        };
+        ctx.push_mask_layer(mask);
         VELLO_OK
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::vello_get_last_error;
+
+    fn new_mask(coverage: &[u8], width: u16, height: u16) -> *mut VelloMask {
+        let mask = mask_from_coverage(coverage, width, height);
+        handle::into_raw(MaskHandle::new(mask)) as *mut VelloMask
+    }
+
+    /// Size and coverage of a mask, freeing it
+    fn take(mask: *mut VelloMask) -> (u16, u16, Vec<u8>) {
+        assert!(!mask.is_null());
+        let (mut data, mut len) = (std::ptr::null(), 0);
+        assert_eq!(vello_mask_data(mask, &mut data, &mut len), VELLO_OK);
+        let coverage = unsafe { std::slice::from_raw_parts(data, len) }.to_vec();
+        let size = (vello_mask_get_width(mask), vello_mask_get_height(mask));
+        vello_mask_free(mask);
+        (size.0, size.1, coverage)
+    }
+
+    fn last_error() -> String {
+        let message = unsafe { std::ffi::CStr::from_ptr(vello_get_last_error()) };
+        message.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn crops_round_outwards_and_clamp_to_the_mask() {
+        let coverage: Vec<u8> = (0..12).map(|i| i * 10).collect();
+        let mask = new_mask(&coverage, 4, 3);
+        let crop = |x0, y0, x1, y1| vello_mask_crop(mask, &VelloRect { x0, y0, x1, y1 });
+
+        assert_eq!(take(crop(1.5, 0.2, 3.0, 2.0)), (2, 2, vec![10, 20, 50, 60]));
+        assert_eq!(take(crop(-5.0, -5.0, 2.0, 1.0)), (2, 1, vec![0, 10]));
+        assert_eq!(take(crop(3.0, 2.0, 100.0, 100.0)), (1, 1, vec![110]));
+
+        let outside = [(10.0, 0.0, 20.0, 3.0), (3.0, 0.0, 1.0, 2.0), (0.0, 1.0, 4.0, 1.0)];
+        for (x0, y0, x1, y1) in outside {
+            assert!(crop(x0, y0, x1, y1).is_null());
+            assert_eq!(last_error(), "Crop rectangle does not intersect the mask");
+        }
+        assert!(vello_mask_crop(mask, std::ptr::null()).is_null());
+        vello_mask_free(mask);
+    }
+
+    #[test]
+    fn resizes_filter_the_coverage() {
+        let mask = new_mask(&[0, 255], 2, 1);
+        let resize = |width, height, filter| vello_mask_resize(mask, width, height, filter);
+        let (nearest, bilinear) = (VelloResampleFilter::Nearest, VelloResampleFilter::Bilinear);
+
+        assert_eq!(take(resize(4, 1, nearest)), (4, 1, vec![0, 0, 255, 255]));
+        assert_eq!(take(resize(4, 1, bilinear)), (4, 1, vec![0, 64, 191, 255]));
+        assert_eq!(take(resize(2, 2, nearest)), (2, 2, vec![0, 255, 0, 255]));
+
+        for (width, height) in [(0, 1), (1, 0), (0, 0)] {
+            assert!(resize(width, height, bilinear).is_null());
+            assert_eq!(last_error(), "Mask dimensions must be non-zero");
+        }
+        vello_mask_free(mask);
+    }
+
+    #[test]
+    fn gradient_masks_interpolate_and_extend_their_stops() {
+        let stop = |offset, alpha| VelloAlphaStop { offset, alpha };
+        let fade = [stop(0.0, 0.0), stop(1.0, 1.0)];
+        let gradient = |(width, height), (x0, y0), (x1, y1), stops: &[VelloAlphaStop], extend| {
+            let (ptr, len) = (stops.as_ptr(), stops.len());
+            take(vello_mask_new_linear_gradient(width, height, x0, y0, x1, y1, ptr, len, extend))
+        };
+
+        // Sampled at pixel centers, at offsets 1/8, 3/8, 5/8 and 7/8
+        let pad = gradient((4, 1), (0.0, 0.0), (4.0, 0.0), &fade, VelloExtend::Pad);
+        assert_eq!(pad, (4, 1, vec![32, 96, 159, 223]));
+        let repeat = gradient((4, 1), (0.0, 0.0), (2.0, 0.0), &fade, VelloExtend::Repeat);
+        assert_eq!(repeat.2, [64, 191, 64, 191]);
+        let reflect = gradient((4, 1), (0.0, 0.0), (2.0, 0.0), &fade, VelloExtend::Reflect);
+        assert_eq!(reflect.2, [64, 191, 191, 64]);
+        let vertical = gradient((1, 2), (0.0, 0.0), (0.0, 2.0), &fade, VelloExtend::Pad);
+        assert_eq!(vertical, (1, 2, vec![64, 191]));
+
+        // Coincident stops make a hard edge, coincident points use the first stop
+        let edge = [stop(0.0, 1.0), stop(0.5, 1.0), stop(0.5, 0.0), stop(1.0, 0.0)];
+        let hard = gradient((4, 1), (0.0, 0.0), (4.0, 0.0), &edge, VelloExtend::Pad);
+        assert_eq!(hard.2, [255, 255, 0, 0]);
+        let point = gradient((2, 1), (1.0, 0.0), (1.0, 0.0), &edge, VelloExtend::Pad);
+        assert_eq!(point.2, [255, 255]);
+    }
+
+    #[test]
+    fn gradient_masks_check_their_arguments() {
+        let stops = [
+            VelloAlphaStop { offset: 0.5, alpha: 1.0 },
+            VelloAlphaStop { offset: 0.25, alpha: 0.0 },
+        ];
+        let pad = VelloExtend::Pad;
+        let gradient = |width, height, stops: *const VelloAlphaStop, count| {
+            vello_mask_new_linear_gradient(width, height, 0.0, 0.0, 1.0, 0.0, stops, count, pad)
+        };
+
+        assert!(gradient(4, 4, std::ptr::null(), 2).is_null());
+        assert_eq!(last_error(), "Null stops pointer");
+        assert!(gradient(4, 4, stops.as_ptr(), 1).is_null());
+        assert_eq!(last_error(), "Gradient requires at least 2 alpha stops");
+        assert!(gradient(0, 4, stops.as_ptr(), 2).is_null());
+        assert_eq!(last_error(), "Mask dimensions must be non-zero");
+        assert!(gradient(4, 4, stops.as_ptr(), 2).is_null());
+        assert!(last_error().contains("below the previous stop"));
+    }
+}
@@ -3,30 +3,65 @@
 
 //! BezPath FFI bindings
 
+use std::ops::{Deref, DerefMut};
 use std::os::raw::c_int;
 
 use vello_cpu::kurbo::BezPath;
 
+use crate::context::RenderContextHandle;
 use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
 use crate::types::*;
 use crate::{ffi_catch, ffi_catch_ptr};
 
+/// The object behind every `VelloBezPath` pointer
+#[repr(C)]
+pub struct PathHandle {
+    header: HandleHeader,
+    pub(crate) path: BezPath,
+}
+
+impl PathHandle {
+    pub(crate) fn new(path: BezPath) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            path,
+        }
+    }
+}
+
+impl TypedHandle for PathHandle {
+    const TAG: u32 = handle::tag(b"VPTH");
+    const NAME: &'static str = "path";
+}
+
+impl Deref for PathHandle {
+    type Target = BezPath;
+
+    fn deref(&self) -> &BezPath {
+        &self.path
+    }
+}
+
+impl DerefMut for PathHandle {
+    fn deref_mut(&mut self) -> &mut BezPath {
+        &mut self.path
+    }
+}
+
 /// Create new empty BezPath
 #[no_mangle]
 pub extern "C" fn vello_bezpath_new() -> *mut VelloBezPath {
     ffi_catch_ptr!({
-        let path = BezPath::new();
-        Box::into_raw(Box::new(path)) as *mut VelloBezPath
+        handle::into_raw(PathHandle::new(BezPath::new())) as *mut VelloBezPath
     })
 }
 
 /// Free BezPath
 #[no_mangle]
 pub extern "C" fn vello_bezpath_free(path: *mut VelloBezPath) {
-    if !path.is_null() {
-        unsafe {
-            drop(Box::from_raw(path as *mut BezPath));
-        }
+    if !path.is_null() && PathHandle::check(path) {
+        handle::free::<PathHandle, _>(path);
     }
 }
 
@@ -39,7 +74,7 @@ pub extern "C" fn vello_bezpath_move_to(path: *mut VelloBezPath, x: f64, y: f64)
     }
 
     ffi_catch!({
-        let path = unsafe { &mut *(path as *mut BezPath) };
+        let path = &mut PathHandle::from_ptr_mut(path).path;
         path.move_to((x, y));
         VELLO_OK
     })
@@ -54,7 +89,7 @@ pub extern "C" fn vello_bezpath_line_to(path: *mut VelloBezPath, x: f64, y: f64)
     }
 
     ffi_catch!({
-        let path = unsafe { &mut *(path as *mut BezPath) };
+        let path = &mut PathHandle::from_ptr_mut(path).path;
         path.line_to((x, y));
         VELLO_OK
     })
@@ -75,7 +110,7 @@ pub extern "C" fn vello_bezpath_quad_to(
     }
 
     ffi_catch!({
-        let path = unsafe { &mut *(path as *mut BezPath) };
+        let path = &mut PathHandle::from_ptr_mut(path).path;
         path.quad_to((x1, y1), (x2, y2));
         VELLO_OK
     })
@@ -98,7 +133,7 @@ pub extern "C" fn vello_bezpath_curve_to(
     }
 
     ffi_catch!({
-        let path = unsafe { &mut *(path as *mut BezPath) };
+        let path = &mut PathHandle::from_ptr_mut(path).path;
         path.curve_to((x1, y1), (x2, y2), (x3, y3));
         VELLO_OK
     })
@@ -113,7 +148,7 @@ pub extern "C" fn vello_bezpath_close(path: *mut VelloBezPath) -> c_int {
     }
 
     ffi_catch!({
-        let path = unsafe { &mut *(path as *mut BezPath) };
+        let path = &mut PathHandle::from_ptr_mut(path).path;
         path.close_path();
         VELLO_OK
     })
@@ -128,7 +163,7 @@ pub extern "C" fn vello_bezpath_clear(path: *mut VelloBezPath) -> c_int {
     }
 
     ffi_catch!({
-        let path = unsafe { &mut *(path as *mut BezPath) };
+        let path = &mut PathHandle::from_ptr_mut(path).path;
         path.truncate(0);
         VELLO_OK
     })
@@ -146,8 +181,8 @@ pub extern "C" fn vello_render_context_fill_path(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut vello_cpu::RenderContext) };
-        let path = unsafe { &*(path as *const BezPath) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let path = &PathHandle::from_ptr(path).path;
         ctx.fill_path(path);
         VELLO_OK
     })
@@ -165,9 +200,98 @@ pub extern "C" fn vello_render_context_stroke_path(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut vello_cpu::RenderContext) };
-        let path = unsafe { &*(path as *const BezPath) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let path = &PathHandle::from_ptr(path).path;
         ctx.stroke_path(path);
         VELLO_OK
     })
 }
+
+/// Fill path with anti-aliasing overridden for this call only
+#[no_mangle]
+pub extern "C" fn vello_render_context_fill_path_aa(
+    ctx: *mut VelloRenderContext,
+    path: *const VelloBezPath,
+    aa_mode: VelloAaMode,
+) -> c_int {
+    if ctx.is_null() || path.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let path = &PathHandle::from_ptr(path).path;
+        ctx.record_fill(path, aa_mode);
+        ctx.with_aa_mode(aa_mode, |ctx| ctx.fill_path(path));
+        VELLO_OK
+    })
+}
+
+/// Stroke path with anti-aliasing overridden for this call only
+#[no_mangle]
+pub extern "C" fn vello_render_context_stroke_path_aa(
+    ctx: *mut VelloRenderContext,
+    path: *const VelloBezPath,
+    aa_mode: VelloAaMode,
+) -> c_int {
+    if ctx.is_null() || path.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let path = &PathHandle::from_ptr(path).path;
+        let snapped = ctx.snap_hairline(path);
+        let path = snapped.as_ref().unwrap_or(path);
+        ctx.record_stroke(path, aa_mode);
+        ctx.with_aa_mode(aa_mode, |ctx| ctx.stroke_path(path));
+        VELLO_OK
+    })
+}
+
+/// Fill a blurred shadow of a path (non-premultiplied RGBA shadow color)
+///
+/// The shadow is offset in device space and uses the current transform and fill
+/// rule. Paint state is left untouched.
+#[no_mangle]
+pub extern "C" fn vello_render_context_fill_path_shadow(
+    ctx: *mut VelloRenderContext,
+    path: *const VelloBezPath,
+    offset_x: f64,
+    offset_y: f64,
+    std_dev: f32,
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+) -> c_int {
+    if ctx.is_null() || path.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    if !(std_dev.is_finite() && std_dev >= 0.0) {
+        set_last_error("Shadow standard deviation must be finite and non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let path = &PathHandle::from_ptr(path).path;
+
+        use vello_cpu::kurbo::{Affine, Shape};
+        use vello_cpu::peniko::color::{AlphaColor, Srgb};
+
+        let transform = Affine::translate((offset_x, offset_y)) * *ctx.transform();
+        let device_bounds = (transform * path.clone()).bounding_box();
+        let fill_rule = *ctx.fill_rule();
+        crate::blur::fill_blurred(ctx, device_bounds, transform, std_dev, std_dev, |shadow| {
+            shadow.set_fill_rule(fill_rule);
+            shadow.set_paint(AlphaColor::<Srgb>::from_rgba8(r, g, b, a));
+            shadow.fill_path(path);
+        });
+        VELLO_OK
+    })
+}
@@ -0,0 +1,237 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Per-pixel helpers shared by the pixmap, mask and filter bindings
+
+use vello_cpu::peniko::color::PremulRgba8;
+
+use crate::types::{VelloCompose, VelloPixelFormat};
+
+/// Convert a premultiplied pixel to straight alpha components in the 0..1 range
+pub(crate) fn unpremultiply(px: PremulRgba8) -> [f32; 4] {
+    if px.a == 0 {
+        return [0.0; 4];
+    }
+    let a = px.a as f32;
+    [px.r as f32 / a, px.g as f32 / a, px.b as f32 / a, a / 255.0]
+}
+
+/// Convert straight alpha components in the 0..1 range to a premultiplied pixel
+pub(crate) fn premultiply(c: [f32; 4]) -> PremulRgba8 {
+    let a = c[3].clamp(0.0, 1.0);
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * a * 255.0).round() as u8;
+    PremulRgba8 {
+        r: channel(c[0]),
+        g: channel(c[1]),
+        b: channel(c[2]),
+        a: (a * 255.0).round() as u8,
+    }
+}
+
+/// Convert a premultiplied pixel to straight alpha RGBA8
+///
+/// Branch-free so row loops vectorize. The channels are divided in floating
+/// point, which gives the same rounding as `(c * 255 + a / 2) / a` for every
+/// 8-bit input.
+#[inline]
+pub(crate) fn unpremultiply_rgba8(px: PremulRgba8) -> [u8; 4] {
+    let a = px.a as f32;
+    let channel = |c: u8| {
+        let straight = ((c as f32 * 255.0) / a + 0.5).min(255.0) as u8;
+        if px.a == 0 {
+            0
+        } else {
+            straight
+        }
+    };
+    [channel(px.r), channel(px.g), channel(px.b), px.a]
+}
+
+/// Convert straight alpha RGBA8 to a premultiplied pixel
+#[inline]
+pub(crate) fn premultiply_rgba8(c: [u8; 4]) -> PremulRgba8 {
+    let a = c[3] as u32;
+    let channel = |v: u8| ((v as u32 * a + 127) / 255) as u8;
+    PremulRgba8 {
+        r: channel(c[0]),
+        g: channel(c[1]),
+        b: channel(c[2]),
+        a: c[3],
+    }
+}
+
+/// Pixels converted per chunk by the row conversions below
+const CONVERT_CHUNK: usize = 16;
+
+/// Convert a row of premultiplied pixels to straight alpha RGBA8 bytes
+///
+/// `dst` must hold `src.len() * 4` bytes. Works in fixed-size chunks so the
+/// loop vectorizes.
+pub(crate) fn unpremultiply_row(src: &[PremulRgba8], dst: &mut [u8]) {
+    let convert = |src: &[PremulRgba8], dst: &mut [u8]| {
+        for (out, px) in dst.chunks_exact_mut(4).zip(src) {
+            out.copy_from_slice(&unpremultiply_rgba8(*px));
+        }
+    };
+    let mut src_chunks = src.chunks_exact(CONVERT_CHUNK);
+    let mut dst_chunks = dst.chunks_exact_mut(CONVERT_CHUNK * 4);
+    for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+        convert(s, d);
+    }
+    convert(src_chunks.remainder(), dst_chunks.into_remainder());
+}
+
+/// Convert a row of straight alpha RGBA8 bytes to premultiplied pixels
+///
+/// `src` must hold `dst.len() * 4` bytes. Works in fixed-size chunks so the
+/// loop vectorizes.
+pub(crate) fn premultiply_row(src: &[u8], dst: &mut [PremulRgba8]) {
+    let convert = |src: &[u8], dst: &mut [PremulRgba8]| {
+        for (px, c) in dst.iter_mut().zip(src.chunks_exact(4)) {
+            *px = premultiply_rgba8([c[0], c[1], c[2], c[3]]);
+        }
+    };
+    let mut src_chunks = src.chunks_exact(CONVERT_CHUNK * 4);
+    let mut dst_chunks = dst.chunks_exact_mut(CONVERT_CHUNK);
+    for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+        convert(s, d);
+    }
+    convert(src_chunks.remainder(), dst_chunks.into_remainder());
+}
+
+/// Convert a row of premultiplied pixels into `format`
+///
+/// `dst` must hold `src.len() * format.bytes_per_pixel()` bytes.
+pub(crate) fn convert_row(src: &[PremulRgba8], format: VelloPixelFormat, dst: &mut [u8]) {
+    // Dispatch once per row so each loop below is a straight pixel loop
+    let pixels = dst.chunks_exact_mut(format.bytes_per_pixel()).zip(src);
+    match format {
+        VelloPixelFormat::Rgba8 => {
+            pixels.for_each(|(out, px)| out.copy_from_slice(&[px.r, px.g, px.b, px.a]));
+        }
+        VelloPixelFormat::Bgra8 => {
+            pixels.for_each(|(out, px)| out.copy_from_slice(&[px.b, px.g, px.r, px.a]));
+        }
+        VelloPixelFormat::Rgba8Unpremultiplied => unpremultiply_row(src, dst),
+        VelloPixelFormat::Bgra8Unpremultiplied => {
+            for (out, px) in pixels {
+                let [r, g, b, a] = unpremultiply_rgba8(*px);
+                out.copy_from_slice(&[b, g, r, a]);
+            }
+        }
+        VelloPixelFormat::Rgb565 => {
+            for (out, px) in pixels {
+                let value = ((px.r as u16 >> 3) << 11) | ((px.g as u16 >> 2) << 5) | (px.b as u16 >> 3);
+                out.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        VelloPixelFormat::Gray8 => {
+            for (out, px) in pixels {
+                // Rec. 709 luma weights in 8.8 fixed point
+                let y = (54 * px.r as u32 + 183 * px.g as u32 + 19 * px.b as u32 + 128) >> 8;
+                out[0] = y.min(255) as u8;
+            }
+        }
+    }
+}
+
+/// Porter-Duff composition of a premultiplied source pixel onto a destination pixel
+pub(crate) fn compose(src: PremulRgba8, dst: PremulRgba8, op: VelloCompose) -> PremulRgba8 {
+    let (sa, da) = (src.a as u32, dst.a as u32);
+    let (fa, fb) = match op {
+        VelloCompose::Clear => (0, 0),
+        VelloCompose::Copy => (255, 0),
+        VelloCompose::Dest => (0, 255),
+        VelloCompose::SrcOver => (255, 255 - sa),
+        VelloCompose::DestOver => (255 - da, 255),
+        VelloCompose::SrcIn => (da, 0),
+        VelloCompose::DestIn => (0, sa),
+        VelloCompose::SrcOut => (255 - da, 0),
+        VelloCompose::DestOut => (0, 255 - sa),
+        VelloCompose::SrcAtop => (da, 255 - sa),
+        VelloCompose::DestAtop => (255 - da, sa),
+        VelloCompose::Xor => (255 - da, 255 - sa),
+        VelloCompose::Plus | VelloCompose::PlusLighter => (255, 255),
+    };
+    let channel = |s: u8, d: u8| ((s as u32 * fa + d as u32 * fb + 127) / 255).min(255) as u8;
+    PremulRgba8 {
+        r: channel(src.r, dst.r),
+        g: channel(src.g, dst.g),
+        b: channel(src.b, dst.b),
+        a: channel(src.a, dst.a),
+    }
+}
+
+/// Porter-Duff composition of a row of source pixels onto a row of destination
+/// pixels, giving the same result as `compose` for each pixel
+///
+/// The operator is resolved once per row, and the pixels are blended in
+/// fixed-size chunks without branches so the loops vectorize.
+pub(crate) fn compose_row(src: &[PremulRgba8], dst: &mut [PremulRgba8], op: VelloCompose) {
+    match op {
+        VelloCompose::Clear => dst.fill(PremulRgba8 { r: 0, g: 0, b: 0, a: 0 }),
+        VelloCompose::Copy => dst.copy_from_slice(src),
+        VelloCompose::Dest => {}
+        VelloCompose::SrcOver => compose_chunks(src, dst, |sa, _| (255, 255 - sa)),
+        VelloCompose::DestOver => compose_chunks(src, dst, |_, da| (255 - da, 255)),
+        VelloCompose::SrcIn => compose_chunks(src, dst, |_, da| (da, 0)),
+        VelloCompose::DestIn => compose_chunks(src, dst, |sa, _| (0, sa)),
+        VelloCompose::SrcOut => compose_chunks(src, dst, |_, da| (255 - da, 0)),
+        VelloCompose::DestOut => compose_chunks(src, dst, |sa, _| (0, 255 - sa)),
+        VelloCompose::SrcAtop => compose_chunks(src, dst, |sa, da| (da, 255 - sa)),
+        VelloCompose::DestAtop => compose_chunks(src, dst, |sa, da| (255 - da, sa)),
+        VelloCompose::Xor => compose_chunks(src, dst, |sa, da| (255 - da, 255 - sa)),
+        VelloCompose::Plus | VelloCompose::PlusLighter => {
+            for (d, s) in dst.iter_mut().zip(src) {
+                *d = PremulRgba8 {
+                    r: d.r.saturating_add(s.r),
+                    g: d.g.saturating_add(s.g),
+                    b: d.b.saturating_add(s.b),
+                    a: d.a.saturating_add(s.a),
+                };
+            }
+        }
+    }
+}
+
+/// Pixels blended per chunk by `compose_row`
+const COMPOSE_CHUNK: usize = 16;
+
+#[inline(always)]
+fn compose_chunks(
+    src: &[PremulRgba8],
+    dst: &mut [PremulRgba8],
+    factors: impl Fn(u32, u32) -> (u32, u32),
+) {
+    let blend = |src: &[PremulRgba8], dst: &mut [PremulRgba8]| {
+        for (d, s) in dst.iter_mut().zip(src) {
+            let (fa, fb) = factors(s.a as u32, d.a as u32);
+            let channel =
+                |s: u8, d: u8| ((s as u32 * fa + d as u32 * fb + 127) / 255).min(255) as u8;
+            *d = PremulRgba8 {
+                r: channel(s.r, d.r),
+                g: channel(s.g, d.g),
+                b: channel(s.b, d.b),
+                a: channel(s.a, d.a),
+            };
+        }
+    };
+    let mut src_chunks = src.chunks_exact(COMPOSE_CHUNK);
+    let mut dst_chunks = dst.chunks_exact_mut(COMPOSE_CHUNK);
+    for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+        blend(s, d);
+    }
+    blend(src_chunks.remainder(), dst_chunks.into_remainder());
+}
+
+/// Composite premultiplied RGBA8 bytes over an opaque color in place
+pub(crate) fn fill_under(data: &mut [u8], base: PremulRgba8) {
+    for px in data.chunks_exact_mut(4) {
+        if px[3] == 255 {
+            continue;
+        }
+        let src = PremulRgba8 { r: px[0], g: px[1], b: px[2], a: px[3] };
+        let out = compose(src, base, VelloCompose::SrcOver);
+        px.copy_from_slice(&[out.r, out.g, out.b, out.a]);
+    }
+}
@@ -3,30 +3,124 @@
 
 //! Pixmap FFI bindings
 
+#[cfg(feature = "png")]
+use std::ffi::CStr;
+#[cfg(feature = "png")]
+use std::os::raw::c_char;
+use std::ops::{Deref, DerefMut};
 use std::os::raw::c_int;
 
+use vello_cpu::peniko::color::PremulRgba8;
 use vello_cpu::Pixmap;
 
+use crate::context::{render_strided, RenderContextHandle};
 use crate::error::set_last_error;
+use crate::handle::{self, CallScope, HandleHeader, HandleLock, TypedHandle};
 use crate::types::*;
 use crate::{ffi_catch, ffi_catch_ptr};
 
+/// The object behind every `VelloPixmap` pointer: pixel data plus its color space tag
+///
+/// Calls taking a pixmap hold its lock, so threads rendering into regions of
+/// one pixmap take turns writing it.
+#[repr(C)]
+pub struct PixmapHandle {
+    header: HandleHeader,
+    pub(crate) pixmap: Pixmap,
+    pub(crate) color_space: VelloColorSpace,
+    lock: HandleLock,
+}
+
+impl PixmapHandle {
+    pub(crate) fn new(pixmap: Pixmap) -> Self {
+        Self::with_color_space(pixmap, VelloColorSpace::Srgb)
+    }
+
+    pub(crate) fn with_color_space(pixmap: Pixmap, color_space: VelloColorSpace) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            pixmap,
+            color_space,
+            lock: HandleLock::default(),
+        }
+    }
+}
+
+impl TypedHandle for PixmapHandle {
+    const TAG: u32 = handle::tag(b"VPIX");
+    const NAME: &'static str = "pixmap";
+
+    unsafe fn lock<'a>(ptr: *const Self) -> Option<&'a HandleLock> {
+        unsafe { Some(&*std::ptr::addr_of!((*ptr).lock)) }
+    }
+}
+
+impl Deref for PixmapHandle {
+    type Target = Pixmap;
+
+    fn deref(&self) -> &Pixmap {
+        &self.pixmap
+    }
+}
+
+impl DerefMut for PixmapHandle {
+    fn deref_mut(&mut self) -> &mut Pixmap {
+        &mut self.pixmap
+    }
+}
+
 /// Create new pixmap
 #[no_mangle]
 pub extern "C" fn vello_pixmap_new(width: u16, height: u16) -> *mut VelloPixmap {
     ffi_catch_ptr!({
         let pixmap = Pixmap::new(width, height);
-        Box::into_raw(Box::new(pixmap)) as *mut VelloPixmap
+        handle::into_raw(PixmapHandle::new(pixmap)) as *mut VelloPixmap
+    })
+}
+
+/// Create new pixmap, failing with `VELLO_ERROR_OUT_OF_MEMORY` instead of
+/// aborting when the pixel buffer cannot be allocated
+#[no_mangle]
+pub extern "C" fn vello_pixmap_try_new(
+    width: u16,
+    height: u16,
+    out_pixmap: *mut *mut VelloPixmap,
+) -> c_int {
+    if out_pixmap.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let Some(pixmap) = try_new_pixmap(width, height) else {
+            set_last_error(format!("Failed to allocate {width}x{height} pixmap"));
+            return VELLO_ERROR_OUT_OF_MEMORY;
+        };
+        let handle = handle::into_raw(PixmapHandle::new(pixmap)) as *mut VelloPixmap;
+        unsafe { *out_pixmap = handle };
+        VELLO_OK
     })
 }
 
-/// Free pixmap
+/// Allocate a transparent pixmap, returning `None` if memory is exhausted
+pub(crate) fn try_new_pixmap(width: u16, height: u16) -> Option<Pixmap> {
+    let len = width as usize * height as usize;
+    let mut data = Vec::new();
+    data.try_reserve_exact(len).ok()?;
+    data.resize(len, PremulRgba8 { r: 0, g: 0, b: 0, a: 0 });
+    Some(Pixmap::from_parts(data, width, height))
+}
+
+/// Free pixmap, including one created by `vello_pixmap_wrap`
 #[no_mangle]
 pub extern "C" fn vello_pixmap_free(pixmap: *mut VelloPixmap) {
-    if !pixmap.is_null() {
-        unsafe {
-            drop(Box::from_raw(pixmap as *mut Pixmap));
-        }
+    if pixmap.is_null() {
+        return;
+    }
+    if WrappedPixmap::is_valid(pixmap) {
+        handle::free::<WrappedPixmap, _>(pixmap);
+    } else if PixmapHandle::check(pixmap) {
+        handle::free::<PixmapHandle, _>(pixmap);
     }
 }
 
@@ -36,8 +130,14 @@ pub extern "C" fn vello_pixmap_width(pixmap: *const VelloPixmap) -> u16 {
     if pixmap.is_null() {
         return 0;
     }
+    if WrappedPixmap::is_valid(pixmap) {
+        return unsafe { (*(pixmap as *const WrappedPixmap)).width };
+    }
+    if !PixmapHandle::check(pixmap) {
+        return 0;
+    }
     unsafe {
-        let pixmap = &*(pixmap as *const Pixmap);
+        let pixmap = &*(pixmap as *const PixmapHandle);
         pixmap.width()
     }
 }
@@ -48,12 +148,69 @@ pub extern "C" fn vello_pixmap_height(pixmap: *const VelloPixmap) -> u16 {
     if pixmap.is_null() {
         return 0;
     }
+    if WrappedPixmap::is_valid(pixmap) {
+        return unsafe { (*(pixmap as *const WrappedPixmap)).height };
+    }
+    if !PixmapHandle::check(pixmap) {
+        return 0;
+    }
     unsafe {
-        let pixmap = &*(pixmap as *const Pixmap);
+        let pixmap = &*(pixmap as *const PixmapHandle);
         pixmap.height()
     }
 }
 
+/// Get the color space a pixmap is tagged with
+#[no_mangle]
+pub extern "C" fn vello_pixmap_color_space(pixmap: *const VelloPixmap) -> VelloColorSpace {
+    if pixmap.is_null() || !PixmapHandle::check(pixmap) {
+        return VelloColorSpace::Srgb;
+    }
+    unsafe {
+        let pixmap = &*(pixmap as *const PixmapHandle);
+        pixmap.color_space
+    }
+}
+
+/// Tag a pixmap with a color space without touching its pixels
+#[no_mangle]
+pub extern "C" fn vello_pixmap_set_color_space(
+    pixmap: *mut VelloPixmap,
+    color_space: VelloColorSpace,
+) -> c_int {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        pixmap.color_space = color_space;
+        VELLO_OK
+    })
+}
+
+/// Convert pixmap contents in place from its tagged color space to `color_space`
+/// and retag it; out-of-gamut colors are clipped
+#[no_mangle]
+pub extern "C" fn vello_pixmap_convert_color_space(
+    pixmap: *mut VelloPixmap,
+    color_space: VelloColorSpace,
+) -> c_int {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        let from = pixmap.color_space;
+        crate::color_space::convert(pixmap.data_mut(), from, color_space);
+        pixmap.color_space = color_space;
+        VELLO_OK
+    })
+}
+
 /// Get pixmap data pointer and length (zero-copy access)
 #[no_mangle]
 pub extern "C" fn vello_pixmap_data(
@@ -67,7 +224,7 @@ pub extern "C" fn vello_pixmap_data(
     }
 
     ffi_catch!({
-        let pixmap = unsafe { &*(pixmap as *const Pixmap) };
+        let pixmap = PixmapHandle::from_ptr(pixmap);
         let data = pixmap.data();
         unsafe {
             *out_ptr = data.as_ptr() as *const VelloPremulRgba8;
@@ -90,7 +247,7 @@ pub extern "C" fn vello_pixmap_data_mut(
     }
 
     ffi_catch!({
-        let pixmap = unsafe { &mut *(pixmap as *mut Pixmap) };
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
         let data = pixmap.data_mut();
         unsafe {
             *out_ptr = data.as_mut_ptr() as *mut VelloPremulRgba8;
@@ -113,12 +270,210 @@ pub extern "C" fn vello_pixmap_resize(
     }
 
     ffi_catch!({
-        let pixmap = unsafe { &mut *(pixmap as *mut Pixmap) };
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
         pixmap.resize(width, height);
         VELLO_OK
     })
 }
 
+/// Create a resampled copy of a pixmap
+#[no_mangle]
+pub extern "C" fn vello_pixmap_resize_to(
+    pixmap: *const VelloPixmap,
+    width: u16,
+    height: u16,
+    filter: VelloResampleFilter,
+) -> *mut VelloPixmap {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return std::ptr::null_mut();
+    }
+
+    if width == 0 || height == 0 {
+        set_last_error("Pixmap dimensions must be non-zero");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        // Premultiplied data can be filtered directly without color fringes
+        let src: Vec<[f32; 4]> = pixmap
+            .data()
+            .iter()
+            .map(|px| [px.r as f32, px.g as f32, px.b as f32, px.a as f32])
+            .collect();
+        let resized = crate::resample::resample(
+            &src,
+            pixmap.width() as usize,
+            pixmap.height() as usize,
+            width as usize,
+            height as usize,
+            filter,
+        );
+
+        let data = resized
+            .iter()
+            .map(|c| {
+                // Sharpening filters can overshoot, so keep the result premultiplied-valid
+                let a = c[3].round().clamp(0.0, 255.0);
+                PremulRgba8 {
+                    r: c[0].round().clamp(0.0, a) as u8,
+                    g: c[1].round().clamp(0.0, a) as u8,
+                    b: c[2].round().clamp(0.0, a) as u8,
+                    a: a as u8,
+                }
+            })
+            .collect();
+        let resized =
+            PixmapHandle::with_color_space(Pixmap::from_parts(data, width, height), pixmap.color_space);
+        handle::into_raw(resized) as *mut VelloPixmap
+    })
+}
+
+/// Mirror pixmap contents left-to-right in place
+#[no_mangle]
+pub extern "C" fn vello_pixmap_flip_horizontal(pixmap: *mut VelloPixmap) -> c_int {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        let width = pixmap.width() as usize;
+        if width > 0 {
+            for row in pixmap.data_mut().chunks_exact_mut(width) {
+                row.reverse();
+            }
+        }
+        VELLO_OK
+    })
+}
+
+/// Mirror pixmap contents top-to-bottom in place
+#[no_mangle]
+pub extern "C" fn vello_pixmap_flip_vertical(pixmap: *mut VelloPixmap) -> c_int {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        let width = pixmap.width() as usize;
+        let height = pixmap.height() as usize;
+        let data = pixmap.data_mut();
+        for y in 0..height / 2 {
+            let (top, bottom) = data.split_at_mut((height - 1 - y) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+        VELLO_OK
+    })
+}
+
+/// Create a rotated copy of a pixmap; `quarter_turns` clockwise turns of 90 degrees
+fn rotated(pixmap: &Pixmap, quarter_turns: u8) -> Pixmap {
+    let (w, h) = (pixmap.width() as usize, pixmap.height() as usize);
+    let src = pixmap.data();
+    let (out_w, out_h) = if quarter_turns % 2 == 1 { (h, w) } else { (w, h) };
+    let mut data = Vec::with_capacity(w * h);
+    for y in 0..out_h {
+        for x in 0..out_w {
+            let (sx, sy) = match quarter_turns % 4 {
+                1 => (y, h - 1 - x),
+                2 => (w - 1 - x, h - 1 - y),
+                3 => (w - 1 - y, x),
+                _ => (x, y),
+            };
+            data.push(src[sy * w + sx]);
+        }
+    }
+    Pixmap::from_parts(data, out_w as u16, out_h as u16)
+}
+
+/// Create a copy of a pixmap rotated 90 degrees clockwise
+#[no_mangle]
+pub extern "C" fn vello_pixmap_rotate90(pixmap: *const VelloPixmap) -> *mut VelloPixmap {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let rotated = PixmapHandle::with_color_space(rotated(pixmap, 1), pixmap.color_space);
+        handle::into_raw(rotated) as *mut VelloPixmap
+    })
+}
+
+/// Create a copy of a pixmap rotated 180 degrees
+#[no_mangle]
+pub extern "C" fn vello_pixmap_rotate180(pixmap: *const VelloPixmap) -> *mut VelloPixmap {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let rotated = PixmapHandle::with_color_space(rotated(pixmap, 2), pixmap.color_space);
+        handle::into_raw(rotated) as *mut VelloPixmap
+    })
+}
+
+/// Create a copy of a pixmap rotated 270 degrees clockwise (90 counter-clockwise)
+#[no_mangle]
+pub extern "C" fn vello_pixmap_rotate270(pixmap: *const VelloPixmap) -> *mut VelloPixmap {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let rotated = PixmapHandle::with_color_space(rotated(pixmap, 3), pixmap.color_space);
+        handle::into_raw(rotated) as *mut VelloPixmap
+    })
+}
+
+/// Clear pixmap to transparent black
+#[no_mangle]
+pub extern "C" fn vello_pixmap_clear(pixmap: *mut VelloPixmap) -> c_int {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        pixmap.data_as_u8_slice_mut().fill(0);
+        VELLO_OK
+    })
+}
+
+/// Fill pixmap with a solid color (non-premultiplied RGBA)
+#[no_mangle]
+pub extern "C" fn vello_pixmap_fill(
+    pixmap: *mut VelloPixmap,
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+) -> c_int {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        use vello_cpu::peniko::color::{AlphaColor, Srgb};
+        let color = AlphaColor::<Srgb>::from_rgba8(r, g, b, a).premultiply().to_rgba8();
+        pixmap.data_mut().fill(color);
+        VELLO_OK
+    })
+}
+
 /// Sample pixel at coordinates
 #[no_mangle]
 pub extern "C" fn vello_pixmap_sample(
@@ -133,7 +488,7 @@ pub extern "C" fn vello_pixmap_sample(
     }
 
     ffi_catch!({
-        let pixmap = unsafe { &*(pixmap as *const Pixmap) };
+        let pixmap = PixmapHandle::from_ptr(pixmap);
         if x >= pixmap.width() || y >= pixmap.height() {
             set_last_error("Coordinates out of bounds");
             return VELLO_ERROR_INVALID_PARAMETER;
@@ -146,83 +501,1291 @@ pub extern "C" fn vello_pixmap_sample(
     })
 }
 
-/// Render to pixmap
+/// Get a pointer to row `y` of a pixmap; `out_len` receives the row length in pixels
 #[no_mangle]
-pub extern "C" fn vello_render_context_render_to_pixmap(
-    ctx: *const VelloRenderContext,
+pub extern "C" fn vello_pixmap_row(
     pixmap: *mut VelloPixmap,
+    y: u16,
+    out_ptr: *mut *mut VelloPremulRgba8,
+    out_len: *mut usize,
 ) -> c_int {
-    if ctx.is_null() || pixmap.is_null() {
+    if pixmap.is_null() || out_ptr.is_null() || out_len.is_null() {
         set_last_error("Null pointer");
         return VELLO_ERROR_NULL_POINTER;
     }
 
     ffi_catch!({
-        let ctx = unsafe { &*(ctx as *const vello_cpu::RenderContext) };
-        let pixmap = unsafe { &mut *(pixmap as *mut Pixmap) };
-        ctx.render_to_pixmap(pixmap);
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        if y >= pixmap.height() {
+            set_last_error("Row out of bounds");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        let width = pixmap.width() as usize;
+        let row = &mut pixmap.data_mut()[y as usize * width..(y as usize + 1) * width];
+        unsafe {
+            *out_ptr = row.as_mut_ptr() as *mut VelloPremulRgba8;
+            *out_len = width;
+        }
         VELLO_OK
     })
 }
 
-#[cfg(feature = "png")]
+/// Write a single premultiplied pixel
 #[no_mangle]
-pub extern "C" fn vello_pixmap_from_png(data: *const u8, len: usize) -> *mut VelloPixmap {
-    if data.is_null() || len == 0 {
-        set_last_error("Null or empty PNG data");
-        return std::ptr::null_mut();
+pub extern "C" fn vello_pixmap_set_pixel(
+    pixmap: *mut VelloPixmap,
+    x: u16,
+    y: u16,
+    color: VelloPremulRgba8,
+) -> c_int {
+    if pixmap.is_null() {
+        set_last_error("Null pixmap pointer");
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    ffi_catch_ptr!({
-        let slice = unsafe { std::slice::from_raw_parts(data, len) };
-        match Pixmap::from_png(slice) {
-            Ok(pixmap) => Box::into_raw(Box::new(pixmap)) as *mut VelloPixmap,
-            Err(e) => {
-                set_last_error(format!("PNG decode error: {:?}", e));
-                std::ptr::null_mut()
-            }
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        if x >= pixmap.width() || y >= pixmap.height() {
+            set_last_error("Coordinates out of bounds");
+            return VELLO_ERROR_INVALID_PARAMETER;
         }
+        let index = y as usize * pixmap.width() as usize + x as usize;
+        pixmap.data_mut()[index] = color.into();
+        VELLO_OK
     })
 }
 
-#[cfg(feature = "png")]
+/// Apply a row-major 4x5 color matrix (20 floats) to every pixel in place
+///
+/// The matrix operates on straight-alpha components in the 0..1 range, as in
+/// SVG `feColorMatrix`; the fifth column is a constant offset.
 #[no_mangle]
-pub extern "C" fn vello_pixmap_to_png(
-    pixmap: *const VelloPixmap,
-    out_data: *mut *mut u8,
-    out_len: *mut usize,
+pub extern "C" fn vello_pixmap_apply_color_matrix(
+    pixmap: *mut VelloPixmap,
+    matrix: *const f32,
 ) -> c_int {
-    if pixmap.is_null() || out_data.is_null() || out_len.is_null() {
+    if pixmap.is_null() || matrix.is_null() {
         set_last_error("Null pointer");
         return VELLO_ERROR_NULL_POINTER;
     }
 
     ffi_catch!({
-        let pixmap = unsafe { &*(pixmap as *const Pixmap) };
-        match pixmap.clone().into_png() {
-            Ok(png_data) => {
-                let mut boxed = png_data.into_boxed_slice();
-                unsafe {
-                    *out_len = boxed.len();
-                    *out_data = boxed.as_mut_ptr();
-                    std::mem::forget(boxed); // Prevent deallocation
-                }
-                VELLO_OK
-            }
-            Err(e) => {
-                set_last_error(format!("PNG encode error: {:?}", e));
-                VELLO_ERROR_PNG_ENCODE
-            }
-        }
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        let mut values = [0.0f32; 20];
+        values.copy_from_slice(unsafe { std::slice::from_raw_parts(matrix, 20) });
+        crate::filter::apply_color_matrix(pixmap.data_mut(), &values);
+        VELLO_OK
     })
 }
 
-#[cfg(feature = "png")]
+/// Compute a 64-bit XXH3 digest of the pixmap dimensions and pixel contents
+///
+/// The digest is stable across platforms and library versions, so it can be
+/// stored alongside golden images.
 #[no_mangle]
-pub extern "C" fn vello_png_data_free(data: *mut u8, len: usize) {
-    if !data.is_null() && len > 0 {
+pub extern "C" fn vello_pixmap_hash(pixmap: *const VelloPixmap, out_hash: *mut u64) -> c_int {
+    if pixmap.is_null() || out_hash.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+        hasher.update(&pixmap.width().to_le_bytes());
+        hasher.update(&pixmap.height().to_le_bytes());
+        hasher.update(pixmap.data_as_u8_slice());
         unsafe {
-            let _ = Box::from_raw(std::slice::from_raw_parts_mut(data, len));
+            *out_hash = hasher.digest();
         }
+        VELLO_OK
+    })
+}
+
+/// Compare two pixmaps of equal size
+///
+/// A pixel differs when any premultiplied channel differs by more than
+/// `tolerance`. When `out_diff` is not null it receives a new pixmap showing
+/// differing pixels in opaque red over a faded grayscale copy of `a`; free it
+/// with `vello_pixmap_free`.
+#[no_mangle]
+pub extern "C" fn vello_pixmap_compare(
+    a: *const VelloPixmap,
+    b: *const VelloPixmap,
+    tolerance: u8,
+    out_diff_count: *mut usize,
+    out_diff: *mut *mut VelloPixmap,
+) -> c_int {
+    if a.is_null() || b.is_null() || out_diff_count.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let a = PixmapHandle::from_ptr(a);
+        let b = PixmapHandle::from_ptr(b);
+        if a.width() != b.width() || a.height() != b.height() {
+            set_last_error("Pixmap dimensions do not match");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let differs = |p: &PremulRgba8, q: &PremulRgba8| {
+            p.r.abs_diff(q.r) > tolerance
+                || p.g.abs_diff(q.g) > tolerance
+                || p.b.abs_diff(q.b) > tolerance
+                || p.a.abs_diff(q.a) > tolerance
+        };
+
+        let mut count = 0;
+        let mut diff = Vec::with_capacity(if out_diff.is_null() { 0 } else { a.data().len() });
+        for (p, q) in a.data().iter().zip(b.data()) {
+            let different = differs(p, q);
+            if different {
+                count += 1;
+            }
+            if !out_diff.is_null() {
+                diff.push(if different {
+                    PremulRgba8 { r: 255, g: 0, b: 0, a: 255 }
+                } else {
+                    // Faded luma so the matching content stays recognizable
+                    let y = (54 * p.r as u32 + 183 * p.g as u32 + 19 * p.b as u32) >> 8;
+                    let v = (191 + y / 4) as u8;
+                    PremulRgba8 { r: v, g: v, b: v, a: 255 }
+                });
+            }
+        }
+
+        unsafe {
+            *out_diff_count = count;
+            if !out_diff.is_null() {
+                let pixmap = Pixmap::from_parts(diff, a.width(), a.height());
+                let pixmap = PixmapHandle::with_color_space(pixmap, a.color_space);
+                *out_diff = handle::into_raw(pixmap) as *mut VelloPixmap;
+            }
+        }
+        VELLO_OK
+    })
+}
+
+/// Composite one pixmap onto another at an offset with a Porter-Duff operator
+/// Parts of the source falling outside the destination are ignored
+#[no_mangle]
+pub extern "C" fn vello_pixmap_blit(
+    dst: *mut VelloPixmap,
+    dst_x: i32,
+    dst_y: i32,
+    src: *const VelloPixmap,
+    compose: VelloCompose,
+) -> c_int {
+    if dst.is_null() || src.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        // Blitting a pixmap onto itself reads from a snapshot
+        let snapshot;
+        let src = if std::ptr::eq(dst as *const VelloPixmap, src) {
+            snapshot = PixmapHandle::from_ptr(src).pixmap.clone();
+            &snapshot
+        } else {
+            &PixmapHandle::from_ptr(src).pixmap
+        };
+        let dst = PixmapHandle::from_ptr_mut(dst);
+
+        let (src_w, src_h) = (src.width() as i64, src.height() as i64);
+        let (dst_w, dst_h) = (dst.width() as i64, dst.height() as i64);
+        let x0 = (dst_x as i64).max(0);
+        let y0 = (dst_y as i64).max(0);
+        let x1 = (dst_x as i64 + src_w).min(dst_w);
+        let y1 = (dst_y as i64 + src_h).min(dst_h);
+        if x1 <= x0 || y1 <= y0 {
+            return VELLO_OK;
+        }
+
+        let src_data = src.data();
+        let dst_data = dst.data_mut();
+        for y in y0..y1 {
+            let src_row = ((y - dst_y as i64) * src_w + (x0 - dst_x as i64)) as usize;
+            let dst_row = (y * dst_w + x0) as usize;
+            let count = (x1 - x0) as usize;
+            let src_px = &src_data[src_row..src_row + count];
+            let dst_px = &mut dst_data[dst_row..dst_row + count];
+            crate::pixel::compose_row(src_px, dst_px, compose);
+        }
+        VELLO_OK
+    })
+}
+
+/// Copy pixmap contents into a buffer as straight (non-premultiplied) RGBA8
+/// Buffer must be at least width * height * 4 bytes
+#[no_mangle]
+pub extern "C" fn vello_pixmap_to_unpremultiplied(
+    pixmap: *const VelloPixmap,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> c_int {
+    if pixmap.is_null() || buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let data = pixmap.data();
+
+        if buffer_len < data.len() * 4 {
+            set_last_error("Buffer too small");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let out = unsafe { std::slice::from_raw_parts_mut(buffer, data.len() * 4) };
+        crate::pixel::unpremultiply_row(data, out);
+        VELLO_OK
+    })
+}
+
+/// Load straight (non-premultiplied) RGBA8 data into a pixmap, premultiplying it
+/// Data must be at least width * height * 4 bytes for the pixmap's dimensions
+#[no_mangle]
+pub extern "C" fn vello_pixmap_load_unpremultiplied(
+    pixmap: *mut VelloPixmap,
+    data: *const u8,
+    data_len: usize,
+) -> c_int {
+    if pixmap.is_null() || data.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr_mut(pixmap);
+        let pixels = pixmap.data_mut();
+
+        if data_len < pixels.len() * 4 {
+            set_last_error("Buffer too small");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let src = unsafe { std::slice::from_raw_parts(data, pixels.len() * 4) };
+        crate::pixel::premultiply_row(src, pixels);
+        VELLO_OK
+    })
+}
+
+/// Convert pixmap contents into another pixel format
+/// `stride` is the distance between rows in bytes (0 = tightly packed)
+#[no_mangle]
+pub extern "C" fn vello_pixmap_convert(
+    pixmap: *const VelloPixmap,
+    format: VelloPixelFormat,
+    buffer: *mut u8,
+    buffer_len: usize,
+    stride: usize,
+) -> c_int {
+    if pixmap.is_null() || buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let width = pixmap.width() as usize;
+        let height = pixmap.height() as usize;
+        let row_len = width * format.bytes_per_pixel();
+        let stride = if stride == 0 { row_len } else { stride };
+
+        if stride < row_len {
+            set_last_error("Stride too small for the pixel format");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        if height > 0 && buffer_len < stride * (height - 1) + row_len {
+            set_last_error("Buffer too small");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        if width > 0 {
+            let out = unsafe { std::slice::from_raw_parts_mut(buffer, buffer_len) };
+            for (y, row) in pixmap.data().chunks_exact(width).enumerate() {
+                crate::pixel::convert_row(row, format, &mut out[y * stride..y * stride + row_len]);
+            }
+        }
+        VELLO_OK
+    })
+}
+
+/// Copy the alpha channel of a pixmap into an A8 buffer
+/// Buffer must be at least width * height bytes
+#[no_mangle]
+pub extern "C" fn vello_pixmap_extract_alpha(
+    pixmap: *const VelloPixmap,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> c_int {
+    if pixmap.is_null() || buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let data = pixmap.data();
+
+        if buffer_len < data.len() {
+            set_last_error("Buffer too small");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let out = unsafe { std::slice::from_raw_parts_mut(buffer, data.len()) };
+        for (dst, px) in out.iter_mut().zip(data) {
+            *dst = px.a;
+        }
+        VELLO_OK
+    })
+}
+
+/// Caller-owned RGBA8 (premultiplied) memory wrapped by `vello_pixmap_wrap`
+///
+/// The memory must stay valid and unmoved until the pixmap is freed.
+#[repr(C)]
+pub(crate) struct WrappedPixmap {
+    header: HandleHeader,
+    data: *mut u8,
+    width: u16,
+    height: u16,
+    stride: usize,
+    /// Tightly packed staging buffer, only used when rows are padded
+    scratch: Vec<u8>,
+    lock: HandleLock,
+}
+
+impl TypedHandle for WrappedPixmap {
+    const TAG: u32 = handle::tag(b"VPXW");
+    const NAME: &'static str = "wrapped pixmap";
+
+    unsafe fn lock<'a>(ptr: *const Self) -> Option<&'a HandleLock> {
+        unsafe { Some(&*std::ptr::addr_of!((*ptr).lock)) }
+    }
+}
+
+impl WrappedPixmap {
+    /// Bytes of row `y`, which must be below the height
+    fn row_mut(&mut self, y: usize) -> &mut [u8] {
+        let row_len = self.width as usize * 4;
+        unsafe { std::slice::from_raw_parts_mut(self.data.add(y * self.stride), row_len) }
+    }
+
+    fn render(&mut self, ctx: &RenderContextHandle, render_mode: vello_cpu::RenderMode) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let len = self.stride * (self.height as usize - 1) + self.width as usize * 4;
+        let buffer = unsafe { std::slice::from_raw_parts_mut(self.data, len) };
+        let (width, height, stride) = (self.width, self.height, self.stride);
+        render_strided(ctx, buffer, width, height, stride, render_mode, &mut self.scratch);
+    }
+}
+
+/// Pixmap a render entry point draws into: one owned by the library, or caller
+/// memory wrapped by `vello_pixmap_wrap`
+pub(crate) enum RenderTarget<'a> {
+    Owned(&'a mut PixmapHandle),
+    Wrapped(&'a mut WrappedPixmap),
+}
+
+impl RenderTarget<'_> {
+    /// Borrow the target behind a `VelloPixmap` pointer inside `ffi_catch!`
+    pub(crate) fn from_ptr(ptr: *mut VelloPixmap) -> Self {
+        if WrappedPixmap::is_valid(ptr) {
+            Self::Wrapped(WrappedPixmap::from_ptr_mut(ptr))
+        } else {
+            Self::Owned(PixmapHandle::from_ptr_mut(ptr))
+        }
+    }
+
+    pub(crate) fn width(&self) -> u16 {
+        match self {
+            Self::Owned(pixmap) => pixmap.width(),
+            Self::Wrapped(wrapped) => wrapped.width,
+        }
+    }
+
+    pub(crate) fn height(&self) -> u16 {
+        match self {
+            Self::Owned(pixmap) => pixmap.height(),
+            Self::Wrapped(wrapped) => wrapped.height,
+        }
+    }
+
+    /// Copy rows of `width` pixels from `src` with their top-left corner at (x, y)
+    pub(crate) fn write_rows(&mut self, x: usize, y: usize, width: usize, src: &[PremulRgba8]) {
+        match self {
+            Self::Owned(pixmap) => {
+                let stride = pixmap.width() as usize;
+                let dst = pixmap.data_mut();
+                for (row, src) in src.chunks_exact(width).enumerate() {
+                    let start = (y + row) * stride + x;
+                    dst[start..start + width].copy_from_slice(src);
+                }
+            }
+            Self::Wrapped(wrapped) => {
+                for (row, src) in src.chunks_exact(width).enumerate() {
+                    let dst = &mut wrapped.row_mut(y + row)[x * 4..(x + width) * 4];
+                    crate::pixel::convert_row(src, VelloPixelFormat::Rgba8, dst);
+                }
+            }
+        }
+    }
+
+    /// Make every pixel transparent
+    pub(crate) fn clear(&mut self) {
+        match self {
+            Self::Owned(pixmap) => pixmap.data_as_u8_slice_mut().fill(0),
+            Self::Wrapped(wrapped) => {
+                for y in 0..wrapped.height as usize {
+                    wrapped.row_mut(y).fill(0);
+                }
+            }
+        }
+    }
+
+    /// Tag an owned pixmap with the color space it was rendered in; wrapped
+    /// memory carries no tag
+    pub(crate) fn set_color_space(&mut self, color_space: VelloColorSpace) {
+        if let Self::Owned(pixmap) = self {
+            pixmap.color_space = color_space;
+        }
+    }
+}
+
+/// Create a pixmap by copying premultiplied RGBA8 rows from caller memory
+/// `stride` is the distance between rows in bytes and must be at least width * 4
+#[no_mangle]
+pub extern "C" fn vello_pixmap_from_buffer(
+    data: *const u8,
+    data_len: usize,
+    width: u16,
+    height: u16,
+    stride: usize,
+) -> *mut VelloPixmap {
+    if data.is_null() {
+        set_last_error("Null data pointer");
+        return std::ptr::null_mut();
+    }
+
+    let row_len = width as usize * 4;
+    if stride < row_len {
+        set_last_error("Stride must be at least width * 4 bytes");
+        return std::ptr::null_mut();
+    }
+
+    if height > 0 && data_len < stride * (height as usize - 1) + row_len {
+        set_last_error("Buffer too small");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let src = unsafe { std::slice::from_raw_parts(data, data_len) };
+        let mut pixmap = Pixmap::new(width, height);
+        let dst = pixmap.data_as_u8_slice_mut();
+        if row_len > 0 {
+            for (y, row) in dst.chunks_exact_mut(row_len).enumerate() {
+                row.copy_from_slice(&src[y * stride..y * stride + row_len]);
+            }
+        }
+        handle::into_raw(PixmapHandle::new(pixmap)) as *mut VelloPixmap
+    })
+}
+
+/// Copy pixmap contents (premultiplied RGBA8) into a caller buffer with the given row stride
+#[no_mangle]
+pub extern "C" fn vello_pixmap_copy_to_buffer(
+    pixmap: *const VelloPixmap,
+    buffer: *mut u8,
+    buffer_len: usize,
+    stride: usize,
+) -> c_int {
+    if pixmap.is_null() || buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let row_len = pixmap.width() as usize * 4;
+        let height = pixmap.height() as usize;
+
+        if stride < row_len {
+            set_last_error("Stride must be at least width * 4 bytes");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        if height > 0 && buffer_len < stride * (height - 1) + row_len {
+            set_last_error("Buffer too small");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        if row_len > 0 {
+            let out = unsafe { std::slice::from_raw_parts_mut(buffer, buffer_len) };
+            for (y, row) in pixmap.data_as_u8_slice().chunks_exact(row_len).enumerate() {
+                out[y * stride..y * stride + row_len].copy_from_slice(row);
+            }
+        }
+        VELLO_OK
+    })
+}
+
+/// Wrap caller-owned memory as a pixmap (zero-copy when stride == width * 4)
+/// `stride` is the distance between rows in bytes and must be at least width * 4
+///
+/// The result can be passed to every `vello_render_context_render_*` function
+/// taking a pixmap, to `vello_pixmap_width`/`vello_pixmap_height`, and must be
+/// released with `vello_pixmap_free`, which leaves the memory untouched. Other
+/// pixmap functions reject it with `VELLO_ERROR_INVALID_HANDLE`.
+#[no_mangle]
+pub extern "C" fn vello_pixmap_wrap(
+    data: *mut u8,
+    width: u16,
+    height: u16,
+    stride: usize,
+) -> *mut VelloPixmap {
+    if data.is_null() {
+        set_last_error("Null data pointer");
+        return std::ptr::null_mut();
+    }
+
+    if stride < width as usize * 4 {
+        set_last_error("Stride must be at least width * 4 bytes");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let wrapped = WrappedPixmap {
+            header: HandleHeader::new::<WrappedPixmap>(),
+            data,
+            width,
+            height,
+            stride,
+            scratch: Vec::new(),
+            lock: HandleLock::default(),
+        };
+        handle::into_raw(wrapped) as *mut VelloPixmap
+    })
+}
+
+/// Render to pixmap
+#[no_mangle]
+pub extern "C" fn vello_render_context_render_to_pixmap(
+    ctx: *const VelloRenderContext,
+    pixmap: *mut VelloPixmap,
+) -> c_int {
+    if ctx.is_null() || pixmap.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        match RenderTarget::from_ptr(pixmap) {
+            RenderTarget::Owned(pixmap) => {
+                ctx.render_to_pixmap(pixmap);
+                pixmap.color_space = ctx.color_space();
+            }
+            RenderTarget::Wrapped(wrapped) => {
+                wrapped.render(ctx, ctx.render_settings().render_mode);
+            }
+        }
+        VELLO_OK
+    })
+}
+
+/// Render to pixmap with the given render mode instead of the context's
+///
+/// Lets one scene be previewed with `OptimizeSpeed` and exported with
+/// `OptimizeQuality` without rebuilding the context.
+#[no_mangle]
+pub extern "C" fn vello_render_context_render_to_pixmap_mode(
+    ctx: *const VelloRenderContext,
+    pixmap: *mut VelloPixmap,
+    render_mode: VelloRenderMode,
+) -> c_int {
+    if ctx.is_null() || pixmap.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        match RenderTarget::from_ptr(pixmap) {
+            RenderTarget::Owned(pixmap) => {
+                let (width, height) = (pixmap.width(), pixmap.height());
+                let buffer = pixmap.data_as_u8_slice_mut();
+                ctx.render_to_buffer(buffer, width, height, render_mode.into());
+                pixmap.color_space = ctx.color_space();
+            }
+            RenderTarget::Wrapped(wrapped) => wrapped.render(ctx, render_mode.into()),
+        }
+        VELLO_OK
+    })
+}
+
+/// Render into the region of a larger pixmap whose top-left corner is (dst_x, dst_y)
+///
+/// The region has the size of the context and must lie within the pixmap;
+/// pixels outside of it are left untouched. Several contexts may render into
+/// regions of the same pixmap from different threads at once: they rasterize
+/// in parallel and only copying the result into the pixmap is serialized. The
+/// pixmap's color space tag is not changed.
+#[no_mangle]
+pub extern "C" fn vello_render_context_render_to_pixmap_at(
+    ctx: *const VelloRenderContext,
+    pixmap: *mut VelloPixmap,
+    dst_x: u16,
+    dst_y: u16,
+) -> c_int {
+    if ctx.is_null() || pixmap.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        // Rasterize without holding the pixmap, releasing the context again
+        // before the pixmap is locked
+        let rendered = {
+            let _scope = CallScope::enter();
+            let ctx = RenderContextHandle::from_ptr(ctx);
+            let mut rendered = Pixmap::new(ctx.width(), ctx.height());
+            ctx.render_to_pixmap(&mut rendered);
+            rendered
+        };
+        let (width, height) = (rendered.width() as usize, rendered.height() as usize);
+
+        let mut target = RenderTarget::from_ptr(pixmap);
+        if dst_x as usize + width > target.width() as usize
+            || dst_y as usize + height > target.height() as usize
+        {
+            set_last_error("Destination region exceeds the pixmap bounds");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        if width == 0 {
+            return VELLO_OK;
+        }
+
+        target.write_rows(dst_x as usize, dst_y as usize, width, rendered.data());
+        VELLO_OK
+    })
+}
+
+/// Render only the given regions into a pixmap, leaving all other pixels untouched
+///
+/// The pixmap must match the context dimensions and normally holds the previous
+/// frame. Rectangles are expanded to whole pixels and clipped to the surface.
+/// Only the dirty rectangles are rasterized, so the cost follows their area.
+#[no_mangle]
+pub extern "C" fn vello_render_context_render_dirty(
+    ctx: *const VelloRenderContext,
+    pixmap: *mut VelloPixmap,
+    dirty_rects: *const VelloRect,
+    count: usize,
+) -> c_int {
+    if ctx.is_null() || pixmap.is_null() || (dirty_rects.is_null() && count > 0) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        let mut target = RenderTarget::from_ptr(pixmap);
+        if target.width() != ctx.width() || target.height() != ctx.height() {
+            set_last_error("Pixmap dimensions must match the render context");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let (width, height) = (ctx.width() as f64, ctx.height() as f64);
+        let rects: Vec<(usize, usize, usize, usize)> = unsafe {
+            std::slice::from_raw_parts(dirty_rects, count)
+        }
+        .iter()
+        .filter_map(|r| {
+            let x0 = r.x0.min(r.x1).floor().clamp(0.0, width) as usize;
+            let y0 = r.y0.min(r.y1).floor().clamp(0.0, height) as usize;
+            let x1 = r.x0.max(r.x1).ceil().clamp(0.0, width) as usize;
+            let y1 = r.y0.max(r.y1).ceil().clamp(0.0, height) as usize;
+            (x0 < x1 && y0 < y1).then_some((x0, y0, x1, y1))
+        })
+        .collect();
+        for (x0, y0, x1, y1) in rects {
+            let width = x1 - x0;
+            let rendered = ctx.render_region(x0 as u16, y0 as u16, width as u16, (y1 - y0) as u16);
+            target.write_rows(x0, y0, width, rendered.data());
+        }
+        target.set_color_space(ctx.color_space());
+        VELLO_OK
+    })
+}
+
+/// Get the number of `tile_size` x `tile_size` tiles covering a context
+///
+/// Tiles are numbered row by row from the top-left corner.
+#[no_mangle]
+pub extern "C" fn vello_render_context_tile_count(
+    ctx: *const VelloRenderContext,
+    tile_size: u16,
+) -> usize {
+    if ctx.is_null() || tile_size == 0 || !RenderContextHandle::check(ctx) {
+        return 0;
+    }
+    let _scope = CallScope::enter();
+    let ctx = RenderContextHandle::from_ptr(ctx);
+    let columns = (ctx.width() as usize).div_ceil(tile_size as usize);
+    let rows = (ctx.height() as usize).div_ceil(tile_size as usize);
+    columns * rows
+}
+
+/// Render one tile into a pixmap whose size is the tile size
+///
+/// The pixmap must be square with the `tile_size` passed to
+/// `vello_render_context_tile_count`. Parts of edge tiles outside the surface
+/// are transparent.
+#[no_mangle]
+pub extern "C" fn vello_render_context_render_tile(
+    ctx: *const VelloRenderContext,
+    tile_index: usize,
+    pixmap: *mut VelloPixmap,
+) -> c_int {
+    if ctx.is_null() || pixmap.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr(ctx);
+        let mut target = RenderTarget::from_ptr(pixmap);
+        let tile_size = target.width() as usize;
+        if tile_size == 0 || target.height() as usize != tile_size {
+            set_last_error("Tile pixmap must be square and non-empty");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        let columns = (ctx.width() as usize).div_ceil(tile_size);
+        let rows = (ctx.height() as usize).div_ceil(tile_size);
+        if tile_index >= columns * rows {
+            set_last_error("Tile index out of range");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let x0 = tile_index % columns * tile_size;
+        let y0 = tile_index / columns * tile_size;
+        let x1 = (x0 + tile_size).min(ctx.width() as usize);
+        let y1 = (y0 + tile_size).min(ctx.height() as usize);
+        let width = x1 - x0;
+        let rendered = ctx.render_region(x0 as u16, y0 as u16, width as u16, (y1 - y0) as u16);
+
+        target.clear();
+        target.write_rows(0, 0, width, rendered.data());
+        target.set_color_space(ctx.color_space());
+        VELLO_OK
+    })
+}
+
+#[cfg(feature = "png")]
+#[no_mangle]
+pub extern "C" fn vello_pixmap_from_png(data: *const u8, len: usize) -> *mut VelloPixmap {
+    if data.is_null() || len == 0 {
+        set_last_error("Null or empty PNG data");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let slice = unsafe { std::slice::from_raw_parts(data, len) };
+        match Pixmap::from_png(slice) {
+            Ok(pixmap) => handle::into_raw(PixmapHandle::new(pixmap)) as *mut VelloPixmap,
+            Err(e) => {
+                set_last_error(format!("PNG decode error: {:?}", e));
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[cfg(feature = "png")]
+#[no_mangle]
+pub extern "C" fn vello_pixmap_to_png(
+    pixmap: *const VelloPixmap,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    if pixmap.is_null() || out_data.is_null() || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        match pixmap.pixmap.clone().into_png() {
+            Ok(png_data) => {
+                let mut boxed = png_data.into_boxed_slice();
+                unsafe {
+                    *out_len = boxed.len();
+                    *out_data = boxed.as_mut_ptr();
+                    std::mem::forget(boxed); // Prevent deallocation
+                }
+                VELLO_OK
+            }
+            Err(e) => {
+                set_last_error(format!("PNG encode error: {:?}", e));
+                VELLO_ERROR_PNG_ENCODE
+            }
+        }
+    })
+}
+
+/// Load a pixmap from a PNG file (UTF-8 path)
+#[cfg(feature = "png")]
+#[no_mangle]
+pub extern "C" fn vello_pixmap_from_png_file(path: *const c_char) -> *mut VelloPixmap {
+    if path.is_null() {
+        set_last_error("Null path");
+        return std::ptr::null_mut();
+    }
+
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(p) => p,
+        Err(_) => {
+            set_last_error("Invalid UTF-8");
+            return std::ptr::null_mut();
+        }
+    };
+
+    ffi_catch_ptr!({
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) => {
+                set_last_error(format!("Failed to read {}: {}", path, e));
+                return std::ptr::null_mut();
+            }
+        };
+        match Pixmap::from_png(data.as_slice()) {
+            Ok(pixmap) => handle::into_raw(PixmapHandle::new(pixmap)) as *mut VelloPixmap,
+            Err(e) => {
+                set_last_error(format!("PNG decode error: {:?}", e));
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Encode a pixmap as PNG and write it to a file (UTF-8 path), replacing any existing file
+#[cfg(feature = "png")]
+#[no_mangle]
+pub extern "C" fn vello_pixmap_to_png_file(
+    pixmap: *const VelloPixmap,
+    path: *const c_char,
+) -> c_int {
+    if pixmap.is_null() || path.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(p) => p,
+        Err(_) => {
+            set_last_error("Invalid UTF-8");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+    };
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        let png_data = match pixmap.pixmap.clone().into_png() {
+            Ok(data) => data,
+            Err(e) => {
+                set_last_error(format!("PNG encode error: {:?}", e));
+                return VELLO_ERROR_PNG_ENCODE;
+            }
+        };
+        match std::fs::write(path, png_data) {
+            Ok(()) => VELLO_OK,
+            Err(e) => {
+                set_last_error(format!("Failed to write {}: {}", path, e));
+                VELLO_ERROR_IO
+            }
+        }
+    })
+}
+
+#[cfg(feature = "png")]
+#[no_mangle]
+pub extern "C" fn vello_png_data_free(data: *mut u8, len: usize) {
+    if !data.is_null() && len > 0 {
+        unsafe {
+            let _ = Box::from_raw(std::slice::from_raw_parts_mut(data, len));
+        }
+    }
+}
+
+/// Decode a lossy or lossless WebP image into a new pixmap
+///
+/// Straight alpha is premultiplied and images without alpha are opaque.
+/// Returns `VELLO_ERROR_WEBP_DECODE` if the data is not a decodable WebP
+/// image, and `VELLO_ERROR_INVALID_PARAMETER` if it is larger than a pixmap
+/// can hold.
+#[cfg(feature = "webp")]
+#[no_mangle]
+pub extern "C" fn vello_pixmap_from_webp(
+    data: *const u8,
+    len: usize,
+    out_pixmap: *mut *mut VelloPixmap,
+) -> c_int {
+    if data.is_null() || out_pixmap.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let slice = unsafe { std::slice::from_raw_parts(data, len) };
+        let Some(image) = webp::Decoder::new(slice).decode() else {
+            set_last_error("WebP decode error: not a supported WebP image");
+            return VELLO_ERROR_WEBP_DECODE;
+        };
+
+        let (width, height) = (image.width(), image.height());
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            set_last_error("WebP image too large");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let channels = if image.is_alpha() { 4 } else { 3 };
+        let pixels = image
+            .chunks_exact(channels)
+            .map(|c| {
+                let a = if channels == 4 { c[3] } else { 255 };
+                crate::pixel::premultiply_rgba8([c[0], c[1], c[2], a])
+            })
+            .collect();
+        let pixmap = Pixmap::from_parts(pixels, width as u16, height as u16);
+        let handle = handle::into_raw(PixmapHandle::new(pixmap)) as *mut VelloPixmap;
+        unsafe { *out_pixmap = handle };
+        VELLO_OK
+    })
+}
+
+/// Encode pixmap as WebP; `quality` (0-100) is ignored for lossless encoding
+#[cfg(feature = "webp")]
+#[no_mangle]
+pub extern "C" fn vello_pixmap_to_webp(
+    pixmap: *const VelloPixmap,
+    lossless: bool,
+    quality: f32,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    if pixmap.is_null() || out_data.is_null() || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    if !lossless && !(0.0..=100.0).contains(&quality) {
+        set_last_error("WebP quality must be between 0 and 100");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let pixmap = PixmapHandle::from_ptr(pixmap);
+        if pixmap.width() == 0 || pixmap.height() == 0 {
+            set_last_error("WebP encode error: empty pixmap");
+            return VELLO_ERROR_WEBP_ENCODE;
+        }
+
+        let straight: Vec<u8> = pixmap
+            .data()
+            .iter()
+            .flat_map(|px| crate::pixel::unpremultiply_rgba8(*px))
+            .collect();
+        let encoder =
+            webp::Encoder::from_rgba(&straight, pixmap.width() as u32, pixmap.height() as u32);
+        let encoded = if lossless {
+            encoder.encode_lossless()
+        } else {
+            encoder.encode(quality)
+        };
+
+        let mut boxed = encoded.to_vec().into_boxed_slice();
+        unsafe {
+            *out_len = boxed.len();
+            *out_data = boxed.as_mut_ptr();
+            std::mem::forget(boxed); // Prevent deallocation
+        }
+        VELLO_OK
+    })
+}
+
+#[cfg(feature = "webp")]
+#[no_mangle]
+pub extern "C" fn vello_webp_data_free(data: *mut u8, len: usize) {
+    if !data.is_null() && len > 0 {
+        unsafe {
+            let _ = Box::from_raw(std::slice::from_raw_parts_mut(data, len));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_flush, vello_render_context_free, vello_render_context_new,
+        vello_render_context_pop_layer, vello_render_context_push_opacity_layer,
+    };
+    use crate::test_util::{fill, pixmap_pixels, render};
+
+    #[test]
+    fn render_dirty_updates_only_the_dirty_rects() {
+        let ctx = vello_render_context_new(40, 24);
+        fill(ctx, [255, 0, 0, 255], 2.5, 1.5, 37.0, 22.5);
+        assert_eq!(vello_render_context_push_opacity_layer(ctx, 0.5), VELLO_OK);
+        fill(ctx, [0, 0, 255, 255], 10.25, 5.0, 30.0, 20.75);
+        assert_eq!(vello_render_context_pop_layer(ctx), VELLO_OK);
+        let expected = render(ctx, 40, 24);
+
+        let pixmap = vello_pixmap_new(40, 24);
+        let pixels = pixmap_pixels(pixmap);
+        pixels.fill(VelloPremulRgba8 { r: 1, g: 2, b: 3, a: 4 });
+
+        // The second rect is clipped to the surface
+        let rects = [
+            VelloRect { x0: 9.5, y0: 3.0, x1: 21.0, y1: 11.25 },
+            VelloRect { x0: 28.0, y0: 17.0, x1: 50.0, y1: 30.0 },
+        ];
+        let result = vello_render_context_render_dirty(ctx, pixmap, rects.as_ptr(), rects.len());
+        assert_eq!(result, VELLO_OK);
+
+        let dirty = |x, y| (9..21).contains(&x) && (3..12).contains(&y) || x >= 28 && y >= 17;
+        for (i, px) in pixels.iter().enumerate() {
+            let (x, y) = (i % 40, i / 40);
+            let rgba = if dirty(x, y) { expected[i] } else { [1, 2, 3, 4] };
+            assert_eq!([px.r, px.g, px.b, px.a], rgba, "pixel ({x}, {y})");
+        }
+
+        vello_pixmap_free(pixmap);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn tiles_assemble_the_full_frame() {
+        let ctx = vello_render_context_new(40, 24);
+        fill(ctx, [255, 0, 0, 255], 2.5, 1.5, 37.0, 22.5);
+        fill(ctx, [0, 0, 255, 128], 10.25, 5.0, 30.0, 20.75);
+        let expected = render(ctx, 40, 24);
+
+        let tiles = vello_render_context_tile_count(ctx, 16);
+        assert_eq!(tiles, 6);
+        let pixmap = vello_pixmap_new(16, 16);
+        for index in 0..tiles {
+            let result = vello_render_context_render_tile(ctx, index, pixmap);
+            assert_eq!(result, VELLO_OK);
+            let pixels = pixmap_pixels(pixmap);
+
+            let (x0, y0) = (index % 3 * 16, index / 3 * 16);
+            for (i, px) in pixels.iter().enumerate() {
+                let (x, y) = (x0 + i % 16, y0 + i / 16);
+                // Edge tiles are transparent outside the surface
+                let rgba = if x < 40 && y < 24 { expected[y * 40 + x] } else { [0; 4] };
+                assert_eq!([px.r, px.g, px.b, px.a], rgba, "pixel ({x}, {y})");
+            }
+        }
+
+        let result = vello_render_context_render_tile(ctx, tiles, pixmap);
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        vello_pixmap_free(pixmap);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn threads_render_side_by_side_into_one_pixmap() {
+        let pixmap = vello_pixmap_new(32, 16);
+        let address = pixmap as usize;
+        let workers: Vec<_> = [[255, 0, 0, 255], [0, 0, 255, 255]]
+            .into_iter()
+            .enumerate()
+            .map(|(i, rgba)| {
+                std::thread::spawn(move || {
+                    let ctx = vello_render_context_new(16, 16);
+                    fill(ctx, rgba, 0.0, 0.0, 16.0, 16.0);
+                    assert_eq!(vello_render_context_flush(ctx), VELLO_OK);
+                    let pixmap = address as *mut VelloPixmap;
+                    let x = i as u16 * 16;
+                    let result = vello_render_context_render_to_pixmap_at(ctx, pixmap, x, 0);
+                    vello_render_context_free(ctx);
+                    result
+                })
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), VELLO_OK);
+        }
+
+        let pixels = pixmap_pixels(pixmap);
+        let px = |x: usize, y: usize| pixels[y * 32 + x];
+        assert_eq!([px(15, 8).r, px(15, 8).b], [255, 0]);
+        assert_eq!([px(16, 8).r, px(16, 8).b], [0, 255]);
+
+        let ctx = vello_render_context_new(16, 16);
+        let result = vello_render_context_render_to_pixmap_at(ctx, pixmap, 17, 0);
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        vello_render_context_free(ctx);
+        vello_pixmap_free(pixmap);
+    }
+
+    #[test]
+    fn wrapped_pixmaps_are_render_targets() {
+        let ctx = vello_render_context_new(8, 4);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 4.0, 4.0);
+        fill(ctx, [0, 0, 128, 128], 4.0, 0.0, 8.0, 4.0);
+        let mut expected = render(ctx, 8, 4);
+
+        // Two bytes of padding per row are left untouched
+        let mut buffer = vec![7u8; 34 * 4];
+        let wrapped = vello_pixmap_wrap(buffer.as_mut_ptr(), 8, 4, 34);
+        assert!(!wrapped.is_null());
+        assert_eq!(vello_pixmap_width(wrapped), 8);
+        assert_eq!(vello_render_context_render_to_pixmap(ctx, wrapped), VELLO_OK);
+
+        let corner = vello_render_context_new(2, 2);
+        fill(corner, [0, 255, 0, 255], 0.0, 0.0, 2.0, 2.0);
+        assert_eq!(vello_render_context_flush(corner), VELLO_OK);
+        assert_eq!(vello_render_context_render_to_pixmap_at(corner, wrapped, 6, 2), VELLO_OK);
+        for y in 2..4 {
+            expected[y * 8 + 6..y * 8 + 8].fill([0, 255, 0, 255]);
+        }
+
+        for (y, row) in buffer.chunks_exact(34).enumerate() {
+            assert_eq!(&row[32..], &[7, 7]);
+            for (x, px) in row[..32].chunks_exact(4).enumerate() {
+                assert_eq!(px, &expected[y * 8 + x]);
+            }
+        }
+
+        // The library does not own the memory, so it cannot hand out its pixels
+        let (mut data, mut len) = (std::ptr::null_mut(), 0);
+        let result = vello_pixmap_data_mut(wrapped, &mut data, &mut len);
+        assert_eq!(result, VELLO_ERROR_INVALID_HANDLE);
+
+        vello_pixmap_free(wrapped);
+        vello_render_context_free(corner);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn blit_matches_per_pixel_composition() {
+        // Longer than two blend chunks, so both the chunks and the rest are covered
+        let (width, height) = (37, 3);
+        let pixel = |i: usize, seed: usize| {
+            let a = ((i * 53 + seed) % 256) as u8;
+            let c = |k: usize| ((i * k + seed) % (a as usize + 1)) as u8;
+            VelloPremulRgba8 { r: c(7), g: c(13), b: c(29), a }
+        };
+        let filled = |seed: usize| {
+            let pixmap = vello_pixmap_new(width, height);
+            let pixels = pixmap_pixels(pixmap);
+            for (i, px) in pixels.iter_mut().enumerate() {
+                *px = pixel(i, seed);
+            }
+            pixmap
+        };
+        let premul = |px: VelloPremulRgba8| PremulRgba8 { r: px.r, g: px.g, b: px.b, a: px.a };
+
+        let src = filled(11);
+        let ops = [
+            VelloCompose::Clear,
+            VelloCompose::Copy,
+            VelloCompose::Dest,
+            VelloCompose::SrcOver,
+            VelloCompose::DestOver,
+            VelloCompose::SrcIn,
+            VelloCompose::DestIn,
+            VelloCompose::SrcOut,
+            VelloCompose::DestOut,
+            VelloCompose::SrcAtop,
+            VelloCompose::DestAtop,
+            VelloCompose::Xor,
+            VelloCompose::Plus,
+            VelloCompose::PlusLighter,
+        ];
+        for op in ops {
+            let dst = filled(5);
+            assert_eq!(vello_pixmap_blit(dst, 0, 0, src, op), VELLO_OK);
+            let pixels = pixmap_pixels(dst);
+            for (i, px) in pixels.iter().enumerate() {
+                let expected = crate::pixel::compose(premul(pixel(i, 11)), premul(pixel(i, 5)), op);
+                assert_eq!(premul(*px), expected, "{op:?} at pixel {i}");
+            }
+            vello_pixmap_free(dst);
+        }
+        vello_pixmap_free(src);
+    }
+
+    #[test]
+    fn alpha_conversions_round_like_integer_division() {
+        // Every alpha and channel pair, including channels above their alpha
+        let pixmap = vello_pixmap_new(256, 256);
+        let pixels = pixmap_pixels(pixmap);
+        for (i, px) in pixels.iter_mut().enumerate() {
+            let (c, a) = ((i % 256) as u8, (i / 256) as u8);
+            *px = VelloPremulRgba8 { r: c, g: 255 - c, b: c / 2, a };
+        }
+        let expected = |c: u8, a: u8| match a as u32 {
+            0 => 0,
+            a => ((c as u32 * 255 + a / 2) / a).min(255) as u8,
+        };
+
+        let mut straight = vec![0u8; 256 * 256 * 4];
+        let result = vello_pixmap_to_unpremultiplied(pixmap, straight.as_mut_ptr(), straight.len());
+        assert_eq!(result, VELLO_OK);
+        for (out, px) in straight.chunks_exact(4).zip(pixels.iter()) {
+            let want = [expected(px.r, px.a), expected(px.g, px.a), expected(px.b, px.a), px.a];
+            assert_eq!(out, want, "{px:?}");
+        }
+
+        let result = vello_pixmap_load_unpremultiplied(pixmap, straight.as_ptr(), straight.len());
+        assert_eq!(result, VELLO_OK);
+        let pixels = pixmap_pixels(pixmap);
+        for (px, c) in pixels.iter().zip(straight.chunks_exact(4)) {
+            let channel = |v: u8| ((v as u32 * c[3] as u32 + 127) / 255) as u8;
+            let want = [channel(c[0]), channel(c[1]), channel(c[2]), c[3]];
+            assert_eq!([px.r, px.g, px.b, px.a], want);
+        }
+        vello_pixmap_free(pixmap);
+    }
+
+    #[cfg(feature = "webp")]
+    #[test]
+    fn webp_round_trips_and_reports_decode_errors() {
+        let ctx = vello_render_context_new(8, 4);
+        fill(ctx, [255, 0, 0, 255], 0.0, 0.0, 4.0, 4.0);
+        fill(ctx, [0, 0, 128, 128], 4.0, 0.0, 8.0, 4.0);
+        let expected = render(ctx, 8, 4);
+        let pixmap = vello_pixmap_new(8, 4);
+        assert_eq!(vello_render_context_render_to_pixmap(ctx, pixmap), VELLO_OK);
+
+        let (mut data, mut len) = (std::ptr::null_mut(), 0);
+        assert_eq!(vello_pixmap_to_webp(pixmap, true, 0.0, &mut data, &mut len), VELLO_OK);
+        let mut decoded = std::ptr::null_mut();
+        assert_eq!(vello_pixmap_from_webp(data, len, &mut decoded), VELLO_OK);
+        vello_webp_data_free(data, len);
+
+        let pixels = pixmap_pixels(decoded);
+        let pixels: Vec<[u8; 4]> = pixels.iter().map(|px| [px.r, px.g, px.b, px.a]).collect();
+        assert_eq!(pixels, expected);
+
+        let garbage = [0x52, 0x49, 0x46, 0x46, 0, 0, 0, 0];
+        let mut failed = std::ptr::null_mut();
+        let result = vello_pixmap_from_webp(garbage.as_ptr(), garbage.len(), &mut failed);
+        assert_eq!(result, VELLO_ERROR_WEBP_DECODE);
+        assert!(failed.is_null());
+
+        vello_pixmap_free(decoded);
+        vello_pixmap_free(pixmap);
+        vello_render_context_free(ctx);
     }
 }
@@ -0,0 +1,139 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Recycling of same-sized pixmaps
+//!
+//! Presentation loops render into a fresh target every frame. A pool keeps
+//! released pixmaps around and hands them out again, so steady-state frames
+//! allocate nothing.
+
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use vello_cpu::Pixmap;
+
+use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::pixmap::PixmapHandle;
+use crate::types::*;
+use crate::{ffi_catch, ffi_catch_ptr};
+
+#[repr(C)]
+pub struct VelloPixmapPool {
+    _private: [u8; 0],
+}
+
+/// Object behind a `VelloPixmapPool` pointer
+#[repr(C)]
+pub(crate) struct PixmapPoolHandle {
+    header: HandleHeader,
+    width: u16,
+    height: u16,
+    /// Maximum number of idle pixmaps kept for reuse
+    max_idle: usize,
+    idle: Mutex<Vec<Box<PixmapHandle>>>,
+}
+
+impl TypedHandle for PixmapPoolHandle {
+    const TAG: u32 = handle::tag(b"VPPL");
+    const NAME: &'static str = "pixmap pool";
+}
+
+/// Create a pool of `width` x `height` pixmaps keeping at most `max_idle`
+/// released pixmaps for reuse
+///
+/// The pool may be used from several threads at once.
+#[no_mangle]
+pub extern "C" fn vello_pixmap_pool_new(
+    width: u16,
+    height: u16,
+    max_idle: usize,
+) -> *mut VelloPixmapPool {
+    ffi_catch_ptr!({
+        let pool = PixmapPoolHandle {
+            header: HandleHeader::new::<PixmapPoolHandle>(),
+            width,
+            height,
+            max_idle,
+            idle: Mutex::new(Vec::new()),
+        };
+        handle::into_raw(pool) as *mut VelloPixmapPool
+    })
+}
+
+/// Free a pool and its idle pixmaps
+///
+/// Pixmaps acquired from the pool stay valid and are freed with
+/// `vello_pixmap_free`.
+#[no_mangle]
+pub extern "C" fn vello_pixmap_pool_free(pool: *mut VelloPixmapPool) {
+    if !pool.is_null() && PixmapPoolHandle::check(pool) {
+        handle::free::<PixmapPoolHandle, _>(pool);
+    }
+}
+
+/// Take a pixmap from the pool, allocating one if none is idle
+///
+/// Recycled pixmaps keep the pixels last rendered into them and are tagged
+/// sRGB. Return the pixmap with `vello_pixmap_pool_release`.
+#[no_mangle]
+pub extern "C" fn vello_pixmap_pool_acquire(pool: *const VelloPixmapPool) -> *mut VelloPixmap {
+    if pool.is_null() {
+        set_last_error("Null pixmap pool pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let pool = PixmapPoolHandle::from_ptr(pool);
+        let recycled = pool.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        let pixmap = match recycled {
+            Some(mut pixmap) => {
+                pixmap.color_space = VelloColorSpace::Srgb;
+                pixmap
+            }
+            None => Box::new(PixmapHandle::new(Pixmap::new(pool.width, pool.height))),
+        };
+        handle::into_raw_box(pixmap) as *mut VelloPixmap
+    })
+}
+
+/// Return a pixmap to the pool
+///
+/// The pixmap must not be used afterwards. Pixmaps of a different size, or
+/// beyond the pool's idle limit, are freed instead.
+#[no_mangle]
+pub extern "C" fn vello_pixmap_pool_release(
+    pool: *const VelloPixmapPool,
+    pixmap: *mut VelloPixmap,
+) -> c_int {
+    if pool.is_null() || pixmap.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let pool = PixmapPoolHandle::from_ptr(pool);
+        let Some(pixmap) = handle::take::<PixmapHandle, _>(pixmap) else {
+            set_last_error("Invalid pixmap handle");
+            return VELLO_ERROR_INVALID_HANDLE;
+        };
+        let mut idle = pool.idle.lock().unwrap_or_else(|e| e.into_inner());
+        if pixmap.width() == pool.width
+            && pixmap.height() == pool.height
+            && idle.len() < pool.max_idle
+        {
+            idle.push(pixmap);
+        }
+        VELLO_OK
+    })
+}
+
+/// Get the number of idle pixmaps held by the pool
+#[no_mangle]
+pub extern "C" fn vello_pixmap_pool_idle_count(pool: *const VelloPixmapPool) -> usize {
+    if pool.is_null() || !PixmapPoolHandle::check(pool) {
+        return 0;
+    }
+    let pool = unsafe { &*(pool as *const PixmapPoolHandle) };
+    pool.idle.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
@@ -1,43 +1,171 @@
 // Copyright 2025 Wieslaw Soltes
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+use crate::context::{
+    color_stops, dash_path, to_dash_tolerance, to_dashed_stroke, to_stroke, undashed,
+    RenderContextHandle,
+};
 use crate::error::set_last_error;
-use crate::types::{VelloAffine, VelloFillRule, VelloStroke};
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::{ffi_catch, ffi_catch_ptr};
+use crate::image::{ImageHandle, VelloImage};
+use crate::mask::MaskHandle;
+use crate::path::PathHandle;
+use crate::text::{FontHandle, VelloFontData, VelloGlyph};
+use crate::types::{
+    VelloAffine, VelloBezPath, VelloBlendMode, VelloColorSpace, VelloColorStop, VelloCompose,
+    VelloExtend, VelloFillRule, VelloMask, VelloMix, VelloRenderContext, VelloStroke,
+    VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_NULL_POINTER, VELLO_OK,
+};
 use crate::VelloRect;
 use std::ffi::c_void;
-use vello_common::recording::Recording as RustRecording;
+use std::os::raw::c_int;
+use std::sync::Arc;
+use vello_cpu::kurbo::{Affine, BezPath, Shape, Stroke};
+use vello_common::recording::{Recorder, RenderCommand, Recording as RustRecording};
 use vello_cpu::RenderContext as RustRenderContext;
 
+use crate::recording_chunks::{strip_offset, PreparedChunks};
+use crate::scene::{CommandReplay, DrawTarget, Scene};
+
 /// Opaque handle to a Recording.
-pub struct VelloRecording(pub(crate) RustRecording);
+///
+/// The command list is the source of truth. Strips are cached per chunk of
+/// commands so that edits only re-prepare the chunks they touch.
+#[repr(C)]
+pub struct VelloRecording {
+    header: HandleHeader,
+    pub(crate) recording: RustRecording,
+    pub(crate) chunks: PreparedChunks,
+}
+
+impl TypedHandle for VelloRecording {
+    const TAG: u32 = handle::tag(b"VREC");
+    const NAME: &'static str = "recording";
+}
+
+impl VelloRecording {
+    pub(crate) fn new(recording: RustRecording) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            recording,
+            chunks: PreparedChunks::default(),
+        }
+    }
+
+    /// Whether every command has cached strips
+    pub(crate) fn is_prepared(&self) -> bool {
+        self.chunks.covers(self.recording.command_count())
+    }
+
+    /// Generate strips for the commands that have none
+    pub(crate) fn prepare(&mut self, ctx: &mut RustRenderContext) {
+        self.chunks.prepare(ctx, self.recording.commands());
+    }
+
+    /// Draw the recording on a render context's scene
+    pub(crate) fn execute(&self, scene: &mut Scene) {
+        scene.execute_recording_with(self.recording.commands(), |ctx| self.execute_on(ctx));
+    }
+
+    /// Draw the recording on a bare context, using cached strips if complete
+    pub(crate) fn execute_on(&self, ctx: &mut RustRenderContext) {
+        use vello_common::recording::Recordable;
+
+        if self.is_prepared() {
+            self.chunks.execute(ctx);
+        } else {
+            ctx.execute_recording(&self.recording);
+        }
+    }
+}
+
+/// The object behind every recorder pointer handed out by
+/// `vello_render_context_record` and `vello_render_context_begin_record`
+#[repr(C)]
+pub(crate) struct RecorderHandle {
+    header: HandleHeader,
+    recorder: RecorderSlot,
+    /// Gradient ramp size of the context the recorder was opened on
+    gradient_ramp_size: u16,
+    /// Dashed stroke and user-space tolerance set with
+    /// `vello_recorder_set_stroke_dashed`, if dashes are flattened here
+    dashed: Option<(Stroke, f64)>,
+}
+
+enum RecorderSlot {
+    /// Recorder lent to a `vello_render_context_record` callback
+    Borrowed(*mut Recorder<'static>),
+    /// Recorder opened by `vello_render_context_begin_record`
+    Owned(Recorder<'static>),
+}
+
+impl TypedHandle for RecorderHandle {
+    const TAG: u32 = handle::tag(b"VRCD");
+    const NAME: &'static str = "recorder";
+}
+
+impl RecorderHandle {
+    pub(crate) fn recorder(&mut self) -> &mut Recorder<'static> {
+        match &mut self.recorder {
+            // Only registered for the duration of the callback it was lent to
+            RecorderSlot::Borrowed(recorder) => unsafe { &mut **recorder },
+            RecorderSlot::Owned(recorder) => recorder,
+        }
+    }
+
+    /// Stroke a path, dashing it here if a dash tolerance is set
+    pub(crate) fn stroke_path(&mut self, path: &BezPath) {
+        match self.dashed.clone() {
+            Some((stroke, tolerance)) => {
+                let dashes = dash_path(path, &stroke, tolerance);
+                let recorder = self.recorder();
+                recorder.set_stroke(undashed(&stroke));
+                recorder.stroke_path(&dashes);
+                recorder.set_stroke(stroke);
+            }
+            None => self.recorder().stroke_path(path),
+        }
+    }
+
+    /// Stroke a rectangle, dashing it here if a dash tolerance is set
+    pub(crate) fn stroke_rect(&mut self, rect: &vello_cpu::kurbo::Rect) {
+        if self.dashed.is_some() {
+            self.stroke_path(&rect.to_path(0.1));
+        } else {
+            self.recorder().stroke_rect(rect);
+        }
+    }
+}
 
 /// Create a new empty recording
 #[no_mangle]
 pub extern "C" fn vello_recording_new() -> *mut VelloRecording {
-    Box::into_raw(Box::new(VelloRecording(RustRecording::new())))
+    ffi_catch_ptr!({ handle::into_raw(VelloRecording::new(RustRecording::new())) })
 }
 
 /// Free a recording
 #[no_mangle]
 pub extern "C" fn vello_recording_free(recording: *mut VelloRecording) {
-    if !recording.is_null() {
-        unsafe {
-            drop(Box::from_raw(recording));
-        }
+    if !recording.is_null() && VelloRecording::check(recording) {
+        handle::free::<VelloRecording, _>(recording);
     }
 }
 
 /// Clear all recorded commands
 #[no_mangle]
-pub extern "C" fn vello_recording_clear(recording: *mut VelloRecording) -> i32 {
+pub extern "C" fn vello_recording_clear(recording: *mut VelloRecording) -> c_int {
     if recording.is_null() {
         set_last_error("Null recording pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let recording = unsafe { &mut *recording };
-    recording.0.clear();
-    0 // Success
+    ffi_catch!({
+        let recording = VelloRecording::from_ptr_mut(recording);
+        recording.recording.clear();
+        recording.chunks.clear();
+        VELLO_OK
+    })
 }
 
 /// Get the number of recorded commands
@@ -48,8 +176,12 @@ pub extern "C" fn vello_recording_len(recording: *const VelloRecording) -> usize
         return 0;
     }
 
+    if !VelloRecording::check(recording) {
+        return 0;
+    }
+
     let recording = unsafe { &*recording };
-    recording.0.command_count()
+    recording.recording.command_count()
 }
 
 /// Check if recording has cached strips
@@ -60,8 +192,12 @@ pub extern "C" fn vello_recording_has_cached_strips(recording: *const VelloRecor
         return 0;
     }
 
+    if !VelloRecording::check(recording) {
+        return 0;
+    }
+
     let recording = unsafe { &*recording };
-    if recording.0.has_cached_strips() {
+    if recording.is_prepared() {
         1
     } else {
         0
@@ -76,8 +212,12 @@ pub extern "C" fn vello_recording_strip_count(recording: *const VelloRecording)
         return 0;
     }
 
+    if !VelloRecording::check(recording) {
+        return 0;
+    }
+
     let recording = unsafe { &*recording };
-    recording.0.strip_count()
+    recording.chunks.strip_count()
 }
 
 /// Get the number of cached alpha bytes
@@ -88,8 +228,335 @@ pub extern "C" fn vello_recording_alpha_count(recording: *const VelloRecording)
         return 0;
     }
 
+    if !VelloRecording::check(recording) {
+        return 0;
+    }
+
     let recording = unsafe { &*recording };
-    recording.0.alpha_count()
+    recording.chunks.alpha_count()
+}
+
+/// Re-issue recorded commands on a recorder or context
+///
+/// Every transform in `commands` is pre-multiplied by `transform`. Outline glyph
+/// commands carry their own absolute transform and fill rule, so the tracked
+/// state is restored after each of them.
+pub(crate) fn replay_commands<'a>(
+    target: &mut impl DrawTarget,
+    commands: impl IntoIterator<Item = &'a RenderCommand>,
+    transform: Affine,
+) {
+    let mut replay = CommandReplay::new(target, transform);
+    for command in commands {
+        replay.apply(target, command);
+    }
+}
+
+/// Build a standalone recording from a command list, pre-multiplied by `transform`
+pub(crate) fn recording_from_commands(
+    commands: &[RenderCommand],
+    transform: Affine,
+) -> RustRecording {
+    use vello_common::recording::Recordable;
+
+    let mut recording = RustRecording::new();
+    // Recorders are only handed out by a render context; a 1x1 one is enough
+    RustRenderContext::new(1, 1).record(&mut recording, |recorder| {
+        replay_commands(recorder, commands, transform);
+    });
+    recording
+}
+
+/// Serialize a recording into a versioned binary blob
+///
+/// Paths, paints (solid colors and gradients), layers and masks are stored.
+/// Image paints cannot be serialized. Cached strips are not stored; call
+/// `vello_render_context_prepare_recording` after deserializing.
+/// Free the result with `vello_recording_data_free`.
+#[no_mangle]
+pub extern "C" fn vello_recording_serialize(
+    recording: *const VelloRecording,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    if recording.is_null() || out_data.is_null() || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recording = VelloRecording::from_ptr(recording);
+        match crate::recording_format::encode(recording.recording.commands()) {
+            Ok(data) => {
+                let mut boxed = data.into_boxed_slice();
+                unsafe {
+                    *out_len = boxed.len();
+                    *out_data = boxed.as_mut_ptr();
+                    std::mem::forget(boxed);
+                }
+                VELLO_OK
+            }
+            Err(e) => {
+                set_last_error(e);
+                VELLO_ERROR_INVALID_PARAMETER
+            }
+        }
+    })
+}
+
+/// Create a recording from data produced by `vello_recording_serialize`
+#[no_mangle]
+pub extern "C" fn vello_recording_deserialize(data: *const u8, len: usize) -> *mut VelloRecording {
+    if data.is_null() || len == 0 {
+        set_last_error("Null or empty recording data");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let slice = unsafe { std::slice::from_raw_parts(data, len) };
+        match crate::recording_format::decode(slice) {
+            Ok(commands) => {
+                let recording = recording_from_commands(&commands, Affine::IDENTITY);
+                handle::into_raw(VelloRecording::new(recording))
+            }
+            Err(e) => {
+                set_last_error(e);
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Free data returned by `vello_recording_serialize`
+#[no_mangle]
+pub extern "C" fn vello_recording_data_free(data: *mut u8, len: usize) {
+    if !data.is_null() && len > 0 {
+        unsafe {
+            let _ = Box::from_raw(std::slice::from_raw_parts_mut(data, len));
+        }
+    }
+}
+
+/// Version of the format written by `vello_recording_serialize`
+#[no_mangle]
+pub extern "C" fn vello_recording_format_version() -> u32 {
+    crate::recording_format::VERSION as u32
+}
+
+/// Execute the commands in `[start_index, end_index)` of a recording
+///
+/// State changes and layer pushes before `start_index` are applied so the range
+/// draws exactly as it would during a full replay; drawing commands outside the
+/// range are skipped. Layers still open at `end_index` are closed. The range is
+/// prepared on every call.
+#[no_mangle]
+pub extern "C" fn vello_render_context_execute_recording_range(
+    ctx: *mut VelloRenderContext,
+    recording: *const VelloRecording,
+    start_index: usize,
+    end_index: usize,
+) -> c_int {
+    if ctx.is_null() || recording.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let commands = VelloRecording::from_ptr(recording).recording.commands();
+        if start_index > end_index || end_index > commands.len() {
+            set_last_error("Invalid command range");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+
+        use vello_common::recording::Recordable;
+
+        let is_draw = |command: &RenderCommand| {
+            matches!(
+                command,
+                RenderCommand::FillPath(_)
+                    | RenderCommand::StrokePath(_)
+                    | RenderCommand::FillRect(_)
+                    | RenderCommand::StrokeRect(_)
+                    | RenderCommand::FillOutlineGlyph(_)
+                    | RenderCommand::StrokeOutlineGlyph(_)
+            )
+        };
+        let selected: Vec<&RenderCommand> = commands[..start_index]
+            .iter()
+            .filter(|command| !is_draw(command))
+            .chain(&commands[start_index..end_index])
+            .collect();
+
+        let mut depth = 0usize;
+        for command in &selected {
+            match command {
+                RenderCommand::PushLayer(_) => depth += 1,
+                RenderCommand::PopLayer => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        let mut range = RustRecording::new();
+        RustRenderContext::new(1, 1).record(&mut range, |recorder| {
+            replay_commands(recorder, selected.iter().copied(), Affine::IDENTITY);
+            for _ in 0..depth {
+                recorder.pop_layer();
+            }
+        });
+        ctx.execute_recording(&mut range);
+        VELLO_OK
+    })
+}
+
+/// Append the commands of `src` to `dst`
+///
+/// The result replays like executing `dst` followed by `src`, so `src` inherits
+/// the state left behind by `dst`. Cached strips of `dst` are discarded because
+/// the merged command list must be prepared again. `dst` and `src` may be the
+/// same recording.
+#[no_mangle]
+pub extern "C" fn vello_recording_append(
+    dst: *mut VelloRecording,
+    src: *const VelloRecording,
+) -> c_int {
+    if dst.is_null() || src.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use vello_common::recording::Recordable;
+
+        let mut combined = RustRecording::new();
+        {
+            let dst = VelloRecording::from_ptr(dst);
+            let src = VelloRecording::from_ptr(src);
+            RustRenderContext::new(1, 1).record(&mut combined, |recorder| {
+                replay_commands(recorder, dst.recording.commands(), Affine::IDENTITY);
+                replay_commands(recorder, src.recording.commands(), Affine::IDENTITY);
+            });
+        }
+        VelloRecording::from_ptr_mut(dst).recording = combined;
+        VELLO_OK
+    })
+}
+
+/// Convert a recording into an SVG document of the given size
+///
+/// Free the returned string with `vello_recording_svg_free`. Effects without an
+/// SVG equivalent (masks, compose modes, sweep gradients) are approximated or
+/// dropped, so the output is meant for inspection and vector export rather
+/// than pixel-exact reproduction.
+#[cfg(feature = "svg-export")]
+#[no_mangle]
+pub extern "C" fn vello_recording_to_svg(
+    recording: *const VelloRecording,
+    width: u32,
+    height: u32,
+    out_string: *mut *mut std::os::raw::c_char,
+) -> c_int {
+    if recording.is_null() || out_string.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recording = VelloRecording::from_ptr(recording);
+        let svg = crate::svg_export::to_svg(recording.recording.commands(), width, height);
+        // The writer never emits NUL bytes
+        let svg = std::ffi::CString::new(svg).unwrap_or_default();
+        unsafe { *out_string = svg.into_raw() };
+        VELLO_OK
+    })
+}
+
+/// Free a string returned by `vello_recording_to_svg`
+#[cfg(feature = "svg-export")]
+#[no_mangle]
+pub extern "C" fn vello_recording_svg_free(svg: *mut std::os::raw::c_char) {
+    if !svg.is_null() {
+        unsafe {
+            drop(std::ffi::CString::from_raw(svg));
+        }
+    }
+}
+
+/// Mark commands `[start, end)` as needing new strips
+///
+/// The next `vello_render_context_prepare_recording` regenerates strips for the
+/// chunks containing these commands and keeps the rest of the cache.
+#[no_mangle]
+pub extern "C" fn vello_recording_invalidate_range(
+    recording: *mut VelloRecording,
+    start: usize,
+    end: usize,
+) -> c_int {
+    if recording.is_null() {
+        set_last_error("Null recording pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recording = VelloRecording::from_ptr_mut(recording);
+        if start > end || end > recording.recording.command_count() {
+            set_last_error("Invalid command range");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        recording.chunks.invalidate(start, end);
+        VELLO_OK
+    })
+}
+
+/// Replace commands `[start, end)` of `dst` with the commands of `src`
+///
+/// Cached strips outside the edited span stay valid unless the edit changes the
+/// transform, fill rule or stroke seen by later commands. Call
+/// `vello_render_context_prepare_recording` afterwards to fill in the gaps.
+#[no_mangle]
+pub extern "C" fn vello_recording_replace_range(
+    dst: *mut VelloRecording,
+    start: usize,
+    end: usize,
+    src: *const VelloRecording,
+) -> c_int {
+    if dst.is_null() || src.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use vello_common::recording::Recordable;
+
+        if start > end || end > VelloRecording::from_ptr(dst).recording.command_count() {
+            set_last_error("Invalid command range");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let mut combined = RustRecording::new();
+        let inserted;
+        {
+            let dst = VelloRecording::from_ptr(dst);
+            let src = VelloRecording::from_ptr(src);
+            let commands = dst.recording.commands();
+            inserted = src.recording.command_count();
+            RustRenderContext::new(1, 1).record(&mut combined, |recorder| {
+                replay_commands(
+                    recorder,
+                    commands[..start]
+                        .iter()
+                        .chain(src.recording.commands())
+                        .chain(&commands[end..]),
+                    Affine::IDENTITY,
+                );
+            });
+        }
+        let dst = VelloRecording::from_ptr_mut(dst);
+        dst.recording = combined;
+        dst.chunks.splice(start, end, inserted);
+        VELLO_OK
+    })
 }
 
 // Record drawing operations for later replay
@@ -103,50 +570,112 @@ pub extern "C" fn vello_render_context_record(
     recording: *mut VelloRecording,
     callback: extern "C" fn(*mut c_void, *mut c_void),
     user_data: *mut c_void,
-) -> i32 {
-    if ctx.is_null() {
-        set_last_error("Null context pointer");
-        return -1;
+) -> c_int {
+    if ctx.is_null() || recording.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
     }
-    if recording.is_null() {
-        set_last_error("Null recording pointer");
-        return -1;
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = VelloRecording::from_ptr_mut(recording);
+        let gradient_ramp_size = ctx.gradient_ramp_size;
+
+        use vello_common::recording::Recordable;
+        ctx.context_mut().record(&mut recording.recording, |recorder| {
+            // The handle is unregistered when the callback returns
+            let recorder = recorder as *mut Recorder<'_> as *mut Recorder<'static>;
+            let mut recorder = RecorderHandle {
+                header: HandleHeader::new::<RecorderHandle>(),
+                recorder: RecorderSlot::Borrowed(recorder),
+                gradient_ramp_size,
+                dashed: None,
+            };
+            callback(user_data, handle::register(&mut recorder) as *mut c_void);
+        });
+        VELLO_OK
+    })
+}
+
+/// Start recording into `recording` without a callback
+///
+/// Returns a recorder for the `vello_recorder_*` functions, valid until
+/// `vello_render_context_end_record`. The recording must not be used or freed
+/// in between. Only one recording can be open per context.
+#[no_mangle]
+pub extern "C" fn vello_render_context_begin_record(
+    ctx: *mut VelloRenderContext,
+    recording: *mut VelloRecording,
+) -> *mut c_void {
+    if ctx.is_null() || recording.is_null() {
+        set_last_error("Null pointer");
+        return std::ptr::null_mut();
     }
 
-    let ctx = unsafe { &mut *(ctx as *mut RustRenderContext) };
-    let recording = unsafe { &mut *recording };
+    ffi_catch_ptr!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        if ctx.recorder.is_some() {
+            set_last_error("A recording is already in progress");
+            return std::ptr::null_mut();
+        }
 
-    use vello_common::recording::Recordable;
-    ctx.record(&mut recording.0, |recorder| {
-        // Pass the recorder to the callback
-        callback(user_data, recorder as *mut _ as *mut c_void);
-    });
+        // The caller keeps the recording alive until end_record
+        let recording = &mut VelloRecording::from_ptr_mut(recording).recording;
+        let recording: &'static mut RustRecording = unsafe { &mut *(recording as *mut _) };
+        let transform = *ctx.transform();
+        let recorder = ctx.recorder.insert(Box::new(RecorderHandle {
+            header: HandleHeader::new::<RecorderHandle>(),
+            recorder: RecorderSlot::Owned(Recorder::new(
+                recording,
+                transform,
+                vello_common::glyph::GlyphCaches::default(),
+            )),
+            gradient_ramp_size: ctx.gradient_ramp_size,
+            dashed: None,
+        }));
+        handle::register(recorder.as_mut()) as *mut c_void
+    })
+}
 
-    0 // Success
+/// Finish the recording started by `vello_render_context_begin_record`
+#[no_mangle]
+pub extern "C" fn vello_render_context_end_record(ctx: *mut VelloRenderContext) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        if ctx.recorder.take().is_none() {
+            set_last_error("No recording in progress");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        VELLO_OK
+    })
 }
 
 /// Prepare a recording for optimized playback
+///
+/// Strips are cached per chunk of commands; chunks that are still valid from a
+/// previous call are kept, so after `vello_recording_replace_range` or
+/// `vello_recording_invalidate_range` only the affected chunks are regenerated.
 #[no_mangle]
 pub extern "C" fn vello_render_context_prepare_recording(
     ctx: *mut c_void,
     recording: *mut VelloRecording,
-) -> i32 {
-    if ctx.is_null() {
-        set_last_error("Null context pointer");
-        return -1;
+) -> c_int {
+    if ctx.is_null() || recording.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
     }
-    if recording.is_null() {
-        set_last_error("Null recording pointer");
-        return -1;
-    }
-
-    let ctx = unsafe { &mut *(ctx as *mut RustRenderContext) };
-    let recording = unsafe { &mut *recording };
 
-    use vello_common::recording::Recordable;
-    ctx.prepare_recording(&mut recording.0);
-
-    0 // Success
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = VelloRecording::from_ptr_mut(recording);
+        recording.prepare(ctx.context_mut());
+        VELLO_OK
+    })
 }
 
 /// Execute a previously recorded set of drawing operations
@@ -154,23 +683,115 @@ pub extern "C" fn vello_render_context_prepare_recording(
 pub extern "C" fn vello_render_context_execute_recording(
     ctx: *mut c_void,
     recording: *const VelloRecording,
-) -> i32 {
-    if ctx.is_null() {
-        set_last_error("Null context pointer");
-        return -1;
+) -> c_int {
+    if ctx.is_null() || recording.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
     }
-    if recording.is_null() {
-        set_last_error("Null recording pointer");
-        return -1;
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = VelloRecording::from_ptr(recording);
+        recording.execute(ctx);
+        VELLO_OK
+    })
+}
+
+/// Execute a recording with all of its transforms pre-multiplied by `transform`
+///
+/// Cached strips are used as-is for the identity transform and are prepared
+/// first if missing. Translations by whole pixels horizontally and multiples of
+/// 4 pixels vertically, as when scrolling, move the cached strips instead, as
+/// long as the recording lies within the canvas both where it was prepared and
+/// where it is moved to and uses no masks. Any other transform changes the
+/// device-space geometry, so a transformed copy of the commands is prepared on
+/// every call.
+#[no_mangle]
+pub extern "C" fn vello_render_context_execute_recording_with_transform(
+    ctx: *mut VelloRenderContext,
+    recording: *mut VelloRecording,
+    transform: *const VelloAffine,
+) -> c_int {
+    if ctx.is_null() || recording.is_null() || transform.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let ctx = unsafe { &mut *(ctx as *mut RustRenderContext) };
-    let recording = unsafe { &*recording };
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = VelloRecording::from_ptr_mut(recording);
+        let a = unsafe { &*transform };
+        let transform = Affine::new([a.m11, a.m12, a.m21, a.m22, a.m13, a.m23]);
+        execute_transformed(ctx, recording, transform);
+        VELLO_OK
+    })
+}
 
-    use vello_common::recording::Recordable;
-    ctx.execute_recording(&recording.0);
+/// Draw a recording with its transforms pre-multiplied by `transform`, reusing
+/// its cached strips where they can be moved
+fn execute_transformed(
+    ctx: &mut RenderContextHandle,
+    recording: &mut VelloRecording,
+    transform: Affine,
+) {
+    let offset = strip_offset(transform);
+    if offset.is_some() && !recording.is_prepared() {
+        recording.prepare(ctx.context_mut());
+    }
+    if transform == Affine::IDENTITY {
+        recording.execute(ctx);
+        return;
+    }
+
+    let (width, height) = (ctx.width(), ctx.height());
+    let moved = offset.and_then(|offset| recording.chunks.translated(offset, width, height));
+    if let Some(moved) = moved {
+        let commands: Vec<RenderCommand> =
+            moved.iter().flat_map(|chunk| chunk.commands()).cloned().collect();
+        ctx.execute_recording_with(&commands, |ctx| {
+            for chunk in &moved {
+                ctx.execute_recording(chunk);
+            }
+        });
+        return;
+    }
+
+    let mut transformed = recording_from_commands(recording.recording.commands(), transform);
+    ctx.execute_recording(&mut transformed);
+}
+
+/// Execute a recording once per transform in a single call
+///
+/// Each instance is drawn as by
+/// `vello_render_context_execute_recording_with_transform` with one entry of
+/// `transforms`, so the recording is prepared once and instances placed at
+/// movable offsets reuse its strips.
+#[no_mangle]
+pub extern "C" fn vello_render_context_execute_recording_instanced(
+    ctx: *mut VelloRenderContext,
+    recording: *mut VelloRecording,
+    transforms: *const VelloAffine,
+    count: usize,
+) -> c_int {
+    if ctx.is_null() || recording.is_null() || (count > 0 && transforms.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
 
-    0 // Success
+    if count == 0 {
+        return VELLO_OK;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = VelloRecording::from_ptr_mut(recording);
+        let transforms = unsafe { std::slice::from_raw_parts(transforms, count) };
+        for a in transforms {
+            let transform = Affine::new([a.m11, a.m12, a.m21, a.m22, a.m13, a.m23]);
+            execute_transformed(ctx, recording, transform);
+        }
+        VELLO_OK
+    })
 }
 
 // Recorder drawing methods - these will be called from the callback
@@ -180,23 +801,24 @@ pub extern "C" fn vello_render_context_execute_recording(
 pub extern "C" fn vello_recorder_fill_rect(
     recorder: *mut c_void,
     rect: *const VelloRect,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if rect.is_null() {
         set_last_error("Null rect pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let r = unsafe { &*rect };
-    let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
+    ffi_catch!({
+        let r = unsafe { &*rect };
+        let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
 
-    recorder.fill_rect(&rect);
-
-    0 // Success
+        recorder.fill_rect(&rect);
+        VELLO_OK
+    })
 }
 
 /// Stroke a rectangle (recorder version)
@@ -204,23 +826,24 @@ pub extern "C" fn vello_recorder_fill_rect(
 pub extern "C" fn vello_recorder_stroke_rect(
     recorder: *mut c_void,
     rect: *const VelloRect,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if rect.is_null() {
         set_last_error("Null rect pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let r = unsafe { &*rect };
-    let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
-
-    recorder.stroke_rect(&rect);
+    ffi_catch!({
+        let r = unsafe { &*rect };
+        let rect = vello_cpu::kurbo::Rect::new(r.x0, r.y0, r.x1, r.y1);
+        let recorder = RecorderHandle::from_ptr_mut(recorder);
 
-    0 // Success
+        recorder.stroke_rect(&rect);
+        VELLO_OK
+    })
 }
 
 /// Fill a path (recorder version)
@@ -228,22 +851,23 @@ pub extern "C" fn vello_recorder_stroke_rect(
 pub extern "C" fn vello_recorder_fill_path(
     recorder: *mut c_void,
     path: *const c_void,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if path.is_null() {
         set_last_error("Null path pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let path = unsafe { &*(path as *const vello_cpu::peniko::kurbo::BezPath) };
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
+    ffi_catch!({
+        let path = &PathHandle::from_ptr(path).path;
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
 
-    recorder.fill_path(path);
-
-    0 // Success
+        recorder.fill_path(path);
+        VELLO_OK
+    })
 }
 
 /// Stroke a path (recorder version)
@@ -251,22 +875,23 @@ pub extern "C" fn vello_recorder_fill_path(
 pub extern "C" fn vello_recorder_stroke_path(
     recorder: *mut c_void,
     path: *const c_void,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if path.is_null() {
         set_last_error("Null path pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let path = unsafe { &*(path as *const vello_cpu::peniko::kurbo::BezPath) };
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
-
-    recorder.stroke_path(path);
+    ffi_catch!({
+        let path = &PathHandle::from_ptr(path).path;
+        let recorder = RecorderHandle::from_ptr_mut(recorder);
 
-    0 // Success
+        recorder.stroke_path(path);
+        VELLO_OK
+    })
 }
 
 /// Set solid color paint (recorder version)
@@ -277,18 +902,19 @@ pub extern "C" fn vello_recorder_set_paint_solid(
     g: u8,
     b: u8,
     a: u8,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let color = vello_cpu::peniko::Color::from_rgba8(r, g, b, a);
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
-
-    recorder.set_paint(color);
+    ffi_catch!({
+        let color = vello_cpu::peniko::Color::from_rgba8(r, g, b, a);
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
 
-    0 // Success
+        recorder.set_paint(color);
+        VELLO_OK
+    })
 }
 
 /// Set transform (recorder version)
@@ -296,23 +922,24 @@ pub extern "C" fn vello_recorder_set_paint_solid(
 pub extern "C" fn vello_recorder_set_transform(
     recorder: *mut c_void,
     affine: *const VelloAffine,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if affine.is_null() {
         set_last_error("Null affine pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let a = unsafe { &*affine };
-    let transform = vello_cpu::kurbo::Affine::new([a.m11, a.m12, a.m21, a.m22, a.m13, a.m23]);
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
+    ffi_catch!({
+        let a = unsafe { &*affine };
+        let transform = Affine::new([a.m11, a.m12, a.m21, a.m22, a.m13, a.m23]);
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
 
-    recorder.set_transform(transform);
-
-    0 // Success
+        recorder.set_transform(transform);
+        VELLO_OK
+    })
 }
 
 /// Set fill rule (recorder version)
@@ -320,21 +947,22 @@ pub extern "C" fn vello_recorder_set_transform(
 pub extern "C" fn vello_recorder_set_fill_rule(
     recorder: *mut c_void,
     fill_rule: VelloFillRule,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let fill_rule = match fill_rule {
-        VelloFillRule::NonZero => vello_cpu::peniko::Fill::NonZero,
-        VelloFillRule::EvenOdd => vello_cpu::peniko::Fill::EvenOdd,
-    };
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
-
-    recorder.set_fill_rule(fill_rule);
+    ffi_catch!({
+        let fill_rule = match fill_rule {
+            VelloFillRule::NonZero => vello_cpu::peniko::Fill::NonZero,
+            VelloFillRule::EvenOdd => vello_cpu::peniko::Fill::EvenOdd,
+        };
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
 
-    0 // Success
+        recorder.set_fill_rule(fill_rule);
+        VELLO_OK
+    })
 }
 
 /// Set stroke settings (recorder version)
@@ -342,41 +970,77 @@ pub extern "C" fn vello_recorder_set_fill_rule(
 pub extern "C" fn vello_recorder_set_stroke(
     recorder: *mut c_void,
     stroke: *const VelloStroke,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if stroke.is_null() {
         set_last_error("Null stroke pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let s = unsafe { &*stroke };
-    let mut rust_stroke = vello_cpu::kurbo::Stroke::new(s.width as f64);
-
-    use crate::types::{VelloCap, VelloJoin};
-    rust_stroke.start_cap = match s.start_cap {
-        VelloCap::Butt => vello_cpu::kurbo::Cap::Butt,
-        VelloCap::Square => vello_cpu::kurbo::Cap::Square,
-        VelloCap::Round => vello_cpu::kurbo::Cap::Round,
-    };
-    rust_stroke.end_cap = match s.end_cap {
-        VelloCap::Butt => vello_cpu::kurbo::Cap::Butt,
-        VelloCap::Square => vello_cpu::kurbo::Cap::Square,
-        VelloCap::Round => vello_cpu::kurbo::Cap::Round,
-    };
-    rust_stroke.join = match s.join {
-        VelloJoin::Bevel => vello_cpu::kurbo::Join::Bevel,
-        VelloJoin::Miter => vello_cpu::kurbo::Join::Miter,
-        VelloJoin::Round => vello_cpu::kurbo::Join::Round,
-    };
-    rust_stroke.miter_limit = s.miter_limit as f64;
+    ffi_catch!({
+        let stroke = match to_stroke(unsafe { &*stroke }) {
+            Ok(stroke) => stroke,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        let recorder = RecorderHandle::from_ptr_mut(recorder);
+        recorder.dashed = None;
+        recorder.recorder().set_stroke(stroke);
+        VELLO_OK
+    })
+}
 
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
-    recorder.set_stroke(rust_stroke);
+/// Set stroke settings with a dash pattern (recorder version)
+///
+/// See `vello_render_context_set_stroke_dashed`.
+#[no_mangle]
+pub extern "C" fn vello_recorder_set_stroke_dashed(
+    recorder: *mut c_void,
+    stroke: *const VelloStroke,
+    dashes: *const f64,
+    dash_count: usize,
+    dash_offset: f64,
+    tolerance: f64,
+    scale: f64,
+) -> c_int {
+    if recorder.is_null() {
+        set_last_error("Null recorder pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    if stroke.is_null() || (dashes.is_null() && dash_count > 0) {
+        set_last_error("Null stroke or dash pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
 
-    0 // Success
+    ffi_catch!({
+        let dashes = if dash_count == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(dashes, dash_count) }
+        };
+        let stroke = to_dashed_stroke(unsafe { &*stroke }, dashes, dash_offset);
+        let (stroke, tolerance) =
+            match stroke.and_then(|stroke| Ok((stroke, to_dash_tolerance(tolerance, scale)?))) {
+                Ok(converted) => converted,
+                Err(msg) => {
+                    set_last_error(msg);
+                    return VELLO_ERROR_INVALID_PARAMETER;
+                }
+            };
+
+        let recorder = RecorderHandle::from_ptr_mut(recorder);
+        recorder.dashed = tolerance
+            .filter(|_| !stroke.dash_pattern.is_empty())
+            .map(|tolerance| (stroke.clone(), tolerance));
+        recorder.recorder().set_stroke(stroke);
+        VELLO_OK
+    })
 }
 
 /// Set paint transform (recorder version)
@@ -384,37 +1048,39 @@ pub extern "C" fn vello_recorder_set_stroke(
 pub extern "C" fn vello_recorder_set_paint_transform(
     recorder: *mut c_void,
     affine: *const VelloAffine,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if affine.is_null() {
         set_last_error("Null affine pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let a = unsafe { &*affine };
-    let transform = vello_cpu::kurbo::Affine::new([a.m11, a.m12, a.m21, a.m22, a.m13, a.m23]);
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
+    ffi_catch!({
+        let a = unsafe { &*affine };
+        let transform = Affine::new([a.m11, a.m12, a.m21, a.m22, a.m13, a.m23]);
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
 
-    recorder.set_paint_transform(transform);
-
-    0 // Success
+        recorder.set_paint_transform(transform);
+        VELLO_OK
+    })
 }
 
 /// Reset paint transform (recorder version)
 #[no_mangle]
-pub extern "C" fn vello_recorder_reset_paint_transform(recorder: *mut c_void) -> i32 {
+pub extern "C" fn vello_recorder_reset_paint_transform(recorder: *mut c_void) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
-    recorder.reset_paint_transform();
-
-    0 // Success
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        recorder.reset_paint_transform();
+        VELLO_OK
+    })
 }
 
 /// Push a clip layer (recorder version)
@@ -422,34 +1088,650 @@ pub extern "C" fn vello_recorder_reset_paint_transform(recorder: *mut c_void) ->
 pub extern "C" fn vello_recorder_push_clip_layer(
     recorder: *mut c_void,
     clip_path: *const c_void,
-) -> i32 {
+) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
     if clip_path.is_null() {
         set_last_error("Null clip path pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let path = unsafe { &*(clip_path as *const vello_cpu::peniko::kurbo::BezPath) };
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
+    ffi_catch!({
+        let path = &PathHandle::from_ptr(clip_path).path;
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
 
-    recorder.push_clip_layer(path);
-
-    0 // Success
+        recorder.push_clip_layer(path);
+        VELLO_OK
+    })
 }
 
 /// Pop a layer (recorder version)
 #[no_mangle]
-pub extern "C" fn vello_recorder_pop_layer(recorder: *mut c_void) -> i32 {
+pub extern "C" fn vello_recorder_pop_layer(recorder: *mut c_void) -> c_int {
+    if recorder.is_null() {
+        set_last_error("Null recorder pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        recorder.pop_layer();
+        VELLO_OK
+    })
+}
+fn to_extend(extend: VelloExtend) -> vello_cpu::peniko::Extend {
+    use vello_cpu::peniko::Extend;
+
+    match extend {
+        VelloExtend::Pad => Extend::Pad,
+        VelloExtend::Repeat => Extend::Repeat,
+        VelloExtend::Reflect => Extend::Reflect,
+    }
+}
+
+fn to_blend_mode(bm: &VelloBlendMode) -> vello_cpu::peniko::BlendMode {
+    use vello_cpu::peniko::{BlendMode, Compose, Mix};
+
+    let mix = match bm.mix {
+        VelloMix::Normal => Mix::Normal,
+        VelloMix::Multiply => Mix::Multiply,
+        VelloMix::Screen => Mix::Screen,
+        VelloMix::Overlay => Mix::Overlay,
+        VelloMix::Darken => Mix::Darken,
+        VelloMix::Lighten => Mix::Lighten,
+        VelloMix::ColorDodge => Mix::ColorDodge,
+        VelloMix::ColorBurn => Mix::ColorBurn,
+        VelloMix::HardLight => Mix::HardLight,
+        VelloMix::SoftLight => Mix::SoftLight,
+        VelloMix::Difference => Mix::Difference,
+        VelloMix::Exclusion => Mix::Exclusion,
+        VelloMix::Hue => Mix::Hue,
+        VelloMix::Saturation => Mix::Saturation,
+        VelloMix::Color => Mix::Color,
+        VelloMix::Luminosity => Mix::Luminosity,
+    };
+
+    let compose = match bm.compose {
+        VelloCompose::Clear => Compose::Clear,
+        VelloCompose::Copy => Compose::Copy,
+        VelloCompose::Dest => Compose::Dest,
+        VelloCompose::SrcOver => Compose::SrcOver,
+        VelloCompose::DestOver => Compose::DestOver,
+        VelloCompose::SrcIn => Compose::SrcIn,
+        VelloCompose::DestIn => Compose::DestIn,
+        VelloCompose::SrcOut => Compose::SrcOut,
+        VelloCompose::DestOut => Compose::DestOut,
+        VelloCompose::SrcAtop => Compose::SrcAtop,
+        VelloCompose::DestAtop => Compose::DestAtop,
+        VelloCompose::Xor => Compose::Xor,
+        VelloCompose::Plus => Compose::Plus,
+        VelloCompose::PlusLighter => Compose::PlusLighter,
+    };
+
+    BlendMode::new(mix, compose)
+}
+
+/// Set paint to linear gradient (recorder version)
+#[no_mangle]
+pub extern "C" fn vello_recorder_set_paint_linear_gradient(
+    recorder: *mut c_void,
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+    extend: VelloExtend,
+) -> c_int {
+    if recorder.is_null() || (stop_count > 0 && stops.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    if stop_count < 2 {
+        set_last_error("Gradient requires at least 2 color stops");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder);
+        let stops = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let stops = match color_stops(stops, recorder.gradient_ramp_size) {
+            Ok(stops) => stops,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        use vello_cpu::kurbo::Point;
+        use vello_cpu::peniko::Gradient;
+
+        let gradient = Gradient::new_linear(Point::new(x0, y0), Point::new(x1, y1))
+            .with_stops(&stops[..])
+            .with_extend(to_extend(extend));
+
+        recorder.recorder().set_paint(gradient);
+        VELLO_OK
+    })
+}
+
+/// Set paint to radial gradient (recorder version)
+#[no_mangle]
+pub extern "C" fn vello_recorder_set_paint_radial_gradient(
+    recorder: *mut c_void,
+    cx: f64,
+    cy: f64,
+    radius: f64,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+    extend: VelloExtend,
+) -> c_int {
+    if recorder.is_null() || (stop_count > 0 && stops.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    if stop_count < 2 {
+        set_last_error("Gradient requires at least 2 color stops");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder);
+        let stops = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let stops = match color_stops(stops, recorder.gradient_ramp_size) {
+            Ok(stops) => stops,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        use vello_cpu::kurbo::Point;
+        use vello_cpu::peniko::Gradient;
+
+        let gradient = Gradient::new_radial(Point::new(cx, cy), radius as f32)
+            .with_stops(&stops[..])
+            .with_extend(to_extend(extend));
+
+        recorder.recorder().set_paint(gradient);
+        VELLO_OK
+    })
+}
+
+/// Set paint to sweep gradient (recorder version)
+#[no_mangle]
+pub extern "C" fn vello_recorder_set_paint_sweep_gradient(
+    recorder: *mut c_void,
+    cx: f64,
+    cy: f64,
+    start_angle: f32,
+    end_angle: f32,
+    stops: *const VelloColorStop,
+    stop_count: usize,
+    extend: VelloExtend,
+) -> c_int {
+    if recorder.is_null() || (stop_count > 0 && stops.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    if stop_count < 2 {
+        set_last_error("Gradient requires at least 2 color stops");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder);
+        let stops = unsafe { std::slice::from_raw_parts(stops, stop_count) };
+        let stops = match color_stops(stops, recorder.gradient_ramp_size) {
+            Ok(stops) => stops,
+            Err(msg) => {
+                set_last_error(msg);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        use vello_cpu::kurbo::Point;
+        use vello_cpu::peniko::Gradient;
+
+        let gradient = Gradient::new_sweep(Point::new(cx, cy), start_angle, end_angle)
+            .with_stops(&stops[..])
+            .with_extend(to_extend(extend));
+
+        recorder.recorder().set_paint(gradient);
+        VELLO_OK
+    })
+}
+
+/// Set paint to image (recorder version)
+///
+/// Recordings have no target color space, so the image is recorded in sRGB.
+#[no_mangle]
+pub extern "C" fn vello_recorder_set_paint_image(
+    recorder: *mut c_void,
+    image: *const VelloImage,
+) -> c_int {
+    if recorder.is_null() || image.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        let image = ImageHandle::from_ptr(image);
+        recorder.set_paint(image.in_color_space(VelloColorSpace::Srgb));
+        VELLO_OK
+    })
+}
+
+/// Fill a blurred rounded rectangle (recorder version)
+///
+/// Recordings cannot capture the analytic blur, so the shadow is rasterized in
+/// user-space units at record time and recorded as an image fill. The color is
+/// passed explicitly because the recorder does not track the current paint; the
+/// current paint is left set to that color.
+#[no_mangle]
+pub extern "C" fn vello_recorder_fill_blurred_rounded_rect(
+    recorder: *mut c_void,
+    rect: *const VelloRect,
+    radius: f32,
+    std_dev: f32,
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+) -> c_int {
+    if recorder.is_null() || rect.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let valid = |value: f32| value.is_finite() && value >= 0.0;
+    if !(valid(std_dev) && valid(radius)) {
+        set_last_error("Radius and standard deviation must be finite and non-negative");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    let rect = unsafe { &*rect };
+    let extent = (std_dev as f64 * 3.0).ceil();
+    let width = (rect.x1 - rect.x0 + 2.0 * extent).ceil();
+    let height = (rect.y1 - rect.y0 + 2.0 * extent).ceil();
+    if !(width >= 1.0 && height >= 1.0 && width <= u16::MAX as f64 && height <= u16::MAX as f64) {
+        set_last_error("Invalid blurred rectangle size");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+
+        use vello_common::paint::{Image, ImageSource};
+        use vello_cpu::kurbo::Rect;
+        use vello_cpu::peniko::{self, Color, Extend, ImageQuality};
+        use vello_cpu::Pixmap;
+
+        let color = Color::from_rgba8(r, g, b, a);
+        let origin = (rect.x0 - extent, rect.y0 - extent);
+        let (width, height) = (width as u16, height as u16);
+
+        let mut shadow_ctx = RustRenderContext::new(width, height);
+        shadow_ctx.set_transform(Affine::translate((-origin.0, -origin.1)));
+        shadow_ctx.set_paint(color);
+        shadow_ctx.fill_blurred_rounded_rect(
+            &Rect::new(rect.x0, rect.y0, rect.x1, rect.y1),
+            radius,
+            std_dev,
+        );
+        shadow_ctx.flush();
+        let mut shadow = Pixmap::new(width, height);
+        shadow_ctx.render_to_pixmap(&mut shadow);
+
+        let image = Image {
+            image: ImageSource::Pixmap(Arc::new(shadow)),
+            sampler: peniko::ImageSampler {
+                x_extend: Extend::Pad,
+                y_extend: Extend::Pad,
+                quality: ImageQuality::Low,
+                alpha: 1.0,
+            },
+        };
+
+        recorder.set_paint(image);
+        recorder.set_paint_transform(Affine::translate(origin));
+        recorder.fill_rect(&Rect::new(
+            origin.0,
+            origin.1,
+            origin.0 + width as f64,
+            origin.1 + height as f64,
+        ));
+        recorder.reset_paint_transform();
+        recorder.set_paint(color);
+        VELLO_OK
+    })
+}
+
+/// Push a blend layer (recorder version)
+#[no_mangle]
+pub extern "C" fn vello_recorder_push_blend_layer(
+    recorder: *mut c_void,
+    blend_mode: *const VelloBlendMode,
+) -> c_int {
+    if recorder.is_null() || blend_mode.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        let blend_mode = to_blend_mode(unsafe { &*blend_mode });
+        recorder.push_layer(None, Some(blend_mode), None, None);
+        VELLO_OK
+    })
+}
+
+/// Push an opacity layer (recorder version)
+#[no_mangle]
+pub extern "C" fn vello_recorder_push_opacity_layer(recorder: *mut c_void, opacity: f32) -> c_int {
     if recorder.is_null() {
         set_last_error("Null recorder pointer");
-        return -1;
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        recorder.push_layer(None, None, Some(opacity), None);
+        VELLO_OK
+    })
+}
+
+/// Push a mask layer (recorder version)
+#[no_mangle]
+pub extern "C" fn vello_recorder_push_mask_layer(
+    recorder: *mut c_void,
+    mask: *const VelloMask,
+) -> c_int {
+    if recorder.is_null() || mask.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
     }
 
-    let recorder = unsafe { &mut *(recorder as *mut vello_common::recording::Recorder) };
-    recorder.pop_layer();
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        let mask = MaskHandle::from_ptr(mask);
+        recorder.push_layer(None, None, None, Some(mask.mask.clone()));
+        VELLO_OK
+    })
+}
 
-    0 // Success
+/// Push a general layer (recorder version)
+///
+/// Pass null pointers for unused options and a negative opacity for none.
+#[no_mangle]
+pub extern "C" fn vello_recorder_push_layer(
+    recorder: *mut c_void,
+    clip_path: *const VelloBezPath,
+    blend_mode: *const VelloBlendMode,
+    opacity: f32,
+    mask: *const VelloMask,
+) -> c_int {
+    if recorder.is_null() {
+        set_last_error("Null recorder pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        let clip_path = (!clip_path.is_null()).then(|| &PathHandle::from_ptr(clip_path).path);
+        let blend_mode = (!blend_mode.is_null()).then(|| to_blend_mode(unsafe { &*blend_mode }));
+        let opacity = (opacity >= 0.0).then_some(opacity);
+        let mask = (!mask.is_null()).then(|| MaskHandle::from_ptr(mask).mask.clone());
+        recorder.push_layer(clip_path, blend_mode, opacity, mask);
+        VELLO_OK
+    })
+}
+
+/// Fill glyphs with the current paint (recorder version)
+///
+/// Glyph outlines are resolved at record time, so replay does not need the font.
+#[no_mangle]
+pub extern "C" fn vello_recorder_fill_glyphs(
+    recorder: *mut c_void,
+    font: *const VelloFontData,
+    font_size: f32,
+    glyphs: *const VelloGlyph,
+    glyph_count: usize,
+) -> c_int {
+    if recorder.is_null() || font.is_null() || (glyph_count > 0 && glyphs.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        let font = &FontHandle::from_ptr(font).font;
+        let glyphs = recorder_glyphs(glyphs, glyph_count);
+        recorder
+            .glyph_run(font)
+            .font_size(font_size)
+            .fill_glyphs(glyphs.into_iter());
+        VELLO_OK
+    })
+}
+
+/// Stroke glyphs with the current paint and stroke (recorder version)
+#[no_mangle]
+pub extern "C" fn vello_recorder_stroke_glyphs(
+    recorder: *mut c_void,
+    font: *const VelloFontData,
+    font_size: f32,
+    glyphs: *const VelloGlyph,
+    glyph_count: usize,
+) -> c_int {
+    if recorder.is_null() || font.is_null() || (glyph_count > 0 && glyphs.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let recorder = RecorderHandle::from_ptr_mut(recorder).recorder();
+        let font = &FontHandle::from_ptr(font).font;
+        let glyphs = recorder_glyphs(glyphs, glyph_count);
+        recorder
+            .glyph_run(font)
+            .font_size(font_size)
+            .stroke_glyphs(glyphs.into_iter());
+        VELLO_OK
+    })
+}
+
+fn recorder_glyphs(glyphs: *const VelloGlyph, count: usize) -> Vec<vello_cpu::Glyph> {
+    if count == 0 {
+        return Vec::new();
+    }
+    unsafe { std::slice::from_raw_parts(glyphs, count) }
+        .iter()
+        .map(|g| vello_cpu::Glyph {
+            id: g.id,
+            x: g.x,
+            y: g.y,
+        })
+        .collect()
+}
+
+/// Set paint to a repeating pattern rasterized from a recording
+///
+/// The area of the recording covered by `tile_rect` is rasterized into a tile
+/// which is then repeated according to the extend modes. The tile spans the
+/// pixel-aligned bounds of `tile_rect` (origin floored, far edge ceiled) and is
+/// anchored at that origin in user space; negative origins are allowed.
+/// Cached strips are reused for tiles at the origin. Otherwise the tile is
+/// drawn from a copy, so the recording is never prepared for the tile size.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_paint_pattern(
+    ctx: *mut VelloRenderContext,
+    recording: *mut VelloRecording,
+    tile_rect: *const VelloRect,
+    extend_x: VelloExtend,
+    extend_y: VelloExtend,
+) -> c_int {
+    if ctx.is_null() || recording.is_null() || tile_rect.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let r = unsafe { &*tile_rect };
+    let (x0, y0) = (r.x0.floor(), r.y0.floor());
+    let tile_width = r.x1.ceil() - x0;
+    let tile_height = r.y1.ceil() - y0;
+    if !(tile_width >= 1.0 && tile_height >= 1.0)
+        || tile_width > u16::MAX as f64
+        || tile_height > u16::MAX as f64
+    {
+        set_last_error("Invalid pattern tile rectangle");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = VelloRecording::from_ptr(recording);
+
+        use vello_common::paint::{Image, ImageSource};
+        use vello_common::recording::Recordable;
+        use vello_cpu::peniko::{self, ImageQuality};
+        use vello_cpu::Pixmap;
+
+        // Rasterize only the tile, with its origin moved to (0, 0)
+        let (tile_width, tile_height) = (tile_width as u16, tile_height as u16);
+        let mut tile_ctx = RustRenderContext::new(tile_width, tile_height);
+        if x0 == 0.0 && y0 == 0.0 && recording.is_prepared() {
+            recording.execute_on(&mut tile_ctx);
+        } else {
+            let mut shifted =
+                recording_from_commands(recording.recording.commands(), Affine::translate((-x0, -y0)));
+            tile_ctx.prepare_recording(&mut shifted);
+            tile_ctx.execute_recording(&shifted);
+        }
+        tile_ctx.flush();
+
+        let mut tile = Pixmap::new(tile_width, tile_height);
+        tile_ctx.render_to_pixmap(&mut tile);
+
+        let image = Image {
+            image: ImageSource::Pixmap(Arc::new(tile)),
+            sampler: peniko::ImageSampler {
+                x_extend: to_extend(extend_x),
+                y_extend: to_extend(extend_y),
+                quality: ImageQuality::Medium,
+                alpha: 1.0,
+            },
+        };
+
+        let anchor = *ctx.paint_transform() * Affine::translate((x0, y0));
+        ctx.set_paint(image);
+        ctx.set_paint_transform(anchor);
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{vello_render_context_free, vello_render_context_new};
+    use crate::test_util::{fill, render, translate};
+
+    extern "C" fn record_rects(_user_data: *mut c_void, recorder: *mut c_void) {
+        assert_eq!(vello_recorder_set_paint_solid(recorder, 255, 0, 0, 255), VELLO_OK);
+        let rect = VelloRect { x0: 2.5, y0: 1.0, x1: 9.0, y1: 7.5 };
+        assert_eq!(vello_recorder_fill_rect(recorder, &rect), VELLO_OK);
+        assert_eq!(vello_recorder_set_paint_solid(recorder, 0, 0, 128, 128), VELLO_OK);
+        let rect = VelloRect { x0: 6.0, y0: 4.25, x1: 12.5, y1: 10.0 };
+        assert_eq!(vello_recorder_fill_rect(recorder, &rect), VELLO_OK);
+    }
+
+    #[test]
+    fn translated_recordings_match_direct_drawing() {
+        let recording = vello_recording_new();
+        let ctx = vello_render_context_new(32, 32);
+        let user_data = std::ptr::null_mut();
+        let result = vello_render_context_record(ctx, recording, record_rects, user_data);
+        assert_eq!(result, VELLO_OK);
+
+        // (5, 8) moves the cached strips, (5, 3) is not a whole number of rows
+        for (dx, dy) in [(5.0, 8.0), (5.0, 3.0)] {
+            let moved = vello_render_context_new(32, 32);
+            let transform = translate(dx, dy);
+            let result =
+                vello_render_context_execute_recording_with_transform(moved, recording, &transform);
+            assert_eq!(result, VELLO_OK);
+            assert_eq!(vello_recording_has_cached_strips(recording), 1);
+
+            let direct = vello_render_context_new(32, 32);
+            fill(direct, [255, 0, 0, 255], 2.5 + dx, 1.0 + dy, 9.0 + dx, 7.5 + dy);
+            fill(direct, [0, 0, 128, 128], 6.0 + dx, 4.25 + dy, 12.5 + dx, 10.0 + dy);
+            assert_eq!(render(moved, 32, 32), render(direct, 32, 32));
+            vello_render_context_free(direct);
+            vello_render_context_free(moved);
+        }
+
+        vello_render_context_free(ctx);
+        vello_recording_free(recording);
+    }
+
+    #[test]
+    fn patterns_leave_the_recording_unprepared() {
+        let recording = vello_recording_new();
+        let ctx = vello_render_context_new(32, 32);
+        let user_data = std::ptr::null_mut();
+        let result = vello_render_context_record(ctx, recording, record_rects, user_data);
+        assert_eq!(result, VELLO_OK);
+
+        // The tile cuts through both rects
+        let tile = VelloRect { x0: 0.0, y0: 0.0, x1: 8.0, y1: 6.0 };
+        let (pad, repeat) = (VelloExtend::Pad, VelloExtend::Repeat);
+        let result = vello_render_context_set_paint_pattern(ctx, recording, &tile, pad, repeat);
+        assert_eq!(result, VELLO_OK);
+        assert_eq!(vello_recording_has_cached_strips(recording), 0);
+
+        // Replaying afterwards draws the whole recording
+        let replayed = vello_render_context_new(32, 32);
+        assert_eq!(vello_render_context_execute_recording(replayed, recording), VELLO_OK);
+        let direct = vello_render_context_new(32, 32);
+        fill(direct, [255, 0, 0, 255], 2.5, 1.0, 9.0, 7.5);
+        fill(direct, [0, 0, 128, 128], 6.0, 4.25, 12.5, 10.0);
+        assert_eq!(render(replayed, 32, 32), render(direct, 32, 32));
+
+        vello_render_context_free(direct);
+        vello_render_context_free(replayed);
+        vello_render_context_free(ctx);
+        vello_recording_free(recording);
+    }
+
+    #[test]
+    fn instances_share_one_preparation() {
+        let recording = vello_recording_new();
+        let ctx = vello_render_context_new(32, 32);
+        let user_data = std::ptr::null_mut();
+        let result = vello_render_context_record(ctx, recording, record_rects, user_data);
+        assert_eq!(result, VELLO_OK);
+
+        let transforms = [translate(0.0, 0.0), translate(16.0, 12.0), translate(3.0, 18.5)];
+        let (data, count) = (transforms.as_ptr(), transforms.len());
+        let result = vello_render_context_execute_recording_instanced(ctx, recording, data, count);
+        assert_eq!(result, VELLO_OK);
+        assert_eq!(vello_recording_has_cached_strips(recording), 1);
+
+        let direct = vello_render_context_new(32, 32);
+        for (dx, dy) in [(0.0, 0.0), (16.0, 12.0), (3.0, 18.5)] {
+            fill(direct, [255, 0, 0, 255], 2.5 + dx, 1.0 + dy, 9.0 + dx, 7.5 + dy);
+            fill(direct, [0, 0, 128, 128], 6.0 + dx, 4.25 + dy, 12.5 + dx, 10.0 + dy);
+        }
+        assert_eq!(render(ctx, 32, 32), render(direct, 32, 32));
+
+        vello_render_context_free(direct);
+        vello_render_context_free(ctx);
+        vello_recording_free(recording);
+    }
 }
@@ -0,0 +1,318 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Incrementally prepared recordings
+//!
+//! A recording is prepared as a sequence of chunks, each a standalone recording
+//! covering a layer-balanced span of commands. Every chunk remembers the
+//! geometry state (transform, fill rule, stroke) it was prepared under, so after
+//! an edit only chunks that overlap the edit, or whose incoming state changed,
+//! need new strips.
+//!
+//! Chunks also remember the device-space bounds of their geometry. Strips are
+//! whole pixels, so a chunk that was prepared entirely on the canvas can be
+//! moved by an integer offset without generating strips again.
+
+use std::f64::consts::SQRT_2;
+
+use vello_common::recording::{Recordable, Recording, RenderCommand};
+use vello_common::strip::Strip;
+use vello_common::tile::Tile;
+use vello_cpu::kurbo::{Affine, Rect, Stroke};
+use vello_cpu::peniko::Fill;
+use vello_cpu::RenderContext;
+
+use crate::recording::replay_commands;
+
+/// Target number of commands per chunk
+const CHUNK_COMMANDS: usize = 64;
+
+/// State that influences the strips generated for a command
+#[derive(Clone, PartialEq)]
+struct GeometryState {
+    transform: Affine,
+    fill_rule: Fill,
+    stroke: Stroke,
+}
+
+impl Default for GeometryState {
+    fn default() -> Self {
+        Self {
+            transform: Affine::IDENTITY,
+            fill_rule: Fill::NonZero,
+            stroke: Stroke::default(),
+        }
+    }
+}
+
+impl GeometryState {
+    fn apply(&mut self, command: &RenderCommand) {
+        match command {
+            RenderCommand::SetTransform(affine) => self.transform = *affine,
+            RenderCommand::SetFillRule(fill) => self.fill_rule = *fill,
+            RenderCommand::SetStroke(stroke) => self.stroke = stroke.clone(),
+            _ => {}
+        }
+    }
+}
+
+struct Chunk {
+    start: usize,
+    end: usize,
+    state: GeometryState,
+    recording: Recording,
+    /// Device-space bounds of the geometry, if it was prepared entirely on the
+    /// canvas and can be moved with its strips
+    movable: Option<Rect>,
+}
+
+/// Prepared chunks of a recording, ordered by command index
+#[derive(Default)]
+pub(crate) struct PreparedChunks {
+    chunks: Vec<Chunk>,
+}
+
+impl PreparedChunks {
+    pub(crate) fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Whether the chunks cover all `len` commands without gaps
+    pub(crate) fn covers(&self, len: usize) -> bool {
+        let mut next = 0;
+        for chunk in &self.chunks {
+            if chunk.start != next {
+                return false;
+            }
+            next = chunk.end;
+        }
+        next == len && !self.chunks.is_empty()
+    }
+
+    pub(crate) fn strip_count(&self) -> usize {
+        self.chunks.iter().map(|c| c.recording.strip_count()).sum()
+    }
+
+    pub(crate) fn alpha_count(&self) -> usize {
+        self.chunks.iter().map(|c| c.recording.alpha_count()).sum()
+    }
+
+    /// Cached strips and alphas of each chunk, in command order
+    pub(crate) fn strips(&self) -> impl Iterator<Item = (&[Strip], &[u8])> {
+        self.chunks.iter().map(|c| c.recording.get_cached_strips())
+    }
+
+    /// Drop the chunks overlapping `[start, end)`
+    pub(crate) fn invalidate(&mut self, start: usize, end: usize) {
+        self.chunks.retain(|c| c.end <= start || c.start >= end);
+    }
+
+    /// Account for commands `[start, end)` being replaced by `inserted` commands
+    pub(crate) fn splice(&mut self, start: usize, end: usize, inserted: usize) {
+        // Chunks touching the edit are rebuilt; an insertion at a boundary
+        // keeps both neighbours
+        self.chunks.retain(|c| c.end <= start || c.start >= end);
+        for chunk in &mut self.chunks {
+            if chunk.start >= end {
+                chunk.start = chunk.start - end + start + inserted;
+                chunk.end = chunk.end - end + start + inserted;
+            }
+        }
+    }
+
+    /// Prepare strips for every command not covered by a still valid chunk
+    pub(crate) fn prepare(&mut self, ctx: &mut RenderContext, commands: &[RenderCommand]) {
+        let len = commands.len();
+        let mut old = std::mem::take(&mut self.chunks).into_iter().peekable();
+        let mut state = GeometryState::default();
+        let mut i = 0;
+
+        while i < len {
+            while old.peek().is_some_and(|c| c.start < i) {
+                old.next();
+            }
+
+            if old
+                .peek()
+                .is_some_and(|c| c.start == i && c.end <= len && c.state == state)
+            {
+                let chunk = old.next().unwrap();
+                for command in &commands[chunk.start..chunk.end] {
+                    state.apply(command);
+                }
+                i = chunk.end;
+                self.chunks.push(chunk);
+                continue;
+            }
+
+            // Cut a new chunk at a layer-balanced point, stopping early at the
+            // next reusable candidate
+            let start = i;
+            let start_state = state.clone();
+            let boundary = old.peek().map(|c| c.start);
+            let mut depth = 0usize;
+            loop {
+                let command = &commands[i];
+                state.apply(command);
+                match command {
+                    RenderCommand::PushLayer(_) => depth += 1,
+                    RenderCommand::PopLayer => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                i += 1;
+                if i == len
+                    || (depth == 0 && (i - start >= CHUNK_COMMANDS || Some(i) == boundary))
+                {
+                    break;
+                }
+            }
+
+            self.chunks.push(prepare_chunk(ctx, commands, start, i, start_state));
+        }
+    }
+
+    pub(crate) fn execute(&self, ctx: &mut RenderContext) {
+        for chunk in &self.chunks {
+            ctx.execute_recording(&chunk.recording);
+        }
+    }
+
+    /// Copies of the chunks moved by `(dx, dy)` pixels, reusing their strips
+    ///
+    /// Returns None unless every chunk can be moved and stays on a
+    /// `width` x `height` canvas.
+    pub(crate) fn translated(
+        &self,
+        (dx, dy): (i32, i32),
+        width: u16,
+        height: u16,
+    ) -> Option<Vec<Recording>> {
+        let offset = Affine::translate((dx as f64, dy as f64));
+        self.chunks
+            .iter()
+            .map(|chunk| {
+                let bounds = chunk.movable?;
+                let moved = offset.transform_rect_bbox(bounds);
+                if bounds.area() > 0.0 && !on_canvas(moved, width, height) {
+                    return None;
+                }
+
+                let (strips, alphas) = chunk.recording.get_cached_strips();
+                let strips = strips
+                    .iter()
+                    .map(|strip| {
+                        let mut strip = *strip;
+                        // Sentinels closing a path keep their out-of-range x
+                        if strip.x != u16::MAX {
+                            strip.x = u16::try_from(strip.x as i32 + dx).ok()?;
+                        }
+                        strip.y = u16::try_from(strip.y as i32 + dy).ok()?;
+                        Some(strip)
+                    })
+                    .collect::<Option<Vec<Strip>>>()?;
+
+                let mut recording = Recording::new();
+                RenderContext::new(1, 1).record(&mut recording, |recorder| {
+                    replay_commands(recorder, chunk.recording.commands(), offset);
+                });
+                let ranges = chunk.recording.get_strip_generation_ranges().to_vec();
+                recording.set_cached_strips(strips, alphas.to_vec(), ranges);
+                Some(recording)
+            })
+            .collect()
+    }
+}
+
+/// Pixel offset of `transform` if it is a translation strips can be moved by:
+/// whole pixels horizontally and whole strip rows vertically
+pub(crate) fn strip_offset(transform: Affine) -> Option<(i32, i32)> {
+    let [a, b, c, d, dx, dy] = transform.as_coeffs();
+    let whole = |v: f64| v.fract() == 0.0 && v.abs() <= u16::MAX as f64;
+    let movable = [a, b, c, d] == [1.0, 0.0, 0.0, 1.0]
+        && whole(dx)
+        && whole(dy)
+        && dy % Tile::HEIGHT as f64 == 0.0;
+    movable.then_some((dx as i32, dy as i32))
+}
+
+fn on_canvas(bounds: Rect, width: u16, height: u16) -> bool {
+    bounds.x0 >= 0.0 && bounds.y0 >= 0.0 && bounds.x1 <= width as f64 && bounds.y1 <= height as f64
+}
+
+/// Device-space bounds of `local` drawn with `transform`, widened for the
+/// joins and caps of `stroke` if it is stroked
+pub(crate) fn draw_bounds(local: Rect, transform: Affine, stroke: Option<&Stroke>) -> Rect {
+    let local = match stroke {
+        // Covers miter joins up to the limit and square caps
+        Some(stroke) => {
+            let pad = stroke.width / 2.0 * stroke.miter_limit.max(SQRT_2);
+            local.inflate(pad, pad)
+        }
+        None => local,
+    };
+    transform.transform_rect_bbox(local)
+}
+
+/// Device-space bounds of the geometry drawn by a standalone recording,
+/// including anti-aliasing; None if it cannot be moved
+pub(crate) fn geometry_bounds(commands: &[RenderCommand]) -> Option<Rect> {
+    let mut state = GeometryState::default();
+    let mut bounds: Option<Rect> = None;
+    for command in commands {
+        let (local, stroked) = match command {
+            RenderCommand::FillPath(path) => (path.bounding_box(), false),
+            RenderCommand::StrokePath(path) => (path.bounding_box(), true),
+            RenderCommand::FillRect(rect) => (*rect, false),
+            RenderCommand::StrokeRect(rect) => (*rect, true),
+            // Masks stay in device space; glyphs are replayed into chunks as paths
+            RenderCommand::PushLayer(layer) if layer.mask.is_some() => return None,
+            RenderCommand::FillOutlineGlyph(_) | RenderCommand::StrokeOutlineGlyph(_) => {
+                return None;
+            }
+            command => {
+                state.apply(command);
+                continue;
+            }
+        };
+        let stroke = stroked.then_some(&state.stroke);
+        let device = draw_bounds(local, state.transform, stroke).inflate(1.0, 1.0);
+        bounds = Some(bounds.map_or(device, |bounds| bounds.union(device)));
+    }
+    Some(bounds.unwrap_or(Rect::ZERO))
+}
+
+fn prepare_chunk(
+    ctx: &mut RenderContext,
+    commands: &[RenderCommand],
+    start: usize,
+    end: usize,
+    state: GeometryState,
+) -> Chunk {
+    // Re-establish the incoming geometry state so the chunk stands on its own
+    let prefix = [
+        RenderCommand::SetTransform(state.transform),
+        RenderCommand::SetFillRule(state.fill_rule),
+        RenderCommand::SetStroke(state.stroke.clone()),
+    ];
+
+    let mut recording = Recording::new();
+    RenderContext::new(1, 1).record(&mut recording, |recorder| {
+        replay_commands(
+            recorder,
+            prefix.iter().chain(&commands[start..end]),
+            Affine::IDENTITY,
+        );
+    });
+    ctx.prepare_recording(&mut recording);
+    let movable = geometry_bounds(recording.commands()).filter(|bounds| {
+        bounds.area() == 0.0 || on_canvas(*bounds, ctx.width(), ctx.height())
+    });
+
+    Chunk {
+        start,
+        end,
+        state,
+        recording,
+        movable,
+    }
+}
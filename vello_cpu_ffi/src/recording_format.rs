@@ -0,0 +1,676 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Versioned binary encoding of recordings
+//!
+//! Layout (all values little-endian):
+//!
+//! ```text
+//! magic    "VREC"
+//! version  u16
+//! flags    u16 (reserved, 0)
+//! count    u32
+//! commands count x (tag u8, payload)
+//! ```
+//!
+//! Only the command list is stored. Cached strips depend on the renderer that
+//! prepared them and are rebuilt by `vello_render_context_prepare_recording`.
+
+use vello_common::paint::PaintType;
+use vello_common::recording::{PushLayerCommand, RenderCommand};
+use vello_cpu::kurbo::{Affine, BezPath, Cap, Join, PathEl, Point, Rect, Stroke};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::{BlendMode, ColorStop, Compose, Extend, Fill, Gradient, GradientKind, Mix};
+
+const MAGIC: &[u8; 4] = b"VREC";
+
+/// Current format version
+pub(crate) const VERSION: u16 = 1;
+
+const TAG_FILL_PATH: u8 = 0;
+const TAG_STROKE_PATH: u8 = 1;
+const TAG_FILL_RECT: u8 = 2;
+const TAG_STROKE_RECT: u8 = 3;
+const TAG_SET_TRANSFORM: u8 = 4;
+const TAG_SET_FILL_RULE: u8 = 5;
+const TAG_SET_STROKE: u8 = 6;
+const TAG_PUSH_LAYER: u8 = 7;
+const TAG_POP_LAYER: u8 = 8;
+const TAG_SET_PAINT: u8 = 9;
+const TAG_SET_PAINT_TRANSFORM: u8 = 10;
+const TAG_RESET_PAINT_TRANSFORM: u8 = 11;
+const TAG_FILL_OUTLINE_GLYPH: u8 = 12;
+const TAG_STROKE_OUTLINE_GLYPH: u8 = 13;
+
+const MIXES: [Mix; 16] = [
+    Mix::Normal,
+    Mix::Multiply,
+    Mix::Screen,
+    Mix::Overlay,
+    Mix::Darken,
+    Mix::Lighten,
+    Mix::ColorDodge,
+    Mix::ColorBurn,
+    Mix::HardLight,
+    Mix::SoftLight,
+    Mix::Difference,
+    Mix::Exclusion,
+    Mix::Hue,
+    Mix::Saturation,
+    Mix::Color,
+    Mix::Luminosity,
+];
+
+const COMPOSES: [Compose; 14] = [
+    Compose::Clear,
+    Compose::Copy,
+    Compose::Dest,
+    Compose::SrcOver,
+    Compose::DestOver,
+    Compose::SrcIn,
+    Compose::DestIn,
+    Compose::SrcOut,
+    Compose::DestOut,
+    Compose::SrcAtop,
+    Compose::DestAtop,
+    Compose::Xor,
+    Compose::Plus,
+    Compose::PlusLighter,
+];
+
+/// Encode a command list
+pub(crate) fn encode(commands: &[RenderCommand]) -> Result<Vec<u8>, &'static str> {
+    let count = u32::try_from(commands.len()).map_err(|_| "Too many commands")?;
+
+    let mut w = Writer(Vec::new());
+    w.0.extend_from_slice(MAGIC);
+    w.u16(VERSION);
+    w.u16(0);
+    w.u32(count);
+
+    for command in commands {
+        match command {
+            RenderCommand::FillPath(path) => {
+                w.u8(TAG_FILL_PATH);
+                w.path(path)?;
+            }
+            RenderCommand::StrokePath(path) => {
+                w.u8(TAG_STROKE_PATH);
+                w.path(path)?;
+            }
+            RenderCommand::FillRect(rect) => {
+                w.u8(TAG_FILL_RECT);
+                w.rect(rect);
+            }
+            RenderCommand::StrokeRect(rect) => {
+                w.u8(TAG_STROKE_RECT);
+                w.rect(rect);
+            }
+            RenderCommand::SetTransform(affine) => {
+                w.u8(TAG_SET_TRANSFORM);
+                w.affine(affine);
+            }
+            RenderCommand::SetFillRule(fill) => {
+                w.u8(TAG_SET_FILL_RULE);
+                w.u8(match fill {
+                    Fill::NonZero => 0,
+                    Fill::EvenOdd => 1,
+                });
+            }
+            RenderCommand::SetStroke(stroke) => {
+                w.u8(TAG_SET_STROKE);
+                w.stroke(stroke)?;
+            }
+            RenderCommand::PushLayer(layer) => {
+                w.u8(TAG_PUSH_LAYER);
+                w.layer(layer)?;
+            }
+            RenderCommand::PopLayer => w.u8(TAG_POP_LAYER),
+            RenderCommand::SetPaint(paint) => {
+                w.u8(TAG_SET_PAINT);
+                w.paint(paint)?;
+            }
+            RenderCommand::SetPaintTransform(affine) => {
+                w.u8(TAG_SET_PAINT_TRANSFORM);
+                w.affine(affine);
+            }
+            RenderCommand::ResetPaintTransform => w.u8(TAG_RESET_PAINT_TRANSFORM),
+            RenderCommand::FillOutlineGlyph((path, affine)) => {
+                w.u8(TAG_FILL_OUTLINE_GLYPH);
+                w.path(path)?;
+                w.affine(affine);
+            }
+            RenderCommand::StrokeOutlineGlyph((path, affine)) => {
+                w.u8(TAG_STROKE_OUTLINE_GLYPH);
+                w.path(path)?;
+                w.affine(affine);
+            }
+        }
+    }
+
+    Ok(w.0)
+}
+
+/// Decode a command list
+pub(crate) fn decode(data: &[u8]) -> Result<Vec<RenderCommand>, &'static str> {
+    let mut r = Reader { data, pos: 0 };
+    if r.bytes(4)? != MAGIC {
+        return Err("Not a serialized recording");
+    }
+    let version = r.u16()?;
+    if version == 0 || version > VERSION {
+        return Err("Unsupported recording format version");
+    }
+    let _flags = r.u16()?;
+    let count = r.u32()? as usize;
+
+    // Every command takes at least one byte, so cap the reservation by the input
+    let mut commands = Vec::with_capacity(count.min(data.len()));
+    for _ in 0..count {
+        let command = match r.u8()? {
+            TAG_FILL_PATH => RenderCommand::FillPath(r.path()?),
+            TAG_STROKE_PATH => RenderCommand::StrokePath(r.path()?),
+            TAG_FILL_RECT => RenderCommand::FillRect(r.rect()?),
+            TAG_STROKE_RECT => RenderCommand::StrokeRect(r.rect()?),
+            TAG_SET_TRANSFORM => RenderCommand::SetTransform(r.affine()?),
+            TAG_SET_FILL_RULE => RenderCommand::SetFillRule(match r.u8()? {
+                0 => Fill::NonZero,
+                1 => Fill::EvenOdd,
+                _ => return Err("Invalid fill rule"),
+            }),
+            TAG_SET_STROKE => RenderCommand::SetStroke(r.stroke()?),
+            TAG_PUSH_LAYER => RenderCommand::PushLayer(r.layer()?),
+            TAG_POP_LAYER => RenderCommand::PopLayer,
+            TAG_SET_PAINT => RenderCommand::SetPaint(r.paint()?),
+            TAG_SET_PAINT_TRANSFORM => RenderCommand::SetPaintTransform(r.affine()?),
+            TAG_RESET_PAINT_TRANSFORM => RenderCommand::ResetPaintTransform,
+            TAG_FILL_OUTLINE_GLYPH => RenderCommand::FillOutlineGlyph((r.path()?, r.affine()?)),
+            TAG_STROKE_OUTLINE_GLYPH => RenderCommand::StrokeOutlineGlyph((r.path()?, r.affine()?)),
+            _ => return Err("Unknown recording command"),
+        };
+        commands.push(command);
+    }
+
+    if r.pos != data.len() {
+        return Err("Trailing data after recording");
+    }
+    Ok(commands)
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) -> Result<(), &'static str> {
+        self.u32(u32::try_from(len).map_err(|_| "Recording element too large")?);
+        Ok(())
+    }
+
+    fn point(&mut self, p: Point) {
+        self.f64(p.x);
+        self.f64(p.y);
+    }
+
+    fn rect(&mut self, r: &Rect) {
+        self.f64(r.x0);
+        self.f64(r.y0);
+        self.f64(r.x1);
+        self.f64(r.y1);
+    }
+
+    fn affine(&mut self, a: &Affine) {
+        for c in a.as_coeffs() {
+            self.f64(c);
+        }
+    }
+
+    fn color(&mut self, c: AlphaColor<Srgb>) {
+        for v in c.components {
+            self.f32(v);
+        }
+    }
+
+    fn path(&mut self, path: &BezPath) -> Result<(), &'static str> {
+        let elements = path.elements();
+        self.len(elements.len())?;
+        for el in elements {
+            match *el {
+                PathEl::MoveTo(p) => {
+                    self.u8(0);
+                    self.point(p);
+                }
+                PathEl::LineTo(p) => {
+                    self.u8(1);
+                    self.point(p);
+                }
+                PathEl::QuadTo(p1, p2) => {
+                    self.u8(2);
+                    self.point(p1);
+                    self.point(p2);
+                }
+                PathEl::CurveTo(p1, p2, p3) => {
+                    self.u8(3);
+                    self.point(p1);
+                    self.point(p2);
+                    self.point(p3);
+                }
+                PathEl::ClosePath => self.u8(4),
+            }
+        }
+        Ok(())
+    }
+
+    fn stroke(&mut self, s: &Stroke) -> Result<(), &'static str> {
+        self.f64(s.width);
+        self.f64(s.miter_limit);
+        self.u8(match s.join {
+            Join::Bevel => 0,
+            Join::Miter => 1,
+            Join::Round => 2,
+        });
+        for cap in [s.start_cap, s.end_cap] {
+            self.u8(match cap {
+                Cap::Butt => 0,
+                Cap::Square => 1,
+                Cap::Round => 2,
+            });
+        }
+        self.f64(s.dash_offset);
+        self.len(s.dash_pattern.len())?;
+        for &dash in s.dash_pattern.iter() {
+            self.f64(dash);
+        }
+        Ok(())
+    }
+
+    fn layer(&mut self, layer: &PushLayerCommand) -> Result<(), &'static str> {
+        match &layer.clip_path {
+            Some(path) => {
+                self.u8(1);
+                self.path(path)?;
+            }
+            None => self.u8(0),
+        }
+        match layer.blend_mode {
+            Some(bm) => {
+                let mix = MIXES.iter().position(|m| *m == bm.mix);
+                let compose = COMPOSES.iter().position(|c| *c == bm.compose);
+                let (Some(mix), Some(compose)) = (mix, compose) else {
+                    return Err("Unsupported blend mode in recording");
+                };
+                self.u8(1);
+                self.u8(mix as u8);
+                self.u8(compose as u8);
+            }
+            None => self.u8(0),
+        }
+        match layer.opacity {
+            Some(opacity) => {
+                self.u8(1);
+                self.f32(opacity);
+            }
+            None => self.u8(0),
+        }
+        match &layer.mask {
+            Some(mask) => {
+                self.u8(1);
+                self.u16(mask.width());
+                self.u16(mask.height());
+                for y in 0..mask.height() {
+                    for x in 0..mask.width() {
+                        self.u8(mask.sample(x, y));
+                    }
+                }
+            }
+            None => self.u8(0),
+        }
+        Ok(())
+    }
+
+    fn paint(&mut self, paint: &PaintType) -> Result<(), &'static str> {
+        match paint {
+            PaintType::Solid(color) => {
+                self.u8(0);
+                self.color(*color);
+            }
+            PaintType::Gradient(gradient) => {
+                self.u8(1);
+                match &gradient.kind {
+                    GradientKind::Linear(pos) => {
+                        self.u8(0);
+                        self.point(pos.start);
+                        self.point(pos.end);
+                    }
+                    GradientKind::Radial(pos) => {
+                        self.u8(1);
+                        self.point(pos.start_center);
+                        self.f32(pos.start_radius);
+                        self.point(pos.end_center);
+                        self.f32(pos.end_radius);
+                    }
+                    GradientKind::Sweep(pos) => {
+                        self.u8(2);
+                        self.point(pos.center);
+                        self.f32(pos.start_angle);
+                        self.f32(pos.end_angle);
+                    }
+                }
+                self.u8(match gradient.extend {
+                    Extend::Pad => 0,
+                    Extend::Repeat => 1,
+                    Extend::Reflect => 2,
+                });
+                self.len(gradient.stops.len())?;
+                for stop in gradient.stops.iter() {
+                    self.f32(stop.offset);
+                    self.color(stop.color.to_alpha_color::<Srgb>());
+                }
+            }
+            PaintType::Image(_) => return Err("Image paints cannot be serialized"),
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len());
+        let end = end.ok_or("Truncated recording data")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, &'static str> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, &'static str> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, &'static str> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, &'static str> {
+        Ok(f64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn flag(&mut self) -> Result<bool, &'static str> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err("Invalid flag in recording"),
+        }
+    }
+
+    fn point(&mut self) -> Result<Point, &'static str> {
+        Ok(Point::new(self.f64()?, self.f64()?))
+    }
+
+    fn rect(&mut self) -> Result<Rect, &'static str> {
+        Ok(Rect::new(
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+        ))
+    }
+
+    fn affine(&mut self) -> Result<Affine, &'static str> {
+        let mut coeffs = [0.0; 6];
+        for c in &mut coeffs {
+            *c = self.f64()?;
+        }
+        Ok(Affine::new(coeffs))
+    }
+
+    fn color(&mut self) -> Result<AlphaColor<Srgb>, &'static str> {
+        Ok(AlphaColor::new([
+            self.f32()?,
+            self.f32()?,
+            self.f32()?,
+            self.f32()?,
+        ]))
+    }
+
+    fn path(&mut self) -> Result<BezPath, &'static str> {
+        let count = self.u32()?;
+        let mut path = BezPath::new();
+        for _ in 0..count {
+            match self.u8()? {
+                0 => path.move_to(self.point()?),
+                1 => path.line_to(self.point()?),
+                2 => path.quad_to(self.point()?, self.point()?),
+                3 => path.curve_to(self.point()?, self.point()?, self.point()?),
+                4 => path.close_path(),
+                _ => return Err("Invalid path element"),
+            }
+        }
+        Ok(path)
+    }
+
+    fn stroke(&mut self) -> Result<Stroke, &'static str> {
+        let mut stroke = Stroke::new(self.f64()?);
+        stroke.miter_limit = self.f64()?;
+        stroke.join = match self.u8()? {
+            0 => Join::Bevel,
+            1 => Join::Miter,
+            2 => Join::Round,
+            _ => return Err("Invalid stroke join"),
+        };
+        let mut caps = [Cap::Butt; 2];
+        for cap in &mut caps {
+            *cap = match self.u8()? {
+                0 => Cap::Butt,
+                1 => Cap::Square,
+                2 => Cap::Round,
+                _ => return Err("Invalid stroke cap"),
+            };
+        }
+        stroke.start_cap = caps[0];
+        stroke.end_cap = caps[1];
+        let dash_offset = self.f64()?;
+        let dash_count = self.u32()?;
+        let mut dashes = Vec::new();
+        for _ in 0..dash_count {
+            dashes.push(self.f64()?);
+        }
+        Ok(stroke.with_dashes(dash_offset, dashes))
+    }
+
+    fn layer(&mut self) -> Result<PushLayerCommand, &'static str> {
+        let clip_path = if self.flag()? {
+            Some(self.path()?)
+        } else {
+            None
+        };
+        let blend_mode = if self.flag()? {
+            let mix = *MIXES.get(self.u8()? as usize).ok_or("Invalid mix mode")?;
+            let compose = *COMPOSES
+                .get(self.u8()? as usize)
+                .ok_or("Invalid compose mode")?;
+            Some(BlendMode::new(mix, compose))
+        } else {
+            None
+        };
+        let opacity = if self.flag()? {
+            Some(self.f32()?)
+        } else {
+            None
+        };
+        let mask = if self.flag()? {
+            let (width, height) = (self.u16()?, self.u16()?);
+            let coverage = self.bytes(width as usize * height as usize)?;
+            Some(crate::mask::mask_from_coverage(coverage, width, height))
+        } else {
+            None
+        };
+        Ok(PushLayerCommand {
+            clip_path,
+            blend_mode,
+            opacity,
+            mask,
+        })
+    }
+
+    fn paint(&mut self) -> Result<PaintType, &'static str> {
+        match self.u8()? {
+            0 => Ok(PaintType::Solid(self.color()?)),
+            1 => {
+                let gradient = match self.u8()? {
+                    0 => Gradient::new_linear(self.point()?, self.point()?),
+                    1 => Gradient::new_two_point_radial(
+                        self.point()?,
+                        self.f32()?,
+                        self.point()?,
+                        self.f32()?,
+                    ),
+                    2 => Gradient::new_sweep(self.point()?, self.f32()?, self.f32()?),
+                    _ => return Err("Invalid gradient kind"),
+                };
+                let extend = match self.u8()? {
+                    0 => Extend::Pad,
+                    1 => Extend::Repeat,
+                    2 => Extend::Reflect,
+                    _ => return Err("Invalid gradient extend"),
+                };
+                let stop_count = self.u32()?;
+                let mut stops = Vec::new();
+                for _ in 0..stop_count {
+                    let offset = self.f32()?;
+                    let color = self.color()?;
+                    stops.push(ColorStop {
+                        offset,
+                        color: color.into(),
+                    });
+                }
+                Ok(PaintType::Gradient(
+                    gradient.with_stops(&stops[..]).with_extend(extend),
+                ))
+            }
+            _ => Err("Invalid paint kind"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands() -> Vec<RenderCommand> {
+        let mut path = BezPath::new();
+        path.move_to((1.0, 2.0));
+        path.quad_to((4.0, 0.5), (6.0, 2.0));
+        path.curve_to((7.0, 3.0), (8.0, 5.0), (6.0, 7.0));
+        path.close_path();
+        let layer = PushLayerCommand {
+            clip_path: Some(path.clone()),
+            blend_mode: Some(BlendMode::new(Mix::Multiply, Compose::SrcAtop)),
+            opacity: Some(0.5),
+            mask: None,
+        };
+        let stop = |offset, rgba| ColorStop { offset, color: AlphaColor::<Srgb>::new(rgba).into() };
+        let stops = [stop(0.0, [1.0, 0.0, 0.0, 1.0]), stop(1.0, [0.0, 0.0, 1.0, 0.5])];
+        let gradient = Gradient::new_linear((0.0, 0.0), (8.0, 0.0)).with_stops(&stops[..]);
+
+        vec![
+            RenderCommand::SetTransform(Affine::translate((3.0, -1.5))),
+            RenderCommand::SetFillRule(Fill::EvenOdd),
+            RenderCommand::SetPaint(PaintType::Solid(AlphaColor::new([0.25, 0.5, 0.75, 1.0]))),
+            RenderCommand::FillPath(path),
+            RenderCommand::SetStroke(Stroke::new(2.5).with_dashes(1.0, [3.0, 1.5])),
+            RenderCommand::StrokeRect(Rect::new(0.5, 1.0, 9.0, 4.5)),
+            RenderCommand::PushLayer(layer),
+            RenderCommand::SetPaint(PaintType::Gradient(gradient.with_extend(Extend::Reflect))),
+            RenderCommand::SetPaintTransform(Affine::scale(2.0)),
+            RenderCommand::FillRect(Rect::new(0.0, 0.0, 4.0, 4.0)),
+            RenderCommand::ResetPaintTransform,
+            RenderCommand::PopLayer,
+        ]
+    }
+
+    #[test]
+    fn commands_round_trip() {
+        let data = encode(&commands()).unwrap();
+        assert_eq!(&data[..4], MAGIC);
+        assert_eq!(u16::from_le_bytes([data[4], data[5]]), VERSION);
+
+        let decoded = decode(&data).unwrap();
+        assert_eq!(decoded.len(), 12);
+        assert!(matches!(decoded[1], RenderCommand::SetFillRule(Fill::EvenOdd)));
+        assert!(matches!(&decoded[5], RenderCommand::StrokeRect(r) if r.y1 == 4.5));
+        assert!(matches!(&decoded[6], RenderCommand::PushLayer(l) if l.opacity == Some(0.5)));
+        // Nothing is lost, so encoding again gives the same bytes
+        assert_eq!(encode(&decoded).unwrap(), data);
+        assert!(decode(&encode(&[]).unwrap()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn truncated_and_corrupt_data_is_rejected() {
+        let data = encode(&commands()).unwrap();
+        for len in 0..data.len() {
+            assert!(decode(&data[..len]).is_err(), "{len} bytes");
+        }
+
+        let mut trailing = data.clone();
+        trailing.push(0);
+        assert_eq!(decode(&trailing).err(), Some("Trailing data after recording"));
+
+        let mut magic = data.clone();
+        magic[0] = b'X';
+        assert_eq!(decode(&magic).err(), Some("Not a serialized recording"));
+
+        // The first command is the transform
+        let mut tag = data.clone();
+        tag[12] = 0xff;
+        assert_eq!(decode(&tag).err(), Some("Unknown recording command"));
+
+        // A huge count is bounded by the input instead of reserved up front
+        let mut count = data.clone();
+        count[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decode(&count).err(), Some("Truncated recording data"));
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let data = encode(&commands()).unwrap();
+        for version in [0, VERSION + 1] {
+            let mut other = data.clone();
+            other[4..6].copy_from_slice(&version.to_le_bytes());
+            assert_eq!(decode(&other).err(), Some("Unsupported recording format version"));
+        }
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Separable image resampling shared by the pixmap and mask bindings
+
+use crate::types::VelloResampleFilter;
+
+/// Filter support radius in source pixels at a scale of 1
+fn support(filter: VelloResampleFilter) -> f32 {
+    match filter {
+        VelloResampleFilter::Nearest => 0.5,
+        VelloResampleFilter::Bilinear => 1.0,
+        VelloResampleFilter::Mitchell => 2.0,
+        VelloResampleFilter::Lanczos3 => 3.0,
+    }
+}
+
+fn weight(filter: VelloResampleFilter, x: f32) -> f32 {
+    let x = x.abs();
+    match filter {
+        VelloResampleFilter::Nearest => {
+            if x < 0.5 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        VelloResampleFilter::Bilinear => (1.0 - x).max(0.0),
+        VelloResampleFilter::Mitchell => {
+            // Mitchell-Netravali with B = C = 1/3
+            const B: f32 = 1.0 / 3.0;
+            const C: f32 = 1.0 / 3.0;
+            if x < 1.0 {
+                ((12.0 - 9.0 * B - 6.0 * C) * x * x * x
+                    + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+                    + (6.0 - 2.0 * B))
+                    / 6.0
+            } else if x < 2.0 {
+                ((-B - 6.0 * C) * x * x * x
+                    + (6.0 * B + 30.0 * C) * x * x
+                    + (-12.0 * B - 48.0 * C) * x
+                    + (8.0 * B + 24.0 * C))
+                    / 6.0
+            } else {
+                0.0
+            }
+        }
+        VelloResampleFilter::Lanczos3 => {
+            if x < 1e-6 {
+                1.0
+            } else if x < 3.0 {
+                let px = std::f32::consts::PI * x;
+                3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+            } else {
+                0.0
+            }
+        }
+    }
+}
+
+/// Source start index and normalized weights for every destination sample
+fn contributions(src_len: usize, dst_len: usize, filter: VelloResampleFilter) -> Vec<(usize, Vec<f32>)> {
+    let scale = src_len as f32 / dst_len as f32;
+    // Widen the filter when shrinking so every source pixel contributes
+    let filter_scale = scale.max(1.0);
+    let radius = support(filter) * filter_scale;
+
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            if filter == VelloResampleFilter::Nearest {
+                let index = (center as usize).min(src_len - 1);
+                return (index, vec![1.0]);
+            }
+
+            let start = ((center - radius).floor().max(0.0)) as usize;
+            let end = ((center + radius).ceil() as usize).min(src_len);
+            let mut weights: Vec<f32> = (start..end)
+                .map(|j| weight(filter, (j as f32 + 0.5 - center) / filter_scale))
+                .collect();
+            let sum: f32 = weights.iter().sum();
+            if sum.abs() > f32::EPSILON {
+                for w in &mut weights {
+                    *w /= sum;
+                }
+            }
+            (start, weights)
+        })
+        .collect()
+}
+
+/// Resample a row-major image with `C` channels per pixel
+pub(crate) fn resample<const C: usize>(
+    src: &[[f32; C]],
+    src_width: usize,
+    src_height: usize,
+    dst_width: usize,
+    dst_height: usize,
+    filter: VelloResampleFilter,
+) -> Vec<[f32; C]> {
+    if src_width == 0 || src_height == 0 || dst_width == 0 || dst_height == 0 {
+        return vec![[0.0; C]; dst_width * dst_height];
+    }
+
+    // Horizontal pass: src_height rows of dst_width samples
+    let columns = contributions(src_width, dst_width, filter);
+    let mut horizontal = vec![[0.0f32; C]; dst_width * src_height];
+    for y in 0..src_height {
+        let row = &src[y * src_width..(y + 1) * src_width];
+        for (x, (start, weights)) in columns.iter().enumerate() {
+            let mut acc = [0.0f32; C];
+            for (k, w) in weights.iter().enumerate() {
+                for (sum, value) in acc.iter_mut().zip(row[start + k]) {
+                    *sum += value * w;
+                }
+            }
+            horizontal[y * dst_width + x] = acc;
+        }
+    }
+
+    // Vertical pass
+    let rows = contributions(src_height, dst_height, filter);
+    let mut out = vec![[0.0f32; C]; dst_width * dst_height];
+    for (y, (start, weights)) in rows.iter().enumerate() {
+        for x in 0..dst_width {
+            let mut acc = [0.0f32; C];
+            for (k, w) in weights.iter().enumerate() {
+                for (sum, value) in acc.iter_mut().zip(horizontal[(start + k) * dst_width + x]) {
+                    *sum += value * w;
+                }
+            }
+            out[y * dst_width + x] = acc;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILTERS: [VelloResampleFilter; 4] = [
+        VelloResampleFilter::Nearest,
+        VelloResampleFilter::Bilinear,
+        VelloResampleFilter::Mitchell,
+        VelloResampleFilter::Lanczos3,
+    ];
+
+    fn gray(values: &[f32]) -> Vec<[f32; 1]> {
+        values.iter().map(|&v| [v]).collect()
+    }
+
+    fn assert_close(actual: &[[f32; 1]], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a[0] - e).abs() < 1e-3, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn interpolating_filters_keep_the_image_at_the_same_size() {
+        let values = [0.0, 50.0, 100.0, 150.0, 200.0, 250.0];
+        for filter in [FILTERS[0], FILTERS[1], FILTERS[3]] {
+            assert_close(&resample(&gray(&values), 3, 2, 3, 2, filter), &values);
+        }
+
+        // Mitchell's B = 1/3 smooths even at a scale of 1
+        let impulse = gray(&[0.0, 255.0, 0.0]);
+        let impulse = resample(&impulse, 3, 1, 3, 1, VelloResampleFilter::Mitchell);
+        assert_close(&impulse, &[15.0, 255.0 * 16.0 / 18.0, 15.0]);
+    }
+
+    #[test]
+    fn weights_are_normalized_when_scaling() {
+        let src = vec![[10.0, 20.0, 30.0, 40.0]; 5 * 3];
+        for filter in FILTERS {
+            for (width, height) in [(12, 7), (2, 1), (1, 9), (5, 3)] {
+                let out = resample(&src, 5, 3, width, height, filter);
+                assert_eq!(out.len(), width * height);
+                for pixel in out {
+                    for (value, expected) in pixel.iter().zip([10.0, 20.0, 30.0, 40.0]) {
+                        assert!((value - expected).abs() < 1e-3, "{filter:?}: {pixel:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shrinking_covers_every_source_pixel() {
+        let src = gray(&[[0.0, 0.0, 255.0, 255.0]; 4].concat());
+
+        let halved = resample(&src, 4, 4, 2, 4, VelloResampleFilter::Nearest);
+        assert_close(&halved, &[[0.0, 255.0]; 4].concat());
+        // A 1-pixel-wide target weighs the row symmetrically around its center
+        let column = resample(&src, 4, 4, 1, 4, VelloResampleFilter::Bilinear);
+        assert_close(&column, &[127.5; 4]);
+        let single = resample(&src, 4, 4, 1, 1, VelloResampleFilter::Bilinear);
+        assert_close(&single, &[127.5]);
+    }
+
+    #[test]
+    fn empty_images_resample_to_transparent() {
+        let src = gray(&[255.0; 4]);
+        for filter in FILTERS {
+            assert!(resample(&src, 2, 2, 0, 0, filter).is_empty());
+            assert!(resample(&src, 2, 2, 0, 3, filter).is_empty());
+            assert_close(&resample(&src, 2, 2, 1, 3, filter), &[255.0; 3]);
+            assert_close(&resample::<1>(&[], 0, 0, 2, 1, filter), &[0.0; 2]);
+        }
+    }
+}
@@ -0,0 +1,995 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Replayable log of the drawing on a render context
+//!
+//! vello_cpu bins drawing into tiles as it is issued and cannot hand the scene
+//! back out. `Scene` wraps a context and logs every draw call and state change
+//! while forwarding it, so the drawing since the last reset can be replayed:
+//! into a copy of the context, into a context rebuilt with other settings, or
+//! offset into a smaller context covering one region. Deferred scenes only log
+//! and are rendered by replaying them in bands on a worker pool.
+
+use std::ops::Deref;
+
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use skrifa::instance::NormalizedCoord;
+use vello_common::paint::PaintType;
+use vello_common::recording::{PushLayerCommand, Recordable, Recorder, Recording, RenderCommand};
+use vello_common::tile::Tile;
+use vello_cpu::kurbo::{Affine, BezPath, Rect, Shape, Stroke};
+use vello_cpu::peniko::color::palette::css::BLACK;
+use vello_cpu::peniko::{BlendMode, Compose, Fill, FontData};
+use vello_cpu::{Glyph, Mask, Pixmap, RenderContext, RenderMode, RenderSettings};
+
+use crate::context::{dash_path, undashed};
+use crate::recording_chunks::{draw_bounds, geometry_bounds};
+
+/// Anything draw calls can be replayed into
+pub(crate) trait DrawTarget {
+    fn set_paint(&mut self, paint: PaintType);
+    fn set_paint_transform(&mut self, transform: Affine);
+    fn reset_paint_transform(&mut self);
+    fn set_transform(&mut self, transform: Affine);
+    fn set_fill_rule(&mut self, fill_rule: Fill);
+    fn set_stroke(&mut self, stroke: Stroke);
+    fn fill_path(&mut self, path: &BezPath);
+    fn stroke_path(&mut self, path: &BezPath);
+    fn fill_rect(&mut self, rect: &Rect);
+    fn stroke_rect(&mut self, rect: &Rect);
+    fn push_layer(
+        &mut self,
+        clip_path: Option<&BezPath>,
+        blend_mode: Option<BlendMode>,
+        opacity: Option<f32>,
+        mask: Option<Mask>,
+    );
+    fn pop_layer(&mut self);
+    fn draw_glyphs(&mut self, run: &GlyphRun);
+
+    /// Push `layer` also clipped to the device-space rectangle `bounds`
+    ///
+    /// The bounds are drawn with `origin` as the transform, after which
+    /// `transform` is restored. A layer without a clip path of its own takes
+    /// the bounds as its clip; otherwise it is nested in a clip layer for them.
+    fn push_bounded_layer(
+        &mut self,
+        layer: &PushLayerCommand,
+        bounds: Rect,
+        origin: Affine,
+        transform: Affine,
+    ) {
+        let bounds = bounds.to_path(0.1);
+        let mask = layer.mask.clone();
+        self.set_transform(origin);
+        match &layer.clip_path {
+            None => self.push_layer(Some(&bounds), layer.blend_mode, layer.opacity, mask),
+            Some(clip) => {
+                self.push_layer(Some(&bounds), None, None, None);
+                self.set_transform(transform);
+                self.push_layer(Some(clip), layer.blend_mode, layer.opacity, mask);
+            }
+        }
+        self.set_transform(transform);
+    }
+
+    /// Pop a layer pushed by `push_bounded_layer`, and its clip layer if `nested`
+    fn pop_bounded_layer(&mut self, nested: bool) {
+        self.pop_layer();
+        if nested {
+            self.pop_layer();
+        }
+    }
+
+    /// Recordings have no blurred rectangles; recorders skip them
+    fn fill_blurred_rounded_rect(&mut self, _rect: &Rect, _radius: f32, _std_dev: f32) {}
+
+    /// Recordings have no aliasing threshold; recorders skip it
+    fn set_aliasing_threshold(&mut self, _threshold: Option<u8>) {}
+}
+
+macro_rules! impl_draw_target {
+    ($ty:ty, { $($extra:item)* }) => {
+        impl DrawTarget for $ty {
+            fn set_paint(&mut self, paint: PaintType) {
+                <$ty>::set_paint(self, paint)
+            }
+            fn set_paint_transform(&mut self, transform: Affine) {
+                <$ty>::set_paint_transform(self, transform)
+            }
+            fn reset_paint_transform(&mut self) {
+                <$ty>::reset_paint_transform(self)
+            }
+            fn set_transform(&mut self, transform: Affine) {
+                <$ty>::set_transform(self, transform)
+            }
+            fn set_fill_rule(&mut self, fill_rule: Fill) {
+                <$ty>::set_fill_rule(self, fill_rule)
+            }
+            fn set_stroke(&mut self, stroke: Stroke) {
+                <$ty>::set_stroke(self, stroke)
+            }
+            fn fill_path(&mut self, path: &BezPath) {
+                <$ty>::fill_path(self, path)
+            }
+            fn stroke_path(&mut self, path: &BezPath) {
+                <$ty>::stroke_path(self, path)
+            }
+            fn fill_rect(&mut self, rect: &Rect) {
+                <$ty>::fill_rect(self, rect)
+            }
+            fn stroke_rect(&mut self, rect: &Rect) {
+                <$ty>::stroke_rect(self, rect)
+            }
+            fn push_layer(
+                &mut self,
+                clip_path: Option<&BezPath>,
+                blend_mode: Option<BlendMode>,
+                opacity: Option<f32>,
+                mask: Option<Mask>,
+            ) {
+                <$ty>::push_layer(self, clip_path, blend_mode, opacity, mask)
+            }
+            fn pop_layer(&mut self) {
+                <$ty>::pop_layer(self)
+            }
+            fn draw_glyphs(&mut self, run: &GlyphRun) {
+                let mut builder = self.glyph_run(&run.font);
+                if let Some(font_size) = run.font_size {
+                    builder = builder.font_size(font_size);
+                }
+                if let Some(hint) = run.hint {
+                    builder = builder.hint(hint);
+                }
+                if let Some(transform) = run.glyph_transform {
+                    builder = builder.glyph_transform(transform);
+                }
+                let builder = builder.normalized_coords(&run.normalized_coords);
+                let glyphs = run.glyphs.iter().copied();
+                if run.stroke {
+                    builder.stroke_glyphs(glyphs);
+                } else {
+                    builder.fill_glyphs(glyphs);
+                }
+            }
+            $($extra)*
+        }
+    };
+}
+
+impl_draw_target!(RenderContext, {
+    fn fill_blurred_rounded_rect(&mut self, rect: &Rect, radius: f32, std_dev: f32) {
+        RenderContext::fill_blurred_rounded_rect(self, rect, radius, std_dev)
+    }
+
+    fn set_aliasing_threshold(&mut self, threshold: Option<u8>) {
+        RenderContext::set_aliasing_threshold(self, threshold)
+    }
+});
+
+impl_draw_target!(Recorder<'_>, {});
+
+/// A glyph run with the options it was drawn with
+#[derive(Clone)]
+pub(crate) struct GlyphRun {
+    font: FontData,
+    font_size: Option<f32>,
+    hint: Option<bool>,
+    normalized_coords: Vec<NormalizedCoord>,
+    glyph_transform: Option<Affine>,
+    glyphs: Vec<Glyph>,
+    stroke: bool,
+}
+
+/// One logged call
+enum SceneOp {
+    Command(RenderCommand),
+    BlurredRoundedRect { rect: Rect, radius: f32, std_dev: f32 },
+    AliasingThreshold(Option<u8>),
+    Glyphs(Box<GlyphRun>),
+    /// Layer pushed with device-space bounds it is also clipped to
+    BoundedLayer { layer: PushLayerCommand, bounds: Rect },
+    PopBoundedLayer { nested: bool },
+}
+
+/// A bounded layer open on a `Scene`
+struct BoundedLayer {
+    /// Index of the `SceneOp::BoundedLayer` that pushed it
+    op: usize,
+    /// Pixel-aligned device-space bounds
+    bounds: Rect,
+    /// Whether the layer is nested in a clip layer for the bounds
+    nested: bool,
+    /// Whether drawing left the bounds, so they have to be dropped on pop
+    overflowed: bool,
+}
+
+/// Tracks the state a command list depends on while replaying it
+pub(crate) struct CommandReplay {
+    origin: Affine,
+    transform: Affine,
+    fill_rule: Fill,
+}
+
+impl CommandReplay {
+    /// Start replaying onto a target in its initial state, with every transform
+    /// pre-multiplied by `origin`
+    pub(crate) fn new(target: &mut impl DrawTarget, origin: Affine) -> Self {
+        if origin != Affine::IDENTITY {
+            target.set_transform(origin);
+        }
+        Self {
+            origin,
+            transform: Affine::IDENTITY,
+            fill_rule: Fill::NonZero,
+        }
+    }
+
+    /// Push a layer logged with device-space `bounds`, moved by the origin
+    pub(crate) fn push_bounded_layer(
+        &self,
+        target: &mut impl DrawTarget,
+        layer: &PushLayerCommand,
+        bounds: Rect,
+    ) {
+        let transform = self.origin * self.transform;
+        target.push_bounded_layer(layer, bounds, self.origin, transform);
+    }
+
+    pub(crate) fn apply(&mut self, target: &mut impl DrawTarget, command: &RenderCommand) {
+        let origin = self.origin;
+        match command {
+            RenderCommand::FillPath(path) => target.fill_path(path),
+            RenderCommand::StrokePath(path) => target.stroke_path(path),
+            RenderCommand::FillRect(rect) => target.fill_rect(rect),
+            RenderCommand::StrokeRect(rect) => target.stroke_rect(rect),
+            RenderCommand::SetTransform(affine) => {
+                self.transform = *affine;
+                target.set_transform(origin * *affine);
+            }
+            RenderCommand::SetFillRule(fill) => {
+                self.fill_rule = *fill;
+                target.set_fill_rule(*fill);
+            }
+            RenderCommand::SetStroke(stroke) => target.set_stroke(stroke.clone()),
+            RenderCommand::PushLayer(layer) => target.push_layer(
+                layer.clip_path.as_ref(),
+                layer.blend_mode,
+                layer.opacity,
+                layer.mask.clone(),
+            ),
+            RenderCommand::PopLayer => target.pop_layer(),
+            RenderCommand::SetPaint(paint) => target.set_paint(paint.clone()),
+            RenderCommand::SetPaintTransform(affine) => target.set_paint_transform(*affine),
+            RenderCommand::ResetPaintTransform => target.reset_paint_transform(),
+            RenderCommand::FillOutlineGlyph((path, glyph_transform)) => {
+                target.set_transform(origin * *glyph_transform);
+                target.set_fill_rule(Fill::NonZero);
+                target.fill_path(path);
+                target.set_fill_rule(self.fill_rule);
+                target.set_transform(origin * self.transform);
+            }
+            RenderCommand::StrokeOutlineGlyph((path, glyph_transform)) => {
+                target.set_transform(origin * *glyph_transform);
+                target.stroke_path(path);
+                target.set_transform(origin * self.transform);
+            }
+        }
+    }
+}
+
+/// A render context together with the log of what was drawn on it
+///
+/// Dereferences to the context for queries. Drawing goes through the methods
+/// below, which mirror the context's and log each call. A deferred scene only
+/// logs draws and keeps the context's state current; it is rendered by
+/// replaying it.
+pub(crate) struct Scene {
+    ctx: RenderContext,
+    ops: Vec<SceneOp>,
+    aliasing_threshold: Option<u8>,
+    /// User-space tolerance dashed strokes are flattened to before dashing,
+    /// if the FFI layer dashes them instead of the renderer
+    dash_tolerance: Option<f64>,
+    deferred: bool,
+    /// Whether a mask layer was pushed; masks are device-space and cannot be
+    /// moved by a replay origin
+    has_masks: bool,
+    /// Open layers, with the bounds of those pushed with a bounds hint
+    layers: Vec<Option<BoundedLayer>>,
+}
+
+impl Scene {
+    pub(crate) fn new(ctx: RenderContext) -> Self {
+        Self {
+            ctx,
+            ops: Vec::new(),
+            aliasing_threshold: None,
+            dash_tolerance: None,
+            deferred: false,
+            has_masks: false,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Like `new`, but draws are only logged until the scene is rendered
+    pub(crate) fn new_deferred(ctx: RenderContext) -> Self {
+        Self {
+            deferred: true,
+            ..Self::new(ctx)
+        }
+    }
+
+    /// Empty scene for an intermediate render, deferred if it will be rendered
+    /// on a pool
+    pub(crate) fn scratch(width: u16, height: u16, pool: Option<&ThreadPool>) -> Self {
+        let ctx = RenderContext::new(width, height);
+        match pool {
+            Some(_) => Self::new_deferred(ctx),
+            None => Self::new(ctx),
+        }
+    }
+
+    /// The wrapped context, for calls that do not draw, like recording
+    pub(crate) fn context_mut(&mut self) -> &mut RenderContext {
+        &mut self.ctx
+    }
+
+    /// Bytes held by the log, roughly
+    pub(crate) fn log_bytes(&self) -> usize {
+        let path_bytes = |path: &BezPath| std::mem::size_of_val(path.elements());
+        let payload = |op: &SceneOp| match op {
+            SceneOp::Command(RenderCommand::FillPath(path) | RenderCommand::StrokePath(path)) => {
+                path_bytes(path)
+            }
+            SceneOp::Command(RenderCommand::PushLayer(layer))
+            | SceneOp::BoundedLayer { layer, .. } => {
+                layer.clip_path.as_ref().map_or(0, path_bytes)
+            }
+            SceneOp::Glyphs(run) => {
+                std::mem::size_of::<GlyphRun>() + std::mem::size_of_val(run.glyphs.as_slice())
+            }
+            _ => 0,
+        };
+        self.ops.capacity() * std::mem::size_of::<SceneOp>()
+            + self.ops.iter().map(payload).sum::<usize>()
+    }
+
+    /// The logged drawing as a prepared recording
+    ///
+    /// Blurred rounded rectangles and aliasing thresholds have no recorded form
+    /// and are left out.
+    pub(crate) fn to_recording(&self) -> Recording {
+        let mut ctx = RenderContext::new(self.width(), self.height());
+        let mut recording = Recording::new();
+        ctx.record(&mut recording, |recorder| self.replay(recorder, Affine::IDENTITY));
+        ctx.prepare_recording(&mut recording);
+        recording
+    }
+
+    /// Bytes of strips and coverage the logged drawing rasterizes to
+    pub(crate) fn strip_bytes(&self) -> usize {
+        let recording = self.to_recording();
+        let (strips, alphas) = recording.get_cached_strips();
+        std::mem::size_of_val(strips) + alphas.len()
+    }
+
+    /// Replay the logged drawing into a target in its initial state, with every
+    /// transform pre-multiplied by `origin`
+    ///
+    /// Masks stay in device space; scenes with masks should only be replayed
+    /// with an identity origin.
+    pub(crate) fn replay(&self, target: &mut impl DrawTarget, origin: Affine) {
+        replay_ops(&self.ops, target, origin);
+    }
+
+    /// Render into premultiplied RGBA8 bytes
+    ///
+    /// A scene drawing into its context renders it directly and must be
+    /// flushed first. A deferred scene is replayed instead: into one band of
+    /// rows per worker of `pool`, or on the calling thread without one. Scenes
+    /// with masks are replayed whole, on one worker, since masks cannot move.
+    pub(crate) fn render(
+        &self,
+        buffer: &mut [u8],
+        width: u16,
+        height: u16,
+        render_mode: RenderMode,
+        pool: Option<&ThreadPool>,
+    ) {
+        if !self.deferred {
+            self.ctx.render_to_buffer(buffer, width, height, render_mode);
+            return;
+        }
+        self.replay_render(buffer, (0, 0), width, height, render_mode, pool);
+    }
+
+    /// Render the `width` x `height` region whose top-left corner is (x, y)
+    ///
+    /// The scene is replayed offset into a context of the region's size, so
+    /// only the region is rasterized. Scenes with masks, which cannot move, are
+    /// rendered up to the region's far corner instead and cropped; a scene
+    /// drawing into its context must then be flushed.
+    pub(crate) fn render_region(
+        &self,
+        (x, y): (u16, u16),
+        width: u16,
+        height: u16,
+        pool: Option<&ThreadPool>,
+    ) -> Pixmap {
+        let render_mode = self.ctx.render_settings().render_mode;
+        let mut region = Pixmap::new(width, height);
+        if !self.has_masks {
+            let buffer = region.data_as_u8_slice_mut();
+            self.replay_render(buffer, (x, y), width, height, render_mode, pool);
+            return region;
+        }
+
+        let (extent_x, extent_y) = (x + width, y + height);
+        let mut extent = Pixmap::new(extent_x, extent_y);
+        let buffer = extent.data_as_u8_slice_mut();
+        self.render(buffer, extent_x, extent_y, render_mode, pool);
+        let (src, dst) = (extent.data(), region.data_mut());
+        for (row, dst) in dst.chunks_exact_mut(width as usize).enumerate() {
+            let start = (y as usize + row) * extent_x as usize + x as usize;
+            dst.copy_from_slice(&src[start..start + width as usize]);
+        }
+        region
+    }
+
+    /// Replay the scene with (x, y) moved to the origin into fresh contexts
+    /// covering the buffer, one band of rows per worker of `pool`
+    fn replay_render(
+        &self,
+        buffer: &mut [u8],
+        (x, y): (u16, u16),
+        width: u16,
+        height: u16,
+        render_mode: RenderMode,
+        pool: Option<&ThreadPool>,
+    ) {
+        let row_len = width as usize * 4;
+        if row_len == 0 || height == 0 {
+            return;
+        }
+
+        let workers = match pool {
+            Some(pool) if !self.has_masks => pool.current_num_threads(),
+            _ => 1,
+        };
+        // Bands start on strip boundaries so no strip is split between two
+        let rows = (height as usize).div_ceil(workers.max(1));
+        let band = rows.next_multiple_of(Tile::HEIGHT as usize);
+        let ops = self.ops.as_slice();
+        let level = self.ctx.render_settings().level;
+        let render_band = |(index, rows): (usize, &mut [u8])| {
+            let band_height = (rows.len() / row_len) as u16;
+            let settings = RenderSettings {
+                level,
+                num_threads: 0,
+                render_mode,
+            };
+            let mut ctx = RenderContext::new_with(width, band_height, settings);
+            let y0 = y as f64 + (index * band) as f64;
+            replay_ops(ops, &mut ctx, Affine::translate((-(x as f64), -y0)));
+            ctx.flush();
+            ctx.render_to_buffer(rows, width, band_height, render_mode);
+        };
+
+        let buffer = &mut buffer[..row_len * height as usize];
+        match pool {
+            Some(pool) => pool.install(|| {
+                buffer.par_chunks_mut(row_len * band).enumerate().for_each(render_band)
+            }),
+            None => buffer.chunks_mut(row_len * band).enumerate().for_each(render_band),
+        }
+    }
+
+    /// Render into a pixmap with the context's render mode; see `render`
+    pub(crate) fn render_pixmap(&self, pixmap: &mut Pixmap, pool: Option<&ThreadPool>) {
+        if !self.deferred {
+            self.ctx.render_to_pixmap(pixmap);
+            return;
+        }
+        let (width, height) = (pixmap.width(), pixmap.height());
+        let render_mode = self.ctx.render_settings().render_mode;
+        self.render(pixmap.data_as_u8_slice_mut(), width, height, render_mode, pool);
+    }
+
+    fn log(&mut self, command: RenderCommand) {
+        self.ops.push(SceneOp::Command(command));
+    }
+
+    /// Log the state of the context as commands
+    fn log_state(&mut self) {
+        let state = [
+            RenderCommand::SetPaint(self.ctx.paint().clone()),
+            RenderCommand::SetPaintTransform(*self.ctx.paint_transform()),
+            RenderCommand::SetTransform(*self.ctx.transform()),
+            RenderCommand::SetFillRule(*self.ctx.fill_rule()),
+            RenderCommand::SetStroke(self.ctx.stroke().clone()),
+        ];
+        self.ops.extend(state.map(SceneOp::Command));
+    }
+
+    pub(crate) fn set_paint(&mut self, paint: impl Into<PaintType>) {
+        let paint = paint.into();
+        self.log(RenderCommand::SetPaint(paint.clone()));
+        self.ctx.set_paint(paint);
+    }
+
+    pub(crate) fn set_paint_transform(&mut self, transform: Affine) {
+        self.log(RenderCommand::SetPaintTransform(transform));
+        self.ctx.set_paint_transform(transform);
+    }
+
+    pub(crate) fn reset_paint_transform(&mut self) {
+        self.log(RenderCommand::ResetPaintTransform);
+        self.ctx.reset_paint_transform();
+    }
+
+    pub(crate) fn set_transform(&mut self, transform: Affine) {
+        self.log(RenderCommand::SetTransform(transform));
+        self.ctx.set_transform(transform);
+    }
+
+    pub(crate) fn reset_transform(&mut self) {
+        self.set_transform(Affine::IDENTITY);
+    }
+
+    pub(crate) fn set_fill_rule(&mut self, fill_rule: Fill) {
+        self.log(RenderCommand::SetFillRule(fill_rule));
+        self.ctx.set_fill_rule(fill_rule);
+    }
+
+    pub(crate) fn set_stroke(&mut self, stroke: Stroke) {
+        self.log(RenderCommand::SetStroke(stroke.clone()));
+        self.ctx.set_stroke(stroke);
+    }
+
+    pub(crate) fn set_aliasing_threshold(&mut self, threshold: Option<u8>) {
+        self.aliasing_threshold = threshold;
+        self.ops.push(SceneOp::AliasingThreshold(threshold));
+        self.ctx.set_aliasing_threshold(threshold);
+    }
+
+    pub(crate) fn set_dash_tolerance(&mut self, tolerance: Option<f64>) {
+        self.dash_tolerance = tolerance;
+    }
+
+    pub(crate) fn fill_path(&mut self, path: &BezPath) {
+        self.drawn(|ctx| Some(draw_bounds(path.bounding_box(), *ctx.transform(), None)));
+        self.log(RenderCommand::FillPath(path.clone()));
+        if !self.deferred {
+            self.ctx.fill_path(path);
+        }
+    }
+
+    pub(crate) fn stroke_path(&mut self, path: &BezPath) {
+        if let Some(dashed) = self.dash(path) {
+            self.stroke_dashes(&dashed);
+            return;
+        }
+        self.drawn(|ctx| {
+            Some(draw_bounds(path.bounding_box(), *ctx.transform(), Some(ctx.stroke())))
+        });
+        self.log(RenderCommand::StrokePath(path.clone()));
+        if !self.deferred {
+            self.ctx.stroke_path(path);
+        }
+    }
+
+    pub(crate) fn fill_rect(&mut self, rect: &Rect) {
+        self.drawn(|ctx| Some(draw_bounds(*rect, *ctx.transform(), None)));
+        self.log(RenderCommand::FillRect(*rect));
+        if !self.deferred {
+            self.ctx.fill_rect(rect);
+        }
+    }
+
+    pub(crate) fn stroke_rect(&mut self, rect: &Rect) {
+        if let Some(dashed) = self.dash(&rect.to_path(0.1)) {
+            self.stroke_dashes(&dashed);
+            return;
+        }
+        self.drawn(|ctx| Some(draw_bounds(*rect, *ctx.transform(), Some(ctx.stroke()))));
+        self.log(RenderCommand::StrokeRect(*rect));
+        if !self.deferred {
+            self.ctx.stroke_rect(rect);
+        }
+    }
+
+    /// Dashes of `path` if dashed strokes are flattened by the FFI layer
+    fn dash(&self, path: &BezPath) -> Option<BezPath> {
+        let tolerance = self.dash_tolerance?;
+        let stroke = self.ctx.stroke();
+        (!stroke.dash_pattern.is_empty()).then(|| dash_path(path, stroke, tolerance))
+    }
+
+    /// Stroke already dashed geometry with the dash pattern lifted off the stroke
+    fn stroke_dashes(&mut self, dashes: &BezPath) {
+        let stroke = self.ctx.stroke().clone();
+        self.set_stroke(undashed(&stroke));
+        self.stroke_path(dashes);
+        self.set_stroke(stroke);
+    }
+
+    pub(crate) fn fill_blurred_rounded_rect(&mut self, rect: &Rect, radius: f32, std_dev: f32) {
+        // The blur fades out within three standard deviations
+        let blur = 3.0 * std_dev as f64;
+        self.drawn(|ctx| Some(draw_bounds(rect.inflate(blur, blur), *ctx.transform(), None)));
+        self.ops.push(SceneOp::BlurredRoundedRect {
+            rect: *rect,
+            radius,
+            std_dev,
+        });
+        if !self.deferred {
+            self.ctx.fill_blurred_rounded_rect(rect, radius, std_dev);
+        }
+    }
+
+    pub(crate) fn push_layer(
+        &mut self,
+        clip_path: Option<&BezPath>,
+        blend_mode: Option<BlendMode>,
+        opacity: Option<f32>,
+        mask: Option<Mask>,
+    ) {
+        self.layers.push(None);
+        self.log(RenderCommand::PushLayer(PushLayerCommand {
+            clip_path: clip_path.cloned(),
+            blend_mode,
+            opacity,
+            mask: mask.clone(),
+        }));
+        self.has_masks |= mask.is_some();
+        if !self.deferred {
+            self.ctx.push_layer(clip_path, blend_mode, opacity, mask);
+        }
+    }
+
+    pub(crate) fn push_clip_layer(&mut self, path: &BezPath) {
+        self.push_layer(Some(path), None, None, None);
+    }
+
+    pub(crate) fn push_blend_layer(&mut self, blend_mode: BlendMode) {
+        self.push_layer(None, Some(blend_mode), None, None);
+    }
+
+    pub(crate) fn push_opacity_layer(&mut self, opacity: f32) {
+        self.push_layer(None, None, Some(opacity), None);
+    }
+
+    pub(crate) fn push_mask_layer(&mut self, mask: Mask) {
+        self.push_layer(None, None, None, Some(mask));
+    }
+
+    /// Push a layer with a user-space bounds hint
+    ///
+    /// The hint limits the area the layer is allocated and composited over,
+    /// on top of the clip path. It does not change what is drawn: if drawing
+    /// leaves the bounds, the layer is turned into a plain one when it is
+    /// popped. Blend modes that change the backdrop where the layer is
+    /// transparent ignore the hint.
+    pub(crate) fn push_layer_bounded(
+        &mut self,
+        clip_path: Option<&BezPath>,
+        blend_mode: Option<BlendMode>,
+        opacity: Option<f32>,
+        mask: Option<Mask>,
+        bounds: Rect,
+    ) {
+        let compose = blend_mode.map_or(Compose::SrcOver, |mode| mode.compose);
+        if !keeps_backdrop(compose) {
+            self.push_layer(clip_path, blend_mode, opacity, mask);
+            return;
+        }
+        // Whole pixels, so the bounds clip has no partially covered pixels
+        let bounds = self.ctx.transform().transform_rect_bbox(bounds).expand().inflate(1.0, 1.0);
+        let layer = PushLayerCommand {
+            clip_path: clip_path.cloned(),
+            blend_mode,
+            opacity,
+            mask,
+        };
+        self.push_device_bounded_layer(layer, bounds);
+    }
+
+    fn push_device_bounded_layer(&mut self, layer: PushLayerCommand, bounds: Rect) {
+        self.has_masks |= layer.mask.is_some();
+        if !self.deferred {
+            let transform = *self.ctx.transform();
+            self.ctx.push_bounded_layer(&layer, bounds, Affine::IDENTITY, transform);
+        }
+        self.layers.push(Some(BoundedLayer {
+            op: self.ops.len(),
+            bounds,
+            nested: layer.clip_path.is_some(),
+            overflowed: false,
+        }));
+        self.ops.push(SceneOp::BoundedLayer { layer, bounds });
+    }
+
+    pub(crate) fn pop_layer(&mut self) {
+        if let Some(layer) = self.layers.pop().flatten() {
+            if !layer.overflowed {
+                self.ops.push(SceneOp::PopBoundedLayer { nested: layer.nested });
+                if !self.deferred {
+                    self.ctx.pop_bounded_layer(layer.nested);
+                }
+                return;
+            }
+            self.drop_bounds(layer.op);
+        }
+        self.log(RenderCommand::PopLayer);
+        if !self.deferred {
+            self.ctx.pop_layer();
+        }
+    }
+
+    /// Note drawing covering the device-space bounds computed by `bounds`, or
+    /// an unknown area if it gives None
+    fn drawn(&mut self, bounds: impl FnOnce(&RenderContext) -> Option<Rect>) {
+        if self.layers.iter().flatten().all(|layer| layer.overflowed) {
+            return;
+        }
+        let bounds = bounds(&self.ctx).map(Rect::expand);
+        for layer in self.layers.iter_mut().flatten() {
+            let inside = bounds.is_some_and(|bounds| layer.bounds.union(bounds) == layer.bounds);
+            layer.overflowed |= !inside;
+        }
+    }
+
+    /// Turn the bounded layer pushed by the op at `index` into a plain one,
+    /// redrawing the context if it was drawn into already
+    fn drop_bounds(&mut self, index: usize) {
+        if let SceneOp::BoundedLayer { layer, .. } = &self.ops[index] {
+            self.ops[index] = SceneOp::Command(RenderCommand::PushLayer(layer.clone()));
+        }
+        if !self.deferred {
+            let settings = self.ctx.render_settings();
+            let settings = RenderSettings {
+                level: settings.level,
+                num_threads: settings.num_threads,
+                render_mode: settings.render_mode,
+            };
+            let mut ctx = RenderContext::new_with(self.ctx.width(), self.ctx.height(), settings);
+            replay_ops(&self.ops, &mut ctx, Affine::IDENTITY);
+            self.ctx = ctx;
+        }
+    }
+
+    /// Start a glyph run drawn with the current state
+    pub(crate) fn glyph_run(&mut self, font: &FontData) -> SceneGlyphRun<'_> {
+        SceneGlyphRun {
+            scene: self,
+            run: GlyphRun {
+                font: font.clone(),
+                font_size: None,
+                hint: None,
+                normalized_coords: Vec::new(),
+                glyph_transform: None,
+                glyphs: Vec::new(),
+                stroke: false,
+            },
+        }
+    }
+
+    /// Log the commands of a recording and run `execute` to draw it
+    ///
+    /// Recordings start from the default state and leave the context's state
+    /// alone, so the logged commands are wrapped in a reset and a restore.
+    pub(crate) fn execute_recording_with(
+        &mut self,
+        commands: &[RenderCommand],
+        execute: impl FnOnce(&mut RenderContext),
+    ) {
+        let defaults = [
+            RenderCommand::SetPaint(BLACK.into()),
+            RenderCommand::ResetPaintTransform,
+            RenderCommand::SetTransform(Affine::IDENTITY),
+            RenderCommand::SetFillRule(Fill::NonZero),
+            RenderCommand::SetStroke(Stroke::default()),
+        ];
+        self.drawn(|_| geometry_bounds(commands));
+        self.ops.extend(defaults.map(SceneOp::Command));
+        for command in commands {
+            if let RenderCommand::PushLayer(layer) = command {
+                self.has_masks |= layer.mask.is_some();
+            }
+            self.log(command.clone());
+        }
+        self.log_state();
+        if !self.deferred {
+            execute(&mut self.ctx);
+        }
+    }
+
+    /// Prepare and draw a one-off recording
+    pub(crate) fn execute_recording(&mut self, recording: &mut Recording) {
+        if !self.deferred {
+            self.ctx.prepare_recording(recording);
+        }
+        let recording = &*recording;
+        self.execute_recording_with(recording.commands(), |ctx| ctx.execute_recording(recording));
+    }
+
+    pub(crate) fn flush(&mut self) {
+        if !self.deferred {
+            self.ctx.flush();
+        }
+    }
+
+    /// Clear the drawing and the log, keeping the current state
+    pub(crate) fn reset(&mut self) {
+        self.ctx.reset();
+        self.ops.clear();
+        self.has_masks = false;
+        self.layers.clear();
+        self.log_state();
+        if self.aliasing_threshold.is_some() {
+            self.set_aliasing_threshold(self.aliasing_threshold);
+        }
+    }
+}
+
+impl Deref for Scene {
+    type Target = RenderContext;
+
+    fn deref(&self) -> &RenderContext {
+        &self.ctx
+    }
+}
+
+impl DrawTarget for Scene {
+    fn set_paint(&mut self, paint: PaintType) {
+        Scene::set_paint(self, paint)
+    }
+    fn set_paint_transform(&mut self, transform: Affine) {
+        Scene::set_paint_transform(self, transform)
+    }
+    fn reset_paint_transform(&mut self) {
+        Scene::reset_paint_transform(self)
+    }
+    fn set_transform(&mut self, transform: Affine) {
+        Scene::set_transform(self, transform)
+    }
+    fn set_fill_rule(&mut self, fill_rule: Fill) {
+        Scene::set_fill_rule(self, fill_rule)
+    }
+    fn set_stroke(&mut self, stroke: Stroke) {
+        Scene::set_stroke(self, stroke)
+    }
+    fn fill_path(&mut self, path: &BezPath) {
+        Scene::fill_path(self, path)
+    }
+    fn stroke_path(&mut self, path: &BezPath) {
+        Scene::stroke_path(self, path)
+    }
+    fn fill_rect(&mut self, rect: &Rect) {
+        Scene::fill_rect(self, rect)
+    }
+    fn stroke_rect(&mut self, rect: &Rect) {
+        Scene::stroke_rect(self, rect)
+    }
+    fn push_layer(
+        &mut self,
+        clip_path: Option<&BezPath>,
+        blend_mode: Option<BlendMode>,
+        opacity: Option<f32>,
+        mask: Option<Mask>,
+    ) {
+        Scene::push_layer(self, clip_path, blend_mode, opacity, mask)
+    }
+    fn pop_layer(&mut self) {
+        Scene::pop_layer(self)
+    }
+    fn draw_glyphs(&mut self, run: &GlyphRun) {
+        self.drawn(|_| None);
+        self.ops.push(SceneOp::Glyphs(Box::new(run.clone())));
+        if !self.deferred {
+            self.ctx.draw_glyphs(run);
+        }
+    }
+    fn push_bounded_layer(
+        &mut self,
+        layer: &PushLayerCommand,
+        bounds: Rect,
+        origin: Affine,
+        _transform: Affine,
+    ) {
+        Scene::push_device_bounded_layer(self, layer.clone(), origin.transform_rect_bbox(bounds))
+    }
+    fn pop_bounded_layer(&mut self, _nested: bool) {
+        Scene::pop_layer(self)
+    }
+    fn fill_blurred_rounded_rect(&mut self, rect: &Rect, radius: f32, std_dev: f32) {
+        Scene::fill_blurred_rounded_rect(self, rect, radius, std_dev)
+    }
+    fn set_aliasing_threshold(&mut self, threshold: Option<u8>) {
+        Scene::set_aliasing_threshold(self, threshold)
+    }
+}
+
+/// Glyph run builder of a `Scene`, with the same options as vello_cpu's
+pub(crate) struct SceneGlyphRun<'a> {
+    scene: &'a mut Scene,
+    run: GlyphRun,
+}
+
+impl SceneGlyphRun<'_> {
+    pub(crate) fn font_size(mut self, font_size: f32) -> Self {
+        self.run.font_size = Some(font_size);
+        self
+    }
+
+    pub(crate) fn hint(mut self, hint: bool) -> Self {
+        self.run.hint = Some(hint);
+        self
+    }
+
+    pub(crate) fn normalized_coords(mut self, coords: &[NormalizedCoord]) -> Self {
+        self.run.normalized_coords = coords.to_vec();
+        self
+    }
+
+    pub(crate) fn glyph_transform(mut self, transform: Affine) -> Self {
+        self.run.glyph_transform = Some(transform);
+        self
+    }
+
+    pub(crate) fn fill_glyphs(self, glyphs: impl Iterator<Item = Glyph>) {
+        self.draw(glyphs, false);
+    }
+
+    pub(crate) fn stroke_glyphs(self, glyphs: impl Iterator<Item = Glyph>) {
+        self.draw(glyphs, true);
+    }
+
+    fn draw(mut self, glyphs: impl Iterator<Item = Glyph>, stroke: bool) {
+        self.run.glyphs = glyphs.collect();
+        self.run.stroke = stroke;
+        self.scene.drawn(|_| None);
+        if !self.scene.deferred {
+            self.scene.ctx.draw_glyphs(&self.run);
+        }
+        self.scene.ops.push(SceneOp::Glyphs(Box::new(self.run)));
+    }
+}
+
+/// Replay logged calls into a target in its initial state, with every
+/// transform pre-multiplied by `origin`
+fn replay_ops(ops: &[SceneOp], target: &mut impl DrawTarget, origin: Affine) {
+    let mut replay = CommandReplay::new(target, origin);
+    for op in ops {
+        match op {
+            SceneOp::Command(command) => replay.apply(target, command),
+            SceneOp::BlurredRoundedRect {
+                rect,
+                radius,
+                std_dev,
+            } => target.fill_blurred_rounded_rect(rect, *radius, *std_dev),
+            SceneOp::AliasingThreshold(threshold) => target.set_aliasing_threshold(*threshold),
+            SceneOp::Glyphs(run) => target.draw_glyphs(run),
+            SceneOp::BoundedLayer { layer, bounds } => {
+                replay.push_bounded_layer(target, layer, *bounds)
+            }
+            SceneOp::PopBoundedLayer { nested } => target.pop_bounded_layer(*nested),
+        }
+    }
+}
+
+/// Whether compositing a transparent layer with `compose` leaves the backdrop
+/// untouched
+fn keeps_backdrop(compose: Compose) -> bool {
+    !matches!(
+        compose,
+        Compose::Clear
+            | Compose::Copy
+            | Compose::SrcIn
+            | Compose::DestIn
+            | Compose::SrcOut
+            | Compose::DestAtop
+    )
+}
@@ -0,0 +1,663 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Text shaping FFI bindings
+//!
+//! With the `shaping` feature, text is shaped with rustybuzz (a HarfBuzz port),
+//! which applies ligatures, kerning, mark positioning and complex script rules.
+//! Without it, the internal helpers fall back to a plain cmap lookup with
+//! nominal advances. Mixed-direction text is split into runs with the Unicode
+//! bidi algorithm in both cases.
+
+use std::os::raw::{c_char, c_int};
+
+use skrifa::instance::{LocationRef, Size};
+use skrifa::{FontRef, MetadataProvider};
+use vello_cpu::peniko::FontData;
+
+use crate::error::set_last_error;
+#[cfg(feature = "shaping")]
+use crate::handle::TypedHandle;
+#[cfg(feature = "shaping")]
+use crate::text::{FontHandle, VelloFontData};
+use crate::types::*;
+use crate::ffi_catch;
+
+/// Text direction for shaping
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloTextDirection {
+    /// Guess from the script of the text
+    Auto = 0,
+    LeftToRight = 1,
+    RightToLeft = 2,
+    TopToBottom = 3,
+    BottomToTop = 4,
+}
+
+/// OpenType feature setting
+///
+/// `tag` packs the four tag characters big-endian, e.g. `'liga'` is
+/// `('l' << 24) | ('i' << 16) | ('g' << 8) | 'a'`. A `value` of 0 disables the
+/// feature, 1 enables it and larger values select alternates.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VelloFontFeature {
+    pub tag: u32,
+    pub value: u32,
+}
+
+/// Shaped glyph with its position and source cluster
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct VelloShapedGlyph {
+    /// Glyph ID (font-specific, not Unicode)
+    pub id: u32,
+    /// X position in pixels relative to the run origin
+    pub x: f32,
+    /// Y position in pixels relative to the run origin (y down)
+    pub y: f32,
+    /// Horizontal advance in pixels
+    pub x_advance: f32,
+    /// Vertical advance in pixels (y down)
+    pub y_advance: f32,
+    /// Byte offset into the UTF-8 text of the cluster this glyph belongs to
+    pub cluster: u32,
+}
+
+/// Pack a 1-4 character tag, padding with spaces
+fn pack_tag(tag: &str) -> Option<u32> {
+    if tag.is_empty() || tag.len() > 4 || !tag.bytes().all(|b| (0x20..=0x7e).contains(&b)) {
+        return None;
+    }
+    let mut bytes = [b' '; 4];
+    bytes[..tag.len()].copy_from_slice(tag.as_bytes());
+    Some(u32::from_be_bytes(bytes))
+}
+
+/// Parse a feature setting in the HarfBuzz/CSS style: `tnum`, `+liga`, `-kern`,
+/// `ss01=2`, `"smcp" on`, `"liga" 0`
+fn parse_feature(s: &str) -> Option<VelloFontFeature> {
+    let s = s.trim();
+    let (s, mut value) = if let Some(rest) = s.strip_prefix('-') {
+        (rest, 0)
+    } else {
+        (s.strip_prefix('+').unwrap_or(s), 1)
+    };
+
+    let (tag, setting) = match s.find(|c: char| c == '=' || c.is_whitespace()) {
+        Some(split) => {
+            let setting = s[split..].trim_start_matches(|c: char| c == '=' || c.is_whitespace());
+            (&s[..split], Some(setting))
+        }
+        None => (s, None),
+    };
+    let tag = tag.trim_matches(|c| c == '"' || c == '\'');
+    if let Some(setting) = setting.map(str::trim).filter(|v| !v.is_empty()) {
+        value = match setting {
+            "on" => 1,
+            "off" => 0,
+            number => number.parse().ok()?,
+        };
+    }
+    Some(VelloFontFeature { tag: pack_tag(tag)?, value })
+}
+
+pub(crate) fn font_ref(font: &FontData) -> Option<FontRef<'_>> {
+    FontRef::from_index(font.data.as_ref(), font.index).ok()
+}
+
+/// Directional run of shaped text, in visual order
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VelloTextRun {
+    /// Index of the first glyph of the run in the shaped glyph array
+    pub glyph_start: u32,
+    pub glyph_count: u32,
+    /// Byte range of the run in the UTF-8 text
+    pub text_start: u32,
+    pub text_end: u32,
+    /// Resolved direction of the run
+    pub direction: VelloTextDirection,
+}
+
+/// Glyphs and directional runs of a shaped line, in visual order
+pub(crate) struct ShapedText {
+    pub(crate) glyphs: Vec<VelloShapedGlyph>,
+    pub(crate) runs: Vec<VelloTextRun>,
+}
+
+/// Shape `text` into positioned glyphs in pixels
+pub(crate) fn shape_text(
+    font: &FontData,
+    text: &str,
+    font_size: f32,
+    direction: VelloTextDirection,
+    language: Option<&str>,
+    features: &[VelloFontFeature],
+) -> Result<Vec<VelloShapedGlyph>, &'static str> {
+    shape_line(font, text, font_size, direction, language, features).map(|shaped| shaped.glyphs)
+}
+
+/// Shape a line of text, splitting it into directional runs with the Unicode
+/// bidi algorithm
+///
+/// `LeftToRight` and `RightToLeft` set the paragraph direction and `Auto`
+/// derives it from the first strong character. Vertical text is shaped as a
+/// single run.
+pub(crate) fn shape_line(
+    font: &FontData,
+    text: &str,
+    font_size: f32,
+    direction: VelloTextDirection,
+    language: Option<&str>,
+    features: &[VelloFontFeature],
+) -> Result<ShapedText, &'static str> {
+    use unicode_bidi::{BidiInfo, Level};
+
+    let base_level = match direction {
+        VelloTextDirection::Auto => None,
+        VelloTextDirection::LeftToRight => Some(Level::ltr()),
+        VelloTextDirection::RightToLeft => Some(Level::rtl()),
+        VelloTextDirection::TopToBottom | VelloTextDirection::BottomToTop => {
+            let glyphs = shape_run(font, text, font_size, direction, language, features)?;
+            let run = VelloTextRun {
+                glyph_start: 0,
+                glyph_count: glyphs.len() as u32,
+                text_start: 0,
+                text_end: text.len() as u32,
+                direction,
+            };
+            return Ok(ShapedText { glyphs, runs: vec![run] });
+        }
+    };
+
+    let info = BidiInfo::new(text, base_level);
+    let mut shaped = ShapedText { glyphs: Vec::new(), runs: Vec::new() };
+    let (mut pen_x, mut pen_y) = (0.0f32, 0.0f32);
+    for paragraph in &info.paragraphs {
+        let (levels, runs) = info.visual_runs(paragraph, paragraph.range.clone());
+        for range in runs {
+            let run_direction = if levels[range.start].is_rtl() {
+                VelloTextDirection::RightToLeft
+            } else {
+                VelloTextDirection::LeftToRight
+            };
+            let glyphs =
+                shape_run(font, &text[range.clone()], font_size, run_direction, language, features)?;
+            shaped.runs.push(VelloTextRun {
+                glyph_start: shaped.glyphs.len() as u32,
+                glyph_count: glyphs.len() as u32,
+                text_start: range.start as u32,
+                text_end: range.end as u32,
+                direction: run_direction,
+            });
+            for glyph in glyphs {
+                shaped.glyphs.push(VelloShapedGlyph {
+                    x: glyph.x + pen_x,
+                    y: glyph.y + pen_y,
+                    cluster: glyph.cluster + range.start as u32,
+                    ..glyph
+                });
+                pen_x += glyph.x_advance;
+                pen_y += glyph.y_advance;
+            }
+        }
+    }
+    Ok(shaped)
+}
+
+/// Shape a single-direction run; positions start at the origin
+fn shape_run(
+    font: &FontData,
+    text: &str,
+    font_size: f32,
+    direction: VelloTextDirection,
+    language: Option<&str>,
+    features: &[VelloFontFeature],
+) -> Result<Vec<VelloShapedGlyph>, &'static str> {
+    #[cfg(feature = "shaping")]
+    {
+        shape_with_rustybuzz(font, text, font_size, direction, language, features)
+    }
+    #[cfg(not(feature = "shaping"))]
+    {
+        let _ = (language, features);
+        shape_with_cmap(font, text, font_size, direction)
+    }
+}
+
+#[cfg(feature = "shaping")]
+fn shape_with_rustybuzz(
+    font: &FontData,
+    text: &str,
+    font_size: f32,
+    direction: VelloTextDirection,
+    language: Option<&str>,
+    features: &[VelloFontFeature],
+) -> Result<Vec<VelloShapedGlyph>, &'static str> {
+    use rustybuzz::ttf_parser::Tag;
+    use rustybuzz::{Direction, Feature, Language, UnicodeBuffer};
+    use std::str::FromStr;
+
+    let face =
+        rustybuzz::Face::from_slice(font.data.as_ref(), font.index).ok_or("Invalid font data")?;
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    match direction {
+        VelloTextDirection::Auto => {}
+        VelloTextDirection::LeftToRight => buffer.set_direction(Direction::LeftToRight),
+        VelloTextDirection::RightToLeft => buffer.set_direction(Direction::RightToLeft),
+        VelloTextDirection::TopToBottom => buffer.set_direction(Direction::TopToBottom),
+        VelloTextDirection::BottomToTop => buffer.set_direction(Direction::BottomToTop),
+    }
+    if let Some(language) = language {
+        buffer.set_language(Language::from_str(language).map_err(|_| "Invalid language tag")?);
+    }
+    buffer.guess_segment_properties();
+
+    let features: Vec<Feature> = features
+        .iter()
+        .map(|f| Feature::new(Tag::from_bytes(&f.tag.to_be_bytes()), f.value, ..))
+        .collect();
+    let output = rustybuzz::shape(&face, &features, buffer);
+
+    let scale = font_size / face.units_per_em() as f32;
+    let (mut pen_x, mut pen_y) = (0.0f32, 0.0f32);
+    let glyphs = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, pos)| {
+            // HarfBuzz positions are y up
+            let glyph = VelloShapedGlyph {
+                id: info.glyph_id,
+                x: pen_x + pos.x_offset as f32 * scale,
+                y: pen_y - pos.y_offset as f32 * scale,
+                x_advance: pos.x_advance as f32 * scale,
+                y_advance: -pos.y_advance as f32 * scale,
+                cluster: info.cluster,
+            };
+            pen_x += glyph.x_advance;
+            pen_y += glyph.y_advance;
+            glyph
+        })
+        .collect();
+    Ok(glyphs)
+}
+
+/// Nominal cmap mapping without shaping; right-to-left text is reversed
+#[cfg_attr(feature = "shaping", allow(dead_code))]
+fn shape_with_cmap(
+    font: &FontData,
+    text: &str,
+    font_size: f32,
+    direction: VelloTextDirection,
+) -> Result<Vec<VelloShapedGlyph>, &'static str> {
+    let font_ref = font_ref(font).ok_or("Invalid font data")?;
+    let charmap = font_ref.charmap();
+    let metrics = font_ref.glyph_metrics(Size::new(font_size), LocationRef::default());
+
+    let mut chars: Vec<(usize, char)> = text.char_indices().collect();
+    if direction == VelloTextDirection::RightToLeft {
+        chars.reverse();
+    }
+
+    let mut pen_x = 0.0f32;
+    let glyphs = chars
+        .into_iter()
+        .map(|(offset, ch)| {
+            let id = charmap.map(ch).unwrap_or_default();
+            let x_advance = metrics.advance_width(id).unwrap_or(0.0);
+            let glyph = VelloShapedGlyph {
+                id: id.to_u32(),
+                x: pen_x,
+                y: 0.0,
+                x_advance,
+                y_advance: 0.0,
+                cluster: offset as u32,
+            };
+            pen_x += x_advance;
+            glyph
+        })
+        .collect();
+    Ok(glyphs)
+}
+
+/// Shape UTF-8 text into positioned glyphs
+///
+/// `language` is an optional BCP 47 tag (null to guess). `out_count` receives
+/// the total number of glyphs; at most `max_glyphs` are written, so passing
+/// `max_glyphs = 0` queries the required capacity.
+#[cfg(feature = "shaping")]
+#[no_mangle]
+pub extern "C" fn vello_shape_text(
+    font: *const VelloFontData,
+    text: *const c_char,
+    font_size: f32,
+    direction: VelloTextDirection,
+    language: *const c_char,
+    features: *const VelloFontFeature,
+    feature_count: usize,
+    out_glyphs: *mut VelloShapedGlyph,
+    max_glyphs: usize,
+    out_count: *mut usize,
+) -> c_int {
+    if font.is_null()
+        || text.is_null()
+        || out_count.is_null()
+        || (max_glyphs > 0 && out_glyphs.is_null())
+        || (feature_count > 0 && features.is_null())
+    {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let font_data = &FontHandle::from_ptr(font).font;
+        let (text, language) = match unsafe { shaping_strings(text, language) } {
+            Ok(strings) => strings,
+            Err(code) => return code,
+        };
+        let features = if feature_count > 0 {
+            unsafe { std::slice::from_raw_parts(features, feature_count) }
+        } else {
+            &[]
+        };
+
+        let glyphs = match shape_text(font_data, text, font_size, direction, language, features) {
+            Ok(glyphs) => glyphs,
+            Err(e) => {
+                set_last_error(e);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        let written = glyphs.len().min(max_glyphs);
+        if written > 0 {
+            let out = unsafe { std::slice::from_raw_parts_mut(out_glyphs, written) };
+            out.copy_from_slice(&glyphs[..written]);
+        }
+        unsafe { *out_count = glyphs.len() };
+        VELLO_OK
+    })
+}
+
+/// Pack an OpenType tag string (1-4 printable ASCII characters) into a u32
+/// Returns 0 for invalid tags
+#[no_mangle]
+pub extern "C" fn vello_font_feature_tag(tag: *const c_char) -> u32 {
+    if tag.is_null() {
+        return 0;
+    }
+    let tag = unsafe { std::ffi::CStr::from_ptr(tag) };
+    tag.to_str().ok().and_then(pack_tag).unwrap_or(0)
+}
+
+/// Parse a feature setting such as `tnum`, `-liga`, `+smcp` or `ss01=2`
+#[no_mangle]
+pub extern "C" fn vello_font_feature_parse(
+    setting: *const c_char,
+    out_feature: *mut VelloFontFeature,
+) -> c_int {
+    if setting.is_null() || out_feature.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let setting = unsafe { std::ffi::CStr::from_ptr(setting) };
+        match setting.to_str().ok().and_then(parse_feature) {
+            Some(feature) => {
+                unsafe { *out_feature = feature };
+                VELLO_OK
+            }
+            None => {
+                set_last_error("Invalid font feature setting");
+                VELLO_ERROR_INVALID_PARAMETER
+            }
+        }
+    })
+}
+
+/// Get the directional runs of a line of UTF-8 text, in visual order
+///
+/// Takes the same arguments as `vello_shape_text`, and the glyph ranges of the
+/// runs index into its output. `out_count` receives the total number of runs;
+/// at most `max_runs` are written.
+#[cfg(feature = "shaping")]
+#[no_mangle]
+pub extern "C" fn vello_shape_text_runs(
+    font: *const VelloFontData,
+    text: *const c_char,
+    font_size: f32,
+    direction: VelloTextDirection,
+    language: *const c_char,
+    features: *const VelloFontFeature,
+    feature_count: usize,
+    out_runs: *mut VelloTextRun,
+    max_runs: usize,
+    out_count: *mut usize,
+) -> c_int {
+    if font.is_null()
+        || text.is_null()
+        || out_count.is_null()
+        || (max_runs > 0 && out_runs.is_null())
+        || (feature_count > 0 && features.is_null())
+    {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let font_data = &FontHandle::from_ptr(font).font;
+        let (text, language) = match unsafe { shaping_strings(text, language) } {
+            Ok(strings) => strings,
+            Err(code) => return code,
+        };
+        let features = if feature_count > 0 {
+            unsafe { std::slice::from_raw_parts(features, feature_count) }
+        } else {
+            &[]
+        };
+
+        let runs = match shape_line(font_data, text, font_size, direction, language, features) {
+            Ok(shaped) => shaped.runs,
+            Err(e) => {
+                set_last_error(e);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        let written = runs.len().min(max_runs);
+        if written > 0 {
+            let out = unsafe { std::slice::from_raw_parts_mut(out_runs, written) };
+            out.copy_from_slice(&runs[..written]);
+        }
+        unsafe { *out_count = runs.len() };
+        VELLO_OK
+    })
+}
+
+/// Decode the text and optional language arguments of the shaping functions
+#[cfg(feature = "shaping")]
+unsafe fn shaping_strings<'a>(
+    text: *const c_char,
+    language: *const c_char,
+) -> Result<(&'a str, Option<&'a str>), c_int> {
+    let Ok(text) = std::ffi::CStr::from_ptr(text).to_str() else {
+        set_last_error("Invalid UTF-8");
+        return Err(VELLO_ERROR_INVALID_PARAMETER);
+    };
+    if language.is_null() {
+        return Ok((text, None));
+    }
+    match std::ffi::CStr::from_ptr(language).to_str() {
+        Ok(language) => Ok((text, Some(language))),
+        Err(_) => {
+            set_last_error("Invalid UTF-8");
+            Err(VELLO_ERROR_INVALID_PARAMETER)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "shaping")]
+    use crate::test_util::INTER_REGULAR;
+    use crate::test_util::inter;
+    #[cfg(feature = "shaping")]
+    use crate::text::{vello_font_data_free, vello_font_data_new};
+
+    use VelloTextDirection::{LeftToRight as Ltr, RightToLeft as Rtl};
+
+    fn feature(tag: &str, value: u32) -> Option<VelloFontFeature> {
+        Some(VelloFontFeature { tag: pack_tag(tag)?, value })
+    }
+
+    /// Text ranges and directions of the runs of a shaped line
+    fn runs(shaped: &ShapedText) -> Vec<(u32, u32, VelloTextDirection)> {
+        shaped.runs.iter().map(|run| (run.text_start, run.text_end, run.direction)).collect()
+    }
+
+    /// Check that the runs partition the glyphs and that the pen only moves forward
+    fn check_layout(shaped: &ShapedText) {
+        let mut next = 0;
+        for run in &shaped.runs {
+            assert_eq!(run.glyph_start, next);
+            next += run.glyph_count;
+        }
+        assert_eq!(next as usize, shaped.glyphs.len());
+        for pair in shaped.glyphs.windows(2) {
+            assert!((pair[1].x - pair[0].x - pair[0].x_advance).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn feature_settings_are_parsed() {
+        assert_eq!(pack_tag("ab"), Some(u32::from_be_bytes(*b"ab  ")));
+        assert_eq!(pack_tag("kerns"), None);
+        assert_eq!(parse_feature("tnum"), feature("tnum", 1));
+        assert_eq!(parse_feature("-liga"), feature("liga", 0));
+        assert_eq!(parse_feature(" +smcp "), feature("smcp", 1));
+        assert_eq!(parse_feature("ss01=2"), feature("ss01", 2));
+        assert_eq!(parse_feature("\"kern\" off"), feature("kern", 0));
+        assert_eq!(parse_feature("'dlig' on"), feature("dlig", 1));
+        assert_eq!(parse_feature("salt=x"), None);
+        assert_eq!(parse_feature(""), None);
+
+        assert_eq!(vello_font_feature_tag(c"liga".as_ptr()), u32::from_be_bytes(*b"liga"));
+        assert_eq!(vello_font_feature_tag(std::ptr::null()), 0);
+        let mut out = VelloFontFeature { tag: 0, value: 0 };
+        assert_eq!(vello_font_feature_parse(c"cv01=3".as_ptr(), &mut out), VELLO_OK);
+        assert_eq!(Some(out), feature("cv01", 3));
+        let result = vello_font_feature_parse(c"cv01=".as_ptr(), std::ptr::null_mut());
+        assert_eq!(result, VELLO_ERROR_NULL_POINTER);
+        let result = vello_font_feature_parse(c"toolong".as_ptr(), &mut out);
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn mixed_direction_text_is_split_into_visual_runs() {
+        let font = inter();
+        // "abc " and " def" around three two-byte Hebrew letters
+        let text = "abc \u{5d0}\u{5d1}\u{5d2} def";
+        let shape = |direction| shape_line(&font, text, 16.0, direction, None, &[]).unwrap();
+
+        let auto = shape(VelloTextDirection::Auto);
+        check_layout(&auto);
+        assert_eq!(runs(&auto), [(0, 4, Ltr), (4, 10, Rtl), (10, 14, Ltr)]);
+        // Right-to-left runs are laid out from their last character
+        let hebrew = &auto.runs[1];
+        let start = hebrew.glyph_start as usize;
+        let clusters: Vec<u32> = auto.glyphs[start..start + hebrew.glyph_count as usize]
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect();
+        assert_eq!(clusters, [8, 6, 4]);
+
+        // In a right-to-left paragraph the spaces join the Hebrew run
+        let rtl = shape(Rtl);
+        check_layout(&rtl);
+        assert_eq!(runs(&rtl), [(11, 14, Ltr), (3, 11, Rtl), (0, 3, Ltr)]);
+        assert_eq!(runs(&shape(Ltr)), runs(&auto));
+    }
+
+    #[test]
+    fn vertical_text_is_one_run() {
+        let font = inter();
+        let direction = VelloTextDirection::TopToBottom;
+        let shaped = shape_line(&font, "ab\u{5d0}", 16.0, direction, None, &[]).unwrap();
+        assert_eq!(runs(&shaped), [(0, 4, direction)]);
+        assert_eq!(shaped.glyphs.len(), 3);
+    }
+
+    #[test]
+    fn invalid_fonts_are_reported() {
+        let font = FontData::new(vello_cpu::peniko::Blob::from(vec![0u8; 16]), 0);
+        let result = shape_text(&font, "a", 16.0, Ltr, None, &[]);
+        assert_eq!(result.err(), Some("Invalid font data"));
+    }
+
+    #[cfg(feature = "shaping")]
+    #[test]
+    fn features_change_the_shaping() {
+        let font = inter();
+        let kerned = shape_text(&font, "AV", 16.0, Ltr, None, &[]).unwrap();
+        let kern_off = [VelloFontFeature { tag: pack_tag("kern").unwrap(), value: 0 }];
+        let unkerned = shape_text(&font, "AV", 16.0, Ltr, None, &kern_off).unwrap();
+        assert_eq!(kerned.len(), 2);
+        assert!(kerned[0].x_advance < unkerned[0].x_advance);
+        assert_eq!(kerned[1].x, kerned[0].x_advance);
+
+        // Explicit directions override the direction guessed from the script
+        let reversed = shape_text(&font, "ab", 16.0, Rtl, None, &[]).unwrap();
+        let clusters: Vec<u32> = reversed.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, [1, 0]);
+        let result = shape_text(&font, "ab", 16.0, Ltr, Some(""), &[]);
+        assert_eq!(result.err(), Some("Invalid language tag"));
+    }
+
+    #[cfg(feature = "shaping")]
+    #[test]
+    fn shaped_glyphs_are_counted_before_they_are_copied() {
+        let data = INTER_REGULAR;
+        let font = vello_font_data_new(data.as_ptr(), data.len(), 0);
+        let shape = |glyphs: &mut [VelloShapedGlyph], count: &mut usize| {
+            let (text, null) = (c"Hello".as_ptr(), std::ptr::null());
+            let (out, max) = (glyphs.as_mut_ptr(), glyphs.len());
+            vello_shape_text(font, text, 16.0, Ltr, null, std::ptr::null(), 0, out, max, count)
+        };
+
+        let mut count = 0;
+        assert_eq!(shape(&mut [], &mut count), VELLO_OK);
+        assert_eq!(count, 5);
+        let mut glyphs = [VelloShapedGlyph::default(); 3];
+        assert_eq!(shape(&mut glyphs, &mut count), VELLO_OK);
+        assert_eq!(count, 5);
+        assert_eq!(glyphs.map(|glyph| glyph.cluster), [0, 1, 2]);
+
+        vello_font_data_free(font);
+    }
+
+    #[cfg(not(feature = "shaping"))]
+    #[test]
+    fn features_are_ignored_without_a_shaper() {
+        let font = inter();
+        let kern_off = [VelloFontFeature { tag: pack_tag("kern").unwrap(), value: 0 }];
+        let plain = shape_text(&font, "AV", 16.0, Ltr, None, &[]).unwrap();
+        assert_eq!(shape_text(&font, "AV", 16.0, Ltr, None, &kern_off).unwrap(), plain);
+
+        // Advances are the nominal ones from the font
+        let font_ref = font_ref(&font).unwrap();
+        let metrics = font_ref.glyph_metrics(Size::new(16.0), LocationRef::default());
+        let id = font_ref.charmap().map('A').unwrap();
+        assert_eq!(plain[0].id, id.to_u32());
+        assert_eq!(Some(plain[0].x_advance), metrics.advance_width(id));
+        let reversed = shape_text(&font, "ab", 16.0, Rtl, None, &[]).unwrap();
+        let clusters: Vec<u32> = reversed.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, [1, 0]);
+    }
+}
@@ -0,0 +1,1077 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Skia C API compatibility layer
+//!
+//! A subset of the Skia C API (`sk_paint_*`, `sk_path_*` and `sk_canvas_*`)
+//! implemented over a render context, so applications drawing through Skia's C
+//! bindings can switch renderers by creating their canvas with
+//! `vello_skia_canvas_new` instead of from a Skia surface. Names, enums and
+//! struct layouts follow the SkiaSharp flavor of the C API.
+//!
+//! Like their Skia counterparts these functions return nothing; failures such
+//! as invalid handles are reported through `vello_get_last_error`. Paints are
+//! solid colors, clips always intersect, perspective terms of `sk_matrix_t` are
+//! ignored, and a canvas overwrites the paint, transform, fill rule and stroke
+//! of its context as it draws.
+
+use std::os::raw::c_int;
+
+use vello_cpu::kurbo::{Affine, BezPath, Cap, Circle, Ellipse, Join, Point, Rect, Shape, Stroke};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::{BlendMode, Compose, Fill, Mix};
+
+use crate::context::RenderContextHandle;
+use crate::error::set_last_error;
+use crate::ffi_catch;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::types::*;
+
+/// Unpremultiplied color packed as `0xAARRGGBB`
+pub type SkColor = u32;
+
+#[repr(C)]
+pub struct SkPaint {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct SkPath {
+    _private: [u8; 0],
+}
+
+/// Rectangle given by its edges
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SkRect {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+/// 3x3 matrix in row-major order
+#[repr(C)]
+#[allow(non_snake_case)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkMatrix {
+    pub scaleX: f32,
+    pub skewX: f32,
+    pub transX: f32,
+    pub skewY: f32,
+    pub scaleY: f32,
+    pub transY: f32,
+    pub persp0: f32,
+    pub persp1: f32,
+    pub persp2: f32,
+}
+
+// Skia's C enums are int-sized, so these are `repr(C)` rather than `repr(u8)`
+
+/// Whether a paint fills, strokes or does both
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkPaintStyle {
+    FILL_SK_PAINT_STYLE = 0,
+    STROKE_SK_PAINT_STYLE = 1,
+    STROKE_AND_FILL_SK_PAINT_STYLE = 2,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkStrokeCap {
+    BUTT_SK_STROKE_CAP = 0,
+    ROUND_SK_STROKE_CAP = 1,
+    SQUARE_SK_STROKE_CAP = 2,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkStrokeJoin {
+    MITER_SK_STROKE_JOIN = 0,
+    ROUND_SK_STROKE_JOIN = 1,
+    BEVEL_SK_STROKE_JOIN = 2,
+}
+
+/// Winding direction of shapes added to a path
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkPathDirection {
+    CW_SK_PATH_DIRECTION = 0,
+    CCW_SK_PATH_DIRECTION = 1,
+}
+
+/// Fill rule of a path; inverse fills are not supported
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkPathFillType {
+    WINDING_SK_PATH_FILLTYPE = 0,
+    EVENODD_SK_PATH_FILLTYPE = 1,
+}
+
+/// Paint settings, with Skia's defaults
+#[derive(Clone)]
+struct PaintState {
+    antialias: bool,
+    color: SkColor,
+    style: SkPaintStyle,
+    stroke_width: f32,
+    stroke_miter: f32,
+    stroke_cap: SkStrokeCap,
+    stroke_join: SkStrokeJoin,
+}
+
+impl Default for PaintState {
+    fn default() -> Self {
+        Self {
+            antialias: false,
+            color: 0xFF00_0000,
+            style: SkPaintStyle::FILL_SK_PAINT_STYLE,
+            stroke_width: 0.0,
+            stroke_miter: 4.0,
+            stroke_cap: SkStrokeCap::BUTT_SK_STROKE_CAP,
+            stroke_join: SkStrokeJoin::MITER_SK_STROKE_JOIN,
+        }
+    }
+}
+
+impl PaintState {
+    fn stroke(&self, width: f32) -> Stroke {
+        Stroke::new(width as f64)
+            .with_caps(match self.stroke_cap {
+                SkStrokeCap::BUTT_SK_STROKE_CAP => Cap::Butt,
+                SkStrokeCap::ROUND_SK_STROKE_CAP => Cap::Round,
+                SkStrokeCap::SQUARE_SK_STROKE_CAP => Cap::Square,
+            })
+            .with_join(match self.stroke_join {
+                SkStrokeJoin::MITER_SK_STROKE_JOIN => Join::Miter,
+                SkStrokeJoin::ROUND_SK_STROKE_JOIN => Join::Round,
+                SkStrokeJoin::BEVEL_SK_STROKE_JOIN => Join::Bevel,
+            })
+            .with_miter_limit(self.stroke_miter as f64)
+    }
+}
+
+/// Object behind an `sk_paint_t` pointer
+#[repr(C)]
+pub(crate) struct SkPaintHandle {
+    header: HandleHeader,
+    state: PaintState,
+}
+
+impl TypedHandle for SkPaintHandle {
+    const TAG: u32 = handle::tag(b"SKPT");
+    const NAME: &'static str = "paint";
+}
+
+/// Object behind an `sk_path_t` pointer
+#[repr(C)]
+pub(crate) struct SkPathHandle {
+    header: HandleHeader,
+    path: BezPath,
+    fill_type: SkPathFillType,
+}
+
+impl TypedHandle for SkPathHandle {
+    const TAG: u32 = handle::tag(b"SKPH");
+    const NAME: &'static str = "path";
+}
+
+impl SkPathHandle {
+    fn fill_rule(&self) -> Fill {
+        match self.fill_type {
+            SkPathFillType::WINDING_SK_PATH_FILLTYPE => Fill::NonZero,
+            SkPathFillType::EVENODD_SK_PATH_FILLTYPE => Fill::EvenOdd,
+        }
+    }
+}
+
+/// Object behind an `sk_canvas_t` pointer
+#[repr(C)]
+pub(crate) struct SkCanvasHandle {
+    header: HandleHeader,
+    ctx: *mut VelloRenderContext,
+    matrix: Affine,
+    /// Clip layers this canvas has open on the context
+    clips: usize,
+    /// Matrix and clip count at each `sk_canvas_save`
+    saves: Vec<(Affine, usize)>,
+}
+
+impl TypedHandle for SkCanvasHandle {
+    const TAG: u32 = handle::tag(b"SKCV");
+    const NAME: &'static str = "canvas";
+}
+
+impl SkCanvasHandle {
+    fn concat(&mut self, transform: Affine) {
+        self.matrix *= transform;
+    }
+
+    fn clip(&mut self, ctx: &mut RenderContextHandle, path: &BezPath, fill_rule: Fill) {
+        ctx.set_transform(self.matrix);
+        ctx.set_fill_rule(fill_rule);
+        ctx.push_clip_layer(path);
+        self.clips += 1;
+    }
+
+    fn restore(&mut self, ctx: &mut RenderContextHandle) {
+        if let Some((matrix, clips)) = self.saves.pop() {
+            self.matrix = matrix;
+            self.pop_clips(ctx, clips);
+        }
+    }
+
+    fn pop_clips(&mut self, ctx: &mut RenderContextHandle, count: usize) {
+        while self.clips > count {
+            ctx.pop_layer();
+            self.clips -= 1;
+        }
+    }
+}
+
+fn get<'a, T: TypedHandle, P>(ptr: *const P) -> Option<&'a T> {
+    T::check(ptr).then(|| unsafe { &*(ptr as *const T) })
+}
+
+fn get_mut<'a, T: TypedHandle, P>(ptr: *mut P) -> Option<&'a mut T> {
+    T::check(ptr).then(|| unsafe { &mut *(ptr as *mut T) })
+}
+
+/// Run `f` on a canvas and its context, reporting failures through the last error
+fn with_canvas(
+    canvas: *mut SkCanvas,
+    f: impl FnOnce(&mut SkCanvasHandle, &mut RenderContextHandle),
+) {
+    if canvas.is_null() {
+        set_last_error("Null canvas pointer");
+        return;
+    }
+    let _: c_int = ffi_catch!({
+        let canvas = SkCanvasHandle::from_ptr_mut(canvas);
+        let ctx = RenderContextHandle::from_ptr_mut(canvas.ctx);
+        f(canvas, ctx);
+        VELLO_OK
+    });
+}
+
+/// Run `f` on a canvas, a paint and the canvas context
+fn with_paint(
+    canvas: *mut SkCanvas,
+    paint: *const SkPaint,
+    f: impl FnOnce(&mut SkCanvasHandle, &mut RenderContextHandle, &PaintState),
+) {
+    if paint.is_null() {
+        set_last_error("Null paint pointer");
+        return;
+    }
+    with_canvas(canvas, |canvas, ctx| {
+        let paint = SkPaintHandle::from_ptr(paint);
+        f(canvas, ctx, &paint.state);
+    });
+}
+
+fn to_color(color: SkColor) -> AlphaColor<Srgb> {
+    let [a, r, g, b] = color.to_be_bytes();
+    AlphaColor::from_rgba8(r, g, b, a)
+}
+
+fn to_rect(rect: &SkRect) -> Rect {
+    Rect::new(
+        rect.left as f64,
+        rect.top as f64,
+        rect.right as f64,
+        rect.bottom as f64,
+    )
+}
+
+fn to_affine(m: &SkMatrix) -> Affine {
+    Affine::new([
+        m.scaleX as f64,
+        m.skewY as f64,
+        m.skewX as f64,
+        m.scaleY as f64,
+        m.transX as f64,
+        m.transY as f64,
+    ])
+}
+
+/// Canvas bounds in device space
+fn device_rect(ctx: &RenderContextHandle) -> Rect {
+    Rect::new(0.0, 0.0, ctx.width() as f64, ctx.height() as f64)
+}
+
+/// Fill and/or stroke `path`, given in canvas coordinates, with a paint
+fn draw(
+    ctx: &mut RenderContextHandle,
+    matrix: Affine,
+    path: &BezPath,
+    fill_rule: Fill,
+    paint: &PaintState,
+) {
+    let aa_mode = if paint.antialias {
+        VelloAaMode::Analytic
+    } else {
+        VelloAaMode::Aliased
+    };
+    ctx.set_paint(to_color(paint.color));
+    ctx.set_paint_transform(Affine::IDENTITY);
+
+    if paint.style != SkPaintStyle::STROKE_SK_PAINT_STYLE {
+        ctx.set_transform(matrix);
+        ctx.set_fill_rule(fill_rule);
+        ctx.record_fill(path, aa_mode);
+        ctx.with_aa_mode(aa_mode, |ctx| ctx.fill_path(path));
+    }
+    if paint.style != SkPaintStyle::FILL_SK_PAINT_STYLE {
+        let stroke = |ctx: &mut RenderContextHandle, path: &BezPath| {
+            ctx.record_stroke(path, aa_mode);
+            ctx.with_aa_mode(aa_mode, |ctx| ctx.stroke_path(path));
+        };
+        if paint.stroke_width == 0.0 {
+            // Width zero is a hairline, one pixel wide whatever the matrix
+            ctx.set_stroke(paint.stroke(1.0));
+            ctx.set_transform(Affine::IDENTITY);
+            stroke(ctx, &(matrix * path.clone()));
+        } else {
+            ctx.set_stroke(paint.stroke(paint.stroke_width));
+            ctx.set_transform(matrix);
+            stroke(ctx, path);
+        }
+    }
+}
+
+/// Append a clockwise `shape` to `path` in the requested direction
+fn add_shape(path: &mut BezPath, shape: BezPath, direction: SkPathDirection) {
+    let shape = match direction {
+        SkPathDirection::CW_SK_PATH_DIRECTION => shape,
+        SkPathDirection::CCW_SK_PATH_DIRECTION => shape.reverse_subpaths(),
+    };
+    path.extend(shape.elements().iter().copied());
+}
+
+// Paints
+
+/// Create a paint: opaque black fill, stroke width 0 (hairline), not anti-aliased
+#[no_mangle]
+pub extern "C" fn sk_paint_new() -> *mut SkPaint {
+    let paint = SkPaintHandle {
+        header: HandleHeader::new::<SkPaintHandle>(),
+        state: PaintState::default(),
+    };
+    handle::into_raw(paint) as *mut SkPaint
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_delete(paint: *mut SkPaint) {
+    if !paint.is_null() && SkPaintHandle::check(paint) {
+        handle::free::<SkPaintHandle, _>(paint);
+    }
+}
+
+/// Restore the default settings
+#[no_mangle]
+pub extern "C" fn sk_paint_reset(paint: *mut SkPaint) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        paint.state = PaintState::default();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_is_antialias(paint: *const SkPaint) -> bool {
+    get::<SkPaintHandle, _>(paint).is_some_and(|paint| paint.state.antialias)
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_set_antialias(paint: *mut SkPaint, antialias: bool) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        paint.state.antialias = antialias;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_get_color(paint: *const SkPaint) -> SkColor {
+    get::<SkPaintHandle, _>(paint).map_or(0, |paint| paint.state.color)
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_set_color(paint: *mut SkPaint, color: SkColor) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        paint.state.color = color;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_get_style(paint: *const SkPaint) -> SkPaintStyle {
+    get::<SkPaintHandle, _>(paint)
+        .map_or(SkPaintStyle::FILL_SK_PAINT_STYLE, |paint| paint.state.style)
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_set_style(paint: *mut SkPaint, style: SkPaintStyle) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        paint.state.style = style;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_get_stroke_width(paint: *const SkPaint) -> f32 {
+    get::<SkPaintHandle, _>(paint).map_or(0.0, |paint| paint.state.stroke_width)
+}
+
+/// Set the stroke width; negative and non-finite widths are ignored, as in Skia
+#[no_mangle]
+pub extern "C" fn sk_paint_set_stroke_width(paint: *mut SkPaint, width: f32) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        if width.is_finite() && width >= 0.0 {
+            paint.state.stroke_width = width;
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_get_stroke_miter(paint: *const SkPaint) -> f32 {
+    get::<SkPaintHandle, _>(paint).map_or(0.0, |paint| paint.state.stroke_miter)
+}
+
+/// Set the miter limit; negative and non-finite limits are ignored, as in Skia
+#[no_mangle]
+pub extern "C" fn sk_paint_set_stroke_miter(paint: *mut SkPaint, miter: f32) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        if miter.is_finite() && miter >= 0.0 {
+            paint.state.stroke_miter = miter;
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_get_stroke_cap(paint: *const SkPaint) -> SkStrokeCap {
+    get::<SkPaintHandle, _>(paint).map_or(SkStrokeCap::BUTT_SK_STROKE_CAP, |paint| {
+        paint.state.stroke_cap
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_set_stroke_cap(paint: *mut SkPaint, cap: SkStrokeCap) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        paint.state.stroke_cap = cap;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_get_stroke_join(paint: *const SkPaint) -> SkStrokeJoin {
+    get::<SkPaintHandle, _>(paint).map_or(SkStrokeJoin::MITER_SK_STROKE_JOIN, |paint| {
+        paint.state.stroke_join
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn sk_paint_set_stroke_join(paint: *mut SkPaint, join: SkStrokeJoin) {
+    if let Some(paint) = get_mut::<SkPaintHandle, _>(paint) {
+        paint.state.stroke_join = join;
+    }
+}
+
+// Paths
+
+/// Create an empty path with the winding fill type
+#[no_mangle]
+pub extern "C" fn sk_path_new() -> *mut SkPath {
+    let path = SkPathHandle {
+        header: HandleHeader::new::<SkPathHandle>(),
+        path: BezPath::new(),
+        fill_type: SkPathFillType::WINDING_SK_PATH_FILLTYPE,
+    };
+    handle::into_raw(path) as *mut SkPath
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_delete(path: *mut SkPath) {
+    if !path.is_null() && SkPathHandle::check(path) {
+        handle::free::<SkPathHandle, _>(path);
+    }
+}
+
+/// Remove all segments and restore the winding fill type
+#[no_mangle]
+pub extern "C" fn sk_path_reset(path: *mut SkPath) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        path.path.truncate(0);
+        path.fill_type = SkPathFillType::WINDING_SK_PATH_FILLTYPE;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_move_to(path: *mut SkPath, x: f32, y: f32) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        path.path.move_to((x as f64, y as f64));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_line_to(path: *mut SkPath, x: f32, y: f32) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        path.path.line_to((x as f64, y as f64));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_quad_to(path: *mut SkPath, x0: f32, y0: f32, x1: f32, y1: f32) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        path.path
+            .quad_to((x0 as f64, y0 as f64), (x1 as f64, y1 as f64));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_cubic_to(
+    path: *mut SkPath,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        path.path.curve_to(
+            (x0 as f64, y0 as f64),
+            (x1 as f64, y1 as f64),
+            (x2 as f64, y2 as f64),
+        );
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_close(path: *mut SkPath) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        path.path.close_path();
+    }
+}
+
+/// Add a closed rectangle contour starting at its top-left corner
+#[no_mangle]
+pub extern "C" fn sk_path_add_rect(
+    path: *mut SkPath,
+    rect: *const SkRect,
+    direction: SkPathDirection,
+) {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return;
+    }
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        let rect = to_rect(unsafe { &*rect });
+        add_shape(&mut path.path, rect.to_path(0.1), direction);
+    }
+}
+
+/// Add a closed ellipse contour inscribed in `rect`
+#[no_mangle]
+pub extern "C" fn sk_path_add_oval(
+    path: *mut SkPath,
+    rect: *const SkRect,
+    direction: SkPathDirection,
+) {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return;
+    }
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        let oval = Ellipse::from_rect(to_rect(unsafe { &*rect }));
+        add_shape(&mut path.path, oval.to_path(0.1), direction);
+    }
+}
+
+/// Add a closed circle contour
+#[no_mangle]
+pub extern "C" fn sk_path_add_circle(
+    path: *mut SkPath,
+    x: f32,
+    y: f32,
+    radius: f32,
+    direction: SkPathDirection,
+) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        let circle = Circle::new((x as f64, y as f64), radius as f64);
+        add_shape(&mut path.path, circle.to_path(0.1), direction);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_get_filltype(path: *const SkPath) -> SkPathFillType {
+    get::<SkPathHandle, _>(path).map_or(SkPathFillType::WINDING_SK_PATH_FILLTYPE, |path| {
+        path.fill_type
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn sk_path_set_filltype(path: *mut SkPath, fill_type: SkPathFillType) {
+    if let Some(path) = get_mut::<SkPathHandle, _>(path) {
+        path.fill_type = fill_type;
+    }
+}
+
+/// Get the bounds of a path; empty paths have all-zero bounds
+#[no_mangle]
+pub extern "C" fn sk_path_get_bounds(path: *const SkPath, out_rect: *mut SkRect) {
+    if out_rect.is_null() {
+        set_last_error("Null rect pointer");
+        return;
+    }
+    if let Some(path) = get::<SkPathHandle, _>(path) {
+        let bounds = if path.path.elements().is_empty() {
+            Rect::ZERO
+        } else {
+            path.path.bounding_box()
+        };
+        unsafe {
+            *out_rect = SkRect {
+                left: bounds.x0 as f32,
+                top: bounds.y0 as f32,
+                right: bounds.x1 as f32,
+                bottom: bounds.y1 as f32,
+            };
+        }
+    }
+}
+
+// Canvases
+
+/// Create a Skia-style canvas drawing into a render context
+///
+/// The context must outlive the canvas. The canvas starts with the identity
+/// matrix and no clip, regardless of the context's transform.
+#[no_mangle]
+pub extern "C" fn vello_skia_canvas_new(ctx: *mut VelloRenderContext) -> *mut SkCanvas {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return std::ptr::null_mut();
+    }
+    if !RenderContextHandle::check(ctx) {
+        return std::ptr::null_mut();
+    }
+
+    let canvas = SkCanvasHandle {
+        header: HandleHeader::new::<SkCanvasHandle>(),
+        ctx,
+        matrix: Affine::IDENTITY,
+        clips: 0,
+        saves: Vec::new(),
+    };
+    handle::into_raw(canvas) as *mut SkCanvas
+}
+
+/// Free a canvas, closing the clip layers it left open on its context
+#[no_mangle]
+pub extern "C" fn sk_canvas_destroy(canvas: *mut SkCanvas) {
+    if !canvas.is_null() && SkCanvasHandle::check(canvas) {
+        with_canvas(canvas, |canvas, ctx| canvas.pop_clips(ctx, 0));
+        handle::free::<SkCanvasHandle, _>(canvas);
+    }
+}
+
+/// Save the matrix and clip; returns the save count before saving
+#[no_mangle]
+pub extern "C" fn sk_canvas_save(canvas: *mut SkCanvas) -> c_int {
+    match get_mut::<SkCanvasHandle, _>(canvas) {
+        Some(canvas) => {
+            canvas.saves.push((canvas.matrix, canvas.clips));
+            canvas.saves.len() as c_int
+        }
+        None => 0,
+    }
+}
+
+/// Restore the matrix and clip of the last save; ignored without a save
+#[no_mangle]
+pub extern "C" fn sk_canvas_restore(canvas: *mut SkCanvas) {
+    with_canvas(canvas, |canvas, ctx| canvas.restore(ctx));
+}
+
+/// Restore until the save count is `count` (at least 1)
+#[no_mangle]
+pub extern "C" fn sk_canvas_restore_to_count(canvas: *mut SkCanvas, count: c_int) {
+    with_canvas(canvas, |canvas, ctx| {
+        let count = count.max(1) as usize;
+        while canvas.saves.len() + 1 > count {
+            canvas.restore(ctx);
+        }
+    });
+}
+
+/// Number of saves plus one, as in Skia
+#[no_mangle]
+pub extern "C" fn sk_canvas_get_save_count(canvas: *const SkCanvas) -> c_int {
+    get::<SkCanvasHandle, _>(canvas).map_or(0, |canvas| canvas.saves.len() as c_int + 1)
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_translate(canvas: *mut SkCanvas, dx: f32, dy: f32) {
+    if let Some(canvas) = get_mut::<SkCanvasHandle, _>(canvas) {
+        canvas.concat(Affine::translate((dx as f64, dy as f64)));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_scale(canvas: *mut SkCanvas, sx: f32, sy: f32) {
+    if let Some(canvas) = get_mut::<SkCanvasHandle, _>(canvas) {
+        canvas.concat(Affine::scale_non_uniform(sx as f64, sy as f64));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_rotate_degrees(canvas: *mut SkCanvas, degrees: f32) {
+    if let Some(canvas) = get_mut::<SkCanvasHandle, _>(canvas) {
+        canvas.concat(Affine::rotate((degrees as f64).to_radians()));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_skew(canvas: *mut SkCanvas, sx: f32, sy: f32) {
+    if let Some(canvas) = get_mut::<SkCanvasHandle, _>(canvas) {
+        canvas.concat(Affine::skew(sx as f64, sy as f64));
+    }
+}
+
+/// Pre-concatenate a matrix with the current matrix
+#[no_mangle]
+pub extern "C" fn sk_canvas_concat(canvas: *mut SkCanvas, matrix: *const SkMatrix) {
+    if matrix.is_null() {
+        set_last_error("Null matrix pointer");
+        return;
+    }
+    if let Some(canvas) = get_mut::<SkCanvasHandle, _>(canvas) {
+        canvas.concat(to_affine(unsafe { &*matrix }));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_set_matrix(canvas: *mut SkCanvas, matrix: *const SkMatrix) {
+    if matrix.is_null() {
+        set_last_error("Null matrix pointer");
+        return;
+    }
+    if let Some(canvas) = get_mut::<SkCanvasHandle, _>(canvas) {
+        canvas.matrix = to_affine(unsafe { &*matrix });
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_reset_matrix(canvas: *mut SkCanvas) {
+    if let Some(canvas) = get_mut::<SkCanvasHandle, _>(canvas) {
+        canvas.matrix = Affine::IDENTITY;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_get_total_matrix(canvas: *const SkCanvas, out_matrix: *mut SkMatrix) {
+    if out_matrix.is_null() {
+        set_last_error("Null matrix pointer");
+        return;
+    }
+    if let Some(canvas) = get::<SkCanvasHandle, _>(canvas) {
+        let [a, b, c, d, e, f] = canvas.matrix.as_coeffs().map(|v| v as f32);
+        unsafe {
+            *out_matrix = SkMatrix {
+                scaleX: a,
+                skewX: c,
+                transX: e,
+                skewY: b,
+                scaleY: d,
+                transY: f,
+                persp0: 0.0,
+                persp1: 0.0,
+                persp2: 1.0,
+            };
+        }
+    }
+}
+
+/// Intersect the clip with a rectangle
+#[no_mangle]
+pub extern "C" fn sk_canvas_clip_rect(canvas: *mut SkCanvas, rect: *const SkRect) {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return;
+    }
+    with_canvas(canvas, |canvas, ctx| {
+        let rect = to_rect(unsafe { &*rect });
+        canvas.clip(ctx, &rect.to_path(0.1), Fill::NonZero);
+    });
+}
+
+/// Intersect the clip with a path
+#[no_mangle]
+pub extern "C" fn sk_canvas_clip_path(canvas: *mut SkCanvas, path: *const SkPath) {
+    if path.is_null() {
+        set_last_error("Null path pointer");
+        return;
+    }
+    with_canvas(canvas, |canvas, ctx| {
+        let path = SkPathHandle::from_ptr(path);
+        canvas.clip(ctx, &path.path, path.fill_rule());
+    });
+}
+
+/// Replace every pixel inside the clip with a color
+#[no_mangle]
+pub extern "C" fn sk_canvas_clear(canvas: *mut SkCanvas, color: SkColor) {
+    with_canvas(canvas, |_, ctx| {
+        let rect = device_rect(ctx);
+        ctx.set_paint(to_color(color));
+        ctx.set_paint_transform(Affine::IDENTITY);
+        ctx.set_transform(Affine::IDENTITY);
+        ctx.push_layer(
+            None,
+            Some(BlendMode::new(Mix::Normal, Compose::Copy)),
+            None,
+            None,
+        );
+        // Not a draw, so it bypasses hit testing and snapping
+        (**ctx).fill_rect(&rect);
+        ctx.pop_layer();
+    });
+}
+
+/// Fill the clip with a paint, ignoring its style
+#[no_mangle]
+pub extern "C" fn sk_canvas_draw_paint(canvas: *mut SkCanvas, paint: *const SkPaint) {
+    with_paint(canvas, paint, |_, ctx, paint| {
+        let fill = PaintState {
+            style: SkPaintStyle::FILL_SK_PAINT_STYLE,
+            ..paint.clone()
+        };
+        let path = device_rect(ctx).to_path(0.1);
+        draw(ctx, Affine::IDENTITY, &path, Fill::NonZero, &fill);
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_draw_rect(
+    canvas: *mut SkCanvas,
+    rect: *const SkRect,
+    paint: *const SkPaint,
+) {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return;
+    }
+    with_paint(canvas, paint, |canvas, ctx, paint| {
+        let path = to_rect(unsafe { &*rect }).to_path(0.1);
+        draw(ctx, canvas.matrix, &path, Fill::NonZero, paint);
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_draw_oval(
+    canvas: *mut SkCanvas,
+    rect: *const SkRect,
+    paint: *const SkPaint,
+) {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return;
+    }
+    with_paint(canvas, paint, |canvas, ctx, paint| {
+        let path = Ellipse::from_rect(to_rect(unsafe { &*rect })).to_path(0.1);
+        draw(ctx, canvas.matrix, &path, Fill::NonZero, paint);
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_draw_circle(
+    canvas: *mut SkCanvas,
+    cx: f32,
+    cy: f32,
+    radius: f32,
+    paint: *const SkPaint,
+) {
+    with_paint(canvas, paint, |canvas, ctx, paint| {
+        let path = Circle::new((cx as f64, cy as f64), radius as f64).to_path(0.1);
+        draw(ctx, canvas.matrix, &path, Fill::NonZero, paint);
+    });
+}
+
+/// Draw a rectangle with elliptical corners of radii `rx` and `ry`
+#[no_mangle]
+pub extern "C" fn sk_canvas_draw_round_rect(
+    canvas: *mut SkCanvas,
+    rect: *const SkRect,
+    rx: f32,
+    ry: f32,
+    paint: *const SkPaint,
+) {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return;
+    }
+    with_paint(canvas, paint, |canvas, ctx, paint| {
+        let rect = to_rect(unsafe { &*rect });
+        let (rx, ry) = (rx.max(0.0) as f64, ry.max(0.0) as f64);
+        let path = if rx == 0.0 || ry == 0.0 {
+            rect.to_path(0.1)
+        } else {
+            // kurbo rounds with circular corners, so round where the corners are circles
+            let squash = ry / rx;
+            let stretched = Rect::new(rect.x0, rect.y0 / squash, rect.x1, rect.y1 / squash);
+            Affine::scale_non_uniform(1.0, squash) * stretched.to_rounded_rect(rx).to_path(0.1)
+        };
+        draw(ctx, canvas.matrix, &path, Fill::NonZero, paint);
+    });
+}
+
+/// Stroke a line segment; the paint is always stroked
+#[no_mangle]
+pub extern "C" fn sk_canvas_draw_line(
+    canvas: *mut SkCanvas,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    paint: *const SkPaint,
+) {
+    with_paint(canvas, paint, |canvas, ctx, paint| {
+        let stroke = PaintState {
+            style: SkPaintStyle::STROKE_SK_PAINT_STYLE,
+            ..paint.clone()
+        };
+        let mut path = BezPath::new();
+        path.move_to(Point::new(x0 as f64, y0 as f64));
+        path.line_to(Point::new(x1 as f64, y1 as f64));
+        draw(ctx, canvas.matrix, &path, Fill::NonZero, &stroke);
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn sk_canvas_draw_path(
+    canvas: *mut SkCanvas,
+    path: *const SkPath,
+    paint: *const SkPaint,
+) {
+    if path.is_null() {
+        set_last_error("Null path pointer");
+        return;
+    }
+    with_paint(canvas, paint, |canvas, ctx, paint| {
+        let path = SkPathHandle::from_ptr(path);
+        draw(ctx, canvas.matrix, &path.path, path.fill_rule(), paint);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{vello_render_context_free, vello_render_context_new};
+    use crate::test_util::render;
+
+    #[test]
+    fn colors_are_unpacked_from_argb() {
+        let color = to_color(0x8011_2233).to_rgba8();
+        assert_eq!([color.r, color.g, color.b, color.a], [0x11, 0x22, 0x33, 0x80]);
+    }
+
+    #[test]
+    fn matrices_map_to_column_major_affines() {
+        let matrix = SkMatrix {
+            scaleX: 2.0,
+            skewX: 3.0,
+            transX: 4.0,
+            skewY: 5.0,
+            scaleY: 6.0,
+            transY: 7.0,
+            persp0: 0.0,
+            persp1: 0.0,
+            persp2: 1.0,
+        };
+        assert_eq!(to_affine(&matrix).as_coeffs(), [2.0, 5.0, 3.0, 6.0, 4.0, 7.0]);
+
+        let ctx = vello_render_context_new(8, 8);
+        let canvas = vello_skia_canvas_new(ctx);
+        sk_canvas_set_matrix(canvas, &matrix);
+        let mut total = SkMatrix { persp2: 0.0, ..matrix };
+        sk_canvas_get_total_matrix(canvas, &mut total);
+        assert_eq!(total, matrix);
+
+        sk_canvas_destroy(canvas);
+        vello_render_context_free(ctx);
+    }
+
+    #[test]
+    fn paint_settings_map_to_strokes() {
+        let paint = sk_paint_new();
+        sk_paint_set_stroke_width(paint, 3.0);
+        sk_paint_set_stroke_width(paint, -1.0);
+        sk_paint_set_stroke_miter(paint, f32::NAN);
+        sk_paint_set_stroke_cap(paint, SkStrokeCap::SQUARE_SK_STROKE_CAP);
+        sk_paint_set_stroke_join(paint, SkStrokeJoin::BEVEL_SK_STROKE_JOIN);
+        assert_eq!(sk_paint_get_stroke_width(paint), 3.0);
+        assert_eq!(sk_paint_get_stroke_miter(paint), 4.0);
+        assert_eq!(sk_paint_get_stroke_cap(paint), SkStrokeCap::SQUARE_SK_STROKE_CAP);
+        assert_eq!(sk_paint_get_stroke_join(paint), SkStrokeJoin::BEVEL_SK_STROKE_JOIN);
+
+        let state = &SkPaintHandle::from_ptr(paint).state;
+        let stroke = state.stroke(state.stroke_width);
+        assert_eq!(stroke.width, 3.0);
+        assert_eq!((stroke.start_cap, stroke.end_cap), (Cap::Square, Cap::Square));
+        assert_eq!(stroke.join, Join::Bevel);
+        assert_eq!(stroke.miter_limit, 4.0);
+
+        sk_paint_reset(paint);
+        assert_eq!(sk_paint_get_stroke_cap(paint), SkStrokeCap::BUTT_SK_STROKE_CAP);
+        sk_paint_delete(paint);
+    }
+
+    #[test]
+    fn fill_types_and_directions_decide_the_holes() {
+        let outer = SkRect { left: 0.0, top: 0.0, right: 12.0, bottom: 12.0 };
+        let inner = SkRect { left: 4.0, top: 4.0, right: 8.0, bottom: 8.0 };
+        let draw = |fill_type, direction| {
+            let ctx = vello_render_context_new(12, 12);
+            let canvas = vello_skia_canvas_new(ctx);
+            let paint = sk_paint_new();
+            sk_paint_set_color(paint, 0xFFFF_0000);
+            let path = sk_path_new();
+            sk_path_add_rect(path, &outer, SkPathDirection::CW_SK_PATH_DIRECTION);
+            sk_path_add_rect(path, &inner, direction);
+            sk_path_set_filltype(path, fill_type);
+            assert_eq!(sk_path_get_filltype(path), fill_type);
+            sk_canvas_draw_path(canvas, path, paint);
+
+            let pixels = render(ctx, 12, 12);
+            sk_path_delete(path);
+            sk_paint_delete(paint);
+            sk_canvas_destroy(canvas);
+            vello_render_context_free(ctx);
+            assert_eq!(pixels[12 + 1], [255, 0, 0, 255]);
+            pixels[6 * 12 + 6]
+        };
+
+        use SkPathDirection::*;
+        use SkPathFillType::*;
+        assert_eq!(draw(WINDING_SK_PATH_FILLTYPE, CW_SK_PATH_DIRECTION), [255, 0, 0, 255]);
+        assert_eq!(draw(WINDING_SK_PATH_FILLTYPE, CCW_SK_PATH_DIRECTION), [0; 4]);
+        assert_eq!(draw(EVENODD_SK_PATH_FILLTYPE, CW_SK_PATH_DIRECTION), [0; 4]);
+    }
+
+    #[test]
+    fn path_bounds_cover_the_added_shapes() {
+        let path = sk_path_new();
+        let mut bounds = SkRect { left: 1.0, ..SkRect::default() };
+        sk_path_get_bounds(path, &mut bounds);
+        assert_eq!(bounds, SkRect::default());
+
+        sk_path_add_circle(path, 10.0, 20.0, 5.0, SkPathDirection::CCW_SK_PATH_DIRECTION);
+        sk_path_get_bounds(path, &mut bounds);
+        let edges = [bounds.left, bounds.top, bounds.right, bounds.bottom];
+        for (edge, expected) in edges.into_iter().zip([5.0, 15.0, 15.0, 25.0]) {
+            assert!((edge - expected).abs() < 0.05, "{edge} != {expected}");
+        }
+        sk_path_delete(path);
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Pixel snapping of axis-aligned geometry
+//!
+//! Edges are moved in device space and mapped back through the transform, so
+//! snapping only applies when the transform keeps axes aligned (scale and
+//! translation, no rotation or skew). Fill edges land on pixel boundaries;
+//! stroke centre lines land on pixel centres for odd device widths and on
+//! boundaries for even ones, so both edges of the stroke are crisp.
+
+use vello_cpu::kurbo::{Affine, BezPath, PathEl, Point, Rect, Stroke};
+
+/// Scale and translation per axis, if `transform` keeps axes aligned
+fn axis_aligned(transform: &Affine) -> Option<[f64; 4]> {
+    let [a, b, c, d, e, f] = transform.as_coeffs();
+    (b == 0.0 && c == 0.0 && a != 0.0 && d != 0.0).then_some([a, d, e, f])
+}
+
+/// Snap a device coordinate to a pixel centre (`centre`) or boundary
+fn snap(v: f64, centre: bool) -> f64 {
+    if centre {
+        v.floor() + 0.5
+    } else {
+        v.round()
+    }
+}
+
+/// Whether a stroke of `width` device pixels is centred on pixel centres
+fn odd_width(width: f64) -> bool {
+    // Hairlines are drawn one pixel wide
+    (width.round().max(1.0) as i64) % 2 == 1
+}
+
+/// Fill rectangle with its edges on pixel boundaries
+pub(crate) fn fill_rect(transform: &Affine, rect: &Rect) -> Rect {
+    let Some([sx, sy, tx, ty]) = axis_aligned(transform) else {
+        return *rect;
+    };
+    let x = |v: f64| (snap(v * sx + tx, false) - tx) / sx;
+    let y = |v: f64| (snap(v * sy + ty, false) - ty) / sy;
+    Rect::new(x(rect.x0), y(rect.y0), x(rect.x1), y(rect.y1))
+}
+
+/// Stroke rectangle with crisp stroke edges
+pub(crate) fn stroke_rect(transform: &Affine, stroke: &Stroke, rect: &Rect) -> Rect {
+    let Some([sx, sy, tx, ty]) = axis_aligned(transform) else {
+        return *rect;
+    };
+    let centre_x = odd_width(stroke.width * sx.abs());
+    let centre_y = odd_width(stroke.width * sy.abs());
+    let x = |v: f64| (snap(v * sx + tx, centre_x) - tx) / sx;
+    let y = |v: f64| (snap(v * sy + ty, centre_y) - ty) / sy;
+    Rect::new(x(rect.x0), y(rect.y0), x(rect.x1), y(rect.y1))
+}
+
+/// Hairline path made of straight segments with its points on pixel centres
+///
+/// Returns `None` for wider strokes, paths with curves and transforms that
+/// do not keep axes aligned.
+pub(crate) fn hairline(transform: &Affine, stroke: &Stroke, path: &BezPath) -> Option<BezPath> {
+    let [sx, sy, tx, ty] = axis_aligned(transform)?;
+    if stroke.width * sx.abs().max(sy.abs()) > 1.0 {
+        return None;
+    }
+    let point = |p: Point| {
+        Point::new(
+            (snap(p.x * sx + tx, true) - tx) / sx,
+            (snap(p.y * sy + ty, true) - ty) / sy,
+        )
+    };
+    path.elements()
+        .iter()
+        .map(|el| match *el {
+            PathEl::MoveTo(p) => Some(PathEl::MoveTo(point(p))),
+            PathEl::LineTo(p) => Some(PathEl::LineTo(point(p))),
+            PathEl::ClosePath => Some(PathEl::ClosePath),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(BezPath::from_vec)
+}
@@ -0,0 +1,319 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Access to the sparse strip representation
+//!
+//! vello_cpu rasterizes every path into strips: runs of 4 pixel high tiles
+//! with per-pixel coverage stored in a shared alpha buffer, plus a flag saying
+//! whether the gap to the previous strip on the row is solidly filled. This
+//! module hands that representation to hosts that want to consume it directly,
+//! for example to upload it to a GPU.
+
+use std::ffi::c_void;
+use std::os::raw::c_int;
+
+use vello_common::recording::{Recordable, Recording};
+use vello_common::strip::Strip;
+use vello_common::tile::Tile;
+use vello_cpu::kurbo::{Affine, Shape};
+use vello_cpu::peniko::Fill;
+use vello_cpu::RenderContext;
+
+use crate::context::RenderContextHandle;
+use crate::error::set_last_error;
+use crate::ffi_catch;
+use crate::handle::TypedHandle;
+use crate::path::PathHandle;
+use crate::recording::VelloRecording;
+use crate::types::*;
+
+/// One strip of coverage
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VelloStrip {
+    /// Left edge in pixels
+    pub x: u16,
+    /// Top edge in pixels, a multiple of `tile_height`
+    pub y: u16,
+    /// Index of the strip's first coverage byte in the alpha buffer
+    pub alpha_idx: u32,
+    /// Whether the pixels between the previous strip on the row and this one
+    /// are fully covered
+    pub fill_gap: bool,
+    pub _padding: [u8; 3],
+}
+
+/// Strip and alpha buffers produced for a recording
+///
+/// A strip's coverage runs from its `alpha_idx` to the next strip's, stored
+/// column by column with `tile_height` bytes per pixel column, so its width is
+/// that length divided by `tile_height`. Strips of one path are ordered by row
+/// and then by `x`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct VelloStripBuffer {
+    pub strips: *const VelloStrip,
+    pub strip_count: usize,
+    pub alphas: *const u8,
+    pub alpha_count: usize,
+    /// Width of a tile in pixels
+    pub tile_width: u16,
+    /// Height of a strip in pixels
+    pub tile_height: u16,
+}
+
+/// Buffers backing the last `vello_render_context_strips` result
+#[derive(Default)]
+pub(crate) struct StripOutput {
+    strips: Vec<VelloStrip>,
+    alphas: Vec<u8>,
+}
+
+impl StripOutput {
+    /// Concatenate strip and alpha buffers, rebasing each part's alpha indices,
+    /// and point `out_buffer` at the result
+    fn fill<'a>(
+        &mut self,
+        parts: impl IntoIterator<Item = (&'a [Strip], &'a [u8])>,
+        out_buffer: *mut VelloStripBuffer,
+    ) {
+        self.strips.clear();
+        self.alphas.clear();
+        for (strips, alphas) in parts {
+            let base = self.alphas.len() as u32;
+            self.strips.extend(strips.iter().map(|strip| VelloStrip {
+                x: strip.x,
+                y: strip.y,
+                alpha_idx: base + strip.alpha_idx(),
+                fill_gap: strip.fill_gap(),
+                _padding: [0; 3],
+            }));
+            self.alphas.extend_from_slice(alphas);
+        }
+
+        unsafe {
+            *out_buffer = VelloStripBuffer {
+                strips: self.strips.as_ptr(),
+                strip_count: self.strips.len(),
+                alphas: self.alphas.as_ptr(),
+                alpha_count: self.alphas.len(),
+                tile_width: Tile::WIDTH,
+                tile_height: Tile::HEIGHT,
+            };
+        }
+    }
+}
+
+/// Get the strips generated for a recording
+///
+/// Prepares the recording on the context if it has no cached strips yet, then
+/// returns read-only copies of its strip and alpha buffers. Use
+/// `vello_render_context_scene_strips` for immediate-mode drawing. The buffers
+/// stay valid until the next call on the same context,
+/// `vello_render_context_trim` or freeing the context.
+#[no_mangle]
+pub extern "C" fn vello_render_context_strips(
+    ctx: *mut VelloRenderContext,
+    recording: *mut VelloRecording,
+    out_buffer: *mut VelloStripBuffer,
+) -> c_int {
+    if ctx.is_null() || recording.is_null() || out_buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = VelloRecording::from_ptr_mut(recording);
+        if !recording.is_prepared() {
+            recording.prepare(ctx.context_mut());
+        }
+
+        // Chunks index their own alpha buffers; rebase them onto one buffer
+        ctx.strip_output.fill(recording.chunks.strips(), out_buffer);
+        VELLO_OK
+    })
+}
+
+/// Get the strips of everything drawn on the context since the last reset
+///
+/// The scene logged by the context is rasterized into strips on every call,
+/// at the context's size and with its transforms, so this shows what the next
+/// flush works with without recording the content separately. Blurred rounded
+/// rectangles are not strip-based and are left out. While an offscreen layer
+/// is open, the drawing queued inside it is returned. The buffers stay valid
+/// as for `vello_render_context_strips`.
+#[no_mangle]
+pub extern "C" fn vello_render_context_scene_strips(
+    ctx: *mut VelloRenderContext,
+    out_buffer: *mut VelloStripBuffer,
+) -> c_int {
+    if ctx.is_null() || out_buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let recording = ctx.to_recording();
+        let strips = std::iter::once(recording.get_cached_strips());
+        ctx.strip_output.fill(strips, out_buffer);
+        VELLO_OK
+    })
+}
+
+/// Run of coverage within one strip row
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct VelloCoverageSpan {
+    /// Left edge in pixels
+    pub x: i32,
+    /// Top edge in pixels, a multiple of `height`
+    pub y: i32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels, the same for every span
+    pub height: u32,
+    /// Coverage column by column with `height` bytes per pixel column, or null
+    /// if every pixel of the span is fully covered
+    pub alphas: *const u8,
+}
+
+/// Callback receiving one coverage span; the span and its alphas are only
+/// valid during the call
+pub type VelloCoverageCallback =
+    extern "C" fn(span: *const VelloCoverageSpan, user_data: *mut c_void);
+
+/// Rasterize a single path and report its coverage without painting it
+///
+/// Uses the same analytic anti-aliasing as regular fills. Spans are reported
+/// row by row, left to right. The transform may be null for identity.
+#[no_mangle]
+pub extern "C" fn vello_rasterize_path_coverage(
+    path: *const VelloBezPath,
+    transform: *const VelloAffine,
+    fill_rule: VelloFillRule,
+    callback: Option<VelloCoverageCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    if path.is_null() {
+        set_last_error("Null path pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    let Some(callback) = callback else {
+        set_last_error("Null callback");
+        return VELLO_ERROR_NULL_POINTER;
+    };
+
+    ffi_catch!({
+        let path = &PathHandle::from_ptr(path).path;
+        let transform = match unsafe { transform.as_ref() } {
+            Some(t) => Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]),
+            None => Affine::IDENTITY,
+        };
+        let bounds = transform.transform_rect_bbox(path.bounding_box());
+        if !bounds.is_finite() || bounds.is_zero_area() {
+            return VELLO_OK;
+        }
+
+        // Rasterize into a context covering just the path, keeping the origin
+        // tile-aligned so coverage matches drawing at the original position
+        let tile_w = Tile::WIDTH as f64;
+        let tile_h = Tile::HEIGHT as f64;
+        let x0 = (bounds.x0 / tile_w).floor() * tile_w;
+        let y0 = (bounds.y0 / tile_h).floor() * tile_h;
+        let width = (bounds.x1 - x0).ceil() + 1.0;
+        let height = (bounds.y1 - y0).ceil() + 1.0;
+        if width > u16::MAX as f64 || height > u16::MAX as f64 {
+            set_last_error("Path is too large to rasterize");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        let width = width as u16;
+
+        let mut ctx = RenderContext::new(width, height as u16);
+        let mut recording = Recording::new();
+        ctx.record(&mut recording, |recorder| {
+            recorder.set_transform(Affine::translate((-x0, -y0)) * transform);
+            recorder.set_fill_rule(match fill_rule {
+                VelloFillRule::NonZero => Fill::NonZero,
+                VelloFillRule::EvenOdd => Fill::EvenOdd,
+            });
+            recorder.fill_path(path);
+        });
+        ctx.prepare_recording(&mut recording);
+        let (strips, alphas) = recording.get_cached_strips();
+
+        let column = Tile::HEIGHT as usize;
+        let emit = |x: u16, y: u16, columns: usize, alphas: *const u8| {
+            let span = VelloCoverageSpan {
+                x: x as i32 + x0 as i32,
+                y: y as i32 + y0 as i32,
+                width: columns as u32,
+                height: Tile::HEIGHT as u32,
+                alphas,
+            };
+            callback(&span, user_data);
+        };
+
+        // Right edge of the previous strip on the current row
+        let mut row_end: Option<(u16, u16)> = None;
+        for (i, strip) in strips.iter().enumerate() {
+            if strip.x >= width {
+                // Sentinel closing the path
+                row_end = None;
+                continue;
+            }
+            let start = strip.alpha_idx() as usize;
+            let end = strips
+                .get(i + 1)
+                .map_or(alphas.len(), |s| s.alpha_idx() as usize);
+            let columns = end.saturating_sub(start) / column;
+
+            if let Some((y, x)) = row_end {
+                if strip.fill_gap() && y == strip.y && strip.x > x {
+                    emit(x, y, (strip.x - x) as usize, std::ptr::null());
+                }
+            }
+            if columns > 0 {
+                emit(strip.x, strip.y, columns, alphas[start..].as_ptr());
+            }
+            row_end = Some((strip.y, strip.x + columns as u16));
+        }
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_fill_rect, vello_render_context_free, vello_render_context_new,
+    };
+
+    #[test]
+    fn scene_strips_cover_immediate_drawing() {
+        let ctx = vello_render_context_new(32, 32);
+        let rect = VelloRect { x0: 4.5, y0: 4.0, x1: 20.0, y1: 12.0 };
+        assert_eq!(vello_render_context_fill_rect(ctx, &rect), VELLO_OK);
+
+        let mut buffer = VelloStripBuffer {
+            strips: std::ptr::null(),
+            strip_count: 0,
+            alphas: std::ptr::null(),
+            alpha_count: 0,
+            tile_width: 0,
+            tile_height: 0,
+        };
+        assert_eq!(vello_render_context_scene_strips(ctx, &mut buffer), VELLO_OK);
+        let strips = unsafe { std::slice::from_raw_parts(buffer.strips, buffer.strip_count) };
+        assert!(!strips.is_empty());
+        assert!(strips.iter().all(|s| (4..12).contains(&s.y) && s.x < 24));
+        assert!(strips.iter().all(|s| (s.alpha_idx as usize) < buffer.alpha_count));
+
+        assert_eq!(
+            vello_render_context_scene_strips(ctx, std::ptr::null_mut()),
+            VELLO_ERROR_NULL_POINTER
+        );
+        vello_render_context_free(ctx);
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! SVG document loading and rendering
+//!
+//! Documents are parsed and normalized by usvg, then drawn by walking the
+//! tree and issuing regular fills, strokes and layers on the context. Solid
+//! colors, linear and radial gradients, group opacity, blend modes, clip paths
+//! and dashed strokes are supported. Patterns, masks, filters and embedded
+//! images are skipped. No fonts are loaded for parsing, so text elements are
+//! dropped; icons should have their text converted to paths.
+
+use std::os::raw::c_int;
+
+use usvg::tiny_skia_path::PathSegment;
+use vello_cpu::kurbo::{Affine, BezPath, Cap, Join, Point, Stroke};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::{BlendMode, ColorStop, Compose, Extend, Fill, Gradient, Mix};
+
+use crate::context::RenderContextHandle;
+use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::types::*;
+use crate::{ffi_catch, ffi_catch_ptr};
+
+/// Object behind a `VelloSvgDocument` pointer
+#[repr(C)]
+pub(crate) struct SvgDocumentHandle {
+    header: HandleHeader,
+    tree: usvg::Tree,
+}
+
+impl TypedHandle for SvgDocumentHandle {
+    const TAG: u32 = handle::tag(b"VSVG");
+    const NAME: &'static str = "SVG document";
+}
+
+fn to_affine(t: usvg::Transform) -> Affine {
+    Affine::new([
+        t.sx as f64,
+        t.ky as f64,
+        t.kx as f64,
+        t.sy as f64,
+        t.tx as f64,
+        t.ty as f64,
+    ])
+}
+
+fn to_bezpath(path: &usvg::tiny_skia_path::Path) -> BezPath {
+    let point = |p: usvg::tiny_skia_path::Point| Point::new(p.x as f64, p.y as f64);
+    let mut out = BezPath::new();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => out.move_to(point(p)),
+            PathSegment::LineTo(p) => out.line_to(point(p)),
+            PathSegment::QuadTo(p1, p) => out.quad_to(point(p1), point(p)),
+            PathSegment::CubicTo(p1, p2, p) => out.curve_to(point(p1), point(p2), point(p)),
+            PathSegment::Close => out.close_path(),
+        }
+    }
+    out
+}
+
+fn to_color(color: usvg::Color, opacity: f32) -> AlphaColor<Srgb> {
+    let alpha = (opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+    AlphaColor::from_rgba8(color.red, color.green, color.blue, alpha)
+}
+
+fn to_mix(mode: usvg::BlendMode) -> Mix {
+    match mode {
+        usvg::BlendMode::Normal => Mix::Normal,
+        usvg::BlendMode::Multiply => Mix::Multiply,
+        usvg::BlendMode::Screen => Mix::Screen,
+        usvg::BlendMode::Overlay => Mix::Overlay,
+        usvg::BlendMode::Darken => Mix::Darken,
+        usvg::BlendMode::Lighten => Mix::Lighten,
+        usvg::BlendMode::ColorDodge => Mix::ColorDodge,
+        usvg::BlendMode::ColorBurn => Mix::ColorBurn,
+        usvg::BlendMode::HardLight => Mix::HardLight,
+        usvg::BlendMode::SoftLight => Mix::SoftLight,
+        usvg::BlendMode::Difference => Mix::Difference,
+        usvg::BlendMode::Exclusion => Mix::Exclusion,
+        usvg::BlendMode::Hue => Mix::Hue,
+        usvg::BlendMode::Saturation => Mix::Saturation,
+        usvg::BlendMode::Color => Mix::Color,
+        usvg::BlendMode::Luminosity => Mix::Luminosity,
+    }
+}
+
+/// Set the context paint for an SVG paint; returns false for unsupported paints
+fn set_paint(ctx: &mut RenderContextHandle, paint: &usvg::Paint, opacity: f32) -> bool {
+    let stops = |base: &usvg::BaseGradient| -> Vec<ColorStop> {
+        base.stops()
+            .iter()
+            .map(|stop| ColorStop {
+                offset: stop.offset().get(),
+                color: to_color(stop.color(), stop.opacity().get() * opacity).into(),
+            })
+            .collect()
+    };
+    let extend = |base: &usvg::BaseGradient| match base.spread_method() {
+        usvg::SpreadMethod::Pad => Extend::Pad,
+        usvg::SpreadMethod::Reflect => Extend::Reflect,
+        usvg::SpreadMethod::Repeat => Extend::Repeat,
+    };
+
+    match paint {
+        usvg::Paint::Color(color) => {
+            ctx.set_paint(to_color(*color, opacity));
+            ctx.set_paint_transform(Affine::IDENTITY);
+        }
+        usvg::Paint::LinearGradient(gradient) => {
+            let start = Point::new(gradient.x1() as f64, gradient.y1() as f64);
+            let end = Point::new(gradient.x2() as f64, gradient.y2() as f64);
+            ctx.set_paint(
+                Gradient::new_linear(start, end)
+                    .with_stops(&stops(gradient)[..])
+                    .with_extend(extend(gradient)),
+            );
+            ctx.set_paint_transform(to_affine(gradient.transform()));
+        }
+        usvg::Paint::RadialGradient(gradient) => {
+            let focus = Point::new(gradient.fx() as f64, gradient.fy() as f64);
+            let center = Point::new(gradient.cx() as f64, gradient.cy() as f64);
+            ctx.set_paint(
+                Gradient::new_two_point_radial(focus, 0.0, center, gradient.r().get())
+                    .with_stops(&stops(gradient)[..])
+                    .with_extend(extend(gradient)),
+            );
+            ctx.set_paint_transform(to_affine(gradient.transform()));
+        }
+        usvg::Paint::Pattern(_) => return false,
+    }
+    true
+}
+
+fn draw_fill(ctx: &mut RenderContextHandle, path: &BezPath, fill: &usvg::Fill) {
+    if set_paint(ctx, fill.paint(), fill.opacity().get()) {
+        ctx.set_fill_rule(match fill.rule() {
+            usvg::FillRule::NonZero => Fill::NonZero,
+            usvg::FillRule::EvenOdd => Fill::EvenOdd,
+        });
+        ctx.fill_path(path);
+    }
+}
+
+fn draw_stroke(ctx: &mut RenderContextHandle, path: &BezPath, stroke: &usvg::Stroke) {
+    if !set_paint(ctx, stroke.paint(), stroke.opacity().get()) {
+        return;
+    }
+    let cap = match stroke.linecap() {
+        usvg::LineCap::Butt => Cap::Butt,
+        usvg::LineCap::Round => Cap::Round,
+        usvg::LineCap::Square => Cap::Square,
+    };
+    let mut style = Stroke::new(stroke.width().get() as f64)
+        .with_caps(cap)
+        .with_join(match stroke.linejoin() {
+            usvg::LineJoin::Miter | usvg::LineJoin::MiterClip => Join::Miter,
+            usvg::LineJoin::Round => Join::Round,
+            usvg::LineJoin::Bevel => Join::Bevel,
+        })
+        .with_miter_limit(stroke.miterlimit().get() as f64);
+    if let Some(dashes) = stroke.dasharray() {
+        let dashes = dashes.iter().map(|d| *d as f64);
+        style = style.with_dashes(stroke.dashoffset() as f64, dashes);
+    }
+    ctx.set_stroke(style);
+    ctx.stroke_path(path);
+}
+
+/// Outline of a clip path's contents in the clip path's coordinate space
+fn clip_outline(group: &usvg::Group, transform: Affine, out: &mut BezPath) {
+    for node in group.children() {
+        match node {
+            usvg::Node::Group(child) => {
+                clip_outline(child, transform * to_affine(child.transform()), out);
+            }
+            usvg::Node::Path(path) if path.is_visible() => {
+                let outline = transform * to_bezpath(path.data());
+                out.extend(outline.elements().iter().copied());
+            }
+            usvg::Node::Text(text) => clip_outline(text.flattened(), transform, out),
+            _ => {}
+        }
+    }
+}
+
+/// Draw a group and its children; groups with masks or filters are skipped
+fn draw_group(ctx: &mut RenderContextHandle, group: &usvg::Group, transform: Affine) {
+    if !group.filters().is_empty() || group.mask().is_some() {
+        return;
+    }
+    let transform = transform * to_affine(group.transform());
+
+    // Nested clip paths intersect, so each becomes its own layer
+    let mut clips = Vec::new();
+    let mut clip_path = group.clip_path();
+    while let Some(clip) = clip_path {
+        let mut outline = BezPath::new();
+        clip_outline(clip.root(), Affine::IDENTITY, &mut outline);
+        clips.push((transform * to_affine(clip.transform()), outline));
+        clip_path = clip.clip_path();
+    }
+
+    let opacity = group.opacity().get();
+    let mix = to_mix(group.blend_mode());
+    let layered = opacity < 1.0 || mix != Mix::Normal;
+    for (clip_transform, outline) in &clips {
+        ctx.set_transform(*clip_transform);
+        ctx.set_fill_rule(Fill::NonZero);
+        ctx.push_clip_layer(outline);
+    }
+    if layered {
+        ctx.push_layer(
+            None,
+            Some(BlendMode::new(mix, Compose::SrcOver)),
+            Some(opacity),
+            None,
+        );
+    }
+
+    for node in group.children() {
+        match node {
+            usvg::Node::Group(child) => draw_group(ctx, child, transform),
+            usvg::Node::Path(path) if path.is_visible() => {
+                let outline = to_bezpath(path.data());
+                ctx.set_transform(transform);
+                match path.paint_order() {
+                    usvg::PaintOrder::FillAndStroke => {
+                        if let Some(fill) = path.fill() {
+                            draw_fill(ctx, &outline, fill);
+                        }
+                        if let Some(stroke) = path.stroke() {
+                            draw_stroke(ctx, &outline, stroke);
+                        }
+                    }
+                    usvg::PaintOrder::StrokeAndFill => {
+                        if let Some(stroke) = path.stroke() {
+                            draw_stroke(ctx, &outline, stroke);
+                        }
+                        if let Some(fill) = path.fill() {
+                            draw_fill(ctx, &outline, fill);
+                        }
+                    }
+                }
+            }
+            usvg::Node::Text(text) => draw_group(ctx, text.flattened(), transform),
+            _ => {}
+        }
+    }
+
+    if layered {
+        ctx.pop_layer();
+    }
+    for _ in &clips {
+        ctx.pop_layer();
+    }
+}
+
+/// Parse an SVG document (UTF-8 XML or gzip-compressed SVGZ)
+///
+/// Returns null if the data is not a valid SVG document; the reason is
+/// available from `vello_get_last_error`.
+#[no_mangle]
+pub extern "C" fn vello_svg_load(data: *const u8, len: usize) -> *mut VelloSvgDocument {
+    if data.is_null() {
+        set_last_error("Null data pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let data = unsafe { std::slice::from_raw_parts(data, len) };
+        match usvg::Tree::from_data(data, &usvg::Options::default()) {
+            Ok(tree) => {
+                let doc = SvgDocumentHandle {
+                    header: HandleHeader::new::<SvgDocumentHandle>(),
+                    tree,
+                };
+                handle::into_raw(doc) as *mut VelloSvgDocument
+            }
+            Err(e) => {
+                set_last_error(format!("Failed to parse SVG: {e}"));
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Free an SVG document
+#[no_mangle]
+pub extern "C" fn vello_svg_free(doc: *mut VelloSvgDocument) {
+    if !doc.is_null() && SvgDocumentHandle::check(doc) {
+        handle::free::<SvgDocumentHandle, _>(doc);
+    }
+}
+
+/// Get the intrinsic size of an SVG document in user units
+#[no_mangle]
+pub extern "C" fn vello_svg_size(
+    doc: *const VelloSvgDocument,
+    out_width: *mut f32,
+    out_height: *mut f32,
+) -> c_int {
+    if doc.is_null() || out_width.is_null() || out_height.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let doc = SvgDocumentHandle::from_ptr(doc);
+        let size = doc.tree.size();
+        unsafe {
+            *out_width = size.width();
+            *out_height = size.height();
+        }
+        VELLO_OK
+    })
+}
+
+/// Draw an SVG document
+///
+/// The document is placed with `transform` (null for identity) applied after
+/// the context's current transform, so user unit (0, 0) maps to the origin.
+/// Paint, stroke, fill rule and transforms are restored afterwards.
+#[no_mangle]
+pub extern "C" fn vello_render_context_draw_svg(
+    ctx: *mut VelloRenderContext,
+    doc: *const VelloSvgDocument,
+    transform: *const VelloAffine,
+) -> c_int {
+    if ctx.is_null() || doc.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let doc = SvgDocumentHandle::from_ptr(doc);
+        let placement = match unsafe { transform.as_ref() } {
+            Some(t) => Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]),
+            None => Affine::IDENTITY,
+        };
+
+        let saved_paint = ctx.paint().clone();
+        let saved_paint_transform = *ctx.paint_transform();
+        let saved_transform = *ctx.transform();
+        let saved_fill_rule = *ctx.fill_rule();
+        let saved_stroke = ctx.stroke().clone();
+
+        draw_group(ctx, doc.tree.root(), saved_transform * placement);
+
+        ctx.set_paint(saved_paint);
+        ctx.set_paint_transform(saved_paint_transform);
+        ctx.set_transform(saved_transform);
+        ctx.set_fill_rule(saved_fill_rule);
+        ctx.set_stroke(saved_stroke);
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_free, vello_render_context_get_transform, vello_render_context_new,
+        vello_render_context_set_transform,
+    };
+    use crate::test_util::{render, translate};
+
+    fn load(svg: &str) -> *mut VelloSvgDocument {
+        vello_svg_load(svg.as_ptr(), svg.len())
+    }
+
+    #[test]
+    fn documents_report_parse_errors_and_sizes() {
+        assert!(load("<svg").is_null());
+        let message = unsafe { std::ffi::CStr::from_ptr(crate::error::vello_get_last_error()) };
+        assert!(message.to_str().unwrap().starts_with("Failed to parse SVG"));
+
+        let doc = load(r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="4"/>"#);
+        assert!(!doc.is_null());
+        let (mut width, mut height) = (0.0, 0.0);
+        assert_eq!(vello_svg_size(doc, &mut width, &mut height), VELLO_OK);
+        assert_eq!((width, height), (8.0, 4.0));
+        let result = vello_svg_size(doc, std::ptr::null_mut(), &mut height);
+        assert_eq!(result, VELLO_ERROR_NULL_POINTER);
+
+        let ctx = vello_render_context_new(4, 4);
+        let not_a_doc = ctx as *const VelloSvgDocument;
+        assert_eq!(vello_svg_size(not_a_doc, &mut width, &mut height), VELLO_ERROR_INVALID_HANDLE);
+        let result = vello_render_context_draw_svg(ctx, not_a_doc, std::ptr::null());
+        assert_eq!(result, VELLO_ERROR_INVALID_HANDLE);
+
+        vello_render_context_free(ctx);
+        vello_svg_free(doc);
+    }
+
+    #[test]
+    fn documents_are_placed_after_the_context_transform() {
+        let doc = load(concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4">"#,
+            r##"<rect width="4" height="4" fill="#0000ff"/></svg>"##,
+        ));
+        let ctx = vello_render_context_new(8, 4);
+        assert_eq!(vello_render_context_set_transform(ctx, &translate(1.0, 0.0)), VELLO_OK);
+        assert_eq!(vello_render_context_draw_svg(ctx, doc, &translate(3.0, 0.0)), VELLO_OK);
+
+        let mut transform = translate(0.0, 0.0);
+        assert_eq!(vello_render_context_get_transform(ctx, &mut transform), VELLO_OK);
+        assert_eq!(transform, translate(1.0, 0.0));
+        let pixels = render(ctx, 8, 4);
+        assert_eq!(pixels[8 + 3], [0; 4]);
+        assert_eq!(pixels[8 + 4], [0, 0, 255, 255]);
+
+        vello_render_context_free(ctx);
+        vello_svg_free(doc);
+    }
+
+    #[test]
+    fn clip_paths_and_group_opacity_are_applied() {
+        let doc = load(concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="4">"#,
+            r#"<clipPath id="c"><rect width="2" height="4"/></clipPath>"#,
+            r##"<rect width="4" height="4" fill="#ff0000" clip-path="url(#c)"/>"##,
+            r##"<g opacity="0.5"><rect x="4" width="4" height="4" fill="#00ff00"/></g></svg>"##,
+        ));
+        let ctx = vello_render_context_new(8, 4);
+        assert_eq!(vello_render_context_draw_svg(ctx, doc, std::ptr::null()), VELLO_OK);
+
+        let pixels = render(ctx, 8, 4);
+        assert_eq!(pixels[8 + 1], [255, 0, 0, 255]);
+        assert_eq!(pixels[8 + 2], [0; 4]);
+        let [r, g, b, a] = pixels[8 + 6];
+        assert_eq!((r, b), (0, 0));
+        assert!(a.abs_diff(128) <= 1 && g == a, "{:?}", pixels[8 + 6]);
+
+        vello_render_context_free(ctx);
+        vello_svg_free(doc);
+    }
+}
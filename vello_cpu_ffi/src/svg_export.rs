@@ -0,0 +1,399 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Conversion of recordings into SVG documents
+//!
+//! Paths, rectangles and outline glyphs become `<path>` elements with their
+//! transform, paint and stroke inlined. Layers become groups with clip paths,
+//! opacity and `mix-blend-mode`. Features without an SVG equivalent degrade:
+//! sweep gradients use their first stop, masks and Porter-Duff compose modes
+//! are dropped.
+
+use std::fmt::Write;
+
+use vello_common::paint::{ImageSource, PaintType};
+use vello_common::recording::RenderCommand;
+use vello_cpu::kurbo::{Affine, BezPath, Cap, Join, Shape, Stroke};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::{Extend, Fill, GradientKind, Mix};
+
+/// Convert a command list into an SVG document of the given size
+pub(crate) fn to_svg(commands: &[RenderCommand], width: u32, height: u32) -> String {
+    let mut svg = SvgWriter {
+        defs: String::new(),
+        body: String::new(),
+        next_id: 0,
+    };
+
+    let mut transform = Affine::IDENTITY;
+    let mut fill_rule = Fill::NonZero;
+    let mut stroke = Stroke::default();
+    let mut paint = PaintType::Solid(AlphaColor::BLACK);
+    let mut paint_transform = Affine::IDENTITY;
+
+    for command in commands {
+        match command {
+            RenderCommand::FillPath(path) => {
+                let attrs = svg.paint("fill", &paint, paint_transform);
+                svg.fill(path, transform, &attrs, fill_rule);
+            }
+            RenderCommand::StrokePath(path) => {
+                let attrs = svg.paint("stroke", &paint, paint_transform);
+                svg.stroke(path, transform, &attrs, &stroke);
+            }
+            RenderCommand::FillRect(rect) => {
+                let attrs = svg.paint("fill", &paint, paint_transform);
+                svg.fill(&rect.to_path(0.1), transform, &attrs, fill_rule);
+            }
+            RenderCommand::StrokeRect(rect) => {
+                let attrs = svg.paint("stroke", &paint, paint_transform);
+                svg.stroke(&rect.to_path(0.1), transform, &attrs, &stroke);
+            }
+            RenderCommand::FillOutlineGlyph((path, glyph_transform)) => {
+                let attrs = svg.paint("fill", &paint, paint_transform);
+                svg.fill(path, *glyph_transform, &attrs, Fill::NonZero);
+            }
+            RenderCommand::StrokeOutlineGlyph((path, glyph_transform)) => {
+                let attrs = svg.paint("stroke", &paint, paint_transform);
+                svg.stroke(path, *glyph_transform, &attrs, &stroke);
+            }
+            RenderCommand::SetTransform(affine) => transform = *affine,
+            RenderCommand::SetFillRule(fill) => fill_rule = *fill,
+            RenderCommand::SetStroke(s) => stroke = s.clone(),
+            RenderCommand::SetPaint(p) => paint = p.clone(),
+            RenderCommand::SetPaintTransform(affine) => paint_transform = *affine,
+            RenderCommand::ResetPaintTransform => paint_transform = Affine::IDENTITY,
+            RenderCommand::PushLayer(layer) => {
+                let mut attrs = String::new();
+                if let Some(clip) = &layer.clip_path {
+                    let id = svg.id();
+                    let _ = write!(
+                        svg.defs,
+                        r#"<clipPath id="{id}"><path{} d="{}"/></clipPath>"#,
+                        transform_attr(transform),
+                        clip.to_svg()
+                    );
+                    let _ = write!(attrs, r#" clip-path="url(#{id})""#);
+                }
+                if let Some(opacity) = layer.opacity {
+                    let _ = write!(attrs, r#" opacity="{opacity}""#);
+                }
+                if let Some(mode) = layer.blend_mode.and_then(|bm| blend_mode_name(bm.mix)) {
+                    let _ = write!(attrs, r#" style="mix-blend-mode:{mode}""#);
+                }
+                let _ = writeln!(svg.body, "<g{attrs}>");
+            }
+            RenderCommand::PopLayer => svg.body.push_str("</g>\n"),
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    if !svg.defs.is_empty() {
+        let _ = writeln!(out, "<defs>{}</defs>", svg.defs);
+    }
+    out.push_str(&svg.body);
+    out.push_str("</svg>\n");
+    out
+}
+
+struct SvgWriter {
+    defs: String,
+    body: String,
+    next_id: u32,
+}
+
+impl SvgWriter {
+    fn id(&mut self) -> String {
+        self.next_id += 1;
+        format!("p{}", self.next_id)
+    }
+
+    fn fill(&mut self, path: &BezPath, transform: Affine, paint: &str, fill_rule: Fill) {
+        let rule = match fill_rule {
+            Fill::NonZero => "",
+            Fill::EvenOdd => r#" fill-rule="evenodd""#,
+        };
+        let _ = writeln!(
+            self.body,
+            r#"<path{} d="{}" {paint}{rule}/>"#,
+            transform_attr(transform),
+            path.to_svg()
+        );
+    }
+
+    fn stroke(&mut self, path: &BezPath, transform: Affine, paint: &str, stroke: &Stroke) {
+        let join = match stroke.join {
+            Join::Bevel => "bevel",
+            Join::Miter => "miter",
+            Join::Round => "round",
+        };
+        let cap = match stroke.start_cap {
+            Cap::Butt => "butt",
+            Cap::Square => "square",
+            Cap::Round => "round",
+        };
+        let _ = write!(
+            self.body,
+            r#"<path{} d="{}" fill="none" {paint} stroke-width="{}" stroke-linejoin="{join}" stroke-linecap="{cap}" stroke-miterlimit="{}""#,
+            transform_attr(transform),
+            path.to_svg(),
+            stroke.width,
+            stroke.miter_limit
+        );
+        if !stroke.dash_pattern.is_empty() {
+            let dashes: Vec<String> = stroke.dash_pattern.iter().map(f64::to_string).collect();
+            let _ = write!(
+                self.body,
+                r#" stroke-dasharray="{}" stroke-dashoffset="{}""#,
+                dashes.join(" "),
+                stroke.dash_offset
+            );
+        }
+        self.body.push_str("/>\n");
+    }
+
+    /// `fill` or `stroke` attributes for a paint, adding gradient or pattern definitions
+    fn paint(&mut self, attr: &str, paint: &PaintType, paint_transform: Affine) -> String {
+        match paint {
+            PaintType::Solid(color) => color_attrs(attr, *color),
+            PaintType::Gradient(gradient) => {
+                let stops: String = gradient
+                    .stops
+                    .iter()
+                    .map(|stop| {
+                        let c = stop.color.to_alpha_color::<Srgb>();
+                        format!(
+                            r#"<stop offset="{}" stop-color="{}" stop-opacity="{}"/>"#,
+                            stop.offset,
+                            rgb(c),
+                            c.components[3]
+                        )
+                    })
+                    .collect();
+                let spread = match gradient.extend {
+                    Extend::Pad => "pad",
+                    Extend::Repeat => "repeat",
+                    Extend::Reflect => "reflect",
+                };
+                let id = self.id();
+                let common = format!(
+                    r#"id="{id}" gradientUnits="userSpaceOnUse" spreadMethod="{spread}" gradientTransform="{}""#,
+                    matrix(paint_transform)
+                );
+                match &gradient.kind {
+                    GradientKind::Linear(pos) => {
+                        let _ = write!(
+                            self.defs,
+                            r#"<linearGradient {common} x1="{}" y1="{}" x2="{}" y2="{}">{stops}</linearGradient>"#,
+                            pos.start.x, pos.start.y, pos.end.x, pos.end.y
+                        );
+                    }
+                    GradientKind::Radial(pos) => {
+                        let _ = write!(
+                            self.defs,
+                            r#"<radialGradient {common} cx="{}" cy="{}" r="{}" fx="{}" fy="{}" fr="{}">{stops}</radialGradient>"#,
+                            pos.end_center.x,
+                            pos.end_center.y,
+                            pos.end_radius,
+                            pos.start_center.x,
+                            pos.start_center.y,
+                            pos.start_radius
+                        );
+                    }
+                    GradientKind::Sweep(_) => {
+                        // SVG has no conic gradients
+                        return match gradient.stops.first() {
+                            Some(stop) => color_attrs(attr, stop.color.to_alpha_color()),
+                            None => format!(r#"{attr}="none""#),
+                        };
+                    }
+                }
+                format!(r#"{attr}="url(#{id})""#)
+            }
+            PaintType::Image(image) => {
+                let ImageSource::Pixmap(pixmap) = &image.image else {
+                    return format!(r#"{attr}="none""#);
+                };
+                let (width, height) = (pixmap.width(), pixmap.height());
+                let Ok(png) = pixmap.as_ref().clone().into_png() else {
+                    return format!(r#"{attr}="none""#);
+                };
+                let id = self.id();
+                let _ = write!(
+                    self.defs,
+                    r#"<pattern id="{id}" patternUnits="userSpaceOnUse" width="{width}" height="{height}" patternTransform="{}"><image width="{width}" height="{height}" href="data:image/png;base64,{}"/></pattern>"#,
+                    matrix(paint_transform),
+                    base64(&png)
+                );
+                format!(r#"{attr}="url(#{id})""#)
+            }
+        }
+    }
+}
+
+fn matrix(affine: Affine) -> String {
+    let [a, b, c, d, e, f] = affine.as_coeffs();
+    format!("matrix({a} {b} {c} {d} {e} {f})")
+}
+
+fn transform_attr(affine: Affine) -> String {
+    if affine == Affine::IDENTITY {
+        String::new()
+    } else {
+        format!(r#" transform="{}""#, matrix(affine))
+    }
+}
+
+fn rgb(color: AlphaColor<Srgb>) -> String {
+    let c = color.to_rgba8();
+    format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)
+}
+
+fn color_attrs(attr: &str, color: AlphaColor<Srgb>) -> String {
+    let alpha = color.components[3];
+    if alpha >= 1.0 {
+        format!(r#"{attr}="{}""#, rgb(color))
+    } else {
+        format!(r#"{attr}="{}" {attr}-opacity="{alpha}""#, rgb(color))
+    }
+}
+
+fn blend_mode_name(mix: Mix) -> Option<&'static str> {
+    Some(match mix {
+        Mix::Normal => return None,
+        Mix::Multiply => "multiply",
+        Mix::Screen => "screen",
+        Mix::Overlay => "overlay",
+        Mix::Darken => "darken",
+        Mix::Lighten => "lighten",
+        Mix::ColorDodge => "color-dodge",
+        Mix::ColorBurn => "color-burn",
+        Mix::HardLight => "hard-light",
+        Mix::SoftLight => "soft-light",
+        Mix::Difference => "difference",
+        Mix::Exclusion => "exclusion",
+        Mix::Hue => "hue",
+        Mix::Saturation => "saturation",
+        Mix::Color => "color",
+        Mix::Luminosity => "luminosity",
+        #[allow(unreachable_patterns)]
+        _ => return None,
+    })
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{c_void, CStr};
+
+    use super::*;
+    use crate::context::{vello_render_context_free, vello_render_context_new};
+    use crate::path::{
+        vello_bezpath_close, vello_bezpath_free, vello_bezpath_line_to, vello_bezpath_move_to,
+        vello_bezpath_new,
+    };
+    use crate::recording::*;
+    use crate::test_util::translate;
+    use crate::types::*;
+
+    extern "C" fn record_scene(clip: *mut c_void, recorder: *mut c_void) {
+        assert_eq!(vello_recorder_set_paint_solid(recorder, 255, 0, 0, 128), VELLO_OK);
+        assert_eq!(vello_recorder_set_transform(recorder, &translate(2.0, 3.0)), VELLO_OK);
+        assert_eq!(vello_recorder_set_fill_rule(recorder, VelloFillRule::EvenOdd), VELLO_OK);
+        let rect = VelloRect { x0: 0.0, y0: 0.0, x1: 4.0, y1: 4.0 };
+        assert_eq!(vello_recorder_fill_rect(recorder, &rect), VELLO_OK);
+
+        assert_eq!(vello_recorder_push_clip_layer(recorder, clip), VELLO_OK);
+        let stroke = VelloStroke {
+            width: 2.0,
+            miter_limit: 10.0,
+            join: VelloJoin::Round,
+            start_cap: VelloCap::Square,
+            end_cap: VelloCap::Square,
+            _padding: [0; 3],
+        };
+        assert_eq!(vello_recorder_set_stroke(recorder, &stroke), VELLO_OK);
+        assert_eq!(vello_recorder_set_paint_solid(recorder, 0, 0, 255, 255), VELLO_OK);
+        assert_eq!(vello_recorder_stroke_rect(recorder, &rect), VELLO_OK);
+        assert_eq!(vello_recorder_pop_layer(recorder), VELLO_OK);
+    }
+
+    #[test]
+    fn recordings_export_their_paints_transforms_and_layers() {
+        let clip = vello_bezpath_new();
+        assert_eq!(vello_bezpath_move_to(clip, 0.0, 0.0), VELLO_OK);
+        assert_eq!(vello_bezpath_line_to(clip, 2.0, 0.0), VELLO_OK);
+        assert_eq!(vello_bezpath_line_to(clip, 2.0, 2.0), VELLO_OK);
+        assert_eq!(vello_bezpath_close(clip), VELLO_OK);
+        let recording = vello_recording_new();
+        let ctx = vello_render_context_new(16, 8);
+        let result = vello_render_context_record(ctx, recording, record_scene, clip as *mut c_void);
+        assert_eq!(result, VELLO_OK);
+
+        let mut out = std::ptr::null_mut();
+        assert_eq!(vello_recording_to_svg(recording, 16, 8, &mut out), VELLO_OK);
+        let svg = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_owned();
+        vello_recording_svg_free(out);
+
+        let header = r#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="8""#;
+        assert!(svg.starts_with(header), "{svg}");
+        assert!(svg.ends_with("</svg>\n"));
+        let mut paths = svg.lines().filter(|line| line.starts_with("<path"));
+        let fill = paths.next().unwrap();
+        assert!(fill.contains(r#" transform="matrix(1 0 0 1 2 3)""#), "{fill}");
+        assert!(fill.contains(r##"fill="#ff0000" fill-opacity="0.50"##), "{fill}");
+        assert!(fill.contains(r#"fill-rule="evenodd""#), "{fill}");
+        let stroke = paths.next().unwrap();
+        assert!(stroke.contains(r##"fill="none" stroke="#0000ff" stroke-width="2""##), "{stroke}");
+        assert!(stroke.contains(r#"stroke-linejoin="round" stroke-linecap="square""#));
+        assert!(stroke.contains(r#"stroke-miterlimit="10""#), "{stroke}");
+        assert!(paths.next().is_none());
+
+        // The clip is defined with the transform current when it was pushed
+        let defs = r#"<defs><clipPath id="p1"><path transform="matrix(1 0 0 1 2 3)""#;
+        assert!(svg.contains(defs), "{svg}");
+        assert!(svg.contains("<g clip-path=\"url(#p1)\">\n"), "{svg}");
+        assert_eq!(svg.matches("</g>").count(), 1);
+
+        let result = vello_recording_to_svg(recording, 16, 8, std::ptr::null_mut());
+        assert_eq!(result, VELLO_ERROR_NULL_POINTER);
+        vello_render_context_free(ctx);
+        vello_recording_free(recording);
+        vello_bezpath_free(clip);
+    }
+
+    #[test]
+    fn helpers_format_colors_blend_modes_and_base64() {
+        let opaque = color_attrs("fill", AlphaColor::from_rgba8(1, 2, 255, 255));
+        assert_eq!(opaque, r##"fill="#0102ff""##);
+        let translucent = color_attrs("stroke", AlphaColor::new([0.0, 0.0, 0.0, 0.25]));
+        assert_eq!(translucent, r##"stroke="#000000" stroke-opacity="0.25""##);
+        assert_eq!(blend_mode_name(Mix::Normal), None);
+        assert_eq!(blend_mode_name(Mix::ColorDodge), Some("color-dodge"));
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! System font discovery FFI bindings (`system-fonts` feature)
+//!
+//! Fonts are discovered with fontdb, which scans the platform font directories
+//! (fontconfig paths on Linux, the Fonts folders on Windows and macOS). The scan
+//! runs once on first use and is shared by all threads.
+
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::sync::OnceLock;
+
+use vello_cpu::peniko::{Blob, FontData};
+
+use crate::error::set_last_error;
+use crate::handle;
+use crate::text::{FontHandle, VelloFontData};
+use crate::types::*;
+use crate::{ffi_catch, ffi_catch_ptr};
+
+/// Font style for system font queries
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloFontStyle {
+    Normal = 0,
+    Italic = 1,
+    Oblique = 2,
+}
+
+impl From<VelloFontStyle> for fontdb::Style {
+    fn from(style: VelloFontStyle) -> Self {
+        match style {
+            VelloFontStyle::Normal => fontdb::Style::Normal,
+            VelloFontStyle::Italic => fontdb::Style::Italic,
+            VelloFontStyle::Oblique => fontdb::Style::Oblique,
+        }
+    }
+}
+
+impl From<fontdb::Style> for VelloFontStyle {
+    fn from(style: fontdb::Style) -> Self {
+        match style {
+            fontdb::Style::Normal => VelloFontStyle::Normal,
+            fontdb::Style::Italic => VelloFontStyle::Italic,
+            fontdb::Style::Oblique => VelloFontStyle::Oblique,
+        }
+    }
+}
+
+/// Called once per system font face; return false to stop the enumeration
+///
+/// The strings are only valid for the duration of the call.
+pub type VelloFontEnumCallback = extern "C" fn(
+    family: *const c_char,
+    postscript_name: *const c_char,
+    weight: u16,
+    style: VelloFontStyle,
+    user_data: *mut c_void,
+) -> bool;
+
+fn database() -> &'static fontdb::Database {
+    static DATABASE: OnceLock<fontdb::Database> = OnceLock::new();
+    DATABASE.get_or_init(|| {
+        let mut db = fontdb::Database::new();
+        db.load_system_fonts();
+        db
+    })
+}
+
+/// Enumerate the font faces installed on the system
+#[no_mangle]
+pub extern "C" fn vello_font_enumerate_system(
+    callback: Option<VelloFontEnumCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    let Some(callback) = callback else {
+        set_last_error("Null callback");
+        return VELLO_ERROR_NULL_POINTER;
+    };
+
+    ffi_catch!({
+        for face in database().faces() {
+            let family = face.families.first().map(|(name, _)| name.as_str()).unwrap_or("");
+            // Names with interior NULs cannot be passed to C; report them empty
+            let family = CString::new(family).unwrap_or_default();
+            let postscript_name = CString::new(face.post_script_name.as_str()).unwrap_or_default();
+            let keep_going = callback(
+                family.as_ptr(),
+                postscript_name.as_ptr(),
+                face.weight.0,
+                face.style.into(),
+                user_data,
+            );
+            if !keep_going {
+                break;
+            }
+        }
+        VELLO_OK
+    })
+}
+
+/// Load the best matching system font (free with `vello_font_data_free`)
+///
+/// `family` is a family name or one of the generic names `serif`,
+/// `sans-serif`, `monospace`, `cursive` and `fantasy`. `weight` follows CSS
+/// (400 regular, 700 bold). Returns null when nothing matches.
+#[no_mangle]
+pub extern "C" fn vello_font_data_new_from_system(
+    family: *const c_char,
+    weight: u16,
+    style: VelloFontStyle,
+) -> *mut VelloFontData {
+    if family.is_null() {
+        set_last_error("Null family name");
+        return std::ptr::null_mut();
+    }
+
+    let Ok(family) = unsafe { CStr::from_ptr(family) }.to_str() else {
+        set_last_error("Invalid UTF-8");
+        return std::ptr::null_mut();
+    };
+
+    ffi_catch_ptr!({
+        let family = match family {
+            "serif" => fontdb::Family::Serif,
+            "sans-serif" => fontdb::Family::SansSerif,
+            "monospace" => fontdb::Family::Monospace,
+            "cursive" => fontdb::Family::Cursive,
+            "fantasy" => fontdb::Family::Fantasy,
+            name => fontdb::Family::Name(name),
+        };
+        let query = fontdb::Query {
+            families: &[family],
+            weight: fontdb::Weight(weight),
+            stretch: fontdb::Stretch::Normal,
+            style: style.into(),
+        };
+
+        let db = database();
+        let Some(id) = db.query(&query) else {
+            set_last_error("No matching system font");
+            return std::ptr::null_mut();
+        };
+        let font_data =
+            db.with_face_data(id, |data, index| FontData::new(Blob::from(data.to_vec()), index));
+        match font_data {
+            Some(font_data) => handle::into_raw(FontHandle::new(font_data)) as *mut VelloFontData,
+            None => {
+                set_last_error("Failed to read system font file");
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
@@ -0,0 +1,71 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Fixtures shared by the unit tests of the FFI modules
+
+use vello_cpu::peniko::{Blob, FontData};
+
+use crate::context::{
+    vello_render_context_fill_rect, vello_render_context_flush,
+    vello_render_context_render_to_buffer, vello_render_context_set_paint_solid,
+};
+use crate::pixmap::vello_pixmap_data_mut;
+use crate::thread_pool::{VelloThreadPriority, VelloWorkerSettings};
+use crate::types::*;
+
+/// Fill a rectangle with a solid, non-premultiplied color
+pub(crate) fn fill(
+    ctx: *mut VelloRenderContext,
+    rgba: [u8; 4],
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+) {
+    let [r, g, b, a] = rgba;
+    assert_eq!(vello_render_context_set_paint_solid(ctx, r, g, b, a), VELLO_OK);
+    let rect = VelloRect { x0, y0, x1, y1 };
+    assert_eq!(vello_render_context_fill_rect(ctx, &rect), VELLO_OK);
+}
+
+/// Flush the context and render it into premultiplied RGBA pixels
+pub(crate) fn render(ctx: *mut VelloRenderContext, width: u16, height: u16) -> Vec<[u8; 4]> {
+    let mut buffer = vec![0u8; width as usize * height as usize * 4];
+    let (data, len) = (buffer.as_mut_ptr(), buffer.len());
+    let mode = VelloRenderMode::OptimizeSpeed;
+    assert_eq!(vello_render_context_flush(ctx), VELLO_OK);
+    let result = vello_render_context_render_to_buffer(ctx, data, len, width, height, mode);
+    assert_eq!(result, VELLO_OK);
+    buffer.chunks_exact(4).map(|px| [px[0], px[1], px[2], px[3]]).collect()
+}
+
+/// Pixels of a pixmap owned by the library; the slice must not outlive it
+pub(crate) fn pixmap_pixels<'a>(pixmap: *mut VelloPixmap) -> &'a mut [VelloPremulRgba8] {
+    let (mut data, mut len) = (std::ptr::null_mut(), 0);
+    assert_eq!(vello_pixmap_data_mut(pixmap, &mut data, &mut len), VELLO_OK);
+    unsafe { std::slice::from_raw_parts_mut(data, len) }
+}
+
+/// Transform moving by `(dx, dy)`
+pub(crate) fn translate(dx: f64, dy: f64) -> VelloAffine {
+    VelloAffine { m11: 1.0, m12: 0.0, m21: 0.0, m22: 1.0, m13: dx, m23: dy }
+}
+
+/// Settings for `num_threads` unpinned workers with default names
+pub(crate) fn worker_settings(num_threads: u16) -> VelloWorkerSettings {
+    VelloWorkerSettings {
+        num_threads,
+        priority: VelloThreadPriority::Normal,
+        _padding: 0,
+        affinity_mask: 0,
+        name_prefix: std::ptr::null(),
+    }
+}
+
+/// Inter Regular, the font the .NET tests use
+pub(crate) const INTER_REGULAR: &[u8] =
+    include_bytes!("../../dotnet/tests/Vello.Tests/TestAssets/fonts/Inter-Regular.ttf");
+
+pub(crate) fn inter() -> FontData {
+    FontData::new(Blob::from(INTER_REGULAR.to_vec()), 0)
+}
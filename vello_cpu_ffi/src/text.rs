@@ -4,7 +4,10 @@
 //! Text rendering FFI functions
 
 use crate::{ffi_catch, ffi_catch_ptr};
+use crate::context::RenderContextHandle;
 use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::path::PathHandle;
 use crate::types::*;
 use std::os::raw::c_int;
 use vello_cpu::peniko::{FontData, Blob};
@@ -12,6 +15,27 @@ use vello_cpu::peniko::{FontData, Blob};
 /// Opaque handle to FontData
 pub type VelloFontData = std::ffi::c_void;
 
+/// The object behind every `VelloFontData` pointer
+#[repr(C)]
+pub struct FontHandle {
+    header: HandleHeader,
+    pub(crate) font: FontData,
+}
+
+impl FontHandle {
+    pub(crate) fn new(font: FontData) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            font,
+        }
+    }
+}
+
+impl TypedHandle for FontHandle {
+    const TAG: u32 = handle::tag(b"VFNT");
+    const NAME: &'static str = "font data";
+}
+
 /// Glyph structure for FFI
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
@@ -24,6 +48,31 @@ pub struct VelloGlyph {
     pub y: f32,
 }
 
+/// Glyph with its own 2x2 transform and optional color, for `vello_render_context_fill_glyphs_ex`
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct VelloGlyphEx {
+    /// Glyph ID (font-specific, not Unicode)
+    pub id: u32,
+    /// X offset in pixels
+    pub x: f32,
+    /// Y offset in pixels
+    pub y: f32,
+    /// Linear part of the per-glyph transform, applied around the glyph origin
+    /// (identity is 1, 0, 0, 1)
+    pub xx: f32,
+    pub yx: f32,
+    pub xy: f32,
+    pub yy: f32,
+    /// Glyph color (non-premultiplied RGBA), used when `use_color` is set
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+    /// Draw with the glyph color instead of the current paint
+    pub use_color: bool,
+}
+
 /// Create FontData from font file bytes
 #[no_mangle]
 pub extern "C" fn vello_font_data_new(
@@ -41,21 +90,23 @@ pub extern "C" fn vello_font_data_new(
         let vec = slice.to_vec();
         let blob = Blob::from(vec);
         let font_data = FontData::new(blob, index);
-        Box::into_raw(Box::new(font_data)) as *mut VelloFontData
+        handle::into_raw(FontHandle::new(font_data)) as *mut VelloFontData
     })
 }
 
 /// Free FontData
 #[no_mangle]
 pub extern "C" fn vello_font_data_free(font: *mut VelloFontData) {
-    if !font.is_null() {
-        unsafe {
-            drop(Box::from_raw(font as *mut FontData));
-        }
+    if !font.is_null() && FontHandle::check(font) {
+        handle::free::<FontHandle, _>(font);
     }
 }
 
 /// Fill glyphs with current paint
+///
+/// Color glyphs (COLRv0, COLRv1, CBDT and sbix bitmaps) are drawn with their
+/// intrinsic colors; the current paint is only used for outline glyphs and for
+/// COLR layers that reference the foreground color.
 #[no_mangle]
 pub extern "C" fn vello_render_context_fill_glyphs(
     ctx: *mut VelloRenderContext,
@@ -70,8 +121,8 @@ pub extern "C" fn vello_render_context_fill_glyphs(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut vello_cpu::RenderContext) };
-        let font_data = unsafe { &*(font as *const FontData) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let font_data = &FontHandle::from_ptr(font).font;
         let glyph_slice = if glyph_count > 0 {
             unsafe { std::slice::from_raw_parts(glyphs, glyph_count) }
         } else {
@@ -91,8 +142,10 @@ pub extern "C" fn vello_render_context_fill_glyphs(
             .collect();
 
         // Create glyph run and fill
+        let hint = ctx.glyph_hinting();
         ctx.glyph_run(font_data)
             .font_size(font_size)
+            .hint(hint)
             .fill_glyphs(vello_glyphs.into_iter());
 
         VELLO_OK
@@ -114,8 +167,8 @@ pub extern "C" fn vello_render_context_stroke_glyphs(
     }
 
     ffi_catch!({
-        let ctx = unsafe { &mut *(ctx as *mut vello_cpu::RenderContext) };
-        let font_data = unsafe { &*(font as *const FontData) };
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let font_data = &FontHandle::from_ptr(font).font;
         let glyph_slice = if glyph_count > 0 {
             unsafe { std::slice::from_raw_parts(glyphs, glyph_count) }
         } else {
@@ -135,8 +188,10 @@ pub extern "C" fn vello_render_context_stroke_glyphs(
             .collect();
 
         // Create glyph run and stroke
+        let hint = ctx.glyph_hinting();
         ctx.glyph_run(font_data)
             .font_size(font_size)
+            .hint(hint)
             .stroke_glyphs(vello_glyphs.into_iter());
 
         VELLO_OK
@@ -158,7 +213,11 @@ pub extern "C" fn vello_font_data_text_to_glyphs(
         return VELLO_ERROR_NULL_POINTER;
     }
 
-    let font_data = unsafe { &*(font as *const FontData) };
+    if !FontHandle::check(font) {
+        return VELLO_ERROR_INVALID_HANDLE;
+    }
+
+    let font_data = &unsafe { &*(font as *const FontHandle) }.font;
     let c_str = unsafe { std::ffi::CStr::from_ptr(text) };
 
     let text_str = match c_str.to_str() {
@@ -211,3 +270,1148 @@ pub extern "C" fn vello_font_data_text_to_glyphs(
     unsafe { *out_count = count };
     VELLO_OK
 }
+
+/// Outline pen collecting a glyph outline into a y-down BezPath
+#[derive(Default)]
+pub(crate) struct BezPathPen {
+    pub(crate) path: vello_cpu::kurbo::BezPath,
+}
+
+impl skrifa::outline::OutlinePen for BezPathPen {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path.move_to((x as f64, -y as f64));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path.line_to((x as f64, -y as f64));
+    }
+
+    fn quad_to(&mut self, cx0: f32, cy0: f32, x: f32, y: f32) {
+        self.path.quad_to((cx0 as f64, -cy0 as f64), (x as f64, -y as f64));
+    }
+
+    fn curve_to(&mut self, cx0: f32, cy0: f32, cx1: f32, cy1: f32, x: f32, y: f32) {
+        self.path.curve_to(
+            (cx0 as f64, -cy0 as f64),
+            (cx1 as f64, -cy1 as f64),
+            (x as f64, -y as f64),
+        );
+    }
+
+    fn close(&mut self) {
+        self.path.close_path();
+    }
+}
+
+/// Draw the outline of a glyph in pixels, with the origin on the baseline and y down
+pub(crate) fn glyph_outline(
+    font_data: &FontData,
+    glyph_id: u32,
+    font_size: f32,
+) -> Result<vello_cpu::kurbo::BezPath, &'static str> {
+    use skrifa::instance::{LocationRef, Size};
+    use skrifa::outline::DrawSettings;
+    use skrifa::{FontRef, GlyphId, MetadataProvider};
+
+    let font_ref = FontRef::from_index(font_data.data.as_ref(), font_data.index)
+        .map_err(|_| "Invalid font data")?;
+    let glyph = font_ref
+        .outline_glyphs()
+        .get(GlyphId::new(glyph_id))
+        .ok_or("Glyph has no outline")?;
+
+    let mut pen = BezPathPen::default();
+    glyph
+        .draw(
+            DrawSettings::unhinted(Size::new(font_size), LocationRef::default()),
+            &mut pen,
+        )
+        .map_err(|_| "Failed to draw glyph outline")?;
+    Ok(pen.path)
+}
+
+/// Get the outline of a glyph as a new BezPath (free with `vello_bezpath_free`)
+///
+/// Coordinates are in pixels with the origin on the baseline and y down, so the
+/// path lines up with `vello_render_context_fill_glyphs` at the same position.
+#[no_mangle]
+pub extern "C" fn vello_font_data_glyph_outline(
+    font: *const VelloFontData,
+    glyph_id: u32,
+    font_size: f32,
+    out_path: *mut *mut VelloBezPath,
+) -> c_int {
+    if font.is_null() || out_path.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let font_data = &FontHandle::from_ptr(font).font;
+        match glyph_outline(font_data, glyph_id, font_size) {
+            Ok(path) => {
+                unsafe { *out_path = handle::into_raw(PathHandle::new(path)) as *mut VelloBezPath };
+                VELLO_OK
+            }
+            Err(e) => {
+                set_last_error(e);
+                VELLO_ERROR_INVALID_PARAMETER
+            }
+        }
+    })
+}
+
+/// Get advances and bounding boxes for a batch of glyphs in one call
+///
+/// Either output may be null; each non-null output must hold `count` entries.
+/// Bounds are in pixels relative to the glyph origin with y down, and empty
+/// for glyphs without an outline.
+#[no_mangle]
+pub extern "C" fn vello_font_data_glyph_metrics(
+    font: *const VelloFontData,
+    glyph_ids: *const u32,
+    count: usize,
+    font_size: f32,
+    out_advances: *mut f32,
+    out_bounds: *mut VelloRect,
+) -> c_int {
+    if font.is_null() || (count > 0 && glyph_ids.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use skrifa::instance::{LocationRef, Size};
+        use skrifa::{GlyphId, MetadataProvider};
+
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Some(font_ref) = crate::shaping::font_ref(font_data) else {
+            set_last_error("Invalid font data");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        if count == 0 {
+            return VELLO_OK;
+        }
+
+        let ids = unsafe { std::slice::from_raw_parts(glyph_ids, count) };
+        let metrics = font_ref.glyph_metrics(Size::new(font_size), LocationRef::default());
+        if !out_advances.is_null() {
+            let out = unsafe { std::slice::from_raw_parts_mut(out_advances, count) };
+            for (advance, id) in out.iter_mut().zip(ids) {
+                *advance = metrics.advance_width(GlyphId::new(*id)).unwrap_or(0.0);
+            }
+        }
+        if !out_bounds.is_null() {
+            let out = unsafe { std::slice::from_raw_parts_mut(out_bounds, count) };
+            for (bounds, id) in out.iter_mut().zip(ids) {
+                *bounds = match metrics.bounds(GlyphId::new(*id)) {
+                    // Font bounds are y up
+                    Some(b) => VelloRect {
+                        x0: b.x_min as f64,
+                        y0: -b.y_max as f64,
+                        x1: b.x_max as f64,
+                        y1: -b.y_min as f64,
+                    },
+                    None => VelloRect { x0: 0.0, y0: 0.0, x1: 0.0, y1: 0.0 },
+                };
+            }
+        }
+        VELLO_OK
+    })
+}
+
+/// Get horizontal advances in pixels for a batch of glyphs
+/// `out_advances` must hold `count` floats
+#[no_mangle]
+pub extern "C" fn vello_font_data_glyph_advances(
+    font: *const VelloFontData,
+    glyph_ids: *const u32,
+    count: usize,
+    font_size: f32,
+    out_advances: *mut f32,
+) -> c_int {
+    if out_advances.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    vello_font_data_glyph_metrics(font, glyph_ids, count, font_size, out_advances, std::ptr::null_mut())
+}
+
+/// Measure a line of UTF-8 text
+///
+/// The width is the sum of the glyph advances (shaped when the `shaping`
+/// feature is enabled) and the height is the font's line height: ascent plus
+/// descent plus line gap.
+#[no_mangle]
+pub extern "C" fn vello_font_data_measure_text(
+    font: *const VelloFontData,
+    text: *const std::os::raw::c_char,
+    font_size: f32,
+    out_width: *mut f32,
+    out_height: *mut f32,
+) -> c_int {
+    if font.is_null() || text.is_null() || out_width.is_null() || out_height.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use crate::shaping::{shape_text, VelloTextDirection};
+        use skrifa::instance::{LocationRef, Size};
+        use skrifa::MetadataProvider;
+
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Ok(text) = unsafe { std::ffi::CStr::from_ptr(text) }.to_str() else {
+            set_last_error("Invalid UTF-8");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        let Some(font_ref) = crate::shaping::font_ref(font_data) else {
+            set_last_error("Invalid font data");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+
+        let glyphs = match shape_text(font_data, text, font_size, VelloTextDirection::Auto, None, &[]) {
+            Ok(glyphs) => glyphs,
+            Err(e) => {
+                set_last_error(e);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+        let metrics = font_ref.metrics(Size::new(font_size), LocationRef::default());
+        unsafe {
+            *out_width = glyphs.iter().map(|g| g.x_advance).sum();
+            *out_height = metrics.ascent - metrics.descent + metrics.leading;
+        }
+        VELLO_OK
+    })
+}
+
+/// How a glyph is drawn by `vello_render_context_fill_glyphs`
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloGlyphKind {
+    /// The font has no data for the glyph
+    Empty = 0,
+    /// Monochrome outline filled with the current paint
+    Outline = 1,
+    /// Layered color glyph from the COLR table
+    Colr = 2,
+    /// Embedded color bitmap (CBDT or sbix)
+    Bitmap = 3,
+}
+
+fn glyph_kind(font_ref: &skrifa::FontRef<'_>, glyph_id: u32) -> VelloGlyphKind {
+    use skrifa::bitmap::BitmapStrikes;
+    use skrifa::instance::Size;
+    use skrifa::{GlyphId, MetadataProvider};
+
+    // Same precedence as the glyph pipeline: COLR, then bitmaps, then outlines
+    let id = GlyphId::new(glyph_id);
+    if font_ref.color_glyphs().get(id).is_some() {
+        VelloGlyphKind::Colr
+    } else if BitmapStrikes::new(font_ref).glyph_for_size(Size::unscaled(), id).is_some() {
+        VelloGlyphKind::Bitmap
+    } else if font_ref.outline_glyphs().get(id).is_some() {
+        VelloGlyphKind::Outline
+    } else {
+        VelloGlyphKind::Empty
+    }
+}
+
+/// Get how a glyph will be drawn, so hosts can tell color glyphs apart
+#[no_mangle]
+pub extern "C" fn vello_font_data_glyph_kind(
+    font: *const VelloFontData,
+    glyph_id: u32,
+    out_kind: *mut VelloGlyphKind,
+) -> c_int {
+    if font.is_null() || out_kind.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Some(font_ref) = crate::shaping::font_ref(font_data) else {
+            set_last_error("Invalid font data");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        unsafe { *out_kind = glyph_kind(&font_ref, glyph_id) };
+        VELLO_OK
+    })
+}
+
+/// Get the number of font faces in font file data (1 for a plain font,
+/// the face count for a TrueType/OpenType collection, 0 if unrecognized)
+#[no_mangle]
+pub extern "C" fn vello_font_data_face_count(data: *const u8, len: usize) -> u32 {
+    if data.is_null() || len == 0 {
+        return 0;
+    }
+
+    let data = unsafe { std::slice::from_raw_parts(data, len) };
+    match skrifa::raw::FileRef::new(data) {
+        Ok(skrifa::raw::FileRef::Font(_)) => 1,
+        Ok(skrifa::raw::FileRef::Collection(collection)) => collection.len(),
+        Err(_) => 0,
+    }
+}
+
+/// English (or first available) entry of an OpenType `name` table record
+pub(crate) fn font_name(font_ref: &skrifa::FontRef<'_>, name_id: u16) -> Option<String> {
+    use skrifa::{MetadataProvider, string::StringId};
+
+    font_ref
+        .localized_strings(StringId::new(name_id))
+        .english_or_first()
+        .map(|name| name.to_string())
+}
+
+/// Copy `value` into a caller buffer as a NUL-terminated string, truncating if needed;
+/// `out_len` receives the full length in bytes excluding the terminator
+pub(crate) fn write_c_string(
+    value: &str,
+    buffer: *mut std::os::raw::c_char,
+    buffer_len: usize,
+    out_len: *mut usize,
+) {
+    if !out_len.is_null() {
+        unsafe { *out_len = value.len() };
+    }
+    if buffer.is_null() || buffer_len == 0 {
+        return;
+    }
+    let count = value.len().min(buffer_len - 1);
+    unsafe {
+        std::ptr::copy_nonoverlapping(value.as_ptr(), buffer as *mut u8, count);
+        *buffer.add(count) = 0;
+    }
+}
+
+/// Read a name table entry (e.g. 1 = family, 2 = subfamily, 4 = full name,
+/// 6 = PostScript name) of face `index` in font file data
+///
+/// The name is written to `buffer` as NUL-terminated UTF-8, truncated to fit;
+/// `out_len` receives the full length so callers can size the buffer.
+#[no_mangle]
+pub extern "C" fn vello_font_face_name(
+    data: *const u8,
+    len: usize,
+    index: u32,
+    name_id: u16,
+    buffer: *mut std::os::raw::c_char,
+    buffer_len: usize,
+    out_len: *mut usize,
+) -> c_int {
+    if data.is_null() || len == 0 || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let data = unsafe { std::slice::from_raw_parts(data, len) };
+        let Ok(font_ref) = skrifa::FontRef::from_index(data, index) else {
+            set_last_error("Invalid font data or face index");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        let Some(name) = font_name(&font_ref, name_id) else {
+            set_last_error("Name not present in font");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        write_c_string(&name, buffer, buffer_len, out_len);
+        VELLO_OK
+    })
+}
+
+/// Arc-length parameterization of a flattened path
+struct PathWalker {
+    /// Segment start, end and cumulative length at the segment start
+    segments: Vec<(vello_cpu::kurbo::Point, vello_cpu::kurbo::Point, f64)>,
+    length: f64,
+}
+
+impl PathWalker {
+    fn new(path: &vello_cpu::kurbo::BezPath) -> Self {
+        use vello_cpu::kurbo::{PathEl, Point};
+
+        let mut segments = Vec::new();
+        let mut length = 0.0;
+        let (mut start, mut last) = (Point::ZERO, Point::ZERO);
+        vello_cpu::kurbo::flatten(path, 0.1, |el| {
+            let to = match el {
+                PathEl::MoveTo(p) => {
+                    start = p;
+                    last = p;
+                    return;
+                }
+                PathEl::LineTo(p) => p,
+                PathEl::ClosePath => start,
+                // flatten only emits lines
+                _ => return,
+            };
+            let len = last.distance(to);
+            if len > 0.0 {
+                segments.push((last, to, length));
+                length += len;
+            }
+            last = to;
+        });
+        Self { segments, length }
+    }
+
+    /// Point and tangent angle at arc length `s`, if it lies on the path
+    fn at(&self, s: f64) -> Option<(vello_cpu::kurbo::Point, f64)> {
+        if s < 0.0 || s > self.length || self.segments.is_empty() {
+            return None;
+        }
+        let i = self.segments.partition_point(|seg| seg.2 <= s).saturating_sub(1);
+        let (p0, p1, offset) = self.segments[i];
+        let len = p0.distance(p1);
+        let t = ((s - offset) / len).clamp(0.0, 1.0);
+        let d = p1 - p0;
+        Some((p0.lerp(p1, t), d.y.atan2(d.x)))
+    }
+}
+
+/// Fill UTF-8 text along a path with the current paint
+///
+/// Glyphs are shaped, then each one is centered on the path at its position
+/// and rotated to the path tangent, as in SVG `<textPath>`. `start_offset`
+/// shifts the text along the path and `spacing` is added after every glyph.
+/// Glyphs falling off either end of the path are not drawn. Glyphs are drawn
+/// from their outlines, so color glyphs use the current paint.
+#[no_mangle]
+pub extern "C" fn vello_render_context_fill_text_on_path(
+    ctx: *mut VelloRenderContext,
+    font: *const VelloFontData,
+    font_size: f32,
+    text: *const std::os::raw::c_char,
+    path: *const VelloBezPath,
+    start_offset: f32,
+    spacing: f32,
+) -> c_int {
+    if ctx.is_null() || font.is_null() || text.is_null() || path.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use crate::shaping::{shape_text, VelloTextDirection};
+        use vello_cpu::kurbo::{Affine, BezPath};
+
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let font_data = &FontHandle::from_ptr(font).font;
+        let path = &PathHandle::from_ptr(path).path;
+        let Ok(text) = unsafe { std::ffi::CStr::from_ptr(text) }.to_str() else {
+            set_last_error("Invalid UTF-8");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+
+        let glyphs = match shape_text(font_data, text, font_size, VelloTextDirection::Auto, None, &[]) {
+            Ok(glyphs) => glyphs,
+            Err(e) => {
+                set_last_error(e);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        let walker = PathWalker::new(path);
+        for (i, glyph) in glyphs.iter().enumerate() {
+            let half = glyph.x_advance as f64 / 2.0;
+            let mid = start_offset as f64 + glyph.x as f64 + i as f64 * spacing as f64 + half;
+            let Some((point, angle)) = walker.at(mid) else {
+                continue;
+            };
+            let outline = match ctx.glyph_cache.outline(font_data, glyph.id, font_size) {
+                Ok(outline) => outline,
+                // Glyphs without outlines (spaces) only advance
+                Err(_) => continue,
+            };
+            let placement = Affine::translate(point.to_vec2())
+                * Affine::rotate(angle)
+                * Affine::translate((-half, glyph.y as f64));
+            ctx.fill_path(&(placement * outline.as_ref().clone()));
+        }
+        VELLO_OK
+    })
+}
+
+/// Capacity of the name fields of `VelloFontInfo`, including the terminator
+pub const VELLO_FONT_NAME_CAPACITY: usize = 128;
+
+/// Font face information
+///
+/// Names are NUL-terminated UTF-8, truncated to fit the field.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct VelloFontInfo {
+    pub family_name: [std::os::raw::c_char; VELLO_FONT_NAME_CAPACITY],
+    pub subfamily_name: [std::os::raw::c_char; VELLO_FONT_NAME_CAPACITY],
+    pub postscript_name: [std::os::raw::c_char; VELLO_FONT_NAME_CAPACITY],
+    pub glyph_count: u32,
+    pub units_per_em: u16,
+    /// Whether the font has variation axes
+    pub is_variable: bool,
+    /// Whether the font has COLR or embedded bitmap color glyphs
+    pub is_color: bool,
+}
+
+fn copy_name(field: &mut [std::os::raw::c_char; VELLO_FONT_NAME_CAPACITY], value: Option<String>) {
+    let value = value.unwrap_or_default();
+    // Truncate on a character boundary so the result stays valid UTF-8
+    let mut len = value.len().min(VELLO_FONT_NAME_CAPACITY - 1);
+    while !value.is_char_boundary(len) {
+        len -= 1;
+    }
+    for (dst, src) in field.iter_mut().zip(&value.as_bytes()[..len]) {
+        *dst = *src as std::os::raw::c_char;
+    }
+    field[len] = 0;
+}
+
+/// Query names and basic properties of a font
+#[no_mangle]
+pub extern "C" fn vello_font_data_info(
+    font: *const VelloFontData,
+    out_info: *mut VelloFontInfo,
+) -> c_int {
+    if font.is_null() || out_info.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use skrifa::raw::TableProvider;
+        use skrifa::string::StringId;
+        use skrifa::MetadataProvider;
+
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Some(font_ref) = crate::shaping::font_ref(font_data) else {
+            set_last_error("Invalid font data");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+
+        let mut info = VelloFontInfo {
+            family_name: [0; VELLO_FONT_NAME_CAPACITY],
+            subfamily_name: [0; VELLO_FONT_NAME_CAPACITY],
+            postscript_name: [0; VELLO_FONT_NAME_CAPACITY],
+            glyph_count: font_ref.maxp().map(|maxp| maxp.num_glyphs() as u32).unwrap_or(0),
+            units_per_em: font_ref.head().map(|head| head.units_per_em()).unwrap_or(0),
+            is_variable: !font_ref.axes().is_empty(),
+            is_color: font_ref.colr().is_ok()
+                || font_ref.cbdt().is_ok()
+                || font_ref.sbix().is_ok(),
+        };
+        // Prefer the typographic family names when present
+        let family = font_name(&font_ref, StringId::TYPOGRAPHIC_FAMILY_NAME.to_u16())
+            .or_else(|| font_name(&font_ref, StringId::FAMILY_NAME.to_u16()));
+        let subfamily = font_name(&font_ref, StringId::TYPOGRAPHIC_SUBFAMILY_NAME.to_u16())
+            .or_else(|| font_name(&font_ref, StringId::SUBFAMILY_NAME.to_u16()));
+        copy_name(&mut info.family_name, family);
+        copy_name(&mut info.subfamily_name, subfamily);
+        copy_name(&mut info.postscript_name, font_name(&font_ref, StringId::POSTSCRIPT_NAME.to_u16()));
+
+        unsafe { *out_info = info };
+        VELLO_OK
+    })
+}
+
+/// Text decoration line
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloTextDecoration {
+    Underline = 0,
+    Strikethrough = 1,
+    Overline = 2,
+}
+
+/// Fill a text decoration line with the current paint
+///
+/// `x`, `y` is the start of the text on its baseline and `width` the length of
+/// the line. Position and thickness come from the font's `post` and `OS/2`
+/// metrics, with conventional fallbacks for fonts lacking them.
+#[no_mangle]
+pub extern "C" fn vello_render_context_draw_text_decoration(
+    ctx: *mut VelloRenderContext,
+    font: *const VelloFontData,
+    font_size: f32,
+    x: f64,
+    y: f64,
+    width: f64,
+    kind: VelloTextDecoration,
+) -> c_int {
+    if ctx.is_null() || font.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use skrifa::instance::{LocationRef, Size};
+        use skrifa::MetadataProvider;
+
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Some(font_ref) = crate::shaping::font_ref(font_data) else {
+            set_last_error("Invalid font data");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+
+        let metrics = font_ref.metrics(Size::new(font_size), LocationRef::default());
+        let fallback_thickness = font_size / 14.0;
+        let underline_thickness = metrics
+            .underline
+            .map(|d| d.thickness)
+            .filter(|t| *t > 0.0)
+            .unwrap_or(fallback_thickness);
+        // Offsets are to the top of the line, y up
+        let (top, thickness) = match kind {
+            VelloTextDecoration::Underline => (
+                metrics.underline.map(|d| d.offset).unwrap_or(-font_size * 0.1),
+                underline_thickness,
+            ),
+            VelloTextDecoration::Strikethrough => match metrics.strikeout {
+                Some(d) if d.thickness > 0.0 => (d.offset, d.thickness),
+                _ => {
+                    let x_height = metrics.x_height.unwrap_or(font_size * 0.5);
+                    (x_height / 2.0 + fallback_thickness / 2.0, fallback_thickness)
+                }
+            },
+            VelloTextDecoration::Overline => (metrics.ascent, underline_thickness),
+        };
+
+        let y0 = y - top as f64;
+        let rect = vello_cpu::kurbo::Rect::new(x, y0, x + width, y0 + thickness as f64);
+        ctx.fill_rect(&rect);
+        VELLO_OK
+    })
+}
+
+/// Fill glyphs that each carry a 2x2 transform and optional color
+///
+/// Consecutive glyphs sharing a transform and color are drawn as one run. The
+/// current paint is used for glyphs without `use_color` and is left unchanged.
+#[no_mangle]
+pub extern "C" fn vello_render_context_fill_glyphs_ex(
+    ctx: *mut VelloRenderContext,
+    font: *const VelloFontData,
+    font_size: f32,
+    glyphs: *const VelloGlyphEx,
+    glyph_count: usize,
+) -> c_int {
+    if ctx.is_null() || font.is_null() || (glyph_count > 0 && glyphs.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use vello_cpu::kurbo::Affine;
+        use vello_cpu::peniko::color::{AlphaColor, Srgb};
+        use vello_cpu::Glyph;
+
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let font_data = &FontHandle::from_ptr(font).font;
+        if glyph_count == 0 {
+            return VELLO_OK;
+        }
+        let glyph_slice = unsafe { std::slice::from_raw_parts(glyphs, glyph_count) };
+
+        let style = |g: &VelloGlyphEx| {
+            (
+                [g.xx, g.yx, g.xy, g.yy].map(f32::to_bits),
+                g.use_color.then_some([g.r, g.g, g.b, g.a]),
+            )
+        };
+
+        let saved_paint = ctx.paint().clone();
+        let hint = ctx.glyph_hinting();
+        for run in glyph_slice.chunk_by(|a, b| style(a) == style(b)) {
+            let first = &run[0];
+            match style(first).1 {
+                Some([r, g, b, a]) => ctx.set_paint(AlphaColor::<Srgb>::from_rgba8(r, g, b, a)),
+                None => ctx.set_paint(saved_paint.clone()),
+            }
+            let glyph_transform = Affine::new([
+                first.xx as f64,
+                first.yx as f64,
+                first.xy as f64,
+                first.yy as f64,
+                0.0,
+                0.0,
+            ]);
+            ctx.glyph_run(font_data)
+                .font_size(font_size)
+                .hint(hint)
+                .glyph_transform(glyph_transform)
+                .fill_glyphs(run.iter().map(|g| Glyph { id: g.id, x: g.x, y: g.y }));
+        }
+        ctx.set_paint(saved_paint);
+        VELLO_OK
+    })
+}
+
+/// Shape and fill a line of UTF-8 text with the current paint in one call
+///
+/// `x`, `y` is the start of the baseline. Text is shaped when the `shaping`
+/// feature is enabled and mapped through the font's cmap otherwise; mixed
+/// direction text is ordered with the Unicode bidi algorithm either way.
+#[no_mangle]
+pub extern "C" fn vello_render_context_draw_text(
+    ctx: *mut VelloRenderContext,
+    font: *const VelloFontData,
+    font_size: f32,
+    x: f32,
+    y: f32,
+    text: *const std::os::raw::c_char,
+) -> c_int {
+    if ctx.is_null() || font.is_null() || text.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use crate::shaping::{shape_text, VelloTextDirection};
+        use vello_cpu::Glyph;
+
+        let ctx = RenderContextHandle::from_ptr_mut(ctx);
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Ok(text) = unsafe { std::ffi::CStr::from_ptr(text) }.to_str() else {
+            set_last_error("Invalid UTF-8");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+
+        let glyphs = match shape_text(font_data, text, font_size, VelloTextDirection::Auto, None, &[]) {
+            Ok(glyphs) => glyphs,
+            Err(e) => {
+                set_last_error(e);
+                return VELLO_ERROR_INVALID_PARAMETER;
+            }
+        };
+
+        let hint = ctx.glyph_hinting();
+        ctx.glyph_run(font_data)
+            .font_size(font_size)
+            .hint(hint)
+            .fill_glyphs(glyphs.iter().map(|g| Glyph { id: g.id, x: x + g.x, y: y + g.y }));
+        VELLO_OK
+    })
+}
+
+/// Opaque glyph run builder bound to a render context and font
+///
+/// The context must outlive the builder. Options persist across fills, so one
+/// builder can draw many runs.
+#[repr(C)]
+pub struct VelloGlyphRun {
+    header: HandleHeader,
+    ctx: *mut RenderContextHandle,
+    font: FontData,
+    font_size: f32,
+    hint: bool,
+    normalized_coords: Vec<skrifa::instance::NormalizedCoord>,
+    glyph_transform: vello_cpu::kurbo::Affine,
+    transform: vello_cpu::kurbo::Affine,
+}
+
+impl TypedHandle for VelloGlyphRun {
+    const TAG: u32 = handle::tag(b"VGRN");
+    const NAME: &'static str = "glyph run";
+}
+
+impl VelloGlyphRun {
+    fn draw(&self, glyphs: &[VelloGlyph], stroke: bool) {
+        use vello_cpu::Glyph;
+
+        let ctx = RenderContextHandle::from_ptr_mut(self.ctx);
+        let saved_transform = *ctx.transform();
+        ctx.set_transform(saved_transform * self.transform);
+        let builder = ctx
+            .glyph_run(&self.font)
+            .font_size(self.font_size)
+            .hint(self.hint)
+            .normalized_coords(&self.normalized_coords)
+            .glyph_transform(self.glyph_transform);
+        let glyphs = glyphs.iter().map(|g| Glyph { id: g.id, x: g.x, y: g.y });
+        if stroke {
+            builder.stroke_glyphs(glyphs);
+        } else {
+            builder.fill_glyphs(glyphs);
+        }
+        ctx.set_transform(saved_transform);
+    }
+}
+
+/// Create a glyph run builder (font size 16, hinting from the context)
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_new(
+    ctx: *mut VelloRenderContext,
+    font: *const VelloFontData,
+) -> *mut VelloGlyphRun {
+    if ctx.is_null() || font.is_null() {
+        set_last_error("Null pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let hint = RenderContextHandle::from_ptr(ctx).glyph_hinting();
+        let font = FontHandle::from_ptr(font).font.clone();
+        handle::into_raw(VelloGlyphRun {
+            header: HandleHeader::new::<VelloGlyphRun>(),
+            ctx: ctx as *mut RenderContextHandle,
+            font,
+            font_size: 16.0,
+            hint,
+            normalized_coords: Vec::new(),
+            glyph_transform: vello_cpu::kurbo::Affine::IDENTITY,
+            transform: vello_cpu::kurbo::Affine::IDENTITY,
+        })
+    })
+}
+
+/// Free a glyph run builder
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_free(run: *mut VelloGlyphRun) {
+    if !run.is_null() && VelloGlyphRun::check(run) {
+        handle::free::<VelloGlyphRun, _>(run);
+    }
+}
+
+/// Set the font size in pixels
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_set_font_size(run: *mut VelloGlyphRun, font_size: f32) -> c_int {
+    if run.is_null() {
+        set_last_error("Null glyph run pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let run = VelloGlyphRun::from_ptr_mut(run);
+        run.font_size = font_size;
+        VELLO_OK
+    })
+}
+
+/// Enable or disable hinting
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_set_hint(run: *mut VelloGlyphRun, hint: bool) -> c_int {
+    if run.is_null() {
+        set_last_error("Null glyph run pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let run = VelloGlyphRun::from_ptr_mut(run);
+        run.hint = hint;
+        VELLO_OK
+    })
+}
+
+/// Set normalized variation coordinates (F2Dot14 values, one per font axis)
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_set_normalized_coords(
+    run: *mut VelloGlyphRun,
+    coords: *const i16,
+    count: usize,
+) -> c_int {
+    if run.is_null() || (count > 0 && coords.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let run = VelloGlyphRun::from_ptr_mut(run);
+        run.normalized_coords = if count > 0 {
+            unsafe { std::slice::from_raw_parts(coords, count) }
+                .iter()
+                .map(|c| skrifa::instance::NormalizedCoord::from_bits(*c))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        VELLO_OK
+    })
+}
+
+/// Set the transform applied to each glyph around its origin (e.g. skew for faux italics)
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_set_glyph_transform(
+    run: *mut VelloGlyphRun,
+    transform: *const VelloAffine,
+) -> c_int {
+    if run.is_null() || transform.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let run = VelloGlyphRun::from_ptr_mut(run);
+        let t = unsafe { &*transform };
+        run.glyph_transform =
+            vello_cpu::kurbo::Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]);
+        VELLO_OK
+    })
+}
+
+/// Set the run transform, applied on top of the context transform when drawing
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_set_transform(
+    run: *mut VelloGlyphRun,
+    transform: *const VelloAffine,
+) -> c_int {
+    if run.is_null() || transform.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let run = VelloGlyphRun::from_ptr_mut(run);
+        let t = unsafe { &*transform };
+        run.transform = vello_cpu::kurbo::Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]);
+        VELLO_OK
+    })
+}
+
+/// Fill glyphs with the builder options and the context's current paint
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_fill(
+    run: *const VelloGlyphRun,
+    glyphs: *const VelloGlyph,
+    glyph_count: usize,
+) -> c_int {
+    if run.is_null() || (glyph_count > 0 && glyphs.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let run = VelloGlyphRun::from_ptr(run);
+        let glyphs = if glyph_count > 0 {
+            unsafe { std::slice::from_raw_parts(glyphs, glyph_count) }
+        } else {
+            &[]
+        };
+        run.draw(glyphs, false);
+        VELLO_OK
+    })
+}
+
+/// Stroke glyphs with the builder options and the context's paint and stroke
+#[no_mangle]
+pub extern "C" fn vello_glyph_run_stroke(
+    run: *const VelloGlyphRun,
+    glyphs: *const VelloGlyph,
+    glyph_count: usize,
+) -> c_int {
+    if run.is_null() || (glyph_count > 0 && glyphs.is_null()) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let run = VelloGlyphRun::from_ptr(run);
+        let glyphs = if glyph_count > 0 {
+            unsafe { std::slice::from_raw_parts(glyphs, glyph_count) }
+        } else {
+            &[]
+        };
+        run.draw(glyphs, true);
+        VELLO_OK
+    })
+}
+
+/// Placement of a rasterized glyph bitmap
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct VelloGlyphBitmapMetrics {
+    /// Bitmap size in pixels (0 for glyphs without an outline)
+    pub width: u16,
+    pub height: u16,
+    /// Offset from the pen position to the left edge of the bitmap
+    pub left: i32,
+    /// Offset from the baseline up to the top edge of the bitmap
+    pub top: i32,
+    /// Horizontal advance in pixels
+    pub advance: f32,
+}
+
+/// Rasterize a glyph outline into an A8 coverage bitmap for glyph atlases
+///
+/// `subpixel_offset` shifts the outline horizontally by a fraction of a pixel
+/// before rasterization. Call with a null `buffer` to query the metrics, then
+/// again with a buffer of at least `width * height` bytes (rows tightly packed).
+#[no_mangle]
+pub extern "C" fn vello_font_rasterize_glyph(
+    font: *const VelloFontData,
+    glyph_id: u32,
+    font_size: f32,
+    subpixel_offset: f32,
+    buffer: *mut u8,
+    buffer_len: usize,
+    out_metrics: *mut VelloGlyphBitmapMetrics,
+) -> c_int {
+    if font.is_null() || out_metrics.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        use skrifa::instance::{LocationRef, Size};
+        use skrifa::{GlyphId, MetadataProvider};
+        use vello_cpu::kurbo::{Affine, Shape};
+        use vello_cpu::peniko::color::palette::css::BLACK;
+        use vello_cpu::{Pixmap, RenderContext};
+
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Some(font_ref) = crate::shaping::font_ref(font_data) else {
+            set_last_error("Invalid font data");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        };
+        let advance = font_ref
+            .glyph_metrics(Size::new(font_size), LocationRef::default())
+            .advance_width(GlyphId::new(glyph_id))
+            .unwrap_or(0.0);
+
+        let outline = match glyph_outline(font_data, glyph_id, font_size) {
+            Ok(outline) => Affine::translate((subpixel_offset as f64, 0.0)) * outline,
+            Err(_) => {
+                unsafe { *out_metrics = VelloGlyphBitmapMetrics { advance, ..Default::default() } };
+                return VELLO_OK;
+            }
+        };
+
+        let bounds = outline.bounding_box();
+        let (x0, y0) = (bounds.x0.floor(), bounds.y0.floor());
+        let (x1, y1) = (bounds.x1.ceil(), bounds.y1.ceil());
+        if !(x1 > x0 && y1 > y0) || x1 - x0 > u16::MAX as f64 || y1 - y0 > u16::MAX as f64 {
+            unsafe { *out_metrics = VelloGlyphBitmapMetrics { advance, ..Default::default() } };
+            return VELLO_OK;
+        }
+
+        let (width, height) = ((x1 - x0) as u16, (y1 - y0) as u16);
+        let metrics = VelloGlyphBitmapMetrics {
+            width,
+            height,
+            left: x0 as i32,
+            top: -y0 as i32,
+            advance,
+        };
+        unsafe { *out_metrics = metrics };
+        if buffer.is_null() {
+            return VELLO_OK;
+        }
+
+        let len = width as usize * height as usize;
+        if buffer_len < len {
+            set_last_error("Buffer too small");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        let mut glyph_ctx = RenderContext::new(width, height);
+        glyph_ctx.set_paint(BLACK);
+        glyph_ctx.fill_path(&(Affine::translate((-x0, -y0)) * outline));
+        glyph_ctx.flush();
+        let mut pixmap = Pixmap::new(width, height);
+        glyph_ctx.render_to_pixmap(&mut pixmap);
+
+        let out = unsafe { std::slice::from_raw_parts_mut(buffer, len) };
+        for (dst, px) in out.iter_mut().zip(pixmap.data()) {
+            *dst = px.a;
+        }
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        vello_render_context_free, vello_render_context_new,
+        vello_render_context_set_glyph_hinting, vello_render_context_set_paint_solid,
+    };
+    use crate::test_util::render;
+
+    /// A 16 units per em TrueType font with a square outline in glyphs 1 and
+    /// 2, where glyph 1 is also a COLRv0 glyph drawing glyph 2 in opaque red
+    fn colr_test_font() -> Vec<u8> {
+        let be16 = |values: &[i32]| -> Vec<u8> {
+            values.iter().flat_map(|v| (*v as u16).to_be_bytes()).collect()
+        };
+        let mut head = be16(&[1, 0, 1, 0, 0, 0, 0x5F0F, 0x3CF5, 0, 16]);
+        head.extend([0; 16]);
+        head.extend(be16(&[0, 0, 16, 16, 0, 8, 2, 0, 0]));
+        let mut maxp = be16(&[1, 0, 3, 4, 1, 0, 0, 2]);
+        maxp.extend([0; 16]);
+        let mut hhea = be16(&[1, 0, 16, 0, 0, 16, 0, 0, 16, 1, 0, 0]);
+        hhea.extend([0; 10]);
+        hhea.extend(be16(&[3]));
+        let hmtx = be16(&[16, 0, 16, 0, 16, 0]);
+        // One contour through (2, 2), (2, 14), (14, 14) and (14, 2)
+        let mut square = be16(&[1, 2, 2, 14, 14, 3, 0]);
+        square.extend([1; 4]);
+        square.extend(be16(&[2, 0, 12, 0, 2, 12, 0, -12]));
+        let glyf = [square.clone(), square].concat();
+        let loca = be16(&[0, 0, 17, 34]);
+        // Base glyph 1 has one layer: glyph 2 with palette entry 0
+        let colr = be16(&[0, 1, 0, 14, 0, 20, 1, 1, 0, 1, 2, 0]);
+        let mut cpal = be16(&[0, 1, 1, 1, 0, 14, 0]);
+        cpal.extend([0, 0, 255, 255]);
+
+        let tables: [(&[u8; 4], Vec<u8>); 8] = [
+            (b"COLR", colr),
+            (b"CPAL", cpal),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ];
+        let mut font = be16(&[1, 0, 8, 128, 3, 0]);
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, data) in &tables {
+            font.extend(*tag);
+            font.extend([0; 4]);
+            font.extend((offset as u32).to_be_bytes());
+            font.extend((data.len() as u32).to_be_bytes());
+            offset += data.len().next_multiple_of(4);
+        }
+        for (_, data) in &tables {
+            font.extend(data);
+            font.resize(font.len().next_multiple_of(4), 0);
+        }
+        font
+    }
+
+    #[test]
+    fn colr_glyphs_fill_with_their_palette_colors() {
+        let data = colr_test_font();
+        let font = vello_font_data_new(data.as_ptr(), data.len(), 0);
+        assert!(!font.is_null());
+        let kind = |id| {
+            let mut kind = VelloGlyphKind::Empty;
+            assert_eq!(vello_font_data_glyph_kind(font, id, &mut kind), VELLO_OK);
+            kind
+        };
+        assert_eq!([kind(1), kind(2)], [VelloGlyphKind::Colr, VelloGlyphKind::Outline]);
+
+        let ctx = vello_render_context_new(32, 16);
+        assert_eq!(vello_render_context_set_glyph_hinting(ctx, false), VELLO_OK);
+        assert_eq!(vello_render_context_set_paint_solid(ctx, 0, 0, 255, 255), VELLO_OK);
+        let glyphs = [
+            VelloGlyph { id: 1, x: 0.0, y: 16.0 },
+            VelloGlyph { id: 2, x: 16.0, y: 16.0 },
+        ];
+        let result = vello_render_context_fill_glyphs(ctx, font, 16.0, glyphs.as_ptr(), 2);
+        assert_eq!(result, VELLO_OK);
+        let pixels = render(ctx, 32, 16);
+
+        // The color glyph ignores the blue paint; the outline glyph uses it
+        let [r, g, b, a] = pixels[8 * 32 + 8];
+        assert!(r >= 250 && g <= 5 && b <= 5 && a >= 250, "{:?}", [r, g, b, a]);
+        assert_eq!(pixels[8 * 32 + 24], [0, 0, 255, 255]);
+        assert_eq!(pixels[0], [0, 0, 0, 0]);
+
+        vello_render_context_free(ctx);
+        vello_font_data_free(font);
+    }
+}
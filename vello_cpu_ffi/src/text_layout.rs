@@ -0,0 +1,472 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Text layout FFI bindings
+//!
+//! A layout is a shaped line of text that keeps its glyphs, directional runs
+//! and caret positions, for editors that need cursor placement and selection.
+//! All text offsets are UTF-8 byte offsets, matching glyph clusters.
+
+use std::os::raw::{c_char, c_int};
+
+use vello_cpu::peniko::FontData;
+
+use crate::error::set_last_error;
+use crate::handle::{self, HandleHeader, TypedHandle};
+use crate::shaping::{shape_line, VelloFontFeature, VelloShapedGlyph, VelloTextDirection, VelloTextRun};
+use crate::text::{FontHandle, VelloFontData};
+use crate::types::*;
+use crate::{ffi_catch, ffi_catch_ptr};
+
+/// Caret position at a character boundary
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VelloCaret {
+    /// UTF-8 byte offset of the boundary
+    pub offset: u32,
+    /// Position along the line in pixels (x for horizontal text, y for vertical)
+    pub position: f32,
+}
+
+/// Opaque handle to a shaped line of text
+#[repr(C)]
+pub struct VelloTextLayout {
+    header: HandleHeader,
+    glyphs: Vec<VelloShapedGlyph>,
+    runs: Vec<VelloTextRun>,
+    /// One caret per character boundary, in logical order
+    carets: Vec<VelloCaret>,
+    advance: f32,
+    vertical: bool,
+}
+
+impl TypedHandle for VelloTextLayout {
+    const TAG: u32 = handle::tag(b"VTLY");
+    const NAME: &'static str = "text layout";
+}
+
+fn is_rtl(runs: &[VelloTextRun], glyph: usize) -> bool {
+    runs.iter()
+        .find(|run| (run.glyph_start..run.glyph_start + run.glyph_count).contains(&(glyph as u32)))
+        .is_some_and(|run| run.direction == VelloTextDirection::RightToLeft)
+}
+
+/// Place a caret at every character boundary
+///
+/// Each cluster spans the pen range of its glyphs; characters inside a cluster
+/// (ligatures) split that range evenly, from its right edge in RTL runs.
+fn caret_positions(
+    text: &str,
+    glyphs: &[VelloShapedGlyph],
+    runs: &[VelloTextRun],
+    vertical: bool,
+) -> Vec<VelloCaret> {
+    let advance = |g: &VelloShapedGlyph| if vertical { g.y_advance } else { g.x_advance };
+
+    // Pen extent and direction of every cluster, keyed by its start offset
+    let mut clusters: Vec<(u32, f32, f32, bool)> = Vec::new();
+    let mut pen = 0.0f32;
+    for (i, glyph) in glyphs.iter().enumerate() {
+        let (start, end) = (pen, pen + advance(glyph));
+        pen = end;
+        match clusters.iter_mut().find(|c| c.0 == glyph.cluster) {
+            Some(cluster) => {
+                cluster.1 = cluster.1.min(start);
+                cluster.2 = cluster.2.max(end);
+            }
+            None => clusters.push((glyph.cluster, start, end, is_rtl(runs, i))),
+        }
+    }
+    clusters.sort_by_key(|c| c.0);
+
+    let mut carets = Vec::with_capacity(text.len() + 1);
+    for (i, &(start, low, high, rtl)) in clusters.iter().enumerate() {
+        let end = clusters.get(i + 1).map_or(text.len(), |next| next.0 as usize);
+        let boundaries: Vec<usize> = text
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .filter(|offset| (start as usize..end).contains(offset))
+            .collect();
+        let count = boundaries.len().max(1) as f32;
+        for (k, offset) in boundaries.iter().enumerate() {
+            let t = k as f32 / count;
+            let position = if rtl { high - (high - low) * t } else { low + (high - low) * t };
+            carets.push(VelloCaret { offset: *offset as u32, position });
+        }
+        if end == text.len() {
+            let position = if rtl { low } else { high };
+            carets.push(VelloCaret { offset: end as u32, position });
+        }
+    }
+    if carets.is_empty() {
+        carets.push(VelloCaret { offset: 0, position: 0.0 });
+    }
+    carets
+}
+
+/// Shape a line of UTF-8 text into a layout (free with `vello_text_layout_free`)
+///
+/// Arguments match `vello_shape_text`; `language` and `features` are optional.
+#[no_mangle]
+pub extern "C" fn vello_text_layout_new(
+    font: *const VelloFontData,
+    text: *const c_char,
+    font_size: f32,
+    direction: VelloTextDirection,
+    language: *const c_char,
+    features: *const VelloFontFeature,
+    feature_count: usize,
+) -> *mut VelloTextLayout {
+    if font.is_null() || text.is_null() || (feature_count > 0 && features.is_null()) {
+        set_last_error("Null pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let font_data = &FontHandle::from_ptr(font).font;
+        let Ok(text) = unsafe { std::ffi::CStr::from_ptr(text) }.to_str() else {
+            set_last_error("Invalid UTF-8");
+            return std::ptr::null_mut();
+        };
+        let language = if language.is_null() {
+            None
+        } else {
+            match unsafe { std::ffi::CStr::from_ptr(language) }.to_str() {
+                Ok(language) => Some(language),
+                Err(_) => {
+                    set_last_error("Invalid UTF-8");
+                    return std::ptr::null_mut();
+                }
+            }
+        };
+        let features = if feature_count > 0 {
+            unsafe { std::slice::from_raw_parts(features, feature_count) }
+        } else {
+            &[]
+        };
+
+        let shaped = match shape_line(font_data, text, font_size, direction, language, features) {
+            Ok(shaped) => shaped,
+            Err(e) => {
+                set_last_error(e);
+                return std::ptr::null_mut();
+            }
+        };
+        let vertical = matches!(
+            direction,
+            VelloTextDirection::TopToBottom | VelloTextDirection::BottomToTop
+        );
+        let carets = caret_positions(text, &shaped.glyphs, &shaped.runs, vertical);
+        let advance = shaped
+            .glyphs
+            .iter()
+            .map(|g| if vertical { g.y_advance } else { g.x_advance })
+            .sum();
+        handle::into_raw(VelloTextLayout {
+            header: HandleHeader::new::<VelloTextLayout>(),
+            glyphs: shaped.glyphs,
+            runs: shaped.runs,
+            carets,
+            advance,
+            vertical,
+        })
+    })
+}
+
+/// Free a text layout
+#[no_mangle]
+pub extern "C" fn vello_text_layout_free(layout: *mut VelloTextLayout) {
+    if !layout.is_null() && VelloTextLayout::check(layout) {
+        handle::free::<VelloTextLayout, _>(layout);
+    }
+}
+
+/// Get the total advance of the line in pixels
+#[no_mangle]
+pub extern "C" fn vello_text_layout_advance(layout: *const VelloTextLayout) -> f32 {
+    if layout.is_null() || !VelloTextLayout::check(layout) {
+        return 0.0;
+    }
+    unsafe { (*layout).advance }
+}
+
+/// Get the shaped glyphs (zero-copy, valid until the layout is freed)
+#[no_mangle]
+pub extern "C" fn vello_text_layout_glyphs(
+    layout: *const VelloTextLayout,
+    out_ptr: *mut *const VelloShapedGlyph,
+    out_len: *mut usize,
+) -> c_int {
+    if layout.is_null() || out_ptr.is_null() || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let layout = VelloTextLayout::from_ptr(layout);
+        unsafe {
+            *out_ptr = layout.glyphs.as_ptr();
+            *out_len = layout.glyphs.len();
+        }
+        VELLO_OK
+    })
+}
+
+/// Get the directional runs (zero-copy, valid until the layout is freed)
+#[no_mangle]
+pub extern "C" fn vello_text_layout_runs(
+    layout: *const VelloTextLayout,
+    out_ptr: *mut *const VelloTextRun,
+    out_len: *mut usize,
+) -> c_int {
+    if layout.is_null() || out_ptr.is_null() || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let layout = VelloTextLayout::from_ptr(layout);
+        unsafe {
+            *out_ptr = layout.runs.as_ptr();
+            *out_len = layout.runs.len();
+        }
+        VELLO_OK
+    })
+}
+
+/// Get the caret positions at every character boundary, in logical order
+/// (zero-copy, valid until the layout is freed)
+#[no_mangle]
+pub extern "C" fn vello_text_layout_carets(
+    layout: *const VelloTextLayout,
+    out_ptr: *mut *const VelloCaret,
+    out_len: *mut usize,
+) -> c_int {
+    if layout.is_null() || out_ptr.is_null() || out_len.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let layout = VelloTextLayout::from_ptr(layout);
+        unsafe {
+            *out_ptr = layout.carets.as_ptr();
+            *out_len = layout.carets.len();
+        }
+        VELLO_OK
+    })
+}
+
+/// Find the character boundary nearest to a point relative to the line origin
+///
+/// `out_offset` receives its UTF-8 byte offset. Only the coordinate along the
+/// line is considered (x for horizontal text, y for vertical text).
+#[no_mangle]
+pub extern "C" fn vello_text_layout_hit_test(
+    layout: *const VelloTextLayout,
+    x: f32,
+    y: f32,
+    out_offset: *mut u32,
+) -> c_int {
+    if layout.is_null() || out_offset.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let layout = VelloTextLayout::from_ptr(layout);
+        let target = if layout.vertical { y } else { x };
+        let nearest = layout
+            .carets
+            .iter()
+            .min_by(|a, b| {
+                (a.position - target).abs().total_cmp(&(b.position - target).abs())
+            })
+            .map_or(0, |caret| caret.offset);
+        unsafe { *out_offset = nearest };
+        VELLO_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+    use crate::test_util::INTER_REGULAR;
+    use crate::text::{vello_font_data_free, vello_font_data_new};
+
+    /// Lay out `text` with Inter at 16 px and pass the layout to `f`
+    fn with_layout(
+        text: &CStr,
+        direction: VelloTextDirection,
+        f: impl FnOnce(*mut VelloTextLayout),
+    ) {
+        let font = vello_font_data_new(INTER_REGULAR.as_ptr(), INTER_REGULAR.len(), 0);
+        let (text, null) = (text.as_ptr(), std::ptr::null());
+        let layout = vello_text_layout_new(font, text, 16.0, direction, null, std::ptr::null(), 0);
+        assert!(!layout.is_null());
+        f(layout);
+        vello_text_layout_free(layout);
+        vello_font_data_free(font);
+    }
+
+    fn glyphs<'a>(layout: *const VelloTextLayout) -> &'a [VelloShapedGlyph] {
+        let (mut ptr, mut len) = (std::ptr::null(), 0);
+        assert_eq!(vello_text_layout_glyphs(layout, &mut ptr, &mut len), VELLO_OK);
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+
+    fn runs<'a>(layout: *const VelloTextLayout) -> &'a [VelloTextRun] {
+        let (mut ptr, mut len) = (std::ptr::null(), 0);
+        assert_eq!(vello_text_layout_runs(layout, &mut ptr, &mut len), VELLO_OK);
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+
+    fn carets<'a>(layout: *const VelloTextLayout) -> &'a [VelloCaret] {
+        let (mut ptr, mut len) = (std::ptr::null(), 0);
+        assert_eq!(vello_text_layout_carets(layout, &mut ptr, &mut len), VELLO_OK);
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+
+    #[test]
+    fn mixed_direction_layouts_place_carets_by_run() {
+        // "ab " then two two-byte Hebrew letters, laid out as a, b, space, bet, alef
+        with_layout(c"ab \u{5d0}\u{5d1}", VelloTextDirection::Auto, |layout| {
+            let ranges: Vec<_> = runs(layout)
+                .iter()
+                .map(|run| (run.glyph_start, run.glyph_count, run.text_start, run.text_end))
+                .collect();
+            assert_eq!(ranges, [(0, 3, 0, 3), (3, 2, 3, 7)]);
+            let directions = runs(layout).iter().map(|run| run.direction);
+            let (ltr, rtl) = (VelloTextDirection::LeftToRight, VelloTextDirection::RightToLeft);
+            assert!(directions.eq([ltr, rtl]));
+
+            let glyphs = glyphs(layout);
+            let clusters: Vec<u32> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+            assert_eq!(clusters, [0, 1, 2, 5, 3]);
+
+            // Pen position before each glyph, and after the last one
+            let pen: Vec<f32> = std::iter::once(0.0)
+                .chain(glyphs.iter().scan(0.0, |pen, glyph| {
+                    *pen += glyph.x_advance;
+                    Some(*pen)
+                }))
+                .collect();
+            // Right-to-left characters start at their right edge and the end of
+            // the text falls between the space and the Hebrew run
+            let expected = [
+                (0, pen[0]),
+                (1, pen[1]),
+                (2, pen[2]),
+                (3, pen[5]),
+                (5, pen[4]),
+                (7, pen[3]),
+            ];
+            let carets: Vec<_> = carets(layout).iter().map(|c| (c.offset, c.position)).collect();
+            assert_eq!(carets.len(), expected.len());
+            for (caret, expected) in carets.iter().zip(expected) {
+                assert_eq!(caret.0, expected.0);
+                assert!((caret.1 - expected.1).abs() < 1e-3, "{caret:?} != {expected:?}");
+            }
+        });
+    }
+
+    #[test]
+    fn ligature_carets_split_the_cluster_advance() {
+        let glyph = |cluster, x_advance| VelloShapedGlyph {
+            cluster,
+            x_advance,
+            ..Default::default()
+        };
+        let run = |glyph_count, text_end, direction| VelloTextRun {
+            glyph_start: 0,
+            glyph_count,
+            text_start: 0,
+            text_end,
+            direction,
+        };
+        let positions = |carets: Vec<VelloCaret>| -> Vec<(u32, f32)> {
+            carets.iter().map(|caret| (caret.offset, caret.position)).collect()
+        };
+
+        // An "fi" ligature followed by "x"
+        let glyphs = [glyph(0, 10.0), glyph(2, 5.0)];
+        let runs = [run(2, 3, VelloTextDirection::LeftToRight)];
+        let carets = caret_positions("fix", &glyphs, &runs, false);
+        assert_eq!(positions(carets), [(0, 0.0), (1, 5.0), (2, 10.0), (3, 15.0)]);
+
+        // Right-to-left clusters are split from their right edge
+        let runs = [run(1, 2, VelloTextDirection::RightToLeft)];
+        let carets = caret_positions("ab", &glyphs[..1], &runs, false);
+        assert_eq!(positions(carets), [(0, 10.0), (1, 5.0), (2, 0.0)]);
+
+        assert_eq!(positions(caret_positions("", &[], &[], false)), [(0, 0.0)]);
+    }
+
+    #[test]
+    fn hit_tests_snap_to_the_nearest_caret() {
+        with_layout(c"Hello", VelloTextDirection::LeftToRight, |layout| {
+            let advance: f32 = glyphs(layout).iter().map(|glyph| glyph.x_advance).sum();
+            assert!(advance > 0.0);
+            assert_eq!(vello_text_layout_advance(layout), advance);
+            let carets = carets(layout);
+            let offsets: Vec<u32> = carets.iter().map(|caret| caret.offset).collect();
+            assert_eq!(offsets, [0, 1, 2, 3, 4, 5]);
+            assert_eq!(carets[5].position, advance);
+
+            let hit = |x, y| {
+                let mut offset = u32::MAX;
+                assert_eq!(vello_text_layout_hit_test(layout, x, y, &mut offset), VELLO_OK);
+                offset
+            };
+            assert_eq!(hit(-5.0, 0.0), 0);
+            assert_eq!(hit(advance + 10.0, 0.0), 5);
+            // Only x counts for horizontal text
+            let near_two = carets[1].position * 0.4 + carets[2].position * 0.6;
+            assert_eq!(hit(near_two, 1000.0), 2);
+        });
+    }
+
+    #[test]
+    fn empty_and_invalid_layouts() {
+        with_layout(c"", VelloTextDirection::Auto, |layout| {
+            assert_eq!(vello_text_layout_advance(layout), 0.0);
+            assert_eq!(carets(layout), [VelloCaret { offset: 0, position: 0.0 }]);
+            let mut offset = u32::MAX;
+            assert_eq!(vello_text_layout_hit_test(layout, 20.0, 0.0, &mut offset), VELLO_OK);
+            assert_eq!(offset, 0);
+            let result = vello_text_layout_hit_test(layout, 0.0, 0.0, std::ptr::null_mut());
+            assert_eq!(result, VELLO_ERROR_NULL_POINTER);
+        });
+
+        let font = vello_font_data_new(INTER_REGULAR.as_ptr(), INTER_REGULAR.len(), 0);
+        let (text, null) = (c"\xff".as_ptr(), std::ptr::null());
+        let direction = VelloTextDirection::Auto;
+        let layout = vello_text_layout_new(font, text, 16.0, direction, null, std::ptr::null(), 0);
+        assert!(layout.is_null());
+
+        let not_a_layout = font as *const VelloTextLayout;
+        assert_eq!(vello_text_layout_advance(not_a_layout), 0.0);
+        let mut offset = 0;
+        let result = vello_text_layout_hit_test(not_a_layout, 0.0, 0.0, &mut offset);
+        assert_eq!(result, VELLO_ERROR_INVALID_HANDLE);
+        vello_font_data_free(font);
+    }
+
+    #[cfg(feature = "shaping")]
+    #[test]
+    fn vertical_layouts_advance_and_hit_test_along_y() {
+        with_layout(c"ab", VelloTextDirection::TopToBottom, |layout| {
+            let advance: f32 = glyphs(layout).iter().map(|glyph| glyph.y_advance).sum();
+            assert!(advance > 0.0);
+            assert_eq!(vello_text_layout_advance(layout), advance);
+
+            let mut offset = u32::MAX;
+            assert_eq!(vello_text_layout_hit_test(layout, 1000.0, 0.0, &mut offset), VELLO_OK);
+            assert_eq!(offset, 0);
+            assert_eq!(vello_text_layout_hit_test(layout, 0.0, advance, &mut offset), VELLO_OK);
+            assert_eq!(offset, 2);
+        });
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Worker thread pools with host-controlled priority, affinity and naming
+//!
+//! A pool created here can be handed to `vello_render_context_adopt_thread_pool`
+//! and shared by several contexts, whose rendering is then split across its
+//! workers.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::sync::Arc;
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+#[cfg(feature = "worker-priority")]
+use thread_priority::{ThreadPriority, ThreadPriorityValue};
+
+use crate::context::render_context_set_thread_pool;
+use crate::error::set_last_error;
+use crate::ffi_catch_ptr;
+use crate::handle::{self, CallScope, HandleHeader, TypedHandle};
+use crate::types::*;
+
+#[repr(C)]
+pub struct VelloThreadPool {
+    _private: [u8; 0],
+}
+
+/// Object behind a `VelloThreadPool` pointer
+#[repr(C)]
+pub(crate) struct ThreadPoolHandle {
+    header: HandleHeader,
+    pub(crate) pool: Arc<ThreadPool>,
+}
+
+impl TypedHandle for ThreadPoolHandle {
+    const TAG: u32 = handle::tag(b"VTPL");
+    const NAME: &'static str = "thread pool";
+}
+
+/// Scheduling priority of worker threads
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloThreadPriority {
+    /// Leave the priority inherited from the creating thread
+    Normal = 0,
+    Low = 1,
+    Lowest = 2,
+    High = 3,
+}
+
+/// Worker pool settings
+///
+/// Priority and affinity need the `worker-priority` feature; without it they
+/// are ignored with a warning in the log.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct VelloWorkerSettings {
+    /// Number of workers (0 = one per logical CPU)
+    pub num_threads: u16,
+    pub priority: VelloThreadPriority,
+    pub _padding: u8,
+    /// Bit `i` allows CPU `i`; workers are pinned round-robin to allowed CPUs
+    /// (0 = no pinning). Platforms without affinity support ignore it.
+    pub affinity_mask: u64,
+    /// UTF-8 thread name prefix, workers are named `<prefix>-<index>` (null = "vello")
+    pub name_prefix: *const c_char,
+}
+
+#[cfg(feature = "worker-priority")]
+fn apply_priority(priority: VelloThreadPriority) {
+    let priority = match priority {
+        VelloThreadPriority::Normal => return,
+        VelloThreadPriority::Low => match ThreadPriorityValue::try_from(25u8) {
+            Ok(value) => ThreadPriority::Crossplatform(value),
+            Err(_) => ThreadPriority::Min,
+        },
+        VelloThreadPriority::Lowest => ThreadPriority::Min,
+        VelloThreadPriority::High => ThreadPriority::Max,
+    };
+    // Raising priority usually needs privileges; run at the default instead
+    if thread_priority::set_current_thread_priority(priority).is_err() {
+        crate::error::log(crate::error::VelloLogLevel::Warning, || {
+            format!("Could not set worker thread priority to {:?}", priority)
+        });
+    }
+}
+
+#[cfg(feature = "worker-priority")]
+fn apply_affinity(index: usize, mask: u64) {
+    if mask == 0 {
+        return;
+    }
+    let Some(cores) = core_affinity::get_core_ids() else {
+        return;
+    };
+    let allowed: Vec<_> = cores
+        .into_iter()
+        .filter(|core| core.id < 64 && mask & (1 << core.id) != 0)
+        .collect();
+    if !allowed.is_empty() {
+        core_affinity::set_for_current(allowed[index % allowed.len()]);
+    }
+}
+
+/// Build a rayon pool from host settings, recording an error on failure
+fn build_pool(settings: &VelloWorkerSettings) -> Result<ThreadPool, c_int> {
+    let prefix = if settings.name_prefix.is_null() {
+        "vello".to_string()
+    } else {
+        match unsafe { CStr::from_ptr(settings.name_prefix) }.to_str() {
+            Ok(prefix) => prefix.to_string(),
+            Err(_) => {
+                set_last_error("Invalid UTF-8 in thread name prefix");
+                return Err(VELLO_ERROR_INVALID_PARAMETER);
+            }
+        }
+    };
+
+    let builder = ThreadPoolBuilder::new()
+        .num_threads(settings.num_threads as usize)
+        .thread_name(move |i| format!("{prefix}-{i}"));
+    #[cfg(feature = "worker-priority")]
+    let builder = {
+        let (priority, affinity_mask) = (settings.priority, settings.affinity_mask);
+        builder.start_handler(move |i| {
+            apply_priority(priority);
+            apply_affinity(i, affinity_mask);
+        })
+    };
+    #[cfg(not(feature = "worker-priority"))]
+    let builder = {
+        if settings.priority != VelloThreadPriority::Normal || settings.affinity_mask != 0 {
+            crate::error::log(crate::error::VelloLogLevel::Warning, || {
+                "Worker priority and affinity need the worker-priority feature".to_string()
+            });
+        }
+        builder
+    };
+
+    builder.build().map_err(|e| {
+        set_last_error(format!("Failed to create thread pool: {}", e));
+        VELLO_ERROR_RENDER_FAILED
+    })
+}
+
+/// Create a worker pool with the given priority, affinity and thread names
+#[no_mangle]
+pub extern "C" fn vello_thread_pool_new(
+    settings: *const VelloWorkerSettings,
+) -> *mut VelloThreadPool {
+    if settings.is_null() {
+        set_last_error("Null settings pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let Ok(pool) = build_pool(unsafe { &*settings }) else {
+            return std::ptr::null_mut();
+        };
+        let handle = ThreadPoolHandle {
+            header: HandleHeader::new::<ThreadPoolHandle>(),
+            pool: Arc::new(pool),
+        };
+        handle::into_raw(handle) as *mut VelloThreadPool
+    })
+}
+
+/// Release a worker pool; contexts that adopted it keep it alive until detached
+#[no_mangle]
+pub extern "C" fn vello_thread_pool_free(pool: *mut VelloThreadPool) {
+    if !pool.is_null() && ThreadPoolHandle::check(pool) {
+        handle::free::<ThreadPoolHandle, _>(pool);
+    }
+}
+
+/// Get the number of workers in a pool
+#[no_mangle]
+pub extern "C" fn vello_thread_pool_num_threads(pool: *const VelloThreadPool) -> usize {
+    if pool.is_null() || !ThreadPoolHandle::check(pool) {
+        return 0;
+    }
+    let _scope = CallScope::enter();
+    ThreadPoolHandle::from_ptr(pool).pool.current_num_threads()
+}
+
+/// Render a context on workers of its own, created with the given priority,
+/// affinity and thread names
+///
+/// vello_cpu's built-in workers (`num_threads` in the render settings) cannot
+/// be named or prioritized, so they are replaced by a pool built from
+/// `settings`, as if it had been adopted with
+/// `vello_render_context_adopt_thread_pool`. The pool is freed with the
+/// context or when another pool is adopted.
+#[no_mangle]
+pub extern "C" fn vello_render_context_set_worker_settings(
+    ctx: *mut VelloRenderContext,
+    settings: *const VelloWorkerSettings,
+) -> c_int {
+    if ctx.is_null() || settings.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let pool = {
+        let _scope = CallScope::enter();
+        build_pool(unsafe { &*settings })
+    };
+    match pool {
+        Ok(pool) => render_context_set_thread_pool(ctx, Some(Arc::new(pool))),
+        Err(code) => code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{vello_render_context_free, vello_render_context_new};
+
+    fn settings(name_prefix: *const c_char) -> VelloWorkerSettings {
+        VelloWorkerSettings {
+            num_threads: 2,
+            priority: VelloThreadPriority::Normal,
+            _padding: 0,
+            affinity_mask: 0,
+            name_prefix,
+        }
+    }
+
+    #[test]
+    fn workers_are_named_after_the_prefix() {
+        let prefix = std::ffi::CString::new("render").unwrap();
+        let pool = build_pool(&settings(prefix.as_ptr())).unwrap();
+        let name = pool.install(|| std::thread::current().name().map(str::to_owned));
+        assert!(name.is_some_and(|name| name.starts_with("render-")));
+    }
+
+    #[test]
+    fn worker_settings_replace_the_context_workers() {
+        let ctx = vello_render_context_new(8, 8);
+        let result = vello_render_context_set_worker_settings(ctx, &settings(std::ptr::null()));
+        assert_eq!(result, VELLO_OK);
+
+        let invalid = [0xffu8 as c_char, 0];
+        let result = vello_render_context_set_worker_settings(ctx, &settings(invalid.as_ptr()));
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+        vello_render_context_free(ctx);
+    }
+}
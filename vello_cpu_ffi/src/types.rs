@@ -14,12 +14,24 @@ pub const VELLO_ERROR_OUT_OF_MEMORY: c_int = -4;
 pub const VELLO_ERROR_INVALID_PARAMETER: c_int = -5;
 pub const VELLO_ERROR_PNG_DECODE: c_int = -6;
 pub const VELLO_ERROR_PNG_ENCODE: c_int = -7;
+pub const VELLO_ERROR_LAYER_STACK_EMPTY: c_int = -8;
+pub const VELLO_ERROR_WEBP_DECODE: c_int = -9;
+pub const VELLO_ERROR_WEBP_ENCODE: c_int = -10;
+pub const VELLO_ERROR_IO: c_int = -11;
+pub const VELLO_ERROR_ABI_MISMATCH: c_int = -12;
 
 /// Opaque handle types (exposed as void pointers to C)
 pub type VelloRenderContext = std::ffi::c_void;
 pub type VelloPixmap = std::ffi::c_void;
 pub type VelloBezPath = std::ffi::c_void;
 pub type VelloMask = std::ffi::c_void;
+pub type VelloSvgDocument = std::ffi::c_void;
+
+/// Canvas of the Skia compatibility layer (`sk_canvas_t` in C)
+#[repr(C)]
+pub struct SkCanvas {
+    _private: [u8; 0],
+}
 
 /// Premultiplied RGBA8 color
 #[repr(C)]
@@ -61,6 +73,16 @@ pub struct VelloAffine {
     pub m23: f64,
 }
 
+/// Radii of the four corners of a rounded rectangle
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VelloCornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
 /// Stroke parameters
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -84,10 +106,15 @@ pub struct VelloRenderSettings {
 }
 
 /// Render mode enumeration
+///
+/// Selects the fine rasterization pipeline. There is no half-float pipeline;
+/// `OptimizeQuality` is the high-precision one.
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum VelloRenderMode {
+    /// 8-bit integer pipeline
     OptimizeSpeed = 0,
+    /// 32-bit float pipeline
     OptimizeQuality = 1,
 }
 
@@ -96,10 +123,16 @@ pub enum VelloRenderMode {
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum VelloSimdLevel {
     Fallback = 0,
+    /// Reported by no CPU and rejected by `vello_render_context_new_with`;
+    /// vello_common has no SSE2-only code path
     Sse2 = 1,
     Sse42 = 2,
+    /// Reported by no CPU and rejected by `vello_render_context_new_with`;
+    /// vello_common has no AVX-only code path
     Avx = 3,
     Avx2 = 4,
+    /// Reported by no CPU and rejected by `vello_render_context_new_with`;
+    /// vello_common has no AVX-512 code path
     Avx512 = 5,
     Neon = 6,
 }
@@ -130,6 +163,18 @@ pub enum VelloFillRule {
     EvenOdd = 1,
 }
 
+/// Anti-aliasing override for a single draw call
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloAaMode {
+    /// Use the context's aliasing threshold
+    Inherit = 0,
+    /// Anti-aliased edges
+    Analytic = 1,
+    /// Hard edges: pixels are either fully covered or not at all
+    Aliased = 2,
+}
+
 /// Blend mix mode
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -191,6 +236,14 @@ pub struct VelloColorStop {
     pub a: u8,
 }
 
+/// Alpha stop for gradient masks (alpha in 0..1)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VelloAlphaStop {
+    pub offset: f32,
+    pub alpha: f32,
+}
+
 /// Gradient extend mode
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -209,6 +262,61 @@ pub enum VelloImageQuality {
     High = 2,
 }
 
+/// Resampling filter for resizing pixmaps and masks
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloResampleFilter {
+    Nearest = 0,
+    Bilinear = 1,
+    Mitchell = 2,
+    Lanczos3 = 3,
+}
+
+/// Pixel format for readback conversions
+///
+/// Formats without alpha (Rgb565, Gray8) are composited over black.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloPixelFormat {
+    /// Premultiplied RGBA8 (native format)
+    Rgba8 = 0,
+    /// Premultiplied BGRA8 (Windows/DirectX/WPF)
+    Bgra8 = 1,
+    /// Straight alpha RGBA8
+    Rgba8Unpremultiplied = 2,
+    /// Straight alpha BGRA8
+    Bgra8Unpremultiplied = 3,
+    /// 16-bit RGB565, little endian
+    Rgb565 = 4,
+    /// 8-bit luminance
+    Gray8 = 5,
+}
+
+impl VelloPixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            VelloPixelFormat::Rgba8
+            | VelloPixelFormat::Bgra8
+            | VelloPixelFormat::Rgba8Unpremultiplied
+            | VelloPixelFormat::Bgra8Unpremultiplied => 4,
+            VelloPixelFormat::Rgb565 => 2,
+            VelloPixelFormat::Gray8 => 1,
+        }
+    }
+}
+
+/// Color space of pixmap contents and render targets
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloColorSpace {
+    /// sRGB primaries and transfer function
+    Srgb = 0,
+    /// Display P3 primaries with the sRGB transfer function
+    DisplayP3 = 1,
+    /// sRGB primaries with linear encoding
+    LinearSrgb = 2,
+}
+
 /// Paint kind enumeration (for querying paint type)
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -261,6 +369,58 @@ impl From<VelloRenderMode> for vello_cpu::RenderMode {
     }
 }
 
+impl From<VelloMix> for vello_cpu::peniko::Mix {
+    fn from(mix: VelloMix) -> Self {
+        use vello_cpu::peniko::Mix;
+        match mix {
+            VelloMix::Normal => Mix::Normal,
+            VelloMix::Multiply => Mix::Multiply,
+            VelloMix::Screen => Mix::Screen,
+            VelloMix::Overlay => Mix::Overlay,
+            VelloMix::Darken => Mix::Darken,
+            VelloMix::Lighten => Mix::Lighten,
+            VelloMix::ColorDodge => Mix::ColorDodge,
+            VelloMix::ColorBurn => Mix::ColorBurn,
+            VelloMix::HardLight => Mix::HardLight,
+            VelloMix::SoftLight => Mix::SoftLight,
+            VelloMix::Difference => Mix::Difference,
+            VelloMix::Exclusion => Mix::Exclusion,
+            VelloMix::Hue => Mix::Hue,
+            VelloMix::Saturation => Mix::Saturation,
+            VelloMix::Color => Mix::Color,
+            VelloMix::Luminosity => Mix::Luminosity,
+        }
+    }
+}
+
+impl From<VelloCompose> for vello_cpu::peniko::Compose {
+    fn from(compose: VelloCompose) -> Self {
+        use vello_cpu::peniko::Compose;
+        match compose {
+            VelloCompose::Clear => Compose::Clear,
+            VelloCompose::Copy => Compose::Copy,
+            VelloCompose::Dest => Compose::Dest,
+            VelloCompose::SrcOver => Compose::SrcOver,
+            VelloCompose::DestOver => Compose::DestOver,
+            VelloCompose::SrcIn => Compose::SrcIn,
+            VelloCompose::DestIn => Compose::DestIn,
+            VelloCompose::SrcOut => Compose::SrcOut,
+            VelloCompose::DestOut => Compose::DestOut,
+            VelloCompose::SrcAtop => Compose::SrcAtop,
+            VelloCompose::DestAtop => Compose::DestAtop,
+            VelloCompose::Xor => Compose::Xor,
+            VelloCompose::Plus => Compose::Plus,
+            VelloCompose::PlusLighter => Compose::PlusLighter,
+        }
+    }
+}
+
+impl From<VelloBlendMode> for vello_cpu::peniko::BlendMode {
+    fn from(mode: VelloBlendMode) -> Self {
+        vello_cpu::peniko::BlendMode::new(mode.mix.into(), mode.compose.into())
+    }
+}
+
 impl From<vello_cpu::Level> for VelloSimdLevel {
     fn from(level: vello_cpu::Level) -> Self {
         // Map based on level name
@@ -284,11 +444,36 @@ impl From<vello_cpu::Level> for VelloSimdLevel {
 }
 
 impl VelloSimdLevel {
-    pub fn to_vello_level(self) -> vello_cpu::Level {
+    /// The exact level requested
+    ///
+    /// vello_common has code paths for `Fallback`, `Sse42` and `Avx2` on x86,
+    /// and `Neon` on aarch64. Other levels fail with a reason, as do supported
+    /// levels this CPU cannot run.
+    pub fn to_vello_level(self) -> Result<vello_cpu::Level, &'static str> {
+        use vello_cpu::Level;
+
+        const UNSUPPORTED_CPU: &str = "is not supported by this CPU";
         match self {
-            VelloSimdLevel::Fallback => vello_cpu::Level::fallback(),
-            // For other levels, try detection first, fallback if not available
-            _ => vello_cpu::Level::try_detect().unwrap_or_else(|| vello_cpu::Level::fallback()),
+            VelloSimdLevel::Fallback => Ok(Level::fallback()),
+            VelloSimdLevel::Sse2 | VelloSimdLevel::Avx | VelloSimdLevel::Avx512 => {
+                Err("has no code path in vello_common")
+            }
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            VelloSimdLevel::Sse42 => Level::try_detect()
+                .and_then(|level| level.as_sse4_2())
+                .map(Level::Sse4_2)
+                .ok_or(UNSUPPORTED_CPU),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            VelloSimdLevel::Avx2 => Level::try_detect()
+                .and_then(|level| level.as_avx2())
+                .map(Level::Avx2)
+                .ok_or(UNSUPPORTED_CPU),
+            #[cfg(target_arch = "aarch64")]
+            VelloSimdLevel::Neon => Level::try_detect()
+                .and_then(|level| level.as_neon())
+                .map(Level::Neon)
+                .ok_or(UNSUPPORTED_CPU),
+            _ => Err("is not available on this architecture"),
         }
     }
 
@@ -302,6 +487,18 @@ mod tests {
     use super::*;
     use std::mem;
 
+    #[test]
+    fn simd_levels_without_a_code_path_are_rejected() {
+        assert!(VelloSimdLevel::Fallback.to_vello_level().is_ok());
+        for level in [VelloSimdLevel::Sse2, VelloSimdLevel::Avx, VelloSimdLevel::Avx512] {
+            assert_eq!(level.to_vello_level().err(), Some("has no code path in vello_common"));
+        }
+        // The detected level round-trips through the FFI enum
+        if let Some(detected) = vello_cpu::Level::try_detect() {
+            assert!(VelloSimdLevel::from(detected).to_vello_level().is_ok());
+        }
+    }
+
     #[test]
     fn test_struct_sizes() {
         // Verify struct sizes match C# expectations
@@ -310,9 +507,16 @@ mod tests {
         assert_eq!(mem::size_of::<VelloRect>(), 32, "VelloRect size mismatch");
         assert_eq!(mem::size_of::<VelloAffine>(), 48, "VelloAffine size mismatch");
         assert_eq!(mem::size_of::<VelloStroke>(), 12, "VelloStroke size mismatch");
+        assert_eq!(mem::size_of::<VelloCornerRadii>(), 16, "VelloCornerRadii size mismatch");
         assert_eq!(mem::size_of::<VelloRenderSettings>(), 6, "VelloRenderSettings size mismatch");
         assert_eq!(mem::size_of::<VelloBlendMode>(), 2, "VelloBlendMode size mismatch");
         assert_eq!(mem::size_of::<VelloColorStop>(), 8, "VelloColorStop size mismatch");
+        assert_eq!(mem::size_of::<VelloAlphaStop>(), 8, "VelloAlphaStop size mismatch");
+        assert_eq!(
+            mem::size_of::<crate::command::VelloCommand>(),
+            56,
+            "VelloCommand size mismatch"
+        );
     }
 
     #[test]
@@ -323,11 +527,15 @@ mod tests {
         assert_eq!(mem::size_of::<VelloJoin>(), 1, "VelloJoin should be 1 byte");
         assert_eq!(mem::size_of::<VelloCap>(), 1, "VelloCap should be 1 byte");
         assert_eq!(mem::size_of::<VelloFillRule>(), 1, "VelloFillRule should be 1 byte");
+        assert_eq!(mem::size_of::<VelloAaMode>(), 1, "VelloAaMode should be 1 byte");
         assert_eq!(mem::size_of::<VelloMix>(), 1, "VelloMix should be 1 byte");
         assert_eq!(mem::size_of::<VelloCompose>(), 1, "VelloCompose should be 1 byte");
         assert_eq!(mem::size_of::<VelloExtend>(), 1, "VelloExtend should be 1 byte");
         assert_eq!(mem::size_of::<VelloImageQuality>(), 1, "VelloImageQuality should be 1 byte");
         assert_eq!(mem::size_of::<VelloPaintKind>(), 1, "VelloPaintKind should be 1 byte");
+        assert_eq!(mem::size_of::<VelloColorSpace>(), 1, "VelloColorSpace should be 1 byte");
+        assert_eq!(mem::size_of::<VelloResampleFilter>(), 1, "VelloResampleFilter should be 1 byte");
+        assert_eq!(mem::size_of::<VelloPixelFormat>(), 1, "VelloPixelFormat should be 1 byte");
     }
 
     #[test]
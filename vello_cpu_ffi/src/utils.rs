@@ -3,9 +3,79 @@
 
 //! Utility functions and version info
 
-use std::os::raw::c_char;
+use std::ffi::CString;
+use std::mem::size_of;
+use std::os::raw::{c_char, c_int};
+use std::sync::OnceLock;
 
-use crate::types::VelloSimdLevel;
+use crate::command::VelloCommand;
+use crate::context::VelloMemoryUsage;
+use crate::error::set_last_error;
+use crate::glyph_cache::VelloGlyphCacheStats;
+use crate::strips::{VelloCoverageSpan, VelloStrip, VelloStripBuffer};
+use crate::text::{VelloGlyph, VelloGlyphEx};
+use crate::thread_pool::VelloWorkerSettings;
+use crate::types::*;
+
+/// ABI major version; bumped for incompatible changes to signatures or layouts
+pub const VELLO_ABI_VERSION_MAJOR: u32 = 1;
+/// ABI minor version; bumped when functions or enum values are added
+pub const VELLO_ABI_VERSION_MINOR: u32 = 0;
+/// ABI patch version; bumped for fixes that leave the interface unchanged
+pub const VELLO_ABI_VERSION_PATCH: u32 = 0;
+
+/// PNG encoding and decoding (`png` feature)
+pub const VELLO_FEATURE_PNG: u32 = 1 << 0;
+/// WebP encoding and decoding (`webp` feature)
+pub const VELLO_FEATURE_WEBP: u32 = 1 << 1;
+/// Text shaping (`shaping` feature)
+pub const VELLO_FEATURE_SHAPING: u32 = 1 << 2;
+/// System font discovery (`system-fonts` feature)
+pub const VELLO_FEATURE_SYSTEM_FONTS: u32 = 1 << 3;
+/// SVG export of recordings (`svg-export` feature)
+pub const VELLO_FEATURE_SVG_EXPORT: u32 = 1 << 4;
+/// Multithreaded rendering (`num_threads` in render settings)
+pub const VELLO_FEATURE_MULTITHREADING: u32 = 1 << 5;
+/// SSE4.2/AVX2 code paths (x86_64 builds)
+pub const VELLO_FEATURE_SIMD_X86: u32 = 1 << 6;
+/// NEON code paths (aarch64 builds)
+pub const VELLO_FEATURE_SIMD_NEON: u32 = 1 << 7;
+/// WebAssembly SIMD128 code paths (wasm32 builds with `simd128` enabled)
+pub const VELLO_FEATURE_SIMD_WASM: u32 = 1 << 8;
+/// SVG document loading and rendering (`svg` feature)
+pub const VELLO_FEATURE_SVG: u32 = 1 << 9;
+/// Skia C API compatibility layer (`skia-compat` feature)
+pub const VELLO_FEATURE_SKIA_COMPAT: u32 = 1 << 10;
+/// Worker thread priority and affinity (`worker-priority` feature)
+pub const VELLO_FEATURE_WORKER_PRIORITY: u32 = 1 << 11;
+
+/// Public structs whose layout can be checked with `vello_abi_struct_size`
+///
+/// Only `repr(C)` structs that bindings declare themselves are listed; opaque
+/// handles have no layout to check.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloAbiStruct {
+    PremulRgba8 = 0,
+    Point = 1,
+    Rect = 2,
+    Affine = 3,
+    Stroke = 4,
+    RenderSettings = 5,
+    BlendMode = 6,
+    ColorStop = 7,
+    AlphaStop = 8,
+    Glyph = 9,
+    GlyphEx = 10,
+    GlyphCacheStats = 11,
+    WorkerSettings = 12,
+    Command = 13,
+    MemoryUsage = 14,
+    Strip = 15,
+    StripBuffer = 16,
+    CoverageSpan = 17,
+    CornerRadii = 18,
+}
 
 /// Get library version string (static lifetime)
 #[no_mangle]
@@ -14,6 +84,76 @@ pub extern "C" fn vello_version() -> *const c_char {
     VERSION.as_ptr() as *const c_char
 }
 
+/// Get the capabilities compiled into this build as a `VELLO_FEATURE_*` bitmask
+///
+/// SIMD flags describe which code paths exist in the binary; use
+/// `vello_simd_detect` to learn which one the current CPU can run.
+#[no_mangle]
+pub extern "C" fn vello_features() -> u32 {
+    let mut features = VELLO_FEATURE_MULTITHREADING;
+    if cfg!(feature = "png") {
+        features |= VELLO_FEATURE_PNG;
+    }
+    if cfg!(feature = "webp") {
+        features |= VELLO_FEATURE_WEBP;
+    }
+    if cfg!(feature = "shaping") {
+        features |= VELLO_FEATURE_SHAPING;
+    }
+    if cfg!(feature = "system-fonts") {
+        features |= VELLO_FEATURE_SYSTEM_FONTS;
+    }
+    if cfg!(feature = "svg-export") {
+        features |= VELLO_FEATURE_SVG_EXPORT;
+    }
+    if cfg!(feature = "svg") {
+        features |= VELLO_FEATURE_SVG;
+    }
+    if cfg!(feature = "skia-compat") {
+        features |= VELLO_FEATURE_SKIA_COMPAT;
+    }
+    if cfg!(feature = "worker-priority") {
+        features |= VELLO_FEATURE_WORKER_PRIORITY;
+    }
+    if cfg!(target_arch = "x86_64") {
+        features |= VELLO_FEATURE_SIMD_X86;
+    }
+    if cfg!(target_arch = "aarch64") {
+        features |= VELLO_FEATURE_SIMD_NEON;
+    }
+    if cfg!(all(target_arch = "wasm32", target_feature = "simd128")) {
+        features |= VELLO_FEATURE_SIMD_WASM;
+    }
+    features
+}
+
+/// Get build information as a JSON object (static lifetime)
+///
+/// Contains the crate version, resolved `vello_cpu` and `vello_common`
+/// versions, git commit, target triple, build profile and the
+/// `vello_features` bitmask, e.g. for attaching to bug reports.
+#[no_mangle]
+pub extern "C" fn vello_build_info() -> *const c_char {
+    static INFO: OnceLock<CString> = OnceLock::new();
+    INFO.get_or_init(|| {
+        let json = format!(
+            concat!(
+                "{{\"version\":\"{}\",\"vello_cpu\":\"{}\",\"vello_common\":\"{}\",",
+                "\"git_hash\":\"{}\",\"target\":\"{}\",\"profile\":\"{}\",\"features\":{}}}"
+            ),
+            env!("CARGO_PKG_VERSION"),
+            env!("VELLO_BUILD_VELLO_CPU_VERSION"),
+            env!("VELLO_BUILD_VELLO_COMMON_VERSION"),
+            env!("VELLO_BUILD_GIT_HASH"),
+            env!("VELLO_BUILD_TARGET"),
+            env!("VELLO_BUILD_PROFILE"),
+            vello_features()
+        );
+        CString::new(json).unwrap_or_default()
+    })
+    .as_ptr()
+}
+
 /// Detect SIMD capabilities of current hardware
 #[no_mangle]
 pub extern "C" fn vello_simd_detect() -> VelloSimdLevel {
@@ -22,3 +162,148 @@ pub extern "C" fn vello_simd_detect() -> VelloSimdLevel {
         None => VelloSimdLevel::Fallback,
     }
 }
+
+/// Get the ABI version of the loaded library
+#[no_mangle]
+pub extern "C" fn vello_abi_version(
+    out_major: *mut u32,
+    out_minor: *mut u32,
+    out_patch: *mut u32,
+) -> c_int {
+    if out_major.is_null() || out_minor.is_null() || out_patch.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    unsafe {
+        *out_major = VELLO_ABI_VERSION_MAJOR;
+        *out_minor = VELLO_ABI_VERSION_MINOR;
+        *out_patch = VELLO_ABI_VERSION_PATCH;
+    }
+    VELLO_OK
+}
+
+/// Check that the loaded library is compatible with the ABI a binding was built for
+///
+/// Compatible means the same major version and at least the expected minor
+/// version. Returns `VELLO_ERROR_ABI_MISMATCH` with a descriptive error otherwise.
+#[no_mangle]
+pub extern "C" fn vello_check_abi(expected_major: u32, expected_minor: u32) -> c_int {
+    if expected_major != VELLO_ABI_VERSION_MAJOR || expected_minor > VELLO_ABI_VERSION_MINOR {
+        set_last_error(format!(
+            "ABI mismatch: binding expects {expected_major}.{expected_minor}, library provides {}.{}.{}",
+            VELLO_ABI_VERSION_MAJOR, VELLO_ABI_VERSION_MINOR, VELLO_ABI_VERSION_PATCH
+        ));
+        return VELLO_ERROR_ABI_MISMATCH;
+    }
+    VELLO_OK
+}
+
+/// Get the size in bytes of a public struct as compiled into the library
+///
+/// Bindings can compare this against their own definitions to catch layout drift.
+#[no_mangle]
+pub extern "C" fn vello_abi_struct_size(which: VelloAbiStruct) -> usize {
+    match which {
+        VelloAbiStruct::PremulRgba8 => size_of::<VelloPremulRgba8>(),
+        VelloAbiStruct::Point => size_of::<VelloPoint>(),
+        VelloAbiStruct::Rect => size_of::<VelloRect>(),
+        VelloAbiStruct::Affine => size_of::<VelloAffine>(),
+        VelloAbiStruct::Stroke => size_of::<VelloStroke>(),
+        VelloAbiStruct::RenderSettings => size_of::<VelloRenderSettings>(),
+        VelloAbiStruct::BlendMode => size_of::<VelloBlendMode>(),
+        VelloAbiStruct::ColorStop => size_of::<VelloColorStop>(),
+        VelloAbiStruct::AlphaStop => size_of::<VelloAlphaStop>(),
+        VelloAbiStruct::Glyph => size_of::<VelloGlyph>(),
+        VelloAbiStruct::GlyphEx => size_of::<VelloGlyphEx>(),
+        VelloAbiStruct::GlyphCacheStats => size_of::<VelloGlyphCacheStats>(),
+        VelloAbiStruct::WorkerSettings => size_of::<VelloWorkerSettings>(),
+        VelloAbiStruct::Command => size_of::<VelloCommand>(),
+        VelloAbiStruct::MemoryUsage => size_of::<VelloMemoryUsage>(),
+        VelloAbiStruct::Strip => size_of::<VelloStrip>(),
+        VelloAbiStruct::StripBuffer => size_of::<VelloStripBuffer>(),
+        VelloAbiStruct::CoverageSpan => size_of::<VelloCoverageSpan>(),
+        VelloAbiStruct::CornerRadii => size_of::<VelloCornerRadii>(),
+    }
+}
+
+/// Transform `count` points by an affine transform
+///
+/// `points_in` and `points_out` may be the same buffer to transform in place.
+#[no_mangle]
+pub extern "C" fn vello_affine_transform_points(
+    transform: *const VelloAffine,
+    points_in: *const VelloPoint,
+    points_out: *mut VelloPoint,
+    count: usize,
+) -> c_int {
+    if transform.is_null() || ((points_in.is_null() || points_out.is_null()) && count > 0) {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    let t = unsafe { &*transform };
+    unsafe { transform_points(t, points_in, points_out, count) };
+    VELLO_OK
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn transform_points(
+    t: &VelloAffine,
+    src: *const VelloPoint,
+    dst: *mut VelloPoint,
+    n: usize,
+) {
+    use std::arch::x86_64::*;
+
+    // SSE2 is part of the x86_64 baseline; each point is one (x, y) lane pair
+    let col_x = _mm_set_pd(t.m12, t.m11);
+    let col_y = _mm_set_pd(t.m22, t.m21);
+    let offset = _mm_set_pd(t.m23, t.m13);
+    for i in 0..n {
+        let p = _mm_loadu_pd(src.add(i) as *const f64);
+        let x = _mm_unpacklo_pd(p, p);
+        let y = _mm_unpackhi_pd(p, p);
+        let r = _mm_add_pd(
+            _mm_add_pd(_mm_mul_pd(col_x, x), _mm_mul_pd(col_y, y)),
+            offset,
+        );
+        _mm_storeu_pd(dst.add(i) as *mut f64, r);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn transform_points(
+    t: &VelloAffine,
+    src: *const VelloPoint,
+    dst: *mut VelloPoint,
+    n: usize,
+) {
+    use std::arch::aarch64::*;
+
+    let col_x = vld1q_f64([t.m11, t.m12].as_ptr());
+    let col_y = vld1q_f64([t.m21, t.m22].as_ptr());
+    let offset = vld1q_f64([t.m13, t.m23].as_ptr());
+    for i in 0..n {
+        let p = vld1q_f64(src.add(i) as *const f64);
+        let r = vfmaq_laneq_f64::<0>(offset, col_x, p);
+        let r = vfmaq_laneq_f64::<1>(r, col_y, p);
+        vst1q_f64(dst.add(i) as *mut f64, r);
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+unsafe fn transform_points(
+    t: &VelloAffine,
+    src: *const VelloPoint,
+    dst: *mut VelloPoint,
+    n: usize,
+) {
+    for i in 0..n {
+        let p = src.add(i).read();
+        dst.add(i).write(VelloPoint {
+            x: t.m11 * p.x + t.m21 * p.y + t.m13,
+            y: t.m12 * p.x + t.m22 * p.y + t.m23,
+        });
+    }
+}
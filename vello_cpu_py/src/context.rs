@@ -0,0 +1,260 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! RenderContext Python bindings
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use vello_common::recording::Recordable;
+use vello_cpu::{Pixmap, RenderContext, RenderSettings};
+
+use crate::convert::{
+    check_opacity, parse_fill_rule, parse_stroke, to_affine, to_color, to_rect,
+};
+use crate::path::PyBezPath;
+use crate::pixmap::PyPixmap;
+use crate::recording::{PyRecorder, PyRecording};
+use crate::VelloError;
+
+/// CPU render context
+///
+/// Draw calls are encoded into sparse strips as they are issued; `flush` and
+/// `render_to_pixmap` rasterize them and release the GIL while doing so.
+#[pyclass(name = "RenderContext", module = "vello_cpu")]
+pub struct PyRenderContext {
+    ctx: RenderContext,
+    /// Number of layers currently open
+    depth: usize,
+}
+
+impl PyRenderContext {
+    fn pop(&mut self) -> PyResult<()> {
+        if self.depth == 0 {
+            return Err(VelloError::new_err("Layer stack is empty"));
+        }
+        self.ctx.pop_layer();
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyRenderContext {
+    /// Create a context; `num_threads = 0` renders on the calling thread
+    #[new]
+    #[pyo3(signature = (width, height, num_threads = 0))]
+    fn new(width: u16, height: u16, num_threads: u16) -> Self {
+        let settings = RenderSettings {
+            num_threads,
+            ..Default::default()
+        };
+        Self {
+            ctx: RenderContext::new_with(width, height, settings),
+            depth: 0,
+        }
+    }
+
+    #[getter]
+    fn width(&self) -> u16 {
+        self.ctx.width()
+    }
+
+    #[getter]
+    fn height(&self) -> u16 {
+        self.ctx.height()
+    }
+
+    /// Discard draw calls and open layers
+    fn reset(&mut self) {
+        self.ctx.reset();
+        self.depth = 0;
+    }
+
+    /// Set paint to a non-premultiplied RGBA8 color
+    #[pyo3(signature = (r, g, b, a = 255))]
+    fn set_paint(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        self.ctx.set_paint(to_color(r, g, b, a));
+    }
+
+    /// Set the transform from coefficients `(a, b, c, d, e, f)`
+    fn set_transform(&mut self, transform: [f64; 6]) -> PyResult<()> {
+        self.ctx.set_transform(to_affine(transform)?);
+        Ok(())
+    }
+
+    fn reset_transform(&mut self) {
+        self.ctx.reset_transform();
+    }
+
+    /// Current transform as coefficients `(a, b, c, d, e, f)`
+    #[getter]
+    fn transform(&self) -> [f64; 6] {
+        self.ctx.transform().as_coeffs()
+    }
+
+    /// Set fill rule: `"non_zero"` or `"even_odd"`
+    fn set_fill_rule(&mut self, rule: &str) -> PyResult<()> {
+        self.ctx.set_fill_rule(parse_fill_rule(rule)?);
+        Ok(())
+    }
+
+    /// Set stroke parameters; `join` is `"bevel"`, `"miter"` or `"round"` and
+    /// `cap` is `"butt"`, `"square"` or `"round"`
+    #[pyo3(signature = (width, join = "miter", cap = "butt", miter_limit = 4.0))]
+    fn set_stroke(&mut self, width: f32, join: &str, cap: &str, miter_limit: f32) -> PyResult<()> {
+        self.ctx.set_stroke(parse_stroke(width, join, cap, miter_limit)?);
+        Ok(())
+    }
+
+    fn fill_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64) {
+        self.ctx.fill_rect(&to_rect(x0, y0, x1, y1));
+    }
+
+    fn stroke_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64) {
+        self.ctx.stroke_rect(&to_rect(x0, y0, x1, y1));
+    }
+
+    fn fill_path(&mut self, path: PyRef<'_, PyBezPath>) {
+        self.ctx.fill_path(&path.path);
+    }
+
+    fn stroke_path(&mut self, path: PyRef<'_, PyBezPath>) {
+        self.ctx.stroke_path(&path.path);
+    }
+
+    fn push_clip_layer(&mut self, path: PyRef<'_, PyBezPath>) {
+        self.ctx.push_clip_layer(&path.path);
+        self.depth += 1;
+    }
+
+    fn push_opacity_layer(&mut self, opacity: f32) -> PyResult<()> {
+        self.ctx.push_opacity_layer(check_opacity(opacity)?);
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn pop_layer(&mut self) -> PyResult<()> {
+        self.pop()
+    }
+
+    /// Number of layers currently open
+    #[getter]
+    fn layer_depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Rasterize pending draw calls; needed before rendering with worker threads
+    fn flush(&mut self, py: Python<'_>) {
+        let ctx = &mut self.ctx;
+        py.allow_threads(|| ctx.flush());
+    }
+
+    /// Render into a pixmap of the context's size
+    fn render_to_pixmap(
+        &mut self,
+        py: Python<'_>,
+        mut pixmap: PyRefMut<'_, PyPixmap>,
+    ) -> PyResult<()> {
+        if self.depth != 0 {
+            return Err(VelloError::new_err(format!(
+                "Cannot render with {} open layer(s)",
+                self.depth
+            )));
+        }
+        let (width, height) = (pixmap.pixmap.width(), pixmap.pixmap.height());
+        if (width, height) != (self.ctx.width(), self.ctx.height()) {
+            return Err(PyValueError::new_err(format!(
+                "Pixmap is {}x{}, context is {}x{}",
+                width,
+                height,
+                self.ctx.width(),
+                self.ctx.height()
+            )));
+        }
+        let ctx = &mut self.ctx;
+        let pixmap = &mut pixmap.pixmap;
+        py.allow_threads(|| {
+            ctx.flush();
+            ctx.render_to_pixmap(pixmap);
+        });
+        Ok(())
+    }
+
+    /// Render into a new pixmap of the context's size
+    fn to_pixmap(&mut self, py: Python<'_>) -> PyResult<Py<PyPixmap>> {
+        let pixmap = Py::new(
+            py,
+            PyPixmap::from_pixmap(Pixmap::new(self.ctx.width(), self.ctx.height())),
+        )?;
+        self.render_to_pixmap(py, pixmap.borrow_mut(py))?;
+        Ok(pixmap)
+    }
+
+    /// Record draw calls into `recording`
+    ///
+    /// `callback` is called with a `Recorder` that accepts the same drawing
+    /// calls as the context and is only valid until the callback returns.
+    fn record(
+        &mut self,
+        py: Python<'_>,
+        mut recording: PyRefMut<'_, PyRecording>,
+        callback: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let mut result = Ok(());
+        self.ctx.record(&mut recording.recording, |recorder| {
+            result = Bound::new(py, PyRecorder::new(recorder)).and_then(|handle| {
+                let called = callback.call1((handle.clone(),));
+                handle.borrow_mut().detach();
+                called.map(drop)
+            });
+        });
+        result
+    }
+
+    /// Generate strips for a recording so that executing it skips path processing
+    fn prepare_recording(&mut self, mut recording: PyRefMut<'_, PyRecording>) {
+        self.ctx.prepare_recording(&mut recording.recording);
+    }
+
+    /// Replay a recording onto the context
+    fn execute_recording(&mut self, recording: PyRef<'_, PyRecording>) {
+        self.ctx.execute_recording(&recording.recording);
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RenderContext(width={}, height={})",
+            self.ctx.width(),
+            self.ctx.height()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contexts_render_into_new_pixmaps() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let mut ctx = PyRenderContext::new(16, 16, 0);
+            ctx.set_paint(255, 0, 0, 255);
+            ctx.fill_rect(4.0, 4.0, 12.0, 12.0);
+            let pixmap = ctx.to_pixmap(py).unwrap();
+            let pixmap = pixmap.borrow(py);
+            let pixel = |x: usize, y: usize| {
+                let px = pixmap.pixmap.data()[y * 16 + x];
+                [px.r, px.g, px.b, px.a]
+            };
+            assert_eq!(pixel(8, 8), [255, 0, 0, 255]);
+            assert_eq!(pixel(1, 1), [0; 4]);
+
+            ctx.push_opacity_layer(0.5).unwrap();
+            assert!(ctx.to_pixmap(py).unwrap_err().is_instance_of::<VelloError>(py));
+            ctx.pop_layer().unwrap();
+            assert!(ctx.pop_layer().unwrap_err().is_instance_of::<VelloError>(py));
+        });
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Conversion of Python arguments into vello types
+//!
+//! Enum names follow the C API's string conversions: lowercase snake_case,
+//! with case, `_` and `-` ignored when parsing.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use vello_cpu::kurbo::{Affine, Rect, Stroke};
+use vello_cpu::peniko::color::{AlphaColor, Srgb};
+use vello_cpu::peniko::Fill;
+use vello_cpu_ffi::context::to_stroke;
+use vello_cpu_ffi::{VelloCap, VelloJoin, VelloStroke};
+
+/// Affine from the six coefficients `(a, b, c, d, e, f)`, mapping `(x, y)` to
+/// `(a*x + c*y + e, b*x + d*y + f)`
+pub(crate) fn to_affine(coeffs: [f64; 6]) -> PyResult<Affine> {
+    if coeffs.iter().any(|c| !c.is_finite()) {
+        return Err(PyValueError::new_err("Transform coefficients must be finite"));
+    }
+    Ok(Affine::new(coeffs))
+}
+
+pub(crate) fn to_rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Rect {
+    Rect::new(x0, y0, x1, y1)
+}
+
+/// Non-premultiplied RGBA8 color
+pub(crate) fn to_color(r: u8, g: u8, b: u8, a: u8) -> AlphaColor<Srgb> {
+    AlphaColor::<Srgb>::from_rgba8(r, g, b, a)
+}
+
+/// Normalize an enum name for comparison
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Look up `name` in a table of snake_case names
+fn parse<T: Copy>(kind: &str, name: &str, names: &[(T, &str)]) -> PyResult<T> {
+    let key = normalize(name);
+    names
+        .iter()
+        .find(|(_, n)| normalize(n) == key)
+        .map(|(value, _)| *value)
+        .ok_or_else(|| PyValueError::new_err(format!("Unknown {kind}: '{name}'")))
+}
+
+pub(crate) fn parse_fill_rule(name: &str) -> PyResult<Fill> {
+    parse(
+        "fill rule",
+        name,
+        &[(Fill::NonZero, "non_zero"), (Fill::EvenOdd, "even_odd")],
+    )
+}
+
+/// Build a stroke, validated like `vello_render_context_set_stroke`
+pub(crate) fn parse_stroke(
+    width: f32,
+    join: &str,
+    cap: &str,
+    miter_limit: f32,
+) -> PyResult<Stroke> {
+    let join = parse(
+        "join",
+        join,
+        &[
+            (VelloJoin::Bevel, "bevel"),
+            (VelloJoin::Miter, "miter"),
+            (VelloJoin::Round, "round"),
+        ],
+    )?;
+    let cap = parse(
+        "cap",
+        cap,
+        &[
+            (VelloCap::Butt, "butt"),
+            (VelloCap::Square, "square"),
+            (VelloCap::Round, "round"),
+        ],
+    )?;
+    to_stroke(&VelloStroke {
+        width,
+        miter_limit,
+        join,
+        start_cap: cap,
+        end_cap: cap,
+        _padding: [0; 3],
+    })
+    .map_err(PyValueError::new_err)
+}
+
+/// Opacity for an opacity layer
+pub(crate) fn check_opacity(opacity: f32) -> PyResult<f32> {
+    if !(0.0..=1.0).contains(&opacity) {
+        return Err(PyValueError::new_err("Opacity must be between 0 and 1"));
+    }
+    Ok(opacity)
+}
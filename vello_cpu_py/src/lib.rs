@@ -0,0 +1,57 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # vello_cpu_py
+//!
+//! Python bindings for vello_cpu, built with PyO3 and packaged with maturin as
+//! the `vello_cpu` module.
+//!
+//! The classes wrap the Rust types directly rather than going through the C
+//! API, so ownership and lifetimes are handled by Python's reference counting:
+//!
+//! - `RenderContext` - drawing state, layers, flushing and rendering
+//! - `Pixmap` - premultiplied RGBA8 pixels with PNG encode/decode
+//! - `BezPath` - path construction
+//! - `Recording` / `Recorder` - recorded draw calls for repeated playback
+//!
+//! ## Pixel access
+//!
+//! `Pixmap` implements the buffer protocol with shape `(height, width, 4)` and
+//! `uint8` items, so `numpy.asarray(pixmap)` is a writable zero-copy view and
+//! `bytes(pixmap)` copies the pixels.
+//!
+//! ## Errors
+//!
+//! Invalid arguments raise `ValueError`; rendering and codec failures raise
+//! `vello_cpu.VelloError`. Stroke parameters are validated by the same code as
+//! the C API.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+mod context;
+mod convert;
+mod path;
+mod pixmap;
+mod recording;
+
+pub use context::PyRenderContext;
+pub use path::PyBezPath;
+pub use pixmap::PyPixmap;
+pub use recording::{PyRecorder, PyRecording};
+
+create_exception!(vello_cpu, VelloError, PyException);
+
+#[pymodule]
+#[pyo3(name = "vello_cpu")]
+fn vello_cpu_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("VelloError", m.py().get_type_bound::<VelloError>())?;
+    m.add_class::<PyRenderContext>()?;
+    m.add_class::<PyPixmap>()?;
+    m.add_class::<PyBezPath>()?;
+    m.add_class::<PyRecording>()?;
+    m.add_class::<PyRecorder>()?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! BezPath Python bindings
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use vello_cpu::kurbo::{BezPath, Shape};
+
+/// Bézier path built from move, line, curve and close commands
+#[pyclass(name = "BezPath", module = "vello_cpu")]
+#[derive(Clone, Default)]
+pub struct PyBezPath {
+    pub(crate) path: BezPath,
+}
+
+#[pymethods]
+impl PyBezPath {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a path from SVG path data (`"M0 0 L10 10 Z"`)
+    #[staticmethod]
+    fn from_svg(data: &str) -> PyResult<Self> {
+        BezPath::from_svg(data)
+            .map(|path| Self { path })
+            .map_err(|e| PyValueError::new_err(format!("Invalid SVG path: {e}")))
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.path.move_to((x, y));
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.path.line_to((x, y));
+    }
+
+    fn quad_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.path.quad_to((x1, y1), (x2, y2));
+    }
+
+    fn curve_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64) {
+        self.path.curve_to((x1, y1), (x2, y2), (x3, y3));
+    }
+
+    fn close(&mut self) {
+        self.path.close_path();
+    }
+
+    /// Remove all elements
+    fn clear(&mut self) {
+        self.path.truncate(0);
+    }
+
+    /// Tight bounding box as `(x0, y0, x1, y1)`
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        let r = self.path.bounding_box();
+        (r.x0, r.y0, r.x1, r.y1)
+    }
+
+    /// Path as SVG path data
+    fn to_svg(&self) -> String {
+        self.path.to_svg()
+    }
+
+    fn __len__(&self) -> usize {
+        self.path.elements().len()
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("BezPath({:?})", self.path.to_svg())
+    }
+}
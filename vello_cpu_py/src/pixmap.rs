@@ -0,0 +1,157 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Pixmap Python bindings
+
+use std::ffi::c_void;
+use std::os::raw::c_int;
+use std::ptr;
+
+use pyo3::exceptions::{PyBufferError, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use vello_cpu::Pixmap;
+
+use crate::VelloError;
+
+/// Premultiplied RGBA8 pixel buffer
+///
+/// Exposes its pixels through the buffer protocol as a C-contiguous
+/// `(height, width, 4)` array of `uint8`.
+#[pyclass(name = "Pixmap", module = "vello_cpu")]
+pub struct PyPixmap {
+    pub(crate) pixmap: Pixmap,
+    /// Buffer protocol shape and strides; they must outlive exported views, so
+    /// they are stored with the pixmap instead of being built per request
+    shape: [ffi::Py_ssize_t; 3],
+    strides: [ffi::Py_ssize_t; 3],
+}
+
+impl PyPixmap {
+    pub(crate) fn from_pixmap(pixmap: Pixmap) -> Self {
+        let width = pixmap.width() as ffi::Py_ssize_t;
+        let height = pixmap.height() as ffi::Py_ssize_t;
+        Self {
+            pixmap,
+            shape: [height, width, 4],
+            strides: [width * 4, 4, 1],
+        }
+    }
+}
+
+#[pymethods]
+impl PyPixmap {
+    #[new]
+    fn new(width: u16, height: u16) -> Self {
+        Self::from_pixmap(Pixmap::new(width, height))
+    }
+
+    /// Decode a PNG image
+    #[staticmethod]
+    fn from_png(data: &[u8]) -> PyResult<Self> {
+        Pixmap::from_png(data)
+            .map(Self::from_pixmap)
+            .map_err(|e| VelloError::new_err(format!("PNG decode error: {:?}", e)))
+    }
+
+    /// Encode the pixels as PNG
+    fn to_png<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        self.pixmap
+            .clone()
+            .into_png()
+            .map(|data| PyBytes::new_bound(py, &data))
+            .map_err(|e| VelloError::new_err(format!("PNG encode error: {:?}", e)))
+    }
+
+    #[getter]
+    fn width(&self) -> u16 {
+        self.pixmap.width()
+    }
+
+    #[getter]
+    fn height(&self) -> u16 {
+        self.pixmap.height()
+    }
+
+    /// Copy of the premultiplied RGBA8 pixels, row by row
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, self.pixmap.data_as_u8_slice())
+    }
+
+    /// Overwrite the pixels with premultiplied RGBA8 data of the same size
+    fn copy_from_bytes(&mut self, data: &[u8]) -> PyResult<()> {
+        let dst = self.pixmap.data_as_u8_slice_mut();
+        if data.len() != dst.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected {} bytes, got {}",
+                dst.len(),
+                data.len()
+            )));
+        }
+        dst.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Set every pixel to a non-premultiplied RGBA8 color
+    #[pyo3(signature = (r, g, b, a = 255))]
+    fn fill(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        let premul = |c: u8| ((c as u16 * a as u16 + 127) / 255) as u8;
+        let pixel = [premul(r), premul(g), premul(b), a];
+        for chunk in self.pixmap.data_as_u8_slice_mut().chunks_exact_mut(4) {
+            chunk.copy_from_slice(&pixel);
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Pixmap(width={}, height={})",
+            self.pixmap.width(),
+            self.pixmap.height()
+        )
+    }
+
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("Null view"));
+        }
+
+        let mut this = slf.borrow_mut();
+        let data = this.pixmap.data_as_u8_slice_mut();
+        let (buf, len) = (data.as_mut_ptr(), data.len());
+
+        (*view).buf = buf as *mut c_void;
+        (*view).len = len as ffi::Py_ssize_t;
+        (*view).readonly = 0;
+        (*view).itemsize = 1;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            c"B".as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        // Without PyBUF_ND the consumer expects a flat byte buffer
+        if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+            (*view).ndim = 3;
+            (*view).shape = this.shape.as_mut_ptr();
+        } else {
+            (*view).ndim = 1;
+            (*view).shape = ptr::null_mut();
+        }
+        (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            this.strides.as_mut_ptr()
+        } else {
+            ptr::null_mut()
+        };
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        // The view keeps the pixmap alive; released by PyBuffer_Release
+        drop(this);
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Recording Python bindings
+//!
+//! `RenderContext.record` hands a `Recorder` to a Python callback. The recorder
+//! borrows the recording only for the duration of that call; it is detached
+//! when the callback returns, and using it afterwards raises `VelloError`.
+
+use std::ptr;
+
+use pyo3::prelude::*;
+
+use vello_common::recording::{Recorder, Recording};
+
+use crate::convert::{
+    check_opacity, parse_fill_rule, parse_stroke, to_affine, to_color, to_rect,
+};
+use crate::path::PyBezPath;
+use crate::VelloError;
+
+/// Recorded draw calls that can be prepared once and executed many times
+#[pyclass(name = "Recording", module = "vello_cpu")]
+#[derive(Default)]
+pub struct PyRecording {
+    pub(crate) recording: Recording,
+}
+
+#[pymethods]
+impl PyRecording {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove all recorded commands and cached strips
+    fn clear(&mut self) {
+        self.recording.clear();
+    }
+
+    /// Whether strips were generated by `RenderContext.prepare_recording`
+    #[getter]
+    fn has_cached_strips(&self) -> bool {
+        self.recording.has_cached_strips()
+    }
+
+    fn __len__(&self) -> usize {
+        self.recording.command_count()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Recording(commands={})", self.recording.command_count())
+    }
+}
+
+/// Drawing interface passed to the `RenderContext.record` callback
+#[pyclass(name = "Recorder", module = "vello_cpu", unsendable)]
+pub struct PyRecorder {
+    /// Recorder owned by the running `record` call; null once it returned
+    recorder: *mut Recorder<'static>,
+    /// Number of layers pushed through this recorder
+    depth: usize,
+}
+
+impl PyRecorder {
+    pub(crate) fn new(recorder: &mut Recorder<'_>) -> Self {
+        Self {
+            recorder: recorder as *mut Recorder<'_> as *mut Recorder<'static>,
+            depth: 0,
+        }
+    }
+
+    pub(crate) fn detach(&mut self) {
+        self.recorder = ptr::null_mut();
+    }
+
+    fn get(&mut self) -> PyResult<&mut Recorder<'static>> {
+        if self.recorder.is_null() {
+            return Err(VelloError::new_err(
+                "Recorder used outside of RenderContext.record",
+            ));
+        }
+        // Non-null only while the `record` call that created it is running
+        Ok(unsafe { &mut *self.recorder })
+    }
+}
+
+#[pymethods]
+impl PyRecorder {
+    /// Set paint to a non-premultiplied RGBA8 color
+    #[pyo3(signature = (r, g, b, a = 255))]
+    fn set_paint(&mut self, r: u8, g: u8, b: u8, a: u8) -> PyResult<()> {
+        self.get()?.set_paint(to_color(r, g, b, a));
+        Ok(())
+    }
+
+    /// Set the transform from coefficients `(a, b, c, d, e, f)`
+    fn set_transform(&mut self, transform: [f64; 6]) -> PyResult<()> {
+        let transform = to_affine(transform)?;
+        self.get()?.set_transform(transform);
+        Ok(())
+    }
+
+    /// Set fill rule: `"non_zero"` or `"even_odd"`
+    fn set_fill_rule(&mut self, rule: &str) -> PyResult<()> {
+        let rule = parse_fill_rule(rule)?;
+        self.get()?.set_fill_rule(rule);
+        Ok(())
+    }
+
+    #[pyo3(signature = (width, join = "miter", cap = "butt", miter_limit = 4.0))]
+    fn set_stroke(&mut self, width: f32, join: &str, cap: &str, miter_limit: f32) -> PyResult<()> {
+        let stroke = parse_stroke(width, join, cap, miter_limit)?;
+        self.get()?.set_stroke(stroke);
+        Ok(())
+    }
+
+    fn fill_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64) -> PyResult<()> {
+        self.get()?.fill_rect(&to_rect(x0, y0, x1, y1));
+        Ok(())
+    }
+
+    fn stroke_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64) -> PyResult<()> {
+        self.get()?.stroke_rect(&to_rect(x0, y0, x1, y1));
+        Ok(())
+    }
+
+    fn fill_path(&mut self, path: PyRef<'_, PyBezPath>) -> PyResult<()> {
+        self.get()?.fill_path(&path.path);
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, path: PyRef<'_, PyBezPath>) -> PyResult<()> {
+        self.get()?.stroke_path(&path.path);
+        Ok(())
+    }
+
+    fn push_clip_layer(&mut self, path: PyRef<'_, PyBezPath>) -> PyResult<()> {
+        self.get()?.push_clip_layer(&path.path);
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn push_opacity_layer(&mut self, opacity: f32) -> PyResult<()> {
+        let opacity = check_opacity(opacity)?;
+        self.get()?.push_layer(None, None, Some(opacity), None);
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn pop_layer(&mut self) -> PyResult<()> {
+        self.get()?;
+        if self.depth == 0 {
+            return Err(VelloError::new_err("Layer stack is empty"));
+        }
+        self.get()?.pop_layer();
+        self.depth -= 1;
+        Ok(())
+    }
+}
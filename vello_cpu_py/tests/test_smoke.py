@@ -0,0 +1,58 @@
+# Copyright 2025 Wieslaw Soltes
+# SPDX-License-Identifier: Apache-2.0 OR MIT
+
+import pytest
+
+import vello_cpu
+
+
+def pixel(pixmap, x, y):
+    data = pixmap.to_bytes()
+    offset = (y * pixmap.width + x) * 4
+    return tuple(data[offset : offset + 4])
+
+
+def test_fill_rect_renders_pixels():
+    ctx = vello_cpu.RenderContext(16, 16)
+    ctx.set_paint(255, 0, 0)
+    ctx.fill_rect(4, 4, 12, 12)
+    pixmap = ctx.to_pixmap()
+
+    assert (pixmap.width, pixmap.height) == (16, 16)
+    assert pixel(pixmap, 8, 8) == (255, 0, 0, 255)
+    assert pixel(pixmap, 1, 1) == (0, 0, 0, 0)
+
+
+def test_pixels_are_premultiplied():
+    ctx = vello_cpu.RenderContext(8, 8)
+    ctx.set_paint(0, 0, 255, 128)
+    ctx.fill_rect(0, 0, 8, 8)
+
+    assert pixel(ctx.to_pixmap(), 4, 4) == (0, 0, 128, 128)
+
+
+def test_buffer_view_shares_pixels():
+    pixmap = vello_cpu.Pixmap(4, 2)
+    pixmap.fill(0, 255, 0)
+    view = memoryview(pixmap)
+
+    assert view.shape == (2, 4, 4)
+    assert bytes(view) == pixmap.to_bytes()
+
+
+def test_png_round_trip():
+    ctx = vello_cpu.RenderContext(8, 8)
+    ctx.set_paint(0, 0, 255)
+    ctx.fill_rect(2, 2, 6, 6)
+    pixmap = ctx.to_pixmap()
+
+    decoded = vello_cpu.Pixmap.from_png(pixmap.to_png())
+    assert decoded.to_bytes() == pixmap.to_bytes()
+
+
+def test_render_rejects_open_layers():
+    ctx = vello_cpu.RenderContext(8, 8)
+    ctx.push_opacity_layer(0.5)
+
+    with pytest.raises(vello_cpu.VelloError):
+        ctx.to_pixmap()
@@ -0,0 +1,92 @@
+from typing import Callable, Tuple
+
+__version__: str
+
+Transform = Tuple[float, float, float, float, float, float]
+
+class VelloError(Exception): ...
+
+class BezPath:
+    def __init__(self) -> None: ...
+    @staticmethod
+    def from_svg(data: str) -> BezPath: ...
+    def move_to(self, x: float, y: float) -> None: ...
+    def line_to(self, x: float, y: float) -> None: ...
+    def quad_to(self, x1: float, y1: float, x2: float, y2: float) -> None: ...
+    def curve_to(
+        self, x1: float, y1: float, x2: float, y2: float, x3: float, y3: float
+    ) -> None: ...
+    def close(self) -> None: ...
+    def clear(self) -> None: ...
+    def bounds(self) -> Tuple[float, float, float, float]: ...
+    def to_svg(self) -> str: ...
+    def __len__(self) -> int: ...
+    def __copy__(self) -> BezPath: ...
+
+class Pixmap:
+    def __init__(self, width: int, height: int) -> None: ...
+    @staticmethod
+    def from_png(data: bytes) -> Pixmap: ...
+    def to_png(self) -> bytes: ...
+    @property
+    def width(self) -> int: ...
+    @property
+    def height(self) -> int: ...
+    def to_bytes(self) -> bytes: ...
+    def copy_from_bytes(self, data: bytes) -> None: ...
+    def fill(self, r: int, g: int, b: int, a: int = 255) -> None: ...
+    def __buffer__(self, flags: int) -> memoryview: ...
+
+class Recording:
+    def __init__(self) -> None: ...
+    def clear(self) -> None: ...
+    @property
+    def has_cached_strips(self) -> bool: ...
+    def __len__(self) -> int: ...
+
+class Recorder:
+    def set_paint(self, r: int, g: int, b: int, a: int = 255) -> None: ...
+    def set_transform(self, transform: Transform) -> None: ...
+    def set_fill_rule(self, rule: str) -> None: ...
+    def set_stroke(
+        self, width: float, join: str = "miter", cap: str = "butt", miter_limit: float = 4.0
+    ) -> None: ...
+    def fill_rect(self, x0: float, y0: float, x1: float, y1: float) -> None: ...
+    def stroke_rect(self, x0: float, y0: float, x1: float, y1: float) -> None: ...
+    def fill_path(self, path: BezPath) -> None: ...
+    def stroke_path(self, path: BezPath) -> None: ...
+    def push_clip_layer(self, path: BezPath) -> None: ...
+    def push_opacity_layer(self, opacity: float) -> None: ...
+    def pop_layer(self) -> None: ...
+
+class RenderContext:
+    def __init__(self, width: int, height: int, num_threads: int = 0) -> None: ...
+    @property
+    def width(self) -> int: ...
+    @property
+    def height(self) -> int: ...
+    def reset(self) -> None: ...
+    def set_paint(self, r: int, g: int, b: int, a: int = 255) -> None: ...
+    def set_transform(self, transform: Transform) -> None: ...
+    def reset_transform(self) -> None: ...
+    @property
+    def transform(self) -> Transform: ...
+    def set_fill_rule(self, rule: str) -> None: ...
+    def set_stroke(
+        self, width: float, join: str = "miter", cap: str = "butt", miter_limit: float = 4.0
+    ) -> None: ...
+    def fill_rect(self, x0: float, y0: float, x1: float, y1: float) -> None: ...
+    def stroke_rect(self, x0: float, y0: float, x1: float, y1: float) -> None: ...
+    def fill_path(self, path: BezPath) -> None: ...
+    def stroke_path(self, path: BezPath) -> None: ...
+    def push_clip_layer(self, path: BezPath) -> None: ...
+    def push_opacity_layer(self, opacity: float) -> None: ...
+    def pop_layer(self) -> None: ...
+    @property
+    def layer_depth(self) -> int: ...
+    def flush(self) -> None: ...
+    def render_to_pixmap(self, pixmap: Pixmap) -> None: ...
+    def to_pixmap(self) -> Pixmap: ...
+    def record(self, recording: Recording, callback: Callable[[Recorder], object]) -> None: ...
+    def prepare_recording(self, recording: Recording) -> None: ...
+    def execute_recording(self, recording: Recording) -> None: ...
@@ -0,0 +1,493 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Hybrid render context: scene building and rendering
+//!
+//! A context pairs a `vello_hybrid::Scene`, which encodes draw calls into
+//! sparse strips on the CPU, with the `Renderer` that rasterizes them on the
+//! device it was created on. Drawing calls mirror the `vello_render_context_*`
+//! ones and take the same path, transform, rect and stroke types.
+
+use std::os::raw::c_int;
+use std::sync::Arc;
+
+use vello_common::kurbo::{Affine, Rect};
+use vello_common::peniko::color::{AlphaColor, Srgb};
+use vello_common::peniko::Fill;
+use vello_cpu_ffi::context::to_stroke;
+use vello_cpu_ffi::error::set_last_error;
+use vello_cpu_ffi::handle::{self, HandleHeader, TypedHandle};
+use vello_cpu_ffi::path::PathHandle;
+use vello_cpu_ffi::{
+    ffi_catch, ffi_catch_ptr, VelloAffine, VelloBezPath, VelloFillRule, VelloRect, VelloStroke,
+    VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_LAYER_STACK_EMPTY, VELLO_ERROR_NULL_POINTER,
+    VELLO_ERROR_RENDER_FAILED, VELLO_OK,
+};
+use vello_hybrid::{RenderSize, RenderTargetConfig, Renderer, Scene};
+
+use crate::device::{DeviceHandle, Gpu, VelloHybridDevice};
+use crate::surface::{SurfaceHandle, VelloHybridSurface};
+use crate::texture::{TextureHandle, VelloHybridTexture, TARGET_FORMAT};
+
+#[repr(C)]
+pub struct VelloHybridContext {
+    _private: [u8; 0],
+}
+
+/// Object behind a `VelloHybridContext` pointer
+#[repr(C)]
+pub(crate) struct ContextHandle {
+    header: HandleHeader,
+    gpu: Arc<Gpu>,
+    scene: Scene,
+    /// Renderer for `TARGET_FORMAT` textures
+    renderer: Renderer,
+    /// Renderer for the format of the last surface rendered to, created on first use
+    surface_renderer: Option<(wgpu::TextureFormat, Renderer)>,
+    /// Number of layers currently open on the scene
+    depth: usize,
+}
+
+impl TypedHandle for ContextHandle {
+    const TAG: u32 = handle::tag(b"VHCX");
+    const NAME: &'static str = "hybrid context";
+}
+
+/// Run `f` on the context behind `ctx`, reporting an invalid handle
+fn with_context(
+    ctx: *mut VelloHybridContext,
+    f: impl FnOnce(&mut ContextHandle) -> c_int,
+) -> c_int {
+    if ctx.is_null() {
+        set_last_error("Null context pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    ffi_catch!(f(ContextHandle::from_ptr_mut(ctx)))
+}
+
+/// Create a hybrid render context on a device
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_new(
+    device: *const VelloHybridDevice,
+    width: u16,
+    height: u16,
+) -> *mut VelloHybridContext {
+    if device.is_null() {
+        set_last_error("Null device pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let device = DeviceHandle::from_ptr(device);
+        let renderer = Renderer::new(
+            &device.gpu.device,
+            &RenderTargetConfig {
+                format: TARGET_FORMAT,
+                width: width as u32,
+                height: height as u32,
+            },
+        );
+        let handle = ContextHandle {
+            header: HandleHeader::new::<ContextHandle>(),
+            gpu: device.gpu.clone(),
+            scene: Scene::new(width, height),
+            renderer,
+            surface_renderer: None,
+            depth: 0,
+        };
+        handle::into_raw(handle) as *mut VelloHybridContext
+    })
+}
+
+/// Free a hybrid render context
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_free(ctx: *mut VelloHybridContext) {
+    if !ctx.is_null() && ContextHandle::check(ctx) {
+        handle::free::<ContextHandle, _>(ctx);
+    }
+}
+
+/// Reset the scene, discarding draw calls and open layers
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_reset(ctx: *mut VelloHybridContext) -> c_int {
+    with_context(ctx, |ctx| {
+        ctx.scene.reset();
+        ctx.depth = 0;
+        VELLO_OK
+    })
+}
+
+/// Set paint to solid color (non-premultiplied RGBA8)
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_set_paint_solid(
+    ctx: *mut VelloHybridContext,
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+) -> c_int {
+    with_context(ctx, |ctx| {
+        ctx.scene
+            .set_paint(AlphaColor::<Srgb>::from_rgba8(r, g, b, a));
+        VELLO_OK
+    })
+}
+
+/// Set transform
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_set_transform(
+    ctx: *mut VelloHybridContext,
+    transform: *const VelloAffine,
+) -> c_int {
+    if transform.is_null() {
+        set_last_error("Null transform pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        let t = unsafe { &*transform };
+        ctx.scene
+            .set_transform(Affine::new([t.m11, t.m12, t.m21, t.m22, t.m13, t.m23]));
+        VELLO_OK
+    })
+}
+
+/// Reset transform to identity
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_reset_transform(ctx: *mut VelloHybridContext) -> c_int {
+    with_context(ctx, |ctx| {
+        ctx.scene.reset_transform();
+        VELLO_OK
+    })
+}
+
+/// Set fill rule
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_set_fill_rule(
+    ctx: *mut VelloHybridContext,
+    fill_rule: VelloFillRule,
+) -> c_int {
+    with_context(ctx, |ctx| {
+        ctx.scene.set_fill_rule(match fill_rule {
+            VelloFillRule::NonZero => Fill::NonZero,
+            VelloFillRule::EvenOdd => Fill::EvenOdd,
+        });
+        VELLO_OK
+    })
+}
+
+/// Set stroke parameters
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_set_stroke(
+    ctx: *mut VelloHybridContext,
+    stroke: *const VelloStroke,
+) -> c_int {
+    if stroke.is_null() {
+        set_last_error("Null stroke pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| match to_stroke(unsafe { &*stroke }) {
+        Ok(stroke) => {
+            ctx.scene.set_stroke(stroke);
+            VELLO_OK
+        }
+        Err(msg) => {
+            set_last_error(msg);
+            VELLO_ERROR_INVALID_PARAMETER
+        }
+    })
+}
+
+/// Fill rectangle
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_fill_rect(
+    ctx: *mut VelloHybridContext,
+    rect: *const VelloRect,
+) -> c_int {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        let r = unsafe { &*rect };
+        ctx.scene.fill_rect(&Rect::new(r.x0, r.y0, r.x1, r.y1));
+        VELLO_OK
+    })
+}
+
+/// Stroke rectangle
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_stroke_rect(
+    ctx: *mut VelloHybridContext,
+    rect: *const VelloRect,
+) -> c_int {
+    if rect.is_null() {
+        set_last_error("Null rect pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        let r = unsafe { &*rect };
+        ctx.scene.stroke_rect(&Rect::new(r.x0, r.y0, r.x1, r.y1));
+        VELLO_OK
+    })
+}
+
+/// Fill path
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_fill_path(
+    ctx: *mut VelloHybridContext,
+    path: *const VelloBezPath,
+) -> c_int {
+    if path.is_null() {
+        set_last_error("Null path pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        ctx.scene.fill_path(PathHandle::from_ptr(path));
+        VELLO_OK
+    })
+}
+
+/// Stroke path
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_stroke_path(
+    ctx: *mut VelloHybridContext,
+    path: *const VelloBezPath,
+) -> c_int {
+    if path.is_null() {
+        set_last_error("Null path pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        ctx.scene.stroke_path(PathHandle::from_ptr(path));
+        VELLO_OK
+    })
+}
+
+/// Push a layer clipped to a path
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_push_clip_layer(
+    ctx: *mut VelloHybridContext,
+    path: *const VelloBezPath,
+) -> c_int {
+    if path.is_null() {
+        set_last_error("Null path pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        ctx.scene.push_clip_layer(PathHandle::from_ptr(path));
+        ctx.depth += 1;
+        VELLO_OK
+    })
+}
+
+/// Pop the innermost layer
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_pop_layer(ctx: *mut VelloHybridContext) -> c_int {
+    with_context(ctx, |ctx| {
+        if ctx.depth == 0 {
+            set_last_error("No layer to pop");
+            return VELLO_ERROR_LAYER_STACK_EMPTY;
+        }
+        ctx.depth -= 1;
+        ctx.scene.pop_layer();
+        VELLO_OK
+    })
+}
+
+impl ContextHandle {
+    /// Check that a target of the given size on `gpu` can be rendered to
+    fn check_target(&self, gpu: &Arc<Gpu>, width: u32, height: u32, kind: &str) -> c_int {
+        if !Arc::ptr_eq(&self.gpu, gpu) {
+            set_last_error(format!("{kind} was created on a different device"));
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        if width != self.scene.width() as u32 || height != self.scene.height() as u32 {
+            set_last_error(format!("{kind} size does not match the context"));
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        if self.depth > 0 {
+            set_last_error("Scene has unbalanced layers");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        VELLO_OK
+    }
+
+    /// Render the scene into `view` and submit the work to the queue
+    fn render_to_view(&mut self, view: &wgpu::TextureView, format: wgpu::TextureFormat) -> c_int {
+        let gpu = &self.gpu;
+        let (width, height) = (self.scene.width() as u32, self.scene.height() as u32);
+        let renderer = if format == TARGET_FORMAT {
+            &mut self.renderer
+        } else {
+            if self.surface_renderer.as_ref().is_none_or(|(f, _)| *f != format) {
+                let config = RenderTargetConfig {
+                    format,
+                    width,
+                    height,
+                };
+                self.surface_renderer = Some((format, Renderer::new(&gpu.device, &config)));
+            }
+            &mut self.surface_renderer.as_mut().unwrap().1
+        };
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let size = RenderSize { width, height };
+        if let Err(e) = renderer.render(
+            &self.scene,
+            &gpu.device,
+            &gpu.queue,
+            &mut encoder,
+            &size,
+            view,
+        ) {
+            set_last_error(format!("Hybrid rendering failed: {e:?}"));
+            return VELLO_ERROR_RENDER_FAILED;
+        }
+        gpu.queue.submit([encoder.finish()]);
+        VELLO_OK
+    }
+}
+
+/// Render the scene into a texture created on the same device
+///
+/// The texture must have the context's size. Rendering is submitted to the
+/// device queue and completes asynchronously; `vello_hybrid_texture_read`
+/// waits for it. All layers must be closed.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_render_to_texture(
+    ctx: *mut VelloHybridContext,
+    texture: *const VelloHybridTexture,
+) -> c_int {
+    if texture.is_null() {
+        set_last_error("Null texture pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        let texture = TextureHandle::from_ptr(texture);
+        let (width, height) = (texture.texture.width(), texture.texture.height());
+        let result = ctx.check_target(&texture.gpu, width, height, "Texture");
+        if result != VELLO_OK {
+            return result;
+        }
+
+        let view = texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        ctx.render_to_view(&view, TARGET_FORMAT)
+    })
+}
+
+/// Render the scene into the next frame of a surface and present it
+///
+/// The surface must have been created on the same device and have the
+/// context's size; when the window changes size, resize the surface and
+/// recreate the context. All layers must be closed. Waits until the surface
+/// has a frame available.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_context_render_to_surface(
+    ctx: *mut VelloHybridContext,
+    surface: *mut VelloHybridSurface,
+) -> c_int {
+    if surface.is_null() {
+        set_last_error("Null surface pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    with_context(ctx, |ctx| {
+        let surface = SurfaceHandle::from_ptr_mut(surface);
+        let (width, height) = (surface.config.width, surface.config.height);
+        let result = ctx.check_target(&surface.gpu, width, height, "Surface");
+        if result != VELLO_OK {
+            return result;
+        }
+
+        let frame = match surface.current_frame() {
+            Ok(frame) => frame,
+            Err(code) => return code,
+        };
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let result = ctx.render_to_view(&view, surface.config.format);
+        if result == VELLO_OK {
+            frame.present();
+        }
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use vello_cpu_ffi::{vello_bezpath_free, vello_bezpath_new, VELLO_ERROR_INVALID_HANDLE};
+
+    use super::*;
+    use crate::{
+        vello_hybrid_device_free, vello_hybrid_device_new, vello_hybrid_texture_free,
+        vello_hybrid_texture_new, vello_hybrid_texture_read, VelloHybridPowerPreference,
+    };
+
+    const RECT: VelloRect = VelloRect { x0: 0.0, y0: 0.0, x1: 8.0, y1: 8.0 };
+
+    #[test]
+    fn null_and_foreign_handles_are_rejected() {
+        assert!(vello_hybrid_context_new(std::ptr::null(), 16, 16).is_null());
+        let null = std::ptr::null_mut();
+        let result = vello_hybrid_context_set_paint_solid(null, 0, 0, 0, 255);
+        assert_eq!(result, VELLO_ERROR_NULL_POINTER);
+        assert_eq!(vello_hybrid_context_fill_rect(null, &RECT), VELLO_ERROR_NULL_POINTER);
+        assert_eq!(vello_hybrid_context_pop_layer(null), VELLO_ERROR_NULL_POINTER);
+
+        let path = vello_bezpath_new();
+        let ctx = path as *mut VelloHybridContext;
+        assert_eq!(vello_hybrid_context_fill_rect(ctx, std::ptr::null()), VELLO_ERROR_NULL_POINTER);
+        assert_eq!(vello_hybrid_context_fill_rect(ctx, &RECT), VELLO_ERROR_INVALID_HANDLE);
+        assert_eq!(vello_hybrid_context_reset(ctx), VELLO_ERROR_INVALID_HANDLE);
+        let texture = path as *const VelloHybridTexture;
+        let result = vello_hybrid_context_render_to_texture(ctx, texture);
+        assert_eq!(result, VELLO_ERROR_INVALID_HANDLE);
+        let result = vello_hybrid_context_render_to_texture(ctx, std::ptr::null());
+        assert_eq!(result, VELLO_ERROR_NULL_POINTER);
+
+        // Freeing through the wrong function leaves the path alone
+        vello_hybrid_context_free(ctx);
+        assert!(PathHandle::is_valid(path));
+        vello_bezpath_free(path);
+    }
+
+    /// Create, render, read back and free; skipped on machines without a GPU adapter
+    #[test]
+    fn scenes_render_into_textures() {
+        let device = vello_hybrid_device_new(VelloHybridPowerPreference::None);
+        if device.is_null() {
+            eprintln!("No GPU adapter available, skipping the hybrid render test");
+            return;
+        }
+        let ctx = vello_hybrid_context_new(device, 16, 16);
+        let texture = vello_hybrid_texture_new(device, 16, 16);
+        assert!(!ctx.is_null() && !texture.is_null());
+
+        assert_eq!(vello_hybrid_context_set_paint_solid(ctx, 255, 0, 0, 255), VELLO_OK);
+        assert_eq!(vello_hybrid_context_fill_rect(ctx, &RECT), VELLO_OK);
+        assert_eq!(vello_hybrid_context_pop_layer(ctx), VELLO_ERROR_LAYER_STACK_EMPTY);
+        assert_eq!(vello_hybrid_context_render_to_texture(ctx, texture), VELLO_OK);
+
+        let mut pixels = vec![0u8; 16 * 16 * 4];
+        let (data, len) = (pixels.as_mut_ptr(), pixels.len());
+        assert_eq!(vello_hybrid_texture_read(texture, data, len, 0), VELLO_OK);
+        let pixel = |x: usize, y: usize| &pixels[(y * 16 + x) * 4..][..4];
+        assert_eq!(pixel(2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(12, 12), [0; 4]);
+
+        // Targets must match the context size
+        let small = vello_hybrid_texture_new(device, 8, 8);
+        let result = vello_hybrid_context_render_to_texture(ctx, small);
+        assert_eq!(result, VELLO_ERROR_INVALID_PARAMETER);
+
+        vello_hybrid_texture_free(small);
+        vello_hybrid_texture_free(texture);
+        vello_hybrid_context_free(ctx);
+        assert_eq!(vello_hybrid_context_reset(ctx), VELLO_ERROR_INVALID_HANDLE);
+        vello_hybrid_device_free(device);
+    }
+}
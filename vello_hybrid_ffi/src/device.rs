@@ -0,0 +1,167 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! GPU device creation
+//!
+//! wgpu initialization stays on the Rust side: hosts pick an adapter
+//! preference and get back a device that contexts, textures and surfaces are
+//! created against. Rust hosts that already run wgpu share their own device
+//! through [`device_from_wgpu`] instead.
+
+use std::sync::Arc;
+
+use vello_cpu_ffi::error::set_last_error;
+use vello_cpu_ffi::ffi_catch_ptr;
+use vello_cpu_ffi::handle::{self, HandleHeader, TypedHandle};
+
+#[repr(C)]
+pub struct VelloHybridDevice {
+    _private: [u8; 0],
+}
+
+/// Adapter preference used when opening a device
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloHybridPowerPreference {
+    None = 0,
+    LowPower = 1,
+    HighPerformance = 2,
+}
+
+/// Device and queue shared by everything created on a `VelloHybridDevice`
+///
+/// The instance and adapter are kept so surfaces can be created and
+/// configured for the same device later.
+pub(crate) struct Gpu {
+    pub(crate) instance: wgpu::Instance,
+    pub(crate) adapter: wgpu::Adapter,
+    pub(crate) device: wgpu::Device,
+    pub(crate) queue: wgpu::Queue,
+}
+
+/// Object behind a `VelloHybridDevice` pointer
+#[repr(C)]
+pub(crate) struct DeviceHandle {
+    header: HandleHeader,
+    pub(crate) gpu: Arc<Gpu>,
+}
+
+impl DeviceHandle {
+    fn new(gpu: Gpu) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            gpu: Arc::new(gpu),
+        }
+    }
+}
+
+impl TypedHandle for DeviceHandle {
+    const TAG: u32 = handle::tag(b"VHDV");
+    const NAME: &'static str = "hybrid device";
+}
+
+/// Open a GPU device for hybrid rendering
+///
+/// Blocks until the adapter and device are available. Returns null if no
+/// suitable adapter exists; the reason is available from `vello_get_last_error`.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_device_new(
+    power_preference: VelloHybridPowerPreference,
+) -> *mut VelloHybridDevice {
+    ffi_catch_ptr!({
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let Some(adapter) =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: match power_preference {
+                    VelloHybridPowerPreference::None => wgpu::PowerPreference::None,
+                    VelloHybridPowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+                    VelloHybridPowerPreference::HighPerformance => {
+                        wgpu::PowerPreference::HighPerformance
+                    }
+                },
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            }))
+        else {
+            set_last_error("No suitable GPU adapter found");
+            return std::ptr::null_mut();
+        };
+
+        let descriptor = wgpu::DeviceDescriptor {
+            label: Some("vello_hybrid_ffi"),
+            required_features: wgpu::Features::empty(),
+            required_limits: adapter.limits(),
+            memory_hints: wgpu::MemoryHints::default(),
+        };
+        let (device, queue) = match pollster::block_on(adapter.request_device(&descriptor, None)) {
+            Ok(pair) => pair,
+            Err(e) => {
+                set_last_error(format!("Failed to open GPU device: {e}"));
+                return std::ptr::null_mut();
+            }
+        };
+
+        let gpu = Gpu {
+            instance,
+            adapter,
+            device,
+            queue,
+        };
+        handle::into_raw(DeviceHandle::new(gpu)) as *mut VelloHybridDevice
+    })
+}
+
+/// Wrap a wgpu device owned by the host
+///
+/// Contexts and surfaces created on the returned handle render with the
+/// host's device and queue, so their output can be composited with the host's
+/// own wgpu work without copies. `instance` and `adapter` must be the ones
+/// `device` was requested from; they are used to create and configure
+/// surfaces. Free the handle with `vello_hybrid_device_free`.
+pub fn device_from_wgpu(
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+) -> *mut VelloHybridDevice {
+    let gpu = Gpu {
+        instance,
+        adapter,
+        device,
+        queue,
+    };
+    handle::into_raw(DeviceHandle::new(gpu)) as *mut VelloHybridDevice
+}
+
+/// Free a device
+///
+/// Contexts, textures and surfaces created on it keep the GPU device alive until they
+/// are freed themselves.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_device_free(device: *mut VelloHybridDevice) {
+    if !device.is_null() && DeviceHandle::check(device) {
+        handle::free::<DeviceHandle, _>(device);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello_cpu_ffi::path::PathHandle;
+    use vello_cpu_ffi::{vello_bezpath_free, vello_bezpath_new};
+
+    use super::*;
+    use crate::{vello_hybrid_context_new, vello_hybrid_texture_new};
+
+    #[test]
+    fn foreign_handles_are_rejected() {
+        let path = vello_bezpath_new();
+        let device = path as *mut VelloHybridDevice;
+        assert!(vello_hybrid_context_new(device, 16, 16).is_null());
+        assert!(vello_hybrid_texture_new(device, 16, 16).is_null());
+
+        // Freeing through the wrong function leaves the path alone
+        vello_hybrid_device_free(device);
+        assert!(PathHandle::is_valid(path));
+        vello_bezpath_free(path);
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # vello_hybrid_ffi
+//!
+//! C FFI bindings for vello_hybrid - the hybrid CPU/GPU 2D renderer.
+//!
+//! Scenes are built with the same call shapes and shared types as the CPU
+//! bindings (`VelloBezPath`, `VelloAffine`, `VelloRect`, `VelloStroke`, the
+//! error codes and `vello_get_last_error`), then rendered on the GPU through
+//! wgpu. The library links `vello_cpu_ffi` and exports its functions as well,
+//! so a host loads one library and switches between CPU and hybrid rendering
+//! behind one binding layer.
+//!
+//! ## Output
+//!
+//! Contexts render into off-screen textures created on the same device, which
+//! can be read back into CPU memory, or present to a window through a surface
+//! created from the host's native window handle.
+//!
+//! The library opens its own device by default. Rust hosts that already own a
+//! wgpu device pass it in with [`device_from_wgpu`] and wrap their textures
+//! with [`texture_from_wgpu`].
+//!
+//! ## Safety
+//!
+//! All functions perform null pointer checks and use panic catching to prevent
+//! unwinding across FFI boundaries. Hybrid handles are registered with the same
+//! live handle registry as the CPU bindings, so passing a freed handle or the
+//! wrong kind of handle fails with `VELLO_ERROR_INVALID_HANDLE`.
+
+#![allow(clippy::missing_safety_doc)]
+
+pub mod context;
+pub mod device;
+pub mod surface;
+pub mod texture;
+
+pub use context::*;
+pub use device::*;
+pub use surface::*;
+pub use texture::*;
@@ -0,0 +1,266 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Presenting to platform windows
+//!
+//! Hosts describe their native window with a `VelloHybridWindowHandle` and get
+//! back a surface on a device. Contexts render into the surface's next frame
+//! and present it in one call.
+
+use std::num::{NonZeroIsize, NonZeroU32};
+use std::os::raw::{c_int, c_ulong, c_void};
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+use vello_cpu_ffi::error::set_last_error;
+use vello_cpu_ffi::handle::{self, HandleHeader, TypedHandle};
+use vello_cpu_ffi::{
+    ffi_catch, ffi_catch_ptr, VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_NULL_POINTER,
+    VELLO_ERROR_RENDER_FAILED, VELLO_OK,
+};
+use wgpu::rwh::{
+    AndroidDisplayHandle, AndroidNdkWindowHandle, AppKitDisplayHandle, AppKitWindowHandle,
+    RawDisplayHandle, RawWindowHandle, UiKitDisplayHandle, UiKitWindowHandle,
+    WaylandDisplayHandle, WaylandWindowHandle, Win32WindowHandle, WindowsDisplayHandle,
+    XcbDisplayHandle, XcbWindowHandle, XlibDisplayHandle, XlibWindowHandle,
+};
+
+use crate::device::{DeviceHandle, Gpu, VelloHybridDevice};
+
+#[repr(C)]
+pub struct VelloHybridSurface {
+    _private: [u8; 0],
+}
+
+/// Windowing system a `VelloHybridWindowHandle` belongs to
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VelloHybridWindowKind {
+    Win32 = 0,
+    Xlib = 1,
+    Xcb = 2,
+    Wayland = 3,
+    AppKit = 4,
+    UiKit = 5,
+    AndroidNdk = 6,
+}
+
+/// Native window to present to
+///
+/// - `Win32`: `window` is the `HWND`, `display` the `HINSTANCE` or null
+/// - `Xlib`: `window_id` is the `Window`, `display` the `Display*` or null
+/// - `Xcb`: `window_id` is the `xcb_window_t`, `display` the
+///   `xcb_connection_t*` or null
+/// - `Wayland`: `window` is the `wl_surface*`, `display` the `wl_display*`
+/// - `AppKit`: `window` is the `NSView*`
+/// - `UiKit`: `window` is the `UIView*`
+/// - `AndroidNdk`: `window` is the `ANativeWindow*`
+///
+/// `screen` is the X11 screen number and ignored elsewhere.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct VelloHybridWindowHandle {
+    pub kind: VelloHybridWindowKind,
+    pub window: *mut c_void,
+    pub window_id: u64,
+    pub display: *mut c_void,
+    pub screen: i32,
+}
+
+/// Convert a host window description into raw-window-handle types
+fn raw_handles(
+    w: &VelloHybridWindowHandle,
+) -> Result<(RawDisplayHandle, RawWindowHandle), &'static str> {
+    let window = NonNull::new(w.window);
+    let display = NonNull::new(w.display);
+    Ok(match w.kind {
+        VelloHybridWindowKind::Win32 => {
+            let hwnd = NonZeroIsize::new(w.window as isize).ok_or("Null HWND")?;
+            let mut handle = Win32WindowHandle::new(hwnd);
+            handle.hinstance = NonZeroIsize::new(w.display as isize);
+            (
+                RawDisplayHandle::Windows(WindowsDisplayHandle::new()),
+                RawWindowHandle::Win32(handle),
+            )
+        }
+        VelloHybridWindowKind::Xlib => (
+            RawDisplayHandle::Xlib(XlibDisplayHandle::new(display, w.screen)),
+            RawWindowHandle::Xlib(XlibWindowHandle::new(w.window_id as c_ulong)),
+        ),
+        VelloHybridWindowKind::Xcb => {
+            let id = NonZeroU32::new(w.window_id as u32).ok_or("Null xcb window")?;
+            (
+                RawDisplayHandle::Xcb(XcbDisplayHandle::new(display, w.screen)),
+                RawWindowHandle::Xcb(XcbWindowHandle::new(id)),
+            )
+        }
+        VelloHybridWindowKind::Wayland => (
+            RawDisplayHandle::Wayland(WaylandDisplayHandle::new(
+                display.ok_or("Null wl_display")?,
+            )),
+            RawWindowHandle::Wayland(WaylandWindowHandle::new(window.ok_or("Null wl_surface")?)),
+        ),
+        VelloHybridWindowKind::AppKit => (
+            RawDisplayHandle::AppKit(AppKitDisplayHandle::new()),
+            RawWindowHandle::AppKit(AppKitWindowHandle::new(window.ok_or("Null NSView")?)),
+        ),
+        VelloHybridWindowKind::UiKit => (
+            RawDisplayHandle::UiKit(UiKitDisplayHandle::new()),
+            RawWindowHandle::UiKit(UiKitWindowHandle::new(window.ok_or("Null UIView")?)),
+        ),
+        VelloHybridWindowKind::AndroidNdk => (
+            RawDisplayHandle::Android(AndroidDisplayHandle::new()),
+            RawWindowHandle::AndroidNdk(AndroidNdkWindowHandle::new(
+                window.ok_or("Null ANativeWindow")?,
+            )),
+        ),
+    })
+}
+
+/// Object behind a `VelloHybridSurface` pointer
+#[repr(C)]
+pub(crate) struct SurfaceHandle {
+    header: HandleHeader,
+    pub(crate) gpu: Arc<Gpu>,
+    surface: wgpu::Surface<'static>,
+    pub(crate) config: wgpu::SurfaceConfiguration,
+}
+
+impl TypedHandle for SurfaceHandle {
+    const TAG: u32 = handle::tag(b"VHSF");
+    const NAME: &'static str = "hybrid surface";
+}
+
+impl SurfaceHandle {
+    /// Get the next frame, reconfiguring once if the surface went out of date
+    pub(crate) fn current_frame(&mut self) -> Result<wgpu::SurfaceTexture, c_int> {
+        let frame = match self.surface.get_current_texture() {
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                self.surface.configure(&self.gpu.device, &self.config);
+                self.surface.get_current_texture()
+            }
+            frame => frame,
+        };
+        frame.map_err(|e| {
+            set_last_error(format!("Failed to acquire surface frame: {e}"));
+            VELLO_ERROR_RENDER_FAILED
+        })
+    }
+}
+
+/// Create a surface presenting to a native window
+///
+/// Rendering uses an 8-bit RGBA or BGRA format without sRGB conversion,
+/// whichever the surface supports. The window must stay alive until the
+/// surface is freed. Returns null if the device's adapter cannot present to
+/// the window; the reason is available from `vello_get_last_error`.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_surface_new(
+    device: *const VelloHybridDevice,
+    window: *const VelloHybridWindowHandle,
+    width: u16,
+    height: u16,
+) -> *mut VelloHybridSurface {
+    if device.is_null() || window.is_null() {
+        set_last_error("Null pointer");
+        return std::ptr::null_mut();
+    }
+    if width == 0 || height == 0 {
+        set_last_error("Surface size must be non-zero");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let device = DeviceHandle::from_ptr(device);
+        let (raw_display_handle, raw_window_handle) = match raw_handles(unsafe { &*window }) {
+            Ok(handles) => handles,
+            Err(msg) => {
+                set_last_error(msg);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let gpu = &device.gpu;
+        let target = wgpu::SurfaceTargetUnsafe::RawHandle {
+            raw_display_handle,
+            raw_window_handle,
+        };
+        let surface = match unsafe { gpu.instance.create_surface_unsafe(target) } {
+            Ok(surface) => surface,
+            Err(e) => {
+                set_last_error(format!("Failed to create surface: {e}"));
+                return std::ptr::null_mut();
+            }
+        };
+        if !gpu.adapter.is_surface_supported(&surface) {
+            set_last_error("The device's adapter cannot present to this window");
+            return std::ptr::null_mut();
+        }
+
+        let caps = surface.get_capabilities(&gpu.adapter);
+        let Some(format) = caps.formats.iter().copied().find(|format| {
+            matches!(
+                format,
+                wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Bgra8Unorm
+            )
+        }) else {
+            set_last_error("Surface supports no 8-bit RGBA or BGRA format");
+            return std::ptr::null_mut();
+        };
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: width as u32,
+            height: height as u32,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+        };
+        surface.configure(&gpu.device, &config);
+
+        let handle = SurfaceHandle {
+            header: HandleHeader::new::<SurfaceHandle>(),
+            gpu: gpu.clone(),
+            surface,
+            config,
+        };
+        handle::into_raw(handle) as *mut VelloHybridSurface
+    })
+}
+
+/// Resize a surface after its window changed size
+///
+/// Contexts rendering to it must have the same size, so recreate them at the
+/// new size as well.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_surface_resize(
+    surface: *mut VelloHybridSurface,
+    width: u16,
+    height: u16,
+) -> c_int {
+    if surface.is_null() {
+        set_last_error("Null surface pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+    if width == 0 || height == 0 {
+        set_last_error("Surface size must be non-zero");
+        return VELLO_ERROR_INVALID_PARAMETER;
+    }
+
+    ffi_catch!({
+        let surface = SurfaceHandle::from_ptr_mut(surface);
+        surface.config.width = width as u32;
+        surface.config.height = height as u32;
+        surface.surface.configure(&surface.gpu.device, &surface.config);
+        VELLO_OK
+    })
+}
+
+/// Free a surface
+#[no_mangle]
+pub extern "C" fn vello_hybrid_surface_free(surface: *mut VelloHybridSurface) {
+    if !surface.is_null() && SurfaceHandle::check(surface) {
+        handle::free::<SurfaceHandle, _>(surface);
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2025 Wieslaw Soltes
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Off-screen render targets and read-back
+//!
+//! Textures are created by the library, or wrapped from the host's wgpu
+//! textures with [`texture_from_wgpu`] to render straight into them.
+
+use std::os::raw::c_int;
+use std::sync::Arc;
+
+use vello_cpu_ffi::error::set_last_error;
+use vello_cpu_ffi::{
+    VELLO_ERROR_INVALID_PARAMETER, VELLO_ERROR_NULL_POINTER, VELLO_ERROR_RENDER_FAILED, VELLO_OK,
+};
+
+use vello_cpu_ffi::handle::{self, HandleHeader, TypedHandle};
+use vello_cpu_ffi::{ffi_catch, ffi_catch_ptr};
+
+use crate::device::{DeviceHandle, Gpu, VelloHybridDevice};
+
+#[repr(C)]
+pub struct VelloHybridTexture {
+    _private: [u8; 0],
+}
+
+/// Format of hybrid render targets: premultiplied RGBA, 8 bits per channel
+pub(crate) const TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Object behind a `VelloHybridTexture` pointer
+#[repr(C)]
+pub(crate) struct TextureHandle {
+    header: HandleHeader,
+    pub(crate) gpu: Arc<Gpu>,
+    pub(crate) texture: wgpu::Texture,
+}
+
+impl TextureHandle {
+    fn new(gpu: Arc<Gpu>, texture: wgpu::Texture) -> Self {
+        Self {
+            header: HandleHeader::new::<Self>(),
+            gpu,
+            texture,
+        }
+    }
+}
+
+impl TypedHandle for TextureHandle {
+    const TAG: u32 = handle::tag(b"VHTX");
+    const NAME: &'static str = "hybrid texture";
+}
+
+/// Create an off-screen render target on a device
+#[no_mangle]
+pub extern "C" fn vello_hybrid_texture_new(
+    device: *const VelloHybridDevice,
+    width: u16,
+    height: u16,
+) -> *mut VelloHybridTexture {
+    if device.is_null() {
+        set_last_error("Null device pointer");
+        return std::ptr::null_mut();
+    }
+    if width == 0 || height == 0 {
+        set_last_error("Texture size must be non-zero");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let device = DeviceHandle::from_ptr(device);
+        let texture = device.gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("vello_hybrid_ffi target"),
+            size: wgpu::Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TARGET_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        handle::into_raw(TextureHandle::new(device.gpu.clone(), texture)) as *mut VelloHybridTexture
+    })
+}
+
+/// Wrap a wgpu texture owned by the host as a render target
+///
+/// The texture must belong to the device behind `device`, which has to wrap
+/// the host's device (see [`crate::device_from_wgpu`]), use
+/// `Rgba8Unorm` and allow `RENDER_ATTACHMENT` usage; reading it back also
+/// needs `COPY_SRC`. Returns null with an error otherwise.
+pub fn texture_from_wgpu(
+    device: *const VelloHybridDevice,
+    texture: wgpu::Texture,
+) -> *mut VelloHybridTexture {
+    if device.is_null() {
+        set_last_error("Null device pointer");
+        return std::ptr::null_mut();
+    }
+
+    ffi_catch_ptr!({
+        let device = DeviceHandle::from_ptr(device);
+        if texture.format() != TARGET_FORMAT {
+            set_last_error("Texture format must be Rgba8Unorm");
+            return std::ptr::null_mut();
+        }
+        if !texture.usage().contains(wgpu::TextureUsages::RENDER_ATTACHMENT) {
+            set_last_error("Texture must allow RENDER_ATTACHMENT usage");
+            return std::ptr::null_mut();
+        }
+        handle::into_raw(TextureHandle::new(device.gpu.clone(), texture)) as *mut VelloHybridTexture
+    })
+}
+
+/// Free a texture
+///
+/// Textures wrapped with [`texture_from_wgpu`] only release this handle's
+/// reference; the host's own `wgpu::Texture` clones stay valid.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_texture_free(texture: *mut VelloHybridTexture) {
+    if !texture.is_null() && TextureHandle::check(texture) {
+        handle::free::<TextureHandle, _>(texture);
+    }
+}
+
+/// Copy a texture into CPU memory as premultiplied RGBA8
+///
+/// `stride` is the distance between rows in bytes (0 = tightly packed). Waits
+/// for rendering submitted to the texture to finish.
+#[no_mangle]
+pub extern "C" fn vello_hybrid_texture_read(
+    texture: *const VelloHybridTexture,
+    buffer: *mut u8,
+    buffer_len: usize,
+    stride: usize,
+) -> c_int {
+    if texture.is_null() || buffer.is_null() {
+        set_last_error("Null pointer");
+        return VELLO_ERROR_NULL_POINTER;
+    }
+
+    ffi_catch!({
+        let texture = TextureHandle::from_ptr(texture);
+        if !texture.texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            set_last_error("Texture does not allow COPY_SRC usage");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        let (width, height) = (texture.texture.width(), texture.texture.height());
+        let row_len = width as usize * 4;
+        let stride = if stride == 0 { row_len } else { stride };
+        if stride < row_len {
+            set_last_error("Stride must be at least width * 4 bytes");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+        if buffer_len < stride * (height as usize - 1) + row_len {
+            set_last_error("Buffer too small");
+            return VELLO_ERROR_INVALID_PARAMETER;
+        }
+
+        // Buffer copies need rows aligned to COPY_BYTES_PER_ROW_ALIGNMENT
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let padded_row = row_len.div_ceil(align) * align;
+        let gpu = &texture.gpu;
+        let staging = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("vello_hybrid_ffi read-back"),
+            size: (padded_row * height as usize) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            texture.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row as u32),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.texture.size(),
+        );
+        gpu.queue.submit([encoder.finish()]);
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        gpu.device.poll(wgpu::Maintain::Wait);
+        if let Err(e) = receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError)) {
+            set_last_error(format!("Failed to read back texture: {e}"));
+            return VELLO_ERROR_RENDER_FAILED;
+        }
+
+        let out = unsafe { std::slice::from_raw_parts_mut(buffer, buffer_len) };
+        let mapped = slice.get_mapped_range();
+        for (y, row) in mapped.chunks_exact(padded_row).enumerate() {
+            out[y * stride..y * stride + row_len].copy_from_slice(&row[..row_len]);
+        }
+        drop(mapped);
+        staging.unmap();
+        VELLO_OK
+    })
+}